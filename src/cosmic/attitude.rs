@@ -0,0 +1,357 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{AttitudeLaw, Spacecraft, State};
+use crate::dynamics::DynamicsError;
+use crate::errors::NyxError;
+use crate::linalg::{Const, Matrix3, OMatrix, OVector, Vector3, Vector4};
+use crate::md::StateParameter;
+use crate::na::{Quaternion, UnitQuaternion};
+use crate::time::Epoch;
+use std::fmt;
+
+/// Attitude state of a rigid spacecraft, propagated alongside its orbit in a `RigidSpacecraft`.
+///
+/// The body frame is the frame in which the inertia tensor, the plates of the surface model and the torques are defined.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Attitude {
+    /// Unit quaternion rotating vectors from the body frame to the integration frame, scalar first
+    pub quaternion: [f64; 4],
+    /// Angular velocity of the body frame with respect to the integration frame, expressed in the body frame, in rad/s
+    pub omega_rad_s: [f64; 3],
+    /// Inertia tensor about the center of mass in the body frame, in kg m^2
+    inertia_kg_m2: Matrix3<f64>,
+    /// Inverse of the inertia tensor, which is checked to exist at initialization
+    inertia_inv: Matrix3<f64>,
+}
+
+impl Default for Attitude {
+    /// Aligned with the integration frame, without any rotation, and with a unit spherical inertia
+    fn default() -> Self {
+        Self {
+            quaternion: [1.0, 0.0, 0.0, 0.0],
+            omega_rad_s: [0.0; 3],
+            inertia_kg_m2: Matrix3::identity(),
+            inertia_inv: Matrix3::identity(),
+        }
+    }
+}
+
+impl Attitude {
+    /// Initialize an attitude from the rotation from the body frame to the integration frame, the angular velocity in the body frame and the inertia tensor.
+    ///
+    /// Returns an error if the inertia tensor is not that of a rigid body, i.e. not symmetric positive definite or whose principal moments violate the triangle inequality.
+    pub fn new(
+        body_to_frame: UnitQuaternion<f64>,
+        omega_rad_s: Vector3<f64>,
+        inertia_kg_m2: Matrix3<f64>,
+    ) -> Result<Self, NyxError> {
+        let tol = 1e-12 * inertia_kg_m2.norm();
+        if (inertia_kg_m2 - inertia_kg_m2.transpose()).amax() > tol {
+            return Err(NyxError::MathDomain {
+                msg: format!("inertia tensor is not symmetric: {inertia_kg_m2}"),
+            });
+        }
+        let inertia_inv = match inertia_kg_m2.cholesky() {
+            Some(chol) => chol.inverse(),
+            None => {
+                return Err(NyxError::MathDomain {
+                    msg: format!("inertia tensor is not positive definite: {inertia_kg_m2}"),
+                })
+            }
+        };
+        let moments = inertia_kg_m2.symmetric_eigenvalues();
+        if moments
+            .iter()
+            .any(|moment| 2.0 * moment > moments.sum() + tol)
+        {
+            return Err(NyxError::MathDomain {
+                msg: format!(
+                    "principal moments of inertia {} violate the triangle inequality",
+                    moments.transpose()
+                ),
+            });
+        }
+
+        let q = body_to_frame.into_inner();
+        Ok(Self {
+            quaternion: [q.w, q.i, q.j, q.k],
+            omega_rad_s: omega_rad_s.into(),
+            inertia_kg_m2,
+            inertia_inv,
+        })
+    }
+
+    /// Initialize an attitude from the rotation matrix from the body frame to the integration frame, the angular velocity in the body frame and the inertia tensor
+    pub fn from_dcm(
+        body_to_frame: Matrix3<f64>,
+        omega_rad_s: Vector3<f64>,
+        inertia_kg_m2: Matrix3<f64>,
+    ) -> Result<Self, NyxError> {
+        Self::new(
+            UnitQuaternion::from_matrix(&body_to_frame),
+            omega_rad_s,
+            inertia_kg_m2,
+        )
+    }
+
+    /// Returns the unit quaternion rotating vectors from the body frame to the integration frame
+    pub fn body_to_frame(&self) -> UnitQuaternion<f64> {
+        let [w, i, j, k] = self.quaternion;
+        UnitQuaternion::from_quaternion(Quaternion::new(w, i, j, k))
+    }
+
+    /// Returns the rotation matrix from the body frame to the integration frame, i.e. whose columns are the body axes
+    pub fn dcm_to_frame(&self) -> Matrix3<f64> {
+        self.body_to_frame().to_rotation_matrix().into_inner()
+    }
+
+    /// Returns the angular velocity in the body frame, in rad/s
+    pub fn omega(&self) -> Vector3<f64> {
+        Vector3::from(self.omega_rad_s)
+    }
+
+    /// Returns the inertia tensor in the body frame, in kg m^2
+    pub fn inertia(&self) -> Matrix3<f64> {
+        self.inertia_kg_m2
+    }
+
+    /// Returns the provided body frame vector (e.g. a thruster axis) in the integration frame
+    pub fn to_frame(&self, body_vec: &Vector3<f64>) -> Vector3<f64> {
+        self.body_to_frame() * body_vec
+    }
+
+    /// Returns a copy of the provided spacecraft oriented by this attitude, which replaces the attitude law of the surface models
+    pub fn orient(&self, sc: Spacecraft) -> Spacecraft {
+        sc.with_attitude(AttitudeLaw::Quaternion(self.quaternion))
+    }
+
+    /// Returns the time derivative of the quaternion (scalar first) and of the angular velocity, provided the external torque in N m in the body frame.
+    ///
+    /// The kinematics are q' = q ⊗ (0, ω) / 2 and the rotational dynamics follow Euler's equations, I ω' = τ - ω × I ω.
+    pub fn derivatives(&self, torque_nm: &Vector3<f64>) -> (Vector4<f64>, Vector3<f64>) {
+        let [w, i, j, k] = self.quaternion;
+        let omega = self.omega();
+        let q_dot = Quaternion::new(w, i, j, k) * Quaternion::from_imag(omega) * 0.5;
+        let omega_dot = self.inertia_inv * (torque_nm - omega.cross(&(self.inertia_kg_m2 * omega)));
+        (Vector4::new(q_dot.w, q_dot.i, q_dot.j, q_dot.k), omega_dot)
+    }
+
+    /// Returns the rotational kinetic energy in J
+    pub fn kinetic_energy_j(&self) -> f64 {
+        0.5 * self.omega().dot(&(self.inertia() * self.omega()))
+    }
+
+    /// Returns the angular momentum in the integration frame, in kg m^2/s
+    pub fn angular_momentum(&self) -> Vector3<f64> {
+        self.to_frame(&(self.inertia() * self.omega()))
+    }
+}
+
+impl fmt::Display for Attitude {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [w, i, j, k] = self.quaternion;
+        let [wx, wy, wz] = self.omega_rad_s;
+        write!(
+            f,
+            "q = [{w:.6}, {i:.6}, {j:.6}, {k:.6}]  ω = [{wx:.6e}, {wy:.6e}, {wz:.6e}] rad/s"
+        )
+    }
+}
+
+/// A spacecraft and its rigid body attitude, which is appended to the state vector of the spacecraft.
+///
+/// The state vector is [X, Y, Z, Vx, Vy, Vz, Cr, Cd, Fuel mass, STM(9x9), q0, q1, q2, q3, ωx, ωy, ωz]: the STM only covers the spacecraft.
+/// Propagate it with the `AttitudeDynamics`, whose torque models rotate the spacecraft and whose guidance law may thrust along its body axes.
+/// Note that the Cartesian error controls ignore the attitude: prefer a fixed step or an error control on the full state (e.g. `RSSStep`) for fast rotations.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RigidSpacecraft {
+    pub sc: Spacecraft,
+    pub attitude: Attitude,
+}
+
+impl RigidSpacecraft {
+    /// Initializes the state from a spacecraft and its attitude
+    pub fn new(sc: Spacecraft, attitude: Attitude) -> Self {
+        Self { sc, attitude }
+    }
+
+    /// Returns a copy of the spacecraft oriented by the attitude
+    pub fn oriented(&self) -> Spacecraft {
+        self.attitude.orient(self.sc)
+    }
+}
+
+impl fmt::Display for RigidSpacecraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let orbit_prec = f.precision().unwrap_or(6);
+        write!(
+            f,
+            "{}  {}",
+            format_args!("{:.*}", orbit_prec, self.sc),
+            self.attitude
+        )
+    }
+}
+
+impl fmt::LowerExp for RigidSpacecraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let orbit_prec = f.precision().unwrap_or(6);
+        write!(
+            f,
+            "{}  {}",
+            format_args!("{:.*e}", orbit_prec, self.sc),
+            self.attitude
+        )
+    }
+}
+
+impl State for RigidSpacecraft {
+    type Size = Const<9>;
+    type VecLength = Const<97>;
+
+    fn reset_stm(&mut self) {
+        self.sc.reset_stm();
+    }
+
+    fn zeros() -> Self {
+        Self {
+            sc: Spacecraft::zeros(),
+            attitude: Attitude::default(),
+        }
+    }
+
+    /// The vector is organized as such:
+    /// [X, Y, Z, Vx, Vy, Vz, Cr, Cd, Fuel mass, STM(9x9), q0, q1, q2, q3, ωx, ωy, ωz]
+    fn as_vector(&self) -> OVector<f64, Const<97>> {
+        let mut vector = OVector::<f64, Const<97>>::zeros();
+        vector
+            .fixed_rows_mut::<90>(0)
+            .copy_from(&self.sc.as_vector());
+        for (i, val) in self
+            .attitude
+            .quaternion
+            .iter()
+            .chain(self.attitude.omega_rad_s.iter())
+            .enumerate()
+        {
+            vector[i + 90] = *val;
+        }
+        vector
+    }
+
+    /// Vector is expected to be organized as such:
+    /// [X, Y, Z, Vx, Vy, Vz, Cr, Cd, Fuel mass, STM(9x9), q0, q1, q2, q3, ωx, ωy, ωz]
+    fn set(&mut self, epoch: Epoch, vector: &OVector<f64, Const<97>>) {
+        self.sc.set(epoch, &vector.fixed_rows::<90>(0).into_owned());
+        // The quaternion is renormalized since the integrator does not preserve its norm
+        let q = vector.fixed_rows::<4>(90);
+        let q_norm = q.norm();
+        for i in 0..4 {
+            self.attitude.quaternion[i] = q[i] / q_norm;
+        }
+        for i in 0..3 {
+            self.attitude.omega_rad_s[i] = vector[94 + i];
+        }
+    }
+
+    fn stm(&self) -> Result<OMatrix<f64, Self::Size, Self::Size>, DynamicsError> {
+        self.sc.stm()
+    }
+
+    fn epoch(&self) -> Epoch {
+        self.sc.epoch()
+    }
+
+    fn set_epoch(&mut self, epoch: Epoch) {
+        self.sc.set_epoch(epoch)
+    }
+
+    fn add(self, other: OVector<f64, Self::Size>) -> Self {
+        let mut me = self;
+        me.sc = self.sc + other;
+        me
+    }
+
+    fn value(&self, param: StateParameter) -> Result<f64, NyxError> {
+        self.sc.value(param)
+    }
+
+    fn set_value(&mut self, param: StateParameter, val: f64) -> Result<(), NyxError> {
+        self.sc.set_value(param, val)
+    }
+
+    fn unset_stm(&mut self) {
+        self.sc.unset_stm();
+    }
+}
+
+#[cfg(test)]
+mod ut_attitude {
+    use super::*;
+
+    #[test]
+    fn test_inertia_validation() {
+        let q = UnitQuaternion::identity();
+        let omega = Vector3::zeros();
+        assert!(Attitude::new(
+            q,
+            omega,
+            Matrix3::from_diagonal(&Vector3::new(10.0, 20.0, 30.0))
+        )
+        .is_ok());
+        // Singular, not positive definite, asymmetric and non physical inertias are rejected
+        for inertia in [
+            Matrix3::zeros(),
+            Matrix3::from_diagonal(&Vector3::new(10.0, -20.0, 30.0)),
+            Matrix3::new(10.0, 1.0, 0.0, 0.0, 20.0, 0.0, 0.0, 0.0, 30.0),
+            Matrix3::from_diagonal(&Vector3::new(10.0, 20.0, 40.0)),
+        ] {
+            assert!(Attitude::new(q, omega, inertia).is_err(), "{inertia}");
+        }
+    }
+
+    #[test]
+    fn test_state_vector() {
+        let attitude = Attitude::new(
+            UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 0.3),
+            Vector3::new(0.01, 0.02, 0.03),
+            Matrix3::from_diagonal(&Vector3::new(10.0, 20.0, 30.0)),
+        )
+        .unwrap();
+        let state = RigidSpacecraft::new(Spacecraft::default(), attitude);
+        let mut vector = state.as_vector();
+        let mut rebuilt = state;
+        rebuilt.set(state.epoch(), &vector);
+        assert!(
+            rebuilt
+                .attitude
+                .body_to_frame()
+                .angle_to(&attitude.body_to_frame())
+                < 1e-15
+        );
+        assert_eq!(rebuilt.attitude.omega(), attitude.omega());
+        // The quaternion is normalized when set
+        for i in 90..94 {
+            vector[i] *= 2.0;
+        }
+        rebuilt.set(state.epoch(), &vector);
+        assert!((rebuilt.attitude.body_to_frame().angle() - 0.3).abs() < 1e-15);
+        assert!((Vector4::from(rebuilt.attitude.quaternion).norm() - 1.0).abs() < 1e-15);
+    }
+}
@@ -37,6 +37,7 @@ pub enum Bodies {
     Earth,
     Luna,
     MarsBarycenter,
+    Mars,
     JupiterBarycenter,
     SaturnBarycenter,
     UranusBarycenter,
@@ -58,6 +59,7 @@ impl Bodies {
             Self::Earth => &[3, 0],
             Self::Luna => &[3, 1],
             Self::MarsBarycenter => &[4],
+            Self::Mars => &[4, 0],
             Self::JupiterBarycenter => &[5],
             Self::SaturnBarycenter => &[6],
             Self::UranusBarycenter => &[7],
@@ -79,6 +81,7 @@ impl Bodies {
             Self::Earth => "Earth".to_string(),
             Self::Luna => "Moon".to_string(),
             Self::MarsBarycenter => "Mars".to_string(),
+            Self::Mars => "Mars".to_string(),
             Self::JupiterBarycenter => "Jupiter Barycenter".to_string(),
             Self::SaturnBarycenter => "Saturn Barycenter".to_string(),
             Self::UranusBarycenter => "Uranus Barycenter".to_string(),
@@ -100,6 +103,7 @@ fn avail() -> Vec<String> {
         "Earth".to_string(),
         "Luna".to_string(),
         "MarsBarycenter".to_string(),
+        "Mars".to_string(),
         "JupiterBarycenter".to_string(),
         "SaturnBarycenter".to_string(),
         "UranusBarycenter".to_string(),
@@ -156,10 +160,11 @@ impl TryFrom<Vec<usize>> for Bodies {
                     haystack: avail(),
                 }),
             },
-            2 if ephem_path[0] == 3 => match ephem_path[1] {
-                // This only support the Earth system
-                0 => Ok(Self::Earth),
-                1 => Ok(Self::Luna),
+            // This only support the Earth system and Mars
+            2 => match (ephem_path[0], ephem_path[1]) {
+                (3, 0) => Ok(Self::Earth),
+                (3, 1) => Ok(Self::Luna),
+                (4, 0) => Ok(Self::Mars),
                 _ => Err(NyxError::ObjectNotFound {
                     needle: format!("{ephem_path:?}"),
                     haystack: avail(),
@@ -28,12 +28,15 @@ use super::frames::*;
 use super::orbit::Orbit;
 use super::rotations::*;
 use super::xb::ephem_interp::StateData::{EqualStates, VarwindowStates};
-use super::xb::{Ephemeris, Xb};
+use super::xb::{Constant, Ephemeris, Unit as XbUnit, Xb};
 use super::SPEED_OF_LIGHT_KMS;
 use crate::errors::NyxError;
 use crate::hifitime::{Epoch, Unit, SECONDS_PER_DAY};
+use crate::io::bpc::Bpc;
+use crate::io::eop::EarthOrientationParams;
 use crate::io::frame_serde;
-use crate::na::{Matrix3, Matrix6};
+use crate::io::spk::{Spk, SpkSegment};
+use crate::na::{Matrix3, Matrix6, Vector3, Vector6};
 use crate::utils::{capitalize, dcm_finite_differencing, rotv};
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
@@ -112,6 +115,8 @@ pub struct Cosm {
     pub frame_root: FrameTree,
     // Maps the ephemeris path to the frame root path (remove this with the upcoming xb file)
     ephem2frame_map: HashMap<Vec<usize>, Vec<usize>>,
+    // Maps the ephemeris path to its SPK segments and the ephemeris path of their centers, highest priority first (empty if built from an XB)
+    spk_segments: HashMap<Vec<usize>, Vec<(SpkSegment, Vec<usize>)>>,
 }
 
 impl fmt::Debug for Cosm {
@@ -169,6 +174,164 @@ impl Cosm {
 
     /// Attempts to build a Cosm from the XB files and the embedded IAU frames
     pub fn try_from_xb(xb: Xb) -> Result<Self, NyxError> {
+        Self::try_from_parts(xb, HashMap::new())
+    }
+
+    /// Builds a Cosm from the provided SPK (BSP) files, e.g. `["de440s.bsp", "sb-433.bsp"]`, and the embedded IAU frames.
+    ///
+    /// Kernels are layered in the order provided: when several segments cover the same object at the same time, the one loaded last is used, as in SPICE.
+    /// Objects without a known NAIF name are called `Body <NAIF ID>`, e.g. Eros is available as the `Body 2000433 J2000` frame.
+    /// The GM of such objects is unknown and set to zero: use `frame_mut_gm` to set it.
+    pub fn from_spk(filenames: &[&str]) -> Result<Self, NyxError> {
+        let mut spks = Vec::with_capacity(filenames.len());
+        for filename in filenames {
+            spks.push(Spk::from_file(filename)?);
+        }
+        Self::try_from_spk(spks)
+    }
+
+    /// Builds a Cosm where the Sun, the planets of the inner solar system, and the Moon are fixed, in the same tree as the DE files.
+    /// This allows testing the frames and the orientations without the DE files.
+    #[cfg(test)]
+    pub(crate) fn fixed_planets() -> Self {
+        let fixed = SpkSegment::fixed;
+        Self::try_from_spk(vec![Spk {
+            segments: vec![
+                fixed(1, 0, 5.8e7),
+                fixed(2, 0, 1.1e8),
+                fixed(3, 0, 1.5e8),
+                fixed(10, 0, 1e3),
+                fixed(399, 3, -4e3),
+                fixed(301, 3, 3.8e5),
+                fixed(4, 0, 2.3e8),
+                fixed(499, 4, 0.0),
+            ],
+        }])
+        .unwrap()
+    }
+
+    /// Attempts to build a Cosm from the provided SPKs, the last one having the highest priority, and the embedded IAU frames.
+    ///
+    /// The state of an object is computed from its highest priority segment which covers the requested epoch, whatever its center.
+    /// Segments whose center cannot be reached from the solar system barycenter are ignored with a warning.
+    pub fn try_from_spk(spks: Vec<Spk>) -> Result<Self, NyxError> {
+        let segments: Vec<SpkSegment> = spks.into_iter().flat_map(|spk| spk.segments).collect();
+        if segments.is_empty() {
+            return Err(NyxError::LoadingError {
+                msg: "no supported segment in the provided SPKs".to_string(),
+            });
+        }
+
+        // Each object is placed in the tree with respect to the center of its highest priority segment
+        let mut centers = HashMap::new();
+        for segment in &segments {
+            centers.insert(segment.target_id, segment.center_id);
+        }
+
+        let mut paths = HashMap::new();
+        let root = Self::spk_ephemeris(0, &centers, &[], &mut paths)?;
+
+        // Every segment is kept, highest priority first, so that the lower priority segments are used outside of the time span of the higher priority ones.
+        // Segments with respect to another center than the one of the tree are translated through that center when evaluated.
+        let mut spk_segments: HashMap<Vec<usize>, Vec<(SpkSegment, Vec<usize>)>> = HashMap::new();
+        for segment in segments.into_iter().rev() {
+            match (
+                paths.get(&segment.target_id),
+                paths.get(&segment.center_id),
+            ) {
+                (Some(target_path), Some(center_path))
+                    if !center_path.starts_with(target_path) =>
+                {
+                    spk_segments
+                        .entry(target_path.clone())
+                        .or_default()
+                        .push((segment, center_path.clone()));
+                }
+                (Some(_), Some(_)) => warn!(
+                    "ignoring SPK segment `{}`: {} is centered on {}, which is one of its own satellites",
+                    segment.name,
+                    naif_name(segment.target_id),
+                    naif_name(segment.center_id)
+                ),
+                _ => warn!(
+                    "ignoring SPK segment `{}`: {} is centered on {}, which cannot be reached from the solar system barycenter",
+                    segment.name,
+                    naif_name(segment.target_id),
+                    naif_name(segment.center_id)
+                ),
+            }
+        }
+
+        Self::try_from_parts(
+            Xb {
+                ephemeris_root: Some(root),
+                ..Default::default()
+            },
+            spk_segments,
+        )
+    }
+
+    /// Builds the ephemeris tree of the provided NAIF ID and of all of the objects centered on it.
+    /// Fails if an object is more than three levels below the solar system barycenter, since frame paths are limited to three levels.
+    fn spk_ephemeris(
+        naif_id: i32,
+        centers: &HashMap<i32, i32>,
+        path: &[usize],
+        paths: &mut HashMap<i32, Vec<usize>>,
+    ) -> Result<Ephemeris, NyxError> {
+        let mut ephem = Ephemeris {
+            name: naif_name(naif_id),
+            orientation: "J2000".to_string(),
+            ..Default::default()
+        };
+
+        for (name, value, unit) in naif_constants(naif_id) {
+            ephem.constants.insert(
+                name.to_string(),
+                Constant {
+                    value,
+                    unit: unit as i32,
+                },
+            );
+        }
+
+        paths.insert(naif_id, path.to_vec());
+
+        let mut children: Vec<i32> = centers
+            .iter()
+            .filter(|(target, center)| **center == naif_id && **target != naif_id)
+            .map(|(target, _)| *target)
+            .collect();
+        // Match the DE ordering: the Sun first around the SSB, and the planet first around its barycenter
+        children.sort_by_key(|id| {
+            let is_primary = (naif_id == 0 && *id == 10)
+                || naif_id.checked_mul(100).and_then(|v| v.checked_add(99)) == Some(*id);
+            (!is_primary, *id)
+        });
+
+        for child_id in children {
+            if path.len() == 3 {
+                return Err(NyxError::LoadingError {
+                    msg: format!(
+                        "{} is centered on {}, which is already three levels below the solar system barycenter",
+                        naif_name(child_id),
+                        naif_name(naif_id)
+                    ),
+                });
+            }
+            let mut child_path = path.to_vec();
+            child_path.push(ephem.children.len());
+            let child = Self::spk_ephemeris(child_id, centers, &child_path, paths)?;
+            ephem.children.push(child);
+        }
+
+        Ok(ephem)
+    }
+
+    fn try_from_parts(
+        xb: Xb,
+        spk_segments: HashMap<Vec<usize>, Vec<(SpkSegment, Vec<usize>)>>,
+    ) -> Result<Self, NyxError> {
         let mut cosm = Cosm {
             xb,
             frame_root: FrameTree {
@@ -177,14 +340,18 @@ impl Cosm {
                     gm: SS_MASS * SUN_GM,
                     ephem_path: [None, None, None],
                     frame_path: [None, None, None],
+                    orientation: FrameOrientation::J2000,
                 },
                 parent_rotation: None,
                 children: Vec::new(),
             },
             ephem2frame_map: HashMap::new(),
+            spk_segments,
         };
         cosm.append_xb();
         cosm.load_iau_frames()?;
+        cosm.append_earth_frames();
+        cosm.append_moon_frames();
         Ok(cosm)
     }
 
@@ -199,8 +366,14 @@ impl Cosm {
         self.frame_mut_gm("IAU Venus", 324_858.598_826_46);
         self.frame_mut_gm("EME2000", 398_600.441_5);
         self.frame_mut_gm("IAU Earth", 398_600.441_5);
+        for orientation in EARTH_FRAMES {
+            self.frame_mut_gm(&format!("Earth {orientation}"), 398_600.441_5);
+        }
         self.frame_mut_gm("Luna", 4_902.800_582_147_8);
         self.frame_mut_gm("IAU Moon", 4_902.800_582_147_8);
+        for orientation in MOON_FRAMES {
+            self.frame_mut_gm(&format!("Moon {orientation}"), 4_902.800_582_147_8);
+        }
         self.frame_mut_gm("Mars Barycenter J2000", 42_828.314258067);
         self.frame_mut_gm("IAU Mars", 42_828.314258067);
         self.frame_mut_gm("Jupiter Barycenter J2000", 126_712_767.857_80);
@@ -224,6 +397,111 @@ impl Cosm {
         )
     }
 
+    /// Adds the Earth ITRF, MOD, TOD and TEME frames as children of Earth J2000
+    fn append_earth_frames(&mut self) {
+        let earth_j2k = match self.try_frame("Earth J2000") {
+            Ok(frame) => frame,
+            Err(_) => {
+                debug!("no Earth J2000 frame, cannot add the Earth ITRF, MOD, TOD and TEME frames");
+                return;
+            }
+        };
+        let parent_path = earth_j2k.frame_path();
+        let children = &mut self.frame_root.children[parent_path[0]].children;
+        for orientation in EARTH_FRAMES {
+            let mut frame = earth_j2k;
+            if let Frame::Geoid {
+                ref mut frame_path,
+                orientation: ref mut frame_orientation,
+                ..
+            }
+            | Frame::Celestial {
+                ref mut frame_path,
+                orientation: ref mut frame_orientation,
+                ..
+            } = frame
+            {
+                *frame_path = [Some(parent_path[0]), Some(children.len()), None];
+                *frame_orientation = FrameOrientation::earth(orientation).unwrap();
+            }
+            let rotation: Box<dyn ParentRotation> = match orientation {
+                "ITRF" => Box::<Itrf>::default(),
+                "MOD" => Box::new(EarthOfDate::MeanOfDate),
+                "TOD" => Box::new(EarthOfDate::TrueOfDate),
+                _ => Box::new(EarthOfDate::Teme),
+            };
+            children.push(FrameTree {
+                name: format!("Earth {orientation}"),
+                frame,
+                parent_rotation: Some(rotation),
+                children: Vec::new(),
+            });
+        }
+    }
+
+    /// Adds the lunar PA and ME frames as children of Moon J2000
+    fn append_moon_frames(&mut self) {
+        let moon_j2k = match self.try_frame("Moon J2000") {
+            Ok(frame) => frame,
+            Err(_) => {
+                debug!("no Moon J2000 frame, cannot add the Moon PA and ME frames");
+                return;
+            }
+        };
+        let parent_path = moon_j2k.frame_path();
+        let children = &mut self.frame_root.children[parent_path[0]].children;
+        for orientation in MOON_FRAMES {
+            let mut frame = moon_j2k;
+            if let Frame::Geoid {
+                ref mut frame_path,
+                orientation: ref mut frame_orientation,
+                ..
+            }
+            | Frame::Celestial {
+                ref mut frame_path,
+                orientation: ref mut frame_orientation,
+                ..
+            } = frame
+            {
+                *frame_path = [Some(parent_path[0]), Some(children.len()), None];
+                *frame_orientation = FrameOrientation::moon(orientation).unwrap();
+            }
+            children.push(FrameTree {
+                name: format!("Moon {orientation}"),
+                frame,
+                parent_rotation: Some(Box::new(LunarFrame {
+                    mean_earth: orientation == "ME",
+                    libration: None,
+                })),
+                children: Vec::new(),
+            });
+        }
+    }
+
+    /// Sets the lunar libration angles from a binary PCK (e.g. `moon_pa_de440_200625.bpc`), used to compute the Moon PA and ME frames.
+    pub fn load_lunar_bpc(&mut self, bpc: &Bpc) -> Result<(), NyxError> {
+        let libration = Arc::new(LunarLibration::try_from_bpc(bpc)?);
+        for orientation in MOON_FRAMES {
+            let frame_path = self.try_frame(&format!("Moon {orientation}"))?.frame_path();
+            self.frame_root.children[frame_path[0]].children[frame_path[1]].parent_rotation =
+                Some(Box::new(LunarFrame {
+                    mean_earth: orientation == "ME",
+                    libration: Some(libration.clone()),
+                }));
+        }
+        Ok(())
+    }
+
+    /// Sets the Earth orientation parameters (e.g. from the IERS `finals2000A.all` file) used to compute the Earth ITRF frame.
+    pub fn load_eop(&mut self, eop: EarthOrientationParams) -> Result<(), NyxError> {
+        let frame_path = self.try_frame("Earth ITRF")?.frame_path();
+        self.frame_root.children[frame_path[0]].children[frame_path[1]].parent_rotation =
+            Some(Box::new(Itrf {
+                eop: Some(Arc::new(eop)),
+            }));
+        Ok(())
+    }
+
     /// Returns the machine path of the ephemeris whose orientation is requested
     pub fn frame_find_path_for_orientation(&self, name: &str) -> Result<Vec<usize>, NyxError> {
         if self.frame_root.name == name {
@@ -314,6 +592,7 @@ impl Cosm {
                         semi_major_radius,
                         ephem_path,
                         frame_path: [Some(pos), None, None],
+                        orientation: FrameOrientation::J2000,
                     },
                     parent_rotation: None,
                     children: Vec::new(),
@@ -332,6 +611,7 @@ impl Cosm {
                             semi_major_radius: 696_342.0,
                             ephem_path,
                             frame_path: [Some(pos), None, None],
+                            orientation: FrameOrientation::J2000,
                         },
                         parent_rotation: None,
                         children: Vec::new(),
@@ -397,58 +677,92 @@ impl Cosm {
                             Ok(src_frame) => {
                                 definition.update_from(&src_frame);
                             }
-                            Err(_) => error!(
-                                "frame `{}` is derived from unknown frame `{}`, skipping!",
-                                name, src_frame_name
-                            ),
+                            Err(_) => {
+                                error!(
+                                    "frame `{}` is derived from unknown frame `{}`, skipping!",
+                                    name, src_frame_name
+                                );
+                                continue;
+                            }
                         }
                     }
-                    let rot = &definition.rotation;
-                    let right_asc: Expr = match rot.right_asc.parse() {
-                        Ok(expr) => expr,
-                        Err(e) => {
-                            let msg = format!("[frame.{}] - could not parse right_asc `{}` - are there any special characters? {}",
-                            &name, &rot.right_asc, e);
-                            error!("{}", msg);
-                            return Err(NyxError::LoadingError { msg });
-                        }
+                    let frame_orientation = if definition.two_vector.is_some() {
+                        FrameOrientation::TwoVector
+                    } else {
+                        FrameOrientation::BodyFixed
                     };
-                    let declin: Expr = match rot.declin.parse() {
-                        Ok(expr) => expr,
-                        Err(e) => {
-                            let msg = format!("[frame.{}] - could not parse declin `{}` - are there any special characters? {}",
-                            &name, &rot.declin, e);
-                            error!("{}", msg);
-                            return Err(NyxError::LoadingError { msg });
+                    let frame_rot: Box<dyn ParentRotation> = match (
+                        &definition.rotation,
+                        &definition.two_vector,
+                    ) {
+                        (Some(rot), None) => {
+                            let right_asc: Expr = match rot.right_asc.parse() {
+                                Ok(expr) => expr,
+                                Err(e) => {
+                                    let msg = format!("[frame.{}] - could not parse right_asc `{}` - are there any special characters? {}",
+                                    &name, &rot.right_asc, e);
+                                    error!("{}", msg);
+                                    return Err(NyxError::LoadingError { msg });
+                                }
+                            };
+                            let declin: Expr = match rot.declin.parse() {
+                                Ok(expr) => expr,
+                                Err(e) => {
+                                    let msg = format!("[frame.{}] - could not parse declin `{}` - are there any special characters? {}",
+                                    &name, &rot.declin, e);
+                                    error!("{}", msg);
+                                    return Err(NyxError::LoadingError { msg });
+                                }
+                            };
+                            let w_expr: Expr = match rot.w.parse() {
+                                Ok(expr) => expr,
+                                Err(e) => {
+                                    let msg = format!("[frame.{}] - could not parse w `{}` - are there any special characters? {}",
+                                    &name, &rot.w, e);
+                                    error!("{}", msg);
+                                    return Err(NyxError::LoadingError { msg });
+                                }
+                            };
+
+                            Box::new(Euler3AxisDt::from_ra_dec_w(
+                                right_asc,
+                                declin,
+                                w_expr,
+                                match &rot.context {
+                                    Some(ctx) => ctx.clone(),
+                                    None => HashMap::new(),
+                                },
+                                match &rot.angle_unit {
+                                    Some(val) => AngleUnit::from_str(val.as_str()).unwrap(),
+                                    None => AngleUnit::Degrees,
+                                },
+                            ))
                         }
-                    };
-                    let w_expr: Expr = match rot.w.parse() {
-                        Ok(expr) => expr,
-                        Err(e) => {
-                            let msg = format!("[frame.{}] - could not parse w `{}` - are there any special characters? {}",
-                            &name, &rot.w, e);
+                        (None, Some(two_vector)) => {
+                            let parent = match &definition.inherit {
+                                Some(src_frame_name) => self.try_frame(src_frame_name.as_str())?,
+                                None => {
+                                    let msg = format!(
+                                        "[frame.{name}] - two-vector frames must inherit from another frame"
+                                    );
+                                    error!("{}", msg);
+                                    return Err(NyxError::LoadingError { msg });
+                                }
+                            };
+                            Box::new(self.try_two_vector_from_toml(name, two_vector, parent)?)
+                        }
+                        _ => {
+                            let msg = format!(
+                                "[frame.{name}] - exactly one of `rotation` or `two_vector` must be defined"
+                            );
                             error!("{}", msg);
                             return Err(NyxError::LoadingError { msg });
                         }
                     };
 
-                    let frame_rot = Euler3AxisDt::from_ra_dec_w(
-                        right_asc,
-                        declin,
-                        w_expr,
-                        match &rot.context {
-                            Some(ctx) => ctx.clone(),
-                            None => HashMap::new(),
-                        },
-                        match &rot.angle_unit {
-                            Some(val) => AngleUnit::from_str(val.as_str()).unwrap(),
-                            None => AngleUnit::Degrees,
-                        },
-                    );
-
                     // Let's now create the Frame, we'll add the ephem path and frame path just after
                     let mut new_frame = definition.as_frame();
-                    let frame_name = name.replace('_', " ").trim().to_string();
+                    let frame_name = Self::fix_frame_name(name);
 
                     // Grab the inherited frame again so we know how to place it in the frame tree
                     if let Some(src_frame_name) = &definition.inherit {
@@ -470,13 +784,16 @@ impl Cosm {
                             Frame::Celestial {
                                 ref mut ephem_path,
                                 ref mut frame_path,
+                                ref mut orientation,
                                 ..
                             }
                             | Frame::Geoid {
                                 ref mut ephem_path,
                                 ref mut frame_path,
+                                ref mut orientation,
                                 ..
                             } => {
+                                *orientation = frame_orientation;
                                 match fpath.len() {
                                     3 => {
                                         *frame_path =
@@ -506,7 +823,7 @@ impl Cosm {
                         let fnode = FrameTree {
                             name: frame_name,
                             frame: new_frame,
-                            parent_rotation: Some(Box::new(frame_rot)),
+                            parent_rotation: Some(frame_rot),
                             children: Vec::new(),
                         };
 
@@ -540,6 +857,18 @@ impl Cosm {
             String::from("Earth Barycenter J2000")
         } else if name == "ssb" || name == "ssb j2000" {
             String::from("SSB J2000")
+        } else if let Some(orientation) = EARTH_FRAMES.iter().find(|orientation| {
+            let orientation = orientation.to_lowercase();
+            name == orientation || name == format!("earth {orientation}")
+        }) {
+            format!("Earth {orientation}")
+        } else if name == "itrf93" || name == "earth itrf93" {
+            String::from("Earth ITRF")
+        } else if let Some(orientation) = MOON_FRAMES.iter().find(|orientation| {
+            let orientation = orientation.to_lowercase();
+            name == format!("moon {orientation}") || name == format!("luna {orientation}")
+        }) {
+            format!("Moon {orientation}")
         } else {
             let splt: Vec<_> = name.split(' ').collect();
             if splt[0] == "iau" {
@@ -640,7 +969,18 @@ impl Cosm {
         }
     }
 
-    /// Returns the celestial state as computed from a de4xx.{FXB,XB} file in the original frame
+    /// Returns the Cartesian state of the object of the provided ephemeris path with respect to the solar system barycenter, in the J2000 orientation
+    fn raw_ssb_state(&self, path: &[usize], epoch: Epoch) -> Result<Vector6<f64>, NyxError> {
+        let mut state = Vector6::zeros();
+        for i in 0..path.len() {
+            state += self
+                .raw_celestial_state(&path[0..=i], epoch)?
+                .to_cartesian_vec();
+        }
+        Ok(state)
+    }
+
+    /// Returns the celestial state as computed from a de4xx.{FXB,XB} file or from the SPKs in the original frame
     #[allow(clippy::comparison_chain)]
     pub fn raw_celestial_state(&self, path: &[usize], epoch: Epoch) -> Result<Orbit, NyxError> {
         if path.is_empty() {
@@ -656,6 +996,30 @@ impl Cosm {
                 self.frame_root.frame,
             ));
         }
+
+        if let Some(segments) = self.spk_segments.get(path) {
+            // SPK times are ET seconds past J2000
+            let et_s = epoch.to_et_seconds();
+            let (segment, center_path) = segments
+                .iter()
+                .find(|(seg, _)| seg.covers(et_s))
+                .ok_or_else(|| NyxError::NoInterpolationData {
+                    msg: format!("no SPK segment for {} at {epoch}", segments[0].0.target_id),
+                })?;
+            let mut state = Vector6::from_row_slice(&segment.evaluate(et_s)?);
+            let parent_path = &path[..path.len() - 1];
+            if center_path != parent_path {
+                // Translate from the center of this segment to the parent in the tree
+                state += self.raw_ssb_state(center_path, epoch)?
+                    - self.raw_ssb_state(parent_path, epoch)?;
+            }
+            return Ok(Orbit::cartesian_vec(
+                &state,
+                epoch,
+                self.frame_from_ephem_path(path),
+            ));
+        }
+
         let ephem = self.xb.ephemeris_from_path(path)?;
 
         // Compute the position as per the algorithm from jplephem
@@ -772,7 +1136,7 @@ impl Cosm {
         match correction {
             LightTimeCalc::None => {
                 let state = Orbit::cartesian(0.0, 0.0, 0.0, 0.0, 0.0, 0.0, datetime, target_frame);
-                Ok(-self.try_frame_chg(&state, frame)?)
+                self.try_frame_chg(&state, frame)
             }
             LightTimeCalc::LightTime | LightTimeCalc::Aberration => {
                 // Get the geometric states as seen from SSB
@@ -891,24 +1255,101 @@ impl Cosm {
         // Walk forward from the destination state
 
         for i in (f_common_path.len()..new_frame_path.len()).rev() {
-            if let Some(parent_rot) = &get_dcm(&new_frame_path[0..=i]).parent_rotation {
-                if let Some(next_dcm) = parent_rot.dcm_to_parent(dt) {
-                    dcm *= next_dcm;
-                }
+            if let Some(next_dcm) = self.try_dcm_to_parent(get_dcm(&new_frame_path[0..=i]), dt)? {
+                dcm *= next_dcm;
             }
         }
         // Walk backward from current state up to common node (we transpose all backward rotations)
         for i in (f_common_path.len()..state_frame_path.len()).rev() {
-            if let Some(parent_rot) = &get_dcm(&state_frame_path[0..=i]).parent_rotation {
-                if let Some(next_dcm) = parent_rot.dcm_to_parent(dt) {
-                    dcm *= next_dcm.transpose();
-                }
+            if let Some(next_dcm) = self.try_dcm_to_parent(get_dcm(&state_frame_path[0..=i]), dt)? {
+                dcm *= next_dcm.transpose();
             }
         }
 
         Ok(dcm)
     }
 
+    /// Returns the rotation of this node of the frame tree from its parent, if any
+    fn try_dcm_to_parent(
+        &self,
+        node: &FrameTree,
+        dt: Epoch,
+    ) -> Result<Option<Matrix3<f64>>, NyxError> {
+        match &node.parent_rotation {
+            Some(parent_rot) => match parent_rot.two_vector() {
+                Some(two_vector) => Ok(Some(self.try_two_vector_dcm(two_vector, dt)?)),
+                None => parent_rot.try_dcm_to_parent(dt),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Return the DCM from the parent frame of this two-vector frame to the two-vector frame
+    pub fn try_two_vector_dcm(
+        &self,
+        two_vector: &TwoVector,
+        dt: Epoch,
+    ) -> Result<Matrix3<f64>, NyxError> {
+        let vector_in_parent = |vector: &FrameVector| -> Result<Vector3<f64>, NyxError> {
+            let mut state = self.try_celestial_state(
+                &vector.target.ephem_path(),
+                dt,
+                vector.observer,
+                LightTimeCalc::None,
+            )?;
+            state.rotate_by(self.try_dcm_from_to(&vector.observer, &two_vector.parent, dt)?);
+            Ok(vector.from_state(state.radius(), state.velocity()))
+        };
+
+        two_vector
+            .dcm_from_vectors(
+                vector_in_parent(&two_vector.primary)?,
+                vector_in_parent(&two_vector.secondary)?,
+            )
+            .ok_or_else(|| NyxError::MathDomain {
+                msg: format!("the primary and secondary vectors of the two-vector frame are parallel at {dt}"),
+            })
+    }
+
+    /// Builds a two-vector rotation from its TOML definition
+    fn try_two_vector_from_toml(
+        &self,
+        name: &str,
+        definition: &frame_serde::TwoVectorToml,
+        parent: Frame,
+    ) -> Result<TwoVector, NyxError> {
+        let loading_err = |msg: String| {
+            let msg = format!("[frame.{name}] - {msg}");
+            error!("{}", msg);
+            NyxError::LoadingError { msg }
+        };
+        let axis = |axis: &str| FrameAxis::from_str(axis).map_err(|e| loading_err(format!("{e}")));
+        let vector = |vector: &frame_serde::FrameVectorToml| -> Result<FrameVector, NyxError> {
+            Ok(FrameVector {
+                kind: TwoVectorKind::from_str(&vector.kind)
+                    .map_err(|e| loading_err(format!("{e}")))?,
+                observer: self.try_frame(&vector.observer)?,
+                target: self.try_frame(&vector.target)?,
+            })
+        };
+
+        let primary_axis = axis(&definition.primary_axis)?;
+        let secondary_axis = axis(&definition.secondary_axis)?;
+        if primary_axis.index == secondary_axis.index {
+            return Err(loading_err(
+                "the primary and secondary axes must be different".to_string(),
+            ));
+        }
+
+        Ok(TwoVector {
+            parent,
+            primary_axis,
+            primary: vector(&definition.primary)?,
+            secondary_axis,
+            secondary: vector(&definition.secondary)?,
+        })
+    }
+
     /// Return the position and velocity DCM (6x6) to go from the `from` frame to the `to` frame
     #[allow(clippy::identity_op)]
     pub fn try_dcm_from_to(
@@ -955,72 +1396,24 @@ impl Cosm {
         let new_ephem_path = new_frame.ephem_path();
         let state_ephem_path = state.frame.ephem_path();
 
-        // This doesn't make sense, but somehow the following algorithm only works when converting spacecraft states
-        let mut new_state = if state.rmag_km() > 0.0 {
-            *state
-        } else if state_ephem_path.is_empty() {
-            // SSB, let's invert this
-            -*state
-        } else {
-            *state
-        };
+        let mut new_state = *state;
 
         // If we only need a rotation, let's skip trying to find the translation
         if new_ephem_path != state_ephem_path {
             // Let's get the translation path between both both states.
             let e_common_path = self.find_common_root(&new_ephem_path, &state_ephem_path);
 
-            // This doesn't make sense, but somehow the following algorithm only works when converting spacecraft states
-            new_state = if state.rmag_km() > 0.0 {
-                // Walk backward from current state up to common node
-                for i in (e_common_path.len()..state_ephem_path.len()).rev() {
-                    let next_state =
-                        self.raw_celestial_state(&state_ephem_path[0..=i], state.epoch)?;
-                    new_state += next_state;
-                }
-
-                // Walk forward from the destination state
-                for i in (e_common_path.len()..new_ephem_path.len()).rev() {
-                    let next_state =
-                        self.raw_celestial_state(&new_ephem_path[0..=i], state.epoch)?;
-                    new_state -= next_state;
-                }
-
-                new_state
-            } else {
-                let mut negated_fwd = false;
-
-                // Walk forward from the destination state
-                for i in (e_common_path.len()..new_ephem_path.len()).rev() {
-                    let next_state =
-                        self.raw_celestial_state(&new_ephem_path[0..=i], state.epoch)?;
-                    if new_ephem_path.len() < state_ephem_path.len() && i == e_common_path.len() {
-                        // We just crossed the common point going forward, so let's add the opposite of this state
-                        new_state -= next_state;
-                        negated_fwd = true;
-                    } else {
-                        new_state += next_state;
-                    }
-                }
-                // Walk backward from current state up to common node
-                for i in (e_common_path.len()..state_ephem_path.len()).rev() {
-                    let next_state =
-                        self.raw_celestial_state(&state_ephem_path[0..=i], state.epoch)?;
-                    if !negated_fwd && i == e_common_path.len() {
-                        // We just crossed the common point (and haven't passed it going forward), so let's negate this state
-                        new_state -= next_state;
-                    } else {
-                        new_state += next_state;
-                    }
-                }
+            // Walk backward from current state up to common node
+            for i in (e_common_path.len()..state_ephem_path.len()).rev() {
+                let next_state = self.raw_celestial_state(&state_ephem_path[0..=i], state.epoch)?;
+                new_state += next_state;
+            }
 
-                if negated_fwd {
-                    // Because we negated the state going forward, let's flip it back to its correct orientation now.
-                    -new_state
-                } else {
-                    new_state
-                }
-            };
+            // Walk forward from the destination state
+            for i in (e_common_path.len()..new_ephem_path.len()).rev() {
+                let next_state = self.raw_celestial_state(&new_ephem_path[0..=i], state.epoch)?;
+                new_state -= next_state;
+            }
         }
         new_state.frame = new_frame;
         Ok(new_state)
@@ -1031,6 +1424,15 @@ impl Cosm {
         if state.frame == new_frame {
             return Ok(*state);
         }
+        // Synodic frames are converted through the J2000 frame of their primary
+        if state.frame.is_synodic() {
+            let inertial = self.try_synodic_to_inertial(state)?;
+            return self.try_frame_chg(&inertial, new_frame);
+        } else if let Frame::Synodic { primary, .. } = new_frame {
+            let inertial =
+                self.try_frame_chg(state, self.frame_from_ephem_path(primary.ephem_path()))?;
+            return self.try_inertial_to_synodic(&inertial, new_frame);
+        }
         // Let's perform the translation
         let mut new_state = self.try_frame_translation(state, new_frame)?;
         // And now let's compute the rotation path
@@ -1074,10 +1476,70 @@ impl Cosm {
     }
 }
 
+/// Returns the name used in the ephemeris tree for the provided NAIF ID, matching the names of the DE XB files
+fn naif_name(naif_id: i32) -> String {
+    match naif_id {
+        0 => "Solar System Barycenter".to_string(),
+        1 => "Mercury Barycenter".to_string(),
+        2 => "Venus Barycenter".to_string(),
+        3 => "Earth Barycenter".to_string(),
+        4 => "Mars Barycenter".to_string(),
+        5 => "Jupiter Barycenter".to_string(),
+        6 => "Saturn Barycenter".to_string(),
+        7 => "Uranus Barycenter".to_string(),
+        8 => "Neptune Barycenter".to_string(),
+        9 => "Pluto Barycenter".to_string(),
+        10 => "Sun".to_string(),
+        199 => "Mercury".to_string(),
+        299 => "Venus".to_string(),
+        301 => "Moon".to_string(),
+        399 => "Earth".to_string(),
+        401 => "Phobos".to_string(),
+        402 => "Deimos".to_string(),
+        499 => "Mars".to_string(),
+        599 => "Jupiter".to_string(),
+        699 => "Saturn".to_string(),
+        799 => "Uranus".to_string(),
+        899 => "Neptune".to_string(),
+        999 => "Pluto".to_string(),
+        _ => format!("Body {naif_id}"),
+    }
+}
+
+/// Returns the constants of the provided NAIF ID: GM values from the DE440 `gm_de440.tpc` kernel, and shape from the IAU 2015 report.
+fn naif_constants(naif_id: i32) -> Vec<(&'static str, f64, XbUnit)> {
+    let (gm, shape) = match naif_id {
+        1 | 199 => (22_031.868_551, Some((2_440.53, 0.0))),
+        2 | 299 => (324_858.592, Some((6_051.8, 0.0))),
+        3 => (403_503.235_502, None),
+        399 => (398_600.435_507, Some((6_378.136_6, 0.003_352_813_1))),
+        301 => (4_902.800_118, Some((1_737.4, 0.0))),
+        4 => (42_828.375_816, Some((3_396.19, 0.005_886_007_6))),
+        499 => (42_828.373_62, Some((3_396.19, 0.005_886_007_6))),
+        5 => (126_712_764.1, Some((71_492.0, 0.064_870_439_3))),
+        6 => (37_940_584.841_8, Some((60_268.0, 0.097_962_432_6))),
+        7 => (5_794_556.4, Some((25_559.0, 0.022_927_322_3))),
+        8 => (6_836_527.100_58, Some((24_764.0, 0.017_081_406_9))),
+        9 => (975.5, Some((1_188.3, 0.0))),
+        // The SSB and Sun frames are built from the Nyx constants
+        0 | 10 => return Vec::new(),
+        // Unknown GM: still build the frame so it can be used, its GM can be set with `Cosm::frame_mut_gm`
+        _ => (0.0, None),
+    };
+
+    let mut constants = vec![("GM", gm, XbUnit::Km3S2)];
+    if let Some((radius, flattening)) = shape {
+        constants.push(("Equatorial radius", radius, XbUnit::Km));
+        constants.push(("Flattening", flattening, XbUnit::Dimensionless));
+    }
+    constants
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::cosmic::Bodies;
+    use crate::linalg::Vector3;
 
     #[test]
     fn test_cosm_indirect() {
@@ -1599,12 +2061,456 @@ mod tests {
             Cosm::fix_frame_name("Mars barycenter j2000"),
             "Mars Barycenter J2000"
         );
+        assert_eq!(Cosm::fix_frame_name("ITRF93"), "Earth ITRF");
+        assert_eq!(Cosm::fix_frame_name("earth_itrf"), "Earth ITRF");
+        assert_eq!(Cosm::fix_frame_name("TEME"), "Earth TEME");
+        assert_eq!(Cosm::fix_frame_name("earth tod"), "Earth TOD");
+    }
+
+    #[test]
+    fn test_cosm_itrf() {
+        use crate::io::eop::EopRecord;
+        use std::f64::consts::TAU;
+
+        let mut cosm = Cosm::fixed_planets();
+        let eme2k = cosm.frame("EME2000");
+        let itrf = cosm.frame("ITRF");
+        let iau_earth = cosm.frame("IAU Earth");
+
+        assert!(itrf.is_itrf());
+        assert!(!iau_earth.is_itrf());
+        assert_eq!(format!("{itrf}"), "Earth ITRF");
+        assert_eq!(format!("{iau_earth}"), "IAU Earth");
+        assert_eq!(cosm.frame(&format!("{itrf}")), itrf);
+        assert_eq!(itrf.gm(), eme2k.gm());
+
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2022, 3, 1);
+        let dcm_itrf = cosm.try_position_dcm_from_to(&eme2k, &itrf, epoch).unwrap();
+        let dcm_iau = cosm
+            .try_position_dcm_from_to(&eme2k, &iau_earth, epoch)
+            .unwrap();
+
+        // The pole of both models only differs by the nutation
+        let z_err = (dcm_itrf.row(2) - dcm_iau.row(2)).norm();
+        assert!(z_err < 1e-4, "pole error of {z_err} rad");
+
+        // In the GCRS, the prime meridian is the Greenwich mean sidereal time minus the precession in right ascension since J2000,
+        // up to the equation of the equinoxes.
+        let tu = (epoch.to_jde_utc_days() - 2_451_545.0) / 36_525.0;
+        let gmst_s =
+            67_310.548_41 + (876_600.0 * 3_600.0 + 8_640_184.812_866) * tu + 0.093_104 * tu.powi(2)
+                - 6.2e-6 * tu.powi(3);
+        let gmst = (gmst_s / 86_400.0).rem_euclid(1.0) * TAU;
+        let expected = (gmst - (4_612.156_534 * tu).to_radians() / 3_600.0).rem_euclid(TAU);
+        let prime_meridian = dcm_itrf[(0, 1)].atan2(dcm_itrf[(0, 0)]).rem_euclid(TAU);
+        let mut ra_err = (prime_meridian - expected).abs();
+        if ra_err > TAU / 2.0 {
+            ra_err = TAU - ra_err;
+        }
+        assert!(ra_err < 1e-4, "prime meridian error of {ra_err} rad");
+
+        // Round trip of a GEO state, and its geodetic position
+        let geo = Orbit::keplerian(42_164.0, 1e-4, 0.1, 10.0, 20.0, 30.0, epoch, eme2k);
+        let geo_itrf = cosm.frame_chg(&geo, itrf);
+        assert!(geo_itrf.geodetic_latitude_deg().abs() < 0.2);
+        assert!((geo_itrf.geodetic_height_km() - 35_786.0).abs() < 10.0);
+        let geo_rtn = cosm.frame_chg(&geo_itrf, eme2k);
+        assert!((geo_rtn.radius() - geo.radius()).norm() < 1e-8);
+        assert!((geo_rtn.velocity() - geo.velocity()).norm() < 1e-8);
+
+        // A ground station in the ITRF
+        let madrid = Orbit::from_geodesic(40.427_222, 4.250_556, 0.834_939, epoch, itrf);
+        let madrid_rtn = cosm.frame_chg(&cosm.frame_chg(&madrid, eme2k), itrf);
+        assert!((madrid_rtn.geodetic_latitude_deg() - 40.427_222).abs() < 1e-7);
+        assert!((madrid_rtn.geodetic_longitude_deg() - 4.250_556).abs() < 1e-7);
+
+        // Earth orientation parameters shift the surface by the UT1-UTC and the polar motion
+        let eop = EarthOrientationParams::from_records(vec![
+            EopRecord {
+                mjd_utc: 59_638.0,
+                x_pole_arcsec: 0.1,
+                y_pole_arcsec: 0.3,
+                ut1_utc_s: -0.2,
+                ..Default::default()
+            },
+            EopRecord {
+                mjd_utc: 59_640.0,
+                x_pole_arcsec: 0.1,
+                y_pole_arcsec: 0.3,
+                ut1_utc_s: -0.2,
+                ..Default::default()
+            },
+        ])
+        .unwrap();
+        cosm.load_eop(eop).unwrap();
+        let dcm_itrf_eop = cosm.try_position_dcm_from_to(&eme2k, &itrf, epoch).unwrap();
+        let surface = dcm_itrf.transpose() * Vector3::new(6_378.137, 0.0, 0.0);
+        let shift_km = (dcm_itrf_eop * surface - dcm_itrf * surface).norm();
+        // 0.2 seconds of Earth rotation is about 93 meters at the equator
+        assert!((0.08..0.12).contains(&shift_km), "shift of {shift_km} km");
+
+        // The EOP are not extrapolated
+        assert!(cosm
+            .try_position_dcm_from_to(&eme2k, &itrf, epoch + Unit::Day * 5)
+            .is_err());
+    }
+
+    #[test]
+    fn test_cosm_two_vector_frames() {
+        let mut cosm = Cosm::de438_raw();
+        cosm.append_frames(
+            r#"
+            [frames.sun_earth_rotating]
+            inherit = "Sun J2000"
+            gm = -1
+            flattening = -1
+            equatorial_radius = -1
+            semi_major_radius = -1
+            [frames.sun_earth_rotating.two_vector]
+            primary_axis = "X"
+            primary = { kind = "position", observer = "Sun J2000", target = "Earth J2000" }
+            secondary_axis = "Z"
+            secondary = { kind = "momentum", observer = "Sun J2000", target = "Earth J2000" }
+
+            [frames.earth_moon_vnc]
+            inherit = "Moon J2000"
+            gm = -1
+            flattening = -1
+            equatorial_radius = -1
+            semi_major_radius = -1
+            [frames.earth_moon_vnc.two_vector]
+            primary_axis = "X"
+            primary = { kind = "velocity", observer = "Earth J2000", target = "Moon J2000" }
+            secondary_axis = "Y"
+            secondary = { kind = "momentum", observer = "Earth J2000", target = "Moon J2000" }
+            "#,
+        )
+        .unwrap();
+
+        let sun_earth = cosm.frame("sun earth rotating");
+        let earth_moon_vnc = cosm.frame("earth moon vnc");
+        let eme2k = cosm.frame("EME2000");
+        let luna = cosm.frame("Moon J2000");
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2022, 1, 1);
+
+        // The Earth is on the X axis of the Sun-Earth rotating frame, and instantaneously only moves radially
+        let earth =
+            cosm.celestial_state(&eme2k.ephem_path(), epoch, sun_earth, LightTimeCalc::None);
+        assert!(earth.x_km > 1.4e8);
+        assert!(
+            earth.y_km.abs() < 1e-3 && earth.z_km.abs() < 1e-3,
+            "{earth}"
+        );
+        assert!(
+            earth.vy_km_s.abs() < 1e-4 && earth.vz_km_s.abs() < 1e-4,
+            "{earth}"
+        );
+
+        // The velocity of the Moon relative to the Earth is along the V axis of the VNC frame
+        let moon = cosm.celestial_state(&luna.ephem_path(), epoch, eme2k, LightTimeCalc::None);
+        let dcm = cosm
+            .try_position_dcm_from_to(&luna, &earth_moon_vnc, epoch)
+            .unwrap();
+        let v_vnc = dcm * moon.velocity();
+        assert!((v_vnc[0] - moon.vmag_km_s()).abs() < 1e-9, "{v_vnc}");
+        // The position relative to the Earth is orthogonal to the momentum, and along +C since C = V x N
+        let r_vnc = dcm * moon.radius();
+        assert!(r_vnc[1].abs() < 1e-6 && r_vnc[2] > 0.0, "{r_vnc}");
+
+        // Round trip
+        let state = Orbit::cartesian(1.0e4, -2.0e3, 500.0, 0.1, 1.2, -0.3, epoch, luna);
+        let back = cosm.frame_chg(&cosm.frame_chg(&state, earth_moon_vnc), luna);
+        assert!((back.radius() - state.radius()).norm() < 1e-6);
+        assert!((back.velocity() - state.velocity()).norm() < 1e-9);
+
+        // Invalid definitions
+        assert!(cosm
+            .append_frames(
+                r#"
+            [frames.invalid]
+            inherit = "Moon J2000"
+            gm = -1
+            flattening = -1
+            equatorial_radius = -1
+            semi_major_radius = -1
+            [frames.invalid.two_vector]
+            primary_axis = "X"
+            primary = { kind = "velocity", observer = "Earth J2000", target = "Moon J2000" }
+            secondary_axis = "-X"
+            secondary = { kind = "momentum", observer = "Earth J2000", target = "Moon J2000" }
+            "#,
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_cosm_frame_orientations() {
+        let cosm = Cosm::fixed_planets();
+
+        // The IAU frames are the first children of the J2000 frames, as in the DE files
+        let iau_earth = cosm.frame("IAU Earth");
+        assert_eq!(iau_earth.frame_path()[1], 0);
+        assert_eq!(iau_earth.orientation(), FrameOrientation::BodyFixed);
+        assert_eq!(format!("{iau_earth}"), "IAU Earth");
+        assert!(iau_earth.earth_frame_name().is_none());
+
+        for orientation in EARTH_FRAMES {
+            let frame = cosm.frame(&format!("Earth {orientation}"));
+            assert_eq!(frame.earth_frame_name(), Some(orientation));
+            assert_eq!(format!("{frame}"), format!("Earth {orientation}"));
+            assert_eq!(frame.is_body_fixed(), orientation == "ITRF");
+        }
+        for orientation in MOON_FRAMES {
+            let frame = cosm.frame(&format!("Moon {orientation}"));
+            assert_eq!(frame.moon_frame_name(), Some(orientation));
+            assert!(frame.is_body_fixed());
+        }
+
+        let eme2k = cosm.frame("EME2000");
+        assert_eq!(eme2k.orientation(), FrameOrientation::J2000);
+        assert!(!eme2k.is_body_fixed());
+    }
+
+    #[test]
+    fn test_cosm_lunar_frames() {
+        use crate::io::bpc::{Bpc, BpcSegment};
+        use crate::io::spk::SpkSegmentData;
+        use crate::od::GroundStation;
+
+        // Only the orientations matter here, so all of the bodies are fixed
+        let mut cosm = Cosm::fixed_planets();
+        let moon_j2k = cosm.frame("Moon J2000");
+        let moon_pa = cosm.frame("moon_pa");
+        let moon_me = cosm.frame("Luna ME");
+        let iau_moon = cosm.frame("IAU Moon");
+
+        assert_eq!(format!("{moon_pa}"), "Moon PA");
+        assert_eq!(cosm.frame(&format!("{moon_me}")), moon_me);
+        assert_eq!(moon_me.moon_frame_name(), Some("ME"));
+        assert!(iau_moon.moon_frame_name().is_none());
+        assert!(moon_pa.is_body_fixed() && moon_me.is_body_fixed());
+
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2023, 1, 1);
+        let state = Orbit::keplerian(1_900.0, 0.01, 85.0, 10.0, 20.0, 30.0, epoch, moon_j2k);
+        // No libration loaded yet
+        assert!(cosm.try_frame_chg(&state, moon_pa).is_err());
+
+        // Build a constant libration matching the IAU Moon orientation at this epoch
+        let iau_dcm = cosm
+            .try_position_dcm_from_to(&moon_j2k, &iau_moon, epoch)
+            .unwrap();
+        let phi = iau_dcm[(2, 0)].atan2(-iau_dcm[(2, 1)]);
+        let delta = iau_dcm[(2, 2)].acos();
+        let w = iau_dcm[(0, 2)].atan2(iau_dcm[(1, 2)]);
+        let et_s = epoch.to_et_seconds();
+        cosm.load_lunar_bpc(&Bpc {
+            segments: vec![BpcSegment {
+                name: "constant libration".to_string(),
+                frame_id: 31008,
+                reference_id: 1,
+                data_type: 2,
+                start_et_s: et_s - 86_400.0,
+                end_et_s: et_s + 86_400.0,
+                data: SpkSegmentData::Chebyshev {
+                    init_s: et_s - 86_400.0,
+                    interval_s: 172_800.0,
+                    record_size: 5,
+                    with_velocity: false,
+                    records: vec![et_s, 86_400.0, phi, delta, w],
+                },
+            }],
+        })
+        .unwrap();
+
+        let state_pa = cosm.frame_chg(&state, moon_pa);
+        let state_iau = cosm.frame_chg(&state, iau_moon);
+        assert!((state_pa.radius() - state_iau.radius()).norm() < 1e-6);
+        let back = cosm.frame_chg(&state_pa, moon_j2k);
+        assert!((back.radius() - state.radius()).norm() < 1e-6);
+        assert!((back.velocity() - state.velocity()).norm() < 1e-9);
+
+        // A landing site in the ME frame is less than a kilometer from the same coordinates in the PA frame
+        let site_me = GroundStation::from_point("Site".to_string(), -89.5, 45.0, 0.0, moon_me)
+            .to_orbit(epoch);
+        let site_pa = GroundStation::from_point("Site".to_string(), -89.5, 45.0, 0.0, moon_pa)
+            .to_orbit(epoch);
+        assert!((site_me.geodetic_latitude_deg() + 89.5).abs() < 1e-9);
+        let offset_km = (cosm.frame_chg(&site_me, moon_j2k).radius()
+            - cosm.frame_chg(&site_pa, moon_j2k).radius())
+        .norm();
+        assert!(offset_km > 0.5 && offset_km < 1.0, "{offset_km} km");
+
+        // Outside of the libration data
+        let mut later = state;
+        later.epoch = epoch + 2 * Unit::Day;
+        assert!(cosm.try_frame_chg(&later, moon_me).is_err());
+    }
+
+    #[test]
+    fn test_cosm_teme_mod_tod() {
+        let cosm = Cosm::de438();
+        let eme2k = cosm.frame("EME2000");
+        let teme = cosm.frame("TEME");
+        let tod = cosm.frame("TOD");
+        let mod_frame = cosm.frame("MOD");
+
+        assert_eq!(format!("{teme}"), "Earth TEME");
+        assert_eq!(format!("{tod}"), "Earth TOD");
+        assert_eq!(format!("{mod_frame}"), "Earth MOD");
+        assert!(!teme.is_body_fixed());
+        assert!(cosm.frame("ITRF").is_body_fixed());
+        assert!(cosm.frame("IAU Earth").is_body_fixed());
+
+        // Vallado, Revisiting Spacetrack Report #3, AIAA 2006-6753, and Fundamentals of Astrodynamics, example 3-15.
+        // The reference values include the EOP nutation corrections, hence the meter level tolerance.
+        let epoch = Epoch::from_gregorian_utc(2004, 4, 6, 7, 51, 28, 386_009_000);
+        let state_teme = Orbit::cartesian(
+            5_094.180_162_10,
+            6_127.644_659_50,
+            6_380.344_532_70,
+            -4.746_131_487,
+            0.785_818_041,
+            5.531_931_288,
+            epoch,
+            teme,
+        );
+
+        let state_tod = cosm.try_frame_chg(&state_teme, tod).unwrap();
+        let tod_ref = Vector3::new(5_094.516_203_00, 6_127.365_278_40, 6_380.344_532_70);
+        // The TEME and TOD frames share the same pole
+        assert!((state_tod.z_km - state_teme.z_km).abs() < 1e-9);
+        assert!((state_tod.radius() - tod_ref).norm() < 5e-3);
+
+        let state_mod = cosm.try_frame_chg(&state_teme, mod_frame).unwrap();
+        let mod_ref = Vector3::new(5_094.028_374_50, 6_127.870_816_40, 6_380.248_516_40);
+        assert!((state_mod.radius() - mod_ref).norm() < 5e-3);
+
+        let state_eme2k = cosm.try_frame_chg(&state_teme, eme2k).unwrap();
+        let eme2k_ref = Vector3::new(5_102.508_957_90, 6_123.011_400_70, 6_378.136_928_20);
+        let eme2k_vel_ref = Vector3::new(-4.743_220_170, 0.790_536_492, 5.533_755_724);
+        assert!((state_eme2k.radius() - eme2k_ref).norm() < 5e-3);
+        assert!((state_eme2k.velocity() - eme2k_vel_ref).norm() < 1e-5);
+
+        // And back
+        let state_rtn = cosm.try_frame_chg(&state_eme2k, teme).unwrap();
+        assert!((state_rtn.radius() - state_teme.radius()).norm() < 1e-8);
+        assert!((state_rtn.velocity() - state_teme.velocity()).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_cosm_from_spk() {
+        let fixed = SpkSegment::fixed;
+
+        let planets = Spk {
+            segments: vec![
+                fixed(3, 0, 1.5e8),
+                fixed(10, 0, 1e3),
+                fixed(399, 3, -4e3),
+                fixed(301, 3, 3.8e5),
+            ],
+        };
+        let small_bodies = Spk {
+            segments: vec![fixed(301, 3, 3.9e5), fixed(2000433, 10, 2e8)],
+        };
+
+        let mut cosm = Cosm::try_from_spk(vec![planets.clone(), small_bodies]).unwrap();
+
+        // Frame paths cannot be more than three levels deep
+        let deep = Spk {
+            segments: vec![fixed(-1000, 301, 1e3), fixed(-1001, -1000, 1.0)],
+        };
+        assert!(Cosm::try_from_spk(vec![planets, deep]).is_err());
+
+        // Same ordering as the DE files
+        assert_eq!(cosm.xb.ephemeris_find_path("Sun".to_string()).unwrap(), [0]);
+        assert_eq!(
+            cosm.xb.ephemeris_find_path("Earth".to_string()).unwrap(),
+            [1, 0]
+        );
+        assert_eq!(
+            cosm.xb.ephemeris_find_path("Moon".to_string()).unwrap(),
+            [1, 1]
+        );
+        assert_eq!(
+            cosm.xb
+                .ephemeris_find_path("Body 2000433".to_string())
+                .unwrap(),
+            [0, 0]
+        );
+
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+        // The Moon segment of the last kernel has priority
+        let eme2k = cosm.frame("EME2000");
+        let moon = cosm.celestial_state(&[1, 1], epoch, eme2k, LightTimeCalc::None);
+        assert!((moon.rmag_km() - 3.94e5).abs() < 1e-6);
+        // Objects two levels deep are translated through their barycenter
+        let ssb = cosm.frame("SSB J2000");
+        let earth = cosm.celestial_state(&[1, 0], epoch, ssb, LightTimeCalc::None);
+        assert!((earth.x_km - (1.5e8 - 4e3)).abs() < 1e-6);
+        let moon = cosm.celestial_state(&[1, 1], epoch, ssb, LightTimeCalc::None);
+        assert!((moon.x_km - (1.5e8 + 3.9e5)).abs() < 1e-6);
+
+        // Frames of unknown objects are usable once their GM is set
+        cosm.frame_mut_gm("Body 2000433 J2000", 4.463e-4);
+        let eros = cosm.frame("Body 2000433 J2000");
+        assert!((eros.gm() - 4.463e-4).abs() < f64::EPSILON);
+        let earth = cosm.celestial_state(&[1, 0], epoch, eros, LightTimeCalc::None);
+        assert!((earth.rmag_km() - (2e8 + 1e3 - 1.5e8 + 4e3)).abs() < 1e-6);
+
+        // Lower priority segments are used outside of the time span of the higher priority ones, even with respect to another center
+        let mut moon_short = fixed(301, 3, 3.7e5);
+        moon_short.start_et_s = -1e3;
+        moon_short.end_et_s = 1e3;
+        let layered = Cosm::try_from_spk(vec![
+            Spk {
+                segments: vec![
+                    fixed(3, 0, 1.5e8),
+                    fixed(10, 0, 1e3),
+                    fixed(301, 0, 1.5e8 + 3.6e5),
+                    // Orphaned object, whose center is unknown
+                    fixed(-1002, -2000, 1.0),
+                ],
+            },
+            Spk {
+                segments: vec![moon_short],
+            },
+        ])
+        .unwrap();
+        assert_eq!(
+            layered.xb.ephemeris_find_path("Moon".to_string()).unwrap(),
+            [1, 0]
+        );
+        assert!(layered.try_frame("Body -1002 J2000").is_err());
+        let ssb = layered.frame("SSB J2000");
+        let moon = layered.celestial_state(
+            &[1, 0],
+            Epoch::from_et_seconds(0.0),
+            ssb,
+            LightTimeCalc::None,
+        );
+        assert!((moon.x_km - (1.5e8 + 3.7e5)).abs() < 1e-6);
+        let moon = layered.celestial_state(
+            &[1, 0],
+            Epoch::from_et_seconds(1e5),
+            ssb,
+            LightTimeCalc::None,
+        );
+        assert!((moon.x_km - (1.5e8 + 3.6e5)).abs() < 1e-6);
+        let emb = layered.frame("Earth Barycenter J2000");
+        let moon = layered.celestial_state(
+            &[1, 0],
+            Epoch::from_et_seconds(1e5),
+            emb,
+            LightTimeCalc::None,
+        );
+        assert!((moon.x_km - 3.6e5).abs() < 1e-6);
     }
 
     #[test]
     fn test_cosm_rotation_spiceypy_pos_dcm() {
         // These validation tests are from tests/spiceypy/rotations.py
-        use crate::linalg::{Matrix3, Vector3};
+        use crate::linalg::Matrix3;
         use std::f64::EPSILON;
         let cosm = Cosm::de438();
 
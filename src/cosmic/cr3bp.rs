@@ -0,0 +1,315 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{Bodies, Cosm, Frame, LightTimeCalc, Orbit};
+use crate::linalg::{Matrix3, Vector3};
+use crate::time::Epoch;
+use crate::NyxError;
+
+/// Maximum number of Newton iterations to locate the collinear libration points
+const MAX_ITERATIONS: usize = 100;
+/// Convergence tolerance on the normalized position of the collinear libration points
+const LIBRATION_TOL: f64 = 1e-14;
+
+impl Cosm {
+    /// Attempts to build the synodic frame of the circular restricted three body problem of the provided primary and secondary.
+    ///
+    /// The mass ratio is computed from the GM of both bodies. The characteristic length is the osculating semi major axis
+    /// of the secondary around the primary at the provided epoch, and the characteristic time is the inverse of the
+    /// associated mean motion.
+    pub fn try_synodic_frame(
+        &self,
+        primary: Bodies,
+        secondary: Bodies,
+        epoch: Epoch,
+    ) -> Result<Frame, NyxError> {
+        if primary == secondary {
+            return Err(NyxError::CustomError {
+                msg: format!("synodic frame requires two distinct bodies, got {primary:?} twice"),
+            });
+        }
+        let primary_frame = self.frame_from_ephem_path(primary.ephem_path());
+        let secondary_frame = self.frame_from_ephem_path(secondary.ephem_path());
+        let gm = primary_frame.gm() + secondary_frame.gm();
+
+        let rel = self.try_celestial_state(
+            secondary.ephem_path(),
+            epoch,
+            primary_frame,
+            LightTimeCalc::None,
+        )?;
+        let length_km = 1.0 / (2.0 / rel.rmag_km() - rel.vmag_km_s().powi(2) / gm);
+        if length_km <= 0.0 {
+            return Err(NyxError::MathDomain {
+                msg: format!("{secondary:?} is not on an elliptical orbit around {primary:?}"),
+            });
+        }
+
+        Ok(Frame::Synodic {
+            primary,
+            secondary,
+            mass_ratio: secondary_frame.gm() / gm,
+            length_km,
+            time_s: (length_km.powi(3) / gm).sqrt(),
+        })
+    }
+
+    /// Returns the synodic frame of the circular restricted three body problem of the provided primary and secondary, or panics
+    pub fn synodic_frame(&self, primary: Bodies, secondary: Bodies, epoch: Epoch) -> Frame {
+        self.try_synodic_frame(primary, secondary, epoch).unwrap()
+    }
+
+    /// Returns the state of the barycenter in the J2000 frame of the primary, the DCM from the synodic axes to that frame,
+    /// and the instantaneous angular velocity of the synodic axes, all at the provided epoch.
+    fn synodic_axes(
+        &self,
+        frame: Frame,
+        epoch: Epoch,
+    ) -> Result<(Orbit, Matrix3<f64>, Vector3<f64>), NyxError> {
+        match frame {
+            Frame::Synodic {
+                primary,
+                secondary,
+                mass_ratio,
+                ..
+            } => {
+                let primary_frame = self.frame_from_ephem_path(primary.ephem_path());
+                let rel = self.try_celestial_state(
+                    secondary.ephem_path(),
+                    epoch,
+                    primary_frame,
+                    LightTimeCalc::None,
+                )?;
+                let x_hat = rel.r_hat();
+                let z_hat = rel.hvec() / rel.hmag_km2_s();
+                let y_hat = z_hat.cross(&x_hat);
+                let omega = rel.hvec() / rel.rmag_km().powi(2);
+
+                let mut barycenter = rel;
+                barycenter.x_km *= mass_ratio;
+                barycenter.y_km *= mass_ratio;
+                barycenter.z_km *= mass_ratio;
+                barycenter.vx_km_s *= mass_ratio;
+                barycenter.vy_km_s *= mass_ratio;
+                barycenter.vz_km_s *= mass_ratio;
+
+                Ok((
+                    barycenter,
+                    Matrix3::from_columns(&[x_hat, y_hat, z_hat]),
+                    omega,
+                ))
+            }
+            _ => Err(NyxError::CustomError {
+                msg: format!("{frame} is not a synodic frame"),
+            }),
+        }
+    }
+
+    /// Converts a normalized state in a synodic frame into the J2000 frame of the primary.
+    ///
+    /// The synodic axes are computed from the ephemeris of the primaries at the epoch of the state, but the state
+    /// is dimensionalized with the (constant) characteristic length and time of the frame.
+    pub(crate) fn try_synodic_to_inertial(&self, state: &Orbit) -> Result<Orbit, NyxError> {
+        let (length_km, time_s) = match state.frame {
+            Frame::Synodic {
+                length_km, time_s, ..
+            } => (length_km, time_s),
+            _ => {
+                return Err(NyxError::CustomError {
+                    msg: format!("{} is not a synodic frame", state.frame),
+                })
+            }
+        };
+        let (barycenter, dcm, omega) = self.synodic_axes(state.frame, state.epoch)?;
+
+        let radius = dcm * (state.radius() * length_km);
+        let velocity = dcm * (state.velocity() * length_km / time_s) + omega.cross(&radius);
+
+        let mut inertial = barycenter;
+        inertial.x_km += radius[0];
+        inertial.y_km += radius[1];
+        inertial.z_km += radius[2];
+        inertial.vx_km_s += velocity[0];
+        inertial.vy_km_s += velocity[1];
+        inertial.vz_km_s += velocity[2];
+        Ok(inertial)
+    }
+
+    /// Converts a state in the J2000 frame of the primary into the provided synodic frame, in normalized units.
+    pub(crate) fn try_inertial_to_synodic(
+        &self,
+        state: &Orbit,
+        frame: Frame,
+    ) -> Result<Orbit, NyxError> {
+        let (length_km, time_s) = match frame {
+            Frame::Synodic {
+                length_km, time_s, ..
+            } => (length_km, time_s),
+            _ => {
+                return Err(NyxError::CustomError {
+                    msg: format!("{frame} is not a synodic frame"),
+                })
+            }
+        };
+        let (barycenter, dcm, omega) = self.synodic_axes(frame, state.epoch)?;
+
+        let radius = state.radius() - barycenter.radius();
+        let velocity = state.velocity() - barycenter.velocity() - omega.cross(&radius);
+        let radius = dcm.transpose() * radius / length_km;
+        let velocity = dcm.transpose() * velocity * time_s / length_km;
+
+        let mut synodic = *state;
+        synodic.x_km = radius[0];
+        synodic.y_km = radius[1];
+        synodic.z_km = radius[2];
+        synodic.vx_km_s = velocity[0];
+        synodic.vy_km_s = velocity[1];
+        synodic.vz_km_s = velocity[2];
+        synodic.frame = frame;
+        Ok(synodic)
+    }
+}
+
+impl Frame {
+    /// Returns the normalized positions of the five libration points (L1 to L5) in this synodic frame.
+    ///
+    /// L1 is between the primaries, L2 beyond the secondary, L3 beyond the primary, and L4 (resp. L5) leads (resp. trails)
+    /// the secondary by 60 degrees.
+    pub fn libration_points(&self) -> Result<[Vector3<f64>; 5], NyxError> {
+        let mu = match self {
+            Frame::Synodic { mass_ratio, .. } => *mass_ratio,
+            _ => {
+                return Err(NyxError::CustomError {
+                    msg: format!("{self} is not a synodic frame"),
+                })
+            }
+        };
+
+        // Collinear points are the roots of the gradient of the pseudo-potential along the X axis
+        let collinear = |x0: f64| -> Result<f64, NyxError> {
+            let mut x = x0;
+            for _ in 0..MAX_ITERATIONS {
+                let r1 = x + mu;
+                let r2 = x - 1.0 + mu;
+                let fx = x - (1.0 - mu) * r1 / r1.abs().powi(3) - mu * r2 / r2.abs().powi(3);
+                let dfx = 1.0 + 2.0 * (1.0 - mu) / r1.abs().powi(3) + 2.0 * mu / r2.abs().powi(3);
+                let delta = fx / dfx;
+                x -= delta;
+                if delta.abs() < LIBRATION_TOL {
+                    return Ok(x);
+                }
+            }
+            Err(NyxError::MaxIterReached {
+                msg: format!("{MAX_ITERATIONS} locating the collinear libration points"),
+            })
+        };
+
+        let hill = (mu / 3.0).cbrt();
+        let half_sqrt3 = 3.0_f64.sqrt() / 2.0;
+
+        Ok([
+            Vector3::new(collinear(1.0 - mu - hill)?, 0.0, 0.0),
+            Vector3::new(collinear(1.0 - mu + hill)?, 0.0, 0.0),
+            Vector3::new(collinear(-1.0 - 5.0 * mu / 12.0)?, 0.0, 0.0),
+            Vector3::new(0.5 - mu, half_sqrt3, 0.0),
+            Vector3::new(0.5 - mu, -half_sqrt3, 0.0),
+        ])
+    }
+}
+
+impl Orbit {
+    /// Returns the Jacobi constant C = 2U - v² of this state, where U is the pseudo-potential of the circular restricted three body problem.
+    ///
+    /// Only defined in a synodic frame, where the state is normalized.
+    pub fn jacobi_constant(&self) -> f64 {
+        let mu = self.frame.mass_ratio();
+        let r1 = ((self.x_km + mu).powi(2) + self.y_km.powi(2) + self.z_km.powi(2)).sqrt();
+        let r2 = ((self.x_km - 1.0 + mu).powi(2) + self.y_km.powi(2) + self.z_km.powi(2)).sqrt();
+        let potential = 0.5 * (self.x_km.powi(2) + self.y_km.powi(2)) + (1.0 - mu) / r1 + mu / r2;
+        2.0 * potential - self.vmag_km_s().powi(2)
+    }
+}
+
+#[cfg(test)]
+mod ut_cr3bp {
+    use crate::cosmic::{Bodies, Cosm, Frame, LightTimeCalc, Orbit};
+    use crate::time::Epoch;
+
+    #[test]
+    fn test_libration_points() {
+        // Earth-Moon system with the usual mass ratio (e.g. Koon, Lo, Marsden, and Ross, 2011)
+        let frame = Frame::Synodic {
+            primary: Bodies::Earth,
+            secondary: Bodies::Luna,
+            mass_ratio: 0.012_150_585_609_624,
+            length_km: 384_400.0,
+            time_s: 375_190.0,
+        };
+        let points = frame.libration_points().unwrap();
+        assert!((points[0][0] - 0.836_915_127).abs() < 1e-8);
+        assert!((points[1][0] - 1.155_682_164).abs() < 1e-8);
+        assert!((points[2][0] + 1.005_062_638).abs() < 1e-8);
+
+        // The libration points are equilibrium points, so their Jacobi constants are those of a stationary state
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2022, 1, 1);
+        let jacobi: Vec<f64> = points
+            .iter()
+            .map(|p| {
+                Orbit::cartesian(p[0], p[1], p[2], 0.0, 0.0, 0.0, epoch, frame).jacobi_constant()
+            })
+            .collect();
+        assert!(jacobi[0] > jacobi[1] && jacobi[1] > jacobi[2] && jacobi[2] > jacobi[3]);
+        assert!((jacobi[3] - jacobi[4]).abs() < 1e-14);
+        assert!(
+            (jacobi[3] - 3.0 + 0.012_150_585_609_624 * (1.0 - 0.012_150_585_609_624)).abs() < 1e-12
+        );
+    }
+
+    #[test]
+    fn test_synodic_conversions() {
+        let cosm = Cosm::de438();
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2022, 1, 1);
+        let frame = cosm.synodic_frame(Bodies::Earth, Bodies::Luna, epoch);
+        let mu = frame.mass_ratio();
+        assert!((mu - 0.012_150_58).abs() < 1e-7);
+        if let Frame::Synodic {
+            length_km, time_s, ..
+        } = frame
+        {
+            assert!((length_km - 384_400.0).abs() < 10_000.0);
+            assert!((time_s - 375_190.0).abs() < 20_000.0);
+        }
+
+        // The Moon is on the X axis of the synodic frame
+        let eme2k = cosm.frame("EME2000");
+        let moon =
+            cosm.celestial_state(Bodies::Luna.ephem_path(), epoch, eme2k, LightTimeCalc::None);
+        let moon_syn = cosm.frame_chg(&moon, frame);
+        assert!(moon_syn.y_km.abs() < 1e-12 && moon_syn.z_km.abs() < 1e-12);
+        assert!(moon_syn.x_km > 1.0 - mu - 0.1 && moon_syn.x_km < 1.0 - mu + 0.1);
+        assert!(moon_syn.vy_km_s.abs() < 1e-12 && moon_syn.vz_km_s.abs() < 1e-12);
+
+        // Round trip of a spacecraft state
+        let sc = Orbit::cartesian(1.02, 0.01, -0.18, 0.002, -0.1, 0.001, epoch, frame);
+        let sc_eme2k = cosm.frame_chg(&sc, eme2k);
+        assert_eq!(sc_eme2k.frame, eme2k);
+        let sc_back = cosm.frame_chg(&sc_eme2k, frame);
+        assert!((sc_back.radius() - sc.radius()).norm() < 1e-12);
+        assert!((sc_back.velocity() - sc.velocity()).norm() < 1e-12);
+    }
+}
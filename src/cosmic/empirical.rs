@@ -0,0 +1,236 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{Orbit, State};
+use crate::dynamics::{DynamicsError, EmpiricalAccel, EMPIRICAL_PARAMS};
+use crate::errors::NyxError;
+use crate::linalg::{Const, DimName, OMatrix, OVector, SVector};
+use crate::md::StateParameter;
+use crate::time::{Duration, Epoch};
+use std::fmt;
+use std::ops::Add;
+
+/// An orbit and its empirical accelerations, whose parameters are appended to the orbit in the state vector so that a filter can solve for them.
+///
+/// The state vector is [X, Y, Z, Vx, Vy, Vz, constant (RIC), cos(u) (RIC), sin(u) (RIC), DMC (RIC)], cf. `EmpiricalAccel`.
+/// Propagate it with the `EmpiricalDynamics`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct EmpiricalOrbit {
+    pub orbit: Orbit,
+    pub empirical: EmpiricalAccel,
+    /// Optionally stores the state transition matrix of the orbit and the empirical parameters
+    pub stm: Option<OMatrix<f64, Const<18>, Const<18>>>,
+}
+
+impl EmpiricalOrbit {
+    /// Initializes the state from an orbit and its empirical accelerations, whose epoch is set to that of the orbit
+    pub fn new(orbit: Orbit, empirical: EmpiricalAccel) -> Self {
+        let mut empirical = empirical;
+        empirical.epoch = orbit.epoch;
+        Self {
+            orbit,
+            empirical,
+            stm: None,
+        }
+    }
+
+    /// Copies the current state but sets the STM to identity
+    pub fn with_stm(self) -> Self {
+        let mut me = self;
+        me.reset_stm();
+        me
+    }
+
+    /// Returns the state parameters of the empirical accelerations, in the order of the state vector
+    pub fn empirical_params() -> [StateParameter; EMPIRICAL_PARAMS] {
+        [
+            StateParameter::EmpiricalConstantR,
+            StateParameter::EmpiricalConstantI,
+            StateParameter::EmpiricalConstantC,
+            StateParameter::EmpiricalCosR,
+            StateParameter::EmpiricalCosI,
+            StateParameter::EmpiricalCosC,
+            StateParameter::EmpiricalSinR,
+            StateParameter::EmpiricalSinI,
+            StateParameter::EmpiricalSinC,
+            StateParameter::DmcR,
+            StateParameter::DmcI,
+            StateParameter::DmcC,
+        ]
+    }
+
+    /// Returns the index of the provided parameter in the empirical parameters, if it is one
+    fn empirical_index(param: StateParameter) -> Option<usize> {
+        Self::empirical_params().iter().position(|p| *p == param)
+    }
+}
+
+impl fmt::Display for EmpiricalOrbit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let orbit_prec = f.precision().unwrap_or(6);
+        write!(
+            f,
+            "{}  {}",
+            format_args!("{:.*}", orbit_prec, self.orbit),
+            self.empirical
+        )
+    }
+}
+
+impl fmt::LowerExp for EmpiricalOrbit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let orbit_prec = f.precision().unwrap_or(6);
+        write!(
+            f,
+            "{}  {}",
+            format_args!("{:.*e}", orbit_prec, self.orbit),
+            self.empirical
+        )
+    }
+}
+
+impl State for EmpiricalOrbit {
+    type Size = Const<18>;
+    type VecLength = Const<342>;
+
+    fn reset_stm(&mut self) {
+        self.stm = Some(OMatrix::<f64, Const<18>, Const<18>>::identity());
+    }
+
+    fn zeros() -> Self {
+        Self {
+            orbit: Orbit::zeros(),
+            ..Default::default()
+        }
+    }
+
+    /// The vector is organized as such:
+    /// [X, Y, Z, Vx, Vy, Vz, constant (RIC), cos(u) (RIC), sin(u) (RIC), DMC (RIC), STM(18x18)]
+    fn as_vector(&self) -> OVector<f64, Const<342>> {
+        let mut vector = OVector::<f64, Const<342>>::zeros();
+        for (i, val) in self.orbit.to_cartesian_vec().iter().enumerate() {
+            vector[i] = *val;
+        }
+        for (i, val) in self.empirical.to_vector().iter().enumerate() {
+            vector[i + 6] = *val;
+        }
+        if let Some(stm) = self.stm {
+            for (idx, stm_val) in stm.as_slice().iter().enumerate() {
+                vector[idx + Self::Size::dim()] = *stm_val;
+            }
+        }
+        vector
+    }
+
+    /// Vector is expected to be organized as such:
+    /// [X, Y, Z, Vx, Vy, Vz, constant (RIC), cos(u) (RIC), sin(u) (RIC), DMC (RIC), STM(18x18)]
+    fn set(&mut self, epoch: Epoch, vector: &OVector<f64, Const<342>>) {
+        self.set_epoch(epoch);
+        self.orbit.x_km = vector[0];
+        self.orbit.y_km = vector[1];
+        self.orbit.z_km = vector[2];
+        self.orbit.vx_km_s = vector[3];
+        self.orbit.vy_km_s = vector[4];
+        self.orbit.vz_km_s = vector[5];
+        self.empirical
+            .set_vector(&SVector::<f64, EMPIRICAL_PARAMS>::from_column_slice(
+                &vector.as_slice()[6..Self::Size::dim()],
+            ));
+        if self.stm.is_some() {
+            self.stm = Some(OMatrix::<f64, Const<18>, Const<18>>::from_column_slice(
+                &vector.as_slice()[Self::Size::dim()..],
+            ));
+        }
+    }
+
+    fn stm(&self) -> Result<OMatrix<f64, Self::Size, Self::Size>, DynamicsError> {
+        match self.stm {
+            Some(stm) => Ok(stm),
+            None => Err(DynamicsError::StateTransitionMatrixUnset),
+        }
+    }
+
+    fn epoch(&self) -> Epoch {
+        self.orbit.epoch
+    }
+
+    /// Sets the epoch of the orbit and of the DMC acceleration, since the latter is propagated in the state
+    fn set_epoch(&mut self, epoch: Epoch) {
+        self.orbit.epoch = epoch;
+        self.empirical.epoch = epoch;
+    }
+
+    fn add(self, other: OVector<f64, Self::Size>) -> Self {
+        self + other
+    }
+
+    fn value(&self, param: StateParameter) -> Result<f64, NyxError> {
+        match Self::empirical_index(param) {
+            Some(idx) => Ok(self.empirical.to_vector()[idx]),
+            None => self.orbit.value(param),
+        }
+    }
+
+    fn set_value(&mut self, param: StateParameter, val: f64) -> Result<(), NyxError> {
+        match Self::empirical_index(param) {
+            Some(idx) => {
+                let mut params = self.empirical.to_vector();
+                params[idx] = val;
+                self.empirical.set_vector(&params);
+                Ok(())
+            }
+            None => self.orbit.set_value(param, val),
+        }
+    }
+
+    fn unset_stm(&mut self) {
+        self.stm = None;
+    }
+
+    /// The DMC accelerations are first-order Gauss-Markov processes, whose discrete process noise is σ²(1 - exp(-2Δt/τ)).
+    /// The correlation with the orbit over the time step is neglected.
+    fn process_noise(&self, delta_t: Duration) -> Option<OMatrix<f64, Self::Size, Self::Size>> {
+        let tau = self.empirical.dmc_tau?;
+        let variance = self.empirical.dmc_sigma_km_s2.powi(2)
+            * (1.0 - (-2.0 * delta_t.to_seconds().abs() / tau.to_seconds()).exp());
+        let mut noise = OMatrix::<f64, Self::Size, Self::Size>::zeros();
+        for i in 15..18 {
+            noise[(i, i)] = variance;
+        }
+        Some(noise)
+    }
+}
+
+impl Add<OVector<f64, Const<18>>> for EmpiricalOrbit {
+    type Output = Self;
+
+    /// Adds the provided state deviation to this orbit and its empirical parameters
+    fn add(self, other: OVector<f64, Const<18>>) -> Self {
+        let mut me = self;
+        me.orbit.x_km += other[0];
+        me.orbit.y_km += other[1];
+        me.orbit.z_km += other[2];
+        me.orbit.vx_km_s += other[3];
+        me.orbit.vy_km_s += other[4];
+        me.orbit.vz_km_s += other[5];
+        me.empirical
+            .set_vector(&(self.empirical.to_vector() + other.fixed_rows::<EMPIRICAL_PARAMS>(6)));
+
+        me
+    }
+}
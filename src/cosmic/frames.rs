@@ -18,11 +18,63 @@
 
 use super::Bodies;
 use crate::time::{Duration, Unit};
+use crate::NyxError;
 use std::cmp::PartialEq;
 use std::convert::TryFrom;
 use std::f64::consts::PI;
 use std::fmt;
 
+/// Orientations of the Earth frames built in Cosm
+pub const EARTH_FRAMES: [&str; 4] = ["ITRF", "MOD", "TOD", "TEME"];
+
+/// Orientations of the lunar principal axes and mean Earth/polar axis frames built in Cosm
+pub const MOON_FRAMES: [&str; 2] = ["PA", "ME"];
+
+/// Orientation of the axes of a celestial or geoid frame
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrameOrientation {
+    /// Inertial axes of the ephemeris (J2000)
+    J2000,
+    /// Axes rotating with the body, defined by their right ascension, declination and prime meridian (e.g. the IAU frames)
+    BodyFixed,
+    /// Axes defined by two vectors
+    TwoVector,
+    /// Earth fixed axes of the IERS conventions
+    Itrf,
+    /// Earth mean equator and equinox of date
+    Mod,
+    /// Earth true equator and equinox of date
+    Tod,
+    /// Earth true equator and mean equinox of date, used by SGP4
+    Teme,
+    /// Lunar principal axes
+    MoonPa,
+    /// Lunar mean Earth/polar axis
+    MoonMe,
+}
+
+impl FrameOrientation {
+    /// Returns the Earth orientation of the provided name of `EARTH_FRAMES`
+    pub fn earth(name: &str) -> Option<Self> {
+        match name {
+            "ITRF" => Some(Self::Itrf),
+            "MOD" => Some(Self::Mod),
+            "TOD" => Some(Self::Tod),
+            "TEME" => Some(Self::Teme),
+            _ => None,
+        }
+    }
+
+    /// Returns the lunar orientation of the provided name of `MOON_FRAMES`
+    pub fn moon(name: &str) -> Option<Self> {
+        match name {
+            "PA" => Some(Self::MoonPa),
+            "ME" => Some(Self::MoonMe),
+            _ => None,
+        }
+    }
+}
+
 #[allow(non_snake_case, clippy::upper_case_acronyms)]
 #[derive(Copy, Clone, PartialEq)]
 pub enum Frame {
@@ -31,6 +83,7 @@ pub enum Frame {
         gm: f64,
         ephem_path: [Option<usize>; 3],
         frame_path: [Option<usize>; 3],
+        orientation: FrameOrientation,
     },
     /// Any Geoid which has a GM, flattening value, etc.
     Geoid {
@@ -40,6 +93,7 @@ pub enum Frame {
         semi_major_radius: f64,
         ephem_path: [Option<usize>; 3],
         frame_path: [Option<usize>; 3],
+        orientation: FrameOrientation,
     },
     /// Velocity, Normal, Cross (called VNB in GMAT)
     VNC,
@@ -51,6 +105,20 @@ pub enum Frame {
     SEZ,
     /// Used as a placeholder only
     Inertial,
+    /// Rotating (synodic) frame of the circular restricted three body problem, centered on the barycenter of the primary and secondary.
+    /// The X axis points from the primary to the secondary, and the Z axis is along their orbital momentum.
+    /// Positions and velocities in this frame are normalized by the characteristic length and time, so only the Cartesian
+    /// parameters and the Jacobi constant are meaningful: use `Cosm::frame_chg` to compute any other orbital parameter.
+    Synodic {
+        primary: Bodies,
+        secondary: Bodies,
+        /// Mass ratio μ = m2 / (m1 + m2)
+        mass_ratio: f64,
+        /// Characteristic length, i.e. distance between the primaries, in km
+        length_km: f64,
+        /// Characteristic time, i.e. the inverse of the mean motion of the primaries, in seconds
+        time_s: f64,
+    },
 }
 
 impl Frame {
@@ -62,6 +130,18 @@ impl Frame {
         matches!(self, Frame::Celestial { .. })
     }
 
+    pub fn is_synodic(&self) -> bool {
+        matches!(self, Frame::Synodic { .. })
+    }
+
+    /// Returns the mass ratio μ of the circular restricted three body problem of this synodic frame
+    pub fn mass_ratio(&self) -> f64 {
+        match self {
+            Frame::Synodic { mass_ratio, .. } => *mass_ratio,
+            _ => panic!("Frame is not Synodic in kind"),
+        }
+    }
+
     pub fn ephem_path(&self) -> Vec<usize> {
         match self {
             Frame::Celestial { ephem_path, .. } | Frame::Geoid { ephem_path, .. } => {
@@ -88,6 +168,14 @@ impl Frame {
         }
     }
 
+    /// Returns the orientation of the axes of this frame
+    pub fn orientation(&self) -> FrameOrientation {
+        match self {
+            Frame::Celestial { orientation, .. } | Frame::Geoid { orientation, .. } => *orientation,
+            _ => panic!("Frame is not Celestial or Geoid in kind"),
+        }
+    }
+
     pub fn gm(&self) -> f64 {
         match self {
             Frame::Celestial { gm, .. } | Frame::Geoid { gm, .. } => *gm,
@@ -140,30 +228,103 @@ impl Frame {
     /// Returns the angular velocity for _some_ planets and moons
     /// Source for Earth: G. Xu and Y. Xu, "GPS", DOI 10.1007/978-3-662-50367-6_2, 2016 (confirmed by https://hpiers.obspm.fr/eop-pc/models/constants.html)
     /// Source for everything else: https://en.wikipedia.org/w/index.php?title=Day&oldid=1008298887
-    #[allow(clippy::identity_op)]
     pub fn angular_velocity(&self) -> f64 {
+        self.try_angular_velocity().unwrap()
+    }
+
+    /// Returns the angular velocity in rad/s of the body of this frame, or an error if it is not one of the planets and moons of `angular_velocity`
+    #[allow(clippy::identity_op)]
+    pub fn try_angular_velocity(&self) -> Result<f64, NyxError> {
         let period_to_mean_motion = |dur: Duration| -> f64 { 2.0 * PI / dur.to_seconds() };
-        match Bodies::try_from(self.ephem_path()).unwrap() {
-            Bodies::MercuryBarycenter | Bodies::Mercury => {
-                period_to_mean_motion(58 * Unit::Day + 15 * Unit::Hour + 30 * Unit::Minute)
+        match Bodies::try_from(self.ephem_path()) {
+            Ok(Bodies::MercuryBarycenter | Bodies::Mercury) => Ok(period_to_mean_motion(
+                58 * Unit::Day + 15 * Unit::Hour + 30 * Unit::Minute,
+            )),
+            Ok(Bodies::VenusBarycenter | Bodies::Venus) => {
+                Ok(period_to_mean_motion(243 * Unit::Day))
+            }
+            Ok(Bodies::Earth) => Ok(7.292_115_146_706_4e-5),
+            Ok(Bodies::Luna) => Ok(period_to_mean_motion(
+                27 * Unit::Day + 7 * Unit::Hour + 12 * Unit::Minute,
+            )),
+            Ok(Bodies::MarsBarycenter | Bodies::Mars) => {
+                Ok(period_to_mean_motion(1 * Unit::Day + 37 * Unit::Minute))
+            }
+            Ok(Bodies::JupiterBarycenter) => {
+                Ok(period_to_mean_motion(9 * Unit::Hour + 56 * Unit::Minute))
+            }
+            Ok(Bodies::SaturnBarycenter) => {
+                Ok(period_to_mean_motion(10 * Unit::Hour + 30 * Unit::Minute))
             }
-            Bodies::VenusBarycenter | Bodies::Venus => period_to_mean_motion(243 * Unit::Day),
-            Bodies::Earth => 7.292_115_146_706_4e-5,
-            Bodies::Luna => {
-                period_to_mean_motion(27 * Unit::Day + 7 * Unit::Hour + 12 * Unit::Minute)
+            Ok(Bodies::UranusBarycenter) => {
+                Ok(period_to_mean_motion(17 * Unit::Hour + 14 * Unit::Minute))
             }
-            Bodies::MarsBarycenter => period_to_mean_motion(1 * Unit::Day + 37 * Unit::Minute),
-            Bodies::JupiterBarycenter => period_to_mean_motion(9 * Unit::Hour + 56 * Unit::Minute),
-            Bodies::SaturnBarycenter => period_to_mean_motion(10 * Unit::Hour + 30 * Unit::Minute),
-            Bodies::UranusBarycenter => period_to_mean_motion(17 * Unit::Hour + 14 * Unit::Minute),
-            Bodies::NeptuneBarycenter => period_to_mean_motion(16 * Unit::Hour + 6 * Unit::Minute),
-            _ => unimplemented!(),
+            Ok(Bodies::NeptuneBarycenter) => {
+                Ok(period_to_mean_motion(16 * Unit::Hour + 6 * Unit::Minute))
+            }
+            _ => Err(NyxError::CustomError {
+                msg: format!("angular velocity of {self} unknown"),
+            }),
         }
     }
 
     /// Returns whether this frame is body fixed or not
     pub fn is_body_fixed(&self) -> bool {
-        self.frame_path().len() == 2 || self.frame_path().len() == 3
+        matches!(
+            self.orientation(),
+            FrameOrientation::BodyFixed
+                | FrameOrientation::Itrf
+                | FrameOrientation::MoonPa
+                | FrameOrientation::MoonMe
+        )
+    }
+
+    /// Returns the orientation name of this frame if it is one of the Earth frames built in Cosm (cf. `EARTH_FRAMES`)
+    pub fn earth_frame_name(&self) -> Option<&'static str> {
+        match self.orientation() {
+            FrameOrientation::Itrf => Some("ITRF"),
+            FrameOrientation::Mod => Some("MOD"),
+            FrameOrientation::Tod => Some("TOD"),
+            FrameOrientation::Teme => Some("TEME"),
+            _ => None,
+        }
+    }
+
+    /// Returns the orientation name of this frame if it is one of the lunar frames built in Cosm (cf. `MOON_FRAMES`)
+    pub fn moon_frame_name(&self) -> Option<&'static str> {
+        match self.orientation() {
+            FrameOrientation::MoonPa => Some("PA"),
+            FrameOrientation::MoonMe => Some("ME"),
+            _ => None,
+        }
+    }
+
+    /// Returns whether this frame is the high fidelity Earth fixed frame (ITRF)
+    pub fn is_itrf(&self) -> bool {
+        self.earth_frame_name() == Some("ITRF")
+    }
+
+    /// Returns the name of the orientation of this frame, used for display
+    fn orientation_name(&self) -> String {
+        if let Some(name) = self.earth_frame_name().or_else(|| self.moon_frame_name()) {
+            return name.to_string();
+        }
+        match self.orientation() {
+            FrameOrientation::BodyFixed if self.frame_path().len() == 3 => {
+                "IAU Poles Fixed".to_string()
+            }
+            FrameOrientation::BodyFixed => "IAU Fixed".to_string(),
+            FrameOrientation::TwoVector => "Two Vector".to_string(),
+            _ => "J2000".to_string(),
+        }
+    }
+
+    /// Returns the name of the center of this frame, or its ephemeris path if it isn't one of the default bodies (e.g. loaded from an SPK)
+    fn body_name(&self) -> String {
+        match Bodies::try_from(self.ephem_path()) {
+            Ok(body) => body.name(),
+            Err(_) => format!("Body {:?}", self.ephem_path()),
+        }
     }
 }
 
@@ -171,26 +332,16 @@ impl fmt::Display for Frame {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Frame::Celestial { .. } | Frame::Geoid { .. } => {
-                if self.frame_path().len() == 2 {
-                    write!(
-                        f,
-                        "IAU {}",
-                        Bodies::try_from(self.ephem_path()).unwrap().name()
-                    )
+                if self.orientation() == FrameOrientation::BodyFixed && self.frame_path().len() == 2
+                {
+                    write!(f, "IAU {}", self.body_name())
                 } else {
-                    write!(
-                        f,
-                        "{} {}",
-                        Bodies::try_from(self.ephem_path()).unwrap().name(),
-                        match self.frame_path().len() {
-                            0 | 1 => "J2000".to_string(),
-                            2 => "IAU Fixed".to_string(),
-                            3 => "IAU Poles Fixed".to_string(),
-                            _ => "Custom".to_string(),
-                        }
-                    )
+                    write!(f, "{} {}", self.body_name(), self.orientation_name())
                 }
             }
+            Frame::Synodic {
+                primary, secondary, ..
+            } => write!(f, "{}-{} Synodic", primary.name(), secondary.name()),
             othframe => write!(f, "{othframe:?}"),
         }
     }
@@ -204,13 +355,8 @@ impl fmt::Debug for Frame {
                 write!(
                     f,
                     "{} {} (μ = {:.06} km^3/s^2)",
-                    Bodies::try_from(self.ephem_path()).unwrap().name(),
-                    match self.frame_path().len() {
-                        0 | 1 => "J2000".to_string(),
-                        2 => "IAU Fixed".to_string(),
-                        3 => "IAU Poles Fixed".to_string(),
-                        _ => "Custom".to_string(),
-                    },
+                    self.body_name(),
+                    self.orientation_name(),
                     gm,
                 )
             }
@@ -223,13 +369,8 @@ impl fmt::Debug for Frame {
                 write!(
                     f,
                     "{} {} (μ = {:.06} km^3/s^2 , r = {:.06} km, f = {:.09})",
-                    Bodies::try_from(self.ephem_path()).unwrap().name(),
-                    match self.frame_path().len() {
-                        0 | 1 => "J2000".to_string(),
-                        2 => "IAU Fixed".to_string(),
-                        3 => "IAU Poles Fixed".to_string(),
-                        _ => "Custom".to_string(),
-                    },
+                    self.body_name(),
+                    self.orientation_name(),
                     gm,
                     equatorial_radius,
                     flattening,
@@ -240,6 +381,21 @@ impl fmt::Debug for Frame {
             Frame::RIC => write!(f, "RIC"),
             Frame::SEZ => write!(f, "SEZ"),
             Frame::Inertial => write!(f, "Inertial"),
+            Frame::Synodic {
+                primary,
+                secondary,
+                mass_ratio,
+                length_km,
+                time_s,
+            } => write!(
+                f,
+                "{}-{} Synodic (μ = {:.09}, L = {:.06} km, T = {:.06} s)",
+                primary.name(),
+                secondary.name(),
+                mass_ratio,
+                length_km,
+                time_s
+            ),
         }
     }
 }
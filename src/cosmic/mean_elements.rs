@@ -0,0 +1,676 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::orbit::compute_mean_to_true_anomaly;
+use super::{Bodies, Frame, Orbit};
+use crate::md::StateParameter;
+use crate::time::Epoch;
+use crate::utils::{between_0_360, between_pm_180};
+use crate::NyxError;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+use std::fmt;
+
+/// Unnormalized J2 zonal harmonic of the Earth (EGM96), the only one used by these mean element theories
+const EARTH_J2: f64 = 1.082_626_683_553_15e-3;
+/// Maximum number of fixed point iterations to invert the mean to osculating mapping
+const MAX_ITERATIONS: usize = 50;
+/// Convergence tolerance on the semi major axis, relative to the semi major axis
+const SMA_REL_TOL: f64 = 1e-13;
+/// Convergence tolerance on the eccentricity and the angles (radians)
+const ANGLE_TOL: f64 = 1e-13;
+/// Tolerance on the mean anomaly to true anomaly conversion
+const ANOMALY_TOL: f64 = 1e-14;
+
+/// The theory used to average out the oscillations of the osculating elements.
+///
+/// All of these theories only account for the J2 zonal harmonic of the Earth (the `gm` and equatorial radius are those of the frame).
+/// Hence, mean elements are only available in Earth centered Geoid frames.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MeanElementsKind {
+    /// Brouwer-Lyddane mean elements where only the short-period terms are removed
+    BrouwerShort,
+    /// Brouwer-Lyddane mean elements where both the short- and long-period terms are removed
+    BrouwerLong,
+    /// Kozai mean elements, i.e. the short-period mean elements expressed with Kozai's mean motion (as used in two-line element sets)
+    Kozai,
+}
+
+impl fmt::Display for MeanElementsKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BrouwerShort => write!(f, "Brouwer-Lyddane short"),
+            Self::BrouwerLong => write!(f, "Brouwer-Lyddane long"),
+            Self::Kozai => write!(f, "Kozai"),
+        }
+    }
+}
+
+/// Mean Keplerian elements of an orbit.
+///
+/// These are _not_ osculating: use `to_orbit` to compute the osculating state they correspond to.
+/// Reference: Schaub and Junkins, "Analytical Mechanics of Space Systems", 2018, Appendix F (Brouwer-Lyddane first order J2 mapping)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MeanElements {
+    /// Theory used to compute these mean elements
+    pub kind: MeanElementsKind,
+    /// Mean semi major axis (km)
+    pub sma_km: f64,
+    /// Mean eccentricity (no unit)
+    pub ecc: f64,
+    /// Mean inclination (deg)
+    pub inc_deg: f64,
+    /// Mean right ascension of the ascending node (deg)
+    pub raan_deg: f64,
+    /// Mean argument of periapsis (deg)
+    pub aop_deg: f64,
+    /// Mean mean anomaly (deg)
+    pub ma_deg: f64,
+    pub epoch: Epoch,
+    /// Frame in which these elements are expressed, must be an Earth centered Geoid frame
+    pub frame: Frame,
+}
+
+impl MeanElements {
+    /// Returns the osculating orbit corresponding to these mean elements
+    pub fn to_orbit(&self) -> Result<Orbit, NyxError> {
+        let (gm, radius_km, j2) = frame_constants(self.frame)?;
+
+        let mut mean = [
+            self.sma_km,
+            self.ecc,
+            self.inc_deg.to_radians(),
+            self.raan_deg.to_radians(),
+            self.aop_deg.to_radians(),
+            self.ma_deg.to_radians(),
+        ];
+
+        if self.kind == MeanElementsKind::Kozai {
+            mean[0] = kozai_to_brouwer_sma(mean[0], mean[1], mean[2], gm, radius_km, j2)?;
+        }
+
+        check_mean_elements(&mean, self.kind)?;
+
+        let osc = mean_to_osculating(
+            &mean,
+            radius_km,
+            j2,
+            self.kind == MeanElementsKind::BrouwerLong,
+        )?;
+
+        Orbit::keplerian_mean_anomaly(
+            osc[0],
+            osc[1],
+            osc[2].to_degrees(),
+            osc[3].to_degrees(),
+            osc[4].to_degrees(),
+            osc[5].to_degrees(),
+            self.epoch,
+            self.frame,
+        )
+    }
+
+    /// Returns the value of the provided mean element parameter, which must be of the same kind as these elements
+    pub fn value(&self, param: StateParameter) -> Result<f64, NyxError> {
+        if param.mean_elements_kind() != Some(self.kind) {
+            return Err(NyxError::StateParameterUnavailable {
+                param,
+                msg: format!("not a {} mean element", self.kind),
+            });
+        }
+
+        match param {
+            StateParameter::BrouwerShortSMA
+            | StateParameter::BrouwerLongSMA
+            | StateParameter::KozaiSMA => Ok(self.sma_km),
+            StateParameter::BrouwerShortEcc
+            | StateParameter::BrouwerLongEcc
+            | StateParameter::KozaiEcc => Ok(self.ecc),
+            StateParameter::BrouwerShortInc
+            | StateParameter::BrouwerLongInc
+            | StateParameter::KozaiInc => Ok(self.inc_deg),
+            StateParameter::BrouwerShortRAAN
+            | StateParameter::BrouwerLongRAAN
+            | StateParameter::KozaiRAAN => Ok(self.raan_deg),
+            StateParameter::BrouwerShortAoP
+            | StateParameter::BrouwerLongAoP
+            | StateParameter::KozaiAoP => Ok(self.aop_deg),
+            StateParameter::BrouwerShortMA
+            | StateParameter::BrouwerLongMA
+            | StateParameter::KozaiMA => Ok(self.ma_deg),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl fmt::Display for MeanElements {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {} mean elements\tsma = {:.6} km\tecc = {:.6}\tinc = {:.6} deg\traan = {:.6} deg\taop = {:.6} deg\tma = {:.6} deg",
+            self.frame,
+            self.kind,
+            self.sma_km,
+            self.ecc,
+            self.inc_deg,
+            self.raan_deg,
+            self.aop_deg,
+            self.ma_deg
+        )
+    }
+}
+
+impl Orbit {
+    /// Returns the mean elements of this osculating orbit using the requested theory.
+    ///
+    /// The mean to osculating mapping is inverted with a fixed point iteration.
+    /// Fails if this state is not an elliptical orbit in an Earth centered Geoid frame (cf. `is_brouwer_short_valid`), or if the
+    /// Brouwer-Lyddane long-period terms are requested near the equator or the critical inclination.
+    pub fn to_mean_elements(&self, kind: MeanElementsKind) -> Result<MeanElements, NyxError> {
+        let (gm, radius_km, j2) = frame_constants(self.frame)?;
+
+        if !self.is_brouwer_short_valid() {
+            return Err(NyxError::MathDomain {
+                msg: format!("{kind} mean elements undefined for {self}"),
+            });
+        }
+
+        let osc = [
+            self.sma_km(),
+            self.ecc(),
+            self.inc_deg().to_radians(),
+            self.raan_deg().to_radians(),
+            self.aop_deg().to_radians(),
+            self.ma_deg().to_radians(),
+        ];
+
+        let long = kind == MeanElementsKind::BrouwerLong;
+
+        let mut mean = osc;
+        let mut converged = false;
+        for _ in 0..MAX_ITERATIONS {
+            check_mean_elements(&mean, kind)?;
+            let guess = mean_to_osculating(&mean, radius_km, j2, long)?;
+
+            // Correct the mean elements with the mismatch, using non-singular elements for small eccentricities
+            let delta_sma = osc[0] - guess[0];
+            let delta_k = osc[1] * osc[4].cos() - guess[1] * guess[4].cos();
+            let delta_h = osc[1] * osc[4].sin() - guess[1] * guess[4].sin();
+            let delta_inc = osc[2] - guess[2];
+            let delta_raan = between_pm_pi(osc[3] - guess[3]);
+            let delta_lambda = between_pm_pi(osc[4] + osc[5] - guess[4] - guess[5]);
+
+            let k = mean[1] * mean[4].cos() + delta_k;
+            let h = mean[1] * mean[4].sin() + delta_h;
+            let aop = h.atan2(k);
+
+            mean = [
+                mean[0] + delta_sma,
+                (k.powi(2) + h.powi(2)).sqrt(),
+                mean[2] + delta_inc,
+                (mean[3] + delta_raan).rem_euclid(TAU),
+                aop.rem_euclid(TAU),
+                (mean[4] + mean[5] + delta_lambda - aop).rem_euclid(TAU),
+            ];
+
+            if delta_sma.abs() < SMA_REL_TOL * osc[0]
+                && [delta_k, delta_h, delta_inc, delta_raan, delta_lambda]
+                    .iter()
+                    .all(|delta| delta.abs() < ANGLE_TOL)
+            {
+                converged = true;
+                break;
+            }
+        }
+
+        if !converged {
+            return Err(NyxError::MaxIterReached {
+                msg: format!("{MAX_ITERATIONS} ({kind} mean elements)"),
+            });
+        }
+
+        if kind == MeanElementsKind::Kozai {
+            mean[0] = brouwer_to_kozai_sma(mean[0], mean[1], mean[2], gm, radius_km, j2);
+        }
+
+        Ok(MeanElements {
+            kind,
+            sma_km: mean[0],
+            ecc: mean[1],
+            inc_deg: mean[2].to_degrees(),
+            raan_deg: between_0_360(mean[3].to_degrees()),
+            aop_deg: between_0_360(mean[4].to_degrees()),
+            ma_deg: between_0_360(mean[5].to_degrees()),
+            epoch: self.epoch,
+            frame: self.frame,
+        })
+    }
+
+    /// Returns the Brouwer-Lyddane mean elements with only the short-period terms removed
+    pub fn brouwer_short(&self) -> Result<MeanElements, NyxError> {
+        self.to_mean_elements(MeanElementsKind::BrouwerShort)
+    }
+
+    /// Returns the Brouwer-Lyddane mean elements with both the short- and long-period terms removed
+    pub fn brouwer_long(&self) -> Result<MeanElements, NyxError> {
+        self.to_mean_elements(MeanElementsKind::BrouwerLong)
+    }
+
+    /// Returns the Kozai mean elements
+    pub fn kozai(&self) -> Result<MeanElements, NyxError> {
+        self.to_mean_elements(MeanElementsKind::Kozai)
+    }
+
+    /// Returns the value of a mean element state parameter
+    pub(crate) fn mean_element_value(&self, param: StateParameter) -> Result<f64, NyxError> {
+        match param.mean_elements_kind() {
+            Some(kind) => self.to_mean_elements(kind)?.value(param),
+            None => Err(NyxError::StateParameterUnavailable {
+                param,
+                msg: "not a mean element".to_string(),
+            }),
+        }
+    }
+}
+
+/// Returns the gravitational parameter, the equatorial radius and the J2 zonal harmonic of this frame
+fn frame_constants(frame: Frame) -> Result<(f64, f64, f64), NyxError> {
+    if !frame.is_geoid() {
+        return Err(NyxError::MathDomain {
+            msg: format!("mean elements require a Geoid frame, got {frame}"),
+        });
+    }
+
+    match Bodies::try_from(frame.ephem_path()) {
+        Ok(Bodies::Earth) => Ok((frame.gm(), frame.equatorial_radius(), EARTH_J2)),
+        _ => Err(NyxError::MathDomain {
+            msg: format!("mean elements are only defined around the Earth, got {frame}"),
+        }),
+    }
+}
+
+/// Returns the angle (radians) between -π and π
+fn between_pm_pi(angle: f64) -> f64 {
+    between_pm_180(angle.to_degrees()).to_radians()
+}
+
+/// Ensures that the mean elements (sma, ecc, inc, raan, aop, ma in radians) can be mapped to osculating elements
+fn check_mean_elements(mean: &[f64; 6], kind: MeanElementsKind) -> Result<(), NyxError> {
+    if !(0.0..1.0).contains(&mean[1]) || mean[0] <= 0.0 {
+        return Err(NyxError::MathDomain {
+            msg: format!(
+                "{kind} mean elements only defined for elliptical orbits (sma = {} km, ecc = {})",
+                mean[0], mean[1]
+            ),
+        });
+    }
+
+    if kind == MeanElementsKind::BrouwerLong {
+        let cos_i2 = mean[2].cos().powi(2);
+        if (1.0 - 5.0 * cos_i2).abs() < 1e-3 {
+            return Err(NyxError::MathDomain {
+                msg: format!(
+                    "{kind} mean elements undefined near the critical inclination (inc = {} deg)",
+                    mean[2].to_degrees()
+                ),
+            });
+        } else if mean[2].sin().abs() < 1e-6 {
+            return Err(NyxError::MathDomain {
+                msg: format!(
+                    "{kind} mean elements undefined for equatorial orbits (inc = {} deg)",
+                    mean[2].to_degrees()
+                ),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Brouwer-Lyddane first order J2 mapping from mean elements to osculating elements.
+///
+/// Elements are (sma, ecc, inc, raan, aop, ma) with angles in radians. The long-period terms are only added if `long` is set.
+/// Reference: Schaub and Junkins, "Analytical Mechanics of Space Systems", 2018, equations F.1 to F.21
+fn mean_to_osculating(
+    mean: &[f64; 6],
+    radius_km: f64,
+    j2: f64,
+    long: bool,
+) -> Result<[f64; 6], NyxError> {
+    let [a, e, i, raan, aop, ma] = *mean;
+
+    let f = compute_mean_to_true_anomaly(ma, e, ANOMALY_TOL)?;
+    let (sin_f, cos_f) = f.sin_cos();
+
+    let lp = if long { 1.0 } else { 0.0 };
+
+    let gamma2 = j2 / 2.0 * (radius_km / a).powi(2);
+    let eta = (1.0 - e.powi(2)).sqrt();
+    let gamma2p = gamma2 / eta.powi(4);
+    let a_r = (1.0 + e * cos_f) / eta.powi(2);
+
+    let cos_i = i.cos();
+    let theta2 = cos_i.powi(2);
+    let theta4 = theta2.powi(2);
+    // Only used in the long-period terms, which are rejected near the critical inclination
+    let crit = 1.0 - 5.0 * theta2;
+
+    let two_w = 2.0 * aop;
+    // Equation of the center: f - M + e sin f
+    let eoc = f - ma + e * sin_f;
+
+    let sma = a + a
+        * gamma2
+        * ((3.0 * theta2 - 1.0) * (a_r.powi(3) - 1.0 / eta.powi(3))
+            + 3.0 * (1.0 - theta2) * a_r.powi(3) * (two_w + 2.0 * f).cos());
+
+    let de_lp = lp * gamma2p / 8.0
+        * e
+        * eta.powi(2)
+        * (1.0 - 11.0 * theta2 - 40.0 * theta4 / crit)
+        * two_w.cos();
+
+    let ecc_poly = 3.0 * cos_f + 3.0 * e * cos_f.powi(2) + e.powi(2) * cos_f.powi(3);
+
+    let de = eta.powi(2) / 2.0
+        * (gamma2
+            * ((3.0 * theta2 - 1.0) / eta.powi(6) * (e * eta + e / (1.0 + eta) + ecc_poly)
+                + 3.0 * (1.0 - theta2) / eta.powi(6) * (e + ecc_poly) * (two_w + 2.0 * f).cos())
+            - gamma2p * (1.0 - theta2) * (3.0 * (two_w + f).cos() + (two_w + 3.0 * f).cos()))
+        + de_lp;
+
+    let sp_trig =
+        3.0 * (two_w + 2.0 * f).sin() + 3.0 * e * (two_w + f).sin() + e * (two_w + 3.0 * f).sin();
+
+    let di = -e * de_lp / (eta.powi(2) * i.tan())
+        + gamma2p / 2.0
+            * cos_i
+            * (1.0 - theta2).sqrt()
+            * (3.0 * (two_w + 2.0 * f).cos()
+                + 3.0 * e * (two_w + f).cos()
+                + e * (two_w + 3.0 * f).cos());
+
+    let node_lp = if long {
+        -gamma2p / 8.0
+            * e.powi(2)
+            * cos_i
+            * (11.0 + 80.0 * theta2 / crit + 200.0 * theta4 / crit.powi(2))
+            * two_w.sin()
+    } else {
+        0.0
+    };
+
+    let draan = node_lp - gamma2p / 2.0 * cos_i * (6.0 * eoc - sp_trig);
+
+    let lambda_lp = if long {
+        gamma2p / 8.0 * eta.powi(3) * (1.0 - 11.0 * theta2 - 40.0 * theta4 / crit) * two_w.sin()
+            - gamma2p / 16.0
+                * (2.0 + e.powi(2)
+                    - 11.0 * (2.0 + 3.0 * e.powi(2)) * theta2
+                    - 40.0 * (2.0 + 5.0 * e.powi(2)) * theta4 / crit
+                    - 400.0 * e.powi(2) * theta2 * theta4 / crit.powi(2))
+                * two_w.sin()
+    } else {
+        0.0
+    };
+
+    let lambda = ma
+        + aop
+        + raan
+        + lambda_lp
+        + gamma2p / 4.0 * (-6.0 * (1.0 - 5.0 * theta2) * eoc + (3.0 - 5.0 * theta2) * sp_trig)
+        + draan;
+
+    let are2 = (a_r * eta).powi(2);
+    let e_dm = lp * gamma2p / 8.0
+        * e
+        * eta.powi(3)
+        * (1.0 - 11.0 * theta2 - 40.0 * theta4 / crit)
+        * two_w.sin()
+        - gamma2p / 4.0
+            * eta.powi(3)
+            * (2.0 * (3.0 * theta2 - 1.0) * (are2 + a_r + 1.0) * sin_f
+                + 3.0
+                    * (1.0 - theta2)
+                    * ((-are2 - a_r + 1.0) * (two_w + f).sin()
+                        + (are2 + a_r + 1.0 / 3.0) * (two_w + 3.0 * f).sin()));
+
+    // Lyddane's modification to avoid the singularities at small eccentricities and inclinations
+    let (sin_ma, cos_ma) = ma.sin_cos();
+    let d1 = (e + de) * sin_ma + e_dm * cos_ma;
+    let d2 = (e + de) * cos_ma - e_dm * sin_ma;
+
+    let ma_osc = d1.atan2(d2);
+    let ecc_osc = (d1.powi(2) + d2.powi(2)).sqrt();
+
+    let (sin_hi, cos_hi) = (i / 2.0).sin_cos();
+    let (sin_raan, cos_raan) = raan.sin_cos();
+    let d3 = (sin_hi + cos_hi * di / 2.0) * sin_raan + sin_hi * draan * cos_raan;
+    let d4 = (sin_hi + cos_hi * di / 2.0) * cos_raan - sin_hi * draan * sin_raan;
+
+    let raan_osc = d3.atan2(d4);
+    let inc_osc = 2.0 * (d3.powi(2) + d4.powi(2)).sqrt().min(1.0).asin();
+    let aop_osc = lambda - ma_osc - raan_osc;
+
+    Ok([
+        sma,
+        ecc_osc,
+        inc_osc,
+        raan_osc.rem_euclid(TAU),
+        aop_osc.rem_euclid(TAU),
+        ma_osc.rem_euclid(TAU),
+    ])
+}
+
+/// Returns the relative difference between Kozai's and Brouwer's mean motions (as in SGP4)
+fn kozai_delta(brouwer_sma_km: f64, ecc: f64, inc: f64, radius_km: f64, j2: f64) -> f64 {
+    0.75 * j2 * (radius_km / brouwer_sma_km).powi(2) * (3.0 * inc.cos().powi(2) - 1.0)
+        / (1.0 - ecc.powi(2)).powf(1.5)
+}
+
+/// Converts a Brouwer mean semi major axis into a Kozai mean semi major axis
+fn brouwer_to_kozai_sma(
+    brouwer_sma_km: f64,
+    ecc: f64,
+    inc: f64,
+    gm: f64,
+    radius_km: f64,
+    j2: f64,
+) -> f64 {
+    let brouwer_n = (gm / brouwer_sma_km.powi(3)).sqrt();
+    let kozai_n = brouwer_n * (1.0 + kozai_delta(brouwer_sma_km, ecc, inc, radius_km, j2));
+    (gm / kozai_n.powi(2)).cbrt()
+}
+
+/// Converts a Kozai mean semi major axis into a Brouwer mean semi major axis
+fn kozai_to_brouwer_sma(
+    kozai_sma_km: f64,
+    ecc: f64,
+    inc: f64,
+    gm: f64,
+    radius_km: f64,
+    j2: f64,
+) -> Result<f64, NyxError> {
+    let kozai_n = (gm / kozai_sma_km.powi(3)).sqrt();
+    let mut brouwer_sma_km = kozai_sma_km;
+    for _ in 0..MAX_ITERATIONS {
+        let brouwer_n = kozai_n / (1.0 + kozai_delta(brouwer_sma_km, ecc, inc, radius_km, j2));
+        let next_sma_km = (gm / brouwer_n.powi(2)).cbrt();
+        if (next_sma_km - brouwer_sma_km).abs() < SMA_REL_TOL * kozai_sma_km {
+            return Ok(next_sma_km);
+        }
+        brouwer_sma_km = next_sma_km;
+    }
+
+    Err(NyxError::MaxIterReached {
+        msg: format!("{MAX_ITERATIONS} (Kozai to Brouwer mean motion)"),
+    })
+}
+
+#[cfg(test)]
+mod ut_mean_elements {
+    use super::{frame_constants, MeanElements, MeanElementsKind};
+    use crate::cosmic::{Cosm, Orbit, OrbitDual};
+    use crate::md::StateParameter;
+    use crate::time::Epoch;
+    use crate::State;
+
+    /// First order short-period J2 correction of the semi-major axis (Brouwer, 1959)
+    fn short_period_sma_km(orbit: &Orbit) -> f64 {
+        let (_, radius_km, j2) = frame_constants(orbit.frame).unwrap();
+        let sma = orbit.sma_km();
+        let (sin_inc, cos_inc) = orbit.inc_deg().to_radians().sin_cos();
+        let a_r3 = (sma / orbit.rmag_km()).powi(3);
+        let eta3 = (1.0 - orbit.ecc().powi(2)).powf(1.5);
+        let arg_lat = (orbit.aop_deg() + orbit.ta_deg()).to_radians();
+        0.5 * j2 * radius_km.powi(2) / sma
+            * ((3.0 * cos_inc.powi(2) - 1.0) * (a_r3 - 1.0 / eta3)
+                + 3.0 * sin_inc.powi(2) * a_r3 * (2.0 * arg_lat).cos())
+    }
+
+    #[test]
+    fn test_mean_elements_round_trip() {
+        let cosm = Cosm::fixed_planets();
+        let eme2k = cosm.frame("EME2000");
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+        for orbit in [
+            Orbit::keplerian(7000.0, 0.01, 51.6, 30.0, 60.0, 10.0, epoch, eme2k),
+            Orbit::keplerian(8000.0, 0.1, 98.0, 210.0, 100.0, 270.0, epoch, eme2k),
+            Orbit::keplerian(26_560.0, 0.001, 55.0, 120.0, 0.0, 45.0, epoch, eme2k),
+            Orbit::keplerian(24_400.0, 0.7, 28.5, 0.0, 180.0, 180.0, epoch, eme2k),
+        ] {
+            for kind in [
+                MeanElementsKind::BrouwerShort,
+                MeanElementsKind::BrouwerLong,
+                MeanElementsKind::Kozai,
+            ] {
+                let mean = orbit.to_mean_elements(kind).unwrap();
+                println!("{orbit:x}\n{mean}");
+
+                // The mean elements differ from the osculating ones by the J2 perturbations only
+                // and the Brouwer SMA matches the first order theory up to second order terms (J2^2 a, i.e. about 10 m)
+                if kind != MeanElementsKind::Kozai {
+                    let err_km = (orbit.sma_km() - mean.sma_km) - short_period_sma_km(&orbit);
+                    assert!(err_km.abs() < 1e-2, "{kind:?} SMA error {err_km} km");
+                }
+                assert!((mean.ecc - orbit.ecc()).abs() < 1e-2);
+                assert!((mean.inc_deg - orbit.inc_deg()).abs() < 0.1);
+
+                let osc = mean.to_orbit().unwrap();
+                assert!(
+                    (osc.radius() - orbit.radius()).norm() < 1e-6,
+                    "{kind:?} radius error {} km",
+                    (osc.radius() - orbit.radius()).norm()
+                );
+                assert!(
+                    (osc.velocity() - orbit.velocity()).norm() < 1e-9,
+                    "{kind:?} velocity error {} km/s",
+                    (osc.velocity() - orbit.velocity()).norm()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_kozai_mean_motion() {
+        // Kozai mean motion is larger than Brouwer's for inclinations below 54.7 degrees (cf. SGP4 initialization)
+        let cosm = Cosm::fixed_planets();
+        let eme2k = cosm.frame("EME2000");
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+        let orbit = Orbit::keplerian(7000.0, 0.01, 28.5, 30.0, 60.0, 10.0, epoch, eme2k);
+
+        let brouwer = orbit.brouwer_short().unwrap();
+        let kozai = orbit.kozai().unwrap();
+        assert!(kozai.sma_km < brouwer.sma_km);
+        assert!((kozai.ecc - brouwer.ecc).abs() < f64::EPSILON);
+        assert!((kozai.ma_deg - brouwer.ma_deg).abs() < f64::EPSILON);
+
+        // Explicitly building the mean elements leads to the same osculating state
+        let rebuilt = MeanElements {
+            kind: MeanElementsKind::Kozai,
+            sma_km: kozai.sma_km,
+            ecc: kozai.ecc,
+            inc_deg: kozai.inc_deg,
+            raan_deg: kozai.raan_deg,
+            aop_deg: kozai.aop_deg,
+            ma_deg: kozai.ma_deg,
+            epoch,
+            frame: eme2k,
+        }
+        .to_orbit()
+        .unwrap();
+        assert!((rebuilt.radius() - orbit.radius()).norm() < 1e-6);
+    }
+
+    #[test]
+    fn test_mean_elements_state_parameters() {
+        let cosm = Cosm::fixed_planets();
+        let eme2k = cosm.frame("EME2000");
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+        let orbit = Orbit::keplerian(7000.0, 0.01, 51.6, 30.0, 60.0, 10.0, epoch, eme2k);
+
+        let mean = orbit.brouwer_long().unwrap();
+        assert_eq!(
+            orbit.value(StateParameter::BrouwerLongSMA).unwrap(),
+            mean.sma_km
+        );
+        assert_eq!(
+            orbit.value(StateParameter::BrouwerLongRAAN).unwrap(),
+            mean.raan_deg
+        );
+        // Mismatched kind
+        assert!(mean.value(StateParameter::KozaiSMA).is_err());
+
+        // Partials are available for the targeters, and close to the osculating ones
+        let dual = OrbitDual::from(orbit);
+        let mean_sma = dual.partial_for(StateParameter::BrouwerShortSMA).unwrap();
+        let osc_sma = dual.partial_for(StateParameter::SMA).unwrap();
+        assert_eq!(
+            mean_sma.real(),
+            orbit.value(StateParameter::BrouwerShortSMA).unwrap()
+        );
+        println!("{mean_sma}\n{osc_sma}");
+        assert!(((mean_sma.wtr_vy() - osc_sma.wtr_vy()) / osc_sma.wtr_vy()).abs() < 1e-2);
+        assert!(((mean_sma.wtr_vx() - osc_sma.wtr_vx()) / osc_sma.wtr_vx()).abs() < 1e-2);
+
+        // Critical inclination
+        let critical = Orbit::keplerian(7000.0, 0.01, 63.43, 30.0, 60.0, 10.0, epoch, eme2k);
+        assert!(critical.brouwer_short().is_ok());
+        assert!(critical.brouwer_long().is_err());
+
+        // Only defined in an Earth centered Geoid frame and for elliptical orbits
+        let moon = cosm.frame("Moon J2000");
+        assert!(
+            Orbit::keplerian(2000.0, 0.01, 51.6, 30.0, 60.0, 10.0, epoch, moon)
+                .brouwer_short()
+                .is_err()
+        );
+        let sun = cosm.frame("Sun J2000");
+        assert!(
+            Orbit::keplerian(1.5e8, 0.01, 10.0, 0.0, 0.0, 0.0, epoch, sun)
+                .brouwer_short()
+                .is_err()
+        );
+        assert!(
+            Orbit::keplerian(-8000.0, 1.5, 51.6, 30.0, 60.0, 10.0, epoch, eme2k)
+                .kozai()
+                .is_err()
+        );
+    }
+}
@@ -115,6 +115,12 @@ where
         })
     }
 
+    /// Returns the process noise covariance intrinsic to this state over the provided duration, e.g. that of its Gauss-Markov parameters.
+    /// By default, there is none. This is only used when filtering on this state.
+    fn process_noise(&self, _delta_t: Duration) -> Option<OMatrix<f64, Self::Size, Self::Size>> {
+        None
+    }
+
     /// Allows setting the value of the given parameter.
     /// NOTE: Most parameters where the `value` is available CANNOT be also set for that parameter (it's a much harder problem!)
     fn set_value(&mut self, param: StateParameter, _val: f64) -> Result<(), NyxError> {
@@ -135,6 +141,8 @@ pub enum AstroError {
     PartialsUndefined,
     #[snafu(display("Orbit is not hyperbolic so there is no hyperbolic anomaly."))]
     NotHyperbolic,
+    #[snafu(display("operation requires a synodic frame"))]
+    NotSynodicFrame,
 }
 
 impl XbEpoch {
@@ -340,6 +348,13 @@ pub use self::orbit::*;
 mod orbitdual;
 pub use self::orbitdual::*;
 
+// Re-Export mean elements
+mod mean_elements;
+pub use self::mean_elements::*;
+
+// Circular restricted three body problem frames, libration points and Jacobi constant
+mod cr3bp;
+
 // Re-Export B Plane
 mod bplane;
 pub use self::bplane::*;
@@ -348,6 +363,26 @@ pub use self::bplane::*;
 mod spacecraft;
 pub use self::spacecraft::*;
 
+// Re-Export the attitude of the spacecraft
+mod attitude;
+pub use self::attitude::*;
+
+// Re-Export the surface model of the spacecraft
+mod surface;
+pub use self::surface::*;
+
+// Re-Export the solar sail of the spacecraft
+mod sail;
+pub use self::sail::*;
+
+// Re-Export the orbit with estimated empirical accelerations
+mod empirical;
+pub use self::empirical::*;
+
+// Re-Export the spacecraft with an estimated planetary radiation pressure
+mod radiation;
+pub use self::radiation::*;
+
 // Re-Export frames
 mod frames;
 pub use self::frames::*;
@@ -355,6 +390,9 @@ pub use self::frames::*;
 mod rotations;
 pub use self::rotations::*;
 
+/// Precession, nutation and Earth rotation models
+pub(crate) mod nutation;
+
 mod cosm;
 mod xb;
 pub use self::cosm::*;
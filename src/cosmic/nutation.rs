@@ -0,0 +1,457 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Precession, nutation and Earth rotation models of the IERS Conventions (2010) and of the older FK5 reduction, ported from the IAU SOFA library.
+
+use crate::io::eop::EopRecord;
+use crate::linalg::Matrix3;
+use crate::time::Epoch;
+use crate::utils::{r1, r2, r3};
+use std::f64::consts::TAU;
+
+/// Arcseconds to radians
+pub(crate) const ARCSEC_TO_RAD: f64 = TAU / 1_296_000.0;
+/// Arcseconds in a full circle
+const TURN_ARCSEC: f64 = 1_296_000.0;
+/// Seconds between J1900 (the hifitime reference) and J2000
+const J1900_TO_J2000_S: f64 = 36_524.5 * 86_400.0;
+
+/// Delaunay arguments l, l', F, D and Ω, in radians (IERS Conventions 2003), from centuries TT since J2000.
+pub(crate) fn delaunay_arguments(t: f64) -> [f64; 5] {
+    let arg = |c: [f64; 5]| -> f64 {
+        ((c[0] + t * (c[1] + t * (c[2] + t * (c[3] + t * c[4])))) % TURN_ARCSEC) * ARCSEC_TO_RAD
+    };
+    [
+        arg([
+            485_868.249_036,
+            1_717_915_923.217_8,
+            31.879_2,
+            0.051_635,
+            -0.000_244_70,
+        ]),
+        arg([
+            1_287_104.793_048,
+            129_596_581.048_1,
+            -0.553_2,
+            0.000_136,
+            -0.000_011_49,
+        ]),
+        arg([
+            335_779.526_232,
+            1_739_527_262.847_8,
+            -12.751_2,
+            -0.001_037,
+            0.000_004_17,
+        ]),
+        arg([
+            1_072_260.703_692,
+            1_602_961_601.209_0,
+            -6.370_6,
+            0.006_593,
+            -0.000_031_69,
+        ]),
+        arg([
+            450_160.398_036,
+            -6_962_890.543_1,
+            7.472_2,
+            0.007_702,
+            -0.000_059_39,
+        ]),
+    ]
+}
+
+/// Luni-solar terms of the IAU 2000B nutation model (McCarthy & Luzum 2003).
+/// Each row is the multipliers of l, l', F, D, Ω, followed by the longitude (sin, t*sin, cos)
+/// and obliquity (cos, t*cos, sin) coefficients, in units of 0.1 microarcseconds.
+#[rustfmt::skip]
+const IAU2000B: [[f64; 11]; 77] = [
+    [0.0, 0.0, 0.0, 0.0, 1.0, -172_064_161.0, -174_666.0, 33_386.0, 92_052_331.0, 9_086.0, 15_377.0],
+    [0.0, 0.0, 2.0, -2.0, 2.0, -13_170_906.0, -1_675.0, -13_696.0, 5_730_336.0, -3_015.0, -4_587.0],
+    [0.0, 0.0, 2.0, 0.0, 2.0, -2_276_413.0, -234.0, 2_796.0, 978_459.0, -485.0, 1_374.0],
+    [0.0, 0.0, 0.0, 0.0, 2.0, 2_074_554.0, 207.0, -698.0, -897_492.0, 470.0, -291.0],
+    [0.0, 1.0, 0.0, 0.0, 0.0, 1_475_877.0, -3_633.0, 11_817.0, 73_871.0, -184.0, -1_924.0],
+    [0.0, 1.0, 2.0, -2.0, 2.0, -516_821.0, 1_226.0, -524.0, 224_386.0, -677.0, -174.0],
+    [1.0, 0.0, 0.0, 0.0, 0.0, 711_159.0, 73.0, -872.0, -6_750.0, 0.0, 358.0],
+    [0.0, 0.0, 2.0, 0.0, 1.0, -387_298.0, -367.0, 380.0, 200_728.0, 18.0, 318.0],
+    [1.0, 0.0, 2.0, 0.0, 2.0, -301_461.0, -36.0, 816.0, 129_025.0, -63.0, 367.0],
+    [0.0, -1.0, 2.0, -2.0, 2.0, 215_829.0, -494.0, 111.0, -95_929.0, 299.0, 132.0],
+    [0.0, 0.0, 2.0, -2.0, 1.0, 128_227.0, 137.0, 181.0, -68_982.0, -9.0, 39.0],
+    [-1.0, 0.0, 2.0, 0.0, 2.0, 123_457.0, 11.0, 19.0, -53_311.0, 32.0, -4.0],
+    [-1.0, 0.0, 0.0, 2.0, 0.0, 156_994.0, 10.0, -168.0, -1_235.0, 0.0, 82.0],
+    [1.0, 0.0, 0.0, 0.0, 1.0, 63_110.0, 63.0, 27.0, -33_228.0, 0.0, -9.0],
+    [-1.0, 0.0, 0.0, 0.0, 1.0, -57_976.0, -63.0, -189.0, 31_429.0, 0.0, -75.0],
+    [-1.0, 0.0, 2.0, 2.0, 2.0, -59_641.0, -11.0, 149.0, 25_543.0, -11.0, 66.0],
+    [1.0, 0.0, 2.0, 0.0, 1.0, -51_613.0, -42.0, 129.0, 26_366.0, 0.0, 78.0],
+    [-2.0, 0.0, 2.0, 0.0, 1.0, 45_893.0, 50.0, 31.0, -24_236.0, -10.0, 20.0],
+    [0.0, 0.0, 0.0, 2.0, 0.0, 63_384.0, 11.0, -150.0, -1_220.0, 0.0, 29.0],
+    [0.0, 0.0, 2.0, 2.0, 2.0, -38_571.0, -1.0, 158.0, 16_452.0, -11.0, 68.0],
+    [0.0, -2.0, 2.0, -2.0, 2.0, 32_481.0, 0.0, 0.0, -13_870.0, 0.0, 0.0],
+    [-2.0, 0.0, 0.0, 2.0, 0.0, -47_722.0, 0.0, -18.0, 477.0, 0.0, -25.0],
+    [2.0, 0.0, 2.0, 0.0, 2.0, -31_046.0, -1.0, 131.0, 13_238.0, -11.0, 59.0],
+    [1.0, 0.0, 2.0, -2.0, 2.0, 28_593.0, 0.0, -1.0, -12_338.0, 10.0, -3.0],
+    [-1.0, 0.0, 2.0, 0.0, 1.0, 20_441.0, 21.0, 10.0, -10_758.0, 0.0, -3.0],
+    [2.0, 0.0, 0.0, 0.0, 0.0, 29_243.0, 0.0, -74.0, -609.0, 0.0, 13.0],
+    [0.0, 0.0, 2.0, 0.0, 0.0, 25_887.0, 0.0, -66.0, -550.0, 0.0, 11.0],
+    [0.0, 1.0, 0.0, 0.0, 1.0, -14_053.0, -25.0, 79.0, 8_551.0, -2.0, -45.0],
+    [-1.0, 0.0, 0.0, 2.0, 1.0, 15_164.0, 10.0, 11.0, -8_001.0, 0.0, -1.0],
+    [0.0, 2.0, 2.0, -2.0, 2.0, -15_794.0, 72.0, -16.0, 6_850.0, -42.0, -5.0],
+    [0.0, 0.0, -2.0, 2.0, 0.0, 21_783.0, 0.0, 13.0, -167.0, 0.0, 13.0],
+    [1.0, 0.0, 0.0, -2.0, 1.0, -12_873.0, -10.0, -37.0, 6_953.0, 0.0, -14.0],
+    [0.0, -1.0, 0.0, 0.0, 1.0, -12_654.0, 11.0, 63.0, 6_415.0, 0.0, 26.0],
+    [-1.0, 0.0, 2.0, 2.0, 1.0, -10_204.0, 0.0, 25.0, 5_222.0, 0.0, 15.0],
+    [0.0, 2.0, 0.0, 0.0, 0.0, 16_707.0, -85.0, -10.0, 168.0, -1.0, 10.0],
+    [1.0, 0.0, 2.0, 2.0, 2.0, -7_691.0, 0.0, 44.0, 3_268.0, 0.0, 19.0],
+    [-2.0, 0.0, 2.0, 0.0, 0.0, -11_024.0, 0.0, -14.0, 104.0, 0.0, 2.0],
+    [0.0, 1.0, 2.0, 0.0, 2.0, 7_566.0, -21.0, -11.0, -3_250.0, 0.0, -5.0],
+    [0.0, 0.0, 2.0, 2.0, 1.0, -6_637.0, -11.0, 25.0, 3_353.0, 0.0, 14.0],
+    [0.0, -1.0, 2.0, 0.0, 2.0, -7_141.0, 21.0, 8.0, 3_070.0, 0.0, 4.0],
+    [0.0, 0.0, 0.0, 2.0, 1.0, -6_302.0, -11.0, 2.0, 3_272.0, 0.0, 4.0],
+    [1.0, 0.0, 2.0, -2.0, 1.0, 5_800.0, 10.0, 2.0, -3_045.0, 0.0, -1.0],
+    [2.0, 0.0, 2.0, -2.0, 2.0, 6_443.0, 0.0, -7.0, -2_768.0, 0.0, -4.0],
+    [-2.0, 0.0, 0.0, 2.0, 1.0, -5_774.0, -11.0, -15.0, 3_041.0, 0.0, -5.0],
+    [2.0, 0.0, 2.0, 0.0, 1.0, -5_350.0, 0.0, 21.0, 2_695.0, 0.0, 12.0],
+    [0.0, -1.0, 2.0, -2.0, 1.0, -4_752.0, -11.0, -3.0, 2_719.0, 0.0, -3.0],
+    [0.0, 0.0, 0.0, -2.0, 1.0, -4_940.0, -11.0, -21.0, 2_720.0, 0.0, -9.0],
+    [-1.0, -1.0, 0.0, 2.0, 0.0, 7_350.0, 0.0, -8.0, -51.0, 0.0, 4.0],
+    [2.0, 0.0, 0.0, -2.0, 1.0, 4_065.0, 0.0, 6.0, -2_206.0, 0.0, 1.0],
+    [1.0, 0.0, 0.0, 2.0, 0.0, 6_579.0, 0.0, -24.0, -199.0, 0.0, 2.0],
+    [0.0, 1.0, 2.0, -2.0, 1.0, 3_579.0, 0.0, 5.0, -1_900.0, 0.0, 1.0],
+    [1.0, -1.0, 0.0, 0.0, 0.0, 4_725.0, 0.0, -6.0, -41.0, 0.0, 3.0],
+    [-2.0, 0.0, 2.0, 0.0, 2.0, -3_075.0, 0.0, -2.0, 1_313.0, 0.0, -1.0],
+    [3.0, 0.0, 2.0, 0.0, 2.0, -2_904.0, 0.0, 15.0, 1_233.0, 0.0, 7.0],
+    [0.0, -1.0, 0.0, 2.0, 0.0, 4_348.0, 0.0, -10.0, -81.0, 0.0, 2.0],
+    [1.0, -1.0, 2.0, 0.0, 2.0, -2_878.0, 0.0, 8.0, 1_232.0, 0.0, 4.0],
+    [0.0, 0.0, 0.0, 1.0, 0.0, -4_230.0, 0.0, 5.0, -20.0, 0.0, -2.0],
+    [-1.0, -1.0, 2.0, 2.0, 2.0, -2_819.0, 0.0, 7.0, 1_207.0, 0.0, 3.0],
+    [-1.0, 0.0, 2.0, 0.0, 0.0, -4_056.0, 0.0, 5.0, 40.0, 0.0, -2.0],
+    [0.0, -1.0, 2.0, 2.0, 2.0, -2_647.0, 0.0, 11.0, 1_129.0, 0.0, 5.0],
+    [-2.0, 0.0, 0.0, 0.0, 1.0, -2_294.0, 0.0, -10.0, 1_266.0, 0.0, -4.0],
+    [1.0, 1.0, 2.0, 0.0, 2.0, 2_481.0, 0.0, -7.0, -1_062.0, 0.0, -3.0],
+    [2.0, 0.0, 0.0, 0.0, 1.0, 2_179.0, 0.0, -2.0, -1_129.0, 0.0, -2.0],
+    [-1.0, 1.0, 0.0, 1.0, 0.0, 3_276.0, 0.0, 1.0, -9.0, 0.0, 0.0],
+    [1.0, 1.0, 0.0, 0.0, 0.0, -3_389.0, 0.0, 5.0, 35.0, 0.0, -2.0],
+    [1.0, 0.0, 2.0, 0.0, 0.0, 3_339.0, 0.0, -13.0, -107.0, 0.0, 1.0],
+    [-1.0, 0.0, 2.0, -2.0, 1.0, -1_987.0, 0.0, -6.0, 1_073.0, 0.0, -2.0],
+    [1.0, 0.0, 0.0, 0.0, 2.0, -1_981.0, 0.0, 0.0, 854.0, 0.0, 0.0],
+    [-1.0, 0.0, 0.0, 1.0, 0.0, 4_026.0, 0.0, -353.0, -553.0, 0.0, -139.0],
+    [0.0, 0.0, 2.0, 1.0, 2.0, 1_660.0, 0.0, -5.0, -710.0, 0.0, -2.0],
+    [-1.0, 0.0, 2.0, 4.0, 2.0, -1_521.0, 0.0, 9.0, 647.0, 0.0, 4.0],
+    [-1.0, 1.0, 0.0, 1.0, 1.0, 1_314.0, 0.0, 0.0, -700.0, 0.0, 0.0],
+    [0.0, -2.0, 2.0, -2.0, 1.0, -1_283.0, 0.0, 0.0, 672.0, 0.0, 0.0],
+    [1.0, 0.0, 2.0, 2.0, 1.0, -1_331.0, 0.0, 8.0, 663.0, 0.0, 4.0],
+    [-2.0, 0.0, 2.0, 2.0, 2.0, 1_383.0, 0.0, -2.0, -594.0, 0.0, -2.0],
+    [-1.0, 0.0, 0.0, 0.0, 2.0, 1_405.0, 0.0, 4.0, -610.0, 0.0, 2.0],
+    [1.0, 1.0, 2.0, -2.0, 2.0, 1_290.0, 0.0, 0.0, -556.0, 0.0, 0.0],
+];
+
+/// Largest periodic terms of the CIO locator s + XY/2 (IERS Conventions 2010, table 5.2d).
+/// Each row is the power of t, the multipliers of l, l', F, D, Ω, and the sin and cos coefficients in microarcseconds.
+#[rustfmt::skip]
+const CIO_S06: [[f64; 8]; 22] = [
+    [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, -2_640.73, 0.39],
+    [0.0, 0.0, 0.0, 0.0, 0.0, 2.0, -63.53, 0.02],
+    [0.0, 0.0, 0.0, 2.0, -2.0, 3.0, -11.75, -0.01],
+    [0.0, 0.0, 0.0, 2.0, -2.0, 1.0, -11.21, -0.01],
+    [0.0, 0.0, 0.0, 2.0, -2.0, 2.0, 4.57, 0.0],
+    [0.0, 0.0, 0.0, 2.0, 0.0, 3.0, -2.02, 0.0],
+    [0.0, 0.0, 0.0, 2.0, 0.0, 1.0, -1.98, 0.0],
+    [0.0, 0.0, 0.0, 0.0, 0.0, 3.0, 1.72, 0.0],
+    [0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.41, 0.01],
+    [0.0, 0.0, 1.0, 0.0, 0.0, -1.0, 1.26, 0.01],
+    [0.0, 1.0, 0.0, 0.0, 0.0, -1.0, 0.63, 0.0],
+    [0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.63, 0.0],
+    [1.0, 0.0, 0.0, 0.0, 0.0, 2.0, -0.07, 3.57],
+    [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.73, -0.03],
+    [1.0, 0.0, 0.0, 2.0, -2.0, 3.0, 0.0, 0.48],
+    [2.0, 0.0, 0.0, 0.0, 0.0, 1.0, 743.52, -0.17],
+    [2.0, 0.0, 0.0, 2.0, -2.0, 2.0, 56.91, 0.06],
+    [2.0, 0.0, 0.0, 2.0, 0.0, 2.0, 9.84, -0.01],
+    [2.0, 0.0, 0.0, 0.0, 0.0, 2.0, -8.85, 0.01],
+    [2.0, 0.0, 1.0, 0.0, 0.0, 0.0, -6.38, -0.05],
+    [2.0, 1.0, 0.0, 0.0, 0.0, 0.0, -3.07, 0.0],
+    [3.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.30, -23.42],
+];
+
+/// Nutation in longitude and obliquity, in radians, from the IAU 2000B model with the IAU 2006 precession adjustments.
+/// The truncated 2000B series agrees with the full IAU 2000A model to about one milliarcsecond.
+pub(crate) fn nutation_iau2000b(t: f64) -> (f64, f64) {
+    let args = delaunay_arguments(t);
+    let (mut dpsi, mut deps) = (0.0, 0.0);
+    for term in IAU2000B.iter().rev() {
+        let arg: f64 = (0..5).map(|i| term[i] * args[i]).sum();
+        let (sin_arg, cos_arg) = arg.sin_cos();
+        dpsi += (term[5] + term[6] * t) * sin_arg + term[7] * cos_arg;
+        deps += (term[8] + term[9] * t) * cos_arg + term[10] * sin_arg;
+    }
+    // Convert from 0.1 microarcsec and add the fixed offsets standing in for the planetary terms
+    let dpsi = (dpsi * 1e-7 - 0.135e-3) * ARCSEC_TO_RAD;
+    let deps = (deps * 1e-7 + 0.388e-3) * ARCSEC_TO_RAD;
+    // IAU 2006 adjustments for the change in J2 rate and obliquity
+    let fj2 = -2.7774e-6 * t;
+    (dpsi * (1.0 + 0.4697e-6 + fj2), deps * (1.0 + fj2))
+}
+
+/// Mean obliquity of the ecliptic in radians, IAU 2006 model.
+pub(crate) fn mean_obliquity_iau2006(t: f64) -> f64 {
+    (84_381.406
+        + t * (-46.836_769
+            + t * (-0.000_183_1 + t * (0.002_003_40 + t * (-0.000_000_576 - t * 0.000_000_043_4)))))
+        * ARCSEC_TO_RAD
+}
+
+/// Coordinates X and Y of the Celestial Intermediate Pole in the GCRS, in radians, using the IAU 2006 precession
+/// (Fukushima-Williams angles) and the IAU 2000B nutation.
+pub(crate) fn cip_xy(t: f64) -> (f64, f64) {
+    let poly = |c: [f64; 6]| -> f64 {
+        (c[0] + t * (c[1] + t * (c[2] + t * (c[3] + t * (c[4] + t * c[5]))))) * ARCSEC_TO_RAD
+    };
+    let gamb = poly([
+        -0.052_928,
+        10.556_378,
+        0.493_204_4,
+        -0.000_312_38,
+        -0.000_002_788,
+        0.000_000_026_0,
+    ]);
+    let phib = poly([
+        84_381.412_819,
+        -46.811_016,
+        0.051_126_8,
+        0.000_532_89,
+        -0.000_000_440,
+        -0.000_000_017_6,
+    ]);
+    let psib = poly([
+        -0.041_775,
+        5_038.481_484,
+        1.558_417_5,
+        -0.000_185_22,
+        -0.000_026_452,
+        -0.000_000_014_8,
+    ]);
+    let (dpsi, deps) = nutation_iau2000b(t);
+    let epsa = mean_obliquity_iau2006(t);
+
+    let npb = r1(-(epsa + deps)) * r3(-(psib + dpsi)) * r1(phib) * r3(gamb);
+    (npb[(2, 0)], npb[(2, 1)])
+}
+
+/// CIO locator s in radians, given the CIP coordinates X and Y (IAU 2006/2000A, series truncated at 0.5 microarcseconds).
+pub(crate) fn cio_locator(t: f64, x: f64, y: f64) -> f64 {
+    let args = delaunay_arguments(t);
+    let mut s_xy2 =
+        94.0 + t * (3_808.65 + t * (-122.68 + t * (-72_574.11 + t * (27.98 + t * 15.62))));
+    for term in &CIO_S06 {
+        let arg: f64 = (0..5).map(|i| term[i + 1] * args[i]).sum();
+        let (sin_arg, cos_arg) = arg.sin_cos();
+        s_xy2 += t.powi(term[0] as i32) * (term[6] * sin_arg + term[7] * cos_arg);
+    }
+    s_xy2 * 1e-6 * ARCSEC_TO_RAD - 0.5 * x * y
+}
+
+/// Earth rotation angle in radians (IAU 2000), given the UT1-UTC offset in seconds.
+pub(crate) fn earth_rotation_angle(epoch: Epoch, ut1_utc_s: f64) -> f64 {
+    let ut1_days = (epoch.to_utc_seconds() - J1900_TO_J2000_S + ut1_utc_s) / 86_400.0;
+    (TAU * (ut1_days.rem_euclid(1.0) + 0.779_057_273_264_0 + 0.002_737_811_911_354_48 * ut1_days))
+        .rem_euclid(TAU)
+}
+
+/// Largest terms of the IAU 1980 nutation model (Seidelmann 1982), i.e. those of at least 0.5 milliarcseconds.
+/// The remaining 57 terms of the series add up to about one milliarcsecond.
+/// Each row is the multipliers of l, l', F, D, Ω, followed by the longitude (sin, t*sin)
+/// and obliquity (cos, t*cos) coefficients, in units of 0.1 milliarcseconds.
+#[rustfmt::skip]
+const IAU1980: [[f64; 9]; 49] = [
+    [0.0, 0.0, 0.0, 0.0, 1.0, -171_996.0, -174.2, 92_025.0, 8.9],
+    [0.0, 0.0, 2.0, -2.0, 2.0, -13_187.0, -1.6, 5_736.0, -3.1],
+    [0.0, 0.0, 2.0, 0.0, 2.0, -2_274.0, -0.2, 977.0, -0.5],
+    [0.0, 0.0, 0.0, 0.0, 2.0, 2_062.0, 0.2, -895.0, 0.5],
+    [0.0, 1.0, 0.0, 0.0, 0.0, 1_426.0, -3.4, 54.0, -0.1],
+    [1.0, 0.0, 0.0, 0.0, 0.0, 712.0, 0.1, -7.0, 0.0],
+    [0.0, 1.0, 2.0, -2.0, 2.0, -517.0, 1.2, 224.0, -0.6],
+    [0.0, 0.0, 2.0, 0.0, 1.0, -386.0, -0.4, 200.0, 0.0],
+    [1.0, 0.0, 2.0, 0.0, 2.0, -301.0, 0.0, 129.0, -0.1],
+    [0.0, -1.0, 2.0, -2.0, 2.0, 217.0, -0.5, -95.0, 0.3],
+    [1.0, 0.0, 0.0, -2.0, 0.0, -158.0, 0.0, -1.0, 0.0],
+    [0.0, 0.0, 2.0, -2.0, 1.0, 129.0, 0.1, -70.0, 0.0],
+    [-1.0, 0.0, 2.0, 0.0, 2.0, 123.0, 0.0, -53.0, 0.0],
+    [1.0, 0.0, 0.0, 0.0, 1.0, 63.0, 0.1, -33.0, 0.0],
+    [0.0, 0.0, 0.0, 2.0, 0.0, 63.0, 0.0, -2.0, 0.0],
+    [-1.0, 0.0, 2.0, 2.0, 2.0, -59.0, 0.0, 26.0, 0.0],
+    [-1.0, 0.0, 0.0, 0.0, 1.0, -58.0, -0.1, 32.0, 0.0],
+    [1.0, 0.0, 2.0, 0.0, 1.0, -51.0, 0.0, 27.0, 0.0],
+    [2.0, 0.0, 0.0, -2.0, 0.0, 48.0, 0.0, 1.0, 0.0],
+    [-2.0, 0.0, 2.0, 0.0, 1.0, 46.0, 0.0, -24.0, 0.0],
+    [0.0, 0.0, 2.0, 2.0, 2.0, -38.0, 0.0, 16.0, 0.0],
+    [2.0, 0.0, 2.0, 0.0, 2.0, -31.0, 0.0, 13.0, 0.0],
+    [2.0, 0.0, 0.0, 0.0, 0.0, 29.0, 0.0, -1.0, 0.0],
+    [1.0, 0.0, 2.0, -2.0, 2.0, 29.0, 0.0, -12.0, 0.0],
+    [0.0, 0.0, 2.0, 0.0, 0.0, 26.0, 0.0, -1.0, 0.0],
+    [0.0, 0.0, 2.0, -2.0, 0.0, -22.0, 0.0, 0.0, 0.0],
+    [-1.0, 0.0, 2.0, 0.0, 1.0, 21.0, 0.0, -10.0, 0.0],
+    [0.0, 2.0, 0.0, 0.0, 0.0, 17.0, -0.1, 0.0, 0.0],
+    [0.0, 2.0, 2.0, -2.0, 2.0, -16.0, 0.1, 7.0, 0.0],
+    [-1.0, 0.0, 0.0, 2.0, 1.0, 16.0, 0.0, -8.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0, 1.0, -15.0, 0.0, 9.0, 0.0],
+    [1.0, 0.0, 0.0, -2.0, 1.0, -13.0, 0.0, 7.0, 0.0],
+    [0.0, -1.0, 0.0, 0.0, 1.0, -12.0, 0.0, 6.0, 0.0],
+    [2.0, 0.0, -2.0, 0.0, 0.0, 11.0, 0.0, 0.0, 0.0],
+    [-1.0, 0.0, 2.0, 2.0, 1.0, -10.0, 0.0, 5.0, 0.0],
+    [1.0, 0.0, 2.0, 2.0, 2.0, -8.0, 0.0, 3.0, 0.0],
+    [0.0, -1.0, 2.0, 0.0, 2.0, -7.0, 0.0, 3.0, 0.0],
+    [0.0, 0.0, 2.0, 2.0, 1.0, -7.0, 0.0, 3.0, 0.0],
+    [1.0, 1.0, 0.0, -2.0, 0.0, -7.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 2.0, 0.0, 2.0, 7.0, 0.0, -3.0, 0.0],
+    [-2.0, 0.0, 0.0, 2.0, 1.0, -6.0, 0.0, 3.0, 0.0],
+    [0.0, 0.0, 0.0, 2.0, 1.0, -6.0, 0.0, 3.0, 0.0],
+    [2.0, 0.0, 2.0, -2.0, 2.0, 6.0, 0.0, -3.0, 0.0],
+    [1.0, 0.0, 0.0, 2.0, 0.0, 6.0, 0.0, 0.0, 0.0],
+    [1.0, 0.0, 2.0, -2.0, 1.0, 6.0, 0.0, -3.0, 0.0],
+    [0.0, 0.0, 0.0, -2.0, 1.0, -5.0, 0.0, 3.0, 0.0],
+    [0.0, -1.0, 2.0, -2.0, 1.0, -5.0, 0.0, 3.0, 0.0],
+    [2.0, 0.0, 2.0, 0.0, 1.0, -5.0, 0.0, 3.0, 0.0],
+    [1.0, -1.0, 0.0, 0.0, 0.0, 5.0, 0.0, 0.0, 0.0],
+];
+
+/// Delaunay arguments l, l', F, D and Ω, in radians, of the IAU 1980 nutation theory, from centuries TT since J2000.
+fn delaunay_arguments_1980(t: f64) -> [f64; 5] {
+    // Each argument is the polynomial in arcseconds and the number of full revolutions per century
+    let arg = |c: [f64; 4], turns: f64| -> f64 {
+        (c[0] + t * (c[1] + t * (c[2] + t * c[3]))) * ARCSEC_TO_RAD + (turns * t).fract() * TAU
+    };
+    [
+        arg([485_866.733, 715_922.633, 31.310, 0.064], 1_325.0),
+        arg([1_287_099.804, 1_292_581.224, -0.577, -0.012], 99.0),
+        arg([335_778.877, 295_263.137, -13.257, 0.011], 1_342.0),
+        arg([1_072_261.307, 1_105_601.328, -6.891, 0.019], 1_236.0),
+        arg([450_160.280, -482_890.539, 7.455, 0.008], -5.0),
+    ]
+}
+
+/// Nutation in longitude and obliquity, in radians, from the IAU 1980 model.
+pub(crate) fn nutation_iau1980(t: f64) -> (f64, f64) {
+    let args = delaunay_arguments_1980(t);
+    let (mut dpsi, mut deps) = (0.0, 0.0);
+    for term in IAU1980.iter().rev() {
+        let arg: f64 = (0..5).map(|i| term[i] * args[i]).sum();
+        dpsi += (term[5] + term[6] * t) * arg.sin();
+        deps += (term[7] + term[8] * t) * arg.cos();
+    }
+    (dpsi * 1e-4 * ARCSEC_TO_RAD, deps * 1e-4 * ARCSEC_TO_RAD)
+}
+
+/// Mean obliquity of the ecliptic in radians, IAU 1980 model.
+pub(crate) fn mean_obliquity_iau1980(t: f64) -> f64 {
+    (84_381.448 + t * (-46.815_0 + t * (-0.000_59 + t * 0.001_813))) * ARCSEC_TO_RAD
+}
+
+/// Rotation matrix from the mean equator and equinox of J2000 to the mean equator and equinox of date, IAU 1976 precession model.
+pub(crate) fn precession_iau1976(t: f64) -> Matrix3<f64> {
+    let zeta = (2_306.218_1 + t * (0.301_88 + t * 0.017_998)) * t * ARCSEC_TO_RAD;
+    let z = (2_306.218_1 + t * (1.094_68 + t * 0.018_203)) * t * ARCSEC_TO_RAD;
+    let theta = (2_004.310_9 + t * (-0.426_65 - t * 0.041_833)) * t * ARCSEC_TO_RAD;
+    r3(-z) * r2(theta) * r3(-zeta)
+}
+
+/// Rotation matrix from the mean of date to the true of date, and the equation of the equinoxes (without the kinematic terms), in radians,
+/// using the IAU 1980 nutation model.
+pub(crate) fn nutation_matrix_iau1980(t: f64) -> (Matrix3<f64>, f64) {
+    let (dpsi, deps) = nutation_iau1980(t);
+    let eps = mean_obliquity_iau1980(t);
+    (r1(-(eps + deps)) * r3(-dpsi) * r1(eps), dpsi * eps.cos())
+}
+
+/// Rotation matrix from the GCRS to the ITRS following the CIO based IAU 2006/2000 reduction, i.e. W * R3(ERA) * Q^T.
+pub(crate) fn gcrs_to_itrs(epoch: Epoch, eop: &EopRecord) -> Matrix3<f64> {
+    let t = epoch.to_tt_centuries_j2k();
+    let (x, y) = cip_xy(t);
+    let x = x + eop.dx_arcsec * ARCSEC_TO_RAD;
+    let y = y + eop.dy_arcsec * ARCSEC_TO_RAD;
+    let s = cio_locator(t, x, y);
+
+    // Celestial to intermediate
+    let r2_xy = x.powi(2) + y.powi(2);
+    let e = if r2_xy > 0.0 { y.atan2(x) } else { 0.0 };
+    let d = (r2_xy / (1.0 - r2_xy)).sqrt().atan();
+    let c2i = r3(-(e + s)) * r2(d) * r3(e);
+
+    // Polar motion, including the TIO locator s'
+    let sp = -47e-6 * ARCSEC_TO_RAD * t;
+    let pom =
+        r1(-eop.y_pole_arcsec * ARCSEC_TO_RAD) * r2(-eop.x_pole_arcsec * ARCSEC_TO_RAD) * r3(sp);
+
+    pom * r3(earth_rotation_angle(epoch, eop.ut1_utc_s)) * c2i
+}
+
+#[cfg(test)]
+mod ut_nutation {
+    use super::*;
+
+    #[test]
+    fn test_era_sofa() {
+        // SOFA t_era00
+        let era = earth_rotation_angle(Epoch::from_mjd_utc(54_388.0), 0.0);
+        assert!((era - 0.402_283_724_002_815_8).abs() < 1e-10, "{era}");
+    }
+
+    #[test]
+    fn test_cip_xy_s_sofa() {
+        // SOFA t_xy06 and t_s06 at 2400000.5 + 53736.0 TT
+        let t = (2_400_000.5 + 53_736.0 - 2_451_545.0) / 36_525.0;
+        let (x, y) = cip_xy(t);
+        // The IAU 2000B nutation is accurate to one milliarcsecond compared to the 2000A one used in SOFA
+        assert!((x - 0.579_130_848_670_601_1e-3).abs() < 1e-8, "{x}");
+        assert!((y - 0.402_057_981_673_296_1e-4).abs() < 1e-8, "{y}");
+
+        let s = cio_locator(t, 0.579_130_848_670_601_1e-3, 0.402_057_981_673_296_1e-4);
+        assert!((s + 0.122_003_221_307_646_3e-7).abs() < 5e-11, "{s}");
+    }
+
+    #[test]
+    fn test_nutation_iau1980() {
+        // Meeus, Astronomical Algorithms, example 22.a: 1987 April 10 at 0h TD, Δψ = -3.788" and Δε = 9.443"
+        let t = (2_446_895.5 - 2_451_545.0) / 36_525.0;
+        let (dpsi, deps) = nutation_iau1980(t);
+        assert!(
+            (dpsi / ARCSEC_TO_RAD + 3.788).abs() < 2e-3,
+            "{}",
+            dpsi / ARCSEC_TO_RAD
+        );
+        assert!(
+            (deps / ARCSEC_TO_RAD - 9.443).abs() < 2e-3,
+            "{}",
+            deps / ARCSEC_TO_RAD
+        );
+        // ε0 = 23°26'27.407"
+        let eps0 = (23.0 + 26.0 / 60.0 + 27.407 / 3600.0_f64).to_radians();
+        assert!((mean_obliquity_iau1980(t) - eps0).abs() < 1e-3 * ARCSEC_TO_RAD);
+    }
+
+    #[test]
+    fn test_gcrs_to_itrs_orthonormal() {
+        let eop = EopRecord {
+            x_pole_arcsec: 0.1,
+            y_pole_arcsec: 0.3,
+            ut1_utc_s: -0.2,
+            ..Default::default()
+        };
+        let dcm = gcrs_to_itrs(Epoch::from_gregorian_utc_at_midnight(2022, 3, 1), &eop);
+        assert!((dcm * dcm.transpose() - Matrix3::identity()).norm() < 1e-14);
+        assert!((dcm.determinant() - 1.0).abs() < 1e-14);
+    }
+}
@@ -19,7 +19,7 @@
 use super::AstroError;
 use super::Cosm;
 use super::State;
-use super::{BPlane, Frame};
+use super::{BPlane, Frame, FrameOrientation};
 use crate::dynamics::DynamicsError;
 use crate::io::orbit::OrbitSerde;
 use crate::io::{
@@ -419,6 +419,121 @@ impl Orbit {
         ))
     }
 
+    /// Creates a new Orbit around the provided Celestial or Geoid frame from the equinoctial orbital elements.
+    ///
+    /// **Units:** km, none, none, none, none, degrees
+    ///
+    /// The elements are a, h = e sin(ω + Ω), k = e cos(ω + Ω), p = tan(i/2) sin(Ω), q = tan(i/2) cos(Ω), and
+    /// the mean longitude λ = M + ω + Ω. These are non-singular for circular and equatorial orbits, but only defined for
+    /// elliptical orbits, and singular for retrograde equatorial orbits (i = 180 deg).
+    /// Reference: Broucke and Cefola, "On the equinoctial orbit elements", Celestial Mechanics 5, 1972
+    pub fn equinoctial(
+        sma_km: f64,
+        h: f64,
+        k: f64,
+        p: f64,
+        q: f64,
+        mean_long_deg: f64,
+        epoch: Epoch,
+        frame: Frame,
+    ) -> Result<Self, NyxError> {
+        let ecc2 = h.powi(2) + k.powi(2);
+        if ecc2 >= 1.0 || sma_km <= 0.0 {
+            return Err(NyxError::MathDomain {
+                msg: format!("equinoctial elements only defined for elliptical orbits (sma = {sma_km} km, e = {})", ecc2.sqrt()),
+            });
+        }
+
+        // Solve the equinoctial form of Kepler's equation for the eccentric longitude F
+        let mean_long = mean_long_deg.to_radians();
+        let mut ecc_long = mean_long;
+        let mut iter = 0;
+        loop {
+            iter += 1;
+            if iter > 1000 {
+                return Err(NyxError::MaxIterReached {
+                    msg: format!("{iter}"),
+                });
+            }
+            let (sin_f, cos_f) = ecc_long.sin_cos();
+            let delta =
+                (ecc_long + h * cos_f - k * sin_f - mean_long) / (1.0 - h * sin_f - k * cos_f);
+            ecc_long -= delta;
+            if delta.abs() < 1e-14 {
+                break;
+            }
+        }
+
+        let (sin_f, cos_f) = ecc_long.sin_cos();
+        let beta = 1.0 / (1.0 + (1.0 - ecc2).sqrt());
+        let n = (frame.gm() / sma_km.powi(3)).sqrt();
+        let rmag = sma_km * (1.0 - k * cos_f - h * sin_f);
+
+        // Position and velocity in the equinoctial frame
+        let x1 = sma_km * ((1.0 - h.powi(2) * beta) * cos_f + h * k * beta * sin_f - k);
+        let y1 = sma_km * ((1.0 - k.powi(2) * beta) * sin_f + h * k * beta * cos_f - h);
+        let vx1 =
+            sma_km.powi(2) * n / rmag * (h * k * beta * cos_f - (1.0 - h.powi(2) * beta) * sin_f);
+        let vy1 =
+            sma_km.powi(2) * n / rmag * ((1.0 - k.powi(2) * beta) * cos_f - h * k * beta * sin_f);
+
+        let (f_hat, g_hat) = equinoctial_basis(p, q);
+        let radius = x1 * f_hat + y1 * g_hat;
+        let velocity = vx1 * f_hat + vy1 * g_hat;
+
+        Ok(Self::cartesian(
+            radius[0],
+            radius[1],
+            radius[2],
+            velocity[0],
+            velocity[1],
+            velocity[2],
+            epoch,
+            frame,
+        ))
+    }
+
+    /// Creates a new Orbit around the provided Celestial or Geoid frame from the modified equinoctial orbital elements.
+    ///
+    /// **Units:** km, none, none, none, none, degrees
+    ///
+    /// The elements are the semi parameter p, f = e cos(ω + Ω), g = e sin(ω + Ω), h = tan(i/2) cos(Ω), k = tan(i/2) sin(Ω),
+    /// and the true longitude L = ν + ω + Ω. These are non-singular for circular and equatorial orbits, defined for all conics,
+    /// but singular for retrograde equatorial orbits (i = 180 deg).
+    /// Reference: Walker, Ireland and Owens, "A set of modified equinoctial orbit elements", Celestial Mechanics 36, 1985
+    pub fn modified_equinoctial(
+        semi_parameter_km: f64,
+        f: f64,
+        g: f64,
+        h: f64,
+        k: f64,
+        true_long_deg: f64,
+        epoch: Epoch,
+        frame: Frame,
+    ) -> Self {
+        let (sin_l, cos_l) = true_long_deg.to_radians().sin_cos();
+        let alpha2 = h.powi(2) - k.powi(2);
+        let s2 = 1.0 + h.powi(2) + k.powi(2);
+        let w = 1.0 + f * cos_l + g * sin_l;
+        let rmag = semi_parameter_km / w;
+        let sqrt_gm_p = (frame.gm() / semi_parameter_km).sqrt();
+
+        Self::cartesian(
+            rmag / s2 * (cos_l + alpha2 * cos_l + 2.0 * h * k * sin_l),
+            rmag / s2 * (sin_l - alpha2 * sin_l + 2.0 * h * k * cos_l),
+            2.0 * rmag / s2 * (h * sin_l - k * cos_l),
+            -sqrt_gm_p / s2
+                * (sin_l + alpha2 * sin_l - 2.0 * h * k * cos_l + g - 2.0 * f * h * k + alpha2 * g),
+            -sqrt_gm_p / s2
+                * (-cos_l + alpha2 * cos_l + 2.0 * h * k * sin_l - f
+                    + 2.0 * g * h * k
+                    + alpha2 * f),
+            2.0 * sqrt_gm_p / s2 * (h * cos_l + k * sin_l + f * h + g * k),
+            epoch,
+            frame,
+        )
+    }
+
     /// Creates a new Orbit from the geodetic latitude (φ), longitude (λ) and height with respect to the ellipsoid of the frame.
     ///
     /// **Units:** degrees, degrees, km
@@ -1259,6 +1374,90 @@ impl Orbit {
         }
     }
 
+    /// Returns the equinoctial elements p = tan(i/2) sin(Ω) and q = tan(i/2) cos(Ω), computed from the orbital momentum.
+    fn equinoctial_pq(&self) -> (f64, f64) {
+        match self.frame {
+            Frame::Celestial { .. } | Frame::Geoid { .. } => {
+                let w_hat = self.hvec() / self.hmag_km2_s();
+                (w_hat[0] / (1.0 + w_hat[2]), -w_hat[1] / (1.0 + w_hat[2]))
+            }
+            _ => panic!("equinoctial elements not defined in this frame"),
+        }
+    }
+
+    /// Returns the eccentricity vector and the position projected on the equinoctial frame.
+    fn equinoctial_projections(&self) -> ((f64, f64), (f64, f64)) {
+        let (p, q) = self.equinoctial_pq();
+        let (f_hat, g_hat) = equinoctial_basis(p, q);
+        let evec = self.evec();
+        let radius = self.radius();
+        (
+            (evec.dot(&f_hat), evec.dot(&g_hat)),
+            (radius.dot(&f_hat), radius.dot(&g_hat)),
+        )
+    }
+
+    /// Returns the equinoctial element h = e sin(ω + Ω) (no unit)
+    pub fn equinoctial_h(&self) -> f64 {
+        self.equinoctial_projections().0 .1
+    }
+
+    /// Returns the equinoctial element k = e cos(ω + Ω) (no unit)
+    pub fn equinoctial_k(&self) -> f64 {
+        self.equinoctial_projections().0 .0
+    }
+
+    /// Returns the equinoctial element p = tan(i/2) sin(Ω) (no unit)
+    pub fn equinoctial_p(&self) -> f64 {
+        self.equinoctial_pq().0
+    }
+
+    /// Returns the equinoctial element q = tan(i/2) cos(Ω) (no unit)
+    pub fn equinoctial_q(&self) -> f64 {
+        self.equinoctial_pq().1
+    }
+
+    /// Returns the mean longitude λ = M + ω + Ω in degrees, computed without the Keplerian singularities.
+    ///
+    /// Only defined for elliptical orbits.
+    pub fn mean_longitude_deg(&self) -> f64 {
+        let ((k, h), (x1, y1)) = self.equinoctial_projections();
+        let a = self.sma_km();
+        let ecc_factor = (1.0 - h.powi(2) - k.powi(2)).sqrt();
+        let beta = 1.0 / (1.0 + ecc_factor);
+        // Eccentric longitude
+        let sin_f = h + ((1.0 - h.powi(2) * beta) * y1 - h * k * beta * x1) / (a * ecc_factor);
+        let cos_f = k + ((1.0 - k.powi(2) * beta) * x1 - h * k * beta * y1) / (a * ecc_factor);
+        let ecc_long = sin_f.atan2(cos_f);
+        between_0_360((ecc_long + h * cos_f - k * sin_f).to_degrees())
+    }
+
+    /// Returns the modified equinoctial element f = e cos(ω + Ω) (no unit), equal to the equinoctial k
+    pub fn mod_equinoctial_f(&self) -> f64 {
+        self.equinoctial_k()
+    }
+
+    /// Returns the modified equinoctial element g = e sin(ω + Ω) (no unit), equal to the equinoctial h
+    pub fn mod_equinoctial_g(&self) -> f64 {
+        self.equinoctial_h()
+    }
+
+    /// Returns the modified equinoctial element h = tan(i/2) cos(Ω) (no unit), equal to the equinoctial q
+    pub fn mod_equinoctial_h(&self) -> f64 {
+        self.equinoctial_q()
+    }
+
+    /// Returns the modified equinoctial element k = tan(i/2) sin(Ω) (no unit), equal to the equinoctial p
+    pub fn mod_equinoctial_k(&self) -> f64 {
+        self.equinoctial_p()
+    }
+
+    /// Returns the modified equinoctial element L = ν + ω + Ω, the true longitude in degrees, computed without the Keplerian singularities
+    pub fn mod_equinoctial_l_deg(&self) -> f64 {
+        let (_, (x1, y1)) = self.equinoctial_projections();
+        between_0_360(y1.atan2(x1).to_degrees())
+    }
+
     /// Returns the radius of periapsis (or perigee around Earth), in kilometers.
     pub fn periapsis_km(&self) -> f64 {
         match self.frame {
@@ -2085,6 +2284,7 @@ impl State for Orbit {
             gm: 1.0,
             ephem_path: [None, None, None],
             frame_path: [None, None, None],
+            orientation: FrameOrientation::J2000,
         };
 
         Self {
@@ -2151,6 +2351,25 @@ impl State for Orbit {
     }
 
     fn value(&self, param: StateParameter) -> Result<f64, NyxError> {
+        if self.frame.is_synodic()
+            && !matches!(
+                param,
+                StateParameter::X
+                    | StateParameter::Y
+                    | StateParameter::Z
+                    | StateParameter::VX
+                    | StateParameter::VY
+                    | StateParameter::VZ
+                    | StateParameter::Rmag
+                    | StateParameter::Vmag
+                    | StateParameter::JacobiConstant
+            )
+        {
+            return Err(NyxError::StateParameterUnavailable {
+                param,
+                msg: "only the Cartesian parameters and the Jacobi constant are defined in a synodic frame".to_string(),
+            });
+        }
         match param {
             StateParameter::ApoapsisRadius => Ok(self.apoapsis_km()),
             StateParameter::AoL => Ok(self.aol_deg()),
@@ -2163,6 +2382,10 @@ impl State for Orbit {
             StateParameter::EccentricAnomaly => Ok(self.ea_deg()),
             StateParameter::Eccentricity => Ok(self.ecc()),
             StateParameter::Energy => Ok(self.energy_km2_s2()),
+            StateParameter::EquinoctialH => Ok(self.equinoctial_h()),
+            StateParameter::EquinoctialK => Ok(self.equinoctial_k()),
+            StateParameter::EquinoctialP => Ok(self.equinoctial_p()),
+            StateParameter::EquinoctialQ => Ok(self.equinoctial_q()),
             StateParameter::FlightPathAngle => Ok(self.fpa_deg()),
             StateParameter::GeodeticHeight => Ok(self.geodetic_height_km()),
             StateParameter::GeodeticLatitude => Ok(self.geodetic_latitude_deg()),
@@ -2173,7 +2396,22 @@ impl State for Orbit {
             StateParameter::HZ => Ok(self.hz_km2_s()),
             StateParameter::HyperbolicAnomaly => self.hyperbolic_anomaly_deg(),
             StateParameter::Inclination => Ok(self.inc_deg()),
+            StateParameter::JacobiConstant => {
+                if self.frame.is_synodic() {
+                    Ok(self.jacobi_constant())
+                } else {
+                    Err(NyxError::StateParameterUnavailable {
+                        param,
+                        msg: "only defined in a synodic frame".to_string(),
+                    })
+                }
+            }
             StateParameter::MeanAnomaly => Ok(self.ma_deg()),
+            StateParameter::MeanLongitude => Ok(self.mean_longitude_deg()),
+            StateParameter::ModEquinoctialF => Ok(self.mod_equinoctial_f()),
+            StateParameter::ModEquinoctialG => Ok(self.mod_equinoctial_g()),
+            StateParameter::ModEquinoctialH => Ok(self.mod_equinoctial_h()),
+            StateParameter::ModEquinoctialK => Ok(self.mod_equinoctial_k()),
             StateParameter::PeriapsisRadius => Ok(self.periapsis_km()),
             StateParameter::Period => Ok(self.period().to_seconds()),
             StateParameter::RightAscension => Ok(self.right_ascension_deg()),
@@ -2192,6 +2430,7 @@ impl State for Orbit {
             StateParameter::VX => Ok(self.vx_km_s),
             StateParameter::VY => Ok(self.vy_km_s),
             StateParameter::VZ => Ok(self.vz_km_s),
+            _ if param.is_mean_element() => self.mean_element_value(param),
             _ => Err(NyxError::StateParameterUnavailable {
                 param,
                 msg: "no such parameter for orbit structure".to_string(),
@@ -2291,6 +2530,15 @@ impl Configurable for Orbit {
     }
 }
 
+/// Returns the unit vectors f and g of the equinoctial frame (with a direct retrograde factor) from the p and q equinoctial elements
+fn equinoctial_basis(p: f64, q: f64) -> (Vector3<f64>, Vector3<f64>) {
+    let denom = 1.0 + p.powi(2) + q.powi(2);
+    (
+        Vector3::new(1.0 - p.powi(2) + q.powi(2), 2.0 * p * q, -2.0 * p) / denom,
+        Vector3::new(2.0 * p * q, 1.0 + p.powi(2) - q.powi(2), 2.0 * q) / denom,
+    )
+}
+
 /// Computes the true anomaly from the given mean anomaly for an orbit.
 ///
 /// The computation process varies depending on whether the orbit is elliptical (eccentricity less than or equal to 1)
@@ -2316,7 +2564,11 @@ impl Configurable for Orbit {
 ///
 /// If a numerical error occurs during computation, the function may return a MathDomain error. In the case of a
 /// non-converging iterative process, the function will return a MaxIterReached error after 1000 iterations.
-fn compute_mean_to_true_anomaly(ma_radians: f64, ecc: f64, tol: f64) -> Result<f64, NyxError> {
+pub(crate) fn compute_mean_to_true_anomaly(
+    ma_radians: f64,
+    ecc: f64,
+    tol: f64,
+) -> Result<f64, NyxError> {
     let rm = ma_radians;
     if ecc <= 1.0 {
         // Elliptical orbit
@@ -2560,3 +2812,85 @@ epoch: 2018-09-15T00:15:53.098 UTC
         assert!(vel_err < 1e-9);
     }
 }
+
+#[test]
+fn test_equinoctial() {
+    use super::{Cosm, OrbitDual};
+
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let epoch = Epoch::from_gregorian_tai_at_midnight(2021, 3, 4);
+
+    // Includes a circular and an equatorial orbit where the Keplerian elements are singular
+    for orbit in [
+        Orbit::keplerian(
+            8_191.93, 0.024_5, 12.85, 306.614, 314.19, 99.887_7, epoch, eme2k,
+        ),
+        Orbit::keplerian(42_164.0, 0.0, 0.0, 0.0, 0.0, 42.0, epoch, eme2k),
+        Orbit::keplerian(26_600.0, 0.74, 63.4, 30.0, 270.0, 190.0, epoch, eme2k),
+    ] {
+        let equinoctial = Orbit::equinoctial(
+            orbit.sma_km(),
+            orbit.equinoctial_h(),
+            orbit.equinoctial_k(),
+            orbit.equinoctial_p(),
+            orbit.equinoctial_q(),
+            orbit.mean_longitude_deg(),
+            epoch,
+            eme2k,
+        )
+        .unwrap();
+        let (pos_err, vel_err) = rss_orbit_errors(&equinoctial, &orbit);
+        assert!(pos_err < 1e-7, "equinoctial: {pos_err} km");
+        assert!(vel_err < 1e-10, "equinoctial: {vel_err} km/s");
+
+        let modified = Orbit::modified_equinoctial(
+            orbit.semi_parameter_km(),
+            orbit.mod_equinoctial_f(),
+            orbit.mod_equinoctial_g(),
+            orbit.mod_equinoctial_h(),
+            orbit.mod_equinoctial_k(),
+            orbit.mod_equinoctial_l_deg(),
+            epoch,
+            eme2k,
+        );
+        let (pos_err, vel_err) = rss_orbit_errors(&modified, &orbit);
+        assert!(pos_err < 1e-7, "modified equinoctial: {pos_err} km");
+        assert!(vel_err < 1e-10, "modified equinoctial: {vel_err} km/s");
+
+        // Check that the mean longitude matches the Keplerian definition when the latter is defined
+        if orbit.ecc() > 1e-3 && orbit.inc_deg() > 1.0 {
+            let kep_mean_long = between_0_360(orbit.ma_deg() + orbit.aop_deg() + orbit.raan_deg());
+            assert!((orbit.mean_longitude_deg() - kep_mean_long).abs() < 1e-8);
+        }
+
+        // Check the partials against central finite differences
+        let dual = OrbitDual::from(orbit);
+        for param in [
+            StateParameter::EquinoctialH,
+            StateParameter::EquinoctialK,
+            StateParameter::EquinoctialP,
+            StateParameter::EquinoctialQ,
+            StateParameter::MeanLongitude,
+            StateParameter::ModEquinoctialF,
+            StateParameter::ModEquinoctialG,
+            StateParameter::ModEquinoctialH,
+            StateParameter::ModEquinoctialK,
+        ] {
+            let partial = dual.partial_for(param).unwrap();
+            assert!((partial.real() - orbit.value(param).unwrap()).abs() < 1e-12);
+
+            let step_km_s = 1e-5;
+            let mut plus = orbit;
+            plus.vx_km_s += step_km_s;
+            let mut minus = orbit;
+            minus.vx_km_s -= step_km_s;
+            let fd = (plus.value(param).unwrap() - minus.value(param).unwrap()) / (2.0 * step_km_s);
+            assert!(
+                (partial.wtr_vx() - fd).abs() < 1e-5 * fd.abs().max(1.0),
+                "{param}: {} != {fd}",
+                partial.wtr_vx()
+            );
+        }
+    }
+}
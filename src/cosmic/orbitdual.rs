@@ -17,10 +17,11 @@
 */
 
 use super::{AstroError, Frame, Orbit, ECC_EPSILON};
-use crate::linalg::{Vector3, U7};
+use crate::linalg::{Vector3, Vector6, U7};
 use crate::md::StateParameter;
 use crate::time::Epoch;
-use crate::TimeTagged;
+use crate::utils::between_pm_180;
+use crate::{State, TimeTagged};
 use hyperdual::linalg::norm;
 use hyperdual::{Float, OHyperdual};
 use std::f64::consts::PI;
@@ -150,6 +151,7 @@ impl OrbitDual {
             StateParameter::SMA => Ok(self.sma()),
             StateParameter::Eccentricity => Ok(self.ecc()),
             StateParameter::Inclination => Ok(self.inc()),
+            StateParameter::JacobiConstant => self.jacobi_constant(),
             StateParameter::AoP => Ok(self.aop()),
             StateParameter::AoL => Ok(self.aol()),
             StateParameter::RAAN => Ok(self.raan()),
@@ -168,6 +170,16 @@ impl OrbitDual {
             StateParameter::HyperbolicAnomaly => self.hyperbolic_anomaly(),
             StateParameter::SemiParameter => Ok(self.semi_parameter()),
             StateParameter::SemiMinorAxis => Ok(self.semi_minor_axis()),
+            StateParameter::EquinoctialH => Ok(self.equinoctial_h()),
+            StateParameter::EquinoctialK => Ok(self.equinoctial_k()),
+            StateParameter::EquinoctialP => Ok(self.equinoctial_p()),
+            StateParameter::EquinoctialQ => Ok(self.equinoctial_q()),
+            StateParameter::MeanLongitude => Ok(self.mean_longitude()),
+            StateParameter::ModEquinoctialF => Ok(self.mod_equinoctial_f()),
+            StateParameter::ModEquinoctialG => Ok(self.mod_equinoctial_g()),
+            StateParameter::ModEquinoctialH => Ok(self.mod_equinoctial_h()),
+            StateParameter::ModEquinoctialK => Ok(self.mod_equinoctial_k()),
+            _ if param.is_mean_element() => self.mean_element(param),
             _ => Err(AstroError::PartialsUndefined),
         }
     }
@@ -188,6 +200,27 @@ impl OrbitDual {
         }
     }
 
+    /// Returns the Jacobi constant of the circular restricted three body problem, only defined in a synodic frame
+    pub fn jacobi_constant(&self) -> Result<OrbitPartial, AstroError> {
+        match self.frame {
+            Frame::Synodic { mass_ratio, .. } => {
+                let one = OHyperdual::from(1.0);
+                let mu = OHyperdual::from(mass_ratio);
+                let r1 = ((self.x + mu).powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt();
+                let r2 = ((self.x - one + mu).powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt();
+                let potential = OHyperdual::from(0.5) * (self.x.powi(2) + self.y.powi(2))
+                    + (one - mu) / r1
+                    + mu / r2;
+                Ok(OrbitPartial {
+                    param: StateParameter::JacobiConstant,
+                    dual: OHyperdual::from(2.0) * potential
+                        - (self.vx.powi(2) + self.vy.powi(2) + self.vz.powi(2)),
+                })
+            }
+            _ => Err(AstroError::NotSynodicFrame),
+        }
+    }
+
     /// Returns the radius vector of this Orbit in [km, km, km]
     pub(crate) fn radius(&self) -> Vector3<OHyperdual<f64, U7>> {
         Vector3::new(self.x, self.y, self.z)
@@ -702,6 +735,166 @@ impl OrbitDual {
         }
     }
 
+    /// Returns the equinoctial elements p = tan(i/2) sin(Ω) and q = tan(i/2) cos(Ω), computed from the orbital momentum
+    fn equinoctial_pq(&self) -> (OHyperdual<f64, U7>, OHyperdual<f64, U7>) {
+        let hvec = self.hvec();
+        let hmag = self.hmag().dual;
+        let denom = hmag + hvec[2];
+        (hvec[0] / denom, -hvec[1] / denom)
+    }
+
+    /// Returns the eccentricity vector and the position projected on the equinoctial frame
+    #[allow(clippy::type_complexity)]
+    fn equinoctial_projections(
+        &self,
+    ) -> (
+        (OHyperdual<f64, U7>, OHyperdual<f64, U7>),
+        (OHyperdual<f64, U7>, OHyperdual<f64, U7>),
+    ) {
+        let (p, q) = self.equinoctial_pq();
+        let one = OHyperdual::from(1.0);
+        let two = OHyperdual::from(2.0);
+        let denom = one + p.powi(2) + q.powi(2);
+        let f_hat = Vector3::new(
+            (one - p.powi(2) + q.powi(2)) / denom,
+            two * p * q / denom,
+            -two * p / denom,
+        );
+        let g_hat = Vector3::new(
+            two * p * q / denom,
+            (one + p.powi(2) - q.powi(2)) / denom,
+            two * q / denom,
+        );
+        let evec = self.evec();
+        let radius = self.radius();
+        (
+            (evec.dot(&f_hat), evec.dot(&g_hat)),
+            (radius.dot(&f_hat), radius.dot(&g_hat)),
+        )
+    }
+
+    /// Returns the equinoctial element h = e sin(ω + Ω)
+    pub fn equinoctial_h(&self) -> OrbitPartial {
+        OrbitPartial {
+            dual: self.equinoctial_projections().0 .1,
+            param: StateParameter::EquinoctialH,
+        }
+    }
+
+    /// Returns the equinoctial element k = e cos(ω + Ω)
+    pub fn equinoctial_k(&self) -> OrbitPartial {
+        OrbitPartial {
+            dual: self.equinoctial_projections().0 .0,
+            param: StateParameter::EquinoctialK,
+        }
+    }
+
+    /// Returns the equinoctial element p = tan(i/2) sin(Ω)
+    pub fn equinoctial_p(&self) -> OrbitPartial {
+        OrbitPartial {
+            dual: self.equinoctial_pq().0,
+            param: StateParameter::EquinoctialP,
+        }
+    }
+
+    /// Returns the equinoctial element q = tan(i/2) cos(Ω)
+    pub fn equinoctial_q(&self) -> OrbitPartial {
+        OrbitPartial {
+            dual: self.equinoctial_pq().1,
+            param: StateParameter::EquinoctialQ,
+        }
+    }
+
+    /// Returns the mean longitude λ = M + ω + Ω in degrees
+    pub fn mean_longitude(&self) -> OrbitPartial {
+        let ((k, h), (x1, y1)) = self.equinoctial_projections();
+        let one = OHyperdual::from(1.0);
+        let a = self.sma().dual;
+        let ecc_factor = (one - h.powi(2) - k.powi(2)).sqrt();
+        let beta = one / (one + ecc_factor);
+        // Eccentric longitude
+        let sin_f = h + ((one - h.powi(2) * beta) * y1 - h * k * beta * x1) / (a * ecc_factor);
+        let cos_f = k + ((one - k.powi(2) * beta) * x1 - h * k * beta * y1) / (a * ecc_factor);
+        let ecc_long = sin_f.atan2(cos_f);
+        let mut mean_long = (ecc_long + h * cos_f - k * sin_f).to_degrees();
+        if mean_long.real() < 0.0 {
+            mean_long += OHyperdual::from(360.0);
+        }
+        OrbitPartial {
+            dual: mean_long,
+            param: StateParameter::MeanLongitude,
+        }
+    }
+
+    /// Returns the modified equinoctial element f = e cos(ω + Ω)
+    pub fn mod_equinoctial_f(&self) -> OrbitPartial {
+        OrbitPartial {
+            dual: self.equinoctial_k().dual,
+            param: StateParameter::ModEquinoctialF,
+        }
+    }
+
+    /// Returns the modified equinoctial element g = e sin(ω + Ω)
+    pub fn mod_equinoctial_g(&self) -> OrbitPartial {
+        OrbitPartial {
+            dual: self.equinoctial_h().dual,
+            param: StateParameter::ModEquinoctialG,
+        }
+    }
+
+    /// Returns the modified equinoctial element h = tan(i/2) cos(Ω)
+    pub fn mod_equinoctial_h(&self) -> OrbitPartial {
+        OrbitPartial {
+            dual: self.equinoctial_q().dual,
+            param: StateParameter::ModEquinoctialH,
+        }
+    }
+
+    /// Returns the modified equinoctial element k = tan(i/2) sin(Ω)
+    pub fn mod_equinoctial_k(&self) -> OrbitPartial {
+        OrbitPartial {
+            dual: self.equinoctial_p().dual,
+            param: StateParameter::ModEquinoctialK,
+        }
+    }
+
+    /// Returns the requested mean element and its partials.
+    ///
+    /// The osculating to mean elements conversion is iterative, so its partials with respect to the Cartesian state
+    /// are computed by central finite differencing and then chained with the partials of this state.
+    pub fn mean_element(&self, param: StateParameter) -> Result<OrbitPartial, AstroError> {
+        let components = [self.x, self.y, self.z, self.vx, self.vy, self.vz];
+        let state = Vector6::from_iterator(components.iter().map(|component| component[0]));
+
+        let value_at = |state: &Vector6<f64>| {
+            Orbit::cartesian_vec(state, self.dt, self.frame)
+                .value(param)
+                .map_err(|_| AstroError::PartialsUndefined)
+        };
+
+        let mut dual = OHyperdual::from(value_at(&state)?);
+        for (k, component) in components.iter().enumerate() {
+            // Perturb positions by 10 meters and velocities by 1 cm/s
+            let step = if k < 3 { 1e-2 } else { 1e-5 };
+            let mut plus = state;
+            plus[k] += step;
+            let mut minus = state;
+            minus[k] -= step;
+
+            let mut delta = value_at(&plus)? - value_at(&minus)?;
+            if param.unit() == "deg" {
+                delta = between_pm_180(delta);
+            }
+            let partial = delta / (2.0 * step);
+
+            for j in 1..7 {
+                dual[j] += partial * component[j];
+            }
+        }
+
+        Ok(OrbitPartial { param, dual })
+    }
+
     /// Returns the hyperbolic anomaly in degrees between 0 and 360.0
     pub fn hyperbolic_anomaly(&self) -> Result<OrbitPartial, AstroError> {
         if self.ecc().real() <= 1.0 {
@@ -0,0 +1,187 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{Spacecraft, State};
+use crate::dynamics::DynamicsError;
+use crate::errors::NyxError;
+use crate::linalg::{Const, DimName, OMatrix, OVector};
+use crate::md::StateParameter;
+use crate::time::Epoch;
+use std::fmt;
+use std::ops::Add;
+
+/// A spacecraft and the scale factor of the planetary radiation pressure (albedo and infrared), which is appended to the orbit in the state
+/// vector so that a filter can solve for it.
+///
+/// The state vector is [X, Y, Z, Vx, Vy, Vz, scale factor]: the other parameters of the spacecraft, including its mass, are constant.
+/// Propagate it with the `RadiationDynamics`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RadiationSpacecraft {
+    pub sc: Spacecraft,
+    /// Scale factor of the planetary radiation pressure, nominally 1.0
+    pub radiation_scale: f64,
+    /// Optionally stores the state transition matrix of the orbit and the scale factor
+    pub stm: Option<OMatrix<f64, Const<7>, Const<7>>>,
+}
+
+impl RadiationSpacecraft {
+    /// Initializes the state from a spacecraft and the scale factor of the planetary radiation pressure
+    pub fn new(sc: Spacecraft, radiation_scale: f64) -> Self {
+        Self {
+            sc,
+            radiation_scale,
+            stm: None,
+        }
+    }
+
+    /// Copies the current state but sets the STM to identity
+    pub fn with_stm(self) -> Self {
+        let mut me = self;
+        me.reset_stm();
+        me
+    }
+}
+
+impl fmt::Display for RadiationSpacecraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let orbit_prec = f.precision().unwrap_or(6);
+        write!(
+            f,
+            "{}  radiation scale factor = {}",
+            format_args!("{:.*}", orbit_prec, self.sc),
+            self.radiation_scale
+        )
+    }
+}
+
+impl fmt::LowerExp for RadiationSpacecraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let orbit_prec = f.precision().unwrap_or(6);
+        write!(
+            f,
+            "{}  radiation scale factor = {:e}",
+            format_args!("{:.*e}", orbit_prec, self.sc),
+            self.radiation_scale
+        )
+    }
+}
+
+impl State for RadiationSpacecraft {
+    type Size = Const<7>;
+    type VecLength = Const<56>;
+
+    fn reset_stm(&mut self) {
+        self.stm = Some(OMatrix::<f64, Const<7>, Const<7>>::identity());
+    }
+
+    fn zeros() -> Self {
+        Self::default()
+    }
+
+    /// The vector is organized as such:
+    /// [X, Y, Z, Vx, Vy, Vz, scale factor, STM(7x7)]
+    fn as_vector(&self) -> OVector<f64, Const<56>> {
+        let mut vector = OVector::<f64, Const<56>>::zeros();
+        for (i, val) in self.sc.orbit.to_cartesian_vec().iter().enumerate() {
+            vector[i] = *val;
+        }
+        vector[6] = self.radiation_scale;
+        if let Some(stm) = self.stm {
+            for (idx, stm_val) in stm.as_slice().iter().enumerate() {
+                vector[idx + Self::Size::dim()] = *stm_val;
+            }
+        }
+        vector
+    }
+
+    /// Vector is expected to be organized as such:
+    /// [X, Y, Z, Vx, Vy, Vz, scale factor, STM(7x7)]
+    fn set(&mut self, epoch: Epoch, vector: &OVector<f64, Const<56>>) {
+        self.set_epoch(epoch);
+        self.sc.orbit.x_km = vector[0];
+        self.sc.orbit.y_km = vector[1];
+        self.sc.orbit.z_km = vector[2];
+        self.sc.orbit.vx_km_s = vector[3];
+        self.sc.orbit.vy_km_s = vector[4];
+        self.sc.orbit.vz_km_s = vector[5];
+        self.radiation_scale = vector[6];
+        if self.stm.is_some() {
+            self.stm = Some(OMatrix::<f64, Const<7>, Const<7>>::from_column_slice(
+                &vector.as_slice()[Self::Size::dim()..],
+            ));
+        }
+    }
+
+    fn stm(&self) -> Result<OMatrix<f64, Self::Size, Self::Size>, DynamicsError> {
+        match self.stm {
+            Some(stm) => Ok(stm),
+            None => Err(DynamicsError::StateTransitionMatrixUnset),
+        }
+    }
+
+    fn epoch(&self) -> Epoch {
+        self.sc.orbit.epoch
+    }
+
+    fn set_epoch(&mut self, epoch: Epoch) {
+        self.sc.orbit.epoch = epoch;
+    }
+
+    fn add(self, other: OVector<f64, Self::Size>) -> Self {
+        self + other
+    }
+
+    fn value(&self, param: StateParameter) -> Result<f64, NyxError> {
+        match param {
+            StateParameter::RadiationScale => Ok(self.radiation_scale),
+            _ => self.sc.value(param),
+        }
+    }
+
+    fn set_value(&mut self, param: StateParameter, val: f64) -> Result<(), NyxError> {
+        match param {
+            StateParameter::RadiationScale => {
+                self.radiation_scale = val;
+                Ok(())
+            }
+            _ => self.sc.set_value(param, val),
+        }
+    }
+
+    fn unset_stm(&mut self) {
+        self.stm = None;
+    }
+}
+
+impl Add<OVector<f64, Const<7>>> for RadiationSpacecraft {
+    type Output = Self;
+
+    /// Adds the provided state deviation to this orbit and its scale factor
+    fn add(self, other: OVector<f64, Const<7>>) -> Self {
+        let mut me = self;
+        me.sc.orbit.x_km += other[0];
+        me.sc.orbit.y_km += other[1];
+        me.sc.orbit.z_km += other[2];
+        me.sc.orbit.vx_km_s += other[3];
+        me.sc.orbit.vy_km_s += other[4];
+        me.sc.orbit.vz_km_s += other[5];
+        me.radiation_scale += other[6];
+
+        me
+    }
+}
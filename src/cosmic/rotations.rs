@@ -16,19 +16,33 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+use super::nutation::{gcrs_to_itrs, nutation_matrix_iau1980, precession_iau1976};
+use super::Frame;
+use crate::io::bpc::{Bpc, BpcSegment};
+use crate::io::eop::{EarthOrientationParams, EopRecord};
 use crate::log::error;
-use crate::na::Matrix3;
+use crate::na::{Matrix3, Vector3};
 use crate::time::Epoch;
 use crate::utils::{r1, r2, r3};
+use crate::NyxError;
 use meval::{Context, Expr};
 use std::cmp::PartialEq;
 use std::collections::HashMap;
 use std::fmt;
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use std::str::FromStr;
+use std::sync::{Arc, Once};
 
 pub trait ParentRotation: Send + Sync + fmt::Debug {
     fn dcm_to_parent(&self, datetime: Epoch) -> Option<Matrix3<f64>>;
+    /// Same as `dcm_to_parent`, but returns an error if the data needed to compute this rotation is not available at this epoch.
+    fn try_dcm_to_parent(&self, datetime: Epoch) -> Result<Option<Matrix3<f64>>, NyxError> {
+        Ok(self.dcm_to_parent(datetime))
+    }
+    /// Returns the two-vector definition of this rotation, if any: its DCM depends on the ephemerides and is computed by the Cosm.
+    fn two_vector(&self) -> Option<&TwoVector> {
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -246,6 +260,314 @@ impl ParentRotation for Euler3AxisDt {
     }
 }
 
+/// An axis of a two-vector frame, possibly reversed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameAxis {
+    /// Index of the axis: 0 for X, 1 for Y, and 2 for Z
+    pub index: usize,
+    /// Set to true if this axis is opposite to the defining vector
+    pub negative: bool,
+}
+
+impl FromStr for FrameAxis {
+    type Err = IoError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let (negative, axis) = match trimmed.strip_prefix('-') {
+            Some(axis) => (true, axis),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        let index = match axis.to_lowercase().as_str() {
+            "x" => 0,
+            "y" => 1,
+            "z" => 2,
+            _ => {
+                return Err(IoError::new(
+                    IoErrorKind::InvalidData,
+                    format!(
+                        "unknown axis `{s}`, expected X, Y or Z, optionally preceeded by a sign"
+                    ),
+                ))
+            }
+        };
+        Ok(Self { index, negative })
+    }
+}
+
+/// Kind of vector defining an axis of a two-vector frame, computed from the state of a target relative to an observer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TwoVectorKind {
+    /// Position of the target relative to the observer
+    Position,
+    /// Velocity of the target relative to the observer
+    Velocity,
+    /// Orbital momentum of the target relative to the observer, i.e. the cross product of the position and the velocity
+    Momentum,
+}
+
+impl FromStr for TwoVectorKind {
+    type Err = IoError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "position" => Ok(Self::Position),
+            "velocity" => Ok(Self::Velocity),
+            "momentum" => Ok(Self::Momentum),
+            _ => Err(IoError::new(
+                IoErrorKind::InvalidData,
+                format!("unknown vector `{s}`, expected position, velocity or momentum"),
+            )),
+        }
+    }
+}
+
+/// A vector defining an axis of a two-vector frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameVector {
+    pub kind: TwoVectorKind,
+    /// The frame whose center is the observer (e.g. Earth J2000)
+    pub observer: Frame,
+    /// The frame whose center is the target (e.g. Luna J2000)
+    pub target: Frame,
+}
+
+impl FrameVector {
+    /// Returns this vector from the state of the target relative to the observer
+    pub fn from_state(&self, radius: Vector3<f64>, velocity: Vector3<f64>) -> Vector3<f64> {
+        match self.kind {
+            TwoVectorKind::Position => radius,
+            TwoVectorKind::Velocity => velocity,
+            TwoVectorKind::Momentum => radius.cross(&velocity),
+        }
+    }
+}
+
+/// A frame defined by two vectors (e.g. a Sun-Earth rotating frame or an Earth-Moon VNC frame), cf. the two-vector frames of SPICE.
+///
+/// The primary axis is along the primary vector, the secondary axis is along the component of the secondary vector orthogonal
+/// to the primary vector, and the third axis completes the right handed frame.
+/// The vectors are computed from the ephemerides in the orientation of the parent frame, so this rotation is computed by the Cosm.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TwoVector {
+    /// The parent frame, in whose orientation the vectors are computed
+    pub parent: Frame,
+    pub primary_axis: FrameAxis,
+    pub primary: FrameVector,
+    pub secondary_axis: FrameAxis,
+    pub secondary: FrameVector,
+}
+
+impl TwoVector {
+    /// Returns the DCM from the parent frame to this frame, from the primary and secondary vectors expressed in the parent frame.
+    /// Returns None if both vectors are parallel.
+    pub fn dcm_from_vectors(
+        &self,
+        primary: Vector3<f64>,
+        secondary: Vector3<f64>,
+    ) -> Option<Matrix3<f64>> {
+        let (i, j) = (self.primary_axis.index, self.secondary_axis.index);
+        let k = 3 - i - j;
+        let sign = |axis: FrameAxis| if axis.negative { -1.0 } else { 1.0 };
+
+        let e_i = sign(self.primary_axis) * primary.try_normalize(f64::EPSILON)?;
+        let s = sign(self.secondary_axis) * secondary.try_normalize(f64::EPSILON)?;
+        // Whether (i, j, k) is an even permutation of (X, Y, Z)
+        let cyclic = (j + 3 - i) % 3 == 1;
+        let e_k = if cyclic { e_i.cross(&s) } else { s.cross(&e_i) }.try_normalize(1e-12)?;
+        let e_j = if cyclic {
+            e_k.cross(&e_i)
+        } else {
+            e_i.cross(&e_k)
+        };
+
+        let mut axes = [Vector3::zeros(); 3];
+        axes[i] = e_i;
+        axes[j] = e_j;
+        axes[k] = e_k;
+        Some(Matrix3::from_rows(&[
+            axes[0].transpose(),
+            axes[1].transpose(),
+            axes[2].transpose(),
+        ]))
+    }
+}
+
+impl ParentRotation for TwoVector {
+    fn dcm_to_parent(&self, _: Epoch) -> Option<Matrix3<f64>> {
+        // Requires the ephemerides, cf. `Cosm::try_two_vector_dcm`
+        None
+    }
+
+    fn two_vector(&self) -> Option<&TwoVector> {
+        Some(self)
+    }
+}
+
+/// The Earth body fixed frame (ITRF) computed with the IAU 2006/2000 CIO based reduction (IERS Conventions 2010).
+/// The DE ephemerides are in the ICRF, so the parent EME2000 frame is used as the GCRS.
+///
+/// Without Earth orientation parameters, polar motion, UT1-UTC and the celestial pole offsets are all zero, and a warning is logged once.
+/// Once Earth orientation parameters are loaded, they are never extrapolated: the rotation is unavailable outside of the loaded data.
+#[derive(Clone, Debug, Default)]
+pub struct Itrf {
+    pub eop: Option<Arc<EarthOrientationParams>>,
+}
+
+/// Ensures that the use of the ITRF without Earth orientation parameters is only reported once
+static ITRF_WITHOUT_EOP: Once = Once::new();
+
+impl ParentRotation for Itrf {
+    fn dcm_to_parent(&self, datetime: Epoch) -> Option<Matrix3<f64>> {
+        self.try_dcm_to_parent(datetime).ok().flatten()
+    }
+
+    fn try_dcm_to_parent(&self, datetime: Epoch) -> Result<Option<Matrix3<f64>>, NyxError> {
+        let eop = match &self.eop {
+            Some(eop) => eop.at(datetime).ok_or_else(|| {
+                let (start, end) = eop.domain();
+                NyxError::NoInterpolationData {
+                    msg: format!("no Earth orientation parameters at {datetime}, loaded from {start} to {end}"),
+                }
+            })?,
+            None => {
+                ITRF_WITHOUT_EOP.call_once(|| {
+                    warn!("no Earth orientation parameters loaded (cf. Cosm::load_eop): the ITRF is computed without polar motion, UT1-UTC nor celestial pole offsets")
+                });
+                EopRecord::default()
+            }
+        };
+        Ok(Some(gcrs_to_itrs(datetime, &eop)))
+    }
+}
+
+/// The Earth equator and equinox of date frames of the FK5 reduction, using the IAU 1976 precession and IAU 1980 nutation models.
+/// As for the ITRF, the parent EME2000 frame is used as the mean equator and equinox of J2000 (i.e. the frame bias is neglected).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EarthOfDate {
+    /// Mean equator and mean equinox of date (MOD)
+    MeanOfDate,
+    /// True equator and true equinox of date (TOD)
+    TrueOfDate,
+    /// True equator and mean equinox of date (TEME), as used by the SGP4 propagator
+    Teme,
+}
+
+impl ParentRotation for EarthOfDate {
+    fn dcm_to_parent(&self, datetime: Epoch) -> Option<Matrix3<f64>> {
+        let t = datetime.to_tt_centuries_j2k();
+        let precession = precession_iau1976(t);
+        match self {
+            Self::MeanOfDate => Some(precession),
+            Self::TrueOfDate => Some(nutation_matrix_iau1980(t).0 * precession),
+            Self::Teme => {
+                let (nutation, eq_equinoxes) = nutation_matrix_iau1980(t);
+                Some(r3(eq_equinoxes) * nutation * precession)
+            }
+        }
+    }
+}
+
+/// NAIF frame class IDs of the lunar principal axes (PA) frames of the binary PCKs, and the angles in arcseconds about the Z, Y and X axes
+/// of the rotation from each of these PA frames to the mean Earth/polar axis (ME) frame, from the NAIF lunar frames kernels.
+pub const MOON_PA_FRAMES: [(i32, [f64; 3]); 2] = [
+    // MOON_PA_DE421 (moon_080317.tf)
+    (31006, [67.92, 78.56, 0.30]),
+    // MOON_PA_DE440 (moon_de440_220930.tf)
+    (31008, [67.8526, 78.6944, 0.2785]),
+];
+
+/// The lunar libration angles of a binary PCK (e.g. `moon_pa_de440_200625.bpc`), which are a fit of the lunar orientation of the DE ephemerides.
+#[derive(Clone, Debug)]
+pub struct LunarLibration {
+    /// Segments of the principal axes frame, highest priority first
+    segments: Vec<BpcSegment>,
+    /// Rotation from the principal axes frame to the mean Earth/polar axis frame
+    pa_to_me: Matrix3<f64>,
+}
+
+impl LunarLibration {
+    /// Builds the lunar libration from the segments of the last lunar principal axes frame of this binary PCK (cf. `MOON_PA_FRAMES`).
+    pub fn try_from_bpc(bpc: &Bpc) -> Result<Self, NyxError> {
+        let (frame_id, angles_arcsec) = bpc
+            .segments
+            .iter()
+            .rev()
+            .find_map(|seg| {
+                MOON_PA_FRAMES
+                    .iter()
+                    .find(|(frame_id, _)| *frame_id == seg.frame_id)
+            })
+            .ok_or_else(|| NyxError::LoadingError {
+                msg: format!(
+                    "no lunar principal axes frame ({:?}) in the binary PCK",
+                    MOON_PA_FRAMES.map(|(frame_id, _)| frame_id)
+                ),
+            })?;
+
+        let [z_rot, y_rot, x_rot] = angles_arcsec.map(|angle| (angle / 3600.0).to_radians());
+
+        Ok(Self {
+            segments: bpc
+                .segments
+                .iter()
+                .rev()
+                .filter(|seg| seg.frame_id == *frame_id)
+                .cloned()
+                .collect(),
+            pa_to_me: r1(-x_rot) * r2(-y_rot) * r3(-z_rot),
+        })
+    }
+
+    /// Returns the DCM from the J2000 frame to the lunar principal axes frame
+    pub fn pa_dcm(&self, datetime: Epoch) -> Result<Matrix3<f64>, NyxError> {
+        // PCK times are ET seconds past J2000
+        let et_s = datetime.to_et_seconds();
+        let segment = self
+            .segments
+            .iter()
+            .find(|seg| seg.covers(et_s))
+            .ok_or_else(|| NyxError::NoInterpolationData {
+                msg: format!("no lunar libration data at {datetime}"),
+            })?;
+        let [phi, delta, w, ..] = segment.evaluate(et_s)?;
+        Ok(r3(w) * r1(delta) * r3(phi))
+    }
+
+    /// Returns the DCM from the J2000 frame to the lunar mean Earth/polar axis frame
+    pub fn me_dcm(&self, datetime: Epoch) -> Result<Matrix3<f64>, NyxError> {
+        Ok(self.pa_to_me * self.pa_dcm(datetime)?)
+    }
+}
+
+/// The high fidelity lunar body fixed frames: principal axes (PA), used by the lunar gravity fields, or mean Earth/polar axis (ME),
+/// used for cartography and landing sites. Unlike the IAU Moon frame, these are computed from the lunar libration angles of a binary PCK,
+/// which must be loaded with `Cosm::load_lunar_bpc`: until then, any rotation to or from these frames returns an error.
+#[derive(Clone, Debug, Default)]
+pub struct LunarFrame {
+    /// Set to true for the mean Earth/polar axis frame, and to false for the principal axes frame
+    pub mean_earth: bool,
+    pub libration: Option<Arc<LunarLibration>>,
+}
+
+impl ParentRotation for LunarFrame {
+    fn dcm_to_parent(&self, datetime: Epoch) -> Option<Matrix3<f64>> {
+        self.try_dcm_to_parent(datetime).ok().flatten()
+    }
+
+    fn try_dcm_to_parent(&self, datetime: Epoch) -> Result<Option<Matrix3<f64>>, NyxError> {
+        let libration = self
+            .libration
+            .as_ref()
+            .ok_or_else(|| NyxError::NoInterpolationData {
+                msg: "no lunar libration loaded, cf. Cosm::load_lunar_bpc".to_string(),
+            })?;
+        if self.mean_earth {
+            Ok(Some(libration.me_dcm(datetime)?))
+        } else {
+            Ok(Some(libration.pa_dcm(datetime)?))
+        }
+    }
+}
+
 #[test]
 fn test_angle_unit_deser() {
     use std::str::FromStr;
@@ -253,3 +575,178 @@ fn test_angle_unit_deser() {
     assert_eq!(AngleUnit::from_str("RaDiaNs").unwrap(), AngleUnit::Radians);
     assert!(AngleUnit::from_str("Gradian").is_err());
 }
+
+#[test]
+fn test_two_vector_dcm() {
+    use super::FrameOrientation;
+    use std::str::FromStr;
+    let sun = Frame::Celestial {
+        gm: 1.327e11,
+        ephem_path: [Some(0), None, None],
+        frame_path: [Some(0), None, None],
+        orientation: FrameOrientation::J2000,
+    };
+    let earth = Frame::Celestial {
+        gm: 398_600.4415,
+        ephem_path: [Some(3), Some(0), None],
+        frame_path: [Some(0), None, None],
+        orientation: FrameOrientation::J2000,
+    };
+    let vector = |kind| FrameVector {
+        kind,
+        observer: sun,
+        target: earth,
+    };
+
+    assert_eq!(
+        FrameAxis::from_str("-z").unwrap(),
+        FrameAxis {
+            index: 2,
+            negative: true
+        }
+    );
+    assert_eq!(FrameAxis::from_str("+Y").unwrap().index, 1);
+    assert!(FrameAxis::from_str("W").is_err());
+    assert_eq!(
+        TwoVectorKind::from_str("Momentum").unwrap(),
+        TwoVectorKind::Momentum
+    );
+    assert!(TwoVectorKind::from_str("acceleration").is_err());
+
+    let radius = Vector3::new(0.0, 1.5e8, 0.0);
+    let velocity = Vector3::new(-30.0, 0.1, 0.0);
+
+    // Rotating frame: X along the position and Z along the momentum
+    let rotating = TwoVector {
+        parent: sun,
+        primary_axis: FrameAxis::from_str("X").unwrap(),
+        primary: vector(TwoVectorKind::Position),
+        secondary_axis: FrameAxis::from_str("Z").unwrap(),
+        secondary: vector(TwoVectorKind::Momentum),
+    };
+    let dcm = rotating
+        .dcm_from_vectors(
+            rotating.primary.from_state(radius, velocity),
+            rotating.secondary.from_state(radius, velocity),
+        )
+        .unwrap();
+    assert!((dcm * dcm.transpose() - Matrix3::identity()).norm() < 1e-12);
+    assert!((dcm.determinant() - 1.0).abs() < 1e-12);
+    assert!((dcm * radius - Vector3::new(1.5e8, 0.0, 0.0)).norm() < 1e-6);
+    // The velocity is mostly along -X of the parent, i.e. +Y of the rotating frame
+    assert!((dcm * velocity)[1] > 29.9);
+
+    // VNC frame: X along the velocity, Y along the momentum
+    let vnc = TwoVector {
+        primary_axis: FrameAxis::from_str("X").unwrap(),
+        primary: vector(TwoVectorKind::Velocity),
+        secondary_axis: FrameAxis::from_str("Y").unwrap(),
+        secondary: vector(TwoVectorKind::Momentum),
+        ..rotating
+    };
+    let dcm = vnc
+        .dcm_from_vectors(
+            vnc.primary.from_state(radius, velocity),
+            vnc.secondary.from_state(radius, velocity),
+        )
+        .unwrap();
+    assert!((dcm.determinant() - 1.0).abs() < 1e-12);
+    assert!((dcm * velocity - Vector3::new(velocity.norm(), 0.0, 0.0)).norm() < 1e-12);
+    assert!((dcm * radius.cross(&velocity)).normalize()[1] > 1.0 - 1e-12);
+
+    // Reversed axis
+    let reversed = TwoVector {
+        primary_axis: FrameAxis::from_str("-X").unwrap(),
+        ..vnc
+    };
+    let dcm = reversed
+        .dcm_from_vectors(
+            reversed.primary.from_state(radius, velocity),
+            reversed.secondary.from_state(radius, velocity),
+        )
+        .unwrap();
+    assert!((dcm.determinant() - 1.0).abs() < 1e-12);
+    assert!((dcm * velocity)[0] < -29.9);
+
+    // Parallel vectors cannot define a frame
+    assert!(rotating.dcm_from_vectors(radius, 2.0 * radius).is_none());
+}
+
+#[test]
+fn test_lunar_libration() {
+    use crate::io::bpc::BpcSegment;
+    use crate::io::spk::SpkSegmentData;
+
+    let (phi, delta, w) = (0.1, 0.4, 1.2);
+    let segment = |frame_id| BpcSegment {
+        name: "constant libration".to_string(),
+        frame_id,
+        reference_id: 1,
+        data_type: 2,
+        start_et_s: -1e9,
+        end_et_s: 1e9,
+        data: SpkSegmentData::Chebyshev {
+            init_s: -1e9,
+            interval_s: 2e9,
+            record_size: 5,
+            with_velocity: false,
+            records: vec![0.0, 1e9, phi, delta, w],
+        },
+    };
+
+    let epoch = Epoch::from_gregorian_tai_at_midnight(2023, 1, 1);
+    let pa = LunarFrame::default();
+    assert!(pa.try_dcm_to_parent(epoch).is_err());
+    assert!(pa.dcm_to_parent(epoch).is_none());
+
+    assert!(LunarLibration::try_from_bpc(&Bpc {
+        segments: vec![segment(10013)]
+    })
+    .is_err());
+
+    let libration = Arc::new(
+        LunarLibration::try_from_bpc(&Bpc {
+            segments: vec![segment(31006), segment(31008)],
+        })
+        .unwrap(),
+    );
+    let pa = LunarFrame {
+        mean_earth: false,
+        libration: Some(libration.clone()),
+    };
+    let me = LunarFrame {
+        mean_earth: true,
+        libration: Some(libration),
+    };
+
+    let pa_dcm = pa.try_dcm_to_parent(epoch).unwrap().unwrap();
+    assert!((pa_dcm - r3(w) * r1(delta) * r3(phi)).norm() < f64::EPSILON);
+
+    // The PA and ME frames differ by about 875 m on the lunar surface with the DE440 angles
+    let me_dcm = me.try_dcm_to_parent(epoch).unwrap().unwrap();
+    let surface = pa_dcm.transpose() * Vector3::new(1737.4, 0.0, 0.0);
+    let offset_m = (me_dcm * surface - pa_dcm * surface).norm() * 1e3;
+    assert!((offset_m - 875.0).abs() < 5.0, "offset = {offset_m} m");
+
+    assert!(pa
+        .try_dcm_to_parent(epoch + 1e5 * crate::time::Unit::Day)
+        .is_err());
+
+    // The DE421 rotation matches the TK frame of moon_080317.tf, where ME to PA is [67.92"]_3 [78.56"]_2 [0.30"]_1 (SPICE axes rotations)
+    let de421 = LunarLibration::try_from_bpc(&Bpc {
+        segments: vec![segment(31006)],
+    })
+    .unwrap();
+    let pa_to_me_de421 = Matrix3::new(
+        9.999_998_732_547_14e-1,
+        -3.292_854_223_755_71e-4,
+        3.808_696_186_713_87e-4,
+        3.292_860_002_109_47e-4,
+        9.999_999_457_843_06e-1,
+        -1.454_440_937_836_27e-6,
+        -3.808_691_190_960_78e-4,
+        1.579_855_786_826_91e-6,
+        9.999_999_274_681_06e-1,
+    );
+    assert!((de421.pa_to_me - pa_to_me_de421).norm() < 1e-15);
+}
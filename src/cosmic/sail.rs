@@ -0,0 +1,258 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::Orbit;
+use crate::linalg::{Matrix3, Vector3};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Optical coefficients of a flat solar sail, as per McInnes, "Solar Sailing", section 2.6.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SailOptics {
+    /// Reflectivity of the front side, between 0.0 (absorbing) and 1.0 (perfectly reflecting)
+    pub reflectivity: f64,
+    /// Fraction of the reflected light which is reflected specularly, the rest being reflected diffusely
+    pub specularity: f64,
+    /// Emissivity of the front side
+    pub emissivity_front: f64,
+    /// Emissivity of the back side
+    pub emissivity_back: f64,
+    /// Non-Lambertian coefficient of the front side
+    pub non_lambertian_front: f64,
+    /// Non-Lambertian coefficient of the back side
+    pub non_lambertian_back: f64,
+}
+
+impl SailOptics {
+    /// A perfectly reflecting sail, whose force is along its normal
+    pub fn ideal() -> Self {
+        Self {
+            reflectivity: 1.0,
+            specularity: 1.0,
+            emissivity_front: 0.0,
+            emissivity_back: 0.0,
+            non_lambertian_front: 2.0 / 3.0,
+            non_lambertian_back: 2.0 / 3.0,
+        }
+    }
+
+    /// The square sail of the JPL Halley rendezvous study, with an aluminized front and a chromium coated back (McInnes, table 2.1)
+    pub fn jpl_square_sail() -> Self {
+        Self {
+            reflectivity: 0.88,
+            specularity: 0.94,
+            emissivity_front: 0.05,
+            emissivity_back: 0.55,
+            non_lambertian_front: 0.79,
+            non_lambertian_back: 0.55,
+        }
+    }
+
+    /// Returns the force in N on a sail of the provided area, given the radiation pressure in N/m^2, the unit vector of the sunlight and the sail normal.
+    ///
+    /// The force is zero if the sail is edge-on or if its back side faces the Sun.
+    pub fn force_n(
+        &self,
+        area_m2: f64,
+        pressure: f64,
+        sunlight_unit: &Vector3<f64>,
+        normal: &Vector3<f64>,
+    ) -> Vector3<f64> {
+        let cos_cone = sunlight_unit.dot(normal);
+        if cos_cone <= 0.0 {
+            return Vector3::zeros();
+        }
+        let r = self.reflectivity;
+        let s = self.specularity;
+        let emission = if self.emissivity_front + self.emissivity_back > 0.0 {
+            (self.emissivity_front * self.non_lambertian_front
+                - self.emissivity_back * self.non_lambertian_back)
+                / (self.emissivity_front + self.emissivity_back)
+        } else {
+            0.0
+        };
+
+        // McInnes eq. 2.39, where the transverse force is along the projection of the sunlight onto the sail
+        let f_normal = (1.0 + r * s) * cos_cone.powi(2)
+            + (self.non_lambertian_front * (1.0 - s) * r + (1.0 - r) * emission) * cos_cone;
+        let transverse = sunlight_unit - cos_cone * normal;
+        // Equals the sine of the cone angle times its unit vector
+        let f_transverse = (1.0 - r * s) * cos_cone;
+
+        pressure * area_m2 * (f_normal * normal + f_transverse * transverse)
+    }
+}
+
+impl Default for SailOptics {
+    fn default() -> Self {
+        Self::ideal()
+    }
+}
+
+/// A flat solar sail, whose orientation is defined by its cone and clock angles in the sun-line frame (cf. `Sail::dcm_sun_line`).
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sail {
+    /// Area of the sail, in m^2
+    pub area_m2: f64,
+    #[serde(default)]
+    pub optics: SailOptics,
+    /// Angle between the sail normal and the sunlight, in degrees, between 0 (facing the Sun) and 90 (edge-on)
+    #[serde(default)]
+    pub cone_deg: f64,
+    /// Angle of the sail normal about the sunlight, from the transverse axis towards the normal axis of the sun-line frame, in degrees
+    #[serde(default)]
+    pub clock_deg: f64,
+}
+
+impl Sail {
+    /// Initializes a sail facing the Sun
+    pub fn new(area_m2: f64, optics: SailOptics) -> Self {
+        Self {
+            area_m2,
+            optics,
+            cone_deg: 0.0,
+            clock_deg: 0.0,
+        }
+    }
+
+    /// Returns the rotation from the sun-line frame to the frame of the provided orbit, given the unit vector from the Sun to the spacecraft.
+    ///
+    /// The first axis is the sunlight, the third axis is the orbit normal projected orthogonally to the sunlight, and the second axis completes the frame,
+    /// i.e. the along-track direction for a heliocentric orbit.
+    pub fn dcm_sun_line(orbit: &Orbit, sunlight_unit: &Vector3<f64>) -> Matrix3<f64> {
+        let h = orbit.hvec();
+        let mut normal = h - h.dot(sunlight_unit) * sunlight_unit;
+        if normal.norm() < f64::EPSILON * h.norm() {
+            // The orbit normal is along the sunlight, so any orthogonal axis will do
+            normal = sunlight_unit.cross(&Vector3::x());
+            if normal.norm() < 1e-6 {
+                normal = sunlight_unit.cross(&Vector3::y());
+            }
+        }
+        let normal = normal / normal.norm();
+        let transverse = normal.cross(sunlight_unit);
+        Matrix3::from_columns(&[*sunlight_unit, transverse, normal])
+    }
+
+    /// Returns the sail normal in the frame of the sun-line frame rotation
+    pub fn normal(&self, dcm_sun_line: &Matrix3<f64>) -> Vector3<f64> {
+        let (cone, clock) = (self.cone_deg.to_radians(), self.clock_deg.to_radians());
+        dcm_sun_line
+            * Vector3::new(
+                cone.cos(),
+                cone.sin() * clock.cos(),
+                cone.sin() * clock.sin(),
+            )
+    }
+
+    /// Sets the cone and clock angles of the sail from the provided normal, in the frame of the sun-line frame rotation
+    pub fn set_normal(&mut self, dcm_sun_line: &Matrix3<f64>, normal: &Vector3<f64>) {
+        let n = dcm_sun_line.transpose() * normal / normal.norm();
+        self.cone_deg = n[0].clamp(-1.0, 1.0).acos().to_degrees();
+        self.clock_deg = n[2].atan2(n[1]).to_degrees();
+    }
+}
+
+impl fmt::Display for Sail {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "sail of {} m^2 (cone = {:.3} deg, clock = {:.3} deg)",
+            self.area_m2, self.cone_deg, self.clock_deg
+        )
+    }
+}
+
+#[cfg(test)]
+mod ut_sail {
+    use super::*;
+    use crate::cosmic::Cosm;
+    use crate::time::Epoch;
+
+    #[test]
+    fn test_sail_optics() {
+        let sunlight = Vector3::x();
+        let ideal = SailOptics::ideal();
+        // Facing the Sun, the ideal sail reflects twice the pressure
+        let force = ideal.force_n(100.0, 1e-5, &sunlight, &Vector3::x());
+        assert!((force - Vector3::new(2e-3, 0.0, 0.0)).norm() < 1e-15);
+
+        // The force of the ideal sail is along its normal and scales with the square of the cosine of the cone angle
+        let cone = 35f64.to_radians();
+        let normal = Vector3::new(cone.cos(), cone.sin(), 0.0);
+        let force = ideal.force_n(100.0, 1e-5, &sunlight, &normal);
+        assert!((force - 2e-3 * cone.cos().powi(2) * normal).norm() < 1e-15);
+
+        // A non-ideal sail has a transverse component along the sunlight, and generates less force along its normal
+        let jpl = SailOptics::jpl_square_sail();
+        let force = jpl.force_n(100.0, 1e-5, &sunlight, &normal);
+        let transverse = (sunlight - cone.cos() * normal) / cone.sin();
+        assert!(force.dot(&transverse) > 0.0);
+        assert!(force.dot(&normal) < 2e-3 * cone.cos().powi(2));
+        // Facing the Sun, the emission of the hotter back side reduces the force
+        let force = jpl.force_n(100.0, 1e-5, &sunlight, &Vector3::x());
+        let expected =
+            1.0 + 0.88 * 0.94 + 0.79 * 0.06 * 0.88 + 0.12 * (0.05 * 0.79 - 0.55 * 0.55) / 0.6;
+        assert!((force[0] - 1e-3 * expected).abs() < 1e-15);
+
+        // No force edge-on or on the back side
+        assert_eq!(
+            ideal.force_n(100.0, 1e-5, &sunlight, &Vector3::y()),
+            Vector3::zeros()
+        );
+        assert_eq!(
+            ideal.force_n(100.0, 1e-5, &sunlight, &-sunlight),
+            Vector3::zeros()
+        );
+    }
+
+    #[test]
+    fn test_sail_angles() {
+        let cosm = Cosm::fixed_planets();
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        let orbit = Orbit::keplerian(
+            7_000.0,
+            0.0,
+            30.0,
+            0.0,
+            0.0,
+            90.0,
+            epoch,
+            cosm.frame("EME2000"),
+        );
+        let sunlight = Vector3::x();
+        let dcm = Sail::dcm_sun_line(&orbit, &sunlight);
+        assert!((dcm.transpose() * dcm - Matrix3::identity()).norm() < 1e-12);
+        assert!(dcm.determinant() > 0.0);
+        // The third axis is along the orbit normal, which is orthogonal to the sunlight for this orbit
+        assert!((dcm.column(2) - orbit.hvec() / orbit.hvec().norm()).norm() < 1e-12);
+
+        let mut sail = Sail::new(10.0, SailOptics::ideal());
+        assert_eq!(sail.normal(&dcm), sunlight);
+
+        sail.cone_deg = 30.0;
+        sail.clock_deg = -120.0;
+        let normal = sail.normal(&dcm);
+        assert!((normal.dot(&sunlight) - 30f64.to_radians().cos()).abs() < 1e-12);
+
+        let mut other = Sail::new(10.0, SailOptics::ideal());
+        other.set_normal(&dcm, &normal);
+        assert!((other.cone_deg - 30.0).abs() < 1e-12);
+        assert!((other.clock_deg + 120.0).abs() < 1e-12);
+    }
+}
@@ -20,7 +20,7 @@ use nalgebra::Vector3;
 use serde::{Deserialize, Serialize};
 
 use super::eclipse::Cosm;
-use super::{Orbit, State};
+use super::{AttitudeLaw, Orbit, Sail, State};
 use crate::dynamics::guidance::Thruster;
 use crate::dynamics::DynamicsError;
 use crate::errors::NyxError;
@@ -97,10 +97,19 @@ pub struct Spacecraft {
     pub srp: SrpConfig,
     #[serde(default)]
     pub drag: DragConfig,
+    /// Optional attitude of the body frame, which overrides the attitude law of the surface models of the SRP and drag (e.g. the propagated attitude)
+    #[serde(default)]
+    pub attitude: Option<AttitudeLaw>,
+    /// Optional solar sail, whose orientation is set by the sail steering laws
+    #[serde(default)]
+    pub sail: Option<Sail>,
     pub thruster: Option<Thruster>,
     /// Any extra information or extension that is needed for specific guidance laws
     #[serde(default)]
     pub mode: GuidanceMode,
+    /// Throttle level used by the guidance law at this state, between 0.0 (coasting) and 1.0 (full thrust)
+    #[serde(default)]
+    pub throttle: f64,
     /// Optionally stores the state transition matrix from the start of the propagation until the current time (i.e. trajectory STM, not step-size STM)
     #[serde(skip)]
     pub stm: Option<OMatrix<f64, Const<9>, Const<9>>>,
@@ -114,8 +123,11 @@ impl Default for Spacecraft {
             fuel_mass_kg: 0.0,
             srp: SrpConfig::default(),
             drag: DragConfig::default(),
+            attitude: None,
+            sail: None,
             thruster: None,
             mode: GuidanceMode::default(),
+            throttle: 0.0,
             stm: None,
         }
     }
@@ -327,6 +339,20 @@ impl Spacecraft {
         me
     }
 
+    /// Returns a copy of the state with the attitude of its body frame, which orients the surface models of the SRP and drag
+    pub fn with_attitude(self, attitude: AttitudeLaw) -> Self {
+        let mut me = self;
+        me.attitude = Some(attitude);
+        me
+    }
+
+    /// Returns a copy of the state with a new solar sail
+    pub fn with_sail(self, sail: Sail) -> Self {
+        let mut me = self;
+        me.sail = Some(sail);
+        me
+    }
+
     /// Returns a copy of the state with a new orbit
     pub fn with_orbit(self, orbit: Orbit) -> Self {
         let mut me = self;
@@ -578,6 +604,19 @@ impl State for Spacecraft {
                 None => Err(NyxError::NoThrusterAvail),
             },
             StateParameter::GuidanceMode => Ok(self.mode.into()),
+            StateParameter::Throttle => Ok(self.throttle),
+            StateParameter::SailCone => match self.sail {
+                Some(sail) => Ok(sail.cone_deg),
+                None => Err(NyxError::CustomError {
+                    msg: "No solar sail on spacecraft".to_string(),
+                }),
+            },
+            StateParameter::SailClock => match self.sail {
+                Some(sail) => Ok(sail.clock_deg),
+                None => Err(NyxError::CustomError {
+                    msg: "No solar sail on spacecraft".to_string(),
+                }),
+            },
             _ => self.orbit.value(param),
         }
     }
@@ -595,6 +634,20 @@ impl State for Spacecraft {
                 Some(ref mut thruster) => thruster.thrust_N = val,
                 None => return Err(NyxError::NoThrusterAvail),
             },
+            StateParameter::SailCone | StateParameter::SailClock => match self.sail {
+                Some(ref mut sail) => {
+                    if param == StateParameter::SailCone {
+                        sail.cone_deg = val
+                    } else {
+                        sail.clock_deg = val
+                    }
+                }
+                None => {
+                    return Err(NyxError::CustomError {
+                        msg: "No solar sail on spacecraft".to_string(),
+                    })
+                }
+            },
             _ => return self.orbit.set_value(param, val),
         }
         Ok(())
@@ -0,0 +1,367 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{Orbit, Spacecraft};
+use crate::linalg::{Matrix3, Vector3};
+use crate::na::{Quaternion, UnitQuaternion};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Attitude law of the body frame of the spacecraft, in which the plates of its surface model are defined
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
+pub enum AttitudeLaw {
+    /// Body +Z points to the nadir, +Y along the negative orbit normal and +X completes the frame (along the velocity for a circular orbit)
+    #[default]
+    NadirPointing,
+    /// The body frame is aligned with the integration frame
+    Inertial,
+    /// The body frame is rotated to the integration frame by this unit quaternion (scalar first), e.g. the propagated attitude of a `RigidSpacecraft`
+    Quaternion([f64; 4]),
+}
+
+impl AttitudeLaw {
+    /// Returns the rotation from the body frame to the frame of the provided orbit, i.e. whose columns are the body axes
+    pub fn dcm_to_frame(&self, orbit: &Orbit) -> Matrix3<f64> {
+        match self {
+            Self::NadirPointing => {
+                let z = -orbit.radius() / orbit.rmag_km();
+                let h = orbit.hvec();
+                let y = -h / h.norm();
+                let x = y.cross(&z);
+                Matrix3::from_columns(&[x, y, z])
+            }
+            Self::Inertial => Matrix3::identity(),
+            Self::Quaternion([w, i, j, k]) => {
+                UnitQuaternion::from_quaternion(Quaternion::new(*w, *i, *j, *k))
+                    .to_rotation_matrix()
+                    .into_inner()
+            }
+        }
+    }
+
+    /// Returns the angular velocity of the body frame in the frame of the provided orbit, in rad/s
+    pub fn angular_velocity(&self, orbit: &Orbit) -> Vector3<f64> {
+        match self {
+            Self::NadirPointing => orbit.hvec() / orbit.rmag_km().powi(2),
+            Self::Inertial | Self::Quaternion(_) => Vector3::zeros(),
+        }
+    }
+}
+
+/// A flat plate of the surface model of a spacecraft
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
+pub struct Plate {
+    /// Area of the plate in m^2
+    pub area_m2: f64,
+    /// Unit normal of the illuminated side of the plate, in the body frame
+    pub normal: [f64; 3],
+    /// Coefficient of specular reflection, between 0 and 1
+    pub specular: f64,
+    /// Coefficient of diffuse reflection, between 0 and 1 (the remainder of the specular and diffuse coefficients is absorbed)
+    pub diffuse: f64,
+    /// If set, the plate rotates about this axis of the body frame to face the Sun, like solar arrays, and both of its sides are active
+    #[serde(default)]
+    pub sun_tracking_axis: Option<[f64; 3]>,
+    /// Center of pressure of the plate relative to the center of mass, in the body frame and in meters, which only matters for the torques
+    #[serde(default)]
+    pub center_m: [f64; 3],
+}
+
+impl Plate {
+    /// Initialize a plate fixed in the body frame
+    pub fn new(area_m2: f64, normal: [f64; 3], specular: f64, diffuse: f64) -> Self {
+        Self {
+            area_m2,
+            normal,
+            specular,
+            diffuse,
+            sun_tracking_axis: None,
+            center_m: [0.0; 3],
+        }
+    }
+
+    /// Returns a copy of this plate with its center of pressure in meters in the body frame
+    pub fn with_center(self, center_m: [f64; 3]) -> Self {
+        let mut me = self;
+        me.center_m = center_m;
+        me
+    }
+
+    /// Initialize a plate rotating about the provided body axis to face the Sun, whose normal is used when the Sun direction is not needed
+    pub fn sun_tracking(
+        area_m2: f64,
+        axis: [f64; 3],
+        normal: [f64; 3],
+        specular: f64,
+        diffuse: f64,
+    ) -> Self {
+        Self {
+            sun_tracking_axis: Some(axis),
+            ..Self::new(area_m2, normal, specular, diffuse)
+        }
+    }
+
+    /// Returns the unit normal of this plate in the frame of the orbit, given the body to frame rotation and the optional direction of the Sun.
+    fn normal_in_frame(&self, dcm: &Matrix3<f64>, sun_unit: Option<&Vector3<f64>>) -> Vector3<f64> {
+        let normal = dcm * Vector3::from(self.normal).normalize();
+        match (self.sun_tracking_axis, sun_unit) {
+            (Some(axis), Some(sun_unit)) => {
+                let axis = dcm * Vector3::from(axis).normalize();
+                let in_plane = sun_unit - sun_unit.dot(&axis) * axis;
+                if in_plane.norm() > f64::EPSILON {
+                    in_plane.normalize()
+                } else {
+                    normal
+                }
+            }
+            _ => normal,
+        }
+    }
+}
+
+/// A macro-model of the surface of a spacecraft (e.g. a box-wing model), as a set of plates oriented by an attitude law.
+///
+/// When set on the SRP and drag force models (e.g. `SolarPressure::with_surface`), they evaluate each plate instead of the cannonball areas
+/// of the spacecraft. The attitude of the spacecraft state, if any, overrides the attitude law of the model.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SurfaceModel {
+    /// Attitude law of the body frame
+    #[serde(default)]
+    pub attitude: AttitudeLaw,
+    plates: Vec<Plate>,
+}
+
+impl SurfaceModel {
+    /// Initialize a surface model from its attitude law and its plates
+    pub fn new(attitude: AttitudeLaw, plates: &[Plate]) -> Self {
+        Self {
+            attitude,
+            plates: plates.to_vec(),
+        }
+    }
+
+    /// Returns the plates of this surface model
+    pub fn plates(&self) -> &[Plate] {
+        &self.plates
+    }
+
+    /// Returns the rotation from the body frame to the frame of the orbit of the spacecraft, from its attitude if set or else from the attitude law
+    fn dcm_to_frame(&self, sc: &Spacecraft) -> Matrix3<f64> {
+        sc.attitude.unwrap_or(self.attitude).dcm_to_frame(&sc.orbit)
+    }
+
+    /// Returns whether any plate tracks the Sun
+    pub fn has_sun_tracking(&self) -> bool {
+        self.plates().iter().any(|p| p.sun_tracking_axis.is_some())
+    }
+
+    /// Returns the solar radiation pressure force on each illuminated plate, in N in the frame of the orbit, as per Montenbruck & Gill, eq. 3.73.
+    fn srp_plate_forces<'a>(
+        &'a self,
+        dcm: &'a Matrix3<f64>,
+        sun_unit: &'a Vector3<f64>,
+        pressure_n_m2: f64,
+    ) -> impl Iterator<Item = (&'a Plate, Vector3<f64>)> + 'a {
+        self.plates().iter().filter_map(move |plate| {
+            let mut normal = plate.normal_in_frame(dcm, Some(sun_unit));
+            let mut cos_theta = normal.dot(sun_unit);
+            if plate.sun_tracking_axis.is_some() && cos_theta < 0.0 {
+                normal = -normal;
+                cos_theta = -cos_theta;
+            }
+            (cos_theta > 0.0).then(|| {
+                let force = -pressure_n_m2
+                    * plate.area_m2
+                    * cos_theta
+                    * ((1.0 - plate.specular) * sun_unit
+                        + 2.0 * (plate.specular * cos_theta + plate.diffuse / 3.0) * normal);
+                (plate, force)
+            })
+        })
+    }
+
+    /// Returns the solar radiation pressure force in N in the frame of the orbit, as per Montenbruck & Gill, eq. 3.73.
+    ///
+    /// The `sun_unit` is the direction from the spacecraft to the Sun and the pressure is in N/m^2. Self-shadowing is ignored.
+    pub fn srp_force_n(
+        &self,
+        sc: &Spacecraft,
+        sun_unit: &Vector3<f64>,
+        pressure_n_m2: f64,
+    ) -> Vector3<f64> {
+        let dcm = self.dcm_to_frame(sc);
+        self.srp_plate_forces(&dcm, sun_unit, pressure_n_m2)
+            .map(|(_, force)| force)
+            .sum()
+    }
+
+    /// Returns the solar radiation pressure torque about the center of mass in N m in the body frame, from the center of pressure of each plate.
+    pub fn srp_torque_nm(
+        &self,
+        sc: &Spacecraft,
+        sun_unit: &Vector3<f64>,
+        pressure_n_m2: f64,
+    ) -> Vector3<f64> {
+        let dcm = self.dcm_to_frame(sc);
+        self.srp_plate_forces(&dcm, sun_unit, pressure_n_m2)
+            .map(|(plate, force)| Vector3::from(plate.center_m).cross(&(dcm.transpose() * force)))
+            .sum()
+    }
+
+    /// Returns the area in m^2 projected onto the plane normal to the flow direction, i.e. the direction of the velocity relative to the atmosphere.
+    ///
+    /// The direction of the Sun is only needed to orient the Sun tracking plates, which otherwise use their nominal normal.
+    pub fn projected_area_m2(
+        &self,
+        sc: &Spacecraft,
+        flow_unit: &Vector3<f64>,
+        sun_unit: Option<&Vector3<f64>>,
+    ) -> f64 {
+        let dcm = self.dcm_to_frame(sc);
+        self.plates()
+            .iter()
+            .map(|plate| {
+                let cos_theta = plate.normal_in_frame(&dcm, sun_unit).dot(flow_unit);
+                if plate.sun_tracking_axis.is_some() {
+                    plate.area_m2 * cos_theta.abs()
+                } else {
+                    plate.area_m2 * cos_theta.max(0.0)
+                }
+            })
+            .sum()
+    }
+}
+
+impl fmt::Display for SurfaceModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let area: f64 = self.plates().iter().map(|p| p.area_m2).sum();
+        write!(
+            f,
+            "{} plates ({area} m^2) with {:?} attitude",
+            self.plates.len(),
+            self.attitude
+        )
+    }
+}
+
+#[cfg(test)]
+mod ut_surface {
+    use super::*;
+    use crate::cosmic::{Cosm, Spacecraft};
+    use crate::dynamics::{ConstantDrag, ForceModel, SolarPressure};
+    use crate::time::Epoch;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_box_wing() {
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let eme2k = cosm.frame("EME2000");
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        // On the sunlit side, since the Sun is along -X in this test Cosm
+        let orbit = Orbit::keplerian(7_000.0, 0.0, 30.0, 0.0, 0.0, 180.0, epoch, eme2k);
+        let srp = SolarPressure::default(eme2k, cosm.clone());
+
+        // A single absorbing plate facing the Sun is equivalent to a cannonball with Cr = 1
+        let absorbing = SurfaceModel::new(
+            AttitudeLaw::Inertial,
+            &[Plate::new(10.0, [-1.0, 0.0, 0.0], 0.0, 0.0)],
+        );
+        let cannonball = Spacecraft::from_srp_defaults(orbit, 100.0, 10.0).with_cr(1.0);
+        let expected = srp.eom(&cannonball).unwrap();
+        let plate_srp = srp
+            .with_surface(Arc::new(absorbing))
+            .eom(&cannonball)
+            .unwrap();
+        assert!((plate_srp - expected).norm() < 1e-12 * expected.norm());
+
+        // A mirror facing the Sun receives twice the force, and a mirror at 45 degrees receives it along its normal
+        let mirror = SurfaceModel::new(
+            AttitudeLaw::Inertial,
+            &[Plate::new(10.0, [-1.0, 0.0, 0.0], 1.0, 0.0)],
+        );
+        let mirror_srp = srp.with_surface(Arc::new(mirror)).eom(&cannonball).unwrap();
+        assert!((mirror_srp - 2.0 * expected).norm() < 1e-12 * expected.norm());
+        let tilted = SurfaceModel::new(
+            AttitudeLaw::Inertial,
+            &[Plate::new(10.0, [-1.0, 1.0, 0.0], 1.0, 0.0)],
+        );
+        let tilted_srp = srp.with_surface(Arc::new(tilted)).eom(&cannonball).unwrap();
+        assert!((tilted_srp.norm() - expected.norm()).abs() < 1e-12 * expected.norm());
+        assert!((tilted_srp.normalize() - Vector3::new(1.0, -1.0, 0.0).normalize()).norm() < 1e-12);
+
+        // Box-wing in nadir pointing: bus faces and solar arrays tracking the Sun about the body Y axis
+        let box_wing = SurfaceModel::new(
+            AttitudeLaw::NadirPointing,
+            &[
+                Plate::new(2.0, [1.0, 0.0, 0.0], 0.1, 0.3),
+                Plate::new(2.0, [-1.0, 0.0, 0.0], 0.1, 0.3),
+                Plate::new(3.0, [0.0, 0.0, 1.0], 0.1, 0.3),
+                Plate::new(3.0, [0.0, 0.0, -1.0], 0.1, 0.3),
+                Plate::sun_tracking(8.0, [0.0, 1.0, 0.0], [1.0, 0.0, 0.0], 0.05, 0.05),
+            ],
+        );
+        assert!(box_wing.has_sun_tracking());
+        assert_eq!(
+            format!("{box_wing}"),
+            "5 plates (18 m^2) with NadirPointing attitude"
+        );
+        let sc = Spacecraft::from_drag_defaults(orbit, 100.0, 0.0);
+        let box_wing = Arc::new(box_wing);
+
+        // The Sun is at the zenith and in the orbital plane, so the arrays fully face it and are edge on along the velocity
+        let dcm = AttitudeLaw::NadirPointing.dcm_to_frame(&orbit);
+        let sun_unit = Vector3::new(-1.0, 0.0, 0.0);
+        let array_normal = box_wing.plates()[4].normal_in_frame(&dcm, Some(&sun_unit));
+        assert!((array_normal.dot(&sun_unit).abs() - 1.0).abs() < 1e-12);
+        let flow_unit = dcm.column(0).into_owned();
+        let ram_area = box_wing.projected_area_m2(&sc, &flow_unit, Some(&sun_unit));
+        assert!((ram_area - 2.0).abs() < 1e-12);
+        // Without the Sun direction, the arrays keep their nominal normal along the velocity
+        assert!((box_wing.projected_area_m2(&sc, &flow_unit, None) - 10.0).abs() < 1e-12);
+        // The attitude of the spacecraft overrides the attitude law: aligned with the integration frame, the arrays face the Sun along -X
+        let inertial = sc.with_attitude(AttitudeLaw::Inertial);
+        let sun_area = box_wing.projected_area_m2(&inertial, &sun_unit, Some(&sun_unit));
+        assert!((sun_area - 10.0).abs() < 1e-12);
+
+        // The drag uses the area projected along the velocity relative to the co-rotating atmosphere
+        let drag = ConstantDrag {
+            rho: 1e-12,
+            drag_frame: cosm.frame("IAU Earth"),
+            surface: None,
+            cosm: cosm.clone(),
+        };
+        let cannonball = sc.with_drag_area(1.0);
+        let plate_drag = drag.with_surface(box_wing.clone());
+        assert!(format!("{plate_drag}").contains("Constant Drag"));
+        let area_m2 = plate_drag.eom(&sc).unwrap().norm() / drag.eom(&cannonball).unwrap().norm();
+        assert!((area_m2 - 2.0).abs() < 1e-2, "{area_m2}");
+
+        // The SRP partials with the surface model are consistent with the force
+        let srp = srp.with_surface(box_wing.clone());
+        assert!(format!("{srp}").contains("5 plates"));
+        let (force, partials) = srp.dual_eom(&sc).unwrap();
+        assert!((force - srp.eom(&sc).unwrap()).norm() < f64::EPSILON);
+        assert!(partials.iter().all(|p| p.is_finite()));
+        assert!(partials.norm() < 1e-3 * force.norm());
+
+        // Serialization of the surface model lists the plates
+        let yaml = serde_yaml::to_string(box_wing.as_ref()).unwrap();
+        let reloaded: SurfaceModel = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(reloaded, *box_wing);
+    }
+}
@@ -0,0 +1,587 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{Dynamics, DynamicsError, ForceModel, SpacecraftDynamics};
+use crate::cosmic::{
+    Bodies, Cosm, Frame, LightTimeCalc, RadiationSpacecraft, Spacecraft, AU, SPEED_OF_LIGHT,
+};
+use crate::linalg::{Const, DimName, Matrix3x6, OMatrix, OVector, Vector3};
+use crate::time::Epoch;
+use crate::State;
+use hyperdual::linalg::norm;
+use hyperdual::{extract_jacobian_and_result, hyperspace_from_vector, Float, OHyperdual};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{PI, TAU};
+use std::fmt;
+use std::sync::Arc;
+
+type Dual = OHyperdual<f64, Const<7>>;
+
+/// Albedo and emissivity of the surface of the central body.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum RadiationCoefficients {
+    /// Same albedo and emissivity over the whole surface
+    Constant { albedo: f64, emissivity: f64 },
+    /// Latitude and season dependent coefficients of the Earth from Knocke et al. (1988), "Earth radiation pressure effects on
+    /// satellites", AIAA/AAS Astrodynamics Conference.
+    Knocke,
+}
+
+impl RadiationCoefficients {
+    /// Returns the albedo and the emissivity at the provided sine of the latitude and epoch
+    pub fn at(&self, sin_lat: f64, epoch: Epoch) -> (f64, f64) {
+        let (albedo, emissivity) = self.dual_at(Dual::from_real(sin_lat), epoch);
+        (albedo.real(), emissivity.real())
+    }
+
+    /// Same as `at` with hyperdual numbers, for the partials of the planetary radiation
+    fn dual_at(&self, sin_lat: Dual, epoch: Epoch) -> (Dual, Dual) {
+        match self {
+            Self::Constant { albedo, emissivity } => {
+                (Dual::from_real(*albedo), Dual::from_real(*emissivity))
+            }
+            Self::Knocke => {
+                // Seasonal variation with respect to 1981 December 22
+                let cos_season = (TAU * (epoch.to_mjd_utc_days() - 44_960.0) / 365.25).cos();
+                let p2 = Dual::from_real(0.5)
+                    * (Dual::from_real(3.0) * sin_lat.powi(2) - Dual::from_real(1.0));
+                (
+                    Dual::from_real(0.34)
+                        + Dual::from_real(0.10 * cos_season) * sin_lat
+                        + Dual::from_real(0.29) * p2,
+                    Dual::from_real(0.68)
+                        - Dual::from_real(0.07 * cos_season) * sin_lat
+                        - Dual::from_real(0.18) * p2,
+                )
+            }
+        }
+    }
+}
+
+/// `PlanetaryRadiation` computes the radiation pressure of the sunlight reflected by the central body (albedo) and of its thermal
+/// infrared emission, following Knocke et al. (1988).
+///
+/// The cap of the central body visible from the spacecraft is split into a central element surrounded by rings of elements.
+/// Each element is a Lambertian source and the spacecraft is a sphere using the area and the coefficient of reflectivity of its `SrpConfig`.
+/// The force is proportional to the `scale_factor`: to estimate it, propagate a `RadiationSpacecraft` with the `RadiationDynamics` instead.
+#[derive(Clone)]
+pub struct PlanetaryRadiation {
+    /// Albedo and emissivity of the surface
+    pub coefficients: RadiationCoefficients,
+    /// Body fixed frame of the central body, whose pole defines the latitude of the coefficients
+    pub body_frame: Frame,
+    /// Solar flux at 1 AU, in W/m^2
+    pub phi: f64,
+    /// Number of rings of elements around the central element, the n-th ring having 6n elements
+    pub rings: usize,
+    /// Scale factor of the force, e.g. to be estimated
+    pub scale_factor: f64,
+    /// A Cosm reference is needed for the position of the Sun and the orientation of the central body
+    pub cosm: Arc<Cosm>,
+}
+
+impl PlanetaryRadiation {
+    /// Earth albedo and infrared radiation pressure using the Knocke coefficients
+    pub fn earth(cosm: Arc<Cosm>) -> Arc<Self> {
+        Arc::new(Self {
+            coefficients: RadiationCoefficients::Knocke,
+            body_frame: cosm.frame("IAU Earth"),
+            phi: 1367.0,
+            rings: 5,
+            scale_factor: 1.0,
+            cosm,
+        })
+    }
+
+    /// Computes the force with hyperdual numbers for the provided scale factor, so that the partials with respect to the position are available.
+    fn dual_force(
+        &self,
+        ctx: &Spacecraft,
+        scale_factor: f64,
+    ) -> Result<Vector3<Dual>, DynamicsError> {
+        let osc = &ctx.orbit;
+        let unavailable = |e| DynamicsError::DataUnavailable {
+            msg: format!("{e}"),
+        };
+
+        let r_sun = self
+            .cosm
+            .try_celestial_state(
+                Bodies::Sun.ephem_path(),
+                osc.epoch,
+                osc.frame,
+                LightTimeCalc::None,
+            )
+            .map_err(unavailable)?
+            .radius();
+        let sun_unit = r_sun / r_sun.norm();
+        // Solar flux at the central body, in W/m^2
+        let solar_flux = self.phi / (r_sun.norm() / AU).powi(2);
+        let pole = self
+            .cosm
+            .try_position_dcm_from_to(&self.body_frame, &osc.frame, osc.epoch)
+            .map_err(unavailable)?
+            .column(2)
+            .into_owned();
+
+        let dual = Dual::from_real;
+        let dot = |a: &Vector3<Dual>, b: &Vector3<Dual>| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+        let to_dual = |v: Vector3<f64>| Vector3::new(dual(v[0]), dual(v[1]), dual(v[2]));
+
+        let radius: Vector3<Dual> = hyperspace_from_vector(&osc.radius());
+        let rmag = norm(&radius);
+        let body_radius = osc.frame.equatorial_radius();
+        if rmag.real() <= body_radius {
+            return Ok(Vector3::from_element(dual(0.0)));
+        }
+
+        // Basis centered on the sub-satellite point
+        let nadir = radius / rmag;
+        let reference = if pole.cross(&nadir.map(|x| x.real())).norm() > 1e-6 {
+            to_dual(pole)
+        } else {
+            to_dual(Vector3::x())
+        };
+        let east = Vector3::new(
+            reference[1] * nadir[2] - reference[2] * nadir[1],
+            reference[2] * nadir[0] - reference[0] * nadir[2],
+            reference[0] * nadir[1] - reference[1] * nadir[0],
+        );
+        let east = east / norm(&east);
+        let north = Vector3::new(
+            nadir[1] * east[2] - nadir[2] * east[1],
+            nadir[2] * east[0] - nadir[0] * east[2],
+            nadir[0] * east[1] - nadir[1] * east[0],
+        );
+
+        // The visible cap is split into elements of equal solid angle as seen from the spacecraft: a central element and rings of 6n
+        // elements. This returns the central angle of the body of the point seen at the middle of the provided number of elements.
+        let sin_max_nadir = dual(body_radius) / rmag;
+        let max_solid_angle = dual(1.0) - (dual(1.0) - sin_max_nadir.powi(2)).sqrt();
+        let total = (1 + 3 * self.rings * (self.rings + 1)) as f64;
+        let central_angle = |enclosed: f64| {
+            let cos_nadir = dual(1.0) - max_solid_angle * dual(enclosed / total);
+            let sin_nadir = (dual(1.0) - cos_nadir.powi(2)).sqrt();
+            (rmag / dual(body_radius) * sin_nadir).asin() - sin_nadir.asin()
+        };
+        // Solid angle of each element, in steradians
+        let element_solid_angle = dual(TAU / total) * max_solid_angle;
+
+        let mut elements = vec![(dual(0.0), dual(0.0))];
+        for ring in 1..=self.rings {
+            let count = 6 * ring;
+            let center = central_angle((1 + 3 * ring * (ring - 1)) as f64 + 0.5 * count as f64);
+            for segment in 0..count {
+                let azimuth = dual(TAU * (segment as f64 + 0.5) / count as f64);
+                elements.push((center, azimuth));
+            }
+        }
+
+        let sun_unit = to_dual(sun_unit);
+        let mut force = Vector3::from_element(dual(0.0));
+        for (angle, azimuth) in elements {
+            let normal =
+                nadir * angle.cos() + (east * azimuth.cos() + north * azimuth.sin()) * angle.sin();
+            let to_sc = radius - normal * dual(body_radius);
+            let to_sc_unit = to_sc / norm(&to_sc);
+
+            let sin_lat = dot(&normal, &to_dual(pole));
+            let (albedo, emissivity) = self.coefficients.dual_at(sin_lat, osc.epoch);
+            let cos_sun = dot(&normal, &sun_unit);
+            // Radiant exitance of the element in W/m^2
+            let mut exitance = emissivity * dual(solar_flux / 4.0);
+            if cos_sun.real() > 0.0 {
+                exitance += albedo * dual(solar_flux) * cos_sun;
+            }
+            // Irradiance at the spacecraft in W/m^2, from the radiance of a Lambertian surface
+            force += to_sc_unit * (exitance / dual(PI) * element_solid_angle);
+        }
+
+        // Note the 1e-3 converts the force from N to kN, like the solar radiation pressure
+        let scale = dual(1e-3 * scale_factor * ctx.srp.cr * ctx.srp.area_m2 / SPEED_OF_LIGHT);
+        Ok(force * scale)
+    }
+
+    /// Returns the force and its partials with respect to the position and velocity for the provided scale factor
+    fn force_partials(
+        &self,
+        ctx: &Spacecraft,
+        scale_factor: f64,
+    ) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError> {
+        let (force, grad) =
+            extract_jacobian_and_result::<_, 3, 3, 7>(&self.dual_force(ctx, scale_factor)?);
+        let mut partials = Matrix3x6::zeros();
+        // The radiation pressure does not depend on the velocity
+        partials.fixed_view_mut::<3, 3>(0, 0).copy_from(&grad);
+        Ok((force, partials))
+    }
+}
+
+impl fmt::Display for PlanetaryRadiation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Albedo and IR radiation of {} with {:?} coefficients ({} rings, scale factor {})",
+            self.body_frame, self.coefficients, self.rings, self.scale_factor
+        )
+    }
+}
+
+impl ForceModel for PlanetaryRadiation {
+    fn eom(&self, ctx: &Spacecraft) -> Result<Vector3<f64>, DynamicsError> {
+        Ok(self.dual_force(ctx, self.scale_factor)?.map(|x| x.real()))
+    }
+
+    fn dual_eom(&self, ctx: &Spacecraft) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError> {
+        self.force_partials(ctx, self.scale_factor)
+    }
+}
+
+/// `RadiationDynamics` propagates a spacecraft with the planetary radiation pressure, whose scale factor is part of the state so that it can be estimated.
+///
+/// The scale factor of the state replaces the `scale_factor` of the radiation model, which must not also be one of the force models of the spacecraft dynamics.
+/// The STM includes the partials of the orbit with respect to the scale factor, which is constant.
+/// Guidance laws are not supported since the fuel mass is not part of the state.
+#[derive(Clone)]
+pub struct RadiationDynamics {
+    pub sc_dyn: SpacecraftDynamics,
+    pub radiation: Arc<PlanetaryRadiation>,
+}
+
+impl RadiationDynamics {
+    /// Initializes the estimated planetary radiation pressure on top of the provided spacecraft dynamics
+    pub fn new(sc_dyn: SpacecraftDynamics, radiation: Arc<PlanetaryRadiation>) -> Self {
+        Self { sc_dyn, radiation }
+    }
+}
+
+impl fmt::Display for RadiationDynamics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} with estimated {}", self.sc_dyn, self.radiation)
+    }
+}
+
+impl Dynamics for RadiationDynamics {
+    type HyperdualSize = Const<8>;
+    type StateType = RadiationSpacecraft;
+
+    fn eom(
+        &self,
+        delta_t_s: f64,
+        state: &OVector<f64, Const<56>>,
+        ctx: &RadiationSpacecraft,
+    ) -> Result<OVector<f64, Const<56>>, DynamicsError> {
+        let osc = ctx.set_with_delta_seconds(delta_t_s, state);
+        let mut d_x = OVector::<f64, Const<56>>::zeros();
+
+        if ctx.stm.is_some() {
+            let (state, grad) = self.dual_eom(delta_t_s, &osc)?;
+
+            // Apply the gradient to the osculating STM, which is valid over the whole propagation and not only over a step
+            let stm_dt = grad * osc.stm()?;
+
+            for (i, val) in state.iter().enumerate() {
+                d_x[i] = *val;
+            }
+
+            for (i, val) in stm_dt.iter().enumerate() {
+                d_x[i + <RadiationSpacecraft as State>::Size::dim()] = *val;
+            }
+        } else {
+            // The osculating spacecraft does not have an STM, so only the first six components are used
+            let d_sc = self.sc_dyn.eom(0.0, &osc.sc.as_vector(), &osc.sc)?;
+            for i in 0..6 {
+                d_x[i] = d_sc[i];
+            }
+
+            let force = self.radiation.eom(&osc.sc)?;
+            for i in 0..3 {
+                d_x[i + 3] += osc.radiation_scale * force[i] / osc.sc.mass_kg();
+            }
+        }
+
+        Ok(d_x)
+    }
+
+    fn dual_eom(
+        &self,
+        delta_t_s: f64,
+        osc: &RadiationSpacecraft,
+    ) -> Result<(OVector<f64, Const<7>>, OMatrix<f64, Const<7>, Const<7>>), DynamicsError> {
+        let mut d_x = OVector::<f64, Const<7>>::zeros();
+        let mut grad = OMatrix::<f64, Const<7>, Const<7>>::zeros();
+
+        let (sc_state, sc_grad) = self.sc_dyn.dual_eom(delta_t_s, &osc.sc)?;
+
+        for i in 0..6 {
+            d_x[i] = sc_state[i];
+            for j in 0..6 {
+                grad[(i, j)] = sc_grad[(i, j)];
+            }
+        }
+
+        // The force is linear in the scale factor, so its partial is the force of a unit scale factor
+        let (force, partials) = self.radiation.force_partials(&osc.sc, 1.0)?;
+        let mass_kg = osc.sc.mass_kg();
+        for i in 0..3 {
+            d_x[i + 3] += osc.radiation_scale * force[i] / mass_kg;
+            for j in 0..6 {
+                grad[(i + 3, j)] += osc.radiation_scale * partials[(i, j)] / mass_kg;
+            }
+            grad[(i + 3, 6)] = force[i] / mass_kg;
+        }
+
+        Ok((d_x, grad))
+    }
+}
+
+#[cfg(test)]
+mod ut_albedo {
+    use super::*;
+    use crate::cosmic::Orbit;
+    use crate::time::{TimeSeries, Unit};
+
+    #[test]
+    fn test_planetary_radiation() {
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let eme2k = cosm.frame("EME2000");
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+
+        // Knocke coefficients at the equator
+        let (albedo, emissivity) = RadiationCoefficients::Knocke.at(0.0, epoch);
+        assert!((albedo - 0.195).abs() < 1e-12);
+        assert!((emissivity - 0.77).abs() < 1e-12);
+
+        // A uniform infrared emission is radial, with the irradiance of a point source: M (R / r)^2
+        let ir_only = PlanetaryRadiation {
+            coefficients: RadiationCoefficients::Constant {
+                albedo: 0.0,
+                emissivity: 1.0,
+            },
+            body_frame: cosm.frame("IAU Earth"),
+            phi: 1367.0,
+            rings: 5,
+            scale_factor: 1.0,
+            cosm: cosm.clone(),
+        };
+        // The Sun is in the -X direction in this Cosm, so this orbit starts on the day side
+        let orbit = Orbit::keplerian(7_000.0, 0.0, 30.0, 0.0, 0.0, 180.0, epoch, eme2k);
+        let sc = Spacecraft::from_srp_defaults(orbit, 500.0, 10.0);
+        let force = ir_only.eom(&sc).unwrap();
+        let r_sun_au = cosm
+            .celestial_state(Bodies::Sun.ephem_path(), epoch, eme2k, LightTimeCalc::None)
+            .rmag_km()
+            / AU;
+        let exitance = 1367.0 / r_sun_au.powi(2) / 4.0;
+        let expected = 1e-3 * sc.srp.cr * sc.srp.area_m2 * exitance / SPEED_OF_LIGHT
+            * (eme2k.equatorial_radius() / orbit.rmag_km()).powi(2);
+        assert!((force.norm() / expected - 1.0).abs() < 1e-3);
+        assert!(force.dot(&orbit.radius()) / (force.norm() * orbit.rmag_km()) > 0.999_999);
+
+        // The albedo vanishes on the night side, and the scale factor scales the force
+        let albedo_only = PlanetaryRadiation {
+            coefficients: RadiationCoefficients::Constant {
+                albedo: 0.3,
+                emissivity: 0.0,
+            },
+            scale_factor: 2.0,
+            ..ir_only
+        };
+        let day = albedo_only.eom(&sc).unwrap();
+        // About 2 x 0.3 x 4 times the infrared near the subsolar point
+        assert!(day.norm() > 2.0 * expected);
+        let night = Orbit::keplerian(7_000.0, 0.0, 30.0, 0.0, 0.0, 0.0, epoch, eme2k);
+        assert!(albedo_only.eom(&sc.with_orbit(night)).unwrap().norm() < 1e-3 * day.norm());
+
+        // The partials match the finite differences of the force
+        let earth = PlanetaryRadiation::earth(cosm);
+        let inclined = Orbit::keplerian(7_000.0, 0.01, 51.6, 20.0, 30.0, 140.0, epoch, eme2k);
+        let sc = sc.with_orbit(inclined);
+        let (force, partials) = earth.dual_eom(&sc).unwrap();
+        assert!((force - earth.eom(&sc).unwrap()).norm() < f64::EPSILON);
+        for j in 0..3 {
+            let perturbed = |delta: f64| {
+                let mut state = inclined.to_cartesian_vec();
+                state[j] += delta;
+                earth
+                    .eom(&sc.with_orbit(Orbit::cartesian_vec(&state, epoch, eme2k)))
+                    .unwrap()
+            };
+            let fd = (perturbed(1e-2) - perturbed(-1e-2)) / 2e-2;
+            let err = (fd - partials.column(j)).norm() / partials.column(j).norm();
+            assert!(err < 1e-3, "column {j} relative error {err:e}");
+        }
+        assert_eq!(partials.fixed_view::<3, 3>(0, 3).norm(), 0.0);
+    }
+
+    #[test]
+    fn test_radiation_scale_stm() {
+        use crate::dynamics::OrbitalDynamics;
+        use crate::propagators::Propagator;
+
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let eme2k = cosm.frame("EME2000");
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        let orbit = Orbit::keplerian(7_000.0, 0.01, 51.6, 20.0, 30.0, 140.0, epoch, eme2k);
+        let start =
+            RadiationSpacecraft::new(Spacecraft::from_srp_defaults(orbit, 100.0, 10.0), 1.2);
+        let duration = Unit::Minute * 30;
+
+        let mut radiation = PlanetaryRadiation::earth(cosm).as_ref().clone();
+        radiation.rings = 2;
+        let dynamics = RadiationDynamics::new(
+            SpacecraftDynamics::new(OrbitalDynamics::two_body()),
+            Arc::new(radiation),
+        );
+
+        // Both propagations match
+        let end = Propagator::default(dynamics.clone())
+            .with(start)
+            .for_duration(duration)
+            .unwrap();
+        let end_stm = Propagator::default(dynamics.clone())
+            .with(start.with_stm())
+            .for_duration(duration)
+            .unwrap();
+        assert!((end.sc.orbit.radius() - end_stm.sc.orbit.radius()).norm() < 1e-6);
+        assert_eq!(end_stm.radiation_scale, 1.2);
+
+        // The column of the STM for the scale factor matches the finite differences
+        let stm = end_stm.stm().unwrap();
+        let perturbed = |delta: f64| {
+            let mut state = start;
+            state.radiation_scale += delta;
+            Propagator::default(dynamics.clone())
+                .with(state)
+                .for_duration(duration)
+                .unwrap()
+                .sc
+                .orbit
+                .to_cartesian_vec()
+        };
+        let column = (perturbed(0.1) - perturbed(-0.1)) / 0.2;
+        assert!(column.fixed_rows::<3>(0).norm() > 1e-4);
+        for i in 0..6 {
+            assert!(
+                (stm[(i, 6)] - column[i]).abs() < 1e-3 * column.norm(),
+                "STM ({i}, 6): {} != {}",
+                stm[(i, 6)],
+                column[i]
+            );
+        }
+
+        // The scale factor can be read and set by name
+        let mut state = end;
+        state
+            .set_value(crate::md::StateParameter::RadiationScale, 0.9)
+            .unwrap();
+        assert_eq!(
+            state
+                .value(crate::md::StateParameter::RadiationScale)
+                .unwrap(),
+            0.9
+        );
+    }
+
+    #[test]
+    fn test_radiation_scale_od() {
+        use crate::dynamics::OrbitalDynamics;
+        use crate::linalg::{Matrix2, OMatrix, Vector2};
+        use crate::od::prelude::*;
+        use crate::propagators::Propagator;
+        use std::collections::BTreeMap;
+
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let eme2k = cosm.frame("EME2000");
+        let iau_earth = cosm.frame("IAU Earth");
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        let orbit = Orbit::keplerian(7_000.0, 0.01, 51.6, 20.0, 30.0, 140.0, epoch, eme2k);
+        let sc = Spacecraft::from_srp_defaults(orbit, 100.0, 10.0);
+        let duration = Unit::Hour * 3;
+
+        let mut radiation = PlanetaryRadiation::earth(cosm.clone()).as_ref().clone();
+        radiation.rings = 2;
+        let dynamics = RadiationDynamics::new(
+            SpacecraftDynamics::new(OrbitalDynamics::two_body()),
+            Arc::new(radiation),
+        );
+
+        // Noiseless range and Doppler every minute from two stations which always see the spacecraft, from the propagated truth
+        let mut devices = BTreeMap::new();
+        for (name, latitude_deg, longitude_deg) in [("north", 40.0, -4.0), ("south", -35.0, 149.0)]
+        {
+            let mut station = GroundStation::from_point(
+                name.to_string(),
+                latitude_deg,
+                longitude_deg,
+                0.0,
+                iau_earth,
+            );
+            station.elevation_mask_deg = -90.0;
+            devices.insert(name.to_string(), station);
+        }
+        let mut truth = RadiationSpacecraft::new(sc, 1.0);
+        let mut measurements = Vec::new();
+        for epoch in
+            TimeSeries::inclusive(epoch + Unit::Minute * 1, epoch + duration, Unit::Minute * 1)
+        {
+            truth = Propagator::default(dynamics.clone())
+                .with(truth)
+                .until_epoch(epoch)
+                .unwrap();
+            for (name, station) in devices.iter_mut() {
+                let msr = station
+                    .measure_instantaneous(truth, None, cosm.clone())
+                    .unwrap()
+                    .unwrap();
+                measurements.push((name.clone(), msr));
+            }
+        }
+
+        // The filter starts with the correct orbit and a scale factor off by 30 %
+        let initial_state = RadiationSpacecraft::new(sc, 1.3).with_stm();
+        let covar = OMatrix::<f64, Const<7>, Const<7>>::from_diagonal(
+            &OVector::<f64, Const<7>>::from_column_slice(&[
+                1e-6,
+                1e-6,
+                1e-6,
+                1e-12,
+                1e-12,
+                1e-12,
+                0.5_f64.powi(2),
+            ]),
+        );
+        let kf = KF::no_snc(
+            KfEstimate::from_covar(initial_state, covar),
+            Matrix2::from_diagonal(&Vector2::new(1e-5_f64.powi(2), 1e-8_f64.powi(2))),
+        );
+        let setup = Propagator::default(dynamics);
+        let prop_est = setup.with(initial_state);
+        let mut odp = ODProcess::ckf(prop_est, kf, None, cosm);
+        odp.process::<GroundStation>(&measurements, &mut devices, Unit::Minute * 1)
+            .unwrap();
+
+        let est = odp.estimates.last().unwrap();
+        let scale = est.state().radiation_scale;
+        let sigma = est.covar[(6, 6)].sqrt();
+        println!("estimated scale factor {scale} ± {sigma:e}");
+        assert!(sigma < 0.01, "scale factor not observable: σ = {sigma:e}");
+        assert!((scale - 1.0).abs() < 1e-4, "scale factor {scale}");
+    }
+}
@@ -0,0 +1,301 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::spacecraft::SpacecraftDynamics;
+use super::{Dynamics, DynamicsError, TorqueModel};
+use crate::cosmic::RigidSpacecraft;
+use crate::linalg::{Const, OMatrix, OVector, Vector3};
+use crate::State;
+use std::fmt::{self, Write};
+use std::sync::Arc;
+
+/// `AttitudeDynamics` propagates the rigid body attitude of a `RigidSpacecraft` alongside its spacecraft dynamics.
+///
+/// The attitude follows Euler's equations under the torque models, orients the surface models of its force models,
+/// and is provided to the guidance law through `GuidanceLaw::direction_in_attitude`.
+#[derive(Clone)]
+pub struct AttitudeDynamics {
+    pub sc_dyn: SpacecraftDynamics,
+    pub torque_models: Vec<Arc<dyn TorqueModel>>,
+}
+
+impl AttitudeDynamics {
+    /// Initializes the attitude dynamics on top of the provided spacecraft dynamics, without any torque
+    pub fn new(sc_dyn: SpacecraftDynamics) -> Self {
+        Self {
+            sc_dyn,
+            torque_models: Vec::new(),
+        }
+    }
+
+    /// Add a torque model to the currently defined attitude dynamics
+    pub fn add_torque_model(&mut self, torque_model: Arc<dyn TorqueModel>) {
+        self.torque_models.push(torque_model);
+    }
+
+    /// Clone these dynamics and add a torque model
+    pub fn with_torque_model(self, torque_model: Arc<dyn TorqueModel>) -> Self {
+        let mut me = self;
+        me.add_torque_model(torque_model);
+        me
+    }
+}
+
+impl fmt::Display for AttitudeDynamics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let torque_models = self
+            .torque_models
+            .iter()
+            .fold(String::new(), |mut output, x| {
+                let _ = write!(output, "{x}; ");
+                output
+            });
+        write!(f, "Attitude dynamics: {}{}", torque_models, self.sc_dyn)
+    }
+}
+
+impl Dynamics for AttitudeDynamics {
+    type HyperdualSize = Const<9>;
+    type StateType = RigidSpacecraft;
+
+    fn finally(&self, next_state: Self::StateType) -> Result<Self::StateType, DynamicsError> {
+        let mut me = next_state;
+        me.sc = self.sc_dyn.finally(next_state.sc)?;
+        Ok(me)
+    }
+
+    fn eom(
+        &self,
+        delta_t: f64,
+        state: &OVector<f64, Const<97>>,
+        ctx: &Self::StateType,
+    ) -> Result<OVector<f64, Const<97>>, DynamicsError> {
+        let osc = ctx.set_with_delta_seconds(delta_t, state);
+        let mut d_x = OVector::<f64, Const<97>>::zeros();
+
+        let d_sc = self.sc_dyn.eom_in_attitude(
+            delta_t,
+            &state.fixed_rows::<90>(0).into_owned(),
+            &ctx.sc,
+            Some(&osc.attitude),
+        )?;
+        d_x.fixed_rows_mut::<90>(0).copy_from(&d_sc);
+
+        let mut torque = Vector3::zeros();
+        for model in &self.torque_models {
+            torque += model.torque(&osc)?;
+        }
+        let (q_dot, omega_dot) = osc.attitude.derivatives(&torque);
+        d_x.fixed_rows_mut::<4>(90).copy_from(&q_dot);
+        d_x.fixed_rows_mut::<3>(94).copy_from(&omega_dot);
+
+        Ok(d_x)
+    }
+
+    fn dual_eom(
+        &self,
+        delta_t_s: f64,
+        osc: &Self::StateType,
+    ) -> Result<(OVector<f64, Const<9>>, OMatrix<f64, Const<9>, Const<9>>), DynamicsError> {
+        self.sc_dyn.dual_eom(delta_t_s, &osc.oriented())
+    }
+}
+
+#[cfg(test)]
+mod ut_attitude_dynamics {
+    use super::*;
+    use crate::cosmic::{Attitude, AttitudeLaw, Cosm, GuidanceMode, Orbit, Spacecraft};
+    use crate::dynamics::guidance::{GuidanceLaw, Thruster};
+    use crate::dynamics::{GravityGradient, OrbitalDynamics, PointingController};
+    use crate::errors::NyxError;
+    use crate::linalg::Matrix3;
+    use crate::na::UnitQuaternion;
+    use crate::propagators::{PropOpts, Propagator, RK89};
+    use crate::time::{Epoch, Unit};
+
+    fn leo(cosm: &Cosm) -> Orbit {
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        Orbit::keplerian(
+            7_000.0,
+            0.0,
+            30.0,
+            0.0,
+            0.0,
+            180.0,
+            epoch,
+            cosm.frame("EME2000"),
+        )
+    }
+
+    #[test]
+    fn test_torque_free() {
+        let cosm = Cosm::fixed_planets();
+        let inertia = Matrix3::from_diagonal(&Vector3::new(10.0, 20.0, 30.0));
+        let attitude = Attitude::new(
+            UnitQuaternion::identity(),
+            Vector3::new(0.01, 0.1, 0.01),
+            inertia,
+        )
+        .unwrap();
+        let state = RigidSpacecraft::new(
+            Spacecraft::from_srp_defaults(leo(&cosm), 100.0, 0.0),
+            attitude,
+        );
+
+        let prop = Propagator::new::<RK89>(
+            AttitudeDynamics::new(SpacecraftDynamics::new(OrbitalDynamics::two_body())),
+            PropOpts::with_fixed_step_s(1.0),
+        );
+        let (end, traj) = prop
+            .with(state)
+            .for_duration_with_traj(10.0 * Unit::Minute)
+            .unwrap();
+        let end_attitude = end.attitude;
+
+        // The rotational kinetic energy and the inertial angular momentum are conserved
+        assert!(
+            (end_attitude.kinetic_energy_j() / attitude.kinetic_energy_j() - 1.0).abs() < 1e-10
+        );
+        assert!(
+            (end_attitude.angular_momentum() - attitude.angular_momentum()).norm()
+                < 1e-10 * attitude.angular_momentum().norm()
+        );
+        // And the body has rotated
+        assert!(end_attitude.body_to_frame().angle() > 0.1);
+
+        // The attitude is interpolated between the steps of the trajectory
+        let mid_epoch = state.epoch() + 5.0 * Unit::Minute + 0.5 * Unit::Second;
+        let mid = prop.with(state).until_epoch(mid_epoch).unwrap();
+        let interp = traj.at(mid_epoch).unwrap();
+        let error_rad =
+            (mid.attitude.body_to_frame().inverse() * interp.attitude.body_to_frame()).angle();
+        assert!(error_rad < 1e-6, "{error_rad}");
+        assert!((mid.attitude.omega() - interp.attitude.omega()).norm() < 1e-6);
+    }
+
+    /// Thrusts along the body X axis of the propagated attitude, or along the velocity without one
+    struct BodyAxisThrust;
+
+    impl fmt::Display for BodyAxisThrust {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "thrust along body X")
+        }
+    }
+
+    impl GuidanceLaw for BodyAxisThrust {
+        fn direction(&self, osc_state: &Spacecraft) -> Vector3<f64> {
+            osc_state.orbit.velocity() / osc_state.orbit.vmag_km_s()
+        }
+
+        fn direction_in_attitude(
+            &self,
+            _osc_state: &Spacecraft,
+            attitude: &Attitude,
+        ) -> Vector3<f64> {
+            attitude.to_frame(&Vector3::x())
+        }
+
+        fn throttle(&self, _osc_state: &Spacecraft) -> f64 {
+            1.0
+        }
+
+        fn next(&self, _next_state: &mut Spacecraft) {}
+
+        fn achieved(&self, _osc_state: &Spacecraft) -> Result<bool, NyxError> {
+            Ok(false)
+        }
+    }
+
+    #[test]
+    fn test_pointing_control() {
+        let cosm = Cosm::fixed_planets();
+        let orbit = leo(&cosm);
+        let inertia = Matrix3::from_diagonal(&Vector3::new(10.0, 20.0, 30.0));
+        let nadir = AttitudeLaw::NadirPointing.dcm_to_frame(&orbit);
+        // Start ten degrees away from the nadir pointing attitude, without any rotation
+        let offset = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), 10f64.to_radians());
+        let attitude = Attitude::from_dcm(
+            nadir * offset.to_rotation_matrix().into_inner(),
+            Vector3::zeros(),
+            inertia,
+        )
+        .unwrap();
+        let sc = Spacecraft::from_thruster(
+            orbit,
+            100.0,
+            10.0,
+            Thruster {
+                thrust_N: 1.0,
+                isp_s: 300.0,
+            },
+            GuidanceMode::Thrust,
+        );
+
+        let controller = PointingController {
+            target: AttitudeLaw::NadirPointing,
+            kp_nm_rad: 0.5,
+            kd_nms_rad: 5.0,
+            max_torque_nm: 0.1,
+        };
+        assert!(format!("{controller}").contains("NadirPointing"));
+        let dynamics = AttitudeDynamics::new(SpacecraftDynamics::from_guidance_law(
+            OrbitalDynamics::two_body(),
+            Arc::new(BodyAxisThrust),
+        ))
+        .with_torque_model(Arc::new(controller))
+        .with_torque_model(Arc::new(GravityGradient));
+
+        let prop = Propagator::new::<RK89>(dynamics, PropOpts::with_fixed_step_s(1.0));
+        let start = RigidSpacecraft::new(sc, attitude);
+        let end = prop.with(start).for_duration(15.0 * Unit::Minute).unwrap();
+
+        // The controller has converged on the nadir pointing attitude
+        let target =
+            UnitQuaternion::from_matrix(&AttitudeLaw::NadirPointing.dcm_to_frame(&end.sc.orbit));
+        let error_deg = (target.inverse() * end.attitude.body_to_frame())
+            .angle()
+            .to_degrees();
+        assert!(error_deg < 0.1, "{error_deg}");
+
+        // The thrust follows the body X axis, i.e. close to the velocity for a nadir pointing spacecraft on a circular orbit
+        assert!(end.sc.fuel_mass_kg < sc.fuel_mass_kg);
+        assert!(end.sc.orbit.sma_km() > orbit.sma_km());
+
+        // The guidance law reads the attitude: with the body X axis along the orbit normal, the thrust no longer raises the orbit
+        let normal = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), -90f64.to_radians());
+        let tilted = RigidSpacecraft::new(
+            sc,
+            Attitude::from_dcm(
+                nadir * normal.to_rotation_matrix().into_inner(),
+                Vector3::zeros(),
+                inertia,
+            )
+            .unwrap(),
+        );
+        let coasting = AttitudeDynamics::new(SpacecraftDynamics::from_guidance_law(
+            OrbitalDynamics::two_body(),
+            Arc::new(BodyAxisThrust),
+        ));
+        let end = Propagator::new::<RK89>(coasting, PropOpts::with_fixed_step_s(1.0))
+            .with(tilted)
+            .for_duration(1.0 * Unit::Minute)
+            .unwrap();
+        assert!((end.sc.orbit.sma_km() - orbit.sma_km()).abs() < 1e-3);
+        assert!((end.sc.orbit.inc_deg() - orbit.inc_deg()).abs() > 1e-3);
+    }
+}
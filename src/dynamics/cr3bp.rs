@@ -0,0 +1,235 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{Dynamics, DynamicsError};
+use crate::cosmic::{AstroError, Frame, Orbit};
+use crate::linalg::{Const, Matrix6, OVector, Vector6};
+use crate::State;
+use hyperdual::linalg::norm;
+use hyperdual::{hyperspace_from_vector, Float, OHyperdual};
+use std::fmt;
+
+/// `Cr3bpDynamics` provides the equations of motion of the circular restricted three body problem (CR3BP), and their variational equations.
+///
+/// The state must be defined in a `Frame::Synodic`, i.e. normalized in the rotating frame of the primaries. The equations of motion
+/// are expressed with respect to the normalized time of the CR3BP, and scaled by the characteristic time of the frame, such that the
+/// epochs of the propagated states remain in seconds.
+#[derive(Clone, Default)]
+pub struct Cr3bpDynamics {}
+
+impl Cr3bpDynamics {
+    /// Initializes the CR3BP dynamics, where the mass ratio is that of the frame of the propagated state
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Returns the mass ratio and the characteristic time of the synodic frame of this state
+    fn frame_constants(osc: &Orbit) -> Result<(f64, f64), DynamicsError> {
+        match osc.frame {
+            Frame::Synodic {
+                mass_ratio, time_s, ..
+            } => Ok((mass_ratio, time_s)),
+            _ => Err(DynamicsError::DynamicsAstro {
+                source: AstroError::NotSynodicFrame,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Cr3bpDynamics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CR3BP dynamics")
+    }
+}
+
+impl Dynamics for Cr3bpDynamics {
+    type HyperdualSize = Const<7>;
+    type StateType = Orbit;
+
+    fn eom(
+        &self,
+        delta_t_s: f64,
+        state: &OVector<f64, Const<42>>,
+        ctx: &Orbit,
+    ) -> Result<OVector<f64, Const<42>>, DynamicsError> {
+        let osc = ctx.set_with_delta_seconds(delta_t_s, state);
+        let (new_state, new_stm) = if ctx.stm.is_some() {
+            let (state, grad) = self.dual_eom(delta_t_s, &osc)?;
+
+            let stm_dt = grad * osc.stm()?;
+            // Rebuild the STM as a vector.
+            let stm_as_vec = OVector::<f64, Const<36>>::from_column_slice(stm_dt.as_slice());
+            (state, stm_as_vec)
+        } else {
+            let (mu, time_s) = Self::frame_constants(&osc)?;
+            let (x, y, z) = (osc.x_km, osc.y_km, osc.z_km);
+            let r1_3 = ((x + mu).powi(2) + y.powi(2) + z.powi(2)).powf(1.5);
+            let r2_3 = ((x - 1.0 + mu).powi(2) + y.powi(2) + z.powi(2)).powf(1.5);
+
+            let d_x = Vector6::new(
+                osc.vx_km_s,
+                osc.vy_km_s,
+                osc.vz_km_s,
+                2.0 * osc.vy_km_s + x - (1.0 - mu) * (x + mu) / r1_3 - mu * (x - 1.0 + mu) / r2_3,
+                -2.0 * osc.vx_km_s + y - (1.0 - mu) * y / r1_3 - mu * y / r2_3,
+                -(1.0 - mu) * z / r1_3 - mu * z / r2_3,
+            );
+
+            // Still return something of size 42, but the STM will be zeros.
+            (d_x / time_s, OVector::<f64, Const<36>>::zeros())
+        };
+        Ok(OVector::<f64, Const<42>>::from_iterator(
+            new_state.iter().chain(new_stm.iter()).cloned(),
+        ))
+    }
+
+    fn dual_eom(
+        &self,
+        _delta_t_s: f64,
+        osc: &Orbit,
+    ) -> Result<(Vector6<f64>, Matrix6<f64>), DynamicsError> {
+        let (mu, time_s) = Self::frame_constants(osc)?;
+
+        // Build full state vector with partials in the right position (hence building with all six components)
+        let state: Vector6<OHyperdual<f64, Const<7>>> =
+            hyperspace_from_vector(&osc.to_cartesian_vec());
+
+        let radius = state.fixed_rows::<3>(0).into_owned();
+        let velocity = state.fixed_rows::<3>(3).into_owned();
+
+        let one = OHyperdual::<f64, Const<7>>::from_real(1.0);
+        let two = OHyperdual::<f64, Const<7>>::from_real(2.0);
+        let mu_d = OHyperdual::<f64, Const<7>>::from_real(mu);
+
+        // Position of the spacecraft with respect to the primary and to the secondary
+        let mut r1 = radius;
+        r1[0] += mu_d;
+        let mut r2 = radius;
+        r2[0] += mu_d - one;
+        let r1_3 = norm(&r1).powi(3);
+        let r2_3 = norm(&r2).powi(3);
+
+        let mut acceleration = -r1 * ((one - mu_d) / r1_3) - r2 * (mu_d / r2_3);
+        // Centrifugal and Coriolis accelerations
+        acceleration[0] += radius[0] + two * velocity[1];
+        acceleration[1] += radius[1] - two * velocity[0];
+
+        // Extract result into Vector6 and Matrix6, scaled from the normalized time to seconds
+        let mut dx = Vector6::zeros();
+        let mut grad = Matrix6::zeros();
+        for i in 0..6 {
+            let component = if i < 3 {
+                velocity[i]
+            } else {
+                acceleration[i - 3]
+            };
+            dx[i] = component.real() / time_s;
+            for j in 1..7 {
+                grad[(i, j - 1)] = component[j] / time_s;
+            }
+        }
+
+        Ok((dx, grad))
+    }
+}
+
+#[cfg(test)]
+mod ut_cr3bp_dynamics {
+    use super::*;
+    use crate::cosmic::Bodies;
+    use crate::propagators::Propagator;
+    use crate::time::{Epoch, Unit};
+
+    fn earth_moon() -> Frame {
+        Frame::Synodic {
+            primary: Bodies::Earth,
+            secondary: Bodies::Luna,
+            mass_ratio: 0.012_150_585_609_624,
+            length_km: 384_400.0,
+            time_s: 375_190.0,
+        }
+    }
+
+    #[test]
+    fn test_jacobi_conservation() {
+        let frame = earth_moon();
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2022, 1, 1);
+        // Near the southern L2 halo family
+        let start = Orbit::cartesian(1.18, 0.0, -0.02, 0.0, -0.158, 0.0, epoch, frame);
+
+        let end = Propagator::default(Cr3bpDynamics::new())
+            .with(start)
+            .for_duration(2.0 * frame_period(&frame) * Unit::Second)
+            .unwrap();
+
+        assert!((end.jacobi_constant() - start.jacobi_constant()).abs() < 1e-10);
+        assert_eq!(
+            end.epoch,
+            start.epoch + 2.0 * frame_period(&frame) * Unit::Second
+        );
+    }
+
+    #[test]
+    fn test_stm() {
+        let frame = earth_moon();
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2022, 1, 1);
+        let start = Orbit::cartesian(0.85, 0.02, 0.05, 0.01, 0.12, -0.01, epoch, frame);
+        let duration = 0.3 * frame_period(&frame) * Unit::Second;
+
+        let end = Propagator::default(Cr3bpDynamics::new())
+            .with(start.with_stm())
+            .for_duration(duration)
+            .unwrap();
+        let stm = end.stm().unwrap();
+
+        // Compare with central finite differences
+        let step = 1e-7;
+        for j in 0..6 {
+            let mut plus = start.to_cartesian_vec();
+            plus[j] += step;
+            let mut minus = start.to_cartesian_vec();
+            minus[j] -= step;
+            let end_plus = Propagator::default(Cr3bpDynamics::new())
+                .with(Orbit::cartesian_vec(&plus, epoch, frame))
+                .for_duration(duration)
+                .unwrap();
+            let end_minus = Propagator::default(Cr3bpDynamics::new())
+                .with(Orbit::cartesian_vec(&minus, epoch, frame))
+                .for_duration(duration)
+                .unwrap();
+            let column =
+                (end_plus.to_cartesian_vec() - end_minus.to_cartesian_vec()) / (2.0 * step);
+            for i in 0..6 {
+                assert!(
+                    (stm[(i, j)] - column[i]).abs() < 1e-4 * column.norm().max(1.0),
+                    "STM ({i}, {j}): {} != {}",
+                    stm[(i, j)],
+                    column[i]
+                );
+            }
+        }
+    }
+
+    /// Returns the period of the primaries in seconds
+    fn frame_period(frame: &Frame) -> f64 {
+        match frame {
+            Frame::Synodic { time_s, .. } => std::f64::consts::TAU * time_s,
+            _ => unreachable!(),
+        }
+    }
+}
@@ -17,8 +17,13 @@
 */
 
 use super::{DynamicsError, ForceModel};
-use crate::cosmic::{AstroError, Cosm, Frame, Spacecraft};
-use crate::linalg::{Matrix3, Vector3};
+use crate::cosmic::{Bodies, Cosm, Frame, LightTimeCalc, Orbit, Spacecraft, SurfaceModel};
+use crate::errors::NyxError;
+use crate::io::space_weather::{SolarIndices, SpaceWeather};
+use crate::linalg::{Matrix3, Matrix3x6, Matrix4, Vector3};
+use crate::na::Complex;
+use crate::time::Epoch;
+use std::f64::consts::{FRAC_PI_4, PI, TAU};
 use std::fmt;
 use std::sync::Arc;
 
@@ -26,25 +31,204 @@ use std::sync::Arc;
 #[derive(Clone, Copy, Debug)]
 pub enum AtmDensity {
     Constant(f64),
-    Exponential { rho0: f64, r0: f64, ref_alt_m: f64 },
-    StdAtm { max_alt_m: f64 },
+    /// Density decaying exponentially with the altitude `h` above the equatorial radius of the drag frame: `rho0 exp(-(h - r0) / ref_alt_m)`
+    Exponential {
+        /// Density at the reference altitude
+        rho0: f64,
+        /// Reference altitude in meters
+        r0: f64,
+        /// Scale height in meters
+        ref_alt_m: f64,
+    },
+    StdAtm {
+        max_alt_m: f64,
+    },
+    /// Jacchia-Roberts model of the Earth thermosphere, from 90 km to 2500 km, driven by the solar flux and geomagnetic indices.
+    /// The drag frame must be an Earth body fixed frame since the diurnal bulge depends on the position of the Sun, and the indices are
+    /// those of the space weather of the `Drag`.
+    JacchiaRoberts,
+}
+
+/// Winds of the atmosphere, i.e. the horizontal velocity of the atmosphere with respect to the atmosphere co-rotating with its body.
+///
+/// This is a hook for empirical wind models such as the Horizontal Wind Model.
+pub trait WindModel: Send + Sync + fmt::Display {
+    /// Returns the eastward and northward components of the wind in km/s, at the provided state expressed in the drag frame
+    fn horizontal_wind_km_s(&self, osc: &Orbit) -> Result<(f64, f64), DynamicsError>;
+}
+
+/// Velocity of the spacecraft relative to an atmosphere co-rotating with its body, computed in the inertial frame of that body.
+pub(crate) struct AtmosphereFlow {
+    /// State of the spacecraft in the drag frame, with its velocity relative to the atmosphere
+    pub(crate) osc_fixed: Orbit,
+    /// Position relative to the body in km
+    pub(crate) radius: Vector3<f64>,
+    /// Velocity relative to the atmosphere in km/s
+    pub(crate) velocity: Vector3<f64>,
+    /// Angular velocity of the atmosphere in rad/s
+    omega: Vector3<f64>,
+    /// Rotation from the drag frame to the inertial frame of the body
+    dcm_to_inertial: Matrix3<f64>,
+    /// Rotation from the inertial frame of the body to the integration frame
+    pub(crate) dcm_to_integr: Matrix3<f64>,
+}
+
+impl AtmosphereFlow {
+    /// Computes the velocity of the provided state relative to the atmosphere co-rotating with the body of the drag frame,
+    /// at the angular velocity of that body, and relative to the winds if any.
+    pub(crate) fn new(
+        cosm: &Cosm,
+        drag_frame: Frame,
+        wind: Option<&dyn WindModel>,
+        orbit: &Orbit,
+    ) -> Result<Self, DynamicsError> {
+        let unavailable = |e: NyxError| DynamicsError::DataUnavailable {
+            msg: format!("{e}"),
+        };
+
+        let inertial_frame = cosm.frame_from_ephem_path(&drag_frame.ephem_path());
+        let inertial = cosm
+            .try_frame_chg(orbit, inertial_frame)
+            .map_err(unavailable)?;
+        let dcm_to_inertial = cosm
+            .try_position_dcm_from_to(&drag_frame, &inertial_frame, orbit.epoch)
+            .map_err(unavailable)?;
+        let dcm_to_integr = cosm
+            .try_position_dcm_from_to(&inertial_frame, &orbit.frame, orbit.epoch)
+            .map_err(unavailable)?;
+
+        // The atmosphere rotates about the pole of the body fixed frame
+        let omega =
+            drag_frame.try_angular_velocity().map_err(unavailable)? * dcm_to_inertial.column(2);
+        let radius = inertial.radius();
+        let mut velocity = inertial.velocity() - omega.cross(&radius);
+
+        let fixed_radius = dcm_to_inertial.transpose() * radius;
+        let fixed_velocity = dcm_to_inertial.transpose() * velocity;
+        let mut osc_fixed = Orbit::cartesian(
+            fixed_radius[0],
+            fixed_radius[1],
+            fixed_radius[2],
+            fixed_velocity[0],
+            fixed_velocity[1],
+            fixed_velocity[2],
+            orbit.epoch,
+            drag_frame,
+        );
+
+        if let Some(wind) = wind {
+            let (east, north) = wind.horizontal_wind_km_s(&osc_fixed)?;
+            let (sin_lon, cos_lon) = osc_fixed.geodetic_longitude_deg().to_radians().sin_cos();
+            let (sin_lat, cos_lat) = osc_fixed.geodetic_latitude_deg().to_radians().sin_cos();
+            let wind_fixed = east * Vector3::new(-sin_lon, cos_lon, 0.0)
+                + north * Vector3::new(-sin_lat * cos_lon, -sin_lat * sin_lon, cos_lat);
+            velocity -= dcm_to_inertial * wind_fixed;
+            let fixed_velocity = fixed_velocity - wind_fixed;
+            osc_fixed.vx_km_s = fixed_velocity[0];
+            osc_fixed.vy_km_s = fixed_velocity[1];
+            osc_fixed.vz_km_s = fixed_velocity[2];
+        }
+
+        Ok(Self {
+            osc_fixed,
+            radius,
+            velocity,
+            omega,
+            dcm_to_inertial,
+            dcm_to_integr,
+        })
+    }
+
+    /// Returns the product of the drag coefficient and of the area of the spacecraft in m^2, which is the area projected along the flow if
+    /// a surface model is provided. The projected area is evaluated at the current state and considered constant in the partials.
+    fn cd_area_m2(
+        &self,
+        cosm: &Cosm,
+        surface: Option<&SurfaceModel>,
+        ctx: &Spacecraft,
+    ) -> Result<f64, DynamicsError> {
+        match surface {
+            Some(surface) => {
+                let sun_unit = if surface.has_sun_tracking() {
+                    let sun = cosm
+                        .try_frame_chg(&ctx.orbit, cosm.frame("Sun J2000"))
+                        .map_err(|e| DynamicsError::DataUnavailable {
+                            msg: format!("{e}"),
+                        })?;
+                    Some(-sun.radius() / sun.rmag_km())
+                } else {
+                    None
+                };
+                let flow = self.dcm_to_integr * self.velocity;
+                if flow.norm() > 0.0 {
+                    let flow_unit = flow / flow.norm();
+                    Ok(ctx.drag.cd * surface.projected_area_m2(ctx, &flow_unit, sun_unit.as_ref()))
+                } else {
+                    Ok(0.0)
+                }
+            }
+            None => Ok(ctx.drag.cd * ctx.drag.area_m2),
+        }
+    }
+
+    /// Returns the drag force in the integration frame, in kN (i.e. in kg km/s^2) as expected by the spacecraft dynamics.
+    fn force(&self, rho: f64, cd_area_m2: f64) -> Vector3<f64> {
+        // The factor 1e3 converts the velocity squared from km^2/s^2 to m^2/s^2 and the force from N to kN
+        -0.5e3 * rho * cd_area_m2 * self.velocity.norm() * (self.dcm_to_integr * self.velocity)
+    }
+
+    /// Returns the partials of the drag force in the integration frame with respect to the position and the velocity,
+    /// provided the gradient of the density with respect to the position (in kg/m^3/km) in the inertial frame of the body.
+    /// The winds are assumed to not vary with the position.
+    fn partials(&self, rho: f64, grad_rho: Vector3<f64>, cd_area_m2: f64) -> Matrix3x6<f64> {
+        let k = -0.5e3 * cd_area_m2;
+        let speed = self.velocity.norm();
+        let wrt_velocity = if speed > 0.0 {
+            k * rho
+                * (speed * Matrix3::identity() + self.velocity * self.velocity.transpose() / speed)
+        } else {
+            Matrix3::zeros()
+        };
+        // The relative velocity depends on the position through the rotation of the atmosphere
+        let wrt_position = -wrt_velocity * self.omega.cross_matrix()
+            + k * speed * self.velocity * grad_rho.transpose();
+
+        let dcm = self.dcm_to_integr;
+        let mut partials = Matrix3x6::zeros();
+        partials
+            .fixed_view_mut::<3, 3>(0, 0)
+            .copy_from(&(dcm * wrt_position * dcm.transpose()));
+        partials
+            .fixed_view_mut::<3, 3>(0, 3)
+            .copy_from(&(dcm * wrt_velocity * dcm.transpose()));
+        partials
+    }
 }
 
 /// `ConstantDrag` implements a constant drag model as defined in Vallado, 4th ed., page 551, with an important caveat.
 ///
-/// **WARNING:** This basic model assumes that the velocity of the spacecraft is identical to the velocity of the upper atmosphere,
-/// This is a **bad** assumption and **should not** be used for high fidelity simulations.
-/// This will be resolved after https://gitlab.com/chrisrabotin/nyx/issues/93 is implemented.
+/// The drag is computed from the velocity of the spacecraft relative to the atmosphere, which co-rotates with the body of the drag frame.
 #[derive(Clone)]
 pub struct ConstantDrag {
     /// atmospheric density in kg/m^3
     pub rho: f64,
     /// Geoid causing the drag
     pub drag_frame: Frame,
+    /// Optional surface model (e.g. box-wing), whose area projected along the flow replaces the drag area of the spacecraft
+    pub surface: Option<Arc<SurfaceModel>>,
     /// a Cosm reference is needed to convert to the state around the correct planet
     pub cosm: Arc<Cosm>,
 }
 
+impl ConstantDrag {
+    /// Returns a copy of this model which uses the area of the provided surface model projected along the flow
+    pub fn with_surface(&self, surface: Arc<SurfaceModel>) -> Arc<Self> {
+        let mut me = self.clone();
+        me.surface = Some(surface);
+        Arc::new(me)
+    }
+}
+
 impl fmt::Display for ConstantDrag {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -57,28 +241,39 @@ impl fmt::Display for ConstantDrag {
 
 impl ForceModel for ConstantDrag {
     fn eom(&self, ctx: &Spacecraft) -> Result<Vector3<f64>, DynamicsError> {
-        let osc = self.cosm.frame_chg(&ctx.orbit, self.drag_frame);
-        let velocity = osc.velocity();
-        Ok(-0.5 * self.rho * ctx.drag.cd * ctx.drag.area_m2 * velocity.norm() * velocity)
+        let flow = AtmosphereFlow::new(&self.cosm, self.drag_frame, None, &ctx.orbit)?;
+        Ok(flow.force(
+            self.rho,
+            flow.cd_area_m2(&self.cosm, self.surface.as_deref(), ctx)?,
+        ))
     }
 
-    fn dual_eom(
-        &self,
-        _osc_ctx: &Spacecraft,
-    ) -> Result<(Vector3<f64>, Matrix3<f64>), DynamicsError> {
-        Err(DynamicsError::DynamicsAstro {
-            source: AstroError::PartialsUndefined,
-        })
+    fn dual_eom(&self, ctx: &Spacecraft) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError> {
+        let flow = AtmosphereFlow::new(&self.cosm, self.drag_frame, None, &ctx.orbit)?;
+        let cd_area_m2 = flow.cd_area_m2(&self.cosm, self.surface.as_deref(), ctx)?;
+        Ok((
+            flow.force(self.rho, cd_area_m2),
+            flow.partials(self.rho, Vector3::zeros(), cd_area_m2),
+        ))
     }
 }
 
 /// `Drag` implements all three drag models.
+///
+/// The drag is computed from the velocity of the spacecraft relative to the atmosphere, which co-rotates with the body of the drag frame,
+/// and relative to the optional winds.
 #[derive(Clone)]
 pub struct Drag {
     /// Density computation method
     pub density: AtmDensity,
-    /// Frame to compute the drag in
+    /// Frame to compute the drag in, e.g. `IAU Earth` or the higher fidelity `Earth ITRF`
     pub drag_frame: Frame,
+    /// Optional winds of the atmosphere
+    pub wind: Option<Arc<dyn WindModel>>,
+    /// Solar flux and geomagnetic indices, required by the density models driven by the space weather (e.g. `JacchiaRoberts`)
+    pub space_weather: Option<Arc<SpaceWeather>>,
+    /// Optional surface model (e.g. box-wing), whose area projected along the flow replaces the drag area of the spacecraft
+    pub surface: Option<Arc<SurfaceModel>>,
     /// a Cosm reference is needed to convert to the state around the correct planet
     pub cosm: Arc<Cosm>,
 }
@@ -93,6 +288,42 @@ impl Drag {
                 ref_alt_m: 88_667.0,
             },
             drag_frame: cosm.frame("IAU Earth"),
+            wind: None,
+            space_weather: None,
+            surface: None,
+            cosm,
+        })
+    }
+
+    /// Exponential atmosphere of the Earth from the sea level, with a scale height of 7.2 km, e.g. for atmospheric entry.
+    /// This is a coarse approximation above 100 km, where the orbital models (e.g. `jacchia_roberts`) should be used instead.
+    pub fn earth_sea_level_exp(cosm: Arc<Cosm>) -> Arc<Self> {
+        Arc::new(Self {
+            density: AtmDensity::Exponential {
+                rho0: 1.225,
+                r0: 0.0,
+                ref_alt_m: 7_200.0,
+            },
+            drag_frame: cosm.frame("IAU Earth"),
+            wind: None,
+            space_weather: None,
+            surface: None,
+            cosm,
+        })
+    }
+
+    /// Exponential atmosphere of Mars from the reference surface, with a surface density of 0.020 kg/m^3 and a scale height of 11.1 km
+    pub fn mars_exp(cosm: Arc<Cosm>) -> Arc<Self> {
+        Arc::new(Self {
+            density: AtmDensity::Exponential {
+                rho0: 0.020,
+                r0: 0.0,
+                ref_alt_m: 11_100.0,
+            },
+            drag_frame: cosm.frame("IAU Mars"),
+            wind: None,
+            space_weather: None,
+            surface: None,
             cosm,
         })
     }
@@ -104,44 +335,44 @@ impl Drag {
                 max_alt_m: 1_000_000.0,
             },
             drag_frame: cosm.frame("IAU Earth"),
+            wind: None,
+            space_weather: None,
+            surface: None,
             cosm,
         })
     }
-}
 
-impl fmt::Display for Drag {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "\tDrag density {:?} in frame {}",
-            self.density, self.drag_frame
-        )
+    /// Drag model which uses the Jacchia-Roberts atmospheric density, with the provided space weather (e.g. `SpaceWeather::constant`)
+    pub fn jacchia_roberts(space_weather: SpaceWeather, cosm: Arc<Cosm>) -> Arc<Self> {
+        Arc::new(Self {
+            density: AtmDensity::JacchiaRoberts,
+            drag_frame: cosm.frame("IAU Earth"),
+            wind: None,
+            space_weather: Some(Arc::new(space_weather)),
+            surface: None,
+            cosm,
+        })
     }
-}
 
-impl ForceModel for Drag {
-    fn eom(&self, ctx: &Spacecraft) -> Result<Vector3<f64>, DynamicsError> {
-        let integration_frame = ctx.orbit.frame;
-        let osc = self.cosm.frame_chg(&ctx.orbit, self.drag_frame);
+    /// Returns a copy of this model which uses the area of the provided surface model projected along the flow
+    pub fn with_surface(&self, surface: Arc<SurfaceModel>) -> Arc<Self> {
+        let mut me = self.clone();
+        me.surface = Some(surface);
+        Arc::new(me)
+    }
+
+    /// Returns the atmospheric density in kg/m^3 at the provided state in the drag frame
+    pub(crate) fn density_at(&self, osc: &Orbit) -> Result<f64, DynamicsError> {
         match self.density {
-            AtmDensity::Constant(rho) => {
-                let velocity = osc.velocity();
-                Ok(-0.5 * rho * ctx.drag.cd * ctx.drag.area_m2 * velocity.norm() * velocity)
-            }
+            AtmDensity::Constant(rho) => Ok(rho),
 
             AtmDensity::Exponential {
                 rho0,
                 r0,
                 ref_alt_m,
             } => {
-                let rho = rho0
-                    * (-(osc.rmag_km() - (r0 + self.drag_frame.equatorial_radius())) / ref_alt_m)
-                        .exp();
-
-                let velocity_integr_frame = self.cosm.frame_chg(&osc, integration_frame).velocity();
-
-                let velocity = velocity_integr_frame - osc.velocity();
-                Ok(-0.5 * rho * ctx.drag.cd * ctx.drag.area_m2 * velocity.norm() * velocity)
+                let altitude_m = (osc.rmag_km() - self.drag_frame.equatorial_radius()) * 1e3;
+                Ok(rho0 * (-(altitude_m - r0) / ref_alt_m).exp())
             }
 
             AtmDensity::StdAtm { max_alt_m } => {
@@ -163,21 +394,642 @@ impl ForceModel for Drag {
                     /* Calculating density by raising 10 to the log of density */
                     10.0_f64.powf(logdensity)
                 };
+                Ok(rho)
+            }
 
-                let velocity_integr_frame = self.cosm.frame_chg(&osc, integration_frame).velocity();
+            AtmDensity::JacchiaRoberts => {
+                let space_weather =
+                    self.space_weather
+                        .as_ref()
+                        .ok_or_else(|| DynamicsError::DataUnavailable {
+                            msg: "the Jacchia-Roberts density requires space weather indices"
+                                .to_string(),
+                        })?;
+                let indices = space_weather.indices_at(osc.epoch).map_err(|e| {
+                    DynamicsError::DataUnavailable {
+                        msg: format!("{e}"),
+                    }
+                })?;
+                // Position of the Sun in the body fixed frame for the diurnal variation
+                let sun = self
+                    .cosm
+                    .try_celestial_state(
+                        Bodies::Sun.ephem_path(),
+                        osc.epoch,
+                        self.drag_frame,
+                        LightTimeCalc::None,
+                    )
+                    .map_err(|e| DynamicsError::DataUnavailable {
+                        msg: format!("{e}"),
+                    })?
+                    .radius();
+                let sun_declination = (sun.z / sun.norm()).asin();
+                let hour_angle = osc.y_km.atan2(osc.x_km) - sun.y.atan2(sun.x);
 
-                let velocity = velocity_integr_frame - osc.velocity();
-                Ok(-0.5 * rho * ctx.drag.cd * ctx.drag.area_m2 * velocity.norm() * velocity)
+                Ok(jacchia_roberts_density(
+                    osc.geodetic_height_km(),
+                    osc.geodetic_latitude_deg().to_radians(),
+                    sun_declination,
+                    hour_angle,
+                    osc.epoch,
+                    indices,
+                ))
             }
         }
     }
+}
 
-    fn dual_eom(
-        &self,
-        _osc_ctx: &Spacecraft,
-    ) -> Result<(Vector3<f64>, Matrix3<f64>), DynamicsError> {
-        Err(DynamicsError::DynamicsAstro {
-            source: AstroError::PartialsUndefined,
+impl fmt::Display for Drag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "\tDrag density {:?} in frame {}",
+            self.density, self.drag_frame
+        )?;
+        if let Some(wind) = &self.wind {
+            write!(f, " with winds {wind}")?;
+        }
+        if let Some(surface) = &self.surface {
+            write!(f, " on {surface}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ForceModel for Drag {
+    fn eom(&self, ctx: &Spacecraft) -> Result<Vector3<f64>, DynamicsError> {
+        let flow = AtmosphereFlow::new(
+            &self.cosm,
+            self.drag_frame,
+            self.wind.as_deref(),
+            &ctx.orbit,
+        )?;
+        let rho = self.density_at(&flow.osc_fixed)?;
+        Ok(flow.force(
+            rho,
+            flow.cd_area_m2(&self.cosm, self.surface.as_deref(), ctx)?,
+        ))
+    }
+
+    fn dual_eom(&self, ctx: &Spacecraft) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError> {
+        let flow = AtmosphereFlow::new(
+            &self.cosm,
+            self.drag_frame,
+            self.wind.as_deref(),
+            &ctx.orbit,
+        )?;
+        let rho = self.density_at(&flow.osc_fixed)?;
+
+        // The density also varies with the latitude and the local solar time for the models driven by the space weather, so its gradient
+        // is computed by central differences along each axis of the drag frame.
+        let step_km = 0.5;
+        let mut grad_fixed = Vector3::zeros();
+        for axis in 0..3 {
+            let rho_at = |delta_km: f64| -> Result<f64, DynamicsError> {
+                let mut osc = flow.osc_fixed;
+                match axis {
+                    0 => osc.x_km += delta_km,
+                    1 => osc.y_km += delta_km,
+                    _ => osc.z_km += delta_km,
+                }
+                self.density_at(&osc)
+            };
+            grad_fixed[axis] = (rho_at(step_km)? - rho_at(-step_km)?) / (2.0 * step_km);
+        }
+        let grad_rho = flow.dcm_to_inertial * grad_fixed;
+
+        let cd_area_m2 = flow.cd_area_m2(&self.cosm, self.surface.as_deref(), ctx)?;
+        Ok((
+            flow.force(rho, cd_area_m2),
+            flow.partials(rho, grad_rho, cd_area_m2),
+        ))
+    }
+}
+
+/// Lower boundary of the Jacchia-Roberts model, in km
+const JR_Z0_KM: f64 = 90.0;
+/// Temperature at the lower boundary, in K
+const JR_T0_K: f64 = 183.0;
+/// Density at the lower boundary, in kg/m^3
+const JR_RHO0: f64 = 3.46e-6;
+/// Altitude of the inflection point of the temperature profile, in km
+const JR_ZX_KM: f64 = 125.0;
+/// Altitude above which the species are in diffusive equilibrium, in km
+const JR_ZD_KM: f64 = 100.0;
+/// Altitude above which hydrogen is modeled, in km
+const JR_ZH_KM: f64 = 500.0;
+/// Polar radius of the Earth used for the gravity and the temperature profile, in km
+const JR_RA_KM: f64 = 6_356.766;
+/// Sea level gravity, in m/s^2
+const JR_G0: f64 = 9.806_65;
+/// Universal gas constant, in J/(kmol K)
+const JR_RSTAR: f64 = 8_314.32;
+/// Avogadro's number, per kmol
+const JR_AVOGADRO: f64 = 6.022_57e26;
+/// Sea level mean molecular mass, in kg/kmol
+const JR_M0: f64 = 28.96;
+/// Molecular masses of N2, O2, O, Ar, He and H, in kg/kmol
+const JR_MOL_MASS: [f64; 6] = [28.0134, 31.9988, 15.9994, 39.948, 4.0026, 1.00797];
+/// Thermal diffusion coefficients of N2, O2, O, Ar, He and H
+const JR_ALPHA: [f64; 6] = [0.0, 0.0, 0.0, 0.0, -0.38, 0.0];
+/// Sea level volume fractions of N2, O2, Ar and He
+const JR_FRAC: [f64; 4] = [0.78110, 0.20955, 9.34e-3, 1.289e-5];
+/// Coefficients of the temperature profile between 90 and 125 km
+const JR_C: [f64; 5] = [-89_284_375.0, 3_542_400.0, -52_687.5, 340.5, -0.8];
+/// Coefficients of Roberts' temperature profile above 125 km, as a polynomial of the exospheric temperature
+const JR_L: [f64; 5] = [
+    0.103_144_5e5,
+    0.234_123_0e1,
+    0.157_920_2e-2,
+    -0.125_248_7e-5,
+    0.246_270_8e-9,
+];
+/// Coefficients of the mean molecular mass between 90 and 100 km, as a polynomial of the altitude above 100 km
+const JR_MOL_MASS_MIX: [f64; 7] = [
+    28.15204, -8.5586e-2, 1.2840e-4, -1.0056e-5, -1.0210e-5, 1.5044e-6, 9.9826e-8,
+];
+/// The ap index for each third of Kp between 0 and 9
+const AP_OF_KP: [f64; 28] = [
+    0.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 9.0, 12.0, 15.0, 18.0, 22.0, 27.0, 32.0, 39.0, 48.0, 56.0,
+    67.0, 80.0, 94.0, 111.0, 132.0, 154.0, 179.0, 207.0, 236.0, 300.0, 400.0,
+];
+
+/// Returns the Kp index matching the provided ap index, by linear interpolation of the standard conversion table
+fn kp_from_ap(ap: f64) -> f64 {
+    let idx = AP_OF_KP.partition_point(|ap_i| *ap_i <= ap);
+    if idx == 0 {
+        0.0
+    } else if idx == AP_OF_KP.len() {
+        9.0
+    } else {
+        let (ap_lo, ap_hi) = (AP_OF_KP[idx - 1], AP_OF_KP[idx]);
+        ((idx - 1) as f64 + (ap - ap_lo) / (ap_hi - ap_lo)) / 3.0
+    }
+}
+
+/// Evaluates the polynomial of the provided coefficients (in increasing powers) and its derivative
+fn poly_eval<T: Copy + std::ops::Mul<Output = T> + std::ops::Add<Output = T> + From<f64>>(
+    coeffs: &[f64],
+    x: T,
+) -> (T, T) {
+    coeffs
+        .iter()
+        .rev()
+        .fold((T::from(0.0), T::from(0.0)), |(p, dp), c| {
+            (p * x + T::from(*c), dp * x + p)
         })
+}
+
+/// Temperature profile of the Jacchia-Roberts model for one exospheric temperature, and the closed form integrals of the
+/// barometric and diffusion equations of Roberts (1971).
+///
+/// Below 125 km, the temperature is a quartic of the altitude, so the integrands are rational functions which are integrated
+/// by partial fractions. Above 125 km, Roberts' exponential temperature profile integrates to a logarithm.
+struct JacchiaRobertsProfile {
+    t_inf: f64,
+    t_x: f64,
+    /// Coefficients of the temperature below 125 km, as a polynomial of the altitude above 100 km
+    quartic: [f64; 5],
+    /// Roots of this quartic, in km above 100 km
+    roots: [Complex<f64>; 4],
+    /// Shape parameter of the temperature profile above 125 km
+    s: f64,
+}
+
+impl JacchiaRobertsProfile {
+    fn new(t_inf: f64) -> Self {
+        let t_x = 371.6678 + 0.0518806 * t_inf - 294.3505 * (-0.002_162_22 * t_inf).exp();
+
+        // Shift the temperature polynomial to the altitude above 100 km to avoid cancellations
+        let scale = (t_x - JR_T0_K) / 35.0_f64.powi(4);
+        let mut quartic = JR_C.map(|c| c * scale);
+        for i in 0..4 {
+            for j in (i..4).rev() {
+                quartic[j] += JR_ZD_KM * quartic[j + 1];
+            }
+        }
+        quartic[0] += t_x;
+
+        // The roots are the eigenvalues of the companion matrix, refined with one Newton step
+        let mut companion = Matrix4::zeros();
+        for i in 0..4 {
+            companion[(0, i)] = -quartic[3 - i] / quartic[4];
+        }
+        for i in 1..4 {
+            companion[(i, i - 1)] = 1.0;
+        }
+        let eigenvalues = companion.complex_eigenvalues();
+        let roots = [0, 1, 2, 3].map(|i| {
+            let root = eigenvalues[i];
+            let (p, dp) = poly_eval(&quartic, root);
+            root - p / dp
+        });
+
+        let l = JR_L.iter().rev().fold(0.0, |acc, l| acc * t_inf + l);
+
+        Self {
+            t_inf,
+            t_x,
+            quartic,
+            roots,
+            s: (t_x - JR_T0_K) / (t_inf - t_x) * l / 35.0,
+        }
+    }
+
+    /// Temperature in K at the provided altitude in km
+    fn temperature(&self, z_km: f64) -> f64 {
+        if z_km <= JR_ZX_KM {
+            poly_eval(&self.quartic, z_km - JR_ZD_KM).0
+        } else {
+            self.t_inf - (self.t_inf - self.t_x) * (-self.s * self.psi(z_km)).exp()
+        }
+    }
+
+    /// Altitude variable of the temperature profile above 125 km
+    fn psi(&self, z_km: f64) -> f64 {
+        (z_km - JR_ZX_KM) / (JR_RA_KM + z_km)
+    }
+
+    /// Returns the integral of N(z) g(z) / T(z) between the two altitudes (in km) below 125 km, with the altitudes in meters,
+    /// where N is the provided polynomial of the altitude above 100 km, of degree six at most.
+    fn lower_integral(&self, num: &[f64], from_km: f64, to_km: f64) -> f64 {
+        // Partial fractions of N(y) / ((y + b)^2 T(y)) where y is the altitude above 100 km
+        let b = JR_RA_KM + JR_ZD_KM;
+        let (y1, y2) = (from_km - JR_ZD_KM, to_km - JR_ZD_KM);
+
+        // Polynomial part
+        let mut integral = if num.len() == 7 {
+            num[6] / self.quartic[4] * (y2 - y1)
+        } else {
+            0.0
+        };
+        // Simple roots of the temperature, whose conjugate terms cancel their imaginary parts
+        for root in self.roots {
+            let (n_r, _) = poly_eval(num, root);
+            let (_, dt_r) = poly_eval(&self.quartic, root);
+            let coeff = n_r / ((root + b).powi(2) * dt_r);
+            integral += (coeff * ((y2 - root).ln() - (y1 - root).ln())).re;
+        }
+        // Double root of the gravity
+        let (n_b, dn_b) = poly_eval(num, -b);
+        let (t_b, dt_b) = poly_eval(&self.quartic, -b);
+        let d2 = n_b / t_b;
+        let d1 = (dn_b * t_b - n_b * dt_b) / t_b.powi(2);
+        integral += d1 * ((y2 + b) / (y1 + b)).ln() + d2 * (1.0 / (y1 + b) - 1.0 / (y2 + b));
+
+        JR_G0 * JR_RA_KM.powi(2) * integral * 1e3
+    }
+
+    /// Returns the integral of g(z) / T(z) between 125 km and the provided altitude in km, with the altitudes in meters
+    fn upper_integral(&self, z_km: f64) -> f64 {
+        let psi = self.psi(z_km);
+        JR_G0 * JR_RA_KM.powi(2) / ((JR_RA_KM + JR_ZX_KM) * self.t_inf)
+            * (psi + (self.temperature(z_km) / self.t_x).ln() / self.s)
+            * 1e3
+    }
+
+    /// Returns the integral of g(z) / T(z) between 100 km and the provided altitude in km, with the altitudes in meters
+    fn diffusion_integral(&self, z_km: f64) -> f64 {
+        if z_km <= JR_ZX_KM {
+            self.lower_integral(&[1.0], JR_ZD_KM, z_km)
+        } else {
+            self.lower_integral(&[1.0], JR_ZD_KM, JR_ZX_KM) + self.upper_integral(z_km)
+        }
+    }
+
+    /// Returns the density in kg/m^3 at the provided altitude in km, between 90 km and 2500 km, where the number density of helium
+    /// at 100 km is scaled by the provided power of ten.
+    fn density(&self, z_km: f64, delta_log_he: f64) -> f64 {
+        let mean_mass = |z: f64| poly_eval(&JR_MOL_MASS_MIX, z - JR_ZD_KM).0;
+
+        // Barometric equation in the mixing region
+        let mixed_density = |z: f64| {
+            JR_RHO0 * mean_mass(z) / mean_mass(JR_Z0_KM) * JR_T0_K / self.temperature(z)
+                * (-self.lower_integral(&JR_MOL_MASS_MIX, JR_Z0_KM, z) / JR_RSTAR).exp()
+        };
+
+        if z_km <= JR_ZD_KM {
+            return mixed_density(z_km);
+        }
+
+        // Composition at the bottom of the diffusion region, where part of the O2 is dissociated
+        let rho_d = mixed_density(JR_ZD_KM);
+        let m_d = mean_mass(JR_ZD_KM);
+        let n_0 = rho_d * JR_AVOGADRO / JR_M0;
+        let n_d = [
+            JR_FRAC[0] * n_0,
+            n_0 * (1.0 + JR_FRAC[1]) - rho_d * JR_AVOGADRO / m_d,
+            2.0 * (rho_d * JR_AVOGADRO / m_d - n_0),
+            JR_FRAC[2] * n_0,
+            JR_FRAC[3] * n_0 * 10.0_f64.powf(delta_log_he),
+        ];
+
+        let t_d = self.temperature(JR_ZD_KM);
+        let t_z = self.temperature(z_km);
+        let g_over_t = self.diffusion_integral(z_km);
+        let mut rho = 0.0;
+        for (species, n_d) in n_d.iter().enumerate() {
+            let n_z = n_d
+                * (t_d / t_z).powf(1.0 + JR_ALPHA[species])
+                * (-JR_MOL_MASS[species] * g_over_t / JR_RSTAR).exp();
+            rho += n_z * JR_MOL_MASS[species] / JR_AVOGADRO;
+        }
+        if z_km > JR_ZH_KM {
+            let log_t_inf = self.t_inf.log10();
+            let n_h_500 = 10.0_f64.powf(73.13 - 39.4 * log_t_inf + 5.5 * log_t_inf.powi(2) + 6.0);
+            let g_over_t_h = self.upper_integral(z_km) - self.upper_integral(JR_ZH_KM);
+            let n_h = n_h_500 * self.temperature(JR_ZH_KM) / t_z
+                * (-JR_MOL_MASS[5] * g_over_t_h / JR_RSTAR).exp();
+            rho += n_h * JR_MOL_MASS[5] / JR_AVOGADRO;
+        }
+        rho
+    }
+}
+
+/// Computes the atmospheric density in kg/m^3 of the Jacchia-Roberts model.
+///
+/// The exospheric temperature, composition and corrections are those of Jacchia (1971), with the temperature profile and the
+/// closed form integrals of Roberts (1971).
+/// The altitude is the geodetic height in km, and the latitude, the declination of the Sun, and the hour angle of the Sun (i.e. the
+/// longitude of the point minus the longitude of the Sun) are in radians. Below 90 km, the density at 90 km is returned.
+pub fn jacchia_roberts_density(
+    altitude_km: f64,
+    latitude: f64,
+    sun_declination: f64,
+    hour_angle: f64,
+    epoch: Epoch,
+    indices: SolarIndices,
+) -> f64 {
+    let z_km = altitude_km.clamp(JR_Z0_KM, 2_500.0);
+    let kp = kp_from_ap(indices.ap);
+
+    // Nighttime minimum of the global exospheric temperature
+    let t_c = 379.0 + 3.24 * indices.f107a + 1.3 * (indices.f107 - indices.f107a);
+    // Diurnal variation
+    let eta = 0.5 * (latitude - sun_declination).abs();
+    let theta = 0.5 * (latitude + sun_declination).abs();
+    let hour_angle = (hour_angle + PI).rem_euclid(TAU) - PI;
+    let tau = hour_angle
+        + (-37.0 + 6.0 * (hour_angle.to_degrees() + 43.0).to_radians().sin()).to_radians();
+    let sin_theta = theta.sin().powf(2.2);
+    let t_l = t_c
+        * (1.0
+            + 0.3
+                * (sin_theta
+                    + (eta.cos().powf(2.2) - sin_theta) * (0.5 * tau).cos().abs().powi(3)));
+    // Geomagnetic activity
+    let delta_t_g = if z_km >= 200.0 {
+        28.0 * kp + 0.03 * kp.exp()
+    } else {
+        14.0 * kp + 0.02 * kp.exp()
+    };
+    let t_inf = t_l + delta_t_g;
+
+    // Seasonal-latitudinal variation of helium
+    let delta_log_he = if sun_declination.abs() > 0.0 {
+        let obliquity = 23.44_f64.to_radians();
+        0.65 * (sun_declination / obliquity).abs()
+            * ((FRAC_PI_4 - 0.5 * latitude * sun_declination.signum())
+                .sin()
+                .powi(3)
+                - 0.35355)
+    } else {
+        0.0
+    };
+
+    let mut log_rho = JacchiaRobertsProfile::new(t_inf)
+        .density(z_km, delta_log_he)
+        .log10();
+
+    // Geomagnetic activity in the lower thermosphere
+    if z_km < 200.0 {
+        log_rho += 0.012 * kp + 1.2e-5 * kp.exp();
+    }
+
+    // Semiannual variation, with the time in years since 1958 January 1
+    let phi = (epoch.to_mjd_utc_days() - 36_204.0) / 365.2422;
+    let tau_sa = phi + 0.09544 * ((0.5 + 0.5 * (TAU * phi + 6.035).sin()).powf(1.650) - 0.5);
+    let f_z = (5.876e-7 * z_km.powf(2.331) + 0.06328) * (-2.868e-3 * z_km).exp();
+    let g_t = 0.02835
+        + 0.3817
+            * (1.0 + 0.4671 * (TAU * tau_sa + 4.137).sin())
+            * (2.0 * TAU * tau_sa + 4.259).sin();
+    log_rho += f_z * g_t;
+
+    // Seasonal-latitudinal variation of the lower thermosphere
+    let dz = z_km - JR_Z0_KM;
+    log_rho += 0.014
+        * dz
+        * (-0.0013 * dz.powi(2)).exp()
+        * (TAU * phi + 1.72).sin()
+        * latitude.sin()
+        * latitude.sin().abs();
+
+    10.0_f64.powf(log_rho)
+}
+
+#[cfg(test)]
+mod ut_drag {
+    use super::*;
+
+    struct EastwardWind(f64);
+
+    impl fmt::Display for EastwardWind {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "eastward wind of {} km/s", self.0)
+        }
+    }
+
+    impl WindModel for EastwardWind {
+        fn horizontal_wind_km_s(&self, _osc: &Orbit) -> Result<(f64, f64), DynamicsError> {
+            Ok((self.0, 0.0))
+        }
+    }
+
+    #[test]
+    fn test_corotating_drag() {
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let eme2k = cosm.frame("EME2000");
+        let iau_earth = cosm.frame("IAU Earth");
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 3, 21);
+
+        // Equatorial orbit of the IAU Earth frame, so the atmosphere rotates along the orbit
+        let equatorial = Orbit::keplerian(6_778.0, 0.0, 0.0, 0.0, 0.0, 0.0, epoch, iau_earth);
+        let sc = Spacecraft::from_srp_defaults(cosm.frame_chg(&equatorial, eme2k), 500.0, 1.0)
+            .with_drag(2.0, 2.2);
+        let orbit = sc.orbit;
+
+        let constant = ConstantDrag {
+            rho: 1e-12,
+            drag_frame: iau_earth,
+            surface: None,
+            cosm: cosm.clone(),
+        };
+        let force = constant.eom(&sc).unwrap();
+        let speed = orbit.vmag_km_s() - iau_earth.angular_velocity() * orbit.rmag_km();
+        // The force is in kN, i.e. 0.5 rho Cd A v^2 with v in m/s, divided by 1000
+        let expected = 0.5 * 1e-12 * 2.2 * 2.0 * (speed * 1e3).powi(2) * 1e-3;
+        assert!((force.norm() / expected - 1.0).abs() < 1e-6);
+        assert!(force.dot(&orbit.velocity()) / (force.norm() * orbit.vmag_km_s()) < -0.999_999);
+
+        // An eastward wind further reduces the relative velocity
+        let windy = Drag {
+            density: AtmDensity::Constant(1e-12),
+            drag_frame: iau_earth,
+            wind: Some(Arc::new(EastwardWind(0.1))),
+            space_weather: None,
+            surface: None,
+            cosm: cosm.clone(),
+        };
+        let windy_force = windy.eom(&sc).unwrap();
+        let windy_expected = expected * ((speed - 0.1) / speed).powi(2);
+        assert!((windy_force.norm() / windy_expected - 1.0).abs() < 1e-6);
+        assert!(format!("{windy}").contains("eastward wind"));
+
+        // The rotation of the atmosphere must be known
+        let iau_sun = cosm.frame("IAU Sun");
+        assert!(AtmosphereFlow::new(&cosm, iau_sun, None, &orbit).is_err());
+        let sun_drag = Drag {
+            drag_frame: iau_sun,
+            ..windy.clone()
+        };
+        assert!(sun_drag.eom(&sc).is_err());
+
+        // The partials match the finite differences of the force, including the density gradient
+        let inclined = Orbit::keplerian(6_778.0, 0.001, 51.6, 20.0, 30.0, 40.0, epoch, eme2k);
+        let sc = sc.with_orbit(inclined);
+        for drag in [
+            Drag::earth_exp(cosm.clone()),
+            Drag::std_atm1976(cosm.clone()),
+            Drag::jacchia_roberts(
+                SpaceWeather::constant(SolarIndices::default()),
+                cosm.clone(),
+            ),
+        ] {
+            let (force, partials) = drag.dual_eom(&sc).unwrap();
+            assert!((force - drag.eom(&sc).unwrap()).norm() < f64::EPSILON);
+
+            for j in 0..6 {
+                let step = if j < 3 { 1e-2 } else { 1e-5 };
+                let perturbed = |delta: f64| {
+                    let mut orbit = inclined;
+                    match j {
+                        0 => orbit.x_km += delta,
+                        1 => orbit.y_km += delta,
+                        2 => orbit.z_km += delta,
+                        3 => orbit.vx_km_s += delta,
+                        4 => orbit.vy_km_s += delta,
+                        _ => orbit.vz_km_s += delta,
+                    }
+                    drag.eom(&sc.with_orbit(orbit)).unwrap()
+                };
+                let fd = (perturbed(step) - perturbed(-step)) / (2.0 * step);
+                let err = (fd - partials.column(j)).norm() / fd.norm();
+                assert!(err < 1e-4, "column {j} relative error {err:e}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_kp_from_ap() {
+        assert_eq!(kp_from_ap(0.0), 0.0);
+        assert!((kp_from_ap(15.0) - 3.0).abs() < 1e-12);
+        assert!((kp_from_ap(13.5) - 17.0 / 6.0).abs() < 1e-12);
+        assert_eq!(kp_from_ap(500.0), 9.0);
+    }
+
+    #[test]
+    fn test_jacchia_roberts_density() {
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 3, 21);
+        let indices = SolarIndices::default();
+        let rho = |alt_km: f64, indices: SolarIndices| {
+            jacchia_roberts_density(alt_km, 0.0, 0.0, 0.0, epoch, indices)
+        };
+
+        // The boundary condition at 90 km is only modified by the geomagnetic and semiannual corrections
+        assert!((rho(90.0, indices) / JR_RHO0 - 1.0).abs() < 0.2);
+        // Typical values for moderate solar activity
+        let rho_400 = rho(400.0, indices);
+        assert!(rho_400 > 1e-12 && rho_400 < 1e-11);
+
+        let mut prev = rho(90.0, indices);
+        for alt_km in (100..2000).step_by(50) {
+            let cur = rho(alt_km as f64, indices);
+            assert!(cur < prev, "density increases at {alt_km} km");
+            prev = cur;
+        }
+
+        let active_sun = SolarIndices {
+            f107: 250.0,
+            f107a: 250.0,
+            ..indices
+        };
+        assert!(rho(400.0, active_sun) > 2.0 * rho_400);
+        let storm = SolarIndices {
+            ap: 207.0,
+            ..indices
+        };
+        assert!(rho(400.0, storm) > rho_400);
+        // The night side is less dense
+        assert!(jacchia_roberts_density(400.0, 0.0, 0.0, PI, epoch, indices) < rho_400);
+    }
+
+    #[test]
+    fn test_jacchia_roberts_reference() {
+        // Boundary conditions of Jacchia (1971) for an exospheric temperature of 1000 K
+        let profile = JacchiaRobertsProfile::new(1_000.0);
+        assert!((profile.temperature(JR_Z0_KM) - JR_T0_K).abs() < 1e-9);
+        assert!((profile.temperature(JR_ZX_KM) - profile.t_x).abs() < 1e-9);
+        assert!((profile.temperature(2_000.0) - 1_000.0).abs() < 1e-3);
+        assert!((profile.density(JR_Z0_KM, 0.0) / JR_RHO0 - 1.0).abs() < 1e-12);
+
+        // Densities in kg/m^3 of the US Standard Atmosphere 1976, whose thermosphere also has an exospheric temperature of 1000 K.
+        // Above 600 km, its helium and hydrogen differ from those of Jacchia.
+        for (alt_km, rho_ussa76) in [
+            (100.0, 5.604e-7),
+            (150.0, 2.076e-9),
+            (200.0, 2.541e-10),
+            (300.0, 1.916e-11),
+            (400.0, 2.803e-12),
+            (500.0, 5.215e-13),
+            (600.0, 1.137e-13),
+        ] {
+            let rel_err = profile.density(alt_km, 0.0) / rho_ussa76 - 1.0;
+            assert!(
+                rel_err.abs() < 0.2,
+                "{alt_km} km: relative error {rel_err:.3}"
+            );
+        }
+
+        // A hotter thermosphere is denser at high altitudes
+        let hot = JacchiaRobertsProfile::new(1_500.0);
+        assert!(hot.density(400.0, 0.0) > 3.0 * profile.density(400.0, 0.0));
+
+        // The drag requires the space weather
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 3, 21);
+        let sc = Spacecraft::from_srp_defaults(
+            Orbit::keplerian(
+                6_778.0,
+                0.001,
+                51.6,
+                20.0,
+                30.0,
+                40.0,
+                epoch,
+                cosm.frame("EME2000"),
+            ),
+            500.0,
+            1.0,
+        )
+        .with_drag(2.0, 2.2);
+        let drag = Drag::jacchia_roberts(SpaceWeather::constant(SolarIndices::default()), cosm);
+        assert!(drag.eom(&sc).unwrap().norm() > 0.0);
+        let no_indices = Drag {
+            space_weather: None,
+            surface: None,
+            ..(*drag).clone()
+        };
+        assert!(no_indices.eom(&sc).is_err());
     }
 }
@@ -0,0 +1,485 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::orbital::OrbitalDynamics;
+use super::{AccelModel, Dynamics, DynamicsError};
+use crate::cosmic::{EmpiricalOrbit, Orbit};
+use crate::linalg::{Const, DimName, Matrix3, Matrix3x6, OMatrix, OVector, SVector, Vector3};
+use crate::time::{Duration, Epoch};
+use crate::State;
+use std::fmt;
+
+/// Number of empirical acceleration parameters: the constant, cosine, sine and DMC terms along each axis of the RIC frame
+pub const EMPIRICAL_PARAMS: usize = 12;
+
+/// Empirical accelerations in the RIC frame of the orbit, used to absorb the unmodeled accelerations of a spacecraft in orbit determination.
+///
+/// The acceleration along each axis of the RIC frame is `a = a_c + a_cos cos(u) + a_sin sin(u) + a_dmc`, where `u` is the argument of latitude,
+/// i.e. a constant term, once-per-revolution terms, and a dynamic model compensation (DMC) term which is a first-order Gauss-Markov process of time constant τ.
+///
+/// When used as an acceleration model, these parameters are fixed and the DMC term decays from the `epoch` with its time constant.
+/// To solve for them in a filter, append them to the orbit in an `EmpiricalOrbit` propagated with the `EmpiricalDynamics`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EmpiricalAccel {
+    /// Constant acceleration, in km/s^2
+    pub constant_km_s2: Vector3<f64>,
+    /// Amplitude of the cosine of the argument of latitude, in km/s^2
+    pub cos_km_s2: Vector3<f64>,
+    /// Amplitude of the sine of the argument of latitude, in km/s^2
+    pub sin_km_s2: Vector3<f64>,
+    /// Dynamic model compensation acceleration at the epoch, in km/s^2
+    pub dmc_km_s2: Vector3<f64>,
+    /// Time constant of the DMC acceleration, which is constant if unset
+    pub dmc_tau: Option<Duration>,
+    /// Steady-state standard deviation of the DMC acceleration in km/s^2, used as the process noise of the filter
+    pub dmc_sigma_km_s2: f64,
+    /// Epoch of the DMC acceleration
+    pub epoch: Epoch,
+}
+
+impl EmpiricalAccel {
+    /// Initializes zero empirical accelerations at the provided epoch, without DMC time constant
+    pub fn new(epoch: Epoch) -> Self {
+        Self {
+            constant_km_s2: Vector3::zeros(),
+            cos_km_s2: Vector3::zeros(),
+            sin_km_s2: Vector3::zeros(),
+            dmc_km_s2: Vector3::zeros(),
+            dmc_tau: None,
+            dmc_sigma_km_s2: 0.0,
+            epoch,
+        }
+    }
+
+    /// Sets the time constant and the steady-state standard deviation (in km/s^2) of the DMC acceleration
+    pub fn with_dmc(self, tau: Duration, sigma_km_s2: f64) -> Self {
+        let mut me = self;
+        me.dmc_tau = Some(tau);
+        me.dmc_sigma_km_s2 = sigma_km_s2;
+        me
+    }
+
+    /// Returns the parameters as [constant (RIC), cosine (RIC), sine (RIC), DMC (RIC)]
+    pub fn to_vector(&self) -> SVector<f64, EMPIRICAL_PARAMS> {
+        SVector::<f64, EMPIRICAL_PARAMS>::from_iterator(
+            self.constant_km_s2
+                .iter()
+                .chain(self.cos_km_s2.iter())
+                .chain(self.sin_km_s2.iter())
+                .chain(self.dmc_km_s2.iter())
+                .copied(),
+        )
+    }
+
+    /// Sets the parameters from a vector organized as [constant (RIC), cosine (RIC), sine (RIC), DMC (RIC)]
+    pub fn set_vector(&mut self, params: &SVector<f64, EMPIRICAL_PARAMS>) {
+        self.constant_km_s2 = params.fixed_rows::<3>(0).into_owned();
+        self.cos_km_s2 = params.fixed_rows::<3>(3).into_owned();
+        self.sin_km_s2 = params.fixed_rows::<3>(6).into_owned();
+        self.dmc_km_s2 = params.fixed_rows::<3>(9).into_owned();
+    }
+
+    /// Returns the DMC acceleration at the provided epoch, decayed from the epoch of these parameters
+    pub fn dmc_at(&self, epoch: Epoch) -> Vector3<f64> {
+        match self.dmc_tau {
+            Some(tau) => {
+                self.dmc_km_s2 * (-(epoch - self.epoch).to_seconds() / tau.to_seconds()).exp()
+            }
+            None => self.dmc_km_s2,
+        }
+    }
+
+    /// Returns the time derivative of the DMC acceleration, zero if it has no time constant
+    pub fn dmc_rate(&self) -> Vector3<f64> {
+        match self.dmc_tau {
+            Some(tau) => -self.dmc_km_s2 / tau.to_seconds(),
+            None => Vector3::zeros(),
+        }
+    }
+
+    /// Returns the rotation from the RIC frame to the frame of the orbit, and the cosine and sine of its argument of latitude.
+    ///
+    /// The argument of latitude is computed from the node line, or from the X axis for equatorial orbits.
+    fn ric_basis(orbit: &Orbit) -> (Matrix3<f64>, f64, f64) {
+        let r_hat = orbit.radius() / orbit.rmag_km();
+        let c_hat = orbit.hvec() / orbit.hvec().norm();
+        let i_hat = c_hat.cross(&r_hat);
+        let node = Vector3::z().cross(&c_hat);
+        let node = if node.norm() < 1e-12 {
+            Vector3::x()
+        } else {
+            node / node.norm()
+        };
+        let cos_u = node.dot(&r_hat);
+        let sin_u = c_hat.cross(&node).dot(&r_hat);
+        (Matrix3::from_columns(&[r_hat, i_hat, c_hat]), cos_u, sin_u)
+    }
+
+    /// Returns the empirical acceleration in the frame of the orbit, in km/s^2
+    pub fn accel(&self, orbit: &Orbit) -> Vector3<f64> {
+        let (dcm, cos_u, sin_u) = Self::ric_basis(orbit);
+        dcm * (self.constant_km_s2
+            + cos_u * self.cos_km_s2
+            + sin_u * self.sin_km_s2
+            + self.dmc_at(orbit.epoch))
+    }
+
+    /// Returns the partials of the acceleration in the frame of the orbit with respect to the parameters, organized as in `to_vector`
+    pub fn param_partials(&self, orbit: &Orbit) -> OMatrix<f64, Const<3>, Const<EMPIRICAL_PARAMS>> {
+        let (dcm, cos_u, sin_u) = Self::ric_basis(orbit);
+        let decay = match self.dmc_tau {
+            Some(tau) => (-(orbit.epoch - self.epoch).to_seconds() / tau.to_seconds()).exp(),
+            None => 1.0,
+        };
+        let mut partials = OMatrix::<f64, Const<3>, Const<EMPIRICAL_PARAMS>>::zeros();
+        for (blk, factor) in [1.0, cos_u, sin_u, decay].iter().enumerate() {
+            partials
+                .fixed_view_mut::<3, 3>(0, 3 * blk)
+                .copy_from(&(dcm * *factor));
+        }
+        partials
+    }
+
+    /// Returns the partials of the acceleration with respect to the position and velocity.
+    /// The RIC frame and the argument of latitude depend on the orbit, so these are computed by central differences.
+    pub fn orbit_partials(&self, orbit: &Orbit) -> Matrix3x6<f64> {
+        let state = orbit.to_cartesian_vec();
+        let mut grad = Matrix3x6::zeros();
+        for j in 0..6 {
+            let step = if j < 3 { 1e-2 } else { 1e-5 };
+            let perturbed = |delta: f64| {
+                let mut perturbed_state = state;
+                perturbed_state[j] += delta;
+                self.accel(&Orbit::cartesian_vec(
+                    &perturbed_state,
+                    orbit.epoch,
+                    orbit.frame,
+                ))
+            };
+            grad.set_column(j, &((perturbed(step) - perturbed(-step)) / (2.0 * step)));
+        }
+        grad
+    }
+}
+
+impl Default for EmpiricalAccel {
+    fn default() -> Self {
+        Self::new(Epoch::from_tai_seconds(0.0))
+    }
+}
+
+impl fmt::Display for EmpiricalAccel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "empirical accelerations (RIC, km/s^2): constant [{:e}, {:e}, {:e}]  cos(u) [{:e}, {:e}, {:e}]  sin(u) [{:e}, {:e}, {:e}]  DMC [{:e}, {:e}, {:e}]",
+            self.constant_km_s2[0],
+            self.constant_km_s2[1],
+            self.constant_km_s2[2],
+            self.cos_km_s2[0],
+            self.cos_km_s2[1],
+            self.cos_km_s2[2],
+            self.sin_km_s2[0],
+            self.sin_km_s2[1],
+            self.sin_km_s2[2],
+            self.dmc_km_s2[0],
+            self.dmc_km_s2[1],
+            self.dmc_km_s2[2],
+        )?;
+        if let Some(tau) = self.dmc_tau {
+            write!(f, " (τ = {tau})")?;
+        }
+        Ok(())
+    }
+}
+
+impl AccelModel for EmpiricalAccel {
+    fn eom(&self, osc: &Orbit) -> Result<Vector3<f64>, DynamicsError> {
+        Ok(self.accel(osc))
+    }
+
+    fn dual_eom(&self, osc: &Orbit) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError> {
+        Ok((self.accel(osc), self.orbit_partials(osc)))
+    }
+}
+
+/// `EmpiricalDynamics` propagates an orbit with its empirical accelerations, whose parameters are part of the state so that they can be estimated.
+///
+/// The constant and once-per-revolution parameters are constant, and the DMC parameters decay with their time constant.
+/// The STM includes the partials of the orbit with respect to all of the parameters.
+#[derive(Clone)]
+pub struct EmpiricalDynamics {
+    pub orbital_dyn: OrbitalDynamics,
+}
+
+impl EmpiricalDynamics {
+    /// Initializes the empirical dynamics on top of the provided orbital dynamics
+    pub fn new(orbital_dyn: OrbitalDynamics) -> Self {
+        Self { orbital_dyn }
+    }
+}
+
+impl fmt::Display for EmpiricalDynamics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} with estimated empirical accelerations",
+            self.orbital_dyn
+        )
+    }
+}
+
+impl Dynamics for EmpiricalDynamics {
+    type HyperdualSize = Const<19>;
+    type StateType = EmpiricalOrbit;
+
+    fn eom(
+        &self,
+        delta_t_s: f64,
+        state: &OVector<f64, Const<342>>,
+        ctx: &EmpiricalOrbit,
+    ) -> Result<OVector<f64, Const<342>>, DynamicsError> {
+        let osc = ctx.set_with_delta_seconds(delta_t_s, state);
+        let mut d_x = OVector::<f64, Const<342>>::zeros();
+
+        if ctx.stm.is_some() {
+            let (state, grad) = self.dual_eom(delta_t_s, &osc)?;
+
+            // Apply the gradient to the osculating STM, which is valid over the whole propagation and not only over a step
+            let stm_dt = grad * osc.stm()?;
+
+            for (i, val) in state.iter().enumerate() {
+                d_x[i] = *val;
+            }
+
+            for (i, val) in stm_dt.iter().enumerate() {
+                d_x[i + <EmpiricalOrbit as State>::Size::dim()] = *val;
+            }
+        } else {
+            // The osculating orbit does not have an STM, so only the first six components are used
+            let mut orbit_vec = OVector::<f64, Const<42>>::zeros();
+            for (i, val) in osc.orbit.to_cartesian_vec().iter().enumerate() {
+                orbit_vec[i] = *val;
+            }
+            let d_orbit = self.orbital_dyn.eom(0.0, &orbit_vec, &osc.orbit)?;
+            for i in 0..6 {
+                d_x[i] = d_orbit[i];
+            }
+
+            let accel = osc.empirical.accel(&osc.orbit);
+            let dmc_rate = osc.empirical.dmc_rate();
+            for i in 0..3 {
+                d_x[i + 3] += accel[i];
+                d_x[i + 15] = dmc_rate[i];
+            }
+        }
+
+        Ok(d_x)
+    }
+
+    fn dual_eom(
+        &self,
+        delta_t_s: f64,
+        osc: &EmpiricalOrbit,
+    ) -> Result<(OVector<f64, Const<18>>, OMatrix<f64, Const<18>, Const<18>>), DynamicsError> {
+        let mut d_x = OVector::<f64, Const<18>>::zeros();
+        let mut grad = OMatrix::<f64, Const<18>, Const<18>>::zeros();
+
+        let (orb_state, orb_grad) = self.orbital_dyn.dual_eom(delta_t_s, &osc.orbit)?;
+
+        for i in 0..6 {
+            d_x[i] = orb_state[i];
+            for j in 0..6 {
+                grad[(i, j)] = orb_grad[(i, j)];
+            }
+        }
+
+        // Add the empirical accelerations and their partials with respect to the orbit and to the parameters
+        let accel = osc.empirical.accel(&osc.orbit);
+        let orbit_partials = osc.empirical.orbit_partials(&osc.orbit);
+        let param_partials = osc.empirical.param_partials(&osc.orbit);
+        for i in 0..3 {
+            d_x[i + 3] += accel[i];
+            for j in 0..6 {
+                grad[(i + 3, j)] += orbit_partials[(i, j)];
+            }
+            for j in 0..EMPIRICAL_PARAMS {
+                grad[(i + 3, j + 6)] = param_partials[(i, j)];
+            }
+        }
+
+        // The DMC parameters are first-order Gauss-Markov processes
+        if let Some(tau) = osc.empirical.dmc_tau {
+            let dmc_rate = osc.empirical.dmc_rate();
+            for i in 0..3 {
+                d_x[i + 15] = dmc_rate[i];
+                grad[(i + 15, i + 15)] = -1.0 / tau.to_seconds();
+            }
+        }
+
+        Ok((d_x, grad))
+    }
+}
+
+#[cfg(test)]
+mod ut_empirical {
+    use super::*;
+    use crate::cosmic::Cosm;
+    use crate::propagators::Propagator;
+    use crate::time::Unit;
+
+    #[test]
+    fn test_empirical_accel() {
+        let cosm = Cosm::fixed_planets();
+        let eme2k = cosm.frame("EME2000");
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        // Circular orbit at the ascending node, so the argument of latitude is zero
+        let leo = Orbit::keplerian(7_000.0, 0.0, 51.6, 30.0, 0.0, 0.0, epoch, eme2k);
+
+        // A constant in-track acceleration is along the velocity of a circular orbit
+        let mut empirical = EmpiricalAccel::new(epoch);
+        empirical.constant_km_s2 = Vector3::new(0.0, 1e-9, 0.0);
+        let accel = empirical.accel(&leo);
+        assert!((accel.norm() - 1e-9).abs() < 1e-20);
+        assert!(accel.dot(&leo.velocity()) / (accel.norm() * leo.vmag_km_s()) > 1.0 - 1e-12);
+
+        // At the node, the once-per-revolution terms are fully along the cosine
+        empirical.constant_km_s2 = Vector3::zeros();
+        empirical.cos_km_s2 = Vector3::new(2e-9, 0.0, 0.0);
+        empirical.sin_km_s2 = Vector3::new(0.0, 0.0, 5e-9);
+        let accel = empirical.accel(&leo);
+        assert!((accel - 2e-9 * leo.radius() / leo.rmag_km()).norm() < 1e-20);
+
+        // The acceleration is linear in the parameters
+        let partials = empirical.param_partials(&leo);
+        assert!((partials * empirical.to_vector() - accel).norm() < 1e-20);
+
+        // The vector round trip is lossless
+        let mut other = EmpiricalAccel::new(epoch);
+        other.set_vector(&empirical.to_vector());
+        assert_eq!(other, empirical);
+
+        // The DMC acceleration decays exponentially
+        let empirical = EmpiricalAccel::new(epoch).with_dmc(Unit::Hour * 1, 1e-8);
+        let mut empirical = empirical;
+        empirical.dmc_km_s2 = Vector3::new(1e-9, 0.0, 0.0);
+        let decayed = empirical.dmc_at(epoch + Unit::Hour * 1);
+        assert!((decayed[0] - 1e-9 * (-1.0_f64).exp()).abs() < 1e-22);
+        assert!((empirical.dmc_rate()[0] + 1e-9 / 3600.0).abs() < 1e-24);
+    }
+
+    #[test]
+    fn test_empirical_stm() {
+        let cosm = Cosm::fixed_planets();
+        let eme2k = cosm.frame("EME2000");
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        let leo = Orbit::keplerian(7_000.0, 0.01, 51.6, 30.0, 20.0, 40.0, epoch, eme2k);
+
+        let mut empirical = EmpiricalAccel::new(epoch).with_dmc(Unit::Minute * 30, 1e-8);
+        empirical.constant_km_s2 = Vector3::new(1e-9, 2e-9, -1e-9);
+        empirical.cos_km_s2 = Vector3::new(0.0, 1e-9, 0.0);
+        empirical.dmc_km_s2 = Vector3::new(0.0, 0.0, 3e-9);
+        let start = EmpiricalOrbit::new(leo, empirical);
+        let duration = Unit::Minute * 45;
+
+        let dynamics = EmpiricalDynamics::new(OrbitalDynamics::two_body());
+        let end = Propagator::default(dynamics.clone())
+            .with(start)
+            .for_duration(duration)
+            .unwrap();
+        let end_stm = Propagator::default(dynamics.clone())
+            .with(start.with_stm())
+            .for_duration(duration)
+            .unwrap();
+
+        // Both propagations match, and the DMC has decayed
+        assert!((end.orbit.radius() - end_stm.orbit.radius()).norm() < 1e-6);
+        let expected_dmc = 3e-9 * (-1.5_f64).exp();
+        assert!((end.empirical.dmc_km_s2[2] - expected_dmc).abs() < 1e-15);
+        assert!((end_stm.empirical.dmc_km_s2[2] - expected_dmc).abs() < 1e-15);
+
+        // The interpolated DMC follows the exponential decay between the samples
+        let (_, traj) = Propagator::default(dynamics.clone())
+            .with(start)
+            .for_duration_with_traj(duration)
+            .unwrap();
+        for minutes in [1, 7, 22, 38, 44] {
+            let state = traj.at(epoch + Unit::Minute * minutes).unwrap();
+            let expected_dmc = 3e-9 * (-(minutes as f64) / 30.0).exp();
+            assert!(
+                (state.empirical.dmc_km_s2[2] - expected_dmc).abs() < 1e-15,
+                "DMC at {minutes} min: {} != {expected_dmc}",
+                state.empirical.dmc_km_s2[2]
+            );
+            assert_eq!(state.empirical.constant_km_s2, empirical.constant_km_s2);
+        }
+
+        // The columns of the STM for the in-track constant acceleration and the DMC match the finite differences
+        let stm = end_stm.stm().unwrap();
+        for (j, step) in [(7, 1e-8), (17, 1e-8)] {
+            let perturbed = |delta: f64| {
+                let mut state = start;
+                let mut params = state.empirical.to_vector();
+                params[j - 6] += delta;
+                state.empirical.set_vector(&params);
+                Propagator::default(dynamics.clone())
+                    .with(state)
+                    .for_duration(duration)
+                    .unwrap()
+                    .orbit
+                    .to_cartesian_vec()
+            };
+            let column = (perturbed(step) - perturbed(-step)) / (2.0 * step);
+            for i in 0..6 {
+                assert!(
+                    (stm[(i, j)] - column[i]).abs() < 1e-3 * column.norm(),
+                    "STM ({i}, {j}): {} != {}",
+                    stm[(i, j)],
+                    column[i]
+                );
+            }
+        }
+
+        // The process noise only applies to the DMC
+        let noise = end.process_noise(Unit::Minute * 30).unwrap();
+        let variance = 1e-16 * (1.0 - (-2.0_f64).exp());
+        for i in 0..18 {
+            let expected = if i >= 15 { variance } else { 0.0 };
+            assert!((noise[(i, i)] - expected).abs() < 1e-30);
+        }
+        assert!(EmpiricalOrbit::new(leo, EmpiricalAccel::new(epoch))
+            .process_noise(Unit::Minute * 30)
+            .is_none());
+
+        // The parameters can be read and set by name
+        let mut state = end;
+        state
+            .set_value(crate::md::StateParameter::EmpiricalConstantI, 4e-9)
+            .unwrap();
+        assert_eq!(
+            state
+                .value(crate::md::StateParameter::EmpiricalConstantI)
+                .unwrap(),
+            4e-9
+        );
+        assert_eq!(
+            state.value(crate::md::StateParameter::SMA).unwrap(),
+            end.orbit.sma_km()
+        );
+    }
+}
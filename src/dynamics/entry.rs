@@ -0,0 +1,833 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::drag::AtmosphereFlow;
+use super::guidance::BankAngleLaw;
+use super::{Drag, DynamicsError, ForceModel};
+use crate::cosmic::{Cosm, Orbit, Spacecraft};
+use crate::errors::NyxError;
+use crate::linalg::{Matrix3x6, Vector3};
+use crate::md::prelude::Traj;
+use crate::md::EventEvaluator;
+use crate::time::{Duration, Epoch, Unit};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::read_to_string;
+use std::sync::Arc;
+
+/// Sutton-Graves constant of the Earth atmosphere, in kg^0.5/m
+pub const SUTTON_GRAVES_EARTH: f64 = 1.7415e-4;
+/// Sutton-Graves constant of the Mars atmosphere, in kg^0.5/m
+pub const SUTTON_GRAVES_MARS: f64 = 1.9027e-4;
+
+/// Lift and drag coefficients of the vehicle at a given Mach number and angle of attack
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AeroPoint {
+    pub mach: f64,
+    pub aoa_deg: f64,
+    pub cl: f64,
+    pub cd: f64,
+}
+
+/// Aerodynamic coefficients of an entry vehicle as a function of the Mach number and of the angle of attack.
+///
+/// The coefficients are bilinearly interpolated on the grid of the table, and held constant outside of it.
+/// The vehicle is assumed to fly at its trim angle of attack, and the reference area is the drag area of the spacecraft.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AeroTable {
+    /// Mach numbers of the grid, sorted
+    mach: Vec<f64>,
+    /// Angles of attack of the grid in degrees, sorted
+    aoa_deg: Vec<f64>,
+    /// Lift coefficients, for each Mach number then each angle of attack
+    cl: Vec<f64>,
+    /// Drag coefficients, for each Mach number then each angle of attack
+    cd: Vec<f64>,
+    /// Trim angle of attack in degrees
+    pub trim_aoa_deg: f64,
+}
+
+impl AeroTable {
+    /// Initializes a table with constant coefficients, e.g. a ballistic capsule with a zero lift coefficient
+    pub fn constant(cl: f64, cd: f64) -> Self {
+        Self {
+            mach: vec![0.0],
+            aoa_deg: vec![0.0],
+            cl: vec![cl],
+            cd: vec![cd],
+            trim_aoa_deg: 0.0,
+        }
+    }
+
+    /// Initializes a table from the provided points, which must cover every combination of their Mach numbers and angles of attack exactly once
+    pub fn new(points: &[AeroPoint]) -> Result<Self, NyxError> {
+        if points.is_empty() {
+            return Err(NyxError::CustomError {
+                msg: "Aerodynamic table is empty".to_string(),
+            });
+        }
+
+        let axis = |value: fn(&AeroPoint) -> f64| -> Vec<f64> {
+            let mut axis = points.iter().map(value).collect::<Vec<f64>>();
+            axis.sort_by(|a, b| a.total_cmp(b));
+            axis.dedup();
+            axis
+        };
+        let mach = axis(|point| point.mach);
+        let aoa_deg = axis(|point| point.aoa_deg);
+
+        if mach.len() * aoa_deg.len() != points.len() {
+            return Err(NyxError::CustomError {
+                msg: format!(
+                    "Aerodynamic table of {} points is not a grid of {} Mach numbers and {} angles of attack",
+                    points.len(),
+                    mach.len(),
+                    aoa_deg.len()
+                ),
+            });
+        }
+
+        let mut cl = vec![f64::NAN; points.len()];
+        let mut cd = vec![f64::NAN; points.len()];
+        for point in points {
+            let i = mach.partition_point(|m| *m < point.mach);
+            let j = aoa_deg.partition_point(|a| *a < point.aoa_deg);
+            let idx = i * aoa_deg.len() + j;
+            if !cl[idx].is_nan() {
+                return Err(NyxError::CustomError {
+                    msg: format!(
+                        "Aerodynamic table has several points at Mach {} and {} deg",
+                        point.mach, point.aoa_deg
+                    ),
+                });
+            }
+            cl[idx] = point.cl;
+            cd[idx] = point.cd;
+        }
+
+        Ok(Self {
+            mach,
+            aoa_deg,
+            cl,
+            cd,
+            trim_aoa_deg: 0.0,
+        })
+    }
+
+    /// Loads a table from a CSV file with the `mach`, `aoa_deg`, `cl` and `cd` columns
+    pub fn from_csv(filepath: &str) -> Result<Self, NyxError> {
+        let content = read_to_string(filepath).map_err(|e| NyxError::FileUnreadable {
+            msg: format!("{filepath}: {e}"),
+        })?;
+        Self::from_csv_str(&content)
+    }
+
+    /// Parses a table from the content of a CSV file with the `mach`, `aoa_deg`, `cl` and `cd` columns
+    pub fn from_csv_str(content: &str) -> Result<Self, NyxError> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(content.as_bytes());
+        let points = rdr
+            .deserialize()
+            .collect::<Result<Vec<AeroPoint>, csv::Error>>()
+            .map_err(|e| NyxError::FileUnreadable {
+                msg: format!("aerodynamic table: {e}"),
+            })?;
+        Self::new(&points)
+    }
+
+    /// Sets the trim angle of attack in degrees
+    pub fn with_trim_aoa_deg(self, trim_aoa_deg: f64) -> Self {
+        let mut me = self;
+        me.trim_aoa_deg = trim_aoa_deg;
+        me
+    }
+
+    /// Returns the lift and drag coefficients at the provided Mach number and angle of attack in degrees
+    pub fn coefficients(&self, mach: f64, aoa_deg: f64) -> (f64, f64) {
+        let (i0, i1, u) = bracket(&self.mach, mach);
+        let (j0, j1, v) = bracket(&self.aoa_deg, aoa_deg);
+        let n = self.aoa_deg.len();
+        let interp = |table: &[f64]| {
+            (1.0 - u) * ((1.0 - v) * table[i0 * n + j0] + v * table[i0 * n + j1])
+                + u * ((1.0 - v) * table[i1 * n + j0] + v * table[i1 * n + j1])
+        };
+        (interp(&self.cl), interp(&self.cd))
+    }
+
+    /// Returns the lift and drag coefficients at the provided Mach number and at the trim angle of attack
+    pub fn trim_coefficients(&self, mach: f64) -> (f64, f64) {
+        self.coefficients(mach, self.trim_aoa_deg)
+    }
+}
+
+/// Returns the indexes bracketing the provided value in the sorted axis and the interpolation ratio between them, clamped to the axis
+fn bracket(axis: &[f64], value: f64) -> (usize, usize, f64) {
+    let idx = axis.partition_point(|x| *x <= value);
+    if idx == 0 {
+        (0, 0, 0.0)
+    } else if idx == axis.len() {
+        (idx - 1, idx - 1, 0.0)
+    } else {
+        (
+            idx - 1,
+            idx,
+            (value - axis[idx - 1]) / (axis[idx] - axis[idx - 1]),
+        )
+    }
+}
+
+/// Speed of sound in the atmosphere, to compute the Mach number
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpeedOfSound {
+    /// Constant speed of sound in km/s
+    Constant(f64),
+    /// Temperature of the U.S. Standard Atmosphere 1976, which is held constant above 86 km
+    Earth1976,
+    /// Temperature of the NASA Glenn model of the Mars atmosphere, which is floored at 130 K in the upper atmosphere
+    Mars,
+}
+
+impl SpeedOfSound {
+    /// Returns the speed of sound in km/s at the provided geometric altitude in km
+    pub fn speed_km_s(&self, altitude_km: f64) -> f64 {
+        match self {
+            Self::Constant(speed_km_s) => *speed_km_s,
+            Self::Earth1976 => {
+                // Base geopotential altitudes in km, temperatures in K and lapse rates in K/km of each layer
+                const LAYERS: [(f64, f64, f64); 7] = [
+                    (0.0, 288.15, -6.5),
+                    (11.0, 216.65, 0.0),
+                    (20.0, 216.65, 1.0),
+                    (32.0, 228.65, 2.8),
+                    (47.0, 270.65, 0.0),
+                    (51.0, 270.65, -2.8),
+                    (71.0, 214.65, -2.0),
+                ];
+                let geopotential_km =
+                    (6_356.766 * altitude_km / (6_356.766 + altitude_km)).clamp(0.0, 84.852);
+                let (base_km, base_k, lapse) = LAYERS
+                    .iter()
+                    .rev()
+                    .find(|(base_km, _, _)| *base_km <= geopotential_km)
+                    .unwrap_or(&LAYERS[0]);
+                let temperature_k = base_k + lapse * (geopotential_km - base_km);
+                (1.4 * 287.053 * temperature_k).sqrt() * 1e-3
+            }
+            Self::Mars => {
+                let altitude_m = altitude_km.max(0.0) * 1e3;
+                let temperature_c = if altitude_m < 7_000.0 {
+                    -31.0 - 0.000998 * altitude_m
+                } else {
+                    -23.4 - 0.00222 * altitude_m
+                };
+                (1.29 * 188.92 * (temperature_c + 273.15).max(130.0)).sqrt() * 1e-3
+            }
+        }
+    }
+}
+
+/// State of an entry vehicle relative to the planet and its atmosphere
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EntryState {
+    pub epoch: Epoch,
+    /// Geodetic altitude in km
+    pub altitude_km: f64,
+    pub latitude_deg: f64,
+    pub longitude_deg: f64,
+    /// Norm of the velocity relative to the atmosphere in km/s
+    pub rel_velocity_km_s: f64,
+    /// Angle of the relative velocity above the local horizontal, negative when descending
+    pub flight_path_angle_deg: f64,
+    /// Azimuth of the relative velocity, clockwise from the north, between 0 and 360 degrees
+    pub heading_deg: f64,
+    pub density_kg_m3: f64,
+    pub mach: f64,
+    pub dynamic_pressure_pa: f64,
+    /// Convective heat rate at the stagnation point from the Sutton-Graves relation
+    pub heat_rate_w_cm2: f64,
+}
+
+impl fmt::Display for EntryState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{}] alt. = {:.3} km\tlat. = {:.3} deg\tlong. = {:.3} deg\tvel. = {:.6} km/s\tFPA = {:.3} deg\theading = {:.3} deg\tMach = {:.2}\tq = {:.1} Pa\theat rate = {:.3} W/cm^2",
+            self.epoch,
+            self.altitude_km,
+            self.latitude_deg,
+            self.longitude_deg,
+            self.rel_velocity_km_s,
+            self.flight_path_angle_deg,
+            self.heading_deg,
+            self.mach,
+            self.dynamic_pressure_pa,
+            self.heat_rate_w_cm2
+        )
+    }
+}
+
+/// Aerodynamic forces of a lifting entry vehicle in three degrees of freedom, i.e. the drag and the lift modulated by the bank angle.
+///
+/// This replaces the `Drag` in the spacecraft dynamics, whose density model, frame and winds it reuses.
+/// The aerodynamic coefficients come from the table at the trim angle of attack, instead of the drag coefficient of the spacecraft.
+#[derive(Clone)]
+pub struct EntryAerodynamics {
+    /// Density model, body fixed frame and winds of the atmosphere
+    pub drag: Arc<Drag>,
+    pub aero: AeroTable,
+    pub bank_law: Arc<dyn BankAngleLaw>,
+    pub sound_speed: SpeedOfSound,
+    /// Nose radius of the vehicle in meters, for the heat rate
+    pub nose_radius_m: f64,
+    /// Sutton-Graves constant of the atmosphere in kg^0.5/m
+    pub sutton_graves_k: f64,
+}
+
+impl EntryAerodynamics {
+    /// Initializes the entry aerodynamics in the provided atmosphere
+    pub fn new(
+        drag: Arc<Drag>,
+        aero: AeroTable,
+        bank_law: Arc<dyn BankAngleLaw>,
+        sound_speed: SpeedOfSound,
+        nose_radius_m: f64,
+        sutton_graves_k: f64,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            drag,
+            aero,
+            bank_law,
+            sound_speed,
+            nose_radius_m,
+            sutton_graves_k,
+        })
+    }
+
+    /// Entry in the exponential atmosphere of the Earth from the sea level, with the speed of sound of the standard atmosphere
+    pub fn earth(
+        aero: AeroTable,
+        bank_law: Arc<dyn BankAngleLaw>,
+        nose_radius_m: f64,
+        cosm: Arc<Cosm>,
+    ) -> Arc<Self> {
+        Self::new(
+            Drag::earth_sea_level_exp(cosm),
+            aero,
+            bank_law,
+            SpeedOfSound::Earth1976,
+            nose_radius_m,
+            SUTTON_GRAVES_EARTH,
+        )
+    }
+
+    /// Entry in the exponential atmosphere of Mars
+    pub fn mars(
+        aero: AeroTable,
+        bank_law: Arc<dyn BankAngleLaw>,
+        nose_radius_m: f64,
+        cosm: Arc<Cosm>,
+    ) -> Arc<Self> {
+        Self::new(
+            Drag::mars_exp(cosm),
+            aero,
+            bank_law,
+            SpeedOfSound::Mars,
+            nose_radius_m,
+            SUTTON_GRAVES_MARS,
+        )
+    }
+
+    /// Computes the flow of the atmosphere around the vehicle and its planet relative state
+    fn flow(&self, orbit: &Orbit) -> Result<(AtmosphereFlow, EntryState), DynamicsError> {
+        let flow = AtmosphereFlow::new(
+            &self.drag.cosm,
+            self.drag.drag_frame,
+            self.drag.wind.as_deref(),
+            orbit,
+        )?;
+        let fixed = flow.osc_fixed;
+        let density_kg_m3 = self.drag.density_at(&fixed)?;
+
+        let altitude_km = fixed.geodetic_height_km();
+        let latitude_deg = fixed.geodetic_latitude_deg();
+        let longitude_deg = fixed.geodetic_longitude_deg();
+        let velocity = fixed.velocity();
+        let speed_km_s = velocity.norm();
+
+        let (sin_lon, cos_lon) = longitude_deg.to_radians().sin_cos();
+        let (sin_lat, cos_lat) = latitude_deg.to_radians().sin_cos();
+        let east = Vector3::new(-sin_lon, cos_lon, 0.0);
+        let north = Vector3::new(-sin_lat * cos_lon, -sin_lat * sin_lon, cos_lat);
+        let up = east.cross(&north);
+
+        let (flight_path_angle_deg, heading_deg) = if speed_km_s > 0.0 {
+            (
+                (velocity.dot(&up) / speed_km_s)
+                    .clamp(-1.0, 1.0)
+                    .asin()
+                    .to_degrees(),
+                velocity
+                    .dot(&east)
+                    .atan2(velocity.dot(&north))
+                    .to_degrees()
+                    .rem_euclid(360.0),
+            )
+        } else {
+            (0.0, 0.0)
+        };
+
+        let speed_m_s = speed_km_s * 1e3;
+        let state = EntryState {
+            epoch: orbit.epoch,
+            altitude_km,
+            latitude_deg,
+            longitude_deg,
+            rel_velocity_km_s: speed_km_s,
+            flight_path_angle_deg,
+            heading_deg,
+            density_kg_m3,
+            mach: speed_km_s / self.sound_speed.speed_km_s(altitude_km),
+            dynamic_pressure_pa: 0.5 * density_kg_m3 * speed_m_s.powi(2),
+            heat_rate_w_cm2: self.sutton_graves_k
+                * (density_kg_m3 / self.nose_radius_m).sqrt()
+                * speed_m_s.powi(3)
+                * 1e-4,
+        };
+
+        Ok((flow, state))
+    }
+
+    /// Returns the state of the vehicle relative to the planet and its atmosphere
+    pub fn entry_state(&self, sc: &Spacecraft) -> Result<EntryState, DynamicsError> {
+        Ok(self.flow(&sc.orbit)?.1)
+    }
+
+    /// Returns the planet relative states along the provided trajectory, every provided step, e.g. to analyze an entry corridor
+    pub fn entry_states(
+        &self,
+        traj: &Traj<Spacecraft>,
+        step: Duration,
+    ) -> Result<Vec<EntryState>, DynamicsError> {
+        traj.every(step).map(|sc| self.entry_state(&sc)).collect()
+    }
+}
+
+impl fmt::Display for EntryAerodynamics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "\tEntry aerodynamics with {} at trim AoA {} deg in {}",
+            self.bank_law, self.aero.trim_aoa_deg, self.drag
+        )
+    }
+}
+
+impl ForceModel for EntryAerodynamics {
+    fn eom(&self, ctx: &Spacecraft) -> Result<Vector3<f64>, DynamicsError> {
+        let (flow, state) = self.flow(&ctx.orbit)?;
+
+        let velocity = flow.dcm_to_integr * flow.velocity;
+        if velocity.norm() <= 0.0 {
+            return Ok(Vector3::zeros());
+        }
+        let v_hat = velocity / velocity.norm();
+
+        // The lift is perpendicular to the relative velocity, in the vertical plane when the bank angle is zero
+        let radius = flow.dcm_to_integr * flow.radius;
+        let vertical = radius - radius.dot(&v_hat) * v_hat;
+        let lift_up = if vertical.norm() > 0.0 {
+            vertical / vertical.norm()
+        } else {
+            Vector3::zeros()
+        };
+        let bank = self.bank_law.bank_angle_deg(&state, ctx).to_radians();
+        let lift_hat = bank.cos() * lift_up + bank.sin() * v_hat.cross(&lift_up);
+
+        let (cl, cd) = self.aero.trim_coefficients(state.mach);
+        // The factor 1e-3 converts the force from N to kN, as expected by the spacecraft dynamics
+        let force_kn = 1e-3 * state.dynamic_pressure_pa * ctx.drag.area_m2;
+        Ok(force_kn * (cl * lift_hat - cd * v_hat))
+    }
+
+    /// The partials are computed by central differences, since the coefficients and the bank angle depend on the state.
+    fn dual_eom(&self, ctx: &Spacecraft) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError> {
+        let state = ctx.orbit.to_cartesian_vec();
+        let mut grad = Matrix3x6::zeros();
+        for j in 0..6 {
+            let step = if j < 3 { 1e-3 } else { 1e-6 };
+            let perturbed = |delta: f64| {
+                let mut perturbed_state = state;
+                perturbed_state[j] += delta;
+                let mut osc_ctx = *ctx;
+                osc_ctx.orbit =
+                    Orbit::cartesian_vec(&perturbed_state, ctx.orbit.epoch, ctx.orbit.frame);
+                self.eom(&osc_ctx)
+            };
+            grad.set_column(j, &((perturbed(step)? - perturbed(-step)?) / (2.0 * step)));
+        }
+        Ok((self.eom(ctx)?, grad))
+    }
+}
+
+/// Condition of the planet relative state at which an entry event occurs
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EntryCondition {
+    /// Geodetic altitude in km
+    Altitude(f64),
+    Mach(f64),
+    /// Dynamic pressure in Pa
+    DynamicPressure(f64),
+    /// Velocity relative to the atmosphere in km/s
+    RelativeVelocity(f64),
+}
+
+/// An event of the descent of an entry vehicle, e.g. the parachute deployment or the touchdown
+#[derive(Clone)]
+pub struct EntryEvent {
+    pub entry: Arc<EntryAerodynamics>,
+    pub condition: EntryCondition,
+}
+
+impl EntryEvent {
+    pub fn new(entry: Arc<EntryAerodynamics>, condition: EntryCondition) -> Self {
+        Self { entry, condition }
+    }
+
+    /// Finds the touchdown, i.e. when the geodetic altitude is zero
+    pub fn touchdown(entry: Arc<EntryAerodynamics>) -> Self {
+        Self::new(entry, EntryCondition::Altitude(0.0))
+    }
+
+    /// Finds the deployment of a parachute triggered at the provided Mach number
+    pub fn parachute_deploy(entry: Arc<EntryAerodynamics>, mach: f64) -> Self {
+        Self::new(entry, EntryCondition::Mach(mach))
+    }
+
+    /// Returns the value of the condition at the provided planet relative state and its desired value
+    fn values(&self, state: &EntryState) -> (f64, f64) {
+        match self.condition {
+            EntryCondition::Altitude(km) => (state.altitude_km, km),
+            EntryCondition::Mach(mach) => (state.mach, mach),
+            EntryCondition::DynamicPressure(pa) => (state.dynamic_pressure_pa, pa),
+            EntryCondition::RelativeVelocity(km_s) => (state.rel_velocity_km_s, km_s),
+        }
+    }
+}
+
+impl fmt::Display for EntryEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.condition {
+            EntryCondition::Altitude(km) => write!(f, "entry altitude = {km} km"),
+            EntryCondition::Mach(mach) => write!(f, "entry Mach = {mach}"),
+            EntryCondition::DynamicPressure(pa) => write!(f, "entry dynamic pressure = {pa} Pa"),
+            EntryCondition::RelativeVelocity(km_s) => {
+                write!(f, "entry relative velocity = {km_s} km/s")
+            }
+        }
+    }
+}
+
+impl EventEvaluator<Spacecraft> for EntryEvent {
+    /// Returns NaN if the planet relative state cannot be computed, e.g. if the frame data is unavailable
+    fn eval(&self, sc: &Spacecraft) -> f64 {
+        match self.entry.entry_state(sc) {
+            Ok(state) => {
+                let (value, desired) = self.values(&state);
+                value - desired
+            }
+            Err(_) => f64::NAN,
+        }
+    }
+
+    /// Stop searching when the time has converged to less than 1 millisecond
+    fn epoch_precision(&self) -> Duration {
+        1 * Unit::Millisecond
+    }
+
+    /// Finds the altitude within a meter, the Mach number within 1e-3, the dynamic pressure within 0.1 Pa, or the velocity within 1 mm/s
+    fn value_precision(&self) -> f64 {
+        match self.condition {
+            EntryCondition::Altitude(_) => 1e-3,
+            EntryCondition::Mach(_) => 1e-3,
+            EntryCondition::DynamicPressure(_) => 1e-1,
+            EntryCondition::RelativeVelocity(_) => 1e-6,
+        }
+    }
+
+    fn eval_string(&self, sc: &Spacecraft) -> String {
+        match self.entry.entry_state(sc) {
+            Ok(state) => format!("{state}"),
+            Err(e) => format!("{self}: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod ut_entry {
+    use super::*;
+    use crate::cosmic::Bodies;
+    use crate::dynamics::guidance::ConstantBank;
+    use crate::dynamics::{OrbitalDynamics, SpacecraftDynamics};
+    use crate::propagators::Propagator;
+
+    /// Capsule of 500 kg and 3 m^2 entering the atmosphere of the Earth eastward along the equator, at 120 km and 7.5 km/s
+    fn capsule(cosm: &Cosm) -> Spacecraft {
+        let eme2k = cosm.frame("EME2000");
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        let fpa = (-6.0_f64).to_radians();
+        let orbit = Orbit::cartesian(
+            eme2k.equatorial_radius() + 120.0,
+            0.0,
+            0.0,
+            7.5 * fpa.sin(),
+            7.5 * fpa.cos(),
+            0.0,
+            epoch,
+            eme2k,
+        );
+        Spacecraft::new(orbit, 500.0, 0.0, 0.0, 3.0, 1.0, 1.3)
+    }
+
+    #[test]
+    fn test_aero_table() {
+        let table = AeroTable::from_csv_str(
+            "mach, aoa_deg, cl, cd
+            2.0, 10.0, 0.4, 1.4
+            0.5, 0.0, 0.0, 1.0
+            2.0, 0.0, 0.2, 1.2
+            0.5, 10.0, 0.2, 1.1",
+        )
+        .unwrap()
+        .with_trim_aoa_deg(5.0);
+
+        // Bilinear interpolation inside the grid, and constant coefficients outside of it
+        let (cl, cd) = table.coefficients(1.25, 5.0);
+        assert!((cl - 0.2).abs() < 1e-12);
+        assert!((cd - 1.175).abs() < 1e-12);
+        assert_eq!(table.coefficients(0.1, -5.0), (0.0, 1.0));
+        assert_eq!(table.coefficients(30.0, 20.0), (0.4, 1.4));
+        assert_eq!(table.trim_coefficients(1.25), table.coefficients(1.25, 5.0));
+
+        // The table must be a full grid
+        let point = |mach, aoa_deg| AeroPoint {
+            mach,
+            aoa_deg,
+            cl: 0.0,
+            cd: 1.0,
+        };
+        assert!(AeroTable::new(&[point(0.5, 0.0), point(2.0, 10.0)]).is_err());
+        assert!(AeroTable::new(&[point(0.5, 0.0), point(0.5, 0.0)]).is_err());
+        assert!(AeroTable::new(&[]).is_err());
+    }
+
+    #[test]
+    fn test_speed_of_sound() {
+        let earth = SpeedOfSound::Earth1976;
+        assert!((earth.speed_km_s(0.0) - 0.340_294).abs() < 1e-6);
+        assert!((earth.speed_km_s(15.0) - 0.295_070).abs() < 1e-6);
+        assert_eq!(earth.speed_km_s(120.0), earth.speed_km_s(90.0));
+        // Surface of Mars at -31 C
+        assert!((SpeedOfSound::Mars.speed_km_s(0.0) - 0.242_928).abs() < 1e-6);
+        assert_eq!(SpeedOfSound::Constant(0.3).speed_km_s(10.0), 0.3);
+    }
+
+    #[test]
+    fn test_ballistic_entry() {
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let sc = capsule(&cosm);
+        let entry = EntryAerodynamics::earth(
+            AeroTable::constant(0.0, 1.3),
+            ConstantBank::new(0.0),
+            1.0,
+            cosm.clone(),
+        );
+
+        let initial = entry.entry_state(&sc).unwrap();
+        assert!((initial.altitude_km - 120.0).abs() < 0.1);
+        assert!((initial.heading_deg - 90.0).abs() < 0.1);
+        assert!(initial.flight_path_angle_deg < -6.0);
+        assert!(
+            (initial.dynamic_pressure_pa
+                - 0.5 * initial.density_kg_m3 * (initial.rel_velocity_km_s * 1e3).powi(2))
+            .abs()
+                < 1e-9
+        );
+
+        // Without lift, the aerodynamic force is opposite to the velocity relative to the atmosphere
+        let force = entry.eom(&sc).unwrap();
+        let relative =
+            sc.orbit.velocity() - sc.orbit.radius().cross(&Vector3::z()).scale(-7.292e-5);
+        assert!(force.dot(&relative) / (force.norm() * relative.norm()) < -1.0 + 1e-6);
+        let (dual_force, grad) = entry.dual_eom(&sc).unwrap();
+        assert_eq!(dual_force, force);
+        assert!(grad.iter().all(|partial| partial.is_finite()));
+
+        // The capsule lands after about seven minutes
+        let dynamics = SpacecraftDynamics::from_model(OrbitalDynamics::two_body(), entry.clone());
+        let (_, traj) = Propagator::default(dynamics)
+            .with(sc)
+            .for_duration_with_traj(420 * Unit::Second)
+            .unwrap();
+
+        let touchdown = traj.find(&EntryEvent::touchdown(entry.clone())).unwrap();
+        assert_eq!(touchdown.len(), 1);
+        let landed = entry.entry_state(&touchdown[0].state).unwrap();
+        assert!(landed.altitude_km.abs() < 1e-3);
+        // At the terminal velocity at sea level, i.e. sqrt(2 m g / (rho Cd A))
+        assert!((landed.rel_velocity_km_s - 0.046).abs() < 2e-3, "{landed}");
+
+        let parachute = traj
+            .find(&EntryEvent::parachute_deploy(entry.clone(), 2.0))
+            .unwrap();
+        assert_eq!(parachute.len(), 1);
+        let deploy = entry.entry_state(&parachute[0].state).unwrap();
+        assert!((deploy.mach - 2.0).abs() < 1e-3);
+        assert!(deploy.epoch < landed.epoch);
+
+        // Peak heating happens well before the parachute deployment
+        let states = entry.entry_states(&traj, Unit::Second * 1).unwrap();
+        let peak_heating = states
+            .iter()
+            .max_by(|a, b| a.heat_rate_w_cm2.total_cmp(&b.heat_rate_w_cm2))
+            .unwrap();
+        assert!(
+            peak_heating.heat_rate_w_cm2 > 50.0 && peak_heating.heat_rate_w_cm2 < 150.0,
+            "{peak_heating}"
+        );
+        assert!(peak_heating.epoch < deploy.epoch);
+    }
+
+    #[test]
+    fn test_lifting_entry() {
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let sc = capsule(&cosm);
+        let peak_dynamic_pressure = |cl: f64, bank_deg: f64| {
+            let entry = EntryAerodynamics::earth(
+                AeroTable::constant(cl, 1.3),
+                ConstantBank::new(bank_deg),
+                1.0,
+                cosm.clone(),
+            );
+            let dynamics =
+                SpacecraftDynamics::from_model(OrbitalDynamics::two_body(), entry.clone());
+            let (_, traj) = Propagator::default(dynamics)
+                .with(sc)
+                .for_duration_with_traj(150 * Unit::Second)
+                .unwrap();
+            let states = entry.entry_states(&traj, Unit::Second * 1).unwrap();
+            let peak = states
+                .iter()
+                .map(|state| state.dynamic_pressure_pa)
+                .fold(0.0, f64::max);
+            (peak, *states.last().unwrap())
+        };
+
+        // Flying lift up lowers the peak dynamic pressure, and flying lift down increases it
+        let (ballistic, _) = peak_dynamic_pressure(0.0, 0.0);
+        let (lift_up, _) = peak_dynamic_pressure(0.4, 0.0);
+        let (lift_down, _) = peak_dynamic_pressure(0.4, 180.0);
+        assert!(lift_up < 0.75 * ballistic, "{lift_up} {ballistic}");
+        assert!(lift_down > ballistic, "{lift_down} {ballistic}");
+
+        // A positive bank angle turns the vehicle to the right, i.e. southward when heading east
+        let (_, right) = peak_dynamic_pressure(0.4, 90.0);
+        let (_, left) = peak_dynamic_pressure(0.4, -90.0);
+        assert!(right.heading_deg > 90.5, "{right}");
+        assert!(left.heading_deg < 89.5, "{left}");
+        assert!(right.latitude_deg < left.latitude_deg);
+    }
+
+    #[test]
+    fn test_mars_entry() {
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let mars_j2k = cosm.frame("Mars Barycenter J2000");
+        // The body frame of Mars rotates like the IAU frame of its barycenter
+        let mars = cosm.frame("Mars J2000");
+        assert_eq!(Bodies::try_from(mars.ephem_path()).unwrap(), Bodies::Mars);
+        assert_eq!(
+            mars.try_angular_velocity().unwrap(),
+            cosm.frame("IAU Mars").try_angular_velocity().unwrap()
+        );
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        // Entry at 125 km and 5.8 km/s, with a flight path angle of -15 degrees
+        let fpa = (-15.0_f64).to_radians();
+        let orbit = Orbit::cartesian(
+            mars_j2k.equatorial_radius() + 125.0,
+            0.0,
+            0.0,
+            5.8 * fpa.sin(),
+            5.8 * fpa.cos(),
+            0.0,
+            epoch,
+            mars_j2k,
+        );
+        let sc = Spacecraft::new(orbit, 500.0, 0.0, 0.0, 3.0, 1.0, 1.3);
+        let entry = EntryAerodynamics::mars(
+            AeroTable::constant(0.0, 1.3),
+            ConstantBank::new(0.0),
+            1.0,
+            cosm.clone(),
+        );
+
+        // The atmosphere co-rotates with Mars, whose equatorial speed is about 0.24 km/s
+        let initial = entry.entry_state(&sc).unwrap();
+        let rotation_km_s = (sc.orbit.vmag_km_s() - initial.rel_velocity_km_s).abs();
+        assert!(rotation_km_s > 0.1 && rotation_km_s < 0.241, "{initial}");
+        assert!(initial.mach > 30.0, "{initial}");
+
+        // The capsule lands after about three minutes
+        let dynamics = SpacecraftDynamics::from_model(OrbitalDynamics::two_body(), entry.clone());
+        let (_, traj) = Propagator::default(dynamics)
+            .with(sc)
+            .for_duration_with_traj(240 * Unit::Second)
+            .unwrap();
+
+        let touchdown = traj.find(&EntryEvent::touchdown(entry.clone())).unwrap();
+        assert_eq!(touchdown.len(), 1);
+        let landed = entry.entry_state(&touchdown[0].state).unwrap();
+        assert!(landed.altitude_km.abs() < 1e-3);
+        // Close to the terminal velocity at the surface, i.e. sqrt(2 m g / (rho Cd A)), since the thin atmosphere barely slows the capsule down
+        assert!((landed.rel_velocity_km_s - 0.218).abs() < 0.02, "{landed}");
+
+        // The capsule is still supersonic in the lower atmosphere, unlike on Earth
+        let parachute = traj
+            .find(&EntryEvent::parachute_deploy(entry.clone(), 2.0))
+            .unwrap();
+        assert_eq!(parachute.len(), 1);
+        let deploy = entry.entry_state(&parachute[0].state).unwrap();
+        assert!((deploy.mach - 2.0).abs() < 1e-3);
+        assert!(
+            deploy.altitude_km > 5.0 && deploy.altitude_km < 10.0,
+            "{deploy}"
+        );
+
+        // Peak heating happens high in the atmosphere, well before the parachute deployment
+        let states = entry.entry_states(&traj, Unit::Second * 1).unwrap();
+        let peak_heating = states
+            .iter()
+            .max_by(|a, b| a.heat_rate_w_cm2.total_cmp(&b.heat_rate_w_cm2))
+            .unwrap();
+        assert!(
+            peak_heating.heat_rate_w_cm2 > 30.0 && peak_heating.heat_rate_w_cm2 < 100.0,
+            "{peak_heating}"
+        );
+        assert!(peak_heating.altitude_km > 30.0, "{peak_heating}");
+        assert!(peak_heating.epoch < deploy.epoch);
+    }
+}
@@ -0,0 +1,124 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::cosmic::Spacecraft;
+use crate::dynamics::EntryState;
+use crate::errors::NyxError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+/// The `BankAngleLaw` trait handles the guidance of the lift of an entry vehicle, which is modulated by rotating it about the
+/// velocity relative to the atmosphere. It is used by the `EntryAerodynamics` like a `GuidanceLaw` is used by the spacecraft dynamics.
+pub trait BankAngleLaw: fmt::Display + Send + Sync {
+    /// Returns the bank angle in degrees, from the planet relative state of the vehicle.
+    /// A zero bank angle points the lift up, i.e. away from the planet, and a positive bank angle rotates it to the right of the velocity.
+    fn bank_angle_deg(&self, entry: &EntryState, osc_state: &Spacecraft) -> f64;
+}
+
+/// Flies the entry at a constant bank angle, e.g. full lift up (0 degrees) or full lift down (180 degrees)
+#[derive(Copy, Clone, Debug)]
+pub struct ConstantBank {
+    pub bank_deg: f64,
+}
+
+impl ConstantBank {
+    /// Creates a new constant bank angle law, as an Arc
+    pub fn new(bank_deg: f64) -> Arc<Self> {
+        Arc::new(Self { bank_deg })
+    }
+}
+
+impl fmt::Display for ConstantBank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "constant bank of {} deg", self.bank_deg)
+    }
+}
+
+impl BankAngleLaw for ConstantBank {
+    fn bank_angle_deg(&self, _entry: &EntryState, _osc_state: &Spacecraft) -> f64 {
+        self.bank_deg
+    }
+}
+
+/// Bank angle of the vehicle from the provided velocity relative to the atmosphere
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BankSetting {
+    pub rel_velocity_km_s: f64,
+    pub bank_deg: f64,
+}
+
+/// Schedules the bank angle as a function of the velocity relative to the atmosphere, which decreases monotonically during the entry.
+///
+/// The bank angle is linearly interpolated between the settings, so a bank reversal is scheduled as two settings of opposite signs.
+/// The bank angle is held constant outside of the velocities of the profile.
+#[derive(Clone, Debug)]
+pub struct BankProfile {
+    /// Settings, sorted by increasing velocity
+    settings: Vec<BankSetting>,
+}
+
+impl BankProfile {
+    /// Creates a new bank angle profile from the provided settings, which will be sorted by velocity, as an Arc
+    pub fn new(settings: &[BankSetting]) -> Result<Arc<Self>, NyxError> {
+        if settings.is_empty() {
+            return Err(NyxError::GuidanceConfigError {
+                msg: "Bank angle profile is empty".to_string(),
+            });
+        }
+
+        let mut settings = settings.to_vec();
+        settings.sort_by(|a, b| a.rel_velocity_km_s.total_cmp(&b.rel_velocity_km_s));
+
+        Ok(Arc::new(Self { settings }))
+    }
+
+    /// Returns the settings, sorted by increasing velocity
+    pub fn settings(&self) -> &[BankSetting] {
+        &self.settings
+    }
+
+    /// Returns the bank angle in degrees at the provided velocity relative to the atmosphere
+    pub fn bank_at(&self, rel_velocity_km_s: f64) -> f64 {
+        let idx = self
+            .settings
+            .partition_point(|setting| setting.rel_velocity_km_s <= rel_velocity_km_s);
+        if idx == 0 {
+            self.settings[0].bank_deg
+        } else if idx == self.settings.len() {
+            self.settings[idx - 1].bank_deg
+        } else {
+            let (lo, hi) = (self.settings[idx - 1], self.settings[idx]);
+            let ratio = (rel_velocity_km_s - lo.rel_velocity_km_s)
+                / (hi.rel_velocity_km_s - lo.rel_velocity_km_s);
+            lo.bank_deg + ratio * (hi.bank_deg - lo.bank_deg)
+        }
+    }
+}
+
+impl fmt::Display for BankProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "bank angle profile of {} settings", self.settings.len())
+    }
+}
+
+impl BankAngleLaw for BankProfile {
+    fn bank_angle_deg(&self, entry: &EntryState, _osc_state: &Spacecraft) -> f64 {
+        self.bank_at(entry.rel_velocity_km_s)
+    }
+}
@@ -0,0 +1,373 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::Thruster;
+use crate::cosmic::eclipse::EclipseLocator;
+use crate::cosmic::{Cosm, Frame, Orbit, AU};
+use crate::dynamics::DynamicsError;
+use crate::errors::NyxError;
+use crate::time::{Epoch, Unit};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+/// A solar array whose power decreases with the square of the heliocentric distance, with its age, and in the shadow of the eclipsing bodies.
+#[derive(Clone)]
+pub struct SolarArray {
+    /// Power generated at 1 AU at the beginning of life, in kW
+    pub power_1au_kw: f64,
+    /// Fraction of the power lost every year, e.g. 0.02 for 2% per year
+    pub degradation_per_year: f64,
+    /// Beginning of life of the array, from which the degradation is computed
+    pub bol_epoch: Epoch,
+    /// Power consumed by the rest of the spacecraft, and therefore unavailable to the thrusters, in kW
+    pub bus_power_kw: f64,
+    /// Locates the eclipses of the Sun, which also provides the heliocentric distance
+    pub e_loc: EclipseLocator,
+}
+
+impl SolarArray {
+    /// Initializes a solar array without degradation nor bus power, shadowed by the provided bodies
+    pub fn new(
+        power_1au_kw: f64,
+        bol_epoch: Epoch,
+        shadow_bodies: Vec<Frame>,
+        cosm: Arc<Cosm>,
+    ) -> Self {
+        Self {
+            power_1au_kw,
+            degradation_per_year: 0.0,
+            bol_epoch,
+            bus_power_kw: 0.0,
+            e_loc: EclipseLocator {
+                light_source: cosm.frame("Sun J2000"),
+                shadow_bodies,
+                cosm,
+            },
+        }
+    }
+
+    /// Returns the power generated by the array at the provided state, in kW
+    pub fn power_kw(&self, osc: &Orbit) -> Result<f64, DynamicsError> {
+        let r_sun_au = self
+            .e_loc
+            .cosm
+            .try_frame_chg(osc, self.e_loc.light_source)
+            .map_err(|e| DynamicsError::DataUnavailable {
+                msg: format!("{e}"),
+            })?
+            .rmag_km()
+            / AU;
+
+        // Shadowing factor, zero in umbra
+        let k: f64 = self.e_loc.compute(osc).into();
+
+        let age_years = ((osc.epoch - self.bol_epoch).to_unit(Unit::Day) / 365.25).max(0.0);
+        let degradation = (1.0 - self.degradation_per_year).powf(age_years);
+
+        Ok(k * self.power_1au_kw * degradation / r_sun_au.powi(2))
+    }
+
+    /// Returns the power available to the thrusters at the provided state, i.e. after the bus consumption, in kW
+    pub fn available_power_kw(&self, osc: &Orbit) -> Result<f64, DynamicsError> {
+        Ok((self.power_kw(osc)? - self.bus_power_kw).max(0.0))
+    }
+}
+
+impl fmt::Display for SolarArray {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "solar array of {} kW at 1 AU (degradation = {} per year since {}, bus = {} kW, {})",
+            self.power_1au_kw,
+            self.degradation_per_year,
+            self.bol_epoch,
+            self.bus_power_kw,
+            self.e_loc
+        )
+    }
+}
+
+/// A row of the throttle table of an electric thruster.
+#[allow(non_snake_case)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ThrottleSetting {
+    /// Input power of the thruster, in kW
+    pub power_kw: f64,
+    /// Thrust at this input power, in Newtons
+    pub thrust_N: f64,
+    /// Isp at this input power, in seconds
+    pub isp_s: f64,
+}
+
+/// An electric thruster, whose thrust and Isp are interpolated from its throttle table against the power available from its solar array.
+///
+/// The thruster cannot operate if the available power is below the lowest setting of the table, and is capped at its highest setting.
+#[derive(Clone)]
+pub struct ElectricThruster {
+    /// Throttle table, sorted by increasing input power
+    settings: Vec<ThrottleSetting>,
+    /// Solar array powering the thruster
+    pub array: SolarArray,
+}
+
+impl ElectricThruster {
+    /// Creates a new electric thruster from its throttle table and solar array, as an Arc
+    /// Note: this returns an Arc so it can be plugged into the Spacecraft dynamics directly.
+    pub fn new(settings: &[ThrottleSetting], array: SolarArray) -> Result<Arc<Self>, NyxError> {
+        if settings.is_empty() {
+            return Err(NyxError::GuidanceConfigError {
+                msg: "Throttle table of electric thruster is empty".to_string(),
+            });
+        }
+
+        for setting in settings {
+            if setting.power_kw <= 0.0 || setting.thrust_N <= 0.0 || setting.isp_s <= 0.0 {
+                return Err(NyxError::GuidanceConfigError {
+                    msg: format!("Throttle setting must be strictly positive: {setting:?}"),
+                });
+            }
+        }
+
+        let mut settings = settings.to_vec();
+        settings.sort_by(|a, b| a.power_kw.total_cmp(&b.power_kw));
+
+        for pair in settings.windows(2) {
+            if pair[0].power_kw == pair[1].power_kw {
+                return Err(NyxError::GuidanceConfigError {
+                    msg: format!(
+                        "Throttle table has several settings at {} kW",
+                        pair[0].power_kw
+                    ),
+                });
+            }
+        }
+
+        Ok(Arc::new(Self { settings, array }))
+    }
+
+    /// Returns the throttle table, sorted by increasing input power
+    pub fn settings(&self) -> &[ThrottleSetting] {
+        &self.settings
+    }
+
+    /// Returns the thrust and Isp at the provided input power in kW, linearly interpolated between the settings of the throttle table,
+    /// or None if the power is insufficient to operate the thruster.
+    pub fn performance(&self, power_kw: f64) -> Option<Thruster> {
+        let first = self.settings.first()?;
+        let last = self.settings.last()?;
+
+        if power_kw < first.power_kw {
+            return None;
+        } else if power_kw >= last.power_kw {
+            return Some(Thruster {
+                thrust_N: last.thrust_N,
+                isp_s: last.isp_s,
+            });
+        }
+
+        let upper = self
+            .settings
+            .iter()
+            .position(|setting| setting.power_kw > power_kw)?;
+        let (lo, hi) = (self.settings[upper - 1], self.settings[upper]);
+        let ratio = (power_kw - lo.power_kw) / (hi.power_kw - lo.power_kw);
+
+        Some(Thruster {
+            thrust_N: lo.thrust_N + ratio * (hi.thrust_N - lo.thrust_N),
+            isp_s: lo.isp_s + ratio * (hi.isp_s - lo.isp_s),
+        })
+    }
+
+    /// Returns the thrust and Isp at the provided state given the power available from the solar array, or None if the thruster cannot operate.
+    /// Fails if the ephemerides of the Sun are not available at that state.
+    pub fn thruster(&self, osc: &Orbit) -> Result<Option<Thruster>, DynamicsError> {
+        Ok(self.performance(self.array.available_power_kw(osc)?))
+    }
+}
+
+impl fmt::Display for ElectricThruster {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "electric thruster with {} throttle settings from {} kW to {} kW, powered by {}",
+            self.settings.len(),
+            self.settings[0].power_kw,
+            self.settings[self.settings.len() - 1].power_kw,
+            self.array
+        )
+    }
+}
+
+#[cfg(test)]
+fn table() -> Vec<ThrottleSetting> {
+    // Roughly a Hall thruster of 4.5 kW
+    vec![
+        ThrottleSetting {
+            power_kw: 4.5,
+            thrust_N: 0.28,
+            isp_s: 1_850.0,
+        },
+        ThrottleSetting {
+            power_kw: 1.0,
+            thrust_N: 0.06,
+            isp_s: 1_200.0,
+        },
+        ThrottleSetting {
+            power_kw: 2.5,
+            thrust_N: 0.15,
+            isp_s: 1_600.0,
+        },
+    ]
+}
+
+#[test]
+fn ep_throttle_table() {
+    let cosm = Arc::new(Cosm::fixed_planets());
+    let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let array = SolarArray::new(5.0, epoch, vec![cosm.frame("EME2000")], cosm);
+
+    let ep = ElectricThruster::new(&table(), array.clone()).unwrap();
+    assert_eq!(ep.settings()[0].power_kw, 1.0);
+    assert_eq!(ep.settings()[2].power_kw, 4.5);
+
+    // Cannot operate below the lowest setting, and capped at the highest one
+    assert!(ep.performance(0.9).is_none());
+    let max = ep.performance(6.0).unwrap();
+    assert_eq!(max.thrust_N, 0.28);
+    assert_eq!(max.isp_s, 1_850.0);
+
+    // Linearly interpolated in between
+    let mid = ep.performance(1.75).unwrap();
+    assert!((mid.thrust_N - 0.105).abs() < 1e-12);
+    assert!((mid.isp_s - 1_400.0).abs() < 1e-9);
+    let exact = ep.performance(2.5).unwrap();
+    assert!((exact.thrust_N - 0.15).abs() < 1e-12);
+
+    // Invalid tables
+    assert!(ElectricThruster::new(&[], array.clone()).is_err());
+    let mut duplicated = table();
+    duplicated[1].power_kw = 4.5;
+    assert!(ElectricThruster::new(&duplicated, array.clone()).is_err());
+    let mut negative = table();
+    negative[0].isp_s = -1.0;
+    assert!(ElectricThruster::new(&negative, array).is_err());
+}
+
+#[test]
+fn ep_solar_array_power() {
+    let cosm = Arc::new(Cosm::fixed_planets());
+    let eme2k = cosm.frame("EME2000");
+    let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let mut array = SolarArray::new(5.0, epoch, vec![eme2k], cosm);
+
+    // The Sun is along -X in this test Cosm, about one AU away
+    let sunlit = Orbit::keplerian(7_000.0, 0.0, 0.0, 0.0, 0.0, 180.0, epoch, eme2k);
+    let r_sun_au = 1.5e8 / AU;
+    let power = array.power_kw(&sunlit).unwrap();
+    assert!((power - 5.0 / r_sun_au.powi(2)).abs() < 1e-3, "{power}");
+
+    // No power in the shadow of the Earth
+    let eclipsed = Orbit::keplerian(7_000.0, 0.0, 0.0, 0.0, 0.0, 0.0, epoch, eme2k);
+    assert_eq!(array.power_kw(&eclipsed).unwrap(), 0.0);
+
+    // Degraded by 2% after a year, minus the bus power
+    array.degradation_per_year = 0.02;
+    array.bus_power_kw = 0.5;
+    let mut one_year_later = sunlit;
+    one_year_later.epoch = epoch + 365.25 * Unit::Day;
+    let degraded = array.available_power_kw(&one_year_later).unwrap();
+    assert!((degraded - (0.98 * power - 0.5)).abs() < 1e-3, "{degraded}");
+    assert_eq!(array.available_power_kw(&eclipsed).unwrap(), 0.0);
+
+    // The ephemerides of this test Cosm end in 2316
+    let mut uncovered = sunlit;
+    uncovered.epoch = Epoch::from_gregorian_tai_at_midnight(2400, 1, 1);
+    assert!(matches!(
+        array.power_kw(&uncovered),
+        Err(DynamicsError::DataUnavailable { .. })
+    ));
+}
+
+#[test]
+fn ep_propagation_through_eclipse() {
+    use crate::cosmic::{GuidanceMode, Spacecraft};
+    use crate::dynamics::guidance::{Objective, QLaw, StateParameter};
+    use crate::dynamics::{OrbitalDynamics, SpacecraftDynamics};
+    use crate::propagators::Propagator;
+    use crate::State;
+
+    let cosm = Arc::new(Cosm::fixed_planets());
+    let eme2k = cosm.frame("EME2000");
+    let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let orbit = Orbit::keplerian(7_000.0, 1e-3, 28.5, 0.0, 0.0, 180.0, epoch, eme2k);
+
+    let ep =
+        ElectricThruster::new(&table(), SolarArray::new(3.0, epoch, vec![eme2k], cosm)).unwrap();
+    // Raise the orbit, far from the target so the law thrusts continuously
+    let qlaw = QLaw::new(&[Objective::within_tolerance(
+        StateParameter::SMA,
+        8_000.0,
+        1.0,
+    )])
+    .unwrap();
+    let dynamics = SpacecraftDynamics::from_guidance_law(OrbitalDynamics::two_body(), qlaw)
+        .with_electric_thruster(ep.clone());
+    assert!(format!("{dynamics}").contains("electric thruster"));
+
+    // The spacecraft does not need a thruster of its own
+    let mut sc = Spacecraft::new(orbit, 500.0, 50.0, 0.0, 0.0, 0.0, 0.0);
+    sc.mut_mode(GuidanceMode::Thrust);
+
+    let (end, traj) = Propagator::default(dynamics)
+        .with(sc)
+        .for_duration_with_traj(orbit.period())
+        .unwrap();
+
+    assert!(end.orbit.sma_km() > orbit.sma_km());
+    assert!(end.fuel_mass_kg < sc.fuel_mass_kg);
+
+    // The trajectory records the throttle and the performance at the available power, after the initial state.
+    // The Sun is about 1.5e8 km away, give or take the radius of the orbit.
+    let sunlit = ep.performance(3.0 / (1.5e8 / AU).powi(2)).unwrap();
+    let mut max_thrust: f64 = 0.0;
+    let mut eclipsed = Vec::new();
+    for state in traj.states.iter().skip(1) {
+        if state.value(StateParameter::Throttle).unwrap() > 0.0 {
+            let thrust = state.value(StateParameter::Thrust).unwrap();
+            // The thrust is reduced in the penumbra
+            assert!(thrust <= sunlit.thrust_N * (1.0 + 1e-3), "{thrust}");
+            max_thrust = max_thrust.max(thrust);
+        } else {
+            // Only in the shadow of the Earth
+            assert!(state.orbit.x_km > 0.0, "{state}");
+            eclipsed.push(*state);
+        }
+    }
+    assert!(
+        (max_thrust / sunlit.thrust_N - 1.0).abs() < 1e-3,
+        "{max_thrust}"
+    );
+    assert!(eclipsed.len() > 1);
+
+    // No fuel is consumed in the eclipse
+    let (entry, exit) = (eclipsed[0], eclipsed[eclipsed.len() - 1]);
+    assert!(exit.epoch() > entry.epoch());
+    assert!((exit.fuel_mass_kg - entry.fuel_mass_kg).abs() < 1e-9);
+    assert!(exit.fuel_mass_kg > end.fuel_mass_kg);
+}
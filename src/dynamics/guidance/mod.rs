@@ -16,7 +16,7 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::cosmic::{Frame, GuidanceMode, Orbit, Spacecraft, STD_GRAVITY};
+use crate::cosmic::{Attitude, Frame, GuidanceMode, Orbit, Spacecraft, STD_GRAVITY};
 use crate::errors::NyxError;
 use crate::linalg::Vector3;
 use serde::{Deserialize, Serialize};
@@ -29,6 +29,19 @@ pub use mnvr::Mnvr;
 
 mod ruggiero;
 pub use ruggiero::{Objective, Ruggiero, StateParameter};
+
+mod qlaw;
+pub use qlaw::{PeriapsisPenalty, QLaw};
+
+mod electric;
+pub use electric::{ElectricThruster, SolarArray, ThrottleSetting};
+
+mod sail;
+pub use sail::{ConeClockProfile, LocallyOptimalSail, SailAngles, SunPointingSail};
+
+mod bank;
+pub use bank::{BankAngleLaw, BankProfile, BankSetting, ConstantBank};
+
 use snafu::Snafu;
 
 use std::fmt;
@@ -70,6 +83,12 @@ pub trait GuidanceLaw: fmt::Display + Send + Sync {
     /// Returns a unit vector corresponding to the thrust direction in the inertial frame.
     fn direction(&self, osc_state: &Spacecraft) -> Vector3<f64>;
 
+    /// Returns a unit vector corresponding to the thrust direction in the inertial frame, provided the attitude propagated by the `AttitudeDynamics`,
+    /// e.g. to thrust along a body fixed axis. By default, the attitude is ignored and the spacecraft is assumed to instantaneously point in the `direction`.
+    fn direction_in_attitude(&self, osc_state: &Spacecraft, _attitude: &Attitude) -> Vector3<f64> {
+        self.direction(osc_state)
+    }
+
     /// Returns a number between [0;1] corresponding to the engine throttle level.
     /// For example, 0 means coasting, i.e. no thrusting, and 1 means maximum thrusting.
     fn throttle(&self, osc_state: &Spacecraft) -> f64;
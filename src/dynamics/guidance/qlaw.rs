@@ -0,0 +1,586 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{Frame, GuidanceLaw, GuidanceMode, NyxError, Orbit, Spacecraft, Vector3};
+use crate::linalg::{SMatrix, SVector};
+pub use crate::md::objective::Objective;
+pub use crate::md::StateParameter;
+use crate::State;
+use std::f64::consts::TAU;
+use std::fmt;
+use std::sync::Arc;
+
+/// Orbital elements controlled by the Q-law, in the order of its element vector [a, e, i, Ω, ω]
+const QLAW_ELEMENTS: [StateParameter; 5] = [
+    StateParameter::SMA,
+    StateParameter::Eccentricity,
+    StateParameter::Inclination,
+    StateParameter::RAAN,
+    StateParameter::AoP,
+];
+
+/// Number of true anomalies at which the best rate of change of Q is searched on the osculating orbit
+const QLAW_TA_SAMPLES: usize = 72;
+
+/// Lower bound of the eccentricity in the Gauss equations and the maximum rates, which are singular on circular orbits
+const QLAW_MIN_ECC: f64 = 1e-4;
+
+/// Lower bound of the sine of the inclination in the Gauss equations and the maximum rates, which are singular on equatorial orbits
+const QLAW_MIN_SIN_INC: f64 = 1e-4;
+
+/// Penalty on the periapsis radius of the Q-law, which increases the proximity quotient when the periapsis drops below its minimum.
+#[derive(Copy, Clone, Debug)]
+pub struct PeriapsisPenalty {
+    /// Minimum periapsis radius in km
+    pub rp_min_km: f64,
+    /// Weight of the penalty, W_p
+    pub weight: f64,
+    /// Steepness of the penalty, k
+    pub k: f64,
+}
+
+impl PeriapsisPenalty {
+    /// Initializes a periapsis penalty with a unit weight and a steepness of 100, as recommended by Petropoulos
+    pub fn new(rp_min_km: f64) -> Self {
+        Self {
+            rp_min_km,
+            weight: 1.0,
+            k: 100.0,
+        }
+    }
+}
+
+/// QLaw defines the Lyapunov closed loop guidance law of Petropoulos, "Refinements to the Q-law for low-thrust orbit transfers", AAS 05-162.
+///
+/// The proximity quotient Q measures the time to go to the targeted Keplerian elements, and the thrust direction minimizes its rate of change.
+/// The thrust is switched off when the effectivity, i.e. the ratio of the current best rate of change of Q to its best on the osculating orbit,
+/// is below the threshold. The effectivity is evaluated once per step, when the guidance mode is updated.
+/// The eccentricity and the sine of the inclination are bounded below in the Gauss equations, so the law remains defined on circular and equatorial orbits,
+/// where the RAAN (resp. AoP) is set to zero.
+/// WARNING: Objectives must be in degrees!
+#[derive(Copy, Clone, Default, Debug)]
+pub struct QLaw {
+    /// Stores the objectives
+    pub objectives: [Option<Objective>; 5],
+    /// Stores the weights of the objectives, defaults to one
+    pub weights: [f64; 5],
+    /// Stores the minimum effectivity to thrust, defaults to zero (i.e. always thrust)
+    pub ηthreshold: f64,
+    /// Optional penalty on the periapsis radius
+    pub periapsis_penalty: Option<PeriapsisPenalty>,
+}
+
+impl QLaw {
+    /// Creates a new Q-law with unit weights and without coasting nor periapsis penalty, as an Arc
+    /// Note: this returns an Arc so it can be plugged into the Spacecraft dynamics directly.
+    pub fn new(objectives: &[Objective]) -> Result<Arc<Self>, NyxError> {
+        Self::with_options(objectives, &[1.0; 5], 0.0, None)
+    }
+
+    /// Creates a new Q-law with the provided weights, effectivity threshold and periapsis penalty, as an Arc
+    /// Note: this returns an Arc so it can be plugged into the Spacecraft dynamics directly.
+    pub fn with_options(
+        objectives: &[Objective],
+        weights: &[f64],
+        ηthreshold: f64,
+        periapsis_penalty: Option<PeriapsisPenalty>,
+    ) -> Result<Arc<Self>, NyxError> {
+        if objectives.len() > 5 || objectives.is_empty() {
+            return Err(NyxError::GuidanceConfigError {
+                msg: format!(
+                    "Must provide between 1 and 5 objectives (included), provided {}",
+                    objectives.len()
+                ),
+            });
+        } else if objectives.len() > weights.len() {
+            return Err(NyxError::GuidanceConfigError {
+                msg: format!(
+                    "Must provide at least {} weights, provided {}",
+                    objectives.len(),
+                    weights.len()
+                ),
+            });
+        }
+
+        let mut objs: [Option<Objective>; 5] = [None; 5];
+        let mut objs_weights = [0.0; 5];
+        for (i, obj) in objectives.iter().enumerate() {
+            if !QLAW_ELEMENTS.contains(&obj.parameter) {
+                return Err(NyxError::GuidanceConfigError {
+                    msg: format!("Objective {} not supported in Q-law", obj.parameter),
+                });
+            }
+            objs[i] = Some(*obj);
+            objs_weights[i] = weights[i];
+        }
+
+        Ok(Arc::new(Self {
+            objectives: objs,
+            weights: objs_weights,
+            ηthreshold,
+            periapsis_penalty,
+        }))
+    }
+
+    /// Returns the element vector [a, e, i, Ω, ω] of the provided orbit, in km and radians, and its true anomaly.
+    ///
+    /// On equatorial (resp. circular) orbits, the RAAN (resp. AoP) is zero and the angles are measured from the X axis (resp. the line of nodes),
+    /// so that the argument of latitude Ω + ω + ν remains that of the orbit.
+    fn elements(osc: &Orbit) -> (SVector<f64, 5>, f64) {
+        let h_hat = osc.hvec() / osc.hmag_km2_s();
+        let node = Vector3::z().cross(&h_hat);
+        let (raan, node_hat) = if node.norm() > f64::EPSILON {
+            (node[1].atan2(node[0]), node / node.norm())
+        } else {
+            (0.0, Vector3::x())
+        };
+        let normal_hat = h_hat.cross(&node_hat);
+        let evec = osc.evec();
+        let ecc = evec.norm();
+        let aop = if ecc > f64::EPSILON {
+            evec.dot(&normal_hat).atan2(evec.dot(&node_hat))
+        } else {
+            0.0
+        };
+        let radius = osc.radius();
+        let aol = radius.dot(&normal_hat).atan2(radius.dot(&node_hat));
+
+        (
+            SVector::<f64, 5>::new(
+                osc.sma_km(),
+                ecc,
+                h_hat[2].clamp(-1.0, 1.0).acos(),
+                raan.rem_euclid(TAU),
+                aop.rem_euclid(TAU),
+            ),
+            aol - aop,
+        )
+    }
+
+    /// Returns the maximum rates of change of each element over the thrust direction and the true anomaly, for a unit thrust acceleration
+    fn max_rates(oe: &SVector<f64, 5>, gm: f64) -> SVector<f64, 5> {
+        let (a, aop) = (oe[0], oe[4]);
+        let e = oe[1].max(QLAW_MIN_ECC);
+        let (sin_i, cos_i) = (oe[2].sin().max(QLAW_MIN_SIN_INC), oe[2].cos());
+        let p = a * (1.0 - e.powi(2));
+        let h = (gm * p).sqrt();
+        let (sin_aop, cos_aop) = aop.sin_cos();
+
+        let a_xx = 2.0 * (a.powi(3) * (1.0 + e) / (gm * (1.0 - e))).sqrt();
+        let e_xx = 2.0 * p / h;
+        let i_xx = p / (h * ((1.0 - (e * sin_aop).powi(2)).sqrt() - e * cos_aop.abs()));
+        let raan_xx = p / (h * sin_i * ((1.0 - (e * cos_aop).powi(2)).sqrt() - e * sin_aop.abs()));
+
+        // In plane rate of change of the AoP, at its optimal true anomaly, blended with the out of plane rate
+        let oe2 = 1.0 - e.powi(2);
+        let e3 = e.powi(3);
+        let sqrt_val = (0.25 * (oe2 / e3).powi(2) + 1.0 / 27.0).sqrt();
+        let cos_ta = ((oe2 / (2.0 * e3) + sqrt_val).cbrt()
+            - (-oe2 / (2.0 * e3) + sqrt_val).cbrt()
+            - 1.0 / e)
+            .clamp(-1.0, 1.0);
+        let r = p / (1.0 + e * cos_ta);
+        let aop_xx_in =
+            ((p * cos_ta).powi(2) + (p + r).powi(2) * (1.0 - cos_ta.powi(2))).sqrt() / (e * h);
+        let aop_xx_out = raan_xx * cos_i.abs();
+        let b = 0.01;
+        let aop_xx = (aop_xx_in + b * aop_xx_out) / (1.0 + b);
+
+        SVector::<f64, 5>::new(a_xx, e_xx, i_xx, raan_xx, aop_xx)
+    }
+
+    /// Returns the Gauss variational equations of the elements [a, e, i, Ω, ω] for a thrust acceleration in the RCN frame, at the provided true anomaly
+    fn gauss(oe: &SVector<f64, 5>, ta: f64, gm: f64) -> SMatrix<f64, 5, 3> {
+        let (a, aop) = (oe[0], oe[4]);
+        let e = oe[1].max(QLAW_MIN_ECC);
+        let (sin_i, cos_i) = (oe[2].sin().max(QLAW_MIN_SIN_INC), oe[2].cos());
+        let p = a * (1.0 - e.powi(2));
+        let h = (gm * p).sqrt();
+        let (sin_ta, cos_ta) = ta.sin_cos();
+        let r = p / (1.0 + e * cos_ta);
+        let (sin_aol, cos_aol) = (aop + ta).sin_cos();
+
+        SMatrix::<f64, 5, 3>::new(
+            2.0 * a.powi(2) * e * sin_ta / h,
+            2.0 * a.powi(2) * p / (h * r),
+            0.0,
+            p * sin_ta / h,
+            ((p + r) * cos_ta + r * e) / h,
+            0.0,
+            0.0,
+            0.0,
+            r * cos_aol / h,
+            0.0,
+            0.0,
+            r * sin_aol / (h * sin_i),
+            -p * cos_ta / (e * h),
+            (p + r) * sin_ta / (e * h),
+            -r * sin_aol * cos_i / (h * sin_i),
+        )
+    }
+
+    /// Returns the proximity quotient Q of the provided elements, for a unit thrust acceleration
+    fn proximity_quotient(&self, oe: &SVector<f64, 5>, gm: f64) -> f64 {
+        let max_rates = Self::max_rates(oe, gm);
+        let mut q = 0.0;
+        for (obj, weight) in self.objectives.iter().zip(self.weights.iter()) {
+            if let Some(obj) = obj {
+                let idx = QLAW_ELEMENTS
+                    .iter()
+                    .position(|param| *param == obj.parameter)
+                    .unwrap();
+                let (dist, scaling) = match idx {
+                    0 => {
+                        let dist = oe[0] - obj.desired_value;
+                        // Scaling of the SMA, which prevents it from growing unbounded
+                        let scaling = (1.0 + (dist / (3.0 * obj.desired_value)).powi(4)).sqrt();
+                        (dist, scaling)
+                    }
+                    1 => (oe[1] - obj.desired_value, 1.0),
+                    2 => (oe[2] - obj.desired_value.to_radians(), 1.0),
+                    // The angular distance accounts for the wrap around
+                    _ => ((oe[idx] - obj.desired_value.to_radians()).cos().acos(), 1.0),
+                };
+                q += weight * scaling * (dist / max_rates[idx]).powi(2);
+            }
+        }
+
+        match self.periapsis_penalty {
+            Some(penalty) => {
+                let rp = oe[0] * (1.0 - oe[1]);
+                (1.0 + penalty.weight * (penalty.k * (1.0 - rp / penalty.rp_min_km)).exp()) * q
+            }
+            None => q,
+        }
+    }
+
+    /// Returns the gradient of the proximity quotient with respect to the elements, computed by central differences
+    fn gradient(&self, oe: &SVector<f64, 5>, gm: f64) -> SVector<f64, 5> {
+        let mut grad = SVector::<f64, 5>::zeros();
+        for j in 0..5 {
+            let step = if j == 0 { 1e-7 * oe[0] } else { 1e-7 };
+            let mut oe_plus = *oe;
+            oe_plus[j] += step;
+            let mut oe_minus = *oe;
+            // The eccentricity must remain positive
+            let back = if j == 1 { step.min(oe[1]) } else { step };
+            oe_minus[j] -= back;
+            grad[j] = (self.proximity_quotient(&oe_plus, gm)
+                - self.proximity_quotient(&oe_minus, gm))
+                / (step + back);
+        }
+        grad
+    }
+
+    /// Returns the rate of change of Q at the provided true anomaly per unit thrust acceleration along each axis of the RCN frame
+    fn rate_at(
+        &self,
+        oe: &SVector<f64, 5>,
+        grad: &SVector<f64, 5>,
+        ta: f64,
+        gm: f64,
+    ) -> Vector3<f64> {
+        Self::gauss(oe, ta, gm).transpose() * grad
+    }
+
+    /// Returns the thrust direction in the RCN frame which minimizes the rate of change of Q, i.e. -B^T dQ/doe, if Q can decrease
+    fn direction_rcn(&self, osc: &Orbit) -> Vector3<f64> {
+        let gm = osc.frame.gm();
+        let (oe, ta) = Self::elements(osc);
+        let rate = self.rate_at(&oe, &self.gradient(&oe, gm), ta, gm);
+        if rate.norm() > 0.0 {
+            -rate / rate.norm()
+        } else {
+            Vector3::zeros()
+        }
+    }
+
+    /// Returns the thrust direction in the RCN frame which minimizes the rate of change of Q, and the effectivity of thrusting at this point of the orbit.
+    pub fn steering(&self, osc: &Orbit) -> (Vector3<f64>, f64) {
+        let gm = osc.frame.gm();
+        let (oe, ta) = Self::elements(osc);
+        let grad = self.gradient(&oe, gm);
+
+        // The best rate of change of Q is -|B^T dQ/doe| times the thrust acceleration
+        let rate = self.rate_at(&oe, &grad, ta, gm);
+        let best_rate = (0..QLAW_TA_SAMPLES)
+            .map(|k| {
+                self.rate_at(&oe, &grad, TAU * k as f64 / QLAW_TA_SAMPLES as f64, gm)
+                    .norm()
+            })
+            .fold(rate.norm(), f64::max);
+
+        if rate.norm() > 0.0 {
+            (-rate / rate.norm(), rate.norm() / best_rate)
+        } else {
+            (Vector3::zeros(), 0.0)
+        }
+    }
+
+    /// Returns the proximity quotient of the provided orbit, for a unit thrust acceleration
+    pub fn q(&self, osc: &Orbit) -> f64 {
+        self.proximity_quotient(&Self::elements(osc).0, osc.frame.gm())
+    }
+}
+
+impl fmt::Display for QLaw {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Q-law with {} objectives and η threshold = {}",
+            self.objectives.iter().flatten().count(),
+            self.ηthreshold
+        )
+    }
+}
+
+impl GuidanceLaw for QLaw {
+    /// Returns whether the guidance law has achieved all goals
+    fn achieved(&self, state: &Spacecraft) -> Result<bool, NyxError> {
+        for obj in self.objectives.iter().flatten() {
+            if !obj.assess_raw(state.orbit.value(obj.parameter)?).0 {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn direction(&self, sc: &Spacecraft) -> Vector3<f64> {
+        if sc.mode() == GuidanceMode::Thrust {
+            // Convert to inertial -- this whole guidance law is computed in the RCN frame
+            sc.orbit.dcm_from_traj_frame(Frame::RCN).unwrap() * self.direction_rcn(&sc.orbit)
+        } else {
+            Vector3::zeros()
+        }
+    }
+
+    // Thrust full power unless coasting, e.g. because the thrust was not effective enough at the last step
+    fn throttle(&self, sc: &Spacecraft) -> f64 {
+        if sc.mode() == GuidanceMode::Thrust {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Update the state for the next iteration: thrust until the objectives are achieved, and coast while the thrust is not effective enough
+    fn next(&self, sc: &mut Spacecraft) {
+        if sc.mode() != GuidanceMode::Inhibit {
+            let achieved = match self.achieved(sc) {
+                Ok(achieved) => achieved,
+                Err(e) => {
+                    error!("Q-law cannot assess its objectives, coasting: {e}");
+                    sc.mut_mode(GuidanceMode::Coast);
+                    return;
+                }
+            };
+            if !achieved {
+                let (steering, η) = self.steering(&sc.orbit);
+                if steering.norm() > 0.0 && η >= self.ηthreshold {
+                    if sc.mode() == GuidanceMode::Coast {
+                        info!("enabling steering: {:x}", sc.orbit);
+                    }
+                    sc.mut_mode(GuidanceMode::Thrust);
+                } else {
+                    if sc.mode() == GuidanceMode::Thrust {
+                        debug!("coasting with effectivity η = {η}: {:x}", sc.orbit);
+                    }
+                    sc.mut_mode(GuidanceMode::Coast);
+                }
+            } else {
+                if sc.mode() == GuidanceMode::Thrust {
+                    info!("disabling steering: {:x}", sc.orbit);
+                }
+                sc.mut_mode(GuidanceMode::Coast);
+            }
+        }
+    }
+}
+
+#[test]
+fn qlaw_direction() {
+    use crate::cosmic::Cosm;
+    use crate::time::Epoch;
+    let cosm = Cosm::fixed_planets();
+    let eme2k = cosm.frame("EME2000");
+    let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    // Geostationary transfer orbit
+    let gto = Orbit::keplerian(24_505.9, 0.725, 7.05, 10.0, 20.0, 60.0, epoch, eme2k);
+
+    let objectives = &[
+        Objective::within_tolerance(StateParameter::SMA, 42_165.0, 20.0),
+        Objective::within_tolerance(StateParameter::Eccentricity, 0.001, 5e-5),
+        Objective::within_tolerance(StateParameter::Inclination, 0.05, 1e-2),
+    ];
+    let qlaw = QLaw::new(objectives).unwrap();
+    assert_eq!(
+        format!("{qlaw}"),
+        "Q-law with 3 objectives and η threshold = 0"
+    );
+
+    let mut sc = Spacecraft::new(gto, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+    // Must set the guidance mode to thrusting otherwise the direction will be set to zero.
+    assert_eq!(qlaw.direction(&sc), Vector3::zeros());
+    sc.mut_mode(GuidanceMode::Thrust);
+
+    // A small impulse along the steering direction must decrease the proximity quotient, and more so than other directions
+    let dir = qlaw.direction(&sc);
+    assert!((dir.norm() - 1.0).abs() < 1e-12);
+    let q_after = |dv: Vector3<f64>| {
+        let mut post = gto;
+        post.vx_km_s += dv[0];
+        post.vy_km_s += dv[1];
+        post.vz_km_s += dv[2];
+        qlaw.q(&post)
+    };
+    let q_steered = q_after(dir * 1e-5);
+    assert!(q_steered < qlaw.q(&gto));
+    for other in [Vector3::x(), Vector3::y(), Vector3::z(), -dir] {
+        assert!(q_steered < q_after(other * 1e-5));
+    }
+
+    // The periapsis penalty increases Q when the periapsis is close to its minimum
+    let penalized = QLaw::with_options(
+        objectives,
+        &[1.0; 3],
+        0.0,
+        Some(PeriapsisPenalty::new(gto.periapsis_km() + 10.0)),
+    )
+    .unwrap();
+    assert!(penalized.q(&gto) > 2.0 * qlaw.q(&gto));
+
+    // Unsupported objectives are rejected
+    assert!(QLaw::new(&[Objective::new(StateParameter::Period, 3600.0)]).is_err());
+}
+
+#[test]
+fn qlaw_effectivity() {
+    use crate::cosmic::Cosm;
+    use crate::time::Epoch;
+    let cosm = Cosm::fixed_planets();
+    let eme2k = cosm.frame("EME2000");
+    let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    // Raising the SMA of an eccentric orbit is most effective at periapsis
+    let objectives = &[Objective::within_tolerance(
+        StateParameter::SMA,
+        30_000.0,
+        1.0,
+    )];
+    let qlaw = QLaw::with_options(objectives, &[1.0], 0.8, None).unwrap();
+    for (ta_deg, throttle) in [(5.0, 1.0), (175.0, 0.0)] {
+        let orbit = Orbit::keplerian(24_505.9, 0.5, 7.05, 10.0, 20.0, ta_deg, epoch, eme2k);
+        let mut sc = Spacecraft::new(orbit, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+        sc.mut_mode(GuidanceMode::Thrust);
+        let (_, η) = qlaw.steering(&orbit);
+        assert!((0.0..=1.0).contains(&η), "{η}");
+        // The effectivity is evaluated when the guidance mode is updated
+        qlaw.next(&mut sc);
+        assert_eq!(qlaw.throttle(&sc), throttle, "ta = {ta_deg}: η = {η}");
+    }
+}
+
+#[test]
+fn qlaw_circular_equatorial() {
+    use crate::cosmic::Cosm;
+    use crate::dynamics::guidance::Thruster;
+    use crate::dynamics::{OrbitalDynamics, SpacecraftDynamics};
+    use crate::propagators::Propagator;
+    use crate::time::{Epoch, Unit};
+    let cosm = Cosm::fixed_planets();
+    let eme2k = cosm.frame("EME2000");
+    let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    // The eccentricity, RAAN and AoP rates of the Gauss equations are singular on this orbit
+    let orbit = Orbit::keplerian(7_000.0, 0.0, 0.0, 0.0, 0.0, 30.0, epoch, eme2k);
+
+    let objectives = &[
+        Objective::within_tolerance(StateParameter::SMA, 7_100.0, 1.0),
+        Objective::within_tolerance(StateParameter::Eccentricity, 0.01, 1e-3),
+        Objective::within_tolerance(StateParameter::Inclination, 1.0, 1e-2),
+    ];
+    let qlaw = QLaw::new(objectives).unwrap();
+    assert!(qlaw.q(&orbit).is_finite());
+    let (steering, η) = qlaw.steering(&orbit);
+    assert!((steering.norm() - 1.0).abs() < 1e-12, "{steering}");
+    assert!((0.0..=1.0).contains(&η), "{η}");
+
+    let sc = Spacecraft::from_thruster(
+        orbit,
+        100.0,
+        10.0,
+        Thruster {
+            thrust_N: 10.0,
+            isp_s: 1_500.0,
+        },
+        GuidanceMode::Thrust,
+    );
+    let dir = qlaw.direction(&sc);
+    assert!((dir.norm() - 1.0).abs() < 1e-12, "{dir}");
+
+    let dynamics = SpacecraftDynamics::from_guidance_law(OrbitalDynamics::two_body(), qlaw.clone());
+    let end = Propagator::default(dynamics)
+        .with(sc)
+        .for_duration(10 * Unit::Minute)
+        .unwrap();
+
+    // The thrust raises the orbit, and moves it away from the circular and equatorial singularities
+    assert!(end.orbit.sma_km() > orbit.sma_km(), "{end}");
+    assert!(end.orbit.ecc() > 1e-4, "{end}");
+    assert!(end.orbit.inc_deg() > 1e-3, "{end}");
+    assert!(qlaw.q(&end.orbit) < qlaw.q(&orbit));
+}
+
+#[test]
+fn qlaw_orbit_raising() {
+    use crate::cosmic::Cosm;
+    use crate::dynamics::guidance::Thruster;
+    use crate::dynamics::{OrbitalDynamics, SpacecraftDynamics};
+    use crate::propagators::Propagator;
+    use crate::time::{Epoch, Unit};
+    let cosm = Cosm::fixed_planets();
+    let eme2k = cosm.frame("EME2000");
+    let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let orbit = Orbit::keplerian(7_000.0, 0.01, 28.5, 10.0, 20.0, 0.0, epoch, eme2k);
+
+    let objectives = &[
+        Objective::within_tolerance(StateParameter::SMA, 7_050.0, 1.0),
+        Objective::within_tolerance(StateParameter::Eccentricity, 0.01, 5e-3),
+    ];
+    let qlaw = QLaw::new(objectives).unwrap();
+    let sc = Spacecraft::from_thruster(
+        orbit,
+        100.0,
+        10.0,
+        Thruster {
+            thrust_N: 10.0,
+            isp_s: 1_500.0,
+        },
+        GuidanceMode::Thrust,
+    );
+
+    let dynamics = SpacecraftDynamics::from_guidance_law(OrbitalDynamics::two_body(), qlaw);
+    let end = Propagator::default(dynamics.clone())
+        .with(sc)
+        .for_duration(45 * Unit::Minute)
+        .unwrap();
+
+    // The guidance mode is updated once all of the objectives are achieved, as for Ruggiero
+    assert!(dynamics.guidance_achieved(&end).unwrap(), "{end}");
+    assert_eq!(end.mode(), GuidanceMode::Coast);
+    assert!(end.fuel_mass_kg < sc.fuel_mass_kg);
+}
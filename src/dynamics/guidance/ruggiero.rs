@@ -80,6 +80,14 @@ impl Ruggiero {
                 StateParameter::Inclination,
                 StateParameter::RAAN,
                 StateParameter::AoP,
+                StateParameter::EquinoctialH,
+                StateParameter::EquinoctialK,
+                StateParameter::EquinoctialP,
+                StateParameter::EquinoctialQ,
+                StateParameter::ModEquinoctialF,
+                StateParameter::ModEquinoctialG,
+                StateParameter::ModEquinoctialH,
+                StateParameter::ModEquinoctialK,
             ]
             .contains(&obj.parameter)
             {
@@ -136,6 +144,33 @@ impl Ruggiero {
                 Ok(num / denom)
             }
             StateParameter::AoP => Ok(1.0),
+            StateParameter::EquinoctialH
+            | StateParameter::EquinoctialK
+            | StateParameter::EquinoctialP
+            | StateParameter::EquinoctialQ
+            | StateParameter::ModEquinoctialF
+            | StateParameter::ModEquinoctialG
+            | StateParameter::ModEquinoctialH
+            | StateParameter::ModEquinoctialK => {
+                // Ratio of the current rate of change to the best rate of change on this osculating orbit
+                let osc_rate = Self::equinoctial_gains(
+                    parameter,
+                    osc_orbit,
+                    osc_orbit.mod_equinoctial_l_deg(),
+                )
+                .norm();
+                let max_rate = (0..360)
+                    .map(|true_long_deg| {
+                        Self::equinoctial_gains(parameter, osc_orbit, f64::from(true_long_deg))
+                            .norm()
+                    })
+                    .fold(0.0, f64::max);
+                if max_rate > 0.0 {
+                    Ok(osc_rate / max_rate)
+                } else {
+                    Ok(0.0)
+                }
+            }
             _ => Err(NyxError::StateParameterUnavailable {
                 param: *parameter,
                 msg: "not a control variable in Ruggiero".to_string(),
@@ -143,6 +178,44 @@ impl Ruggiero {
         }
     }
 
+    /// Returns the gains, in the RCN frame, of a thrust acceleration on the rate of change of the provided equinoctial element,
+    /// at the provided true longitude and up to the common factor sqrt(p/μ).
+    ///
+    /// These are the Gauss variational equations in modified equinoctial elements, cf. Walker et al., Celestial Mechanics 36, 1985.
+    fn equinoctial_gains(
+        parameter: &StateParameter,
+        osc_orbit: &Orbit,
+        true_long_deg: f64,
+    ) -> Vector3<f64> {
+        let f = osc_orbit.mod_equinoctial_f();
+        let g = osc_orbit.mod_equinoctial_g();
+        let h = osc_orbit.mod_equinoctial_h();
+        let k = osc_orbit.mod_equinoctial_k();
+        let (sin_l, cos_l) = true_long_deg.to_radians().sin_cos();
+        let w = 1.0 + f * cos_l + g * sin_l;
+        let s2 = 1.0 + h.powi(2) + k.powi(2);
+
+        match parameter {
+            StateParameter::EquinoctialK | StateParameter::ModEquinoctialF => Vector3::new(
+                sin_l,
+                ((w + 1.0) * cos_l + f) / w,
+                -g * (h * sin_l - k * cos_l) / w,
+            ),
+            StateParameter::EquinoctialH | StateParameter::ModEquinoctialG => Vector3::new(
+                -cos_l,
+                ((w + 1.0) * sin_l + g) / w,
+                f * (h * sin_l - k * cos_l) / w,
+            ),
+            StateParameter::EquinoctialQ | StateParameter::ModEquinoctialH => {
+                Vector3::new(0.0, 0.0, s2 * cos_l / (2.0 * w))
+            }
+            StateParameter::EquinoctialP | StateParameter::ModEquinoctialK => {
+                Vector3::new(0.0, 0.0, s2 * sin_l / (2.0 * w))
+            }
+            _ => Vector3::zeros(),
+        }
+    }
+
     /// Computes the weight at which to correct this orbital element, will be zero if the current efficiency is below the threshold
     fn weighting(&self, obj: &Objective, osc_orbit: &Orbit, η_threshold: f64) -> f64 {
         let init = self.init_state.value(obj.parameter).unwrap();
@@ -249,6 +322,24 @@ impl GuidanceLaw for Ruggiero {
                             steering += unit_vector_from_plane_angles(0.0, beta) * weight;
                         };
                     }
+                    StateParameter::EquinoctialH
+                    | StateParameter::EquinoctialK
+                    | StateParameter::EquinoctialP
+                    | StateParameter::EquinoctialQ
+                    | StateParameter::ModEquinoctialF
+                    | StateParameter::ModEquinoctialG
+                    | StateParameter::ModEquinoctialH
+                    | StateParameter::ModEquinoctialK => {
+                        // Thrust along the direction which maximizes the rate of change of this element
+                        let gains = Self::equinoctial_gains(
+                            &obj.parameter,
+                            &osc,
+                            osc.mod_equinoctial_l_deg(),
+                        );
+                        if gains.norm() > 0.0 {
+                            steering += gains / gains.norm() * weight;
+                        }
+                    }
                     _ => unreachable!(),
                 }
             }
@@ -346,3 +437,43 @@ fn ruggiero_weight() {
         "incorrect direction computed"
     );
 }
+
+#[test]
+fn ruggiero_equinoctial() {
+    use crate::cosmic::Cosm;
+    use crate::time::Epoch;
+    let cosm = Cosm::de438_raw();
+    let eme2k = cosm.frame("EME2000");
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    // Near circular and equatorial orbit, where the Keplerian elements are singular
+    let orbit = Orbit::keplerian(24_396.0, 1e-4, 0.01, 0.0, 0.0, 30.0, start_time, eme2k);
+
+    for (parameter, target) in [
+        (StateParameter::EquinoctialH, 0.1),
+        (StateParameter::EquinoctialK, -0.1),
+        (StateParameter::EquinoctialP, 0.05),
+        (StateParameter::ModEquinoctialH, -0.05),
+    ] {
+        let objectives = &[Objective::within_tolerance(parameter, target, 1e-4)];
+        let ruggiero = Ruggiero::new(objectives, orbit).unwrap();
+
+        let mut sc = Spacecraft::new(orbit, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+        sc.mut_mode(GuidanceMode::Thrust);
+
+        let eff = Ruggiero::efficency(&parameter, &orbit).unwrap();
+        assert!(
+            (0.0..=1.0).contains(&eff),
+            "{parameter}: efficiency of {eff}"
+        );
+
+        // A small impulse along the steering direction must move the element towards its target
+        let dv = ruggiero.direction(&sc) * 1e-3;
+        let mut post = orbit;
+        post.vx_km_s += dv[0];
+        post.vy_km_s += dv[1];
+        post.vz_km_s += dv[2];
+        let before = (target - orbit.value(parameter).unwrap()).abs();
+        let after = (target - post.value(parameter).unwrap()).abs();
+        assert!(after < before, "{parameter}: {before} -> {after}");
+    }
+}
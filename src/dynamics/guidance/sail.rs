@@ -0,0 +1,436 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::GuidanceLaw;
+use crate::cosmic::{Cosm, Frame, Orbit, Sail, Spacecraft};
+use crate::linalg::Vector3;
+use crate::time::Epoch;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::FRAC_PI_2;
+use std::fmt;
+use std::sync::Arc;
+
+// The sail steering laws do not use the thruster of the spacecraft: their throttle is zero and their direction is the sail normal.
+// They orient the sail of the spacecraft in `next`, i.e. at the end of each integration step, so a fixed step size sets the steering rate.
+
+/// Returns the unit vector from the Sun to the spacecraft
+fn sunlight_unit(cosm: &Cosm, sun: Frame, osc: &Orbit) -> Vector3<f64> {
+    let r_sun = cosm.frame_chg(osc, sun).radius();
+    r_sun / r_sun.norm()
+}
+
+/// Returns the sail normal in the integration frame from its cone and clock angles in degrees
+fn normal_from_angles(
+    cosm: &Cosm,
+    sun: Frame,
+    osc_state: &Spacecraft,
+    cone_deg: f64,
+    clock_deg: f64,
+) -> Vector3<f64> {
+    let sunlight = sunlight_unit(cosm, sun, &osc_state.orbit);
+    let mut sail = osc_state.sail.unwrap_or(Sail::new(0.0, Default::default()));
+    sail.cone_deg = cone_deg;
+    sail.clock_deg = clock_deg;
+    sail.normal(&Sail::dcm_sun_line(&osc_state.orbit, &sunlight))
+}
+
+/// Keeps the sail of the spacecraft facing the Sun, i.e. with a zero cone angle, which maximizes the radiation pressure.
+#[derive(Clone)]
+pub struct SunPointingSail {
+    cosm: Arc<Cosm>,
+    sun: Frame,
+}
+
+impl SunPointingSail {
+    /// Creates a new sun pointing law, as an Arc
+    pub fn new(cosm: Arc<Cosm>) -> Arc<Self> {
+        Arc::new(Self {
+            sun: cosm.frame("Sun J2000"),
+            cosm,
+        })
+    }
+}
+
+impl fmt::Display for SunPointingSail {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "sun pointing sail")
+    }
+}
+
+impl GuidanceLaw for SunPointingSail {
+    fn direction(&self, osc_state: &Spacecraft) -> Vector3<f64> {
+        sunlight_unit(&self.cosm, self.sun, &osc_state.orbit)
+    }
+
+    fn throttle(&self, _osc_state: &Spacecraft) -> f64 {
+        0.0
+    }
+
+    fn next(&self, next_state: &mut Spacecraft) {
+        if let Some(sail) = next_state.sail.as_mut() {
+            sail.cone_deg = 0.0;
+            sail.clock_deg = 0.0;
+        }
+    }
+}
+
+/// Cone and clock angles of the sail from the provided epoch onward
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SailAngles {
+    pub epoch: Epoch,
+    pub cone_deg: f64,
+    pub clock_deg: f64,
+}
+
+/// Orients the sail of the spacecraft following a piecewise constant profile of cone and clock angles.
+///
+/// The first angles of the profile are used before its first epoch.
+#[derive(Clone)]
+pub struct ConeClockProfile {
+    /// Profile, sorted by epoch
+    profile: Vec<SailAngles>,
+    cosm: Arc<Cosm>,
+    sun: Frame,
+}
+
+impl ConeClockProfile {
+    /// Creates a new profile from the provided angles, which will be sorted by epoch, as an Arc
+    pub fn new(profile: &[SailAngles], cosm: Arc<Cosm>) -> Arc<Self> {
+        let mut profile = profile.to_vec();
+        profile.sort_by_key(|angles| angles.epoch);
+        Arc::new(Self {
+            profile,
+            sun: cosm.frame("Sun J2000"),
+            cosm,
+        })
+    }
+
+    /// Returns the cone and clock angles in degrees at the provided epoch, or None if the profile is empty
+    pub fn angles(&self, epoch: Epoch) -> Option<SailAngles> {
+        self.profile
+            .iter()
+            .rev()
+            .find(|angles| angles.epoch <= epoch)
+            .or_else(|| self.profile.first())
+            .copied()
+    }
+}
+
+impl fmt::Display for ConeClockProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "sail cone and clock profile of {} steps",
+            self.profile.len()
+        )
+    }
+}
+
+impl GuidanceLaw for ConeClockProfile {
+    fn direction(&self, osc_state: &Spacecraft) -> Vector3<f64> {
+        match self.angles(osc_state.orbit.epoch) {
+            Some(angles) => normal_from_angles(
+                &self.cosm,
+                self.sun,
+                osc_state,
+                angles.cone_deg,
+                angles.clock_deg,
+            ),
+            None => sunlight_unit(&self.cosm, self.sun, &osc_state.orbit),
+        }
+    }
+
+    fn throttle(&self, _osc_state: &Spacecraft) -> f64 {
+        0.0
+    }
+
+    fn next(&self, next_state: &mut Spacecraft) {
+        if let Some(angles) = self.angles(next_state.orbit.epoch) {
+            if let Some(sail) = next_state.sail.as_mut() {
+                sail.cone_deg = angles.cone_deg;
+                sail.clock_deg = angles.clock_deg;
+            }
+        }
+    }
+}
+
+/// Locally optimal steering of the sail, which maximizes (or minimizes) the instantaneous rate of change of the semi-major axis,
+/// i.e. the component of the sail force along the velocity, cf. McInnes, "Solar Sailing", section 4.4.
+///
+/// The sail is feathered (i.e. edge-on) when it cannot produce any force in the desired direction.
+#[derive(Clone)]
+pub struct LocallyOptimalSail {
+    /// Set to true to raise the orbit, and to false to lower it
+    pub raise: bool,
+    cosm: Arc<Cosm>,
+    sun: Frame,
+}
+
+impl LocallyOptimalSail {
+    /// Creates a new locally optimal orbit raising law, as an Arc
+    pub fn raising(cosm: Arc<Cosm>) -> Arc<Self> {
+        Arc::new(Self {
+            raise: true,
+            sun: cosm.frame("Sun J2000"),
+            cosm,
+        })
+    }
+
+    /// Creates a new locally optimal orbit lowering law, as an Arc
+    pub fn lowering(cosm: Arc<Cosm>) -> Arc<Self> {
+        Arc::new(Self {
+            raise: false,
+            sun: cosm.frame("Sun J2000"),
+            cosm,
+        })
+    }
+
+    /// Returns the optimal sail normal in the integration frame
+    pub fn optimal_normal(&self, osc_state: &Spacecraft) -> Vector3<f64> {
+        let osc = &osc_state.orbit;
+        let sunlight = sunlight_unit(&self.cosm, self.sun, osc);
+        let mut target = osc.velocity() / osc.vmag_km_s();
+        if !self.raise {
+            target = -target;
+        }
+
+        // The optimal normal is in the plane of the sunlight and of the target direction
+        let in_plane = target - target.dot(&sunlight) * sunlight;
+        if in_plane.norm() < 1e-12 {
+            // Target along the sunlight: face the Sun if it pushes along the target, else feather the sail
+            return if target.dot(&sunlight) > 0.0 {
+                sunlight
+            } else {
+                Sail::dcm_sun_line(osc, &sunlight).column(1).into()
+            };
+        }
+        let in_plane = in_plane / in_plane.norm();
+
+        let optics = osc_state.sail.map(|sail| sail.optics).unwrap_or_default();
+        let normal = |cone: f64| cone.cos() * sunlight + cone.sin() * in_plane;
+        let along_target = |cone: f64| {
+            optics
+                .force_n(1.0, 1.0, &sunlight, &normal(cone))
+                .dot(&target)
+        };
+
+        // Golden section search of the cone angle in [0; 90] degrees
+        let inv_phi = (5f64.sqrt() - 1.0) / 2.0;
+        let (mut lo, mut hi) = (0.0, FRAC_PI_2);
+        while hi - lo > 1e-8 {
+            let c = hi - inv_phi * (hi - lo);
+            let d = lo + inv_phi * (hi - lo);
+            if along_target(c) > along_target(d) {
+                hi = d;
+            } else {
+                lo = c;
+            }
+        }
+        let cone = 0.5 * (lo + hi);
+
+        if along_target(cone) > 0.0 {
+            normal(cone)
+        } else {
+            // Edge-on
+            in_plane
+        }
+    }
+}
+
+impl fmt::Display for LocallyOptimalSail {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "locally optimal sail orbit {}",
+            if self.raise { "raising" } else { "lowering" }
+        )
+    }
+}
+
+impl GuidanceLaw for LocallyOptimalSail {
+    fn direction(&self, osc_state: &Spacecraft) -> Vector3<f64> {
+        self.optimal_normal(osc_state)
+    }
+
+    fn throttle(&self, _osc_state: &Spacecraft) -> f64 {
+        0.0
+    }
+
+    fn next(&self, next_state: &mut Spacecraft) {
+        if next_state.sail.is_none() {
+            return;
+        }
+        let normal = self.optimal_normal(next_state);
+        let sunlight = sunlight_unit(&self.cosm, self.sun, &next_state.orbit);
+        let dcm = Sail::dcm_sun_line(&next_state.orbit, &sunlight);
+        if let Some(sail) = next_state.sail.as_mut() {
+            sail.set_normal(&dcm, &normal);
+        }
+    }
+}
+
+#[test]
+fn sail_steering_laws() {
+    use crate::cosmic::SailOptics;
+    use crate::time::Unit;
+
+    let cosm = Arc::new(Cosm::fixed_planets());
+    let eme2k = cosm.frame("EME2000");
+    let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    // The Sun is along -X in this test Cosm, so the velocity is along the sunlight at this true anomaly
+    let orbit = Orbit::keplerian(7_000.0, 0.0, 0.0, 0.0, 0.0, 270.0, epoch, eme2k);
+    let sc = Spacecraft::from_srp_defaults(orbit, 10.0, 0.0)
+        .with_sail(Sail::new(100.0, SailOptics::ideal()));
+    let sunlight = Vector3::x();
+
+    let sun_pointing = SunPointingSail::new(cosm.clone());
+    assert!((sun_pointing.direction(&sc) - sunlight).norm() < 1e-4);
+
+    // Along the sunlight, the raising law faces the Sun and the lowering law feathers the sail
+    let raising = LocallyOptimalSail::raising(cosm.clone());
+    let lowering = LocallyOptimalSail::lowering(cosm.clone());
+    assert!(raising.direction(&sc).dot(&sunlight) > 1.0 - 1e-6);
+    assert!(lowering.direction(&sc).dot(&sunlight).abs() < 1e-3);
+
+    // With the velocity orthogonal to the sunlight, the ideal sail is at the optimal cone angle of 35.26 degrees
+    let mut sc_90 = sc;
+    sc_90.orbit = Orbit::keplerian(7_000.0, 0.0, 0.0, 0.0, 0.0, 0.0, epoch, eme2k);
+    let normal = raising.direction(&sc_90);
+    let cone_deg = normal.dot(&sunlight).acos().to_degrees();
+    assert!(
+        (cone_deg - (1.0 / 2f64.sqrt()).atan().to_degrees()).abs() < 1e-3,
+        "{cone_deg}"
+    );
+    assert!(normal.dot(&sc_90.orbit.velocity()) > 0.0);
+    assert!(lowering.direction(&sc_90).dot(&sc_90.orbit.velocity()) < 0.0);
+
+    // The laws orient the sail of the spacecraft
+    let mut next = sc_90;
+    raising.next(&mut next);
+    assert!((next.sail.unwrap().cone_deg - cone_deg).abs() < 1e-6);
+    assert_eq!(raising.throttle(&next), 0.0);
+
+    let profile = ConeClockProfile::new(
+        &[
+            SailAngles {
+                epoch: epoch + Unit::Hour,
+                cone_deg: 45.0,
+                clock_deg: 90.0,
+            },
+            SailAngles {
+                epoch,
+                cone_deg: 10.0,
+                clock_deg: 0.0,
+            },
+        ],
+        cosm,
+    );
+    assert_eq!(profile.angles(epoch - Unit::Hour).unwrap().cone_deg, 10.0);
+    assert_eq!(
+        profile.angles(epoch + 30 * Unit::Minute).unwrap().cone_deg,
+        10.0
+    );
+    let mut later = sc_90;
+    later.orbit.epoch = epoch + 2 * Unit::Hour;
+    profile.next(&mut later);
+    assert_eq!(later.sail.unwrap().cone_deg, 45.0);
+    assert_eq!(later.sail.unwrap().clock_deg, 90.0);
+    // A clock angle of 90 degrees tilts the normal towards the orbit normal
+    assert!(profile.direction(&later).dot(&later.orbit.hvec()) > 0.0);
+}
+
+#[test]
+fn sail_orbit_raising() {
+    use crate::cosmic::SailOptics;
+    use crate::dynamics::{OrbitalDynamics, SolarSail, SpacecraftDynamics};
+    use crate::io::ExportCfg;
+    use crate::md::trajectory::Interpolatable;
+    use crate::md::StateParameter;
+    use crate::propagators::{PropOpts, Propagator, RK89};
+    use crate::time::Unit;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::fs::File;
+
+    let cosm = Arc::new(Cosm::fixed_planets());
+    let eme2k = cosm.frame("EME2000");
+    let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let orbit = Orbit::keplerian(42_164.0, 1e-3, 5.0, 0.0, 0.0, 180.0, epoch, eme2k);
+    let sc = Spacecraft::from_srp_defaults(orbit, 50.0, 0.0)
+        .with_sail(Sail::new(200.0, SailOptics::jpl_square_sail()));
+
+    let sail = SolarSail::default(eme2k, cosm.clone());
+    let prop_for = |law: Arc<dyn GuidanceLaw>| {
+        let dynamics = SpacecraftDynamics::from_guidance_law(OrbitalDynamics::two_body(), law)
+            .with_model(sail.clone());
+        Propagator::new::<RK89>(dynamics, PropOpts::with_fixed_step(5 * Unit::Minute))
+            .with(sc)
+            .for_duration_with_traj(12 * Unit::Hour)
+            .unwrap()
+    };
+
+    let (raised, traj) = prop_for(LocallyOptimalSail::raising(cosm.clone()));
+    let (lowered, _) = prop_for(LocallyOptimalSail::lowering(cosm.clone()));
+    assert!(
+        raised.orbit.sma_km() - orbit.sma_km() > 1.0,
+        "{}",
+        raised.orbit.sma_km()
+    );
+    assert!(
+        orbit.sma_km() - lowered.orbit.sma_km() > 1.0,
+        "{}",
+        lowered.orbit.sma_km()
+    );
+
+    // The sail attitude is exported
+    assert!(Spacecraft::export_params().contains(&StateParameter::SailCone));
+    let path = std::env::temp_dir().join("nyx_sail_orbit_raising.parquet");
+    let path = traj
+        .to_parquet_with_cfg(
+            path,
+            ExportCfg {
+                fields: Some(vec![
+                    StateParameter::SMA,
+                    StateParameter::SailCone,
+                    StateParameter::SailClock,
+                ]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+    let schema = reader.schema().clone();
+    let batch = reader.build().unwrap().next().unwrap().unwrap();
+    let cone_column = schema
+        .fields()
+        .iter()
+        .position(|field| field.name().starts_with("sail_cone"))
+        .unwrap();
+    let cones = batch
+        .column(cone_column)
+        .as_any()
+        .downcast_ref::<arrow::array::Float64Array>()
+        .unwrap();
+    assert_eq!(cones.len(), traj.states.len());
+    // The sail is steered within [0; 90] degrees and not always facing the Sun
+    assert!(cones
+        .iter()
+        .flatten()
+        .all(|cone| (0.0..=90.0).contains(&cone)));
+    assert!(cones.iter().flatten().any(|cone| cone > 1.0));
+    let _ = std::fs::remove_file(path);
+}
@@ -16,9 +16,9 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::cosmic::{AstroError, Orbit};
+use crate::cosmic::{AstroError, Orbit, RigidSpacecraft};
 use crate::linalg::allocator::Allocator;
-use crate::linalg::{DefaultAllocator, DimName, Matrix3, OMatrix, OVector, Vector3};
+use crate::linalg::{DefaultAllocator, DimName, Matrix3x6, OMatrix, OVector, Vector3};
 use crate::State;
 use hyperdual::{OHyperdual, Owned};
 use snafu::Snafu;
@@ -57,14 +57,54 @@ pub mod deltavctrl;
 pub mod solarpressure;
 pub use self::solarpressure::*;
 
+/// Defines the solar sail force model
+pub mod solarsail;
+pub use self::solarsail::*;
+
+/// Define the albedo and infrared radiation pressure of the central body
+pub mod albedo;
+pub use self::albedo::*;
+
 /// Define drag models
 pub mod drag;
 pub use self::drag::*;
 
+/// Define the circular restricted three body problem dynamics
+pub mod cr3bp;
+pub use self::cr3bp::*;
+
 /// Define the spherical harmonic models.
 pub mod sph_harmonics;
 pub use self::sph_harmonics::*;
 
+/// Define the solid body and ocean tide corrections of the spherical harmonics.
+pub mod tides;
+pub use self::tides::*;
+
+/// Define the polyhedron and mascon gravity models of small bodies.
+pub mod small_body;
+pub use self::small_body::*;
+
+/// Define the torque models of the attitude dynamics
+pub mod torques;
+pub use self::torques::*;
+
+/// Define the rigid body attitude dynamics, propagated alongside the spacecraft dynamics
+pub mod attitude;
+pub use self::attitude::*;
+
+/// Define the post-Newtonian relativistic corrections.
+pub mod relativity;
+pub use self::relativity::*;
+
+/// Define the empirical accelerations and the dynamics to estimate them.
+pub mod empirical;
+pub use self::empirical::*;
+
+/// Define the aerodynamics of atmospheric entry, descent and landing, with lift.
+pub mod entry;
+pub use self::entry::*;
+
 /// The `Dynamics` trait handles and stores any equation of motion *and* the state is integrated.
 ///
 /// Its design is such that several of the provided dynamics can be combined fairly easily. However,
@@ -135,10 +175,12 @@ pub trait ForceModel: Send + Sync + fmt::Display {
     /// Defines the equations of motion for this force model from the provided osculating state.
     fn eom(&self, ctx: &Spacecraft) -> Result<Vector3<f64>, DynamicsError>;
 
-    /// Force models must implement their partials, although those will only be called if the propagation requires the
-    /// computation of the STM. The `osc_ctx` is the osculating context, i.e. it changes for each sub-step of the integrator.
-    fn dual_eom(&self, osc_ctx: &Spacecraft)
-        -> Result<(Vector3<f64>, Matrix3<f64>), DynamicsError>;
+    /// Force models must implement their partials with respect to the position and the velocity, although those will only be called
+    /// if the propagation requires the computation of the STM. The `osc_ctx` is the osculating context, i.e. it changes for each sub-step of the integrator.
+    fn dual_eom(
+        &self,
+        osc_ctx: &Spacecraft,
+    ) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError>;
 }
 
 /// The `AccelModel` trait handles immutable dynamics which return an acceleration. Those can be added directly to Orbital Dynamics for example.
@@ -148,9 +190,17 @@ pub trait AccelModel: Send + Sync + fmt::Display {
     /// Defines the equations of motion for this force model from the provided osculating state in the integration frame.
     fn eom(&self, osc: &Orbit) -> Result<Vector3<f64>, DynamicsError>;
 
-    /// Acceleration models must implement their partials, although those will only be called if the propagation requires the
-    /// computation of the STM.
-    fn dual_eom(&self, osc_ctx: &Orbit) -> Result<(Vector3<f64>, Matrix3<f64>), DynamicsError>;
+    /// Acceleration models must implement their partials with respect to the position and the velocity, although those will only be
+    /// called if the propagation requires the computation of the STM.
+    fn dual_eom(&self, osc_ctx: &Orbit) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError>;
+}
+
+/// The `TorqueModel` trait handles the torques acting on a `RigidSpacecraft`, whose attitude is propagated by the `AttitudeDynamics`.
+///
+/// Examples include the gravity gradient, the SRP on the plates of a surface model, or the control torques of reaction wheels or thrusters.
+pub trait TorqueModel: Send + Sync + fmt::Display {
+    /// Returns the torque about the center of mass in N m, in the body frame of the spacecraft from the provided osculating state.
+    fn torque(&self, ctx: &RigidSpacecraft) -> Result<Vector3<f64>, DynamicsError>;
 }
 
 /// Stores dynamical model errors
@@ -165,4 +215,7 @@ pub enum DynamicsError {
     DynamicsAstro { source: AstroError },
     #[snafu(display("dynamical model encountered an issue with the guidance: {source}"))]
     DynamicsGuidance { source: GuidanceErrors },
+    /// Data needed by the dynamical model is not available at this epoch (e.g. space weather)
+    #[snafu(display("dynamical model data unavailable: {msg}"))]
+    DataUnavailable { msg: String },
 }
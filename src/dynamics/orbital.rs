@@ -18,7 +18,7 @@
 
 use super::{AccelModel, Dynamics, DynamicsError};
 use crate::cosmic::{Bodies, Cosm, Frame, LightTimeCalc, Orbit};
-use crate::linalg::{Const, Matrix3, Matrix6, OVector, Vector3, Vector6};
+use crate::linalg::{Const, Matrix3x6, Matrix6, OVector, Vector3, Vector6};
 use crate::State;
 use hyperdual::linalg::norm;
 use hyperdual::{extract_jacobian_and_result, hyperspace_from_vector, Float, OHyperdual};
@@ -163,8 +163,8 @@ impl Dynamics for OrbitalDynamics {
             let (model_acc, model_grad) = model.dual_eom(osc)?;
             for i in 0..3 {
                 dx[i + 3] += model_acc[i];
-                for j in 1..4 {
-                    grad[(i + 3, j - 1)] += model_grad[(i, j - 1)];
+                for j in 0..6 {
+                    grad[(i + 3, j)] += model_grad[(i, j)];
                 }
             }
         }
@@ -254,12 +254,12 @@ impl AccelModel for PointMasses {
         Ok(d_x)
     }
 
-    fn dual_eom(&self, osc: &Orbit) -> Result<(Vector3<f64>, Matrix3<f64>), DynamicsError> {
+    fn dual_eom(&self, osc: &Orbit) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError> {
         // Build the hyperdual space of the radius vector
         let radius: Vector3<OHyperdual<f64, Const<7>>> = hyperspace_from_vector(&osc.radius());
         // Extract result into Vector6 and Matrix6
         let mut fx = Vector3::zeros();
-        let mut grad = Matrix3::zeros();
+        let mut grad = Matrix3x6::zeros();
 
         // Get all of the position vectors between the center body and the third bodies
         for third_body in &self.bodies {
@@ -294,7 +294,9 @@ impl AccelModel for PointMasses {
 
             let (fxp, gradp) = extract_jacobian_and_result::<_, 3, 3, 7>(&third_body_acc_d);
             fx += fxp;
-            grad += gradp;
+            // Point masses do not depend on the velocity
+            let mut grad_r = grad.fixed_view_mut::<3, 3>(0, 0);
+            grad_r += gradp;
         }
 
         Ok((fx, grad))
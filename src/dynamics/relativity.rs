@@ -0,0 +1,313 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{AccelModel, DynamicsError};
+use crate::cosmic::{Bodies, Cosm, Frame, LightTimeCalc, Orbit, SPEED_OF_LIGHT_KMS};
+use crate::linalg::{Const, Matrix3x6, Vector3, Vector6};
+use hyperdual::linalg::norm;
+use hyperdual::{extract_jacobian_and_result, hyperspace_from_vector, Float, OHyperdual};
+use std::fmt;
+use std::sync::Arc;
+
+/// Angular momentum per unit mass of the Earth, in km^2/s, as per the IERS Conventions (2010)
+pub const EARTH_ANGULAR_MOMENTUM_KM2_S: f64 = 980.0;
+
+/// `Relativity` provides the post-Newtonian corrections to the acceleration due to the central body of the integration frame,
+/// as per the IERS Conventions (2010), section 10.3, with the PPN parameters β = γ = 1.
+///
+/// These are the Schwarzschild term, the Lense-Thirring precession due to the rotation of the central body, and the
+/// de Sitter (geodesic) precession due to the motion of the central body around the Sun. The integration frame must be inertial.
+pub struct Relativity {
+    /// Enables the Schwarzschild term
+    pub schwarzschild: bool,
+    /// Body fixed frame of the central body, whose pole is the direction of its angular momentum (Lense-Thirring)
+    pub body_frame: Frame,
+    /// Angular momentum per unit mass of the central body in km^2/s, enables the Lense-Thirring term if set
+    pub angular_momentum_km2_s: Option<f64>,
+    /// Enables the de Sitter term
+    pub de_sitter: bool,
+    /// A Cosm reference is needed for the orientation of the central body and its motion around the Sun
+    pub cosm: Arc<Cosm>,
+}
+
+impl Relativity {
+    /// Schwarzschild, Lense-Thirring and de Sitter corrections for orbits around the Earth
+    pub fn earth(cosm: Arc<Cosm>) -> Arc<Self> {
+        Arc::new(Self {
+            schwarzschild: true,
+            body_frame: cosm.frame("IAU Earth"),
+            angular_momentum_km2_s: Some(EARTH_ANGULAR_MOMENTUM_KM2_S),
+            de_sitter: true,
+            cosm,
+        })
+    }
+
+    /// Only the Schwarzschild correction of the central body of the provided body fixed frame, which dominates the other terms
+    pub fn schwarzschild(body_frame: Frame, cosm: Arc<Cosm>) -> Arc<Self> {
+        Arc::new(Self {
+            schwarzschild: true,
+            body_frame,
+            angular_momentum_km2_s: None,
+            de_sitter: false,
+            cosm,
+        })
+    }
+
+    /// Returns the angular momentum per unit mass of the central body (Lense-Thirring) and the angular velocity of the
+    /// de Sitter precession, both in the integration frame and set to zero if disabled.
+    fn precession_vectors(
+        &self,
+        osc: &Orbit,
+    ) -> Result<(Vector3<f64>, Vector3<f64>), DynamicsError> {
+        let unavailable = |e| DynamicsError::DataUnavailable {
+            msg: format!("{e}"),
+        };
+
+        let angular_momentum = match self.angular_momentum_km2_s {
+            Some(momentum) => {
+                let dcm = self
+                    .cosm
+                    .try_position_dcm_from_to(&self.body_frame, &osc.frame, osc.epoch)
+                    .map_err(unavailable)?;
+                momentum * dcm.column(2)
+            }
+            None => Vector3::zeros(),
+        };
+
+        let sun_path = Bodies::Sun.ephem_path();
+        let de_sitter = if self.de_sitter && osc.frame.ephem_path() != sun_path {
+            // State of the central body with respect to the Sun
+            let sun = self
+                .cosm
+                .try_celestial_state(sun_path, osc.epoch, osc.frame, LightTimeCalc::None)
+                .map_err(unavailable)?;
+            let (r_sun, v_sun) = (-sun.radius(), -sun.velocity());
+            let gm_sun = self.cosm.frame_from_ephem_path(sun_path).gm();
+            3.0 * v_sun
+                .cross(&(-gm_sun * r_sun / (SPEED_OF_LIGHT_KMS.powi(2) * r_sun.norm().powi(3))))
+        } else {
+            Vector3::zeros()
+        };
+
+        Ok((angular_momentum, de_sitter))
+    }
+
+    /// Computes the acceleration with hyperdual numbers, so that the partials with respect to the state are available.
+    fn dual_accel(&self, osc: &Orbit) -> Result<Vector3<OHyperdual<f64, Const<7>>>, DynamicsError> {
+        let (angular_momentum, de_sitter) = self.precession_vectors(osc)?;
+
+        let state: Vector6<OHyperdual<f64, Const<7>>> =
+            hyperspace_from_vector(&osc.to_cartesian_vec());
+        let r = state.fixed_rows::<3>(0).into_owned();
+        let v = state.fixed_rows::<3>(3).into_owned();
+
+        let dual = OHyperdual::<f64, Const<7>>::from_real;
+        let dot = |a: &Vector3<OHyperdual<f64, Const<7>>>,
+                   b: &Vector3<OHyperdual<f64, Const<7>>>| {
+            a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+        };
+        let cross = |a: &Vector3<OHyperdual<f64, Const<7>>>,
+                     b: &Vector3<OHyperdual<f64, Const<7>>>| {
+            Vector3::new(
+                a[1] * b[2] - a[2] * b[1],
+                a[2] * b[0] - a[0] * b[2],
+                a[0] * b[1] - a[1] * b[0],
+            )
+        };
+
+        let gm = osc.frame.gm();
+        let c2 = SPEED_OF_LIGHT_KMS.powi(2);
+        let rmag = norm(&r);
+        let gm_c2r3 = dual(gm / c2) / rmag.powi(3);
+
+        let mut accel = Vector3::from_element(dual(0.0));
+
+        if self.schwarzschild {
+            let radial = dual(4.0 * gm) / rmag - dot(&v, &v);
+            let along = dual(4.0) * dot(&r, &v);
+            for i in 0..3 {
+                accel[i] += gm_c2r3 * (radial * r[i] + along * v[i]);
+            }
+        }
+
+        if self.angular_momentum_km2_s.is_some() {
+            let j = Vector3::new(
+                dual(angular_momentum[0]),
+                dual(angular_momentum[1]),
+                dual(angular_momentum[2]),
+            );
+            let r_cross_v = cross(&r, &v);
+            let v_cross_j = cross(&v, &j);
+            let r_dot_j = dual(3.0) * dot(&r, &j) / rmag.powi(2);
+            for i in 0..3 {
+                accel[i] += dual(2.0) * gm_c2r3 * (r_dot_j * r_cross_v[i] + v_cross_j[i]);
+            }
+        }
+
+        if self.de_sitter {
+            let omega = Vector3::new(dual(de_sitter[0]), dual(de_sitter[1]), dual(de_sitter[2]));
+            accel += cross(&omega, &v);
+        }
+
+        Ok(accel)
+    }
+}
+
+impl fmt::Display for Relativity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut terms = Vec::new();
+        if self.schwarzschild {
+            terms.push("Schwarzschild".to_string());
+        }
+        if let Some(momentum) = self.angular_momentum_km2_s {
+            terms.push(format!(
+                "Lense-Thirring (J = {momentum} km^2/s about {})",
+                self.body_frame
+            ));
+        }
+        if self.de_sitter {
+            terms.push("de Sitter".to_string());
+        }
+        write!(f, "Relativistic corrections: {}", terms.join(", "))
+    }
+}
+
+impl AccelModel for Relativity {
+    fn eom(&self, osc: &Orbit) -> Result<Vector3<f64>, DynamicsError> {
+        Ok(self.dual_eom(osc)?.0)
+    }
+
+    fn dual_eom(&self, osc: &Orbit) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError> {
+        let accel = self.dual_accel(osc)?;
+        Ok(extract_jacobian_and_result::<_, 6, 3, 7>(&accel))
+    }
+}
+
+#[cfg(test)]
+mod ut_relativity {
+    use super::*;
+    use crate::io::spk::{Spk, SpkSegment};
+    use crate::time::Epoch;
+
+    #[test]
+    fn test_relativity() {
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let eme2k = cosm.frame("EME2000");
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+
+        // The Schwarzschild term of a circular orbit is radial, of magnitude 3 (GM)^2 / (c^2 r^3)
+        let gps = Orbit::keplerian(26_560.0, 0.0, 55.0, 10.0, 0.0, 30.0, epoch, eme2k);
+        let schwarzschild = Relativity::schwarzschild(cosm.frame("IAU Earth"), cosm.clone());
+        let accel = schwarzschild.eom(&gps).unwrap();
+        let expected =
+            3.0 * eme2k.gm().powi(2) / (SPEED_OF_LIGHT_KMS.powi(2) * gps.rmag_km().powi(3));
+        assert!((accel.norm() / expected - 1.0).abs() < 1e-9);
+        assert!(accel.dot(&gps.radius()) / (accel.norm() * gps.rmag_km()) > 1.0 - 1e-12);
+
+        // The Lense-Thirring term is a few orders of magnitude smaller, and the de Sitter term vanishes since the Earth is fixed here
+        let full = Relativity::earth(cosm);
+        assert!(format!("{full}").contains("Lense-Thirring"));
+        let leo = Orbit::keplerian(7_000.0, 0.01, 98.0, 10.0, 20.0, 30.0, epoch, eme2k);
+        let lense_thirring = full.eom(&leo).unwrap() - schwarzschild.eom(&leo).unwrap();
+        let ratio = lense_thirring.norm() / schwarzschild.eom(&leo).unwrap().norm();
+        assert!(ratio > 1e-3 && ratio < 1e-1, "{ratio}");
+
+        // The partials match the finite differences of the acceleration
+        let (accel, partials) = full.dual_eom(&leo).unwrap();
+        assert!((accel - full.eom(&leo).unwrap()).norm() < f64::EPSILON);
+        for j in 0..6 {
+            let step = if j < 3 { 1e-1 } else { 1e-4 };
+            let perturbed = |delta: f64| {
+                let mut state = leo.to_cartesian_vec();
+                state[j] += delta;
+                full.eom(&Orbit::cartesian_vec(&state, epoch, eme2k))
+                    .unwrap()
+            };
+            let fd = (perturbed(step) - perturbed(-step)) / (2.0 * step);
+            let err = (fd - partials.column(j)).norm() / partials.column(j).norm();
+            assert!(err < 1e-5, "column {j} relative error {err:e}");
+        }
+    }
+
+    #[test]
+    fn test_de_sitter_lense_thirring() {
+        // The Earth moves at 29.78 km/s along Y, 1.5e8 km from the Sun along X, at J2000 where the IAU Earth pole is the Z axis
+        let earth_sun_km = 1.5e8 - 1e3 - 4e3;
+        let earth_vel_km_s = 29.78;
+        let cosm = Arc::new(
+            Cosm::try_from_spk(vec![Spk {
+                segments: vec![
+                    SpkSegment::fixed(10, 0, 1e3),
+                    SpkSegment::linear(3, 0, 1.5e8, earth_vel_km_s),
+                    SpkSegment::fixed(399, 3, -4e3),
+                ],
+            }])
+            .unwrap(),
+        );
+        let eme2k = cosm.frame("EME2000");
+        let epoch = Epoch::from_et_seconds(0.0);
+        let c2 = SPEED_OF_LIGHT_KMS.powi(2);
+
+        let only = |lense_thirring: bool, de_sitter: bool| Relativity {
+            schwarzschild: false,
+            body_frame: cosm.frame("IAU Earth"),
+            angular_momentum_km2_s: if lense_thirring {
+                Some(EARTH_ANGULAR_MOMENTUM_KM2_S)
+            } else {
+                None
+            },
+            de_sitter,
+            cosm: cosm.clone(),
+        };
+
+        // IERS Conventions (2010) eq. 10.12: the de Sitter precession is Ω = 3 V x (-GM_S R / (c^2 R^3)) = 3 GM_S V / (c^2 R^2) Z here,
+        // i.e. 5.9e-15 rad/s, so the acceleration Ω x v of a GNSS orbit is at most 2.3e-14 km/s^2, and 1.6e-14 km/s^2 with this inclination.
+        let gps = Orbit::keplerian(26_560.0, 0.0, 55.0, 10.0, 0.0, 30.0, epoch, eme2k);
+        let gm_sun = cosm.frame("Sun J2000").gm();
+        let omega = Vector3::z() * 3.0 * gm_sun * earth_vel_km_s / (c2 * earth_sun_km.powi(2));
+        let expected = omega.cross(&gps.velocity());
+        let accel = only(false, true).eom(&gps).unwrap();
+        assert!((accel - expected).norm() / expected.norm() < 1e-9);
+        assert!((omega.norm() * gps.vmag_km_s() - 2.3e-14).abs() < 1e-15);
+        assert!(
+            accel.norm() > 1.5e-14 && accel.norm() < 1.7e-14,
+            "{:e}",
+            accel.norm()
+        );
+
+        // Lense-Thirring of a circular equatorial orbit: radial, of magnitude 2 GM J v / (c^2 r^3), i.e. 1.8e-15 km/s^2 for GPS
+        let gm = eme2k.gm();
+        let lense_thirring = only(true, false);
+        let equatorial = Orbit::keplerian(26_560.0, 0.0, 0.0, 0.0, 0.0, 0.0, epoch, eme2k);
+        let expected = 2.0 * gm * EARTH_ANGULAR_MOMENTUM_KM2_S * equatorial.vmag_km_s()
+            / (c2 * equatorial.rmag_km().powi(3));
+        let accel = lense_thirring.eom(&equatorial).unwrap();
+        assert!((accel.norm() / expected - 1.0).abs() < 1e-9);
+        assert!(
+            accel.dot(&equatorial.radius()) / (accel.norm() * equatorial.rmag_km()) > 1.0 - 1e-9
+        );
+
+        // Over the pole of a circular polar orbit, it is twice as large and normal to the orbit plane (LAGEOS altitude here)
+        let polar = Orbit::keplerian(12_270.0, 0.0, 90.0, 0.0, 0.0, 90.0, epoch, eme2k);
+        let expected = 4.0 * gm * EARTH_ANGULAR_MOMENTUM_KM2_S * polar.vmag_km_s()
+            / (c2 * polar.rmag_km().powi(3));
+        let accel = lense_thirring.eom(&polar).unwrap();
+        assert!((accel.norm() / expected - 1.0).abs() < 1e-9);
+        assert!(accel.dot(&polar.hvec()).abs() / (accel.norm() * polar.hmag_km2_s()) > 1.0 - 1e-9);
+    }
+}
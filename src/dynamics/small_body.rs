@@ -0,0 +1,630 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{AccelModel, DynamicsError};
+use crate::cosmic::{Cosm, Frame, Orbit, Spacecraft};
+use crate::io::shape::ShapeModel;
+use crate::linalg::{Matrix3, Matrix3x6, Vector3};
+use crate::md::EventEvaluator;
+use crate::time::{Duration, Unit};
+use crate::NyxError;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Returns the point mass acceleration and gravity gradient of the provided GM at the provided position relative to it
+fn point_mass(gm: f64, radius: &Vector3<f64>) -> (Vector3<f64>, Matrix3<f64>) {
+    let rmag = radius.norm();
+    let accel = -gm / rmag.powi(3) * radius;
+    let gradient = gm / rmag.powi(5) * (3.0 * radius * radius.transpose())
+        - gm / rmag.powi(3) * Matrix3::identity();
+    (accel, gradient)
+}
+
+/// Rotates the body fixed acceleration and gravity gradient into the frame of the osculating orbit, and removes the two body
+/// acceleration of that frame (which is always included in the orbital dynamics).
+fn to_perturbation(
+    cosm: &Cosm,
+    compute_frame: &Frame,
+    osc: &Orbit,
+    accel: Vector3<f64>,
+    gradient: Matrix3<f64>,
+) -> (Vector3<f64>, Matrix3x6<f64>) {
+    // As for the spherical harmonics, the rotation of the body does not change the acceleration, so there is no transport theorem here.
+    let dcm = cosm
+        .try_position_dcm_from_to(compute_frame, &osc.frame, osc.epoch)
+        .unwrap();
+    let (two_body_accel, two_body_gradient) = point_mass(osc.frame.gm(), &osc.radius());
+
+    let mut grad = Matrix3x6::zeros();
+    grad.fixed_view_mut::<3, 3>(0, 0)
+        .copy_from(&(dcm * gradient * dcm.transpose() - two_body_gradient));
+    (dcm * accel - two_body_accel, grad)
+}
+
+/// Constant density polyhedron gravity field of a small body (Werner and Scheeres, 1997, "Exterior gravitation of a polyhedron
+/// derived and compared with harmonic and mascon gravitation representations of asteroid 4769 Castalia").
+///
+/// Unlike the spherical harmonics, this field is exact everywhere, including inside the Brillouin sphere and inside the body.
+/// The density is set such that the mass of the polyhedron matches the GM of the compute frame.
+///
+/// Like the `Harmonics`, this model only returns the difference with the two body acceleration of the integration frame, which must hence be centered on the small body.
+#[derive(Clone)]
+pub struct Polyhedron {
+    cosm: Arc<Cosm>,
+    compute_frame: Frame,
+    shape: Arc<ShapeModel>,
+    /// Gravitational constant times the density, in 1/s^2
+    g_rho: f64,
+    /// Vertex indices of each edge and its edge dyad
+    edges: Vec<(usize, usize, Matrix3<f64>)>,
+    /// Face dyad of each face
+    face_dyads: Vec<Matrix3<f64>>,
+}
+
+impl Polyhedron {
+    /// Initializes the polyhedron gravity field of the provided shape model expressed in the compute frame (a body fixed frame of the small body).
+    pub fn from_shape(compute_frame: Frame, shape: ShapeModel, cosm: Arc<Cosm>) -> Arc<Self> {
+        let g_rho = compute_frame.gm() / shape.volume_km3();
+
+        let mut face_dyads = Vec::with_capacity(shape.faces().len());
+        // Maps each edge to the sum of the face normal times the edge normal of the faces sharing it
+        let mut edge_dyads: HashMap<(usize, usize), Matrix3<f64>> =
+            HashMap::with_capacity(3 * shape.faces().len() / 2);
+
+        for (f, face) in shape.faces().iter().enumerate() {
+            let [v1, v2, v3] = shape.face_vertices(f);
+            let normal = (v2 - v1).cross(&(v3 - v1)).normalize();
+            face_dyads.push(normal * normal.transpose());
+
+            for k in 0..3 {
+                let (i, j) = (face[k], face[(k + 1) % 3]);
+                // The faces are counter-clockwise, so this edge normal is in the plane of the face and points outward
+                let edge_normal = (shape.vertices()[j] - shape.vertices()[i])
+                    .cross(&normal)
+                    .normalize();
+                *edge_dyads
+                    .entry((i.min(j), i.max(j)))
+                    .or_insert_with(Matrix3::zeros) += normal * edge_normal.transpose();
+            }
+        }
+
+        let mut edges: Vec<(usize, usize, Matrix3<f64>)> = edge_dyads
+            .into_iter()
+            .map(|((i, j), dyad)| (i, j, dyad))
+            .collect();
+        // Sort the edges so that the sums are always computed in the same order
+        edges.sort_by_key(|(i, j, _)| (*i, *j));
+
+        Arc::new(Self {
+            cosm,
+            compute_frame,
+            shape: Arc::new(shape),
+            g_rho,
+            edges,
+            face_dyads,
+        })
+    }
+
+    /// Loads the shape model from the provided OBJ or PLY file (with vertices in km) and initializes its polyhedron gravity field
+    pub fn load(path: &str, compute_frame: Frame, cosm: Arc<Cosm>) -> Result<Arc<Self>, NyxError> {
+        Ok(Self::from_shape(
+            compute_frame,
+            ShapeModel::load(path)?,
+            cosm,
+        ))
+    }
+
+    /// Returns the shape model of this polyhedron
+    pub fn shape(&self) -> &ShapeModel {
+        &self.shape
+    }
+
+    /// Returns the body fixed acceleration in km/s^2, the gravity gradient in 1/s^2 and the Laplacian of the potential at the provided body fixed position in km
+    pub fn gravity(&self, radius: &Vector3<f64>) -> (Vector3<f64>, Matrix3<f64>, f64) {
+        let vertices = self.shape.vertices();
+
+        let mut accel = Vector3::zeros();
+        let mut gradient = Matrix3::zeros();
+        for (i, j, dyad) in &self.edges {
+            let r_i = vertices[*i] - radius;
+            let r_j = vertices[*j] - radius;
+            let (a, b) = (r_i.norm(), r_j.norm());
+            let e = (vertices[*j] - vertices[*i]).norm();
+            let l_e = ((a + b + e) / (a + b - e)).ln();
+            accel -= dyad * r_i * l_e;
+            gradient += dyad * l_e;
+        }
+
+        let mut solid_angle = 0.0;
+        for (f, dyad) in self.face_dyads.iter().enumerate() {
+            let r_f = vertices[self.shape.faces()[f][0]] - radius;
+            let omega_f = self.shape.face_solid_angle(f, radius);
+            accel += dyad * r_f * omega_f;
+            gradient -= dyad * omega_f;
+            solid_angle += omega_f;
+        }
+
+        (
+            self.g_rho * accel,
+            self.g_rho * gradient,
+            -self.g_rho * solid_angle,
+        )
+    }
+
+    /// Returns whether the provided orbit is inside the body, i.e. where the Laplacian of the potential is not zero
+    pub fn is_inside(&self, osc: &Orbit) -> bool {
+        let state = self.cosm.frame_chg(osc, self.compute_frame);
+        self.shape.contains(&state.radius())
+    }
+
+    /// Returns an event which finds when the spacecraft crosses the surface of this body
+    pub fn to_contact_event(&self) -> SurfaceContactEvent {
+        SurfaceContactEvent {
+            shape: self.shape.clone(),
+            body_frame: self.compute_frame,
+            cosm: self.cosm.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Polyhedron {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} polyhedron gravity field of {} edges and {} faces",
+            self.compute_frame,
+            self.edges.len(),
+            self.face_dyads.len()
+        )
+    }
+}
+
+impl AccelModel for Polyhedron {
+    fn eom(&self, osc: &Orbit) -> Result<Vector3<f64>, DynamicsError> {
+        Ok(self.dual_eom(osc)?.0)
+    }
+
+    fn dual_eom(&self, osc: &Orbit) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError> {
+        let state = self.cosm.frame_chg(osc, self.compute_frame);
+        let (accel, gradient, _) = self.gravity(&state.radius());
+        Ok(to_perturbation(
+            &self.cosm,
+            &self.compute_frame,
+            osc,
+            accel,
+            gradient,
+        ))
+    }
+}
+
+/// A point mass of a mascon gravity field
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mascon {
+    /// Position in the body fixed frame, in km
+    pub position_km: Vector3<f64>,
+    /// Gravitational parameter, in km^3/s^2
+    pub gm: f64,
+}
+
+/// Mass concentration (mascon) gravity field of a small body, i.e. a cloud of point masses.
+///
+/// This field is cheaper to evaluate than the polyhedron for detailed shape models but is singular close to each mascon.
+///
+/// Like the `Harmonics`, this model only returns the difference with the two body acceleration of the integration frame, which must hence be centered on the small body.
+#[derive(Clone)]
+pub struct Mascons {
+    cosm: Arc<Cosm>,
+    compute_frame: Frame,
+    mascons: Vec<Mascon>,
+    shape: Option<Arc<ShapeModel>>,
+}
+
+impl Mascons {
+    /// Initializes a mascon gravity field from the provided point masses expressed in the compute frame (a body fixed frame of the small body).
+    ///
+    /// The sum of the GMs of the mascons should match the GM of the integration frame.
+    pub fn new(compute_frame: Frame, mascons: Vec<Mascon>, cosm: Arc<Cosm>) -> Arc<Self> {
+        Arc::new(Self {
+            cosm,
+            compute_frame,
+            mascons,
+            shape: None,
+        })
+    }
+
+    /// Fills the provided shape model with mascons of equal mass on a cubic grid, whose resolution is set by the number of cells along the largest dimension of the body.
+    ///
+    /// The total mass of the mascons matches the GM of the compute frame.
+    pub fn from_shape(
+        compute_frame: Frame,
+        shape: ShapeModel,
+        cells_across: usize,
+        cosm: Arc<Cosm>,
+    ) -> Result<Arc<Self>, NyxError> {
+        if cells_across == 0 {
+            return Err(NyxError::CustomError {
+                msg: "mascon grid needs at least one cell".to_string(),
+            });
+        }
+        let mut min = Vector3::repeat(f64::INFINITY);
+        let mut max = Vector3::repeat(f64::NEG_INFINITY);
+        for vertex in shape.vertices() {
+            min = min.inf(vertex);
+            max = max.sup(vertex);
+        }
+        let cell = (max - min).max() / cells_across as f64;
+
+        let mut positions = Vec::new();
+        let counts = ((max - min) / cell).map(|n| n.ceil() as usize);
+        for i in 0..counts[0] {
+            for j in 0..counts[1] {
+                for k in 0..counts[2] {
+                    let center =
+                        min + cell * Vector3::new(i as f64, j as f64, k as f64).add_scalar(0.5);
+                    if shape.contains(&center) {
+                        positions.push(center);
+                    }
+                }
+            }
+        }
+        if positions.is_empty() {
+            return Err(NyxError::CustomError {
+                msg: format!(
+                    "mascon grid of {cells_across} cells across is too coarse for {shape}"
+                ),
+            });
+        }
+
+        let gm = compute_frame.gm() / positions.len() as f64;
+        let mascons = positions
+            .into_iter()
+            .map(|position_km| Mascon { position_km, gm })
+            .collect();
+
+        Ok(Arc::new(Self {
+            cosm,
+            compute_frame,
+            mascons,
+            shape: Some(Arc::new(shape)),
+        }))
+    }
+
+    /// Returns the mascons of this gravity field
+    pub fn mascons(&self) -> &[Mascon] {
+        &self.mascons
+    }
+
+    /// Returns the body fixed acceleration in km/s^2 and the gravity gradient in 1/s^2 at the provided body fixed position in km
+    pub fn gravity(&self, radius: &Vector3<f64>) -> (Vector3<f64>, Matrix3<f64>) {
+        let mut accel = Vector3::zeros();
+        let mut gradient = Matrix3::zeros();
+        for mascon in &self.mascons {
+            let (mascon_accel, mascon_gradient) =
+                point_mass(mascon.gm, &(radius - mascon.position_km));
+            accel += mascon_accel;
+            gradient += mascon_gradient;
+        }
+        (accel, gradient)
+    }
+
+    /// Returns whether the provided orbit is inside the shape model of the body, or None if these mascons were not built from a shape model
+    pub fn is_inside(&self, osc: &Orbit) -> Option<bool> {
+        let state = self.cosm.frame_chg(osc, self.compute_frame);
+        self.shape
+            .as_ref()
+            .map(|shape| shape.contains(&state.radius()))
+    }
+
+    /// Returns an event which finds when the spacecraft crosses the surface of this body, or None if these mascons were not built from a shape model
+    pub fn to_contact_event(&self) -> Option<SurfaceContactEvent> {
+        self.shape.as_ref().map(|shape| SurfaceContactEvent {
+            shape: shape.clone(),
+            body_frame: self.compute_frame,
+            cosm: self.cosm.clone(),
+        })
+    }
+}
+
+impl fmt::Display for Mascons {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} mascon gravity field of {} point masses",
+            self.compute_frame,
+            self.mascons.len()
+        )
+    }
+}
+
+impl AccelModel for Mascons {
+    fn eom(&self, osc: &Orbit) -> Result<Vector3<f64>, DynamicsError> {
+        Ok(self.dual_eom(osc)?.0)
+    }
+
+    fn dual_eom(&self, osc: &Orbit) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError> {
+        let state = self.cosm.frame_chg(osc, self.compute_frame);
+        let (accel, gradient) = self.gravity(&state.radius());
+        Ok(to_perturbation(
+            &self.cosm,
+            &self.compute_frame,
+            osc,
+            accel,
+            gradient,
+        ))
+    }
+}
+
+/// An event to find when the spacecraft crosses the surface of a small body, evaluated as the signed distance to its shape model (negative inside the body).
+pub struct SurfaceContactEvent {
+    shape: Arc<ShapeModel>,
+    body_frame: Frame,
+    cosm: Arc<Cosm>,
+}
+
+impl SurfaceContactEvent {
+    /// Initializes a surface contact event of the provided shape model expressed in the provided body fixed frame
+    pub fn new(shape: ShapeModel, body_frame: Frame, cosm: Arc<Cosm>) -> Self {
+        Self {
+            shape: Arc::new(shape),
+            body_frame,
+            cosm,
+        }
+    }
+
+    /// Returns the distance to the surface of the body in km, negative inside the body
+    pub fn altitude_km(&self, osc: &Orbit) -> f64 {
+        let state = self.cosm.frame_chg(osc, self.body_frame);
+        self.shape.signed_distance_km(&state.radius())
+    }
+}
+
+impl fmt::Display for SurfaceContactEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "surface contact with {} in {}",
+            self.shape, self.body_frame
+        )
+    }
+}
+
+impl EventEvaluator<Orbit> for SurfaceContactEvent {
+    fn eval(&self, observer: &Orbit) -> f64 {
+        self.altitude_km(observer)
+    }
+
+    /// Stop searching when the time has converged to less than 1 millisecond
+    fn epoch_precision(&self) -> Duration {
+        1 * Unit::Millisecond
+    }
+
+    /// Finds the contact within 1 centimeter of the surface
+    fn value_precision(&self) -> f64 {
+        1e-5
+    }
+
+    fn eval_string(&self, state: &Orbit) -> String {
+        format!("altitude above shape = {:.6} km", self.altitude_km(state))
+    }
+}
+
+impl EventEvaluator<Spacecraft> for SurfaceContactEvent {
+    fn eval(&self, sc: &Spacecraft) -> f64 {
+        self.altitude_km(&sc.orbit)
+    }
+
+    /// Stop searching when the time has converged to less than 1 millisecond
+    fn epoch_precision(&self) -> Duration {
+        1 * Unit::Millisecond
+    }
+
+    /// Finds the contact within 1 centimeter of the surface
+    fn value_precision(&self) -> f64 {
+        1e-5
+    }
+
+    fn eval_string(&self, state: &Spacecraft) -> String {
+        format!(
+            "altitude above shape = {:.6} km",
+            self.altitude_km(&state.orbit)
+        )
+    }
+}
+
+#[cfg(test)]
+mod ut_small_body {
+    use super::*;
+    use crate::dynamics::OrbitalDynamics;
+    use crate::propagators::Propagator;
+    use crate::time::Epoch;
+    use std::f64::consts::PI;
+
+    /// A 2 km cube of density 2 g/cm^3 around the origin of a copy of EME2000 with the GM of the cube
+    fn cube() -> (ShapeModel, Frame, Arc<Cosm>) {
+        let mut vertices = Vec::new();
+        for k in 0..8 {
+            vertices.push(Vector3::new(
+                if k & 1 == 0 { -1.0 } else { 1.0 },
+                if k & 2 == 0 { -1.0 } else { 1.0 },
+                if k & 4 == 0 { -1.0 } else { 1.0 },
+            ));
+        }
+        let quads = [
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+        ];
+        let mut faces = Vec::new();
+        for q in quads {
+            faces.push([q[0], q[1], q[2]]);
+            faces.push([q[0], q[2], q[3]]);
+        }
+        let shape = ShapeModel::new(vertices, faces).unwrap();
+
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let mut frame = cosm.frame("EME2000");
+        // G * rho * V with G in km^3/kg/s^2, rho in kg/km^3 and V in km^3
+        frame.gm_mut(6.674_30e-20 * 2e12 * 8.0);
+        (shape, frame, cosm)
+    }
+
+    #[test]
+    fn test_polyhedron_gravity() {
+        let (shape, frame, cosm) = cube();
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        let poly = Polyhedron::from_shape(frame, shape, cosm);
+
+        // Far from the body, the field is that of a point mass (the cube has no second degree harmonics)
+        let far = Vector3::new(30.0, -20.0, 10.0);
+        let (accel, gradient, laplacian) = poly.gravity(&far);
+        let (pm_accel, pm_gradient) = point_mass(frame.gm(), &far);
+        assert!(
+            (accel - pm_accel).norm() < 1e-6 * pm_accel.norm(),
+            "{accel} {pm_accel}"
+        );
+        assert!((gradient - pm_gradient).norm() < 1e-5 * pm_gradient.norm());
+        assert!(laplacian.abs() < 1e-15);
+
+        // The perturbation with respect to the two body acceleration is hence tiny
+        let orbit = Orbit::cartesian(30.0, -20.0, 10.0, 0.0, 0.0, 0.0, epoch, frame);
+        assert!(poly.eom(&orbit).unwrap().norm() < 1e-6 * pm_accel.norm());
+
+        // On the surface, the acceleration points inward, and the Laplacian is -4πGρ inside the body
+        let (accel, _, _) = poly.gravity(&Vector3::new(1.0, 0.3, 0.1));
+        assert!(accel[0] < 0.0);
+        let (accel, gradient, laplacian) = poly.gravity(&Vector3::new(0.1, 0.2, -0.3));
+        let g_rho = frame.gm() / 8.0;
+        assert!((laplacian + 4.0 * PI * g_rho).abs() < 1e-12 * g_rho);
+        assert!((gradient.trace() - laplacian).abs() < 1e-12 * g_rho);
+        assert!(accel.dot(&Vector3::new(0.1, 0.2, -0.3)) < 0.0);
+        // By symmetry, the acceleration at the center is zero and the gradient is isotropic
+        let (accel, gradient, _) = poly.gravity(&Vector3::zeros());
+        assert!(accel.norm() < 1e-15);
+        assert!((gradient + 4.0 * PI / 3.0 * g_rho * Matrix3::identity()).norm() < 1e-12 * g_rho);
+
+        // The gravity gradient matches central differences, both inside and outside of the Brillouin sphere
+        for radius in [
+            Vector3::new(1.5, 0.3, -0.2),
+            Vector3::new(0.5, 0.9, -0.2),
+            Vector3::new(3.0, 2.0, 1.0),
+        ] {
+            let (_, gradient, _) = poly.gravity(&radius);
+            for j in 0..3 {
+                let mut step = Vector3::zeros();
+                step[j] = 1e-5;
+                let fd =
+                    (poly.gravity(&(radius + step)).0 - poly.gravity(&(radius - step)).0) / 2e-5;
+                assert!(
+                    (gradient.column(j) - fd).norm() < 1e-6 * gradient.norm(),
+                    "{radius}: {gradient} {fd}"
+                );
+            }
+        }
+
+        // And so do the partials of the acceleration model, including the two body gradient
+        let orbit = Orbit::cartesian(1.5, 0.3, -0.2, 0.0, 0.0, 0.0, epoch, frame);
+        let (accel, grad) = poly.dual_eom(&orbit).unwrap();
+        assert!((accel - poly.eom(&orbit).unwrap()).norm() < f64::EPSILON);
+        let (_, pm_gradient) = point_mass(frame.gm(), &orbit.radius());
+        let (_, gradient, _) = poly.gravity(&orbit.radius());
+        assert!((grad.fixed_view::<3, 3>(0, 0) - (gradient - pm_gradient)).norm() < 1e-15);
+        assert_eq!(grad.fixed_view::<3, 3>(0, 3), Matrix3::zeros());
+
+        assert!(poly.is_inside(&Orbit::cartesian(
+            0.9, 0.9, -0.9, 0.0, 0.0, 0.0, epoch, frame
+        )));
+        assert!(!poly.is_inside(&Orbit::cartesian(
+            1.1, 0.0, 0.0, 0.0, 0.0, 0.0, epoch, frame
+        )));
+    }
+
+    #[test]
+    fn test_mascon_gravity() {
+        let (shape, frame, cosm) = cube();
+        let mascons = Mascons::from_shape(frame, shape.clone(), 10, cosm.clone()).unwrap();
+        // The cube is exactly filled by the grid
+        assert_eq!(mascons.mascons().len(), 1000);
+        let poly = Polyhedron::from_shape(frame, shape, cosm.clone());
+
+        // The mascons approach the polyhedron field close to the body
+        for radius in [Vector3::new(3.0, 0.0, 0.0), Vector3::new(1.5, 1.5, 1.5)] {
+            let (accel, gradient) = mascons.gravity(&radius);
+            let (poly_accel, poly_gradient, _) = poly.gravity(&radius);
+            assert!((accel - poly_accel).norm() < 1e-3 * poly_accel.norm());
+            assert!((gradient - poly_gradient).norm() < 1e-2 * poly_gradient.norm());
+        }
+
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        let inside = Orbit::cartesian(0.5, 0.0, 0.0, 0.0, 0.0, 0.0, epoch, frame);
+        assert_eq!(mascons.is_inside(&inside), Some(true));
+
+        // Mascons without a shape model cannot tell whether the spacecraft is inside
+        let binary = Mascons::new(
+            frame,
+            vec![
+                Mascon {
+                    position_km: Vector3::new(-1.0, 0.0, 0.0),
+                    gm: frame.gm() / 2.0,
+                },
+                Mascon {
+                    position_km: Vector3::new(1.0, 0.0, 0.0),
+                    gm: frame.gm() / 2.0,
+                },
+            ],
+            cosm,
+        );
+        assert_eq!(binary.is_inside(&inside), None);
+        assert!(binary.to_contact_event().is_none());
+        // On the axis of the masses, the mascons pull more than the point mass
+        let on_axis = Orbit::cartesian(3.0, 0.0, 0.0, 0.0, 0.0, 0.0, epoch, frame);
+        assert!(binary.eom(&on_axis).unwrap()[0] < 0.0);
+    }
+
+    #[test]
+    fn test_surface_contact() {
+        let (shape, frame, cosm) = cube();
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        let poly = Polyhedron::from_shape(frame, shape, cosm);
+        let event = poly.to_contact_event();
+
+        // Start 5 km away with a slow tangential velocity: the periapsis is inside the cube
+        let orbit = Orbit::cartesian(5.0, 0.0, 0.2, 0.0, 2e-4, 0.0, epoch, frame).with_stm();
+        let dynamics = OrbitalDynamics::from_model(poly.clone());
+        let (contact, traj) = Propagator::default(dynamics)
+            .with(orbit)
+            .until_event(8 * Unit::Hour, &event)
+            .unwrap();
+
+        assert!(
+            event.eval(&contact).abs() < 1e-5,
+            "{}",
+            event.eval_string(&contact)
+        );
+        // The spacecraft is falling when it reaches the surface
+        assert!(contact.radius().dot(&contact.velocity()) < 0.0);
+        assert!(contact.epoch > epoch + 1 * Unit::Hour);
+        // It goes through the body and comes back out
+        let crossings = traj.find(&event).unwrap();
+        assert_eq!(crossings.len(), 2);
+        assert!(poly.is_inside(&traj.at(contact.epoch + 1 * Unit::Second).unwrap()));
+    }
+}
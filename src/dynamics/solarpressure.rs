@@ -16,10 +16,12 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use super::{DynamicsError, ForceModel};
+use super::{DynamicsError, ForceModel, TorqueModel};
 use crate::cosmic::eclipse::EclipseLocator;
-use crate::cosmic::{Cosm, Frame, Spacecraft, AU, SPEED_OF_LIGHT};
-use crate::linalg::{Const, Matrix3, Vector3};
+use crate::cosmic::{
+    Cosm, Frame, Orbit, RigidSpacecraft, Spacecraft, SurfaceModel, AU, SPEED_OF_LIGHT,
+};
+use crate::linalg::{Const, Matrix3x6, Vector3};
 use hyperdual::{hyperspace_from_vector, linalg::norm, Float, OHyperdual};
 use std::fmt;
 use std::sync::Arc;
@@ -30,6 +32,8 @@ pub struct SolarPressure {
     /// solar flux at 1 AU, in W/m^2
     pub phi: f64,
     pub e_loc: EclipseLocator,
+    /// Optional surface model (e.g. box-wing), whose plates replace the SRP area and coefficient of reflectivity of the spacecraft
+    pub surface: Option<Arc<SurfaceModel>>,
 }
 
 impl SolarPressure {
@@ -40,7 +44,11 @@ impl SolarPressure {
             shadow_bodies,
             cosm,
         };
-        Self { phi: 1367.0, e_loc }
+        Self {
+            phi: 1367.0,
+            e_loc,
+            surface: None,
+        }
     }
 
     /// Accounts for the shadowing of only one body and will set the solar flux at 1 AU to: Phi = 1367.0
@@ -54,12 +62,19 @@ impl SolarPressure {
         me.phi = flux_w_m2;
         Arc::new(me)
     }
+
+    /// Returns a copy of this model which evaluates the plates of the provided surface model
+    pub fn with_surface(&self, surface: Arc<SurfaceModel>) -> Arc<Self> {
+        let mut me = self.clone();
+        me.surface = Some(surface);
+        Arc::new(me)
+    }
 }
 
-impl ForceModel for SolarPressure {
-    fn eom(&self, ctx: &Spacecraft) -> Result<Vector3<f64>, DynamicsError> {
-        let osc = &ctx.orbit;
-        // Compute the position of the Sun as seen from the spacecraft
+impl SolarPressure {
+    /// Returns the unit vector from the Sun to the spacecraft and the radiation pressure in N/m^2, accounting for the eclipses
+    fn sun_unit_and_pressure(&self, osc: &Orbit) -> (Vector3<f64>, f64) {
+        // Compute the position of the spacecraft as seen from the Sun
         let r_sun = self
             .e_loc
             .cosm
@@ -75,11 +90,42 @@ impl ForceModel for SolarPressure {
         // in N/(m^2)
         let flux_pressure = (k * self.phi / SPEED_OF_LIGHT) * (1.0 / r_sun_au).powi(2);
 
+        (r_sun_unit, flux_pressure)
+    }
+}
+
+impl ForceModel for SolarPressure {
+    fn eom(&self, ctx: &Spacecraft) -> Result<Vector3<f64>, DynamicsError> {
+        let (r_sun_unit, flux_pressure) = self.sun_unit_and_pressure(&ctx.orbit);
+
         // Note the 1e-3 is to convert the SRP from m/s^2 to km/s^2
-        Ok(1e-3 * ctx.srp.cr * ctx.srp.area_m2 * flux_pressure * r_sun_unit)
+        match &self.surface {
+            // The radiation is evaluated on each plate, whose illuminated side faces the Sun, i.e. opposite to `r_sun`
+            Some(surface) => Ok(1e-3 * surface.srp_force_n(ctx, &-r_sun_unit, flux_pressure)),
+            None => Ok(1e-3 * ctx.srp.cr * ctx.srp.area_m2 * flux_pressure * r_sun_unit),
+        }
     }
 
-    fn dual_eom(&self, ctx: &Spacecraft) -> Result<(Vector3<f64>, Matrix3<f64>), DynamicsError> {
+    fn dual_eom(&self, ctx: &Spacecraft) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError> {
+        if self.surface.is_some() {
+            // The attitude of the plates depends on the orbit, so the partials of the surface model are computed by central differences.
+            let state = ctx.orbit.to_cartesian_vec();
+            let mut grad = Matrix3x6::zeros();
+            for j in 0..6 {
+                let step = if j < 3 { 1e-2 } else { 1e-5 };
+                let perturbed = |delta: f64| {
+                    let mut perturbed_state = state;
+                    perturbed_state[j] += delta;
+                    let mut osc_ctx = *ctx;
+                    osc_ctx.orbit =
+                        Orbit::cartesian_vec(&perturbed_state, ctx.orbit.epoch, ctx.orbit.frame);
+                    self.eom(&osc_ctx)
+                };
+                grad.set_column(j, &((perturbed(step)? - perturbed(-step)?) / (2.0 * step)));
+            }
+            return Ok((self.eom(ctx)?, grad));
+        }
+
         let osc = &ctx.orbit;
 
         // Compute the position of the Sun as seen from the spacecraft
@@ -112,10 +158,10 @@ impl ForceModel for SolarPressure {
 
         // Extract result into Vector6 and Matrix6
         let mut dx = Vector3::zeros();
-        let mut grad = Matrix3::zeros();
+        let mut grad = Matrix3x6::zeros();
         for i in 0..3 {
             dx[i] += dual_force[i].real();
-            // NOTE: Although the hyperdual state is of size 7, we're only setting the values up to 3 (Matrix3)
+            // NOTE: SRP does not depend on the velocity, so only the position partials are set
             for j in 0..3 {
                 grad[(i, j)] += dual_force[i][j + 1];
             }
@@ -125,12 +171,30 @@ impl ForceModel for SolarPressure {
     }
 }
 
+impl TorqueModel for SolarPressure {
+    /// The SRP torque is computed from the plates of the surface model, and is zero without one.
+    fn torque(&self, ctx: &RigidSpacecraft) -> Result<Vector3<f64>, DynamicsError> {
+        let sc = ctx.oriented();
+        match &self.surface {
+            Some(surface) => {
+                let (r_sun_unit, flux_pressure) = self.sun_unit_and_pressure(&sc.orbit);
+                Ok(surface.srp_torque_nm(&sc, &-r_sun_unit, flux_pressure))
+            }
+            None => Ok(Vector3::zeros()),
+        }
+    }
+}
+
 impl fmt::Display for SolarPressure {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
             "SRP with φ = {} W/m^2 and eclipse {}",
             self.phi, self.e_loc
-        )
+        )?;
+        if let Some(surface) = &self.surface {
+            write!(f, " on {surface}")?;
+        }
+        Ok(())
     }
 }
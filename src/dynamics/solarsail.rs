@@ -0,0 +1,172 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{DynamicsError, ForceModel};
+use crate::cosmic::eclipse::EclipseLocator;
+use crate::cosmic::{Cosm, Frame, Orbit, Sail, Spacecraft, AU, SPEED_OF_LIGHT};
+use crate::linalg::{Matrix3x6, Vector3};
+use std::fmt;
+use std::sync::Arc;
+
+/// Solar radiation pressure on the flat solar sail of the spacecraft, oriented by its cone and clock angles (cf. `Sail`).
+///
+/// The force is zero for a spacecraft without a sail. Use the sail steering guidance laws to orient the sail during the propagation.
+#[derive(Clone)]
+pub struct SolarSail {
+    /// solar flux at 1 AU, in W/m^2
+    pub phi: f64,
+    pub e_loc: EclipseLocator,
+}
+
+impl SolarSail {
+    /// Will set the solar flux at 1 AU to: Phi = 1367.0
+    pub fn default_raw(shadow_bodies: Vec<Frame>, cosm: Arc<Cosm>) -> Self {
+        let e_loc = EclipseLocator {
+            light_source: cosm.frame("Sun J2000"),
+            shadow_bodies,
+            cosm,
+        };
+        Self { phi: 1367.0, e_loc }
+    }
+
+    /// Accounts for the shadowing of only one body and will set the solar flux at 1 AU to: Phi = 1367.0
+    pub fn default(shadow_body: Frame, cosm: Arc<Cosm>) -> Arc<Self> {
+        Arc::new(Self::default_raw(vec![shadow_body], cosm))
+    }
+
+    /// Must provide the flux in W/m^2
+    pub fn with_flux(flux_w_m2: f64, shadow_bodies: Vec<Frame>, cosm: Arc<Cosm>) -> Arc<Self> {
+        let mut me = Self::default_raw(shadow_bodies, cosm);
+        me.phi = flux_w_m2;
+        Arc::new(me)
+    }
+
+    /// Returns the force in N of the provided sail at the provided state
+    pub fn force_n(&self, sail: &Sail, osc: &Orbit) -> Result<Vector3<f64>, DynamicsError> {
+        let r_sun = self
+            .e_loc
+            .cosm
+            .try_frame_chg(osc, self.e_loc.light_source)
+            .map_err(|e| DynamicsError::DataUnavailable {
+                msg: format!("{e}"),
+            })?
+            .radius();
+        let sunlight_unit = r_sun / r_sun.norm();
+
+        // Compute the shadowing factor.
+        let k: f64 = self.e_loc.compute(osc).into();
+        if k == 0.0 {
+            return Ok(Vector3::zeros());
+        }
+
+        let r_sun_au = r_sun.norm() / AU;
+        // in N/(m^2)
+        let flux_pressure = (k * self.phi / SPEED_OF_LIGHT) * (1.0 / r_sun_au).powi(2);
+
+        let normal = sail.normal(&Sail::dcm_sun_line(osc, &sunlight_unit));
+        Ok(sail
+            .optics
+            .force_n(sail.area_m2, flux_pressure, &sunlight_unit, &normal))
+    }
+}
+
+impl ForceModel for SolarSail {
+    fn eom(&self, ctx: &Spacecraft) -> Result<Vector3<f64>, DynamicsError> {
+        match &ctx.sail {
+            // Note the 1e-3 is to convert the force from N to kN
+            Some(sail) => Ok(1e-3 * self.force_n(sail, &ctx.orbit)?),
+            None => Ok(Vector3::zeros()),
+        }
+    }
+
+    fn dual_eom(&self, ctx: &Spacecraft) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError> {
+        let mut grad = Matrix3x6::zeros();
+        if ctx.sail.is_some() {
+            // The orientation of the sail depends on the orbit, so the partials are computed by central differences.
+            let state = ctx.orbit.to_cartesian_vec();
+            for j in 0..6 {
+                let step = if j < 3 { 1e-2 } else { 1e-5 };
+                let perturbed = |delta: f64| {
+                    let mut perturbed_state = state;
+                    perturbed_state[j] += delta;
+                    let mut osc_ctx = *ctx;
+                    osc_ctx.orbit =
+                        Orbit::cartesian_vec(&perturbed_state, ctx.orbit.epoch, ctx.orbit.frame);
+                    self.eom(&osc_ctx)
+                };
+                grad.set_column(j, &((perturbed(step)? - perturbed(-step)?) / (2.0 * step)));
+            }
+        }
+        Ok((self.eom(ctx)?, grad))
+    }
+}
+
+impl fmt::Display for SolarSail {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "solar sail with φ = {} W/m^2 and eclipse {}",
+            self.phi, self.e_loc
+        )
+    }
+}
+
+#[cfg(test)]
+mod ut_solarsail {
+    use super::*;
+    use crate::cosmic::SailOptics;
+    use crate::time::Epoch;
+
+    #[test]
+    fn test_solar_sail_force() {
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let eme2k = cosm.frame("EME2000");
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        let sail_model = SolarSail::default(eme2k, cosm);
+
+        // On the sunlit side, since the Sun is along -X in this test Cosm
+        let orbit = Orbit::keplerian(7_000.0, 0.0, 0.0, 0.0, 0.0, 180.0, epoch, eme2k);
+        let sc = Spacecraft::from_srp_defaults(orbit, 10.0, 0.0);
+        // No sail, no force
+        assert_eq!(sail_model.eom(&sc).unwrap(), Vector3::zeros());
+
+        let sc = sc.with_sail(Sail::new(100.0, SailOptics::ideal()));
+        let force_n = 1e3 * sail_model.eom(&sc).unwrap();
+        let r_sun_au = (1.5e8 - 1e3 - 4e3 - 7_000.0) / AU;
+        let expected = 2.0 * 100.0 * 1367.0 / SPEED_OF_LIGHT / r_sun_au.powi(2);
+        assert!((force_n[0] - expected).abs() < 1e-6 * expected, "{force_n}");
+        assert!(force_n[1].abs() < 1e-12 && force_n[2].abs() < 1e-12);
+
+        // The sail force depends on the position
+        let (_, grad) = sail_model.dual_eom(&sc).unwrap();
+        assert!(grad.norm() > 0.0);
+
+        // No force in the shadow of the Earth
+        let mut eclipsed = sc;
+        eclipsed.orbit = Orbit::keplerian(7_000.0, 0.0, 0.0, 0.0, 0.0, 0.0, epoch, eme2k);
+        assert_eq!(sail_model.eom(&eclipsed).unwrap(), Vector3::zeros());
+
+        // The ephemerides of this test Cosm end in 2316
+        let mut uncovered = sc;
+        uncovered.orbit.epoch = Epoch::from_gregorian_utc_at_midnight(2400, 1, 1);
+        assert!(matches!(
+            sail_model.eom(&uncovered),
+            Err(DynamicsError::DataUnavailable { .. })
+        ));
+    }
+}
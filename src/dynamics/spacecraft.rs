@@ -16,25 +16,30 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use super::guidance::{ra_dec_from_unit_vector, GuidanceErrors, GuidanceLaw};
+use super::albedo::{PlanetaryRadiation, RadiationCoefficients};
+use super::drag::{AtmDensity, Drag};
+use super::guidance::{ra_dec_from_unit_vector, ElectricThruster, GuidanceErrors, GuidanceLaw};
 use super::orbital::OrbitalDynamics;
+use super::sph_harmonics::is_lunar_non_pa;
 use super::{AccelModel, Dynamics, ForceModel};
 pub use crate::cosmic::{GuidanceMode, Spacecraft, STD_GRAVITY};
 use crate::dynamics::DynamicsError;
 use crate::errors::NyxError;
-use crate::io::dynamics::DynamicsSerde;
-use crate::io::gravity::HarmonicsMem;
+use crate::io::dynamics::{DensitySerde, DynamicsSerde};
+use crate::io::gravity::{GfcField, HarmonicsMem};
+use crate::io::space_weather::SpaceWeather;
 
 use crate::io::{ConfigError, Configurable};
 use crate::linalg::{Const, DimName, OMatrix, OVector, Vector3};
 pub use crate::md::prelude::SolarPressure;
-use crate::md::prelude::{Harmonics, PointMasses};
+use crate::md::prelude::{Harmonics, PointMasses, Relativity};
 use crate::State;
 
 use std::fmt::{self, Write};
+use std::path::Path;
 use std::sync::Arc;
 
-use crate::cosmic::{AstroError, Cosm};
+use crate::cosmic::{AstroError, Attitude, Cosm};
 #[cfg(feature = "python")]
 use crate::io::ConfigRepr;
 #[cfg(feature = "python")]
@@ -62,6 +67,8 @@ pub struct SpacecraftDynamics {
     pub orbital_dyn: OrbitalDynamics,
     pub force_models: Vec<Arc<dyn ForceModel>>,
     pub guid_law: Option<Arc<dyn GuidanceLaw>>,
+    /// Electric thruster whose performance depends on the available power, used instead of the thruster of the spacecraft when set
+    pub electric_thruster: Option<Arc<ElectricThruster>>,
     pub decrement_mass: bool,
 }
 
@@ -73,6 +80,7 @@ impl SpacecraftDynamics {
             orbital_dyn,
             guid_law: Some(guid_law),
             force_models: Vec::new(),
+            electric_thruster: None,
             decrement_mass: true,
         }
     }
@@ -87,6 +95,7 @@ impl SpacecraftDynamics {
             orbital_dyn,
             guid_law: Some(guid_law),
             force_models: Vec::new(),
+            electric_thruster: None,
             decrement_mass: false,
         }
     }
@@ -97,6 +106,7 @@ impl SpacecraftDynamics {
             orbital_dyn,
             guid_law: None,
             force_models: Vec::new(),
+            electric_thruster: None,
             decrement_mass: true,
         }
     }
@@ -107,6 +117,7 @@ impl SpacecraftDynamics {
             orbital_dyn,
             guid_law: None,
             force_models: vec![force_model],
+            electric_thruster: None,
             decrement_mass: true,
         }
     }
@@ -133,6 +144,13 @@ impl SpacecraftDynamics {
         me
     }
 
+    /// Clone these dynamics and power the guidance law with the provided electric thruster instead of the thruster of the spacecraft
+    pub fn with_electric_thruster(self, electric_thruster: Arc<ElectricThruster>) -> Self {
+        let mut me = self;
+        me.electric_thruster = Some(electric_thruster);
+        me
+    }
+
     /// A shortcut to spacecraft.guid_law if a guidance law is defined for these dynamics
     pub fn guidance_achieved(&self, state: &Spacecraft) -> Result<bool, NyxError> {
         match &self.guid_law {
@@ -141,12 +159,139 @@ impl SpacecraftDynamics {
         }
     }
 
+    /// Equations of motion of the spacecraft, whose surface model and thrust direction follow the provided attitude if any,
+    /// as propagated by the `AttitudeDynamics`.
+    pub(crate) fn eom_in_attitude(
+        &self,
+        delta_t: f64,
+        state: &OVector<f64, Const<90>>,
+        ctx: &Spacecraft,
+        attitude: Option<&Attitude>,
+    ) -> Result<OVector<f64, Const<90>>, DynamicsError> {
+        // Rebuild the osculating state for the EOM context, oriented by the attitude if any
+        let mut osc_sc = ctx.set_with_delta_seconds(delta_t, state);
+        if let Some(attitude) = attitude {
+            osc_sc = attitude.orient(osc_sc);
+        }
+        let mut d_x = OVector::<f64, Const<90>>::zeros();
+
+        if ctx.orbit.stm.is_some() {
+            // Call the gradient (also called the dual EOM function of the force models)
+            let (state, grad) = self.dual_eom(delta_t, &osc_sc)?;
+
+            // Apply the gradient to the STM
+            let stm_dt = ctx.stm()? * grad;
+
+            // Rebuild the state vectors
+            for (i, val) in state.iter().enumerate() {
+                d_x[i] = *val;
+            }
+
+            for (i, val) in stm_dt.iter().enumerate() {
+                d_x[i + <Spacecraft as State>::Size::dim()] = *val;
+            }
+        } else {
+            // Compute the orbital dynamics
+            let orbital_dyn_vec = state.fixed_rows::<42>(0).into_owned();
+            // Copy the d orbit dt data
+            for (i, val) in self
+                .orbital_dyn
+                .eom(delta_t, &orbital_dyn_vec, &ctx.orbit)?
+                .iter()
+                .enumerate()
+            {
+                d_x[i] = *val;
+            }
+
+            // Apply the force models for non STM propagation
+            for model in &self.force_models {
+                let model_frc = model.eom(&osc_sc)? / osc_sc.mass_kg();
+                for i in 0..3 {
+                    d_x[i + 3] += model_frc[i];
+                }
+            }
+        }
+
+        // Now include the control as needed.
+        if let Some(guid_law) = &self.guid_law {
+            let (thrust_force, fuel_rate) = {
+                let thrust_throttle_lvl = guid_law.throttle(&osc_sc);
+                // A thruster is only needed to thrust, e.g. the sail steering laws never do
+                let thruster = if thrust_throttle_lvl > 0.0 {
+                    match &self.electric_thruster {
+                        // The performance of the electric thruster depends on the power available at the osculating state
+                        Some(ep) => ep.thruster(&osc_sc.orbit)?,
+                        None => Some(osc_sc.thruster.ok_or(DynamicsError::DynamicsGuidance {
+                            source: GuidanceErrors::NoThrustersDefined,
+                        })?),
+                    }
+                } else {
+                    None
+                };
+                if !(0.0..=1.0).contains(&thrust_throttle_lvl) {
+                    return Err(DynamicsError::DynamicsGuidance {
+                        source: GuidanceErrors::ThrottleRatio {
+                            ratio: thrust_throttle_lvl,
+                        },
+                    });
+                } else if let Some(thruster) = thruster {
+                    // Thrust arc
+                    let thrust_inertial = match attitude {
+                        Some(attitude) => guid_law.direction_in_attitude(&osc_sc, attitude),
+                        None => guid_law.direction(&osc_sc),
+                    };
+                    if (thrust_inertial.norm() - 1.0).abs() > NORM_ERR {
+                        let (alpha, delta) = ra_dec_from_unit_vector(thrust_inertial);
+                        return Err(DynamicsError::DynamicsGuidance {
+                            source: GuidanceErrors::InvalidDirection {
+                                x: thrust_inertial[0],
+                                y: thrust_inertial[1],
+                                z: thrust_inertial[2],
+                                in_plane_deg: alpha.to_degrees(),
+                                out_of_plane_deg: delta.to_degrees(),
+                            },
+                        });
+                    } else if thrust_inertial.norm().is_normal() {
+                        // Compute the thrust in Newtons and Isp
+                        let total_thrust = (thrust_throttle_lvl * thruster.thrust_N) * 1e-3; // Convert m/s^-2 to km/s^-2
+                        (
+                            thrust_inertial * total_thrust,
+                            if self.decrement_mass {
+                                let fuel_usage = thrust_throttle_lvl * thruster.thrust_N
+                                    / (thruster.isp_s * STD_GRAVITY);
+                                -fuel_usage
+                            } else {
+                                0.0
+                            },
+                        )
+                    } else {
+                        warn!(
+                            "Abnormal thrust direction vector\t|u| = {}",
+                            thrust_inertial.norm()
+                        );
+                        (Vector3::zeros(), 0.0)
+                    }
+                } else {
+                    (Vector3::zeros(), 0.0)
+                }
+            };
+
+            for i in 0..3 {
+                d_x[i + 3] += thrust_force[i] / osc_sc.mass_kg();
+            }
+            d_x[8] += fuel_rate;
+        }
+
+        Ok(d_x)
+    }
+
     /// Clone these spacecraft dynamics and update the control to the one provided.
     pub fn with_guidance_law(&self, guid_law: Arc<dyn GuidanceLaw>) -> Self {
         Self {
             orbital_dyn: self.orbital_dyn.clone(),
             guid_law: Some(guid_law),
             force_models: self.force_models.clone(),
+            electric_thruster: self.electric_thruster.clone(),
             decrement_mass: self.decrement_mass,
         }
     }
@@ -157,6 +302,7 @@ impl SpacecraftDynamics {
             orbital_dyn: self.orbital_dyn.clone(),
             guid_law: Some(guid_law),
             force_models: self.force_models.clone(),
+            electric_thruster: self.electric_thruster.clone(),
             decrement_mass: false,
         }
     }
@@ -167,6 +313,7 @@ impl SpacecraftDynamics {
             orbital_dyn: self.orbital_dyn.clone(),
             guid_law: None,
             force_models: self.force_models.clone(),
+            electric_thruster: self.electric_thruster.clone(),
             decrement_mass: self.decrement_mass,
         }
     }
@@ -255,8 +402,12 @@ impl fmt::Display for SpacecraftDynamics {
         };
         write!(
             f,
-            "Spacecraft dynamics (with guidance = {}): {} {}",
+            "Spacecraft dynamics (with guidance = {}): {}{} {}",
             self.guid_law.is_some(),
+            match &self.electric_thruster {
+                Some(ep) => format!("{ep}; "),
+                None => String::new(),
+            },
             force_models,
             self.orbital_dyn
         )
@@ -279,6 +430,18 @@ impl Dynamics for SpacecraftDynamics {
             let mut state = next_state;
             // Update the control mode
             guid_law.next(&mut state);
+            // Record the throttle level and, for electric propulsion, the performance available at this state
+            let thruster = match &self.electric_thruster {
+                Some(ep) => ep.thruster(&state.orbit)?,
+                None => state.thruster,
+            };
+            state.throttle = match thruster {
+                Some(thruster) => {
+                    state.thruster = Some(thruster);
+                    guid_law.throttle(&state)
+                }
+                None => 0.0,
+            };
             Ok(state)
         } else {
             Ok(next_state)
@@ -291,108 +454,7 @@ impl Dynamics for SpacecraftDynamics {
         state: &OVector<f64, Const<90>>,
         ctx: &Self::StateType,
     ) -> Result<OVector<f64, Const<90>>, DynamicsError> {
-        // Rebuild the osculating state for the EOM context.
-        let osc_sc = ctx.set_with_delta_seconds(delta_t, state);
-        let mut d_x = OVector::<f64, Const<90>>::zeros();
-
-        if ctx.orbit.stm.is_some() {
-            // Call the gradient (also called the dual EOM function of the force models)
-            let (state, grad) = self.dual_eom(delta_t, &osc_sc)?;
-
-            // Apply the gradient to the STM
-            let stm_dt = ctx.stm()? * grad;
-
-            // Rebuild the state vectors
-            for (i, val) in state.iter().enumerate() {
-                d_x[i] = *val;
-            }
-
-            for (i, val) in stm_dt.iter().enumerate() {
-                d_x[i + <Spacecraft as State>::Size::dim()] = *val;
-            }
-        } else {
-            // Compute the orbital dynamics
-            let orbital_dyn_vec = state.fixed_rows::<42>(0).into_owned();
-            // Copy the d orbit dt data
-            for (i, val) in self
-                .orbital_dyn
-                .eom(delta_t, &orbital_dyn_vec, &ctx.orbit)?
-                .iter()
-                .enumerate()
-            {
-                d_x[i] = *val;
-            }
-
-            // Apply the force models for non STM propagation
-            for model in &self.force_models {
-                let model_frc = model.eom(&osc_sc)? / osc_sc.mass_kg();
-                for i in 0..3 {
-                    d_x[i + 3] += model_frc[i];
-                }
-            }
-        }
-
-        // Now include the control as needed.
-        if let Some(guid_law) = &self.guid_law {
-            let (thrust_force, fuel_rate) = {
-                if osc_sc.thruster.is_none() {
-                    return Err(DynamicsError::DynamicsGuidance {
-                        source: GuidanceErrors::NoThrustersDefined,
-                    });
-                }
-                let thruster = osc_sc.thruster.unwrap();
-                let thrust_throttle_lvl = guid_law.throttle(&osc_sc);
-                if !(0.0..=1.0).contains(&thrust_throttle_lvl) {
-                    return Err(DynamicsError::DynamicsGuidance {
-                        source: GuidanceErrors::ThrottleRatio {
-                            ratio: thrust_throttle_lvl,
-                        },
-                    });
-                } else if thrust_throttle_lvl > 0.0 {
-                    // Thrust arc
-                    let thrust_inertial = guid_law.direction(&osc_sc);
-                    if (thrust_inertial.norm() - 1.0).abs() > NORM_ERR {
-                        let (alpha, delta) = ra_dec_from_unit_vector(thrust_inertial);
-                        return Err(DynamicsError::DynamicsGuidance {
-                            source: GuidanceErrors::InvalidDirection {
-                                x: thrust_inertial[0],
-                                y: thrust_inertial[1],
-                                z: thrust_inertial[2],
-                                in_plane_deg: alpha.to_degrees(),
-                                out_of_plane_deg: delta.to_degrees(),
-                            },
-                        });
-                    } else if thrust_inertial.norm().is_normal() {
-                        // Compute the thrust in Newtons and Isp
-                        let total_thrust = (thrust_throttle_lvl * thruster.thrust_N) * 1e-3; // Convert m/s^-2 to km/s^-2
-                        (
-                            thrust_inertial * total_thrust,
-                            if self.decrement_mass {
-                                let fuel_usage = thrust_throttle_lvl * thruster.thrust_N
-                                    / (thruster.isp_s * STD_GRAVITY);
-                                -fuel_usage
-                            } else {
-                                0.0
-                            },
-                        )
-                    } else {
-                        warn!(
-                            "Abnormal thrust direction vector\t|u| = {}",
-                            thrust_inertial.norm()
-                        );
-                        (Vector3::zeros(), 0.0)
-                    }
-                } else {
-                    (Vector3::zeros(), 0.0)
-                }
-            };
-
-            for i in 0..3 {
-                d_x[i + 3] += thrust_force[i] / osc_sc.mass_kg();
-            }
-            d_x[8] += fuel_rate;
-        }
-        Ok(d_x)
+        self.eom_in_attitude(delta_t, state, ctx, None)
     }
 
     fn dual_eom(
@@ -431,9 +493,9 @@ impl Dynamics for SpacecraftDynamics {
             for i in 0..3 {
                 // Add the velocity changes
                 d_x[i + 3] += model_frc[i] / total_mass;
-                // Add the velocity partials
-                for j in 1..4 {
-                    grad[(i + 3, j - 1)] += model_grad[(i, j - 1)] / total_mass;
+                // Add the position and velocity partials
+                for j in 0..6 {
+                    grad[(i + 3, j)] += model_grad[(i, j)] / total_mass;
                 }
             }
         }
@@ -457,6 +519,34 @@ impl Configurable for SpacecraftDynamics {
         if let Some(harmonics_serde) = cfg.harmonics {
             for hh in harmonics_serde {
                 let gunzipped = hh.coeffs.ends_with(".gz");
+
+                // Grab the frame
+                let frame = cosm
+                    .try_frame(&hh.frame)
+                    .map_err(|e| ConfigError::InvalidConfig { msg: e.to_string() })?;
+
+                // Lunar fields are expressed in the principal axes frame
+                if is_lunar_non_pa(&frame) {
+                    return Err(ConfigError::InvalidConfig {
+                        msg: format!(
+                            "lunar harmonics must be computed in the Moon PA frame, got {frame}"
+                        ),
+                    });
+                }
+
+                // ICGEM fields are identified by their extension, before the compression one if any
+                let is_gfc = Path::new(hh.coeffs.strip_suffix(".gz").unwrap_or(&hh.coeffs))
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("gfc"));
+                if is_gfc {
+                    // ICGEM fields are scaled to the frame and include their time-variable terms
+                    let harmonics = GfcField::load(&hh.coeffs, hh.degree, hh.order, gunzipped)
+                        .and_then(|field| Harmonics::from_gfc(frame, field, cosm.clone()))
+                        .map_err(|e| ConfigError::InvalidConfig { msg: e.to_string() })?;
+                    accel_models.push(harmonics);
+                    continue;
+                }
+
                 let stor = if hh.coeffs.contains("cof") {
                     HarmonicsMem::from_cof(&hh.coeffs, hh.degree, hh.order, gunzipped)
                         .map_err(|e| ConfigError::InvalidConfig { msg: e.to_string() })?
@@ -472,15 +562,24 @@ impl Configurable for SpacecraftDynamics {
                     });
                 };
 
-                // Grab the frame
-                let frame = cosm
-                    .try_frame(&hh.frame)
-                    .map_err(|e| ConfigError::InvalidConfig { msg: e.to_string() })?;
-
                 accel_models.push(Harmonics::from_stor(frame, stor, cosm.clone()));
             }
         }
 
+        if let Some(relativity) = cfg.relativity {
+            let body_frame = cosm
+                .try_frame(&relativity.frame)
+                .map_err(|e| ConfigError::InvalidConfig { msg: e.to_string() })?;
+
+            accel_models.push(Arc::new(Relativity {
+                schwarzschild: relativity.schwarzschild,
+                body_frame,
+                angular_momentum_km2_s: relativity.angular_momentum_km2_s,
+                de_sitter: relativity.de_sitter,
+                cosm: cosm.clone(),
+            }));
+        }
+
         let orbital_dyn = OrbitalDynamics::new(accel_models);
 
         let mut force_models: Vec<Arc<dyn ForceModel>> = Vec::new();
@@ -490,11 +589,77 @@ impl Configurable for SpacecraftDynamics {
             force_models.push(SolarPressure::with_flux(
                 srp.phi.map_or(1367.0, |v| v),
                 srp.shadows,
-                cosm,
+                cosm.clone(),
             ));
         }
 
-        // TODO: Drag -- https://github.com/nyx-space/nyx/issues/86
+        // Albedo and infrared radiation of the central body
+        if let Some(albedo) = cfg.albedo {
+            let body_frame = cosm
+                .try_frame(albedo.frame.as_deref().unwrap_or("IAU Earth"))
+                .map_err(|e| ConfigError::InvalidConfig { msg: e.to_string() })?;
+
+            force_models.push(Arc::new(PlanetaryRadiation {
+                coefficients: albedo.coefficients.unwrap_or(RadiationCoefficients::Knocke),
+                body_frame,
+                phi: albedo.phi.unwrap_or(1367.0),
+                rings: albedo.rings.unwrap_or(5),
+                scale_factor: albedo.scale_factor.unwrap_or(1.0),
+                cosm: cosm.clone(),
+            }));
+        }
+
+        // Drag
+        if let Some(drag) = cfg.drag {
+            let mut space_weather = None;
+            let density = match drag.density {
+                DensitySerde::Constant { rho } => AtmDensity::Constant(rho),
+                DensitySerde::Exponential {
+                    rho0,
+                    r0,
+                    ref_alt_m,
+                } => AtmDensity::Exponential {
+                    rho0,
+                    r0,
+                    ref_alt_m,
+                },
+                DensitySerde::StdAtm { max_alt_m } => AtmDensity::StdAtm {
+                    max_alt_m: max_alt_m.unwrap_or(1_000_000.0),
+                },
+                DensitySerde::JacchiaRoberts {
+                    space_weather: space_weather_path,
+                    fallback,
+                } => {
+                    let indices = match space_weather_path {
+                        Some(path) => {
+                            let sw = SpaceWeather::from_celestrak_csv(&path)
+                                .map_err(|e| ConfigError::InvalidConfig { msg: e.to_string() })?;
+                            match fallback {
+                                Some(indices) => sw.with_fallback(indices),
+                                None => sw,
+                            }
+                        }
+                        // Constant indices for design studies
+                        None => SpaceWeather::constant(fallback.unwrap_or_default()),
+                    };
+                    space_weather = Some(Arc::new(indices));
+                    AtmDensity::JacchiaRoberts
+                }
+            };
+
+            let drag_frame = cosm
+                .try_frame(drag.frame.as_deref().unwrap_or("IAU Earth"))
+                .map_err(|e| ConfigError::InvalidConfig { msg: e.to_string() })?;
+
+            force_models.push(Arc::new(Drag {
+                density,
+                drag_frame,
+                wind: None,
+                space_weather,
+                surface: None,
+                cosm,
+            }));
+        }
 
         Ok(SpacecraftDynamics::from_models(orbital_dyn, force_models))
     }
@@ -16,10 +16,13 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::cosmic::{Cosm, Frame, Orbit};
-use crate::dynamics::AccelModel;
-use crate::io::gravity::HarmonicsMem;
-use crate::linalg::{DMatrix, Matrix3, Vector3, U7};
+use crate::cosmic::{Bodies, Cosm, Frame, FrameOrientation, Orbit};
+use crate::dynamics::{AccelModel, TideModel};
+use crate::errors::NyxError;
+use crate::io::gravity::{GfcField, HarmonicsMem};
+use crate::linalg::{DMatrix, Matrix3, Matrix3x6, Vector3, U7};
+use crate::log::warn;
+use crate::time::Epoch;
 use hyperdual::linalg::norm;
 use hyperdual::{hyperspace_from_vector, Float, OHyperdual};
 use std::cmp::min;
@@ -33,6 +36,10 @@ pub struct Harmonics {
     cosm: Arc<Cosm>,
     compute_frame: Frame,
     stor: HarmonicsMem,
+    tides: Vec<Arc<dyn TideModel>>,
+    /// Degree (exclusive) and order of the field, accounting for the degree and order of the tidal corrections
+    max_degree: usize,
+    max_order: usize,
     a_nm: DMatrix<f64>,
     b_nm: DMatrix<f64>,
     c_nm: DMatrix<f64>,
@@ -45,14 +52,87 @@ pub struct Harmonics {
     vr11_h: DMatrix<OHyperdual<f64, U7>>,
 }
 
+/// Returns whether this frame is centered on the Moon without being the lunar principal axes (PA) frame, in which the lunar gravity fields are expressed
+pub(crate) fn is_lunar_non_pa(frame: &Frame) -> bool {
+    matches!(Bodies::try_from(frame.ephem_path()), Ok(Bodies::Luna))
+        && frame.orientation() != FrameOrientation::MoonPa
+}
+
 impl Harmonics {
     /// Create a new Harmonics dynamical model from the provided gravity potential storage instance.
     pub fn from_stor(compute_frame: Frame, stor: HarmonicsMem, cosm: Arc<Cosm>) -> Arc<Self> {
+        Self::with_tides(compute_frame, stor, vec![], cosm)
+    }
+
+    /// Create a new Harmonics dynamical model from the provided ICGEM gravity field, expressed in the provided frame of its body.
+    ///
+    /// The coefficients are scaled from the GM and the reference radius of the field to those of the frame, and the time-variable terms
+    /// of the field are applied at each evaluation of the harmonics.
+    /// Returns an error if the GM of the frame differs from that of the field by more than 0.1%, i.e. if the frame is not that of the body of the field.
+    pub fn from_gfc(
+        compute_frame: Frame,
+        field: GfcField,
+        cosm: Arc<Cosm>,
+    ) -> Result<Arc<Self>, NyxError> {
+        if !compute_frame.is_geoid() {
+            return Err(NyxError::CustomError {
+                msg: format!("harmonics only work around geoids, got {compute_frame}"),
+            });
+        }
+        if (compute_frame.gm() - field.gm_km3_s2).abs() > 1e-3 * compute_frame.gm() {
+            return Err(NyxError::CustomError {
+                msg: format!(
+                    "{} has a GM of {} km^3/s^2 but {compute_frame} has a GM of {} km^3/s^2",
+                    field.model_name,
+                    field.gm_km3_s2,
+                    compute_frame.gm()
+                ),
+            });
+        }
+
+        let gm_ratio = field.gm_km3_s2 / compute_frame.gm();
+        let radius_ratio = field.radius_km / compute_frame.equatorial_radius();
+        let scale = |degree: usize| gm_ratio * radius_ratio.powi(degree as i32);
+
+        let mut stor = field.stor;
+        stor.scale_by_degree(scale);
+        let mut time_variable = field.time_variable;
+        for term in &mut time_variable.terms {
+            term.c_nm *= scale(term.degree);
+            term.s_nm *= scale(term.degree);
+        }
+
+        let tides: Vec<Arc<dyn TideModel>> = if time_variable.terms.is_empty() {
+            Vec::new()
+        } else {
+            vec![Arc::new(time_variable)]
+        };
+        Ok(Self::with_tides(compute_frame, stor, tides, cosm))
+    }
+
+    /// Create a new Harmonics dynamical model from the provided gravity potential storage instance, whose coefficients are corrected by the provided tide models
+    /// every time the acceleration is evaluated.
+    pub fn with_tides(
+        compute_frame: Frame,
+        stor: HarmonicsMem,
+        tides: Vec<Arc<dyn TideModel>>,
+        cosm: Arc<Cosm>,
+    ) -> Arc<Self> {
         assert!(
             compute_frame.is_geoid(),
             "harmonics only work around geoids"
         );
-        let degree_np2 = stor.max_degree_n() + 2;
+        if is_lunar_non_pa(&compute_frame) {
+            warn!("lunar harmonics are expressed in the Moon PA frame but will be computed in {compute_frame}");
+        }
+        let tides_degree = tides.iter().map(|tide| tide.max_degree()).max();
+        let max_degree = tides_degree.map_or(stor.max_degree_n(), |degree| {
+            stor.max_degree_n().max(degree + 1)
+        });
+        let max_order =
+            tides_degree.map_or(stor.max_order_m(), |degree| stor.max_order_m().max(degree));
+
+        let degree_np2 = max_degree + 2;
         let mut a_nm = DMatrix::from_element(degree_np2 + 1, degree_np2 + 1, 0.0);
         let mut b_nm = DMatrix::from_element(degree_np2, degree_np2, 0.0);
         let mut c_nm = DMatrix::from_element(degree_np2, degree_np2, 0.0);
@@ -122,6 +202,9 @@ impl Harmonics {
             cosm,
             compute_frame,
             stor,
+            tides,
+            max_degree,
+            max_order,
             a_nm,
             b_nm,
             c_nm,
@@ -136,6 +219,46 @@ impl Harmonics {
     }
 }
 
+impl Harmonics {
+    /// Returns the tidal corrections to the C_nm and S_nm at the provided epoch, if there are any tide models
+    fn tidal_corrections(
+        &self,
+        epoch: Epoch,
+    ) -> Result<Option<(DMatrix<f64>, DMatrix<f64>)>, DynamicsError> {
+        if self.tides.is_empty() {
+            return Ok(None);
+        }
+        let mut delta_c = DMatrix::from_element(self.max_degree + 1, self.max_degree + 1, 0.0);
+        let mut delta_s = delta_c.clone();
+        for tide in &self.tides {
+            tide.add_corrections(epoch, self.compute_frame, &mut delta_c, &mut delta_s)?;
+        }
+        Ok(Some((delta_c, delta_s)))
+    }
+
+    /// Returns the C_nm and S_nm of the provided degree and order, including the tidal corrections if any
+    fn cs_nm(
+        &self,
+        degree: usize,
+        order: usize,
+        corrections: &Option<(DMatrix<f64>, DMatrix<f64>)>,
+    ) -> (f64, f64) {
+        let (c_nm, s_nm) = if degree < self.stor.max_degree_n() && order <= self.stor.max_order_m()
+        {
+            self.stor.cs_nm(degree, order)
+        } else {
+            (0.0, 0.0)
+        };
+        match corrections {
+            Some((delta_c, delta_s)) => (
+                c_nm + delta_c[(degree, order)],
+                s_nm + delta_s[(degree, order)],
+            ),
+            None => (c_nm, s_nm),
+        }
+    }
+}
+
 impl fmt::Display for Harmonics {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -144,7 +267,11 @@ impl fmt::Display for Harmonics {
             self.compute_frame,
             self.stor.max_order_m(),
             self.stor.max_degree_n(),
-        )
+        )?;
+        for tide in &self.tides {
+            write!(f, " with {tide}")?;
+        }
+        Ok(())
     }
 }
 
@@ -158,8 +285,9 @@ impl AccelModel for Harmonics {
         let s_ = state.x_km / r_;
         let t_ = state.y_km / r_;
         let u_ = state.z_km / r_;
-        let max_degree = self.stor.max_degree_n(); // In GMAT, the degree is NN
-        let max_order = self.stor.max_order_m(); // In GMAT, the order is MM
+        let max_degree = self.max_degree; // In GMAT, the degree is NN
+        let max_order = self.max_order; // In GMAT, the order is MM
+        let corrections = self.tidal_corrections(osc.epoch)?;
 
         // Create the associated Legendre polynomials. Note that we add three items as per GMAT (this may be useful for the STM)
         let mut a_nm = self.a_nm.clone();
@@ -207,7 +335,7 @@ impl AccelModel for Harmonics {
             rho_np1 *= rho;
 
             for m in 0..=min(n, max_order) {
-                let (c_val, s_val) = self.stor.cs_nm(n, m);
+                let (c_val, s_val) = self.cs_nm(n, m, &corrections);
                 let d_ = (c_val * r_m[m] + s_val * i_m[m]) * 2.0.sqrt();
                 let e_ = if m == 0 {
                     0.0
@@ -242,7 +370,7 @@ impl AccelModel for Harmonics {
         Ok(dcm * accel)
     }
 
-    fn dual_eom(&self, osc: &Orbit) -> Result<(Vector3<f64>, Matrix3<f64>), DynamicsError> {
+    fn dual_eom(&self, osc: &Orbit) -> Result<(Vector3<f64>, Matrix3x6<f64>), DynamicsError> {
         // Convert the osculating orbit to the correct frame (needed for multiple harmonic fields)
         let state = self.cosm.frame_chg(osc, self.compute_frame);
 
@@ -253,8 +381,9 @@ impl AccelModel for Harmonics {
         let s_ = radius[0] / r_;
         let t_ = radius[1] / r_;
         let u_ = radius[2] / r_;
-        let max_degree = self.stor.max_degree_n(); // In GMAT, the order is NN
-        let max_order = self.stor.max_order_m(); // In GMAT, the order is MM
+        let max_degree = self.max_degree; // In GMAT, the order is NN
+        let max_order = self.max_order; // In GMAT, the order is MM
+        let corrections = self.tidal_corrections(osc.epoch)?;
 
         // Create the associated Legendre polynomials. Note that we add three items as per GMAT (this may be useful for the STM)
         let mut a_nm = self.a_nm_h.clone();
@@ -305,7 +434,7 @@ impl AccelModel for Harmonics {
             rho_np1 *= rho;
 
             for m in 0..=min(n, max_order) {
-                let (c_valf64, s_valf64) = self.stor.cs_nm(n, m);
+                let (c_valf64, s_valf64) = self.cs_nm(n, m, &corrections);
                 let c_val = OHyperdual::<f64, U7>::from(c_valf64);
                 let s_val = OHyperdual::<f64, U7>::from(s_valf64);
 
@@ -357,10 +486,10 @@ impl AccelModel for Harmonics {
         let accel = dcm_d * Vector3::new(a0 + a3 * s_, a1 + a3 * t_, a2 + a3 * u_);
         // Extract data
         let mut dx = Vector3::zeros();
-        let mut grad = Matrix3::zeros();
+        let mut grad = Matrix3x6::zeros();
         for i in 0..3 {
             dx[i] += accel[i].real();
-            // NOTE: Although the hyperdual state is of size 7, we're only setting the values up to 3 (Matrix3)
+            // NOTE: The gravity field does not depend on the velocity, so only the position partials are set
             for j in 1..4 {
                 grad[(i, j - 1)] += accel[i][j];
             }
@@ -0,0 +1,844 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::DynamicsError;
+use crate::cosmic::nutation::{delaunay_arguments, earth_rotation_angle, ARCSEC_TO_RAD};
+use crate::cosmic::{Cosm, Frame, LightTimeCalc};
+use crate::linalg::DMatrix;
+use crate::time::Epoch;
+use crate::NyxError;
+use std::f64::consts::PI;
+use std::fmt;
+use std::fs;
+use std::sync::Arc;
+
+/// A model of the time-variable corrections to the fully normalized Stokes coefficients of a gravity field, applied by `Harmonics`.
+pub trait TideModel: fmt::Display + Send + Sync {
+    /// Maximum degree (and order) of the corrections
+    fn max_degree(&self) -> usize;
+
+    /// Adds the corrections to the C_nm and S_nm of the gravity field of the provided body fixed frame at the provided epoch.
+    /// The matrices are indexed by (degree, order) and are at least of size `max_degree() + 1`.
+    fn add_corrections(
+        &self,
+        epoch: Epoch,
+        body_frame: Frame,
+        delta_c: &mut DMatrix<f64>,
+        delta_s: &mut DMatrix<f64>,
+    ) -> Result<(), DynamicsError>;
+}
+
+/// Doodson arguments τ, s, h, p, N' and p_s in radians (IERS Conventions 2010, section 6.2), assuming UT1 = UTC.
+///
+/// The UT1-UTC offset shifts the arguments by less than 0.01 degrees, which is negligible for the tidal corrections.
+pub fn doodson_arguments(epoch: Epoch) -> [f64; 6] {
+    let t = epoch.to_tt_centuries_j2k();
+    let [l, l_p, f, d, omega] = delaunay_arguments(t);
+    let s = f + omega;
+    // Greenwich mean sidereal time, with the largest terms of the IAU 2006 polynomial
+    let gmst = earth_rotation_angle(epoch, 0.0)
+        + (0.014_506 + t * (4_612.156_534 + t * 1.391_581_7)) * ARCSEC_TO_RAD;
+    [gmst + PI - s, s, s - d, s - l, -omega, s - d - l_p]
+}
+
+/// Returns the multipliers of the Doodson arguments of the provided Doodson number, e.g. `165.555` for K1
+fn doodson_multipliers(doodson: &str) -> Option<[f64; 6]> {
+    let (integer, fraction) = doodson.split_once('.')?;
+    let digits = format!("{integer:0>3}{fraction}");
+    if digits.len() != 6 {
+        return None;
+    }
+    let mut multipliers = [0.0; 6];
+    for (i, digit) in digits.chars().enumerate() {
+        let digit = digit.to_digit(10)? as f64;
+        multipliers[i] = if i == 0 { digit } else { digit - 5.0 };
+    }
+    Some(multipliers)
+}
+
+/// Returns the fully normalized associated Legendre functions up to the provided degree, indexed by (degree, order)
+fn normalized_legendre(max_degree: usize, sin_lat: f64) -> DMatrix<f64> {
+    let cos_lat = (1.0 - sin_lat.powi(2)).max(0.0).sqrt();
+    let mut p_nm = DMatrix::from_element(max_degree + 1, max_degree + 1, 0.0);
+    p_nm[(0, 0)] = 1.0;
+    for m in 0..=max_degree {
+        let mf64 = m as f64;
+        if m > 0 {
+            let factor = if m == 1 {
+                3.0f64.sqrt()
+            } else {
+                ((2.0 * mf64 + 1.0) / (2.0 * mf64)).sqrt()
+            };
+            p_nm[(m, m)] = factor * cos_lat * p_nm[(m - 1, m - 1)];
+        }
+        if m < max_degree {
+            p_nm[(m + 1, m)] = (2.0 * mf64 + 3.0).sqrt() * sin_lat * p_nm[(m, m)];
+        }
+        for n in (m + 2)..=max_degree {
+            let nf64 = n as f64;
+            let a_nm =
+                ((2.0 * nf64 - 1.0) * (2.0 * nf64 + 1.0) / ((nf64 - mf64) * (nf64 + mf64))).sqrt();
+            let b_nm = ((2.0 * nf64 + 1.0) * (nf64 + mf64 - 1.0) * (nf64 - mf64 - 1.0)
+                / ((nf64 - mf64) * (nf64 + mf64) * (2.0 * nf64 - 3.0)))
+                .sqrt();
+            p_nm[(n, m)] = a_nm * sin_lat * p_nm[(n - 1, m)] - b_nm * p_nm[(n - 2, m)];
+        }
+    }
+    p_nm
+}
+
+/// A tidal constituent of the frequency dependent corrections, identified by its Doodson number
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Constituent {
+    multipliers: [f64; 6],
+    degree: usize,
+    order: usize,
+    /// In-phase and out-of-phase amplitudes of the solid tides, or C+, S+, C- and S- of the ocean tides
+    coefficients: [f64; 4],
+}
+
+impl Constituent {
+    fn argument(&self, doodson_args: &[f64; 6]) -> f64 {
+        (0..6).map(|i| self.multipliers[i] * doodson_args[i]).sum()
+    }
+}
+
+/// Love number of a degree and order of the tidal response of a body
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LoveNumber {
+    pub degree: usize,
+    pub order: usize,
+    pub real: f64,
+    /// Imaginary part, which models the anelasticity of the mantle
+    pub imag: f64,
+}
+
+impl LoveNumber {
+    pub fn new(degree: usize, order: usize, real: f64, imag: f64) -> Self {
+        Self {
+            degree,
+            order,
+            real,
+            imag,
+        }
+    }
+}
+
+/// Solid body tides raised by the provided perturbing bodies, as per the IERS Conventions 2010, section 6.2.
+///
+/// The frequency independent corrections of degree 2 and 3 (step 1) are computed from the positions of the perturbing bodies, and the degree 2 also
+/// deforms the degree 4. The frequency dependent corrections (step 2) are computed from the tables 6.5a, 6.5b and 6.5c of the IERS Conventions,
+/// which must be loaded with `load_frequency_corrections`.
+#[derive(Clone)]
+pub struct SolidTides {
+    /// Perturbing bodies raising the tides
+    pub perturbers: Vec<Frame>,
+    /// Love numbers k_nm of degree 2 and 3
+    pub love_numbers: Vec<LoveNumber>,
+    /// Love numbers k+_2m of the degree 4 corrections from the degree 2 tides, for orders 0, 1 and 2
+    pub love_numbers_plus: [f64; 3],
+    /// Set to true if the gravity field is a "zero tide" field, i.e. it includes the permanent tidal deformation of the Earth, which must hence be removed from the corrections
+    pub zero_tide: bool,
+    constituents: Vec<Constituent>,
+    cosm: Arc<Cosm>,
+}
+
+impl SolidTides {
+    /// Solid Earth tides raised by the Moon and the Sun, with the anelastic Love numbers of the IERS Conventions 2010 (table 6.3).
+    #[allow(clippy::approx_constant)] // k22 happens to be close to log10(2)
+    pub fn earth_raw(cosm: Arc<Cosm>) -> Self {
+        Self {
+            perturbers: vec![cosm.frame("Luna"), cosm.frame("Sun J2000")],
+            love_numbers: vec![
+                LoveNumber::new(2, 0, 0.301_90, 0.0),
+                LoveNumber::new(2, 1, 0.298_30, -0.001_44),
+                LoveNumber::new(2, 2, 0.301_02, -0.001_30),
+                LoveNumber::new(3, 0, 0.093, 0.0),
+                LoveNumber::new(3, 1, 0.093, 0.0),
+                LoveNumber::new(3, 2, 0.093, 0.0),
+                LoveNumber::new(3, 3, 0.094, 0.0),
+            ],
+            love_numbers_plus: [-0.000_89, -0.000_80, -0.000_57],
+            zero_tide: false,
+            constituents: Vec::new(),
+            cosm,
+        }
+    }
+
+    /// Solid Earth tides raised by the Moon and the Sun, with the anelastic Love numbers of the IERS Conventions 2010 (table 6.3).
+    pub fn earth(cosm: Arc<Cosm>) -> Arc<Self> {
+        Arc::new(Self::earth_raw(cosm))
+    }
+
+    /// Solid Earth tides with the frequency dependent corrections of the provided IERS Conventions 2010 tables 6.5a, 6.5b and 6.5c
+    pub fn earth_with_frequency_corrections(
+        tables: &[&str],
+        cosm: Arc<Cosm>,
+    ) -> Result<Arc<Self>, NyxError> {
+        let mut me = Self::earth_raw(cosm);
+        for table in tables {
+            me.load_frequency_corrections(table)?;
+        }
+        Ok(Arc::new(me))
+    }
+
+    /// Lunar solid tides raised by the Earth and the Sun, with the degree 2 Love number of the GRAIL mission (Konopliv et al., 2013)
+    pub fn moon_raw(cosm: Arc<Cosm>) -> Self {
+        Self {
+            perturbers: vec![cosm.frame("EME2000"), cosm.frame("Sun J2000")],
+            love_numbers: (0..=2)
+                .map(|order| LoveNumber::new(2, order, 0.024_05, 0.0))
+                .collect(),
+            love_numbers_plus: [0.0; 3],
+            zero_tide: false,
+            constituents: Vec::new(),
+            cosm,
+        }
+    }
+
+    /// Lunar solid tides raised by the Earth and the Sun, with the degree 2 Love number of the GRAIL mission (Konopliv et al., 2013)
+    pub fn moon(cosm: Arc<Cosm>) -> Arc<Self> {
+        Arc::new(Self::moon_raw(cosm))
+    }
+
+    /// Loads the frequency dependent corrections from a text file of the IERS Conventions 2010 table 6.5a, 6.5b or 6.5c.
+    ///
+    /// Each line must start with the Doodson number of the constituent (optionally preceded by its name), and end with the in-phase and
+    /// out-of-phase amplitudes in units of 1e-12. The order of the corrected coefficients is the first digit of the Doodson number. Other lines are ignored.
+    pub fn load_frequency_corrections(&mut self, path: &str) -> Result<(), NyxError> {
+        let contents = fs::read_to_string(path).map_err(|e| NyxError::FileUnreadable {
+            msg: format!("{path}: {e}"),
+        })?;
+        for line in contents.lines() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let multipliers = match tokens.iter().take(2).find_map(|t| doodson_multipliers(t)) {
+                Some(multipliers) => multipliers,
+                None => continue,
+            };
+            if tokens.len() < 3 {
+                continue;
+            }
+            let amplitude = |token: &str| {
+                token
+                    .parse::<f64>()
+                    .map(|amp| amp * 1e-12)
+                    .map_err(|_| NyxError::FileUnreadable {
+                        msg: format!("{path}: invalid amplitude `{token}` in `{line}`"),
+                    })
+            };
+            let in_phase = amplitude(tokens[tokens.len() - 2])?;
+            let out_of_phase = amplitude(tokens[tokens.len() - 1])?;
+            self.constituents.push(Constituent {
+                multipliers,
+                degree: 2,
+                order: multipliers[0] as usize,
+                coefficients: [in_phase, out_of_phase, 0.0, 0.0],
+            });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SolidTides {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let perturbers: Vec<String> = self.perturbers.iter().map(|p| format!("{p}")).collect();
+        write!(
+            f,
+            "solid tides from {} ({} frequency dependent corrections)",
+            perturbers.join(", "),
+            self.constituents.len()
+        )
+    }
+}
+
+impl TideModel for SolidTides {
+    fn max_degree(&self) -> usize {
+        let love_degree = self
+            .love_numbers
+            .iter()
+            .map(|k| k.degree)
+            .max()
+            .unwrap_or(2);
+        if self.love_numbers_plus.iter().any(|k| *k != 0.0) {
+            love_degree.max(4)
+        } else {
+            love_degree
+        }
+    }
+
+    fn add_corrections(
+        &self,
+        epoch: Epoch,
+        body_frame: Frame,
+        delta_c: &mut DMatrix<f64>,
+        delta_s: &mut DMatrix<f64>,
+    ) -> Result<(), DynamicsError> {
+        let max_degree = self.max_degree();
+        let eq_radius = body_frame.equatorial_radius();
+
+        // Step 1: frequency independent corrections (IERS eq. 6.6 and 6.7)
+        for perturber in &self.perturbers {
+            let r_j = self
+                .cosm
+                .try_celestial_state(
+                    &perturber.ephem_path(),
+                    epoch,
+                    body_frame,
+                    LightTimeCalc::None,
+                )
+                .map_err(|e| DynamicsError::DataUnavailable {
+                    msg: format!("{e}"),
+                })?
+                .radius();
+            let rmag = r_j.norm();
+            let longitude = r_j[1].atan2(r_j[0]);
+            let p_nm = normalized_legendre(max_degree, r_j[2] / rmag);
+            let mass_ratio = perturber.gm() / body_frame.gm();
+
+            for k in &self.love_numbers {
+                let (n, m) = (k.degree, k.order);
+                let factor = mass_ratio * (eq_radius / rmag).powi(n as i32 + 1) * p_nm[(n, m)]
+                    / (2 * n + 1) as f64;
+                let (sin_ml, cos_ml) = (m as f64 * longitude).sin_cos();
+                // (k_re + i k_im) * exp(-i m λ) = ΔC_nm - i ΔS_nm
+                delta_c[(n, m)] += factor * (k.real * cos_ml + k.imag * sin_ml);
+                delta_s[(n, m)] += factor * (k.real * sin_ml - k.imag * cos_ml);
+            }
+
+            for (m, k_plus) in self.love_numbers_plus.iter().enumerate() {
+                if *k_plus != 0.0 {
+                    let factor =
+                        k_plus / 5.0 * mass_ratio * (eq_radius / rmag).powi(3) * p_nm[(2, m)];
+                    let (sin_ml, cos_ml) = (m as f64 * longitude).sin_cos();
+                    delta_c[(4, m)] += factor * cos_ml;
+                    delta_s[(4, m)] += factor * sin_ml;
+                }
+            }
+        }
+
+        // Step 2: frequency dependent corrections (IERS eq. 6.8)
+        if !self.constituents.is_empty() {
+            let doodson_args = doodson_arguments(epoch);
+            for constituent in &self.constituents {
+                let (sin_theta, cos_theta) = constituent.argument(&doodson_args).sin_cos();
+                let [in_phase, out_of_phase, _, _] = constituent.coefficients;
+                match constituent.order {
+                    0 => delta_c[(2, 0)] += in_phase * cos_theta - out_of_phase * sin_theta,
+                    1 => {
+                        delta_c[(2, 1)] += in_phase * sin_theta + out_of_phase * cos_theta;
+                        delta_s[(2, 1)] += in_phase * cos_theta - out_of_phase * sin_theta;
+                    }
+                    _ => {
+                        delta_c[(2, 2)] += in_phase * cos_theta;
+                        delta_s[(2, 2)] -= in_phase * sin_theta;
+                    }
+                }
+            }
+        }
+
+        if self.zero_tide {
+            // Permanent tide of the Earth (IERS eq. 6.13)
+            let k20 = self
+                .love_numbers
+                .iter()
+                .find(|k| k.degree == 2 && k.order == 0)
+                .map_or(0.0, |k| k.real);
+            delta_c[(2, 0)] -= 4.4228e-8 * -0.31460 * k20;
+        }
+        Ok(())
+    }
+}
+
+/// Ocean tides loaded from a file of normalized Stokes coefficients in the format of the IERS Conventions 2010 (section 6.3), e.g. FES2004 or EOT11a.
+#[derive(Clone)]
+pub struct OceanTides {
+    max_degree: usize,
+    constituents: Vec<Constituent>,
+}
+
+impl OceanTides {
+    /// Loads the ocean tide coefficients up to the provided degree.
+    ///
+    /// Each line must start with the Doodson number of the constituent, optionally followed by its Darwin name, then the degree and order,
+    /// and the C+, S+, C- and S- coefficients in units of 1e-11. Other lines are ignored.
+    pub fn from_file(path: &str, max_degree: usize) -> Result<Arc<Self>, NyxError> {
+        let contents = fs::read_to_string(path).map_err(|e| NyxError::FileUnreadable {
+            msg: format!("{path}: {e}"),
+        })?;
+
+        let mut constituents = Vec::new();
+        for (lno, line) in contents.lines().enumerate() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let multipliers = match tokens.first().and_then(|t| doodson_multipliers(t)) {
+                Some(multipliers) => multipliers,
+                None => continue,
+            };
+            let unreadable = || NyxError::FileUnreadable {
+                msg: format!("{path}:{}: invalid ocean tide coefficients", lno + 1),
+            };
+            // Skip the Darwin name, if any
+            let first = if tokens.get(1).is_some_and(|t| t.parse::<usize>().is_ok()) {
+                1
+            } else {
+                2
+            };
+            if tokens.len() < first + 6 {
+                return Err(unreadable());
+            }
+            let degree: usize = tokens[first].parse().map_err(|_| unreadable())?;
+            let order: usize = tokens[first + 1].parse().map_err(|_| unreadable())?;
+            if degree > max_degree {
+                continue;
+            }
+            if order > degree {
+                return Err(unreadable());
+            }
+            let mut coefficients = [0.0; 4];
+            for (i, coeff) in coefficients.iter_mut().enumerate() {
+                *coeff = tokens[first + 2 + i]
+                    .parse::<f64>()
+                    .map_err(|_| unreadable())?
+                    * 1e-11;
+            }
+            constituents.push(Constituent {
+                multipliers,
+                degree,
+                order,
+                coefficients,
+            });
+        }
+
+        if constituents.is_empty() {
+            return Err(NyxError::LoadingError {
+                msg: format!("no ocean tide coefficients up to degree {max_degree} in {path}"),
+            });
+        }
+
+        Ok(Arc::new(Self {
+            max_degree: constituents.iter().map(|c| c.degree).max().unwrap(),
+            constituents,
+        }))
+    }
+}
+
+impl fmt::Display for OceanTides {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ocean tides to degree {} ({} coefficients)",
+            self.max_degree,
+            self.constituents.len()
+        )
+    }
+}
+
+impl TideModel for OceanTides {
+    fn max_degree(&self) -> usize {
+        self.max_degree
+    }
+
+    fn add_corrections(
+        &self,
+        epoch: Epoch,
+        _body_frame: Frame,
+        delta_c: &mut DMatrix<f64>,
+        delta_s: &mut DMatrix<f64>,
+    ) -> Result<(), DynamicsError> {
+        // IERS eq. 6.15
+        let doodson_args = doodson_arguments(epoch);
+        for constituent in &self.constituents {
+            let (sin_theta, cos_theta) = constituent.argument(&doodson_args).sin_cos();
+            let [c_plus, s_plus, c_minus, s_minus] = constituent.coefficients;
+            let (n, m) = (constituent.degree, constituent.order);
+            delta_c[(n, m)] += (c_plus + c_minus) * cos_theta + (s_plus + s_minus) * sin_theta;
+            delta_s[(n, m)] += (s_plus - s_minus) * cos_theta - (c_plus - c_minus) * sin_theta;
+        }
+        Ok(())
+    }
+}
+
+/// Kind of time variation of a coefficient of a time-variable gravity field
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TimeVariation {
+    /// Constant offset, e.g. the piecewise constant coefficients of ICGEM 2.0 fields
+    Offset,
+    /// Linear trend, with coefficients per year
+    Trend,
+    /// Sine of the provided period, with the phase counted from the reference epoch
+    Sine { period_years: f64 },
+    /// Cosine of the provided period, with the phase counted from the reference epoch
+    Cosine { period_years: f64 },
+}
+
+/// A time-variable correction of a C_nm and S_nm, e.g. a `trnd`, `asin` or `acos` term of an ICGEM gravity field
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TimeVariableTerm {
+    pub degree: usize,
+    pub order: usize,
+    pub c_nm: f64,
+    pub s_nm: f64,
+    pub variation: TimeVariation,
+    /// Reference epoch of the trend and of the phase of the periodic terms
+    pub reference: Epoch,
+    /// Interval in which this term applies, start included and end excluded, or None if it always applies
+    pub validity: Option<(Epoch, Epoch)>,
+}
+
+impl TimeVariableTerm {
+    /// Returns the factor by which the C_nm and S_nm of this term are multiplied at the provided epoch
+    pub fn factor(&self, epoch: Epoch) -> f64 {
+        if let Some((start, end)) = self.validity {
+            if epoch < start || epoch >= end {
+                return 0.0;
+            }
+        }
+        let years = (epoch - self.reference).to_seconds() / (365.25 * 86_400.0);
+        match self.variation {
+            TimeVariation::Offset => 1.0,
+            TimeVariation::Trend => years,
+            TimeVariation::Sine { period_years } => (2.0 * PI * years / period_years).sin(),
+            TimeVariation::Cosine { period_years } => (2.0 * PI * years / period_years).cos(),
+        }
+    }
+}
+
+/// Time-variable part of a gravity field, as distributed in the ICGEM format (cf. `GfcField`).
+#[derive(Clone, Debug, Default)]
+pub struct TimeVariableGravity {
+    pub terms: Vec<TimeVariableTerm>,
+}
+
+impl fmt::Display for TimeVariableGravity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "time-variable gravity to degree {} ({} terms)",
+            self.max_degree(),
+            self.terms.len()
+        )
+    }
+}
+
+impl TideModel for TimeVariableGravity {
+    fn max_degree(&self) -> usize {
+        self.terms.iter().map(|term| term.degree).max().unwrap_or(0)
+    }
+
+    fn add_corrections(
+        &self,
+        epoch: Epoch,
+        _body_frame: Frame,
+        delta_c: &mut DMatrix<f64>,
+        delta_s: &mut DMatrix<f64>,
+    ) -> Result<(), DynamicsError> {
+        for term in &self.terms {
+            let factor = term.factor(epoch);
+            if factor != 0.0 {
+                delta_c[(term.degree, term.order)] += factor * term.c_nm;
+                delta_s[(term.degree, term.order)] += factor * term.s_nm;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod ut_tides {
+    use super::*;
+    use crate::cosmic::Orbit;
+    use crate::dynamics::{AccelModel, Harmonics};
+    use crate::io::gravity::HarmonicsMem;
+    use crate::na::Complex;
+    use std::env::temp_dir;
+
+    #[test]
+    fn test_doodson() {
+        assert_eq!(
+            doodson_multipliers("165.555"),
+            Some([1.0, 1.0, 0.0, 0.0, 0.0, 0.0])
+        );
+        assert_eq!(
+            doodson_multipliers("55.565"),
+            Some([0.0, 0.0, 0.0, 0.0, 1.0, 0.0])
+        );
+        assert_eq!(
+            doodson_multipliers("245.655"),
+            Some([2.0, -1.0, 0.0, 1.0, 0.0, 0.0])
+        );
+        assert_eq!(doodson_multipliers("Sa"), None);
+        assert_eq!(doodson_multipliers("1.5"), None);
+
+        // The argument of K1 is the sidereal angle plus 90 degrees, which advances by a full turn every sidereal day
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        let k1 = |epoch: Epoch| {
+            let args = doodson_arguments(epoch);
+            args[0] + args[1]
+        };
+        let sidereal_day = crate::time::Unit::Second * 86_164.090_5;
+        let delta = (k1(epoch + sidereal_day) - k1(epoch)).rem_euclid(2.0 * PI);
+        assert!(delta.min(2.0 * PI - delta) < 1e-6);
+
+        let p_nm = normalized_legendre(4, 0.3);
+        assert!((p_nm[(2, 0)] - 5f64.sqrt() * (3.0 * 0.09 - 1.0) / 2.0).abs() < 1e-15);
+        assert!((p_nm[(2, 2)] - 15f64.sqrt() / 2.0 * 0.91).abs() < 1e-15);
+        assert!(
+            (p_nm[(3, 1)] - (21f64 / 8.0).sqrt() * (1.0f64 - 0.09).sqrt() * (5.0 * 0.09 - 1.0))
+                .abs()
+                < 1e-15
+        );
+    }
+
+    #[test]
+    fn test_solid_tides() {
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let iau_earth = cosm.frame("IAU Earth");
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+
+        // Only the Moon and the elastic degree 2 Love numbers
+        let mut tides = SolidTides::earth_raw(cosm.clone());
+        tides.perturbers.truncate(1);
+        tides.love_numbers = (0..=2).map(|m| LoveNumber::new(2, m, 0.3, 0.0)).collect();
+        tides.love_numbers_plus = [0.0; 3];
+        assert_eq!(tides.max_degree(), 2);
+
+        let mut delta_c = DMatrix::from_element(3, 3, 0.0);
+        let mut delta_s = delta_c.clone();
+        tides
+            .add_corrections(epoch, iau_earth, &mut delta_c, &mut delta_s)
+            .unwrap();
+
+        let r_moon = cosm
+            .celestial_state(
+                &cosm.frame("Luna").ephem_path(),
+                epoch,
+                iau_earth,
+                LightTimeCalc::None,
+            )
+            .radius();
+        let (sin_lat, cos_lat) = (
+            r_moon[2] / r_moon.norm(),
+            (r_moon[0].powi(2) + r_moon[1].powi(2)).sqrt() / r_moon.norm(),
+        );
+        let lon = r_moon[1].atan2(r_moon[0]);
+        let factor = 0.3 / 5.0 * cosm.frame("Luna").gm() / iau_earth.gm()
+            * (iau_earth.equatorial_radius() / r_moon.norm()).powi(3);
+        let expected_c20 = factor * 5f64.sqrt() / 2.0 * (3.0 * sin_lat.powi(2) - 1.0);
+        let expected_c22 = factor * 15f64.sqrt() / 2.0 * cos_lat.powi(2) * (2.0 * lon).cos();
+        let expected_s22 = factor * 15f64.sqrt() / 2.0 * cos_lat.powi(2) * (2.0 * lon).sin();
+        let expected_s21 = factor * 15f64.sqrt() * sin_lat * cos_lat * lon.sin();
+        assert!((delta_c[(2, 0)] - expected_c20).abs() < 1e-20);
+        assert!((delta_c[(2, 2)] - expected_c22).abs() < 1e-20);
+        assert!((delta_s[(2, 2)] - expected_s22).abs() < 1e-20);
+        assert!((delta_s[(2, 1)] - expected_s21).abs() < 1e-20);
+        // The Moon is about 60 Earth radii away, hence a correction of a few 1e-9
+        assert!(delta_c[(2, 0)].abs() > 1e-9 && delta_c[(2, 0)].abs() < 1e-8);
+
+        // The full model also corrects degrees 3 and 4, and the Sun adds about half of the lunar tide
+        let full = SolidTides::earth(cosm.clone());
+        assert_eq!(full.max_degree(), 4);
+        let mut full_c = DMatrix::from_element(5, 5, 0.0);
+        let mut full_s = full_c.clone();
+        full.add_corrections(epoch, iau_earth, &mut full_c, &mut full_s)
+            .unwrap();
+        assert!(full_c[(3, 0)] != 0.0 && full_c[(4, 0)] != 0.0);
+        let ratio = full_c[(2, 0)] / delta_c[(2, 0)];
+        assert!(ratio > 1.3 && ratio < 1.6, "{ratio}");
+
+        // A frequency dependent correction table, whose amplitudes are given in 1e-12
+        let table = temp_dir().join("nyx_ut_tab6.5b.txt");
+        fs::write(
+            &table,
+            "Name Doodson tau s h p N' ps l l' F D Om Amp(ip) Amp(op)\n K1 165.555 1 1 0 0 0 0 0 0 0 0 0 100.0 -10.0\n",
+        )
+        .unwrap();
+        let mut corrected = SolidTides::earth_raw(cosm.clone());
+        corrected
+            .load_frequency_corrections(table.to_str().unwrap())
+            .unwrap();
+        assert_eq!(corrected.constituents.len(), 1);
+        let mut corr_c = DMatrix::from_element(5, 5, 0.0);
+        let mut corr_s = corr_c.clone();
+        corrected
+            .add_corrections(epoch, iau_earth, &mut corr_c, &mut corr_s)
+            .unwrap();
+        let args = doodson_arguments(epoch);
+        let theta = args[0] + args[1];
+        assert!(
+            (corr_c[(2, 1)] - full_c[(2, 1)] - 1e-12 * (100.0 * theta.sin() - 10.0 * theta.cos()))
+                .abs()
+                < 1e-22
+        );
+        assert!(
+            (corr_s[(2, 1)] - full_s[(2, 1)] - 1e-12 * (100.0 * theta.cos() + 10.0 * theta.sin()))
+                .abs()
+                < 1e-22
+        );
+        assert_eq!(corr_c[(2, 0)], full_c[(2, 0)]);
+
+        // Tides raised by the Earth on the Moon
+        let moon_tides = SolidTides::moon(cosm.clone());
+        let mut moon_c = DMatrix::from_element(3, 3, 0.0);
+        let mut moon_s = moon_c.clone();
+        moon_tides
+            .add_corrections(epoch, cosm.frame("IAU Moon"), &mut moon_c, &mut moon_s)
+            .unwrap();
+        assert!(moon_c[(2, 0)].abs() > 1e-8, "{moon_c}");
+    }
+
+    #[test]
+    fn test_ocean_tides() {
+        let path = temp_dir().join("nyx_ut_ocean_tides.dat");
+        fs::write(
+            &path,
+            "Doodson Darw  l   m    DelC+     DelS+       DelC-     DelS-\n\
+              55.565 Om1   2   0   6.58128   -0.00000   -0.00000   -0.00000\n\
+             255.555 M2    2   2   0.30000    0.40000    0.10000    0.20000\n\
+             255.555 M2    9   2   1.00000    1.00000    1.00000    1.00000\n\
+             165.555       3   1   0.50000    0.00000    0.00000    0.00000\n",
+        )
+        .unwrap();
+        let tides = OceanTides::from_file(path.to_str().unwrap(), 4).unwrap();
+        assert_eq!(tides.max_degree(), 3);
+        assert_eq!(tides.constituents.len(), 3);
+
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let mut delta_c = DMatrix::from_element(4, 4, 0.0);
+        let mut delta_s = delta_c.clone();
+        tides
+            .add_corrections(epoch, cosm.frame("IAU Earth"), &mut delta_c, &mut delta_s)
+            .unwrap();
+        let args = doodson_arguments(epoch);
+
+        // IERS eq. 6.15 in complex form: ΔC - i ΔS = Σ (C+ - i S+) exp(iθ) + (C- + i S-) exp(-iθ)
+        let eq_6_15 = |theta: f64, [c_plus, s_plus, c_minus, s_minus]: [f64; 4]| {
+            let sum = Complex::new(c_plus, -s_plus) * Complex::from_polar(1.0, theta)
+                + Complex::new(c_minus, s_minus) * Complex::from_polar(1.0, -theta);
+            (1e-11 * sum.re, -1e-11 * sum.im)
+        };
+        let theta_m2 = 2.0 * args[0];
+        let (c22, s22) = eq_6_15(theta_m2, [0.3, 0.4, 0.1, 0.2]);
+        assert!((delta_c[(2, 2)] - c22).abs() < 1e-25);
+        assert!((delta_s[(2, 2)] - s22).abs() < 1e-25);
+        // The prograde and retrograde sine terms add up in ΔC, and the cosine terms of ΔS are their difference
+        assert!(
+            (delta_c[(2, 2)] - 1e-11 * (0.4 * theta_m2.cos() + 0.6 * theta_m2.sin())).abs() < 1e-25
+        );
+        assert!(
+            (delta_s[(2, 2)] - 1e-11 * (0.2 * theta_m2.cos() - 0.2 * theta_m2.sin())).abs() < 1e-25
+        );
+        assert!((delta_c[(2, 0)] - 6.58128e-11 * (-args[4]).cos()).abs() < 1e-25);
+        let (c31, s31) = eq_6_15(args[0] + args[1], [0.5, 0.0, 0.0, 0.0]);
+        assert!((delta_c[(3, 1)] - c31).abs() < 1e-25);
+        assert!((delta_s[(3, 1)] - s31).abs() < 1e-25);
+
+        assert!(OceanTides::from_file(path.to_str().unwrap(), 1).is_err());
+        assert!(OceanTides::from_file("not_a_file.dat", 4).is_err());
+    }
+
+    /// A constant correction to test how the harmonics apply the tide models
+    struct ConstantTide {
+        degree: usize,
+        order: usize,
+        delta_c: f64,
+    }
+
+    impl fmt::Display for ConstantTide {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "constant tide")
+        }
+    }
+
+    impl TideModel for ConstantTide {
+        fn max_degree(&self) -> usize {
+            self.degree
+        }
+
+        fn add_corrections(
+            &self,
+            _epoch: Epoch,
+            _body_frame: Frame,
+            delta_c: &mut DMatrix<f64>,
+            _delta_s: &mut DMatrix<f64>,
+        ) -> Result<(), DynamicsError> {
+            delta_c[(self.degree, self.order)] += self.delta_c;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_harmonics_with_tides() {
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let iau_earth = cosm.frame("IAU Earth");
+        let eme2k = cosm.frame("EME2000");
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        let orbit = Orbit::keplerian(7_000.0, 0.01, 51.6, 30.0, 45.0, 60.0, epoch, eme2k);
+
+        let j2 = -4.841_653_748_864_70e-04;
+        let static_field = Harmonics::from_stor(iau_earth, HarmonicsMem::from_j2(j2), cosm.clone());
+        // A constant correction of the C20 is the same as the corrected static field
+        let tide: Arc<dyn TideModel> = Arc::new(ConstantTide {
+            degree: 2,
+            order: 0,
+            delta_c: 1e-6,
+        });
+        let tidal_field = Harmonics::with_tides(
+            iau_earth,
+            HarmonicsMem::from_j2(j2),
+            vec![tide],
+            cosm.clone(),
+        );
+        let corrected_field =
+            Harmonics::from_stor(iau_earth, HarmonicsMem::from_j2(j2 + 1e-6), cosm.clone());
+        let accel = tidal_field.eom(&orbit).unwrap();
+        assert!((accel - corrected_field.eom(&orbit).unwrap()).norm() < 1e-18);
+        assert!((accel - static_field.eom(&orbit).unwrap()).norm() > 1e-12);
+        let (dual_accel, grad) = tidal_field.dual_eom(&orbit).unwrap();
+        let (_, corrected_grad) = corrected_field.dual_eom(&orbit).unwrap();
+        assert!((dual_accel - accel).norm() < 1e-15);
+        assert!((grad - corrected_grad).norm() < 1e-18);
+
+        // Corrections beyond the degree and order of the static field are also applied
+        let tide: Arc<dyn TideModel> = Arc::new(ConstantTide {
+            degree: 3,
+            order: 3,
+            delta_c: 1e-6,
+        });
+        let tidal_field = Harmonics::with_tides(
+            iau_earth,
+            HarmonicsMem::from_j2(j2),
+            vec![tide],
+            cosm.clone(),
+        );
+        let delta = tidal_field.eom(&orbit).unwrap() - static_field.eom(&orbit).unwrap();
+        assert!(delta.norm() > 1e-12);
+
+        // The solid tides are a small perturbation of the J2 acceleration
+        let solid = Harmonics::with_tides(
+            iau_earth,
+            HarmonicsMem::from_j2(j2),
+            vec![SolidTides::earth(cosm.clone())],
+            cosm,
+        );
+        let j2_accel = static_field.eom(&orbit).unwrap();
+        let tidal_accel = solid.eom(&orbit).unwrap() - j2_accel;
+        let ratio = tidal_accel.norm() / j2_accel.norm();
+        assert!(ratio > 1e-6 && ratio < 1e-3, "{ratio}");
+        assert!(format!("{solid}").contains("solid tides"));
+    }
+}
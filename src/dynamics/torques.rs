@@ -0,0 +1,172 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{DynamicsError, TorqueModel};
+use crate::cosmic::{AttitudeLaw, RigidSpacecraft};
+use crate::linalg::Vector3;
+use crate::na::UnitQuaternion;
+use std::fmt;
+
+/// `GravityGradient` is the torque due to the central body of the integration frame on a rigid body, as per Wertz, eq. 17-42.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GravityGradient;
+
+impl fmt::Display for GravityGradient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "gravity gradient torque")
+    }
+}
+
+impl TorqueModel for GravityGradient {
+    fn torque(&self, ctx: &RigidSpacecraft) -> Result<Vector3<f64>, DynamicsError> {
+        let osc = &ctx.sc.orbit;
+        let attitude = &ctx.attitude;
+        // Nadir direction in the body frame
+        let r_body = attitude.dcm_to_frame().transpose() * osc.radius() / osc.rmag_km();
+        // The GM in km^3/s^2 divided by the cube of the distance in km yields s^-2, so the torque is in N m
+        let k = 3.0 * osc.frame.gm() / osc.rmag_km().powi(3);
+        Ok(k * r_body.cross(&(attitude.inertia() * r_body)))
+    }
+}
+
+/// `PointingController` is a proportional-derivative attitude controller tracking an attitude law, e.g. with reaction wheels or thrusters.
+///
+/// The actuators are ideal: the torque is applied instantaneously and saturates on each body axis, and the momentum stored in reaction wheels is not tracked.
+#[derive(Copy, Clone, Debug)]
+pub struct PointingController {
+    /// Attitude law to track
+    pub target: AttitudeLaw,
+    /// Proportional gain in N m/rad
+    pub kp_nm_rad: f64,
+    /// Derivative gain in N m s/rad
+    pub kd_nms_rad: f64,
+    /// Maximum torque on each body axis, in N m
+    pub max_torque_nm: f64,
+}
+
+impl fmt::Display for PointingController {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?} pointing controller (kp = {} N m/rad, kd = {} N m s/rad, max torque = {} N m)",
+            self.target, self.kp_nm_rad, self.kd_nms_rad, self.max_torque_nm
+        )
+    }
+}
+
+impl TorqueModel for PointingController {
+    fn torque(&self, ctx: &RigidSpacecraft) -> Result<Vector3<f64>, DynamicsError> {
+        let attitude = &ctx.attitude;
+        let target_dcm = self.target.dcm_to_frame(&ctx.sc.orbit);
+        let target = UnitQuaternion::from_matrix(&target_dcm);
+        // Rotation vector from the target to the current attitude, in the body frame
+        let error = (target.inverse() * attitude.body_to_frame()).scaled_axis();
+        // Angular velocity relative to the target attitude, in the body frame
+        let target_omega =
+            attitude.dcm_to_frame().transpose() * self.target.angular_velocity(&ctx.sc.orbit);
+        let rate_error = attitude.omega() - target_omega;
+
+        let torque = -self.kp_nm_rad * error - self.kd_nms_rad * rate_error;
+        Ok(torque.map(|t| t.clamp(-self.max_torque_nm, self.max_torque_nm)))
+    }
+}
+
+#[cfg(test)]
+mod ut_torques {
+    use super::*;
+    use crate::cosmic::{Attitude, Cosm, Orbit, Plate, Spacecraft, SurfaceModel};
+    use crate::dynamics::{ForceModel, SolarPressure};
+    use crate::linalg::Matrix3;
+    use crate::time::Epoch;
+    use std::f64::consts::FRAC_PI_4;
+    use std::sync::Arc;
+
+    fn leo(cosm: &Cosm) -> Orbit {
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2023, 1, 1);
+        // On the sunlit side, since the Sun is along -X in this test Cosm
+        Orbit::keplerian(
+            7_000.0,
+            0.0,
+            30.0,
+            0.0,
+            0.0,
+            180.0,
+            epoch,
+            cosm.frame("EME2000"),
+        )
+    }
+
+    #[test]
+    fn test_gravity_gradient() {
+        let cosm = Cosm::fixed_planets();
+        let orbit = leo(&cosm);
+        let inertia = Matrix3::from_diagonal(&Vector3::new(10.0, 20.0, 30.0));
+        let nadir = AttitudeLaw::NadirPointing.dcm_to_frame(&orbit);
+        let sc = Spacecraft::from_srp_defaults(orbit, 100.0, 0.0);
+
+        // No torque when the principal axes are aligned with the nadir
+        let aligned = RigidSpacecraft::new(
+            sc,
+            Attitude::from_dcm(nadir, Vector3::zeros(), inertia).unwrap(),
+        );
+        assert!(GravityGradient.torque(&aligned).unwrap().norm() < 1e-18);
+
+        // Rotated by 45 degrees about the body Y axis, the torque is 3 GM/r^3 (Iz - Ix) / 2 about that axis
+        let pitch = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), FRAC_PI_4);
+        let pitched = RigidSpacecraft::new(
+            sc,
+            Attitude::from_dcm(
+                nadir * pitch.to_rotation_matrix().into_inner(),
+                Vector3::zeros(),
+                inertia,
+            )
+            .unwrap(),
+        );
+        let torque = GravityGradient.torque(&pitched).unwrap();
+        let expected = 3.0 * orbit.frame.gm() / orbit.rmag_km().powi(3) * (30.0 - 10.0) / 2.0;
+        assert!((torque[1].abs() - expected).abs() < 1e-12 * expected);
+        assert!(torque[0].abs() < 1e-18 && torque[2].abs() < 1e-18);
+    }
+
+    #[test]
+    fn test_srp_torque() {
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let orbit = leo(&cosm);
+        let cannonball_srp = SolarPressure::default(orbit.frame, cosm);
+        // An absorbing plate facing the Sun, offset from the center of mass along the body Y axis
+        let surface = SurfaceModel::new(
+            AttitudeLaw::NadirPointing,
+            &[Plate::new(2.0, [-1.0, 0.0, 0.0], 0.0, 0.0).with_center([0.0, 1.5, 0.0])],
+        );
+        let srp = cannonball_srp.with_surface(Arc::new(surface));
+        let inertia = Matrix3::from_diagonal(&Vector3::new(10.0, 20.0, 30.0));
+        // The propagated attitude, aligned with the integration frame, replaces the nadir pointing law of the surface model
+        let state = RigidSpacecraft::new(
+            Spacecraft::from_srp_defaults(orbit, 100.0, 0.0),
+            Attitude::new(UnitQuaternion::identity(), Vector3::zeros(), inertia).unwrap(),
+        );
+
+        let force_n = 1e3 * srp.eom(&state.oriented()).unwrap();
+        let torque = srp.torque(&state).unwrap();
+        assert!(force_n[0] > 0.0);
+        assert!((torque - Vector3::new(0.0, 1.5, 0.0).cross(&force_n)).norm() < 1e-15);
+
+        // Without a surface model, the SRP does not produce any torque
+        assert_eq!(cannonball_srp.torque(&state).unwrap(), Vector3::zeros());
+    }
+}
@@ -99,6 +99,9 @@ pub enum NyxError {
     /// Configuration file error
     #[snafu(display("Config error: {source}"))]
     ConfigError { source: ConfigError },
+    /// SGP4 propagation error
+    #[snafu(display("SGP4 error: {msg}"))]
+    Sgp4 { msg: String },
 }
 
 impl From<TrajError> for NyxError {
@@ -0,0 +1,195 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::spk::{DafReader, SpkSegmentData, NAIF_J2000_FRAME_ID};
+use crate::NyxError;
+use std::fs::File;
+use std::io::Read;
+
+/// Number of double precision components in a binary PCK summary
+const BPC_ND: usize = 2;
+/// Number of integer components in a binary PCK summary
+const BPC_NI: usize = 5;
+
+/// A segment of a binary PCK file, providing the Euler angles of a body fixed frame with respect to a reference frame over a time span.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BpcSegment {
+    pub name: String,
+    /// NAIF frame class ID of the body fixed frame (e.g. 31008 for MOON_PA_DE440)
+    pub frame_id: i32,
+    /// NAIF ID of the reference frame of the Euler angles
+    pub reference_id: i32,
+    /// PCK type of this segment
+    pub data_type: i32,
+    /// Start of the validity of this segment, in ET seconds past J2000
+    pub start_et_s: f64,
+    /// End of the validity of this segment, in ET seconds past J2000
+    pub end_et_s: f64,
+    pub data: SpkSegmentData,
+}
+
+impl BpcSegment {
+    /// Returns whether this segment provides data at the provided ET seconds past J2000
+    pub fn covers(&self, et_s: f64) -> bool {
+        et_s >= self.start_et_s && et_s <= self.end_et_s
+    }
+
+    /// Returns the Euler angles (rad) φ, δ and w at the provided ET seconds past J2000, followed by their rates (rad/s).
+    /// The rotation from the reference frame to the body fixed frame is R3(w) * R1(δ) * R3(φ).
+    pub fn evaluate(&self, et_s: f64) -> Result<[f64; 6], NyxError> {
+        if !self.covers(et_s) {
+            return Err(NyxError::NoInterpolationData {
+                msg: format!(
+                    "PCK segment `{}` covers {} to {} ET seconds, requested {et_s}",
+                    self.name, self.start_et_s, self.end_et_s
+                ),
+            });
+        }
+
+        self.data.interpolate(et_s)
+    }
+}
+
+/// A binary PCK file (also known as a BPC) decoded in memory, e.g. the lunar libration angles `moon_pa_de440_200625.bpc`.
+///
+/// Only type 2 segments (Chebyshev polynomials of the Euler angles) with respect to the J2000 orientation are supported.
+/// Unsupported segments are skipped with a warning.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bpc {
+    /// Segments in the order of the file, i.e. the last segment has the highest priority
+    pub segments: Vec<BpcSegment>,
+}
+
+impl Bpc {
+    /// Loads the provided binary PCK file
+    pub fn from_file(input_filename: &str) -> Result<Self, NyxError> {
+        let mut buf = Vec::new();
+        let mut f = File::open(input_filename).map_err(|e| NyxError::LoadingError {
+            msg: format!("{input_filename}: {e}"),
+        })?;
+        f.read_to_end(&mut buf)
+            .map_err(|e| NyxError::FileUnreadable {
+                msg: format!("{input_filename}: {e}"),
+            })?;
+
+        let bpc = Self::from_buffer(&buf)?;
+        info!(
+            "{input_filename} loaded with {} PCK segments",
+            bpc.segments.len()
+        );
+        Ok(bpc)
+    }
+
+    /// Decodes the provided buffer as a binary PCK
+    pub fn from_buffer(buf: &[u8]) -> Result<Self, NyxError> {
+        let daf = DafReader::try_from_buffer(buf, "PCK", BPC_ND, BPC_NI)?;
+
+        let mut segments = Vec::new();
+        for summary in daf.summaries()? {
+            let name = summary.name;
+            let (frame_id, reference_id, data_type) =
+                (summary.ints[0], summary.ints[1], summary.ints[2]);
+            let (begin_addr, end_addr) = (summary.ints[3] as usize, summary.ints[4] as usize);
+
+            if reference_id != NAIF_J2000_FRAME_ID {
+                warn!("skipping PCK segment `{name}`: frame {reference_id} is not J2000");
+                continue;
+            }
+
+            if data_type != 2 {
+                warn!("skipping PCK segment `{name}`: type {data_type} is not supported");
+                continue;
+            }
+
+            segments.push(BpcSegment {
+                name,
+                frame_id,
+                reference_id,
+                data_type,
+                start_et_s: summary.start_et_s,
+                end_et_s: summary.end_et_s,
+                data: daf.chebyshev(begin_addr, end_addr, false)?,
+            });
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Returns the highest priority segment of the provided frame class ID which covers the provided ET seconds past J2000
+    pub fn segment_at(&self, frame_id: i32, et_s: f64) -> Option<&BpcSegment> {
+        self.segments
+            .iter()
+            .rev()
+            .find(|seg| seg.frame_id == frame_id && seg.covers(et_s))
+    }
+}
+
+#[cfg(test)]
+mod ut_bpc {
+    use super::*;
+
+    #[test]
+    fn test_bpc_chebyshev() {
+        // File record, one summary record, one name record, then the data
+        let mut buf = vec![0_u8; 3 * 1024];
+        buf[0..8].copy_from_slice(b"DAF/PCK ");
+        buf[8..12].copy_from_slice(&(BPC_ND as i32).to_le_bytes());
+        buf[12..16].copy_from_slice(&(BPC_NI as i32).to_le_bytes());
+        buf[76..80].copy_from_slice(&2_i32.to_le_bytes());
+        buf[88..96].copy_from_slice(b"LTL-IEEE");
+        buf[1024 + 16..1024 + 24].copy_from_slice(&1.0_f64.to_le_bytes());
+
+        // One record over [-100; 100] seconds: phi = 0.1 + 0.2 T1, delta = 0.3, w = 0.5 T1
+        let data: [f64; 12] = [
+            0.0, 100.0, 0.1, 0.2, 0.3, 0.0, 0.0, 0.5, -100.0, 200.0, 8.0, 1.0,
+        ];
+        let first_addr = 3 * 1024 / 8 + 1;
+        let summary = 1024 + 24;
+        buf[summary..summary + 8].copy_from_slice(&(-100.0_f64).to_le_bytes());
+        buf[summary + 8..summary + 16].copy_from_slice(&100.0_f64.to_le_bytes());
+        let ints = [
+            31008,
+            NAIF_J2000_FRAME_ID,
+            2,
+            first_addr as i32,
+            (first_addr + data.len() - 1) as i32,
+        ];
+        for (ino, val) in ints.iter().enumerate() {
+            buf[summary + 16 + 4 * ino..summary + 20 + 4 * ino].copy_from_slice(&val.to_le_bytes());
+        }
+        for val in data {
+            buf.extend_from_slice(&val.to_le_bytes());
+        }
+
+        let bpc = Bpc::from_buffer(&buf).unwrap();
+        assert_eq!(bpc.segments.len(), 1);
+        assert!(bpc.segment_at(31006, 0.0).is_none());
+        assert!(bpc.segment_at(31008, 150.0).is_none());
+
+        let angles = bpc.segment_at(31008, 50.0).unwrap().evaluate(50.0).unwrap();
+        assert!((angles[0] - 0.2).abs() < f64::EPSILON);
+        assert!((angles[1] - 0.3).abs() < f64::EPSILON);
+        assert!((angles[2] - 0.25).abs() < f64::EPSILON);
+        assert!((angles[3] - 0.002).abs() < f64::EPSILON);
+        assert!((angles[5] - 0.005).abs() < f64::EPSILON);
+
+        // An SPK is not a binary PCK
+        buf[0..8].copy_from_slice(b"DAF/SPK ");
+        assert!(Bpc::from_buffer(&buf).is_err());
+    }
+}
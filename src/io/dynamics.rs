@@ -23,6 +23,8 @@ use serde::{Deserialize, Serialize};
 use super::{frames_from_str, frames_to_str, ConfigRepr};
 
 use crate::cosmic::{Bodies, Frame};
+use crate::dynamics::RadiationCoefficients;
+use crate::io::space_weather::SolarIndices;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct HarmonicsSerde {
@@ -39,13 +41,71 @@ pub struct SrpSerde {
     pub shadows: Vec<Frame>,
 }
 
+/// Atmospheric density model of the drag, as per `AtmDensity`, with densities in kg/m^3 and altitudes in meters
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum DensitySerde {
+    Constant {
+        rho: f64,
+    },
+    Exponential {
+        rho0: f64,
+        r0: f64,
+        ref_alt_m: f64,
+    },
+    StdAtm {
+        max_alt_m: Option<f64>,
+    },
+    /// Jacchia-Roberts density using a CelesTrak space weather CSV file, and/or constant indices used outside of that file
+    JacchiaRoberts {
+        space_weather: Option<String>,
+        fallback: Option<SolarIndices>,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DragSerde {
+    /// Frame to compute the drag in, defaults to `IAU Earth`
+    pub frame: Option<String>,
+    pub density: DensitySerde,
+}
+
+/// Post-Newtonian corrections of the central body of the integration frame, as per `Relativity`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RelativitySerde {
+    /// Body fixed frame of the central body, e.g. `IAU Earth`
+    pub frame: String,
+    #[serde(default)]
+    pub schwarzschild: bool,
+    /// Angular momentum per unit mass of the central body in km^2/s, enables the Lense-Thirring term if set
+    pub angular_momentum_km2_s: Option<f64>,
+    #[serde(default)]
+    pub de_sitter: bool,
+}
+
+/// Planetary radiation pressure (albedo and infrared) of the central body, as per `PlanetaryRadiation`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PlanetaryRadiationSerde {
+    /// Body fixed frame of the central body, defaults to `IAU Earth`
+    pub frame: Option<String>,
+    /// Albedo and emissivity of the surface, defaults to the Knocke coefficients
+    pub coefficients: Option<RadiationCoefficients>,
+    pub phi: Option<f64>,
+    pub rings: Option<usize>,
+    /// Scale factor of the force, e.g. as estimated by the orbit determination, defaults to 1.0
+    pub scale_factor: Option<f64>,
+}
+
 /// A representation of spacecraft dynamics that need to be used in Python with the spacecraft Propagator class.
 #[derive(Debug, Deserialize, Serialize)]
 #[cfg_attr(feature = "python", pyclass)]
 pub struct DynamicsSerde {
     pub point_masses: Vec<Bodies>,
     pub harmonics: Option<Vec<HarmonicsSerde>>,
+    pub relativity: Option<RelativitySerde>,
     pub srp: Option<SrpSerde>,
+    pub drag: Option<DragSerde>,
+    pub albedo: Option<PlanetaryRadiationSerde>,
 }
 
 impl ConfigRepr for DynamicsSerde {}
@@ -72,16 +132,51 @@ hifi:
       coeffs: data/JGM3.cof.gz
       degree: 10
       order: 10
+  relativity:
+    frame: IAU Earth
+    schwarzschild: true
+    angular_momentum_km2_s: 980.0
+    de_sitter: true
   srp:
     phi: 1367.0
     shadows:
       - Sun J2000
       - Moon J2000
+  drag:
+    density:
+      model: jacchia_roberts
+      fallback:
+        f107: 150.0
+        f107a: 150.0
+        ap: 15.0
+  albedo:
+    coefficients:
+      model: constant
+      albedo: 0.3
+      emissivity: 0.7
+    rings: 3
+    scale_factor: 1.2
 ";
 
     let cosm = Cosm::de438();
 
     let mut dynamics_serde: HashMap<String, DynamicsSerde> = serde_yaml::from_str(yaml).unwrap();
+    assert!(matches!(
+        dynamics_serde["hifi"].drag.as_ref().unwrap().density,
+        DensitySerde::JacchiaRoberts {
+            space_weather: None,
+            fallback: Some(_)
+        }
+    ));
+    let albedo = dynamics_serde["hifi"].albedo.as_ref().unwrap();
+    assert_eq!(
+        albedo.coefficients,
+        Some(RadiationCoefficients::Constant {
+            albedo: 0.3,
+            emissivity: 0.7
+        })
+    );
+    assert_eq!(albedo.scale_factor, Some(1.2));
 
     // Access the "hifi" dynamics
     let hifi_dynamics =
@@ -94,3 +189,69 @@ hifi:
         SpacecraftDynamics::from_config(dynamics_serde.remove("lofi").unwrap(), cosm).unwrap();
     println!("lofi dynamics: {}", lofi_dynamics);
 }
+
+#[test]
+fn test_lunar_harmonics_frame() {
+    use crate::cosmic::Cosm;
+    use crate::io::Configurable;
+    use crate::md::prelude::SpacecraftDynamics;
+    use std::sync::Arc;
+
+    // Lunar harmonics are expressed in the Moon PA frame, so they cannot be computed in the IAU Moon frame
+    let yaml = "
+point_masses:
+  - Luna
+harmonics:
+  - frame: IAU Moon
+    coeffs: data/Luna_jggrx_1500e_sha.tab.gz
+    degree: 10
+    order: 10
+";
+
+    let dynamics_serde: DynamicsSerde = serde_yaml::from_str(yaml).unwrap();
+    match SpacecraftDynamics::from_config(dynamics_serde, Arc::new(Cosm::fixed_planets())) {
+        Ok(_) => panic!("lunar harmonics computed in the IAU Moon frame"),
+        Err(err) => assert!(format!("{err}").contains("Moon PA"), "{err}"),
+    }
+}
+
+#[test]
+fn test_relativity_frame() {
+    // The central body of the relativistic corrections must be explicit
+    let yaml = "
+point_masses:
+  - Earth
+relativity:
+  schwarzschild: true
+";
+    assert!(serde_yaml::from_str::<DynamicsSerde>(yaml).is_err());
+
+    let relativity: DynamicsSerde =
+        serde_yaml::from_str(&format!("{yaml}  frame: IAU Earth\n")).unwrap();
+    assert_eq!(relativity.relativity.unwrap().frame, "IAU Earth");
+}
+
+#[test]
+fn test_albedo() {
+    use crate::cosmic::Cosm;
+    use crate::io::Configurable;
+    use crate::md::prelude::SpacecraftDynamics;
+    use std::sync::Arc;
+
+    // The planetary radiation defaults to the Knocke coefficients of the Earth
+    let yaml = "
+point_masses:
+  - Earth
+albedo:
+  scale_factor: 0.8
+";
+
+    let dynamics_serde: DynamicsSerde = serde_yaml::from_str(yaml).unwrap();
+    let dynamics =
+        SpacecraftDynamics::from_config(dynamics_serde, Arc::new(Cosm::fixed_planets())).unwrap();
+    let repr = format!("{dynamics}");
+    assert!(
+        repr.contains("Knocke coefficients (5 rings, scale factor 0.8)"),
+        "{repr}"
+    );
+}
@@ -0,0 +1,258 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::errors::NyxError;
+use crate::time::Epoch;
+use std::fs::read_to_string;
+
+/// A single day of Earth orientation parameters, as published by the IERS.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct EopRecord {
+    /// Modified Julian Date (UTC) of this record
+    pub mjd_utc: f64,
+    /// X coordinate of the pole, in arcseconds
+    pub x_pole_arcsec: f64,
+    /// Y coordinate of the pole, in arcseconds
+    pub y_pole_arcsec: f64,
+    /// UT1 - UTC, in seconds
+    pub ut1_utc_s: f64,
+    /// Celestial pole offset dX with respect to the IAU 2006/2000A model, in arcseconds
+    pub dx_arcsec: f64,
+    /// Celestial pole offset dY with respect to the IAU 2006/2000A model, in arcseconds
+    pub dy_arcsec: f64,
+}
+
+/// Earth orientation parameters loaded from an IERS `finals2000A` or C04 file.
+///
+/// Records are sorted by date and linearly interpolated. The UT1-UTC leap second discontinuities are removed prior to interpolation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EarthOrientationParams {
+    pub records: Vec<EopRecord>,
+}
+
+impl EarthOrientationParams {
+    /// Loads the IERS `finals2000A.all` (or `finals2000A.data`, `finals2000A.daily`) file.
+    pub fn from_finals2000a(filepath: &str) -> Result<Self, NyxError> {
+        Self::from_finals2000a_str(&Self::read(filepath)?)
+    }
+
+    /// Loads the IERS EOP 14 C04 or EOP 20 C04 (IAU 2000A) file.
+    pub fn from_c04(filepath: &str) -> Result<Self, NyxError> {
+        Self::from_c04_str(&Self::read(filepath)?)
+    }
+
+    /// Parses the content of a `finals2000A` file, a fixed column format.
+    /// Lines without polar motion or UT1-UTC (e.g. the end of the predictions) are ignored,
+    /// and missing celestial pole offsets are set to zero.
+    pub fn from_finals2000a_str(content: &str) -> Result<Self, NyxError> {
+        let field = |line: &str, start: usize, end: usize| -> Option<f64> {
+            line.get(start..end.min(line.len()))
+                .and_then(|s| s.trim().parse::<f64>().ok())
+        };
+
+        let mut records = Vec::new();
+        for line in content.lines() {
+            let (Some(mjd_utc), Some(x_pole_arcsec), Some(y_pole_arcsec), Some(ut1_utc_s)) = (
+                field(line, 7, 15),
+                field(line, 18, 27),
+                field(line, 37, 46),
+                field(line, 58, 68),
+            ) else {
+                continue;
+            };
+            records.push(EopRecord {
+                mjd_utc,
+                x_pole_arcsec,
+                y_pole_arcsec,
+                ut1_utc_s,
+                // Celestial pole offsets are in milliarcseconds in this file
+                dx_arcsec: field(line, 97, 106).unwrap_or(0.0) * 1e-3,
+                dy_arcsec: field(line, 116, 125).unwrap_or(0.0) * 1e-3,
+            });
+        }
+
+        Self::from_records(records)
+    }
+
+    /// Parses the content of a C04 file, whitespace separated.
+    /// Both the EOP 14 C04 (`YR MM DD MJD x y UT1-UTC LOD dX dY ...`) and the EOP 20 C04 (`YR MM DD HH MJD x y UT1-UTC dX dY ...`) layouts are supported.
+    pub fn from_c04_str(content: &str) -> Result<Self, NyxError> {
+        let mut records = Vec::new();
+        for line in content.lines() {
+            let fields: Vec<f64> = match line
+                .split_whitespace()
+                .take(10)
+                .map(|s| s.parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+            {
+                Ok(fields) if fields.len() == 10 => fields,
+                // Header or comment line
+                _ => continue,
+            };
+
+            // In the 14 C04 layout, the fourth column is the MJD, whereas it's the hour in the 20 C04 layout.
+            // The celestial pole offsets are in the same columns in both layouts because the 14 C04 also lists the LOD.
+            let mjd_idx = if fields[3] > 24.0 { 3 } else { 4 };
+            records.push(EopRecord {
+                mjd_utc: fields[mjd_idx],
+                x_pole_arcsec: fields[mjd_idx + 1],
+                y_pole_arcsec: fields[mjd_idx + 2],
+                ut1_utc_s: fields[mjd_idx + 3],
+                dx_arcsec: fields[8],
+                dy_arcsec: fields[9],
+            });
+        }
+
+        Self::from_records(records)
+    }
+
+    /// Builds the EOP from the provided records, which are sorted by date.
+    pub fn from_records(mut records: Vec<EopRecord>) -> Result<Self, NyxError> {
+        if records.is_empty() {
+            return Err(NyxError::LoadingError {
+                msg: "no Earth orientation parameters found".to_string(),
+            });
+        }
+        records.sort_by(|a, b| a.mjd_utc.total_cmp(&b.mjd_utc));
+        records.dedup_by(|a, b| a.mjd_utc == b.mjd_utc);
+        Ok(Self { records })
+    }
+
+    /// Returns the first and last dates of these EOP as UTC epochs
+    pub fn domain(&self) -> (Epoch, Epoch) {
+        (
+            Epoch::from_mjd_utc(self.records[0].mjd_utc),
+            Epoch::from_mjd_utc(self.records[self.records.len() - 1].mjd_utc),
+        )
+    }
+
+    /// Linearly interpolates the Earth orientation parameters at the provided epoch.
+    /// Returns None if the epoch is outside of the loaded data.
+    pub fn at(&self, epoch: Epoch) -> Option<EopRecord> {
+        let mjd_utc = epoch.to_mjd_utc_days();
+        let idx = self.records.partition_point(|r| r.mjd_utc <= mjd_utc);
+        if idx == 0 {
+            return None;
+        } else if idx == self.records.len() {
+            let last = self.records[idx - 1];
+            return if last.mjd_utc == mjd_utc {
+                Some(last)
+            } else {
+                None
+            };
+        }
+
+        let prev = self.records[idx - 1];
+        let next = self.records[idx];
+        let frac = (mjd_utc - prev.mjd_utc) / (next.mjd_utc - prev.mjd_utc);
+        let lerp = |a: f64, b: f64| a + frac * (b - a);
+
+        // Remove the leap second, if any, which happens at the start of the next record
+        let leap_s = (next.ut1_utc_s - prev.ut1_utc_s).round();
+
+        Some(EopRecord {
+            mjd_utc,
+            x_pole_arcsec: lerp(prev.x_pole_arcsec, next.x_pole_arcsec),
+            y_pole_arcsec: lerp(prev.y_pole_arcsec, next.y_pole_arcsec),
+            ut1_utc_s: lerp(prev.ut1_utc_s, next.ut1_utc_s - leap_s),
+            dx_arcsec: lerp(prev.dx_arcsec, next.dx_arcsec),
+            dy_arcsec: lerp(prev.dy_arcsec, next.dy_arcsec),
+        })
+    }
+
+    fn read(filepath: &str) -> Result<String, NyxError> {
+        read_to_string(filepath).map_err(|e| NyxError::FileUnreadable {
+            msg: format!("{filepath}: {e}"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod ut_eop {
+    use super::*;
+
+    /// Builds a line with the column layout of the finals2000A files
+    fn finals_line(mjd: f64, x: f64, y: f64, ut1_utc: f64, dx_mas: f64, dy_mas: f64) -> String {
+        format!(
+            "{:6} {:8.2} I {:9.6}{:9.6} {:9.6}{:9.6}  I{:10.7}{:10.7} {:7.4}{:7.4}  I {:9.3}{:9.3} {:9.3}{:9.3}",
+            "161230", mjd, x, 0.00002, y, 0.00002, ut1_utc, 0.00001, 1.1, 0.01, dx_mas, 0.1, dy_mas, 0.1
+        )
+    }
+
+    #[test]
+    fn test_eop_finals2000a() {
+        let content = [
+            finals_line(57752.0, 0.080052, 0.258741, -0.4131279, 0.061, -0.086),
+            finals_line(57753.0, 0.078533, 0.259547, -0.4140946, 0.078, -0.095),
+            // 2017 January 1 leap second
+            finals_line(57754.0, 0.076975, 0.260317, 0.5850325, 0.092, -0.103),
+            // Prediction without polar motion is skipped
+            "170102 57755.00 P".to_string(),
+        ]
+        .join("\n");
+
+        let eop = EarthOrientationParams::from_finals2000a_str(&content).unwrap();
+        assert_eq!(eop.records.len(), 3);
+        assert_eq!(eop.records[0].mjd_utc, 57752.0);
+        assert!((eop.records[1].x_pole_arcsec - 0.078533).abs() < 1e-12);
+        assert!((eop.records[1].y_pole_arcsec - 0.259547).abs() < 1e-12);
+        assert!((eop.records[1].ut1_utc_s + 0.4140946).abs() < 1e-12);
+        assert!((eop.records[1].dx_arcsec - 0.078e-3).abs() < 1e-12);
+        assert!((eop.records[1].dy_arcsec + 0.095e-3).abs() < 1e-12);
+
+        // Interpolation
+        let mid = eop.at(Epoch::from_mjd_utc(57752.5)).unwrap();
+        assert!((mid.x_pole_arcsec - 0.0792925).abs() < 1e-9);
+        assert!((mid.ut1_utc_s + 0.41361125).abs() < 1e-9);
+
+        // Across the leap second, UT1-UTC must remain continuous until the leap second
+        let before_leap = eop.at(Epoch::from_mjd_utc(57753.75)).unwrap();
+        assert!(
+            (before_leap.ut1_utc_s + 0.41474928).abs() < 1e-6,
+            "{}",
+            before_leap.ut1_utc_s
+        );
+
+        // Exact last point and outside of the domain
+        let last = eop.at(Epoch::from_mjd_utc(57754.0)).unwrap();
+        assert!((last.x_pole_arcsec - 0.076975).abs() < 1e-9);
+        assert!(eop.at(Epoch::from_mjd_utc(57751.0)).is_none());
+        assert!(eop.at(Epoch::from_mjd_utc(57755.0)).is_none());
+
+        assert!(EarthOrientationParams::from_finals2000a_str("# nothing\n").is_err());
+    }
+
+    #[test]
+    fn test_eop_c04() {
+        let c04_14 = "      Date      MJD      x          y        UT1-UTC       LOD         dX        dY\n\
+            2017   1   1  57754   0.076975   0.260317   0.5850325   0.0010540   0.000092  -0.000103   0.000030   0.000030  0.0000100  0.0000100    0.000050    0.000050\n\
+            2017   1   2  57755   0.075580   0.261179   0.5840002   0.0010090   0.000097  -0.000110   0.000030   0.000030  0.0000100  0.0000100    0.000050    0.000050\n";
+        let eop = EarthOrientationParams::from_c04_str(c04_14).unwrap();
+        assert_eq!(eop.records.len(), 2);
+        assert_eq!(eop.records[1].mjd_utc, 57755.0);
+        assert_eq!(eop.records[1].ut1_utc_s, 0.5840002);
+        assert_eq!(eop.records[1].dx_arcsec, 0.000097);
+        assert_eq!(eop.records[1].dy_arcsec, -0.000110);
+
+        let c04_20 = "# YR  MM  DD  HH       MJD        Xp(\")     Yp(\")  UT1-UTC(s)       dX(\")      dY(\")\n\
+            2017  01  01  00  57754.00    0.076975   0.260317   0.5850325    0.000092   -0.000103    0.000010   0.000011   0.0010540\n\
+            2017  01  02  00  57755.00    0.075580   0.261179   0.5840002    0.000097   -0.000110    0.000010   0.000011   0.0010090\n";
+        let eop20 = EarthOrientationParams::from_c04_str(c04_20).unwrap();
+        assert_eq!(eop20, eop);
+    }
+}
@@ -16,7 +16,7 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::cosmic::Frame;
+use crate::cosmic::{Frame, FrameOrientation};
 use serde_derive::Deserialize;
 use std::collections::HashMap;
 
@@ -34,7 +34,10 @@ pub struct FrameSerde {
     flattening: f64,
     equatorial_radius: f64,
     semi_major_radius: f64,
-    pub rotation: RotationToml,
+    /// Rotation from the parent frame defined by its Euler angles, or its right ascension, declination and twist
+    pub rotation: Option<RotationToml>,
+    /// Rotation from the parent frame defined by two vectors
+    pub two_vector: Option<TwoVectorToml>,
 }
 
 impl FrameSerde {
@@ -61,6 +64,7 @@ impl FrameSerde {
             semi_major_radius: self.semi_major_radius,
             ephem_path: [None, None, None],
             frame_path: [None, None, None],
+            orientation: FrameOrientation::J2000,
         }
     }
 }
@@ -74,6 +78,24 @@ pub struct RotationToml {
     pub context: Option<HashMap<String, String>>,
 }
 
+/// A two-vector frame definition, where the axes are X, Y or Z, optionally preceeded by a sign (e.g. "-Z")
+#[derive(Clone, Deserialize)]
+pub struct TwoVectorToml {
+    pub primary_axis: String,
+    pub primary: FrameVectorToml,
+    pub secondary_axis: String,
+    pub secondary: FrameVectorToml,
+}
+
+/// A vector of a two-vector frame definition: the position, velocity or momentum of the target relative to the observer,
+/// where both are the names of existing frames (e.g. "Earth J2000")
+#[derive(Clone, Deserialize)]
+pub struct FrameVectorToml {
+    pub kind: String,
+    pub observer: String,
+    pub target: String,
+}
+
 #[test]
 fn test_deser_frame_toml() {
     use toml;
@@ -114,7 +136,7 @@ fn test_deser_frame_toml() {
     assert!((iau_sun.equatorial_radius - 696_342.0).abs() < std::f64::EPSILON);
     assert!((iau_sun.semi_major_radius - 696_342.0).abs() < std::f64::EPSILON);
 
-    let iau_sun_rot = &iau_sun.rotation;
+    let iau_sun_rot = iau_sun.rotation.as_ref().unwrap();
     assert_eq!(iau_sun_rot.right_asc, "289.13");
     assert_eq!(iau_sun_rot.declin, "63.87");
     assert_eq!(iau_sun_rot.w, "84.176 + 14.18440000*d");
@@ -128,9 +150,42 @@ fn test_deser_frame_toml() {
     assert!((iau_sun.equatorial_radius - -1.0).abs() < std::f64::EPSILON);
     assert!((iau_sun.semi_major_radius - -1.0).abs() < std::f64::EPSILON);
 
-    let iau_sun_rot = &iau_sun.rotation;
+    let iau_sun_rot = iau_sun.rotation.as_ref().unwrap();
     assert_eq!(iau_sun_rot.right_asc, "289.13");
     assert_eq!(iau_sun_rot.declin, "63.87");
     assert_eq!(iau_sun_rot.w, "84.176 + 14.18440000*d");
     assert_eq!(iau_sun_rot.angle_unit.as_ref().unwrap(), "degrees");
+    assert!(iau_sun.two_vector.is_none());
+}
+
+#[test]
+fn test_deser_two_vector_frame_toml() {
+    use toml;
+
+    let frames: FramesSerde = toml::from_str(
+        r#"
+        [frames.earth_moon_vnc]
+        inherit = "Moon J2000"
+        gm = -1
+        flattening = -1
+        equatorial_radius = -1
+        semi_major_radius = -1
+        [frames.earth_moon_vnc.two_vector]
+        primary_axis = "X"
+        secondary_axis = "Y"
+        primary = { kind = "velocity", observer = "Earth J2000", target = "Moon J2000" }
+        secondary = { kind = "momentum", observer = "Earth J2000", target = "Moon J2000" }
+    "#,
+    )
+    .unwrap();
+
+    let vnc = &frames.frames["earth_moon_vnc"];
+    assert!(vnc.rotation.is_none());
+    let two_vector = vnc.two_vector.as_ref().unwrap();
+    assert_eq!(two_vector.primary_axis, "X");
+    assert_eq!(two_vector.secondary_axis, "Y");
+    assert_eq!(two_vector.primary.kind, "velocity");
+    assert_eq!(two_vector.secondary.kind, "momentum");
+    assert_eq!(two_vector.secondary.observer, "Earth J2000");
+    assert_eq!(two_vector.secondary.target, "Moon J2000");
 }
@@ -16,9 +16,12 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+use crate::dynamics::{TimeVariableGravity, TimeVariableTerm, TimeVariation};
 use crate::linalg::DMatrix;
+use crate::time::Epoch;
 use crate::NyxError;
 use flate2::read::GzDecoder;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 use std::str::FromStr;
@@ -75,7 +78,7 @@ impl HarmonicsMem {
     ///
     /// Gravity models provided by `nyx`:
     /// + EMG2008 to 2190 for Earth (tide free)
-    /// + Moon to 1500 (from SHADR file)
+    /// + Moon to 1500 (from SHADR file), expressed in the `Moon PA` frame
     /// + Mars to 120 (from SHADR file)
     /// + Venus to 150 (from SHADR file)
     pub fn from_shadr(
@@ -452,6 +455,19 @@ impl HarmonicsMem {
         })
     }
 
+    /// Initialize `HarmonicsMem` from the static part of an ICGEM gravity field file (`.gfc`), i.e. the `gfc` coefficients and the `gfct` coefficients of ICGEM 1.0 files.
+    ///
+    /// The coefficients are normalized, but they are relative to the GM and the reference radius of the file: use `GfcField` to account for these
+    /// and for the time-variable terms.
+    pub fn from_gfc(
+        filepath: &str,
+        degree: usize,
+        order: usize,
+        gunzipped: bool,
+    ) -> Result<HarmonicsMem, NyxError> {
+        Ok(GfcField::load(filepath, degree, order, gunzipped)?.stor)
+    }
+
     /// Returns the maximum order of this gravity potential storage (Jnm=Jn2,Jn3...)
     pub fn max_order_m(&self) -> usize {
         self.order
@@ -466,6 +482,281 @@ impl HarmonicsMem {
     pub fn cs_nm(&self, degree: usize, order: usize) -> (f64, f64) {
         (self.c_nm[(degree, order)], self.s_nm[(degree, order)])
     }
+
+    /// Multiplies all of the coefficients of each degree by the provided scale of that degree
+    pub(crate) fn scale_by_degree(&mut self, scale: impl Fn(usize) -> f64) {
+        for n in 0..self.c_nm.nrows() {
+            let scale_n = scale(n);
+            for m in 0..self.c_nm.ncols() {
+                self.c_nm[(n, m)] *= scale_n;
+                self.s_nm[(n, m)] *= scale_n;
+            }
+        }
+    }
+}
+
+/// Reads the whole file, decompressing it if needed
+fn read_to_string(filepath: &str, gunzipped: bool) -> Result<String, NyxError> {
+    let mut f = File::open(filepath).map_err(|_| NyxError::FileUnreadable {
+        msg: format!("File not found: {filepath}"),
+    })?;
+    let mut buffer = vec![0; 0];
+    if gunzipped {
+        let mut d = GzDecoder::new(f);
+        d.read_to_end(&mut buffer)
+            .map_err(|_| NyxError::FileUnreadable {
+                msg: "could not read file as gunzip".to_string(),
+            })?;
+    } else {
+        f.read_to_end(&mut buffer)
+            .map_err(|_| NyxError::FileUnreadable {
+                msg: "could not read file to end".to_string(),
+            })?;
+    }
+
+    String::from_utf8(buffer).map_err(|_| NyxError::FileUnreadable {
+        msg: "could not decode file contents as utf8".to_string(),
+    })
+}
+
+/// Returns the factor from the fully normalized to the unnormalized coefficient of the provided degree and order
+fn normalization_factor(degree: usize, order: usize) -> f64 {
+    let delta = if order == 0 { 1.0 } else { 2.0 };
+    // (n-m)!/(n+m)! is computed in log space to support high degrees
+    let log_factorials: f64 = ((degree - order + 1)..=(degree + order))
+        .map(|k| (k as f64).ln())
+        .sum();
+    (0.5 * (delta * (2 * degree + 1) as f64).ln() - 0.5 * log_factorials).exp()
+}
+
+/// A gravity field in the ICGEM format (`.gfc`) of the International Centre for Global Earth Models, in which most modern fields of the Earth,
+/// the Moon and the planets are distributed (e.g. GOCO, EIGEN, GRGM1200A).
+///
+/// Both the ICGEM 1.0 and 2.0 formats are supported, including the time-variable terms (`gfct`, `trnd` or `dot`, `asin` and `acos`).
+/// Unnormalized fields are normalized when loaded.
+#[derive(Clone)]
+pub struct GfcField {
+    pub model_name: String,
+    /// Gravitational parameter of the field, in km^3/s^2
+    pub gm_km3_s2: f64,
+    /// Reference radius of the field, in km
+    pub radius_km: f64,
+    /// Tide system of the field (e.g. `tide_free` or `zero_tide`), if specified
+    pub tide_system: Option<String>,
+    /// Static part of the field, relative to its GM and reference radius
+    pub stor: HarmonicsMem,
+    /// Time-variable part of the field, relative to its GM and reference radius
+    pub time_variable: TimeVariableGravity,
+}
+
+impl GfcField {
+    /// Loads an ICGEM gravity field file up to the provided degree and order
+    pub fn load(
+        filepath: &str,
+        degree: usize,
+        order: usize,
+        gunzipped: bool,
+    ) -> Result<Self, NyxError> {
+        let data_as_str = read_to_string(filepath, gunzipped)?;
+        let unreadable = |lno: usize, msg: &str| NyxError::FileUnreadable {
+            msg: format!("ICGEM file {filepath}:{}: {msg}", lno + 1),
+        };
+        let parse_f64 = |lno: usize, item: &str| {
+            f64::from_str(&item.replace(['D', 'd'], "E"))
+                .map_err(|_| unreadable(lno, &format!("could not parse `{item}`")))
+        };
+
+        let mut lines = data_as_str.lines().enumerate();
+
+        // Parse the header
+        let mut model_name = String::new();
+        let mut gm = None;
+        let mut radius = None;
+        let mut tide_system = None;
+        let mut sigma_columns = 2;
+        let mut normalized = true;
+        let mut icgem2 = false;
+        let mut end_of_head = false;
+        for (lno, line) in lines.by_ref() {
+            let mut tokens = line.split_whitespace();
+            let key = match tokens.next() {
+                Some(key) => key,
+                None => continue,
+            };
+            let value = tokens.next();
+            match (key, value) {
+                ("end_of_head", _) => {
+                    end_of_head = true;
+                    break;
+                }
+                ("modelname", Some(value)) => model_name = value.to_string(),
+                (key, Some(value)) if key.ends_with("gravity_constant") => {
+                    gm = Some(parse_f64(lno, value)? * 1e-9)
+                }
+                ("radius", Some(value)) => radius = Some(parse_f64(lno, value)? * 1e-3),
+                ("tide_system", Some(value)) => tide_system = Some(value.to_string()),
+                ("norm", Some(value)) => normalized = value != "unnormalized",
+                ("format", Some(value)) => icgem2 = value.starts_with("icgem2"),
+                ("errors", Some(value)) => {
+                    sigma_columns = match value {
+                        "no" => 0,
+                        "calibrated_and_formal" => 4,
+                        _ => 2,
+                    }
+                }
+                _ => continue,
+            }
+        }
+        if !end_of_head {
+            return Err(NyxError::FileUnreadable {
+                msg: format!("ICGEM file {filepath}: missing `end_of_head`"),
+            });
+        }
+        let (gm_km3_s2, radius_km) = match (gm, radius) {
+            (Some(gm), Some(radius)) => (gm, radius),
+            _ => {
+                return Err(NyxError::FileUnreadable {
+                    msg: format!(
+                        "ICGEM file {filepath}: missing gravity constant or reference radius"
+                    ),
+                })
+            }
+        };
+
+        let parse_epoch = |lno: usize, item: &str| -> Result<Epoch, NyxError> {
+            // Epochs are formatted as yyyymmdd or yyyymmdd.hhmm
+            let (date, time) = item.split_once('.').unwrap_or((item, ""));
+            let field = |s: Option<&str>| s.and_then(|s| s.parse::<u8>().ok());
+            if date.len() != 8 {
+                return Err(unreadable(lno, &format!("could not parse epoch `{item}`")));
+            }
+            let year = date[..4]
+                .parse::<i32>()
+                .map_err(|_| unreadable(lno, &format!("could not parse epoch `{item}`")))?;
+            let (month, day) = match (field(date.get(4..6)), field(date.get(6..8))) {
+                (Some(month), Some(day)) => (month, day),
+                _ => return Err(unreadable(lno, &format!("could not parse epoch `{item}`"))),
+            };
+            let hour = field(time.get(..2)).unwrap_or(0);
+            let minute = field(time.get(2..4)).unwrap_or(0);
+            Ok(Epoch::from_gregorian_utc(
+                year, month, day, hour, minute, 0, 0,
+            ))
+        };
+
+        let mut c_nm_mat = DMatrix::from_element(degree + 1, degree + 1, 0.0);
+        let mut s_nm_mat = DMatrix::from_element(degree + 1, degree + 1, 0.0);
+        let mut max_degree: usize = 0;
+        let mut max_order: usize = 0;
+        let mut terms = Vec::new();
+        // Reference epochs of the `gfct` coefficients of ICGEM 1.0 files, which are also those of their time-variable terms
+        let mut reference_epochs = HashMap::new();
+
+        for (lno, line) in lines {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let key = match tokens.first() {
+                Some(key) => *key,
+                None => continue,
+            };
+            if !matches!(key, "gfc" | "gfct" | "trnd" | "dot" | "asin" | "acos") {
+                continue;
+            }
+            // Index of the first column after the coefficients and their standard deviations
+            let extra = 5 + sigma_columns;
+            let expected_extra = match (key, icgem2) {
+                ("gfc", _) | ("trnd" | "dot", false) => 0,
+                ("gfct", false) | ("asin" | "acos", false) => 1,
+                ("gfct" | "trnd" | "dot", true) => 2,
+                _ => 3,
+            };
+            if tokens.len() < extra + expected_extra {
+                return Err(unreadable(lno, &format!("missing columns for `{key}`")));
+            }
+
+            let cur_degree = usize::from_str(tokens[1])
+                .map_err(|_| unreadable(lno, &format!("could not parse degree `{}`", tokens[1])))?;
+            let cur_order = usize::from_str(tokens[2])
+                .map_err(|_| unreadable(lno, &format!("could not parse order `{}`", tokens[2])))?;
+            if cur_degree > degree || cur_order > order {
+                continue;
+            }
+            let mut c_nm = parse_f64(lno, tokens[3])?;
+            let mut s_nm = parse_f64(lno, tokens[4])?;
+            if !normalized {
+                let factor = normalization_factor(cur_degree, cur_order);
+                c_nm /= factor;
+                s_nm /= factor;
+            }
+
+            let static_coefficient = key == "gfc" || (key == "gfct" && !icgem2);
+            if static_coefficient {
+                c_nm_mat[(cur_degree, cur_order)] = c_nm;
+                s_nm_mat[(cur_degree, cur_order)] = s_nm;
+                max_degree = max_degree.max(cur_degree);
+                max_order = max_order.max(cur_order);
+                if key == "gfct" {
+                    reference_epochs
+                        .insert((cur_degree, cur_order), parse_epoch(lno, tokens[extra])?);
+                }
+                continue;
+            }
+
+            let (reference, validity, period_idx) = if icgem2 {
+                let start = parse_epoch(lno, tokens[extra])?;
+                let end = parse_epoch(lno, tokens[extra + 1])?;
+                (start, Some((start, end)), extra + 2)
+            } else {
+                let reference =
+                    *reference_epochs
+                        .get(&(cur_degree, cur_order))
+                        .ok_or_else(|| {
+                            unreadable(lno, &format!("`{key}` without a preceding `gfct`"))
+                        })?;
+                (reference, None, extra)
+            };
+            let variation = match key {
+                "gfct" => TimeVariation::Offset,
+                "trnd" | "dot" => TimeVariation::Trend,
+                "asin" => TimeVariation::Sine {
+                    period_years: parse_f64(lno, tokens[period_idx])?,
+                },
+                _ => TimeVariation::Cosine {
+                    period_years: parse_f64(lno, tokens[period_idx])?,
+                },
+            };
+            terms.push(TimeVariableTerm {
+                degree: cur_degree,
+                order: cur_order,
+                c_nm,
+                s_nm,
+                variation,
+                reference,
+                validity,
+            });
+        }
+
+        if max_degree < degree || max_order < order {
+            warn!(
+                "{filepath} only contained (degree, order) of ({max_degree}, {max_order}) instead of requested ({degree}, {order})",
+            );
+        } else {
+            info!("{filepath} loaded with (degree, order) = ({degree}, {order})");
+        }
+
+        Ok(Self {
+            model_name,
+            gm_km3_s2,
+            radius_km,
+            tide_system,
+            stor: HarmonicsMem {
+                degree: max_degree,
+                order: max_order,
+                c_nm: c_nm_mat,
+                s_nm: s_nm_mat,
+            },
+            time_variable: TimeVariableGravity { terms },
+        })
+    }
 }
 
 #[test]
@@ -478,3 +769,127 @@ fn test_load_harmonic_files() {
     HarmonicsMem::from_shadr("data/Luna_jggrx_1500e_sha.tab.gz", 1500, 1500, true)
         .expect("could not load jggrx");
 }
+
+#[test]
+fn test_load_gfc() {
+    use crate::cosmic::{Cosm, Orbit};
+    use crate::dynamics::{AccelModel, Harmonics};
+    use std::env::temp_dir;
+    use std::sync::Arc;
+
+    let cosm = Arc::new(Cosm::fixed_planets());
+    let itrf = cosm.frame("Earth ITRF");
+
+    // ICGEM 1.0 field with time-variable terms referenced to the epoch of the `gfct` coefficient
+    let header = |name: &str, gm: f64, radius: f64| {
+        format!("product_type gravity_field\nmodelname {name}\nearth_gravity_constant {gm:e}\nradius {radius:e}\nmax_degree 3\nnorm fully_normalized\ntide_system tide_free\nerrors formal\nend_of_head\nkey L M C S sigC sigS\n")
+    };
+    let gfc_v1 = temp_dir().join("nyx_ut_icgem1.gfc");
+    std::fs::write(
+        &gfc_v1,
+        header("TEST-1", itrf.gm() * 1e9, itrf.equatorial_radius() * 1e3)
+            + "gfc 0 0 1.0 0.0 0.0 0.0\n\
+               gfct 2 0 -4.84165D-04 0.0 1e-12 0.0 20050101.0000\n\
+               dot 2 0 1.0D-11 0.0 0.0 0.0\n\
+               acos 2 0 2.0E-11 0.0 0.0 0.0 0.5\n\
+               gfc 2 2 2.43938E-06 -1.40027E-06 0.0 0.0\n\
+               gfc 3 0 9.57E-07 0.0 0.0 0.0\n",
+    )
+    .unwrap();
+
+    let field = GfcField::load(gfc_v1.to_str().unwrap(), 2, 2, false).unwrap();
+    assert_eq!(field.model_name, "TEST-1");
+    assert_eq!(field.tide_system.as_deref(), Some("tide_free"));
+    assert!((field.gm_km3_s2 - itrf.gm()).abs() < 1e-6);
+    // The degree 3 is ignored
+    assert_eq!(field.stor.max_degree_n(), 2);
+    assert_eq!(field.stor.max_order_m(), 2);
+    assert_eq!(field.stor.cs_nm(2, 0), (-4.84165e-4, 0.0));
+    assert_eq!(field.stor.cs_nm(2, 2), (2.43938e-6, -1.40027e-6));
+    assert_eq!(field.time_variable.terms.len(), 2);
+    // One year and a quarter after the reference epoch
+    let epoch = Epoch::from_gregorian_utc_at_midnight(2005, 1, 1)
+        + crate::time::Unit::Day * (1.25 * 365.25);
+    let trend = field.time_variable.terms[0];
+    assert_eq!(trend.variation, TimeVariation::Trend);
+    // Within the leap second of 2005
+    assert!((trend.factor(epoch) - 1.25).abs() < 1e-7);
+    let cosine = field.time_variable.terms[1];
+    assert!((cosine.factor(epoch) + 1.0).abs() < 1e-6);
+    assert!(cosine.validity.is_none());
+
+    // The static part only
+    let stor = HarmonicsMem::from_gfc(gfc_v1.to_str().unwrap(), 2, 2, false).unwrap();
+    assert_eq!(stor.cs_nm(2, 0), field.stor.cs_nm(2, 0));
+
+    // The Earth field cannot be computed in the frame of another body
+    assert!(Harmonics::from_gfc(itrf, field.clone(), cosm.clone()).is_ok());
+    assert!(Harmonics::from_gfc(cosm.frame("IAU Moon"), field, cosm.clone()).is_err());
+
+    // ICGEM 2.0 unnormalized lunar field with piecewise time-variable terms, with the reference radius of the field twice that of the frame
+    let moon = cosm.frame("IAU Moon");
+    // As for the other formats, the harmonics stop before the highest degree read, hence the zero degree 3
+    let gfc_v2 = temp_dir().join("nyx_ut_icgem2.gfc");
+    std::fs::write(
+        &gfc_v2,
+        format!(
+            "format icgem2.0\nmodelname TEST-2\ngravity_constant {:e}\nradius {:e}\nnorm unnormalized\nerrors no\nend_of_head\n",
+            moon.gm() * 1e9,
+            2.0 * moon.equatorial_radius() * 1e3
+        ) + "gfc 2 0 -2.0e-4 0.0\n\
+             gfc 3 0 0.0 0.0\n\
+             gfct 2 2 2.0e-5 0.0 20000101.0000 20100101.0000\n\
+             trnd 2 2 1.0e-6 0.0 20000101.0000 20100101.0000\n\
+             asin 2 2 1.0e-6 0.0 20000101.0000 20100101.0000 0.5\n",
+    )
+    .unwrap();
+    let field = GfcField::load(gfc_v2.to_str().unwrap(), 10, 10, false).unwrap();
+    assert!((field.stor.cs_nm(2, 0).0 + 2.0e-4 / 5f64.sqrt()).abs() < 1e-18);
+    assert_eq!(field.time_variable.terms.len(), 3);
+    let offset = field.time_variable.terms[0];
+    assert!((offset.c_nm - 2.0e-5 / (5f64 / 12.0).sqrt()).abs() < 1e-18);
+    assert_eq!(
+        offset.factor(Epoch::from_gregorian_utc_at_midnight(2005, 1, 1)),
+        1.0
+    );
+    assert_eq!(
+        offset.factor(Epoch::from_gregorian_utc_at_midnight(2010, 1, 1)),
+        0.0
+    );
+
+    // The coefficients are scaled to the reference radius of the frame
+    let c20 = field.stor.cs_nm(2, 0).0;
+    let harmonics = Harmonics::from_gfc(moon, field, cosm.clone()).unwrap();
+    let scaled = Harmonics::from_stor(moon, HarmonicsMem::from_j2(4.0 * c20), cosm.clone());
+    let epoch = Epoch::from_gregorian_utc_at_midnight(2015, 1, 1);
+    let orbit = Orbit::keplerian(
+        2_000.0,
+        0.01,
+        80.0,
+        30.0,
+        45.0,
+        60.0,
+        epoch,
+        cosm.frame("Moon J2000"),
+    );
+    let accel = harmonics.eom(&orbit).unwrap();
+    assert!((accel - scaled.eom(&orbit).unwrap()).norm() < 1e-15 * accel.norm().max(1e-3));
+    // During the validity of the time-variable terms, the C22 is corrected
+    let orbit = Orbit::keplerian(
+        2_000.0,
+        0.01,
+        80.0,
+        30.0,
+        45.0,
+        60.0,
+        Epoch::from_gregorian_utc_at_midnight(2005, 1, 1),
+        cosm.frame("Moon J2000"),
+    );
+    assert!((harmonics.eom(&orbit).unwrap() - scaled.eom(&orbit).unwrap()).norm() > 1e-12);
+
+    // Missing header items are reported
+    let invalid = temp_dir().join("nyx_ut_invalid.gfc");
+    std::fs::write(&invalid, "modelname TEST\nend_of_head\ngfc 2 0 1.0 0.0\n").unwrap();
+    assert!(GfcField::load(invalid.to_str().unwrap(), 2, 2, false).is_err());
+    assert!(GfcField::load("not_a_file.gfc", 2, 2, false).is_err());
+}
@@ -45,9 +45,13 @@ use typed_builder::TypedBuilder;
 use self::orbit::OrbitSerde;
 use crate::cosmic::{Cosm, Frame};
 
+/// Handles reading of SPICE binary PCK (BPC) orientation files
+pub mod bpc;
 /// Handles writing to an XYZV file
 pub mod cosmo;
 pub mod dynamics;
+/// Handles loading of the IERS Earth orientation parameters
+pub mod eop;
 pub mod estimate;
 /// Handles reading from frames defined in input files
 pub mod frame_serde;
@@ -55,6 +59,14 @@ pub mod frame_serde;
 pub mod gravity;
 pub mod matrices;
 pub mod orbit;
+/// Handles loading of polyhedral shape models of small bodies from OBJ and PLY files
+pub mod shape;
+/// Handles loading of the CelesTrak space weather data (solar flux and geomagnetic indices)
+pub mod space_weather;
+/// Handles reading of SPICE SPK (BSP) ephemeris files
+pub mod spk;
+/// Handles parsing of two-line element sets (TLE)
+pub mod tle;
 pub mod tracking_data;
 pub mod trajectory_data;
 
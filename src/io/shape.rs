@@ -0,0 +1,487 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::linalg::Vector3;
+use crate::NyxError;
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// A closed triangular shape model of a small body, with vertices in km expressed in the body fixed frame.
+///
+/// The faces are always oriented such that their normals point outside of the body.
+#[derive(Clone, Debug)]
+pub struct ShapeModel {
+    vertices: Vec<Vector3<f64>>,
+    faces: Vec<[usize; 3]>,
+}
+
+impl ShapeModel {
+    /// Initializes a new shape model from its vertices (in km) and the indices of the vertices of each triangular face.
+    ///
+    /// Returns an error if the faces do not form a closed polyhedron. If the faces are oriented clockwise, they are reordered to point outward.
+    pub fn new(vertices: Vec<Vector3<f64>>, faces: Vec<[usize; 3]>) -> Result<Self, NyxError> {
+        if faces.len() < 4 {
+            return Err(NyxError::LoadingError {
+                msg: format!("shape model needs at least four faces, got {}", faces.len()),
+            });
+        }
+        if let Some(idx) = faces.iter().flatten().find(|idx| **idx >= vertices.len()) {
+            return Err(NyxError::LoadingError {
+                msg: format!(
+                    "face vertex index {idx} out of bounds ({} vertices)",
+                    vertices.len()
+                ),
+            });
+        }
+
+        // Each directed edge must appear exactly once, and its reverse must also appear exactly once
+        let mut edges = HashSet::with_capacity(3 * faces.len());
+        for face in &faces {
+            for k in 0..3 {
+                let edge = (face[k], face[(k + 1) % 3]);
+                if !edges.insert(edge) {
+                    return Err(NyxError::LoadingError {
+                        msg: format!("edge {edge:?} is shared by more than two faces or the faces are not consistently oriented"),
+                    });
+                }
+            }
+        }
+        if let Some((i, j)) = edges.iter().find(|(i, j)| !edges.contains(&(*j, *i))) {
+            return Err(NyxError::LoadingError {
+                msg: format!("shape model is not closed: edge ({i}, {j}) belongs to a single face"),
+            });
+        }
+
+        let mut me = Self { vertices, faces };
+        if me.volume_km3() < 0.0 {
+            for face in &mut me.faces {
+                face.swap(1, 2);
+            }
+        }
+        Ok(me)
+    }
+
+    /// Loads a shape model from a Wavefront OBJ or a Stanford PLY file, depending on its extension
+    pub fn load(path: &str) -> Result<Self, NyxError> {
+        match Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .as_deref()
+        {
+            Some("obj") => Self::from_obj(path),
+            Some("ply") => Self::from_ply(path),
+            _ => Err(NyxError::LoadingError {
+                msg: format!("unknown shape model format of {path} (expected .obj or .ply)"),
+            }),
+        }
+    }
+
+    /// Loads a shape model from a Wavefront OBJ file, whose vertices must be in km.
+    ///
+    /// Only the vertices (`v`) and faces (`f`) are read, and polygonal faces are split into triangles.
+    pub fn from_obj(path: &str) -> Result<Self, NyxError> {
+        let contents = read_to_string(path)?;
+
+        let mut vertices = Vec::new();
+        let mut faces = Vec::new();
+        for (lno, line) in contents.lines().enumerate() {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => vertices.push(parse_vertex(tokens, path, lno)?),
+                Some("f") => {
+                    let mut polygon = Vec::with_capacity(3);
+                    for token in tokens {
+                        // Faces may also reference the texture and normal indices, e.g. `f 1/1/1 2/2/2 3/3/3`
+                        let idx = token
+                            .split('/')
+                            .next()
+                            .and_then(|idx| idx.parse::<i64>().ok())
+                            .ok_or_else(|| unreadable(path, lno, "invalid face index"))?;
+                        // OBJ indices start at 1, and negative indices are relative to the last vertex
+                        let idx = if idx < 0 {
+                            vertices.len() as i64 + idx
+                        } else {
+                            idx - 1
+                        };
+                        if idx < 0 {
+                            return Err(unreadable(path, lno, "invalid face index"));
+                        }
+                        polygon.push(idx as usize);
+                    }
+                    triangulate(&polygon, &mut faces, path, lno)?;
+                }
+                _ => continue,
+            }
+        }
+
+        Self::new(vertices, faces)
+    }
+
+    /// Loads a shape model from an ASCII Stanford PLY file, whose vertices must be in km.
+    ///
+    /// Only the `vertex` and `face` elements are read, and polygonal faces are split into triangles.
+    pub fn from_ply(path: &str) -> Result<Self, NyxError> {
+        let contents = read_to_string(path)?;
+        let mut lines = contents.lines().enumerate();
+
+        if lines.next().map(|(_, line)| line.trim()) != Some("ply") {
+            return Err(unreadable(path, 0, "missing `ply` magic number"));
+        }
+
+        // Parse the header: the name, count and property names of each element, in the order of the body
+        let mut elements: Vec<(String, usize, Vec<String>)> = Vec::new();
+        loop {
+            let (lno, line) = lines
+                .next()
+                .ok_or_else(|| unreadable(path, 0, "missing `end_header`"))?;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.first() {
+                Some(&"format") => {
+                    if tokens.get(1) != Some(&"ascii") {
+                        return Err(unreadable(path, lno, "only ASCII PLY files are supported"));
+                    }
+                }
+                Some(&"element") => {
+                    let count = tokens
+                        .get(2)
+                        .and_then(|count| count.parse().ok())
+                        .ok_or_else(|| unreadable(path, lno, "invalid element count"))?;
+                    elements.push((tokens[1].to_string(), count, Vec::new()));
+                }
+                Some(&"property") => {
+                    let name = tokens
+                        .last()
+                        .ok_or_else(|| unreadable(path, lno, "invalid property"))?;
+                    elements
+                        .last_mut()
+                        .ok_or_else(|| unreadable(path, lno, "property without an element"))?
+                        .2
+                        .push(name.to_string());
+                }
+                Some(&"end_header") => break,
+                _ => continue,
+            }
+        }
+
+        let mut vertices = Vec::new();
+        let mut faces = Vec::new();
+        for (name, count, properties) in &elements {
+            for _ in 0..*count {
+                let (lno, line) = lines
+                    .next()
+                    .ok_or_else(|| unreadable(path, 0, "unexpected end of file"))?;
+                let tokens: Vec<&str> = line.split_whitespace().collect();
+                match name.as_str() {
+                    "vertex" => {
+                        let mut xyz = Vector3::zeros();
+                        for (i, axis) in ["x", "y", "z"].iter().enumerate() {
+                            xyz[i] = properties
+                                .iter()
+                                .position(|prop| prop == axis)
+                                .and_then(|pos| tokens.get(pos))
+                                .and_then(|val| val.parse().ok())
+                                .ok_or_else(|| unreadable(path, lno, "invalid vertex"))?;
+                        }
+                        vertices.push(xyz);
+                    }
+                    "face" => {
+                        let polygon = tokens
+                            .iter()
+                            .skip(1)
+                            .map(|idx| idx.parse::<usize>())
+                            .collect::<Result<Vec<usize>, _>>()
+                            .map_err(|_| unreadable(path, lno, "invalid face index"))?;
+                        if tokens.first().and_then(|n| n.parse().ok()) != Some(polygon.len()) {
+                            return Err(unreadable(path, lno, "invalid face vertex count"));
+                        }
+                        triangulate(&polygon, &mut faces, path, lno)?;
+                    }
+                    _ => continue,
+                }
+            }
+        }
+
+        Self::new(vertices, faces)
+    }
+
+    /// Returns the vertices of this shape model, in km
+    pub fn vertices(&self) -> &[Vector3<f64>] {
+        &self.vertices
+    }
+
+    /// Returns the vertex indices of each face of this shape model, ordered counter-clockwise when seen from outside
+    pub fn faces(&self) -> &[[usize; 3]] {
+        &self.faces
+    }
+
+    /// Returns the three vertices of the provided face
+    pub fn face_vertices(&self, face: usize) -> [Vector3<f64>; 3] {
+        let [i, j, k] = self.faces[face];
+        [self.vertices[i], self.vertices[j], self.vertices[k]]
+    }
+
+    /// Returns the volume enclosed by this shape model, in km^3
+    pub fn volume_km3(&self) -> f64 {
+        (0..self.faces.len())
+            .map(|f| {
+                let [v1, v2, v3] = self.face_vertices(f);
+                v1.dot(&v2.cross(&v3))
+            })
+            .sum::<f64>()
+            / 6.0
+    }
+
+    /// Returns the center of mass of this shape model assuming a constant density, in km
+    pub fn centroid(&self) -> Vector3<f64> {
+        let mut weighted = Vector3::zeros();
+        let mut volume = 0.0;
+        for f in 0..self.faces.len() {
+            let [v1, v2, v3] = self.face_vertices(f);
+            // Signed volume of the tetrahedron formed by the face and the origin
+            let tetra = v1.dot(&v2.cross(&v3));
+            weighted += tetra * (v1 + v2 + v3) / 4.0;
+            volume += tetra;
+        }
+        weighted / volume
+    }
+
+    /// Returns the solid angle subtended by the provided face as seen from the provided point, in steradians (van Oosterom and Strackee, 1983).
+    ///
+    /// The sign is positive if the point is behind the face.
+    pub fn face_solid_angle(&self, face: usize, point: &Vector3<f64>) -> f64 {
+        let [v1, v2, v3] = self.face_vertices(face);
+        let (r1, r2, r3) = (v1 - point, v2 - point, v3 - point);
+        let (n1, n2, n3) = (r1.norm(), r2.norm(), r3.norm());
+        2.0 * r1
+            .dot(&r2.cross(&r3))
+            .atan2(n1 * n2 * n3 + n1 * r2.dot(&r3) + n2 * r3.dot(&r1) + n3 * r1.dot(&r2))
+    }
+
+    /// Returns the solid angle subtended by the whole shape as seen from the provided point, i.e. 4π inside the body and zero outside
+    pub fn solid_angle(&self, point: &Vector3<f64>) -> f64 {
+        (0..self.faces.len())
+            .map(|f| self.face_solid_angle(f, point))
+            .sum()
+    }
+
+    /// Returns whether the provided point (in km in the body fixed frame) is inside this shape model
+    pub fn contains(&self, point: &Vector3<f64>) -> bool {
+        self.solid_angle(point) > 2.0 * PI
+    }
+
+    /// Returns the distance from the provided point to the surface of this shape model, in km, which is negative inside the body
+    pub fn signed_distance_km(&self, point: &Vector3<f64>) -> f64 {
+        let distance = (0..self.faces.len())
+            .map(|f| {
+                let [a, b, c] = self.face_vertices(f);
+                (closest_point_on_triangle(point, &a, &b, &c) - point).norm()
+            })
+            .fold(f64::INFINITY, f64::min);
+        if self.contains(point) {
+            -distance
+        } else {
+            distance
+        }
+    }
+}
+
+impl fmt::Display for ShapeModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "shape model with {} vertices and {} faces",
+            self.vertices.len(),
+            self.faces.len()
+        )
+    }
+}
+
+/// Returns the point of the triangle ABC closest to P (Ericson, Real-Time Collision Detection, section 5.1.5)
+fn closest_point_on_triangle(
+    p: &Vector3<f64>,
+    a: &Vector3<f64>,
+    b: &Vector3<f64>,
+    c: &Vector3<f64>,
+) -> Vector3<f64> {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(&ap);
+    let d2 = ac.dot(&ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return *a;
+    }
+
+    let bp = p - b;
+    let d3 = ab.dot(&bp);
+    let d4 = ac.dot(&bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return *b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + d1 / (d1 - d3) * ab;
+    }
+
+    let cp = p - c;
+    let d5 = ab.dot(&cp);
+    let d6 = ac.dot(&cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return *c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + d2 / (d2 - d6) * ac;
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        return b + (d4 - d3) / ((d4 - d3) + (d5 - d6)) * (c - b);
+    }
+
+    // Inside the face
+    let denom = 1.0 / (va + vb + vc);
+    a + ab * vb * denom + ac * vc * denom
+}
+
+fn read_to_string(path: &str) -> Result<String, NyxError> {
+    fs::read_to_string(path).map_err(|e| NyxError::FileUnreadable {
+        msg: format!("{path}: {e}"),
+    })
+}
+
+fn unreadable(path: &str, lno: usize, msg: &str) -> NyxError {
+    NyxError::FileUnreadable {
+        msg: format!("{path}:{}: {msg}", lno + 1),
+    }
+}
+
+fn parse_vertex<'a, I: Iterator<Item = &'a str>>(
+    tokens: I,
+    path: &str,
+    lno: usize,
+) -> Result<Vector3<f64>, NyxError> {
+    let xyz = tokens
+        .take(3)
+        .map(|val| val.parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|_| unreadable(path, lno, "invalid vertex"))?;
+    if xyz.len() != 3 {
+        return Err(unreadable(path, lno, "invalid vertex"));
+    }
+    Ok(Vector3::new(xyz[0], xyz[1], xyz[2]))
+}
+
+/// Splits the provided polygon into a fan of triangles
+fn triangulate(
+    polygon: &[usize],
+    faces: &mut Vec<[usize; 3]>,
+    path: &str,
+    lno: usize,
+) -> Result<(), NyxError> {
+    if polygon.len() < 3 {
+        return Err(unreadable(path, lno, "face with fewer than three vertices"));
+    }
+    for k in 1..polygon.len() - 1 {
+        faces.push([polygon[0], polygon[k], polygon[k + 1]]);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod ut_shape {
+    use super::*;
+    use std::env::temp_dir;
+
+    /// A cube of 2 km of side centered on the origin, written as quads
+    const CUBE_OBJ: &str = "# cube
+v -1 -1 -1
+v 1 -1 -1
+v 1 1 -1
+v -1 1 -1
+v -1 -1 1
+v 1 -1 1
+v 1 1 1
+v -1 1 1
+f 1 4 3 2
+f 5 6 7 8
+f 1 2 6 5
+f 2 3 7 6
+f 3 4 8 7
+f 4 1 5 8
+";
+
+    #[test]
+    fn test_shape_obj_ply() {
+        let obj_path = temp_dir().join("nyx_ut_cube.obj");
+        fs::write(&obj_path, CUBE_OBJ).unwrap();
+        let cube = ShapeModel::load(obj_path.to_str().unwrap()).unwrap();
+        assert_eq!(cube.vertices().len(), 8);
+        assert_eq!(cube.faces().len(), 12);
+        assert!((cube.volume_km3() - 8.0).abs() < 1e-12);
+        assert!(cube.centroid().norm() < 1e-12);
+
+        // The same cube, clockwise and in PLY format, is reoriented outward
+        let ply_path = temp_dir().join("nyx_ut_cube.ply");
+        let mut ply = "ply\nformat ascii 1.0\nelement vertex 8\nproperty float x\nproperty float y\nproperty float z\nelement face 6\nproperty list uchar int vertex_indices\nend_header\n".to_string();
+        for line in CUBE_OBJ.lines() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens[0] {
+                "v" => ply.push_str(&format!("{}\n", tokens[1..].join(" "))),
+                "f" => {
+                    let idx: Vec<String> = tokens[1..]
+                        .iter()
+                        .rev()
+                        .map(|i| format!("{}", i.parse::<usize>().unwrap() - 1))
+                        .collect();
+                    ply.push_str(&format!("4 {}\n", idx.join(" ")));
+                }
+                _ => {}
+            }
+        }
+        fs::write(&ply_path, ply).unwrap();
+        let cube_ply = ShapeModel::load(ply_path.to_str().unwrap()).unwrap();
+        assert!((cube_ply.volume_km3() - 8.0).abs() < 1e-12);
+
+        // Inside and outside checks
+        for shape in [&cube, &cube_ply] {
+            assert!((shape.solid_angle(&Vector3::new(0.2, -0.3, 0.5)) - 4.0 * PI).abs() < 1e-12);
+            assert!(shape.solid_angle(&Vector3::new(3.0, 1.0, 0.0)).abs() < 1e-12);
+            assert!(shape.contains(&Vector3::zeros()));
+            assert!(!shape.contains(&Vector3::new(0.0, 0.0, 1.01)));
+            assert!((shape.signed_distance_km(&Vector3::zeros()) + 1.0).abs() < 1e-12);
+            assert!((shape.signed_distance_km(&Vector3::new(3.0, 0.5, 0.0)) - 2.0).abs() < 1e-12);
+            assert!(
+                (shape.signed_distance_km(&Vector3::new(2.0, 2.0, 2.0)) - 3f64.sqrt()).abs()
+                    < 1e-12
+            );
+        }
+
+        // An open shape is rejected
+        let open_path = temp_dir().join("nyx_ut_open_cube.obj");
+        fs::write(&open_path, CUBE_OBJ.replace("f 4 1 5 8\n", "")).unwrap();
+        assert!(ShapeModel::from_obj(open_path.to_str().unwrap()).is_err());
+        assert!(ShapeModel::load("shape.stl").is_err());
+    }
+}
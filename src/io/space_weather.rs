@@ -0,0 +1,273 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::errors::NyxError;
+use crate::time::{Epoch, Unit};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::read_to_string;
+
+/// Solar flux and geomagnetic indices used by the thermospheric density models.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SolarIndices {
+    /// Observed 10.7 cm solar radio flux of the previous day, in solar flux units
+    pub f107: f64,
+    /// 81 day average of the observed 10.7 cm solar radio flux, centered on the day, in solar flux units
+    pub f107a: f64,
+    /// Planetary geomagnetic index ap, using the three hourly value when available
+    pub ap: f64,
+}
+
+impl Default for SolarIndices {
+    /// Moderate solar and geomagnetic activity
+    fn default() -> Self {
+        Self {
+            f107: 150.0,
+            f107a: 150.0,
+            ap: 15.0,
+        }
+    }
+}
+
+/// A single day of space weather data, as published by CelesTrak.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SpaceWeatherRecord {
+    /// Modified Julian Date (UTC) of the start of this day
+    pub mjd_utc: f64,
+    /// Observed 10.7 cm solar radio flux, in solar flux units
+    pub f107_obs: f64,
+    /// 81 day average of the observed 10.7 cm solar radio flux, centered on this day
+    pub f107_obs_ctr81: f64,
+    /// Daily average of the planetary geomagnetic index ap
+    pub ap_avg: f64,
+    /// Three hourly planetary geomagnetic index ap, set to the daily average if unavailable (e.g. in the predictions)
+    pub ap: [f64; 8],
+}
+
+/// Daily space weather data loaded from a CelesTrak file (e.g. `SW-All.csv`), with optional constant indices used outside of these data.
+#[derive(Clone, Default, PartialEq)]
+pub struct SpaceWeather {
+    /// Records sorted by date, one per day
+    pub records: Vec<SpaceWeatherRecord>,
+    /// Indices used outside of the records, e.g. for design studies far in the future
+    pub fallback: Option<SolarIndices>,
+}
+
+impl SpaceWeather {
+    /// Constant solar flux and geomagnetic indices, e.g. for design studies
+    pub fn constant(indices: SolarIndices) -> Self {
+        Self {
+            records: Vec::new(),
+            fallback: Some(indices),
+        }
+    }
+
+    /// Loads the CelesTrak space weather CSV file (e.g. `SW-All.csv` or `SW-Last5Years.csv`).
+    pub fn from_celestrak_csv(filepath: &str) -> Result<Self, NyxError> {
+        let content = read_to_string(filepath).map_err(|e| NyxError::FileUnreadable {
+            msg: format!("{filepath}: {e}"),
+        })?;
+        Self::from_celestrak_csv_str(&content)
+    }
+
+    /// Parses the content of a CelesTrak space weather CSV file.
+    /// Lines without the observed solar flux or its centered 81 day average (e.g. the end of the monthly predictions) are ignored.
+    pub fn from_celestrak_csv_str(content: &str) -> Result<Self, NyxError> {
+        let mut rdr = csv::Reader::from_reader(content.as_bytes());
+        let headers = rdr
+            .headers()
+            .map_err(|e| NyxError::FileUnreadable {
+                msg: format!("space weather header: {e}"),
+            })?
+            .clone();
+        let column = |name: &str| -> Result<usize, NyxError> {
+            headers
+                .iter()
+                .position(|header| header.trim() == name)
+                .ok_or_else(|| NyxError::FileUnreadable {
+                    msg: format!("space weather file has no `{name}` column"),
+                })
+        };
+
+        let date_col = column("DATE")?;
+        let f107_col = column("F10.7_OBS")?;
+        let f107a_col = column("F10.7_OBS_CENTER81")?;
+        let ap_avg_col = column("AP_AVG")?;
+        let ap_cols = (1..=8)
+            .map(|i| column(&format!("AP{i}")))
+            .collect::<Result<Vec<usize>, NyxError>>()?;
+
+        let mut records = Vec::new();
+        for row in rdr.records() {
+            let row = row.map_err(|e| NyxError::FileUnreadable {
+                msg: format!("space weather record: {e}"),
+            })?;
+            let field = |col: usize| -> Option<f64> { row.get(col)?.trim().parse::<f64>().ok() };
+
+            let (Some(f107_obs), Some(f107_obs_ctr81)) = (field(f107_col), field(f107a_col)) else {
+                continue;
+            };
+
+            let date = row.get(date_col).unwrap_or_default().trim();
+            let ymd: Vec<Option<u32>> = date.split('-').map(|s| s.parse().ok()).collect();
+            let epoch = match ymd[..] {
+                [Some(year), Some(month), Some(day)] => {
+                    Epoch::maybe_from_gregorian_utc(year as i32, month as u8, day as u8, 0, 0, 0, 0)
+                        .map_err(|e| NyxError::FileUnreadable {
+                            msg: format!("space weather date `{date}`: {e}"),
+                        })?
+                }
+                _ => {
+                    return Err(NyxError::FileUnreadable {
+                        msg: format!("space weather date `{date}` is not YYYY-MM-DD"),
+                    })
+                }
+            };
+
+            let ap_values: Vec<Option<f64>> = ap_cols.iter().map(|col| field(*col)).collect();
+            let ap_avg = match field(ap_avg_col) {
+                Some(ap_avg) => ap_avg,
+                None => {
+                    let known: Vec<f64> = ap_values.iter().flatten().copied().collect();
+                    if known.is_empty() {
+                        continue;
+                    }
+                    known.iter().sum::<f64>() / known.len() as f64
+                }
+            };
+            let mut ap = [ap_avg; 8];
+            for (ap_3h, value) in ap.iter_mut().zip(ap_values) {
+                if let Some(value) = value {
+                    *ap_3h = value;
+                }
+            }
+
+            records.push(SpaceWeatherRecord {
+                mjd_utc: epoch.to_mjd_utc_days().round(),
+                f107_obs,
+                f107_obs_ctr81,
+                ap_avg,
+                ap,
+            });
+        }
+
+        if records.is_empty() {
+            return Err(NyxError::FileUnreadable {
+                msg: "no space weather record found".to_string(),
+            });
+        }
+
+        records.sort_by(|a, b| a.mjd_utc.partial_cmp(&b.mjd_utc).unwrap());
+        records.dedup_by(|a, b| a.mjd_utc == b.mjd_utc);
+
+        Ok(Self {
+            records,
+            fallback: None,
+        })
+    }
+
+    /// Sets the indices used outside of the records
+    pub fn with_fallback(mut self, indices: SolarIndices) -> Self {
+        self.fallback = Some(indices);
+        self
+    }
+
+    /// Returns the record of the day of the provided Modified Julian Date (UTC), if any
+    fn record(&self, mjd_utc: f64) -> Option<&SpaceWeatherRecord> {
+        let day = mjd_utc.floor();
+        self.records
+            .binary_search_by(|rec| rec.mjd_utc.partial_cmp(&day).unwrap())
+            .ok()
+            .map(|idx| &self.records[idx])
+    }
+
+    /// Returns the solar indices at the provided epoch, with the lags of the Jacchia models: the solar flux of the previous day and
+    /// the three hourly ap index 6.7 hours prior. The fallback indices are returned if the records do not cover these dates.
+    pub fn indices_at(&self, epoch: Epoch) -> Result<SolarIndices, NyxError> {
+        let mjd_utc = epoch.to_mjd_utc_days();
+        let ap_mjd_utc = (epoch - 6.7 * Unit::Hour).to_mjd_utc_days();
+
+        match (
+            self.record(mjd_utc),
+            self.record(mjd_utc - 1.0),
+            self.record(ap_mjd_utc),
+        ) {
+            (Some(today), Some(yesterday), Some(ap_day)) => {
+                let ap_slot = ((ap_mjd_utc - ap_day.mjd_utc) * 8.0).floor() as usize;
+                Ok(SolarIndices {
+                    f107: yesterday.f107_obs,
+                    f107a: today.f107_obs_ctr81,
+                    ap: ap_day.ap[ap_slot.min(7)],
+                })
+            }
+            _ => self.fallback.ok_or_else(|| NyxError::NoInterpolationData {
+                msg: format!("no space weather data at {epoch}"),
+            }),
+        }
+    }
+}
+
+impl fmt::Debug for SpaceWeather {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.records.first(), self.records.last()) {
+            (Some(first), Some(last)) => write!(
+                f,
+                "space weather from MJD {} to {} (fallback {:?})",
+                first.mjd_utc, last.mjd_utc, self.fallback
+            ),
+            _ => write!(f, "constant space weather {:?}", self.fallback),
+        }
+    }
+}
+
+#[cfg(test)]
+mod ut_space_weather {
+    use super::*;
+
+    const SW_CSV: &str = "DATE,BSRT,ND,KP1,KP2,KP3,KP4,KP5,KP6,KP7,KP8,KP_SUM,AP1,AP2,AP3,AP4,AP5,AP6,AP7,AP8,AP_AVG,CP,C9,ISN,F10.7_OBS,F10.7_ADJ,F10.7_DATA_TYPE,F10.7_OBS_CENTER81,F10.7_OBS_LAST81,F10.7_ADJ_CENTER81,F10.7_ADJ_LAST81
+2023-01-01,2585,1,20,13,17,10,7,3,7,13,90,7,5,6,4,3,2,3,5,4,0.2,1,113,143.0,138.2,OBS,175.6,165.2,171.1,160.9
+2023-01-02,2585,2,3,7,10,13,13,17,20,23,106,2,3,4,5,5,6,7,9,5,0.2,1,140,155.6,150.4,OBS,176.2,165.9,171.8,161.6
+2023-01-03,2585,3,,,,,,,,,,,,,,,,,,12,,,,,,PRD,,,,
+2023-01-04,2585,4,,,,,,,,,,,,,,,,,,10,,,,160.0,,PRD,170.0,,,";
+
+    #[test]
+    fn test_celestrak_space_weather() {
+        let sw = SpaceWeather::from_celestrak_csv_str(SW_CSV).unwrap();
+        // The third day has no solar flux
+        assert_eq!(sw.records.len(), 3);
+        assert_eq!(sw.records[2].ap, [10.0; 8]);
+
+        // 2023-01-02 at 12:00 UTC uses the flux of the first and the ap between 03:00 and 06:00 UTC
+        let epoch = Epoch::from_gregorian_utc_hms(2023, 1, 2, 12, 0, 0);
+        let indices = sw.indices_at(epoch).unwrap();
+        assert_eq!(indices.f107, 143.0);
+        assert_eq!(indices.f107a, 176.2);
+        assert_eq!(indices.ap, 3.0);
+
+        // The previous day is needed for the flux
+        let epoch = Epoch::from_gregorian_utc_hms(2023, 1, 1, 12, 0, 0);
+        assert!(sw.indices_at(epoch).is_err());
+        let sw = sw.with_fallback(SolarIndices::default());
+        assert_eq!(sw.indices_at(epoch).unwrap(), SolarIndices::default());
+
+        let constant = SpaceWeather::constant(SolarIndices::default());
+        assert_eq!(constant.indices_at(epoch).unwrap().ap, 15.0);
+
+        assert!(SpaceWeather::from_celestrak_csv_str("DATE,AP1\n2023-01-01,3").is_err());
+    }
+}
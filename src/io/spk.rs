@@ -0,0 +1,701 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::polyfit::hermite::hermite_eval;
+use crate::polyfit::lagrange::lagrange_eval;
+use crate::NyxError;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::Read;
+
+/// Length in bytes of a DAF record
+const DAF_RECORD_LEN: usize = 1024;
+/// Length in bytes of a DAF double precision word
+const DAF_DOUBLE_LEN: usize = 8;
+/// Number of double precision components in an SPK summary
+const SPK_ND: usize = 2;
+/// Number of integer components in an SPK summary
+const SPK_NI: usize = 6;
+
+/// NAIF ID of the J2000 orientation, the only orientation supported in SPK segments.
+pub const NAIF_J2000_FRAME_ID: i32 = 1;
+
+/// The data of an SPK segment, as stored in the kernel.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpkSegmentData {
+    /// Chebyshev polynomials over fixed length intervals, position only (type 2) or position and velocity (type 3).
+    Chebyshev {
+        /// Start of the first interval, in ET seconds past J2000
+        init_s: f64,
+        /// Length of each interval in seconds
+        interval_s: f64,
+        /// Number of doubles in each record, including the midpoint and the radius of the interval
+        record_size: usize,
+        /// Set to true if the velocity has its own set of coefficients (type 3)
+        with_velocity: bool,
+        /// All of the records, flattened
+        records: Vec<f64>,
+    },
+    /// Discrete states at unequal time steps, interpolated with Lagrange (type 9) or Hermite (type 13) polynomials.
+    Discrete {
+        /// Set to true if the states are interpolated with Hermite polynomials (type 13)
+        hermite: bool,
+        /// Number of states used in each interpolation
+        window_size: usize,
+        /// Epochs of each state, in ET seconds past J2000
+        epochs_s: Vec<f64>,
+        /// Position (km) and velocity (km/s) of each state
+        states: Vec<[f64; 6]>,
+    },
+}
+
+/// A segment of an SPK file, providing the state of a target with respect to a center over a time span.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpkSegment {
+    pub name: String,
+    /// NAIF ID of the target object
+    pub target_id: i32,
+    /// NAIF ID of the center object
+    pub center_id: i32,
+    /// NAIF ID of the orientation of the states
+    pub frame_id: i32,
+    /// SPK type of this segment
+    pub data_type: i32,
+    /// Start of the validity of this segment, in ET seconds past J2000
+    pub start_et_s: f64,
+    /// End of the validity of this segment, in ET seconds past J2000
+    pub end_et_s: f64,
+    pub data: SpkSegmentData,
+}
+
+impl SpkSegment {
+    /// Returns whether this segment provides data at the provided ET seconds past J2000
+    pub fn covers(&self, et_s: f64) -> bool {
+        et_s >= self.start_et_s && et_s <= self.end_et_s
+    }
+
+    /// Returns the position (km) and velocity (km/s) of the target with respect to the center at the provided ET seconds past J2000
+    pub fn evaluate(&self, et_s: f64) -> Result<[f64; 6], NyxError> {
+        if !self.covers(et_s) {
+            return Err(NyxError::NoInterpolationData {
+                msg: format!(
+                    "SPK segment `{}` covers {} to {} ET seconds, requested {et_s}",
+                    self.name, self.start_et_s, self.end_et_s
+                ),
+            });
+        }
+
+        self.data.interpolate(et_s)
+    }
+
+    /// Builds a segment where the target is fixed on the X axis of its center, at all times
+    #[cfg(test)]
+    pub(crate) fn fixed(target_id: i32, center_id: i32, x_km: f64) -> Self {
+        Self {
+            name: format!("{target_id} wrt {center_id}"),
+            target_id,
+            center_id,
+            frame_id: NAIF_J2000_FRAME_ID,
+            data_type: 2,
+            start_et_s: -1e10,
+            end_et_s: 1e10,
+            data: SpkSegmentData::Chebyshev {
+                init_s: -1e10,
+                interval_s: 2e10,
+                record_size: 5,
+                with_velocity: false,
+                records: vec![0.0, 1e10, x_km, 0.0, 0.0],
+            },
+        }
+    }
+
+    /// Builds a segment where the target moves along the Y axis of its center at a constant velocity, crossing the X axis at J2000
+    #[cfg(test)]
+    pub(crate) fn linear(target_id: i32, center_id: i32, x_km: f64, vy_km_s: f64) -> Self {
+        Self {
+            data: SpkSegmentData::Chebyshev {
+                init_s: -1e10,
+                interval_s: 2e10,
+                record_size: 8,
+                with_velocity: false,
+                records: vec![0.0, 1e10, x_km, 0.0, 0.0, vy_km_s * 1e10, 0.0, 0.0],
+            },
+            ..Self::fixed(target_id, center_id, x_km)
+        }
+    }
+}
+
+impl SpkSegmentData {
+    /// Interpolates the data at the provided ET seconds past J2000: the first three components are interpolated, and the last three are
+    /// their time derivatives (interpolated for the discrete data and the type 3 Chebyshev segments, and differentiated otherwise).
+    pub(crate) fn interpolate(&self, et_s: f64) -> Result<[f64; 6], NyxError> {
+        let mut state = [0.0; 6];
+
+        match self {
+            Self::Chebyshev {
+                init_s,
+                interval_s,
+                record_size,
+                with_velocity,
+                records,
+            } => {
+                let num_records = records.len() / record_size;
+                let index_f = ((et_s - init_s) / interval_s).floor();
+                if num_records == 0 || index_f < 0.0 || index_f as usize > num_records {
+                    return Err(NyxError::NoInterpolationData {
+                        msg: format!("no Chebyshev record for {et_s}"),
+                    });
+                }
+                // The very end of the segment is in the last record
+                let index = (index_f as usize).min(num_records - 1);
+                let record = &records[index * record_size..(index + 1) * record_size];
+                let (mid_s, radius_s) = (record[0], record[1]);
+                let components = if *with_velocity { 6 } else { 3 };
+                let coeff_count = (record_size - 2) / components;
+
+                let (t_k, dt_k) = chebyshev_basis((et_s - mid_s) / radius_s, coeff_count);
+
+                for i in 0..3 {
+                    let pos_coeffs = &record[2 + i * coeff_count..2 + (i + 1) * coeff_count];
+                    state[i] = dot(pos_coeffs, &t_k);
+                    state[i + 3] = if *with_velocity {
+                        let vel_coeffs =
+                            &record[2 + (i + 3) * coeff_count..2 + (i + 4) * coeff_count];
+                        dot(vel_coeffs, &t_k)
+                    } else {
+                        dot(pos_coeffs, &dt_k) / radius_s
+                    };
+                }
+            }
+            Self::Discrete {
+                hermite,
+                window_size,
+                epochs_s,
+                states,
+            } => {
+                let window = (*window_size).clamp(1, epochs_s.len());
+                let first = window_start(epochs_s, et_s, window);
+                // Offset the times by the start of the window for numerical conditioning
+                let xs: Vec<f64> = epochs_s[first..first + window]
+                    .iter()
+                    .map(|epoch_s| epoch_s - epochs_s[first])
+                    .collect();
+                let x = et_s - epochs_s[first];
+
+                for i in 0..3 {
+                    let ys: Vec<f64> = states[first..first + window].iter().map(|s| s[i]).collect();
+                    let ydots: Vec<f64> = states[first..first + window]
+                        .iter()
+                        .map(|s| s[i + 3])
+                        .collect();
+
+                    if *hermite {
+                        let (pos, vel) = hermite_eval(&xs, &ys, &ydots, x)?;
+                        state[i] = pos;
+                        state[i + 3] = vel;
+                    } else {
+                        state[i] = lagrange_eval(&xs, &ys, x)?;
+                        state[i + 3] = lagrange_eval(&xs, &ydots, x)?;
+                    }
+                }
+            }
+        }
+
+        Ok(state)
+    }
+}
+
+/// An SPK file (also known as a BSP) decoded in memory.
+///
+/// Supported segment types are 2 and 3 (Chebyshev), 9 (Lagrange) and 13 (Hermite), all in the J2000 orientation.
+/// Unsupported segments are skipped with a warning.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Spk {
+    /// Segments in the order of the file, i.e. the last segment has the highest priority
+    pub segments: Vec<SpkSegment>,
+}
+
+impl Spk {
+    /// Loads the provided SPK file
+    pub fn from_file(input_filename: &str) -> Result<Self, NyxError> {
+        let mut buf = Vec::new();
+        let mut f = File::open(input_filename).map_err(|e| NyxError::LoadingError {
+            msg: format!("{input_filename}: {e}"),
+        })?;
+        f.read_to_end(&mut buf)
+            .map_err(|e| NyxError::FileUnreadable {
+                msg: format!("{input_filename}: {e}"),
+            })?;
+
+        let spk = Self::from_buffer(&buf)?;
+        info!(
+            "{input_filename} loaded with {} SPK segments",
+            spk.segments.len()
+        );
+        Ok(spk)
+    }
+
+    /// Decodes the provided buffer as an SPK
+    pub fn from_buffer(buf: &[u8]) -> Result<Self, NyxError> {
+        let daf = DafReader::try_from_buffer(buf, "SPK", SPK_ND, SPK_NI)?;
+
+        let mut segments = Vec::new();
+        for summary in daf.summaries()? {
+            let name = summary.name;
+            let (target_id, center_id, frame_id, data_type) = (
+                summary.ints[0],
+                summary.ints[1],
+                summary.ints[2],
+                summary.ints[3],
+            );
+            let (begin_addr, end_addr) = (summary.ints[4] as usize, summary.ints[5] as usize);
+
+            if frame_id != NAIF_J2000_FRAME_ID {
+                warn!("skipping SPK segment `{name}`: frame {frame_id} is not J2000");
+                continue;
+            }
+
+            let data = match data_type {
+                2 | 3 => daf.chebyshev(begin_addr, end_addr, data_type == 3)?,
+                9 | 13 => daf.discrete(begin_addr, end_addr, data_type == 13)?,
+                _ => {
+                    warn!("skipping SPK segment `{name}`: type {data_type} is not supported");
+                    continue;
+                }
+            };
+
+            segments.push(SpkSegment {
+                name,
+                target_id,
+                center_id,
+                frame_id,
+                data_type,
+                start_et_s: summary.start_et_s,
+                end_et_s: summary.end_et_s,
+                data,
+            });
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+/// The summary of a segment of a DAF file, with two double precision components (the start and end times)
+pub(crate) struct DafSummary {
+    pub(crate) name: String,
+    pub(crate) start_et_s: f64,
+    pub(crate) end_et_s: f64,
+    /// Integer components, the last two being the initial and final addresses of the segment data
+    pub(crate) ints: Vec<i32>,
+}
+
+/// Reads the raw words of a DAF buffer
+pub(crate) struct DafReader<'a> {
+    buf: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> DafReader<'a> {
+    /// Checks the file record of the provided buffer, which must be a DAF of the provided kind (e.g. "SPK" or "PCK")
+    pub(crate) fn try_from_buffer(
+        buf: &'a [u8],
+        kind: &str,
+        expected_nd: usize,
+        expected_ni: usize,
+    ) -> Result<Self, NyxError> {
+        if buf.len() < DAF_RECORD_LEN {
+            return Err(NyxError::LoadingError {
+                msg: format!("{kind} buffer is shorter than one DAF record"),
+            });
+        }
+
+        let locidw = String::from_utf8_lossy(&buf[0..8]);
+        if !locidw.starts_with(&format!("DAF/{kind}")) && !locidw.starts_with("NAIF/DAF") {
+            return Err(NyxError::LoadingError {
+                msg: format!("not a {kind} file (ID word is `{locidw}`)"),
+            });
+        }
+
+        let big_endian = match String::from_utf8_lossy(&buf[88..96]).trim() {
+            "LTL-IEEE" => false,
+            "BIG-IEEE" => true,
+            fmt => {
+                return Err(NyxError::LoadingError {
+                    msg: format!("unsupported DAF binary format `{fmt}`"),
+                })
+            }
+        };
+
+        let daf = Self { buf, big_endian };
+
+        let nd = daf.i32_at(8)? as usize;
+        let ni = daf.i32_at(12)? as usize;
+        if nd != expected_nd || ni != expected_ni {
+            return Err(NyxError::LoadingError {
+                msg: format!("DAF has ND = {nd} and NI = {ni}, which is not a {kind}"),
+            });
+        }
+
+        Ok(daf)
+    }
+
+    /// Returns the summaries of all of the segments, in the order of the file
+    pub(crate) fn summaries(&self) -> Result<Vec<DafSummary>, NyxError> {
+        let nd = self.i32_at(8)? as usize;
+        let ni = self.i32_at(12)? as usize;
+        // Size of a summary in double precision words
+        let summary_size = nd + ni.div_ceil(2);
+
+        let mut summaries = Vec::new();
+        let mut record_no = self.i32_at(76)? as usize;
+
+        while record_no > 0 {
+            let record_start = (record_no - 1) * DAF_RECORD_LEN;
+            let next_record_no = self.f64_at(record_start)? as usize;
+            let num_summaries = self.f64_at(record_start + 2 * DAF_DOUBLE_LEN)? as usize;
+            // The name record immediately follows its summary record
+            let name_start = record_start + DAF_RECORD_LEN;
+            let name_len = summary_size * DAF_DOUBLE_LEN;
+
+            for sno in 0..num_summaries {
+                let summary_start =
+                    record_start + 3 * DAF_DOUBLE_LEN + sno * summary_size * DAF_DOUBLE_LEN;
+                let start_et_s = self.f64_at(summary_start)?;
+                let end_et_s = self.f64_at(summary_start + DAF_DOUBLE_LEN)?;
+                let ints_start = summary_start + nd * DAF_DOUBLE_LEN;
+                let ints = (0..ni)
+                    .map(|ino| self.i32_at(ints_start + 4 * ino))
+                    .collect::<Result<Vec<i32>, NyxError>>()?;
+
+                let name = self
+                    .buf
+                    .get(name_start + sno * name_len..name_start + (sno + 1) * name_len)
+                    .map(|bytes| {
+                        String::from_utf8_lossy(bytes)
+                            .trim_end_matches([' ', '\0'])
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+
+                summaries.push(DafSummary {
+                    name,
+                    start_et_s,
+                    end_et_s,
+                    ints,
+                });
+            }
+
+            if next_record_no == record_no {
+                // Corrupted file, avoid looping forever
+                break;
+            }
+            record_no = next_record_no;
+        }
+
+        Ok(summaries)
+    }
+
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N], NyxError> {
+        self.buf
+            .get(offset..offset + N)
+            .and_then(|bytes| <[u8; N]>::try_from(bytes).ok())
+            .ok_or_else(|| NyxError::FileUnreadable {
+                msg: format!("DAF ends before byte {}", offset + N),
+            })
+    }
+
+    fn f64_at(&self, offset: usize) -> Result<f64, NyxError> {
+        let bytes = self.bytes::<8>(offset)?;
+        Ok(if self.big_endian {
+            f64::from_be_bytes(bytes)
+        } else {
+            f64::from_le_bytes(bytes)
+        })
+    }
+
+    fn i32_at(&self, offset: usize) -> Result<i32, NyxError> {
+        let bytes = self.bytes::<4>(offset)?;
+        Ok(if self.big_endian {
+            i32::from_be_bytes(bytes)
+        } else {
+            i32::from_le_bytes(bytes)
+        })
+    }
+
+    /// Reads the double at the provided DAF address (addresses start at one)
+    fn f64_addr(&self, addr: usize) -> Result<f64, NyxError> {
+        if addr == 0 {
+            return Err(NyxError::FileUnreadable {
+                msg: "DAF addresses start at one".to_string(),
+            });
+        }
+        self.f64_at((addr - 1) * DAF_DOUBLE_LEN)
+    }
+
+    /// Reads the doubles between the provided DAF addresses, inclusive
+    fn f64_slice(&self, start_addr: usize, end_addr: usize) -> Result<Vec<f64>, NyxError> {
+        (start_addr..=end_addr)
+            .map(|addr| self.f64_addr(addr))
+            .collect()
+    }
+
+    /// Ensures that the segment between the provided addresses, inclusive, holds at least `min_len` doubles
+    fn check_segment_len(
+        begin_addr: usize,
+        end_addr: usize,
+        min_len: usize,
+    ) -> Result<(), NyxError> {
+        match end_addr.checked_sub(begin_addr) {
+            Some(span) if begin_addr > 0 && span + 1 >= min_len => Ok(()),
+            _ => Err(NyxError::LoadingError {
+                msg: format!(
+                    "SPK segment from address {begin_addr} to {end_addr} cannot hold {min_len} doubles"
+                ),
+            }),
+        }
+    }
+
+    /// Reads a type 2 or type 3 segment
+    pub(crate) fn chebyshev(
+        &self,
+        begin_addr: usize,
+        end_addr: usize,
+        with_velocity: bool,
+    ) -> Result<SpkSegmentData, NyxError> {
+        // The directory of four doubles is stored at the end of the segment
+        Self::check_segment_len(begin_addr, end_addr, 4)?;
+        let init_s = self.f64_addr(end_addr - 3)?;
+        let interval_s = self.f64_addr(end_addr - 2)?;
+        let record_size = self.f64_addr(end_addr - 1)? as usize;
+        let num_records = self.f64_addr(end_addr)? as usize;
+
+        let components = if with_velocity { 6 } else { 3 };
+        if record_size < 2 + components || !(record_size - 2).is_multiple_of(components) {
+            return Err(NyxError::InvalidInterpolationData {
+                msg: format!("invalid Chebyshev record size {record_size}"),
+            });
+        }
+
+        let records_len = record_size.saturating_mul(num_records);
+        Self::check_segment_len(begin_addr, end_addr, records_len.saturating_add(4))?;
+        let records = self.f64_slice(begin_addr, begin_addr + records_len - 1)?;
+
+        Ok(SpkSegmentData::Chebyshev {
+            init_s,
+            interval_s,
+            record_size,
+            with_velocity,
+            records,
+        })
+    }
+
+    /// Reads a type 9 or type 13 segment
+    fn discrete(
+        &self,
+        begin_addr: usize,
+        end_addr: usize,
+        hermite: bool,
+    ) -> Result<SpkSegmentData, NyxError> {
+        // The window size and the number of states are stored at the end of the segment
+        Self::check_segment_len(begin_addr, end_addr, 2)?;
+        let num_states = self.f64_addr(end_addr)? as usize;
+        // Both types store the window size minus one
+        let window_size = self.f64_addr(end_addr - 1)? as usize + 1;
+        if num_states == 0 {
+            return Err(NyxError::InvalidInterpolationData {
+                msg: "SPK segment has no states".to_string(),
+            });
+        }
+        Self::check_segment_len(
+            begin_addr,
+            end_addr,
+            num_states.saturating_mul(7).saturating_add(2),
+        )?;
+
+        let states = self
+            .f64_slice(begin_addr, begin_addr + 6 * num_states - 1)?
+            .chunks_exact(6)
+            .map(|s| [s[0], s[1], s[2], s[3], s[4], s[5]])
+            .collect();
+        let epochs_s =
+            self.f64_slice(begin_addr + 6 * num_states, begin_addr + 7 * num_states - 1)?;
+
+        Ok(SpkSegmentData::Discrete {
+            hermite,
+            window_size,
+            epochs_s,
+            states,
+        })
+    }
+}
+
+/// Returns the Chebyshev polynomials of the first kind and their derivatives evaluated at `t`
+fn chebyshev_basis(t: f64, count: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut t_k = vec![0.0; count];
+    let mut dt_k = vec![0.0; count];
+    if count > 0 {
+        t_k[0] = 1.0;
+    }
+    if count > 1 {
+        t_k[1] = t;
+        dt_k[1] = 1.0;
+    }
+    for k in 2..count {
+        t_k[k] = 2.0 * t * t_k[k - 1] - t_k[k - 2];
+        dt_k[k] = 2.0 * t_k[k - 1] + 2.0 * t * dt_k[k - 1] - dt_k[k - 2];
+    }
+    (t_k, dt_k)
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Returns the index of the first state of the interpolation window, centered on the requested time as done in SPICE.
+fn window_start(epochs_s: &[f64], et_s: f64, window: usize) -> usize {
+    let n = epochs_s.len();
+    // Index of the first epoch at or after the requested time
+    let near = epochs_s.partition_point(|epoch_s| *epoch_s < et_s);
+    let first = if window.is_multiple_of(2) {
+        near.saturating_sub(window / 2)
+    } else {
+        // Center odd windows on the closest state
+        let closest = if near == 0 {
+            0
+        } else if near == n || et_s - epochs_s[near - 1] <= epochs_s[near] - et_s {
+            near - 1
+        } else {
+            near
+        };
+        closest.saturating_sub(window / 2)
+    };
+    first.min(n - window)
+}
+
+#[cfg(test)]
+mod ut_spk {
+    use super::*;
+
+    /// Builds a little endian DAF with one summary record, one name record, and the provided segments
+    fn build_daf(segments: &[(i32, i32, i32, f64, f64, Vec<f64>)]) -> Vec<u8> {
+        let mut buf = vec![0_u8; 3 * DAF_RECORD_LEN];
+        buf[0..8].copy_from_slice(b"DAF/SPK ");
+        buf[8..12].copy_from_slice(&(SPK_ND as i32).to_le_bytes());
+        buf[12..16].copy_from_slice(&(SPK_NI as i32).to_le_bytes());
+        // First summary record is the second record
+        buf[76..80].copy_from_slice(&2_i32.to_le_bytes());
+        buf[88..96].copy_from_slice(b"LTL-IEEE");
+
+        let summary_start = DAF_RECORD_LEN;
+        buf[summary_start + 16..summary_start + 24]
+            .copy_from_slice(&(segments.len() as f64).to_le_bytes());
+
+        let mut next_addr = 3 * DAF_RECORD_LEN / DAF_DOUBLE_LEN + 1;
+        for (sno, (target, center, data_type, start, end, data)) in segments.iter().enumerate() {
+            let offset = summary_start + 24 + sno * 5 * DAF_DOUBLE_LEN;
+            buf[offset..offset + 8].copy_from_slice(&start.to_le_bytes());
+            buf[offset + 8..offset + 16].copy_from_slice(&end.to_le_bytes());
+            let ints = [
+                *target,
+                *center,
+                NAIF_J2000_FRAME_ID,
+                *data_type,
+                next_addr as i32,
+                (next_addr + data.len() - 1) as i32,
+            ];
+            for (ino, val) in ints.iter().enumerate() {
+                let int_offset = offset + 16 + ino * 4;
+                buf[int_offset..int_offset + 4].copy_from_slice(&val.to_le_bytes());
+            }
+            for val in data {
+                buf.extend_from_slice(&val.to_le_bytes());
+            }
+            next_addr += data.len();
+        }
+
+        buf
+    }
+
+    #[test]
+    fn test_spk_chebyshev_and_hermite() {
+        // Two type 2 records covering [0; 100] and [100; 200] seconds, where x(t) = 1 + 2 T1 + 3 T2
+        let mut cheby = vec![];
+        for mid in [50.0, 150.0] {
+            cheby.extend_from_slice(&[mid, 50.0]);
+            cheby.extend_from_slice(&[1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 4.0, 0.0, 0.0]);
+        }
+        cheby.extend_from_slice(&[0.0, 100.0, 11.0, 2.0]);
+
+        // Type 13 states along a straight line: x = 10 + 2 t
+        let mut discrete = vec![];
+        let epochs = [0.0, 10.0, 20.0, 30.0];
+        for t in epochs {
+            discrete.extend_from_slice(&[10.0 + 2.0 * t, 0.0, 0.0, 2.0, 0.0, 0.0]);
+        }
+        discrete.extend_from_slice(&epochs);
+        discrete.extend_from_slice(&[3.0, 4.0]);
+
+        let buf = build_daf(&[
+            (399, 3, 2, 0.0, 200.0, cheby),
+            (2000433, 10, 13, 0.0, 30.0, discrete),
+        ]);
+
+        let spk = Spk::from_buffer(&buf).unwrap();
+        assert_eq!(spk.segments.len(), 2);
+
+        let earth = &spk.segments[0];
+        assert_eq!(earth.target_id, 399);
+        assert_eq!(earth.center_id, 3);
+        let state = earth.evaluate(75.0).unwrap();
+        // t = 0.5, so x = 1 + 2*0.5 + 3*(2*0.25 - 1)
+        assert!((state[0] - 0.5).abs() < f64::EPSILON);
+        assert!((state[2] - 4.0).abs() < f64::EPSILON);
+        // dx/dt = (2 + 3 * 4 t) / radius
+        assert!((state[3] - 8.0 / 50.0).abs() < f64::EPSILON);
+        // End of the segment is in the last record
+        assert!(earth.evaluate(200.0).is_ok());
+        assert!(earth.evaluate(200.1).is_err());
+
+        let eros = &spk.segments[1];
+        let state = eros.evaluate(15.0).unwrap();
+        assert!((state[0] - 40.0).abs() < 1e-10);
+        assert!((state[3] - 2.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_spk_lagrange_window() {
+        let epochs = [0.0, 10.0, 20.0, 30.0, 40.0];
+        assert_eq!(window_start(&epochs, 12.0, 2), 1);
+        assert_eq!(window_start(&epochs, 12.0, 3), 0);
+        assert_eq!(window_start(&epochs, 16.0, 3), 1);
+        assert_eq!(window_start(&epochs, 40.0, 4), 1);
+    }
+
+    #[test]
+    fn test_spk_invalid() {
+        assert!(Spk::from_buffer(&[0_u8; 10]).is_err());
+        assert!(Spk::from_buffer(&[0_u8; DAF_RECORD_LEN]).is_err());
+        // Segments too short for their directory, or for the records it declares
+        let truncated = build_daf(&[(399, 3, 2, 0.0, 100.0, vec![0.0, 100.0])]);
+        assert!(Spk::from_buffer(&truncated).is_err());
+        let overflowing = build_daf(&[(399, 3, 2, 0.0, 100.0, vec![0.0, 100.0, 11.0, 2.0])]);
+        assert!(Spk::from_buffer(&overflowing).is_err());
+        let no_directory = build_daf(&[(399, 3, 13, 0.0, 100.0, vec![3.0])]);
+        assert!(Spk::from_buffer(&no_directory).is_err());
+    }
+}
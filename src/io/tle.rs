@@ -0,0 +1,374 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::errors::NyxError;
+use crate::time::Epoch;
+use std::fmt;
+use std::fs::read_to_string;
+use std::str::FromStr;
+
+/// A two-line element set (TLE), as distributed by the 18th Space Defense Squadron and CelesTrak.
+///
+/// The elements are SGP4 mean elements in the TEME frame: they must be propagated with [`crate::propagators::Sgp4`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tle {
+    /// Name of the object, from the optional title line
+    pub name: Option<String>,
+    /// NORAD catalog number (Alpha-5 numbers are decoded)
+    pub norad_id: u32,
+    /// Classification (U, C or S)
+    pub classification: char,
+    /// International designator (launch year, launch number and piece)
+    pub intl_designator: String,
+    /// Epoch of the elements (UTC)
+    pub epoch: Epoch,
+    /// First derivative of the mean motion divided by two, in rev/day^2
+    pub mean_motion_dot: f64,
+    /// Second derivative of the mean motion divided by six, in rev/day^3
+    pub mean_motion_ddot: f64,
+    /// B* drag term, in inverse Earth radii
+    pub bstar: f64,
+    /// Element set number
+    pub element_set_number: u32,
+    /// Inclination, in degrees
+    pub inc_deg: f64,
+    /// Right ascension of the ascending node, in degrees
+    pub raan_deg: f64,
+    /// Eccentricity
+    pub ecc: f64,
+    /// Argument of perigee, in degrees
+    pub aop_deg: f64,
+    /// Mean anomaly, in degrees
+    pub ma_deg: f64,
+    /// Kozai mean motion, in revolutions per day
+    pub mean_motion_rev_per_day: f64,
+    /// Revolution number at epoch
+    pub rev_number: u32,
+}
+
+impl Tle {
+    /// Parses a TLE from its two data lines. The checksums are verified.
+    pub fn from_lines(line1: &str, line2: &str) -> Result<Self, NyxError> {
+        let line1 = line1.trim_end();
+        let line2 = line2.trim_end();
+
+        for (num, line) in [(1, line1), (2, line2)] {
+            if line.len() < 69 || !line.is_ascii() {
+                return Err(tle_err(format!(
+                    "line {num} must be 69 ASCII characters long, got `{line}`"
+                )));
+            }
+            if !line.starts_with(&format!("{num} ")) {
+                return Err(tle_err(format!("line {num} must start with `{num} `")));
+            }
+            let expected = checksum(&line[..68]);
+            let found = line[68..69]
+                .parse::<u32>()
+                .map_err(|_| tle_err(format!("invalid checksum character on line {num}")))?;
+            if expected != found {
+                return Err(tle_err(format!(
+                    "checksum of line {num} is {expected} but the line declares {found}"
+                )));
+            }
+        }
+
+        let norad_id = parse_norad_id(&line1[2..7])?;
+        if norad_id != parse_norad_id(&line2[2..7])? {
+            return Err(tle_err(
+                "the catalog numbers of both lines differ".to_string(),
+            ));
+        }
+
+        // Two digit year: 57 to 99 are 1957 to 1999, 00 to 56 are 2000 to 2056
+        let year_2d: i32 = parse_field(line1, 18..20, "epoch year")?;
+        let year = if year_2d < 57 {
+            2000 + year_2d
+        } else {
+            1900 + year_2d
+        };
+        let day_of_year: f64 = parse_field(line1, 20..32, "epoch day")?;
+        let epoch = Epoch::from_mjd_utc(
+            Epoch::from_gregorian_utc_at_midnight(year, 1, 1).to_mjd_utc_days() + day_of_year - 1.0,
+        );
+
+        Ok(Self {
+            name: None,
+            norad_id,
+            classification: line1[7..8].chars().next().unwrap_or('U'),
+            intl_designator: line1[9..17].trim().to_string(),
+            epoch,
+            mean_motion_dot: parse_field(line1, 33..43, "mean motion derivative")?,
+            mean_motion_ddot: parse_exponent(&line1[44..52], "mean motion second derivative")?,
+            bstar: parse_exponent(&line1[53..61], "B*")?,
+            element_set_number: line1[64..68].trim().parse().unwrap_or(0),
+            inc_deg: parse_field(line2, 8..16, "inclination")?,
+            raan_deg: parse_field(line2, 17..25, "RAAN")?,
+            ecc: parse_field::<f64>(line2, 26..33, "eccentricity")? * 1e-7,
+            aop_deg: parse_field(line2, 34..42, "argument of perigee")?,
+            ma_deg: parse_field(line2, 43..51, "mean anomaly")?,
+            mean_motion_rev_per_day: parse_field(line2, 52..63, "mean motion")?,
+            rev_number: line2[63..68].trim().parse().unwrap_or(0),
+        })
+    }
+
+    /// Parses a TLE from a title line and its two data lines.
+    pub fn from_lines_with_name(name: &str, line1: &str, line2: &str) -> Result<Self, NyxError> {
+        let mut tle = Self::from_lines(line1, line2)?;
+        // Three line element sets from space-track prefix the name with `0 `
+        let name = name.trim();
+        let name = name.strip_prefix("0 ").unwrap_or(name).trim();
+        if !name.is_empty() {
+            tle.name = Some(name.to_string());
+        }
+        Ok(tle)
+    }
+
+    /// Loads all of the two or three line element sets of the provided file.
+    pub fn from_file(filepath: &str) -> Result<Vec<Self>, NyxError> {
+        let content = read_to_string(filepath).map_err(|e| NyxError::FileUnreadable {
+            msg: format!("{filepath}: {e}"),
+        })?;
+        Self::from_str_all(&content)
+    }
+
+    /// Parses all of the two or three line element sets in this string. Empty lines and lines starting with `#` are ignored.
+    pub fn from_str_all(content: &str) -> Result<Vec<Self>, NyxError> {
+        let lines: Vec<&str> = content
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .collect();
+
+        let mut tles = Vec::new();
+        let mut name: Option<&str> = None;
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            if line.starts_with("1 ") && i + 1 < lines.len() && lines[i + 1].starts_with("2 ") {
+                let tle = match name.take() {
+                    Some(name) => Self::from_lines_with_name(name, line, lines[i + 1])?,
+                    None => Self::from_lines(line, lines[i + 1])?,
+                };
+                tles.push(tle);
+                i += 2;
+            } else if name.is_none() {
+                name = Some(line);
+                i += 1;
+            } else {
+                return Err(tle_err(format!("unexpected line `{line}`")));
+            }
+        }
+
+        if tles.is_empty() {
+            return Err(tle_err("no element set found".to_string()));
+        }
+
+        Ok(tles)
+    }
+}
+
+impl FromStr for Tle {
+    type Err = NyxError;
+
+    /// Parses a single two or three line element set.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tles = Self::from_str_all(s)?;
+        if tles.len() > 1 {
+            return Err(tle_err(format!(
+                "expected one element set but found {}",
+                tles.len()
+            )));
+        }
+        Ok(tles.remove(0))
+    }
+}
+
+impl fmt::Display for Tle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "TLE {}{} @ {}: inc = {} deg\traan = {} deg\tecc = {}\taop = {} deg\tma = {} deg\tn = {} rev/day\tB* = {:e}",
+            self.norad_id,
+            match &self.name {
+                Some(name) => format!(" ({name})"),
+                None => String::new(),
+            },
+            self.epoch,
+            self.inc_deg,
+            self.raan_deg,
+            self.ecc,
+            self.aop_deg,
+            self.ma_deg,
+            self.mean_motion_rev_per_day,
+            self.bstar
+        )
+    }
+}
+
+fn tle_err(msg: String) -> NyxError {
+    NyxError::LoadingError {
+        msg: format!("TLE: {msg}"),
+    }
+}
+
+/// Modulo 10 checksum: the sum of the digits, where minus signs count as one.
+fn checksum(line: &str) -> u32 {
+    line.chars()
+        .map(|c| match c {
+            '-' => 1,
+            _ => c.to_digit(10).unwrap_or(0),
+        })
+        .sum::<u32>()
+        % 10
+}
+
+fn parse_field<T: FromStr>(
+    line: &str,
+    cols: std::ops::Range<usize>,
+    field: &str,
+) -> Result<T, NyxError> {
+    let text = line[cols].trim();
+    // Some generators pad the eccentricity and the day of year with spaces instead of zeros
+    text.replace(' ', "0")
+        .parse()
+        .map_err(|_| tle_err(format!("could not parse the {field} from `{text}`")))
+}
+
+/// Parses the catalog number, including the Alpha-5 scheme where the first digit is a letter (excluding I and O).
+fn parse_norad_id(text: &str) -> Result<u32, NyxError> {
+    let text = text.trim();
+    let err = || tle_err(format!("invalid catalog number `{text}`"));
+    let first = text.chars().next().ok_or_else(err)?;
+    if first.is_ascii_alphabetic() {
+        let first = first.to_ascii_uppercase();
+        let mut value = first as u32 - 'A' as u32 + 10;
+        if first > 'I' {
+            value -= 1;
+        }
+        if first > 'O' {
+            value -= 1;
+        }
+        let rest: u32 = text[1..].parse().map_err(|_| err())?;
+        Ok(value * 10_000 + rest)
+    } else {
+        text.parse().map_err(|_| err())
+    }
+}
+
+/// Parses the fields with an implied leading decimal point and an exponent, e.g. ` 12345-3` is 0.12345e-3.
+fn parse_exponent(text: &str, field: &str) -> Result<f64, NyxError> {
+    let text = text.trim();
+    let err = || tle_err(format!("could not parse the {field} from `{text}`"));
+    if text.is_empty() {
+        return Ok(0.0);
+    }
+    // The exponent sign is the last `+` or `-` which is not the leading sign
+    let exp_idx = text
+        .char_indices()
+        .skip(1)
+        .filter(|(_, c)| *c == '-' || *c == '+')
+        .map(|(idx, _)| idx)
+        .last()
+        .ok_or_else(err)?;
+    let (mantissa, exponent) = text.split_at(exp_idx);
+    let (sign, digits) = match mantissa.strip_prefix('-') {
+        Some(digits) => (-1.0, digits),
+        None => (1.0, mantissa.strip_prefix('+').unwrap_or(mantissa)),
+    };
+    let exponent: i32 = exponent.parse().map_err(|_| err())?;
+    let value: f64 = format!("0.{}e{exponent}", digits.trim())
+        .parse()
+        .map_err(|_| err())?;
+    Ok(sign * value)
+}
+
+#[cfg(test)]
+mod ut_tle {
+    use super::*;
+    use crate::time::TimeUnits;
+
+    const VANGUARD_L1: &str =
+        "1 00005U 58002B   00179.78495062  .00000023  00000-0  28098-4 0  4753";
+    const VANGUARD_L2: &str =
+        "2 00005  34.2682 348.7242 1859667 331.7664  19.3264 10.82419157413667";
+
+    #[test]
+    fn test_tle_parse() {
+        let tle = Tle::from_lines(VANGUARD_L1, VANGUARD_L2).unwrap();
+        assert_eq!(tle.norad_id, 5);
+        assert_eq!(tle.classification, 'U');
+        assert_eq!(tle.intl_designator, "58002B");
+        assert_eq!(tle.name, None);
+        assert!((tle.mean_motion_dot - 2.3e-7).abs() < f64::EPSILON);
+        assert_eq!(tle.mean_motion_ddot, 0.0);
+        assert!((tle.bstar - 0.28098e-4).abs() < 1e-15);
+        assert_eq!(tle.element_set_number, 475);
+        assert_eq!(tle.inc_deg, 34.2682);
+        assert_eq!(tle.raan_deg, 348.7242);
+        assert!((tle.ecc - 0.1859667).abs() < f64::EPSILON);
+        assert_eq!(tle.aop_deg, 331.7664);
+        assert_eq!(tle.ma_deg, 19.3264);
+        assert_eq!(tle.mean_motion_rev_per_day, 10.82419157);
+        assert_eq!(tle.rev_number, 41366);
+
+        // Day 179.78495062 of 2000 is 27 June 2000 at 18:50:19.733568 UTC
+        let expected = Epoch::from_gregorian_utc(2000, 6, 27, 18, 50, 19, 733_568_000);
+        assert!((tle.epoch - expected).abs() < 1.microseconds());
+
+        // Three line element set with a negative B*
+        let tles = Tle::from_str_all(
+            "ISS (ZARYA)
+1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537
+",
+        )
+        .unwrap();
+        assert_eq!(tles.len(), 1);
+        assert_eq!(tles[0].name, Some("ISS (ZARYA)".to_string()));
+        assert_eq!(tles[0].norad_id, 25544);
+        assert!((tles[0].bstar + 0.11606e-4).abs() < 1e-15);
+        assert!((tles[0].mean_motion_dot + 2.182e-5).abs() < f64::EPSILON);
+        assert_eq!(tles[0].rev_number, 56353);
+
+        let tle = Tle::from_str(&format!("0 VANGUARD 1\n{VANGUARD_L1}\n{VANGUARD_L2}")).unwrap();
+        assert_eq!(tle.name, Some("VANGUARD 1".to_string()));
+        assert_eq!(tle.norad_id, 5);
+    }
+
+    #[test]
+    fn test_tle_invalid() {
+        // Corrupt the checksum
+        let bad = format!("{}2", &VANGUARD_L1[..68]);
+        assert!(Tle::from_lines(&bad, VANGUARD_L2).is_err());
+        // Swapped lines
+        assert!(Tle::from_lines(VANGUARD_L2, VANGUARD_L1).is_err());
+        // Truncated line
+        assert!(Tle::from_lines(&VANGUARD_L1[..60], VANGUARD_L2).is_err());
+    }
+
+    #[test]
+    fn test_tle_alpha5() {
+        assert_eq!(parse_norad_id("A0000").unwrap(), 100_000);
+        assert_eq!(parse_norad_id("E8493").unwrap(), 148_493);
+        assert_eq!(parse_norad_id("J2931").unwrap(), 182_931);
+        assert_eq!(parse_norad_id("P0000").unwrap(), 230_000);
+        assert_eq!(parse_norad_id("Z9999").unwrap(), 339_999);
+        assert_eq!(parse_exponent(" 12345-3", "test").unwrap(), 0.12345e-3);
+        assert_eq!(parse_exponent("-11606-4", "test").unwrap(), -0.11606e-4);
+        assert_eq!(parse_exponent(" 00000+0", "test").unwrap(), 0.0);
+    }
+}
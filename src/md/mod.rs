@@ -34,7 +34,8 @@ pub mod prelude {
         LightTimeCalc, Orbit, OrbitDual,
     };
     pub use crate::dynamics::{
-        Drag, Harmonics, OrbitalDynamics, PointMasses, SolarPressure, SpacecraftDynamics,
+        Drag, Harmonics, OrbitalDynamics, PointMasses, Relativity, SolarPressure,
+        SpacecraftDynamics,
     };
     pub use crate::dynamics::{Dynamics, NyxError};
     pub use crate::io::gravity::HarmonicsMem;
@@ -0,0 +1,454 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use rayon::prelude::*;
+use snafu::{ResultExt, Snafu};
+
+use crate::cosmic::Orbit;
+use crate::dynamics::Dynamics;
+use crate::io::ExportCfg;
+use crate::linalg::{Matrix6, Vector6};
+use crate::md::events::EventEvaluator;
+use crate::md::trajectory::Traj;
+use crate::propagators::error_ctrl::ErrorCtrl;
+use crate::propagators::{PropagationError, Propagator};
+use crate::time::Duration;
+use crate::State;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Eigenvalues of the monodromy matrix within this distance of one are considered to be the trivial pair
+const TRIVIAL_TOL: f64 = 1e-3;
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum ManifoldError {
+    #[snafu(display("the periodic orbit trajectory must include the STM of each state"))]
+    NoStm,
+    #[snafu(display("the periodic orbit trajectory must include at least two states"))]
+    TooFewStates,
+    #[snafu(display(
+        "the monodromy matrix has no {kind} eigenvalue (eigenvalues: {eigenvalues})"
+    ))]
+    NoHyperbolicMode {
+        kind: ManifoldKind,
+        eigenvalues: String,
+    },
+    #[snafu(display("{kind} manifold arc #{index} encountered {source}"))]
+    ArcPropagation {
+        kind: ManifoldKind,
+        index: usize,
+        source: PropagationError,
+    },
+}
+
+/// Kind of invariant manifold of a periodic orbit
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ManifoldKind {
+    /// Set of trajectories which asymptotically approach the periodic orbit, computed by propagating backward in time
+    Stable,
+    /// Set of trajectories which asymptotically depart the periodic orbit, computed by propagating forward in time
+    Unstable,
+}
+
+impl fmt::Display for ManifoldKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Stable => write!(f, "stable"),
+            Self::Unstable => write!(f, "unstable"),
+        }
+    }
+}
+
+/// A single trajectory of an invariant manifold
+#[derive(Clone)]
+pub struct ManifoldArc {
+    /// Index of the point of the periodic orbit from which this arc departs
+    pub index: usize,
+    /// Whether this arc departs along the positive or negative direction of the eigenvector
+    pub positive: bool,
+    /// Trajectory of the arc, ending at the first event if it was found
+    pub traj: Traj<Orbit>,
+    /// State at the first event, e.g. the crossing of a Poincaré section, if found within the maximum duration
+    pub crossing: Option<Orbit>,
+}
+
+/// The stable or unstable manifold of a periodic orbit, as a tube of trajectories.
+#[derive(Clone)]
+pub struct InvariantManifold {
+    /// Kind of this manifold
+    pub kind: ManifoldKind,
+    /// Eigenvalue of the monodromy matrix along which the manifold was computed
+    pub eigenvalue: f64,
+    /// Trajectories of the manifold, ordered by departure point then direction
+    pub arcs: Vec<ManifoldArc>,
+}
+
+impl InvariantManifold {
+    /// Returns the arcs departing along the positive (e.g. interior) or negative (e.g. exterior) direction of the eigenvector
+    pub fn branch(&self, positive: bool) -> impl Iterator<Item = &ManifoldArc> {
+        self.arcs.iter().filter(move |arc| arc.positive == positive)
+    }
+
+    /// Returns the states at the first event of each arc, i.e. the intersection of the manifold with a Poincaré section
+    pub fn crossings(&self) -> Vec<Orbit> {
+        self.arcs.iter().filter_map(|arc| arc.crossing).collect()
+    }
+
+    /// Store each arc of this manifold to its own parquet file, named after the provided path suffixed with the kind, direction and
+    /// departure point of the arc (e.g. `tube-unstable-pos-003.parquet`). Returns the paths of all of the files.
+    pub fn to_parquet<P: AsRef<Path>>(
+        &self,
+        path: P,
+        cfg: ExportCfg,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let path = path.as_ref();
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("manifold");
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("parquet");
+
+        let mut paths = Vec::with_capacity(self.arcs.len());
+        for arc in &self.arcs {
+            let mut cfg = cfg.clone();
+            let mut metadata = cfg.metadata.unwrap_or_default();
+            metadata.insert("Manifold".to_string(), format!("{}", self.kind));
+            metadata.insert("Eigenvalue".to_string(), format!("{}", self.eigenvalue));
+            metadata.insert("Departure index".to_string(), format!("{}", arc.index));
+            metadata.insert(
+                "Direction".to_string(),
+                if arc.positive { "positive" } else { "negative" }.to_string(),
+            );
+            cfg.metadata = Some(metadata);
+
+            let file_name = format!(
+                "{stem}-{}-{}-{:03}.{ext}",
+                self.kind,
+                if arc.positive { "pos" } else { "neg" },
+                arc.index
+            );
+            paths.push(
+                arc.traj
+                    .to_parquet_with_cfg(path.with_file_name(file_name), cfg)?,
+            );
+        }
+        Ok(paths)
+    }
+}
+
+/// Generates the stable and unstable invariant manifolds of periodic orbits.
+///
+/// The manifolds are globalized from points sampled along the periodic orbit, perturbed along the stable or unstable eigenvector of the
+/// monodromy matrix mapped to each point by the STM, and each arc is propagated in parallel until the first occurrence of an event.
+/// Source: Gómez, Koon, Lo, Marsden, Masdemont, Ross, "Connecting orbits and invariant manifolds in the spatial restricted three-body problem", Nonlinearity 17, 2004
+pub struct ManifoldGenerator<'a, D: Dynamics<StateType = Orbit>, E: ErrorCtrl> {
+    /// The propagator setup (kind, stages, etc.), which is typically that of the CR3BP dynamics
+    pub prop: &'a Propagator<'a, D, E>,
+    /// Number of points sampled along the periodic orbit
+    pub num_points: usize,
+    /// Magnitude of the position perturbation along the eigenvector, in the units of the state (i.e. normalized in a synodic frame)
+    pub perturbation: f64,
+    /// Maximum duration of each arc
+    pub max_duration: Duration,
+}
+
+impl<'a, D, E> ManifoldGenerator<'a, D, E>
+where
+    D: Dynamics<StateType = Orbit>,
+    E: ErrorCtrl,
+{
+    /// Initializes a new manifold generator
+    pub fn new(
+        prop: &'a Propagator<'a, D, E>,
+        num_points: usize,
+        perturbation: f64,
+        max_duration: Duration,
+    ) -> Self {
+        Self {
+            prop,
+            num_points,
+            perturbation,
+            max_duration,
+        }
+    }
+
+    /// Generates the requested manifold of the periodic orbit, provided as a trajectory over exactly one period whose states include the STM
+    /// since the start of the trajectory (e.g. from `PeriodicOrbit::to_traj`). Each arc ends at the first occurrence of the event.
+    pub fn generate<F: EventEvaluator<Orbit>>(
+        &self,
+        orbit: &Traj<Orbit>,
+        kind: ManifoldKind,
+        event: &F,
+    ) -> Result<InvariantManifold, ManifoldError> {
+        if orbit.states.len() < 2 {
+            return Err(ManifoldError::TooFewStates);
+        }
+        let monodromy = orbit.last().stm().map_err(|_| ManifoldError::NoStm)?;
+        let (eigenvalue, eigenvector) = hyperbolic_mode(&monodromy, kind)?;
+
+        // Sample the stored states closest to equally spaced epochs, since the interpolated states do not include the STM
+        let start = orbit.first().epoch;
+        let step = (orbit.last().epoch - start) / (self.num_points as f64);
+        let mut departures = Vec::with_capacity(2 * self.num_points);
+        for index in 0..self.num_points {
+            let epoch = start + step * (index as f64);
+            let closest = match orbit
+                .states
+                .binary_search_by(|state| state.epoch.cmp(&epoch))
+            {
+                Ok(idx) => idx,
+                Err(idx) => {
+                    if idx == 0 {
+                        0
+                    } else if idx == orbit.states.len()
+                        || epoch - orbit.states[idx - 1].epoch < orbit.states[idx].epoch - epoch
+                    {
+                        idx - 1
+                    } else {
+                        idx
+                    }
+                }
+            };
+            let state = orbit.states[closest];
+            let stm = state.stm().map_err(|_| ManifoldError::NoStm)?;
+            // Map the eigenvector to this point, and scale it such that its position has the requested magnitude
+            let direction = stm * eigenvector;
+            let direction = direction * self.perturbation / direction.fixed_rows::<3>(0).norm();
+
+            let mut nominal = state;
+            nominal.unset_stm();
+            let nominal_vec = nominal.to_cartesian_vec();
+            for positive in [true, false] {
+                let sign = if positive { 1.0 } else { -1.0 };
+                departures.push((
+                    index,
+                    positive,
+                    Orbit::cartesian_vec(
+                        &(nominal_vec + sign * direction),
+                        nominal.epoch,
+                        nominal.frame,
+                    ),
+                ));
+            }
+        }
+
+        let max_duration = match kind {
+            ManifoldKind::Stable => -self.max_duration,
+            ManifoldKind::Unstable => self.max_duration,
+        };
+
+        info!(
+            "Generating {} arcs of the {kind} manifold (λ = {eigenvalue:.6})",
+            departures.len()
+        );
+        let mut arcs = departures
+            .par_iter()
+            .map(|(index, positive, departure)| {
+                let (_, traj) = self
+                    .prop
+                    .with(*departure)
+                    .for_duration_with_traj(max_duration)
+                    .with_context(|_| ArcPropagationSnafu {
+                        kind,
+                        index: *index,
+                    })?;
+                // Stop the arc at the first occurrence of the event in the direction of propagation
+                let events = traj.find(event).unwrap_or_default();
+                let crossing = match kind {
+                    ManifoldKind::Stable => events.last(),
+                    ManifoldKind::Unstable => events.first(),
+                }
+                .map(|details| details.state);
+                let traj = match crossing {
+                    Some(crossing) => truncate(traj, crossing, kind),
+                    None => traj,
+                };
+                Ok(ManifoldArc {
+                    index: *index,
+                    positive: *positive,
+                    traj,
+                    crossing,
+                })
+            })
+            .collect::<Result<Vec<ManifoldArc>, ManifoldError>>()?;
+        arcs.sort_by_key(|arc| (arc.index, !arc.positive));
+
+        Ok(InvariantManifold {
+            kind,
+            eigenvalue,
+            arcs,
+        })
+    }
+}
+
+/// Returns the real eigenvalue and the eigenvector of the monodromy matrix of the requested manifold: the largest eigenvalue for the
+/// unstable manifold and the smallest one for the stable manifold.
+fn hyperbolic_mode(
+    monodromy: &Matrix6<f64>,
+    kind: ManifoldKind,
+) -> Result<(f64, Vector6<f64>), ManifoldError> {
+    let eigenvalues = monodromy.complex_eigenvalues();
+    let real = eigenvalues
+        .iter()
+        .filter(|lambda| lambda.im.abs() < 1e-9 * lambda.norm())
+        .map(|lambda| lambda.re);
+    let eigenvalue = match kind {
+        ManifoldKind::Unstable => real.fold(f64::NAN, |acc, lambda| {
+            if lambda.abs() > 1.0 + TRIVIAL_TOL && (acc.is_nan() || lambda.abs() > acc.abs()) {
+                lambda
+            } else {
+                acc
+            }
+        }),
+        ManifoldKind::Stable => real.fold(f64::NAN, |acc, lambda| {
+            if lambda.abs() < 1.0 - TRIVIAL_TOL && (acc.is_nan() || lambda.abs() < acc.abs()) {
+                lambda
+            } else {
+                acc
+            }
+        }),
+    };
+    if eigenvalue.is_nan() {
+        return Err(ManifoldError::NoHyperbolicMode {
+            kind,
+            eigenvalues: format!("{}", eigenvalues.transpose()),
+        });
+    }
+
+    // The eigenvector spans the null space of (M - λI)
+    let svd = (monodromy - Matrix6::identity() * eigenvalue).svd(false, true);
+    let null = svd.singular_values.imin();
+    let eigenvector = svd.v_t.unwrap().row(null).transpose().normalize();
+    Ok((eigenvalue, eigenvector))
+}
+
+/// Removes the states of the arc past the crossing, in the direction of propagation, and appends the crossing itself
+fn truncate(traj: Traj<Orbit>, crossing: Orbit, kind: ManifoldKind) -> Traj<Orbit> {
+    let mut truncated = Traj::new();
+    truncated.states = traj
+        .states
+        .into_iter()
+        .filter(|state| match kind {
+            ManifoldKind::Stable => state.epoch > crossing.epoch,
+            ManifoldKind::Unstable => state.epoch < crossing.epoch,
+        })
+        .collect();
+    truncated.states.push(crossing);
+    truncated.finalize();
+    truncated
+}
+
+#[cfg(test)]
+mod ut_manifold {
+    use super::*;
+    use crate::cosmic::{Bodies, Frame};
+    use crate::dynamics::Cr3bpDynamics;
+    use crate::md::opti::periodic::{PeriodicFamily, PeriodicOrbitCorrector};
+    use crate::md::{Event, StateParameter};
+    use crate::time::{Epoch, Unit};
+
+    #[test]
+    fn test_lyapunov_manifolds() {
+        let frame = Frame::Synodic {
+            primary: Bodies::Earth,
+            secondary: Bodies::Luna,
+            mass_ratio: 0.012_150_585_609_624,
+            length_km: 384_400.0,
+            time_s: 375_190.0,
+        };
+        let mu = frame.mass_ratio();
+        let prop = Propagator::default(Cr3bpDynamics::new());
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2022, 1, 1);
+
+        let family = PeriodicFamily::Lyapunov { point: 1 };
+        let lyapunov = PeriodicOrbitCorrector::for_family(&prop, family)
+            .correct(family.initial_guess(0.01, epoch, frame).unwrap())
+            .unwrap();
+        let orbit = lyapunov.to_traj(&prop).unwrap();
+
+        // Poincaré section at the x of the Moon
+        let section = Event::within_tolerance(StateParameter::X, 1.0 - mu, 1e-9);
+        let generator = ManifoldGenerator::new(&prop, 8, 1e-6, 30 * Unit::Day);
+
+        let unstable = generator
+            .generate(&orbit, ManifoldKind::Unstable, &section)
+            .unwrap();
+        assert_eq!(unstable.arcs.len(), 16);
+        assert!(unstable.eigenvalue > 1000.0);
+        // The interior branch falls towards the Earth, and the exterior one reaches the section at the Moon
+        let mut reached = 0;
+        for arc in &unstable.arcs {
+            assert!(arc.traj.last().epoch > epoch);
+            assert!(
+                (arc.traj.first().jacobi_constant() - lyapunov.jacobi_constant).abs() < 1e-6,
+                "{}",
+                arc.traj.first()
+            );
+            if let Some(crossing) = arc.crossing {
+                reached += 1;
+                assert!((crossing.x_km - (1.0 - mu)).abs() < 1e-6);
+                assert_eq!(arc.traj.last().epoch, crossing.epoch);
+            }
+        }
+        assert!(reached >= 8, "{reached} arcs reached the section");
+        assert_eq!(unstable.crossings().len(), reached);
+
+        let stable = generator
+            .generate(&orbit, ManifoldKind::Stable, &section)
+            .unwrap();
+        assert!((stable.eigenvalue * unstable.eigenvalue - 1.0).abs() < 1e-2);
+        for arc in &stable.arcs {
+            assert!(arc.traj.first().epoch < arc.traj.last().epoch);
+            assert!(arc.traj.first().epoch < epoch + lyapunov.period);
+        }
+
+        // A stable orbit has no manifolds
+        let dro = PeriodicOrbitCorrector::for_family(&prop, PeriodicFamily::DistantRetrograde)
+            .correct(
+                PeriodicFamily::DistantRetrograde
+                    .initial_guess(0.1, epoch, frame)
+                    .unwrap(),
+            )
+            .unwrap();
+        assert!(generator
+            .generate(
+                &dro.to_traj(&prop).unwrap(),
+                ManifoldKind::Unstable,
+                &section
+            )
+            .is_err());
+
+        // Export the tube
+        let path: PathBuf = [
+            env!("CARGO_MANIFEST_DIR"),
+            "output_data",
+            "l1_lyapunov.parquet",
+        ]
+        .iter()
+        .collect();
+        let paths = unstable.to_parquet(path, ExportCfg::default()).unwrap();
+        assert_eq!(paths.len(), 16);
+        for path in paths {
+            assert!(path.exists());
+        }
+    }
+}
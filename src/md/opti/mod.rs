@@ -22,7 +22,11 @@ pub use multipleshooting::{ctrlnodes, multishoot};
 /// Uses a Levenberg Marquardt minimizer to solve the damped least squares problem.
 // #[cfg(feature = "broken-donotuse")]
 // pub mod minimize_lm;
+/// Stable and unstable invariant manifolds of periodic orbits
+pub mod manifold;
 pub mod optimizer;
+/// Differential correction and continuation of periodic orbits of the circular restricted three body problem
+pub mod periodic;
 /// Uses a [Newton Raphson](https://en.wikipedia.org/wiki/Newton%27s_method_in_optimization) method where the Jacobian is computed via finite differencing.
 pub mod raphson_finite_diff;
 /// Uses a [Newton Raphson](https://en.wikipedia.org/wiki/Newton%27s_method_in_optimization) method where the Jacobian is computed via hyperdual numbers.
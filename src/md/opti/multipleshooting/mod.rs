@@ -24,6 +24,7 @@ pub mod altitude_heuristic;
 pub mod ctrlnodes;
 pub mod equidistant_heuristic;
 pub mod multishoot;
+pub mod periodic;
 
 /// Built-in cost functions to minimize
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
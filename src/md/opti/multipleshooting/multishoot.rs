@@ -35,6 +35,18 @@ pub trait MultishootNode<const O: usize>: Copy + Into<[Objective; O]> {
     fn update_component(&mut self, component: usize, add_val: f64);
 }
 
+/// Adds the correction of the optimization variables to the nodes, where the `vars_per_node` variables of each node are contiguous,
+/// i.e. the correction of the component `j` of the node `i` is at index `vars_per_node * i + j`.
+pub(crate) fn update_nodes<T: MultishootNode<O>, const O: usize>(
+    nodes: &mut [T],
+    correction: &DVector<f64>,
+    vars_per_node: usize,
+) {
+    for (i, val) in correction.iter().enumerate() {
+        nodes[i / vars_per_node].update_component(i % vars_per_node, *val);
+    }
+}
+
 /// Multiple shooting is an optimization method.
 /// Source of implementation: "Low Thrust Optimization in Cislunar and Translunar space", 2018 Nathan Re (Parrish)
 /// OT: size of the objectives for each node (e.g. 3 if the objectives are X, Y, Z).
@@ -267,12 +279,7 @@ impl<'a, E: ErrorCtrl, T: MultishootNode<OT>, const VT: usize, const OT: usize>
                 .with_context(|_| TargetingSnafu { segment: 0_usize })?;
             let delta_r = inv_jac * cost_vec;
             // 3. Apply the correction to the node positions and iterator
-            let node_vector = -delta_r;
-            for (i, val) in node_vector.iter().enumerate() {
-                let node_no = i / 3;
-                let component_no = i % OT;
-                self.targets[node_no].update_component(component_no, *val);
-            }
+            update_nodes(&mut self.targets, &-delta_r, OT);
             self.current_iteration += 1;
         }
         Err(MultipleShootingError::TargetingError {
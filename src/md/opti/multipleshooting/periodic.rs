@@ -0,0 +1,269 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use snafu::ResultExt;
+
+use super::multishoot::{update_nodes, MultishootNode};
+use super::{MultipleShootingError, TargetingSnafu};
+use crate::cosmic::Orbit;
+use crate::dynamics::Cr3bpDynamics;
+use crate::linalg::{DMatrix, DVector};
+use crate::md::opti::periodic::{characteristic_time, state_derivative, PeriodicOrbit};
+use crate::md::prelude::{Objective, StateParameter};
+use crate::md::{PropSnafu, TargetingError};
+use crate::propagators::error_ctrl::ErrorCtrl;
+use crate::propagators::Propagator;
+use crate::pseudo_inverse;
+use crate::time::{Duration, Epoch, Unit};
+use crate::State;
+
+/// Number of optimization variables of each node: its Cartesian state and the duration of its segment
+const VARS_PER_NODE: usize = 7;
+
+/// Node of a periodic orbit: the state which the segment of the previous node must reach, and the duration of its own segment.
+#[derive(Copy, Clone, Debug)]
+pub struct PeriodicNode {
+    pub state: Orbit,
+    pub duration: Duration,
+}
+
+impl MultishootNode<6> for PeriodicNode {
+    fn epoch(&self) -> Epoch {
+        self.state.epoch
+    }
+
+    fn update_component(&mut self, component: usize, add_val: f64) {
+        match component {
+            0 => self.state.x_km += add_val,
+            1 => self.state.y_km += add_val,
+            2 => self.state.z_km += add_val,
+            3 => self.state.vx_km_s += add_val,
+            4 => self.state.vy_km_s += add_val,
+            5 => self.state.vz_km_s += add_val,
+            6 => self.duration += add_val * Unit::Second,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<[Objective; 6]> for PeriodicNode {
+    fn into(self) -> [Objective; 6] {
+        [
+            Objective::new(StateParameter::X, self.state.x_km),
+            Objective::new(StateParameter::Y, self.state.y_km),
+            Objective::new(StateParameter::Z, self.state.z_km),
+            Objective::new(StateParameter::VX, self.state.vx_km_s),
+            Objective::new(StateParameter::VY, self.state.vy_km_s),
+            Objective::new(StateParameter::VZ, self.state.vz_km_s),
+        ]
+    }
+}
+
+/// Multiple shooting corrector of periodic orbits of the circular restricted three body problem.
+///
+/// Unlike the single shooting `PeriodicOrbitCorrector`, this does not require the orbit to be symmetric, and it is far less sensitive
+/// to the instability of orbits such as large halo orbits or NRHOs, since the STM is only propagated between consecutive nodes.
+/// As in the `MultipleShooting`, each node is the target of the segment of the previous node, and the last segment targets the first
+/// node, which closes the orbit. The states and the segment durations of all the nodes are corrected with a minimum norm update.
+pub struct PeriodicMultipleShooting<'a, E: ErrorCtrl> {
+    /// The propagator setup (kind, stages, etc.), which must use the CR3BP dynamics
+    pub prop: &'a Propagator<'a, Cr3bpDynamics, E>,
+    /// List of nodes of the periodic orbit, starting at the initial state
+    pub targets: Vec<PeriodicNode>,
+    /// Convergence tolerance on the continuity of the segments, in normalized units
+    pub tolerance: f64,
+    /// Maximum number of iterations of the corrector
+    pub max_iterations: usize,
+    /// Current iteration of the corrector
+    pub current_iteration: usize,
+}
+
+impl<'a, E: ErrorCtrl> PeriodicMultipleShooting<'a, E> {
+    /// Builds the nodes by propagating the initial guess over the guessed period, such that the nodes are equidistant in time.
+    pub fn from_guess(
+        prop: &'a Propagator<'a, Cr3bpDynamics, E>,
+        guess: Orbit,
+        period: Duration,
+        num_nodes: usize,
+    ) -> Result<Self, MultipleShootingError> {
+        if num_nodes < 2 {
+            return Err(MultipleShootingError::TargetingError {
+                segment: 0,
+                source: TargetingError::UnderdeterminedProblem,
+            });
+        }
+        let duration = period / (num_nodes as f64);
+        let mut state = guess;
+        state.unset_stm();
+        let mut targets = Vec::with_capacity(num_nodes);
+        for segment in 0..num_nodes {
+            targets.push(PeriodicNode { state, duration });
+            if segment + 1 < num_nodes {
+                state = prop
+                    .with(state)
+                    .for_duration(duration)
+                    .with_context(|_| PropSnafu)
+                    .with_context(|_| TargetingSnafu { segment })?;
+            }
+        }
+
+        Ok(Self {
+            prop,
+            targets,
+            tolerance: 1e-11,
+            max_iterations: 50,
+            current_iteration: 0,
+        })
+    }
+
+    /// Corrects the nodes until the orbit is periodic, and returns the periodic orbit starting at the first node.
+    ///
+    /// The constraints are the continuity between consecutive nodes, the periodicity closure without its VY component (which is redundant
+    /// with the conservation of the Jacobi constant), and a phase constraint setting the Y component of the first node to zero.
+    pub fn solve(&mut self) -> Result<PeriodicOrbit, MultipleShootingError> {
+        let num_nodes = self.targets.len();
+        let time_s = characteristic_time(&self.targets[0].state.frame)
+            .with_context(|_| TargetingSnafu { segment: 0_usize })?;
+        let num_vars = VARS_PER_NODE * num_nodes;
+        let num_cons = 6 * num_nodes;
+
+        for it in 0..self.max_iterations {
+            self.current_iteration = it;
+            let mut g = DVector::zeros(num_cons);
+            let mut jac = DMatrix::zeros(num_cons, num_vars);
+
+            for (segment, node) in self.targets.iter().enumerate() {
+                let end = self
+                    .prop
+                    .with(node.state.with_stm())
+                    .for_duration(node.duration)
+                    .with_context(|_| PropSnafu)
+                    .with_context(|_| TargetingSnafu { segment })?;
+                let achieved = end.to_cartesian_vec();
+                let stm = end.stm().unwrap();
+                // Time derivative per characteristic time, since the durations are corrected in normalized units
+                let deriv = state_derivative(&self.prop.dynamics, &end)
+                    .with_context(|_| TargetingSnafu { segment })?
+                    * time_s;
+
+                let next = (segment + 1) % num_nodes;
+                let objectives: [Objective; 6] = self.targets[next].into();
+                // The closure skips the VY component of the periodicity
+                let closing = next == 0;
+                for (k, (i, obj)) in objectives
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, obj)| !(closing && obj.parameter == StateParameter::VY))
+                    .enumerate()
+                {
+                    let row = 6 * segment + k;
+                    g[row] = achieved[i] - obj.desired_value;
+                    for j in 0..6 {
+                        jac[(row, VARS_PER_NODE * segment + j)] = stm[(i, j)];
+                    }
+                    jac[(row, VARS_PER_NODE * next + i)] -= 1.0;
+                    jac[(row, VARS_PER_NODE * segment + 6)] = deriv[i];
+                }
+            }
+            // Phase constraint
+            g[num_cons - 1] = self.targets[0].state.y_km;
+            jac[(num_cons - 1, 1)] = 1.0;
+
+            debug!(
+                "Periodic multiple shooting iteration #{it}: |g| = {:.3e}",
+                g.norm()
+            );
+            if g.norm() < self.tolerance {
+                let period = self
+                    .targets
+                    .iter()
+                    .fold(Duration::ZERO, |acc, node| acc + node.duration);
+                return PeriodicOrbit::from_state(self.prop, self.targets[0].state, period)
+                    .with_context(|_| TargetingSnafu { segment: 0_usize });
+            }
+
+            let mut correction =
+                -pseudo_inverse!(&jac).with_context(|_| TargetingSnafu { segment: 0_usize })? * g;
+            // The durations are corrected in seconds
+            for segment in 0..num_nodes {
+                correction[VARS_PER_NODE * segment + 6] *= time_s;
+            }
+            update_nodes(&mut self.targets, &correction, VARS_PER_NODE);
+
+            // Each node starts at the end of the segment of the previous one
+            let mut epoch = self.targets[0].state.epoch;
+            for node in self.targets.iter_mut() {
+                node.state.epoch = epoch;
+                epoch += node.duration;
+            }
+        }
+
+        Err(MultipleShootingError::TargetingError {
+            segment: 0,
+            source: TargetingError::TooManyIterations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod ut_periodic_ms {
+    use super::*;
+    use crate::cosmic::{Bodies, Frame};
+    use crate::md::opti::periodic::{PeriodicOrbitCorrector, PeriodicSymmetry};
+    use crate::md::StateParameter;
+    use crate::time::Epoch;
+
+    #[test]
+    fn test_nrho_multiple_shooting() {
+        let frame = Frame::Synodic {
+            primary: Bodies::Earth,
+            secondary: Bodies::Luna,
+            mass_ratio: 0.012_150_585_609_624,
+            length_km: 384_400.0,
+            time_s: 375_190.0,
+        };
+        let prop = Propagator::default(Cr3bpDynamics::new());
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2022, 1, 1);
+
+        // Rough 9:2 NRHO guess, about 1.51 characteristic times
+        let guess = Orbit::cartesian(1.0221, 0.0, -0.1821, 0.0, -0.1033, 0.0, epoch, frame);
+        let period = 1.51 * 375_190.0 * Unit::Second;
+
+        let mut ms = PeriodicMultipleShooting::from_guess(&prop, guess, period, 6).unwrap();
+        let nrho = ms.solve().unwrap();
+        assert!(ms.current_iteration < 20);
+
+        let end = prop.with(nrho.state).for_duration(nrho.period).unwrap();
+        assert!((end.to_cartesian_vec() - nrho.state.to_cartesian_vec()).norm() < 1e-7);
+        assert!(nrho.state.y_km.abs() < 1e-11);
+
+        // Same family as the single shooting solution
+        let single = PeriodicOrbitCorrector::new(
+            &prop,
+            crate::md::opti::periodic::PeriodicSymmetry::XZPlane,
+        )
+        .holding(StateParameter::X)
+        .correct(guess)
+        .unwrap();
+        assert!((nrho.jacobi_constant - single.jacobi_constant).abs() < 1e-3);
+        assert!((nrho.period - single.period).abs() < 0.5 * Unit::Hour);
+
+        assert!(PeriodicMultipleShooting::from_guess(&prop, guess, period, 1).is_err());
+    }
+}
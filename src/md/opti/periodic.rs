@@ -0,0 +1,868 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use snafu::ResultExt;
+
+use crate::cosmic::{Frame, Orbit};
+use crate::dynamics::{Cr3bpDynamics, Dynamics};
+use crate::linalg::{DMatrix, DVector, Matrix6, Vector6};
+use crate::md::trajectory::Traj;
+use crate::md::{PropSnafu, StateParameter, TargetingError};
+use crate::propagators::error_ctrl::ErrorCtrl;
+use crate::propagators::Propagator;
+use crate::pseudo_inverse;
+use crate::time::{Duration, Epoch, Unit};
+use crate::NyxError;
+use crate::State;
+use num::Complex;
+use std::fmt;
+
+/// Maximum number of Newton iterations to locate the crossing of the plane of symmetry
+const MAX_CROSSING_ITERATIONS: usize = 20;
+/// Convergence tolerance on the crossing of the plane of symmetry, in normalized units
+const CROSSING_TOL: f64 = 1e-13;
+
+/// Symmetry of a periodic orbit of the circular restricted three body problem, used by the single shooting corrector.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PeriodicSymmetry {
+    /// Symmetric about the XZ plane, which the orbit crosses perpendicularly twice per period (Lyapunov, halo, NRHO, DRO).
+    /// The initial state is [x, 0, z, 0, vy, 0], and the half period ends at the next crossing of the XZ plane where vx = vz = 0.
+    XZPlane,
+    /// Symmetric about the X axis, which the orbit crosses perpendicularly twice per period (vertical orbits).
+    /// The initial state is [x, 0, 0, 0, vy, vz], and the half period ends at the next crossing of the XY plane where y = vx = 0.
+    XAxis,
+}
+
+/// Families of periodic orbits for which an initial guess can be built, so they may be corrected and continued.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PeriodicFamily {
+    /// Planar Lyapunov orbits around the collinear libration point L1, L2 or L3 (1, 2 or 3)
+    Lyapunov { point: usize },
+    /// Halo orbits around L1 or L2 (1 or 2); northern halos are above the XY plane at their crossing of the XZ plane closest to the secondary.
+    /// Their continuation towards the secondary leads to the near rectilinear halo orbits (NRHO).
+    Halo { point: usize, northern: bool },
+    /// Vertical (figure eight) orbits around the collinear libration point L1, L2 or L3 (1, 2 or 3)
+    Vertical { point: usize },
+    /// Distant retrograde orbits around the secondary
+    DistantRetrograde,
+}
+
+impl PeriodicFamily {
+    /// Returns the symmetry of the orbits of this family
+    pub fn symmetry(&self) -> PeriodicSymmetry {
+        match self {
+            Self::Vertical { .. } => PeriodicSymmetry::XAxis,
+            _ => PeriodicSymmetry::XZPlane,
+        }
+    }
+
+    /// Builds an initial guess of an orbit of this family in the provided synodic frame, from the provided normalized amplitude.
+    ///
+    /// The amplitude is along X for Lyapunov orbits, along Z for halo and vertical orbits, and the distance to the secondary on the X axis
+    /// for distant retrograde orbits. Lyapunov and vertical orbits use the linearized dynamics about the libration point, halo orbits use
+    /// Richardson's third order approximation, and distant retrograde orbits use Hill's approximation.
+    /// These guesses are only accurate for small amplitudes: larger orbits should be reached by continuation.
+    pub fn initial_guess(
+        &self,
+        amplitude: f64,
+        epoch: Epoch,
+        frame: Frame,
+    ) -> Result<Orbit, NyxError> {
+        let points = frame.libration_points()?;
+        let mu = frame.mass_ratio();
+        let collinear = |point: usize| -> Result<(f64, f64), NyxError> {
+            if !(1..=3).contains(&point) {
+                return Err(NyxError::CustomError {
+                    msg: format!("L{point} is not a collinear libration point"),
+                });
+            }
+            let x_l = points[point - 1][0];
+            // Second order coefficient of the expansion of the potential about this libration point
+            let c2 = (1.0 - mu) / (x_l + mu).abs().powi(3) + mu / (x_l - 1.0 + mu).abs().powi(3);
+            Ok((x_l, c2))
+        };
+
+        match *self {
+            Self::Lyapunov { point } => {
+                let (x_l, c2) = collinear(point)?;
+                let (omega, kappa) = in_plane_frequency(c2);
+                Ok(Orbit::cartesian(
+                    x_l - amplitude,
+                    0.0,
+                    0.0,
+                    0.0,
+                    kappa * omega * amplitude,
+                    0.0,
+                    epoch,
+                    frame,
+                ))
+            }
+            Self::Vertical { point } => {
+                let (x_l, c2) = collinear(point)?;
+                Ok(Orbit::cartesian(
+                    x_l,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    c2.sqrt() * amplitude,
+                    epoch,
+                    frame,
+                ))
+            }
+            Self::Halo { point, northern } => {
+                if !(1..=2).contains(&point) {
+                    return Err(NyxError::CustomError {
+                        msg: format!("halo orbit initial guesses are only available around L1 and L2, not L{point}"),
+                    });
+                }
+                let (x_l, _) = collinear(point)?;
+                let (x, z, vy) = richardson_halo(mu, x_l, point, northern, amplitude)?;
+                Ok(Orbit::cartesian(x, 0.0, z, 0.0, vy, 0.0, epoch, frame))
+            }
+            Self::DistantRetrograde => Ok(Orbit::cartesian(
+                1.0 - mu - amplitude,
+                0.0,
+                0.0,
+                0.0,
+                (mu / amplitude).sqrt() + amplitude,
+                0.0,
+                epoch,
+                frame,
+            )),
+        }
+    }
+}
+
+/// Returns the in-plane frequency and the ratio of the Y to X amplitudes of the linearized motion about a collinear libration point
+fn in_plane_frequency(c2: f64) -> (f64, f64) {
+    let omega2 =
+        (2.0 - c2 + ((c2 - 2.0).powi(2) + 4.0 * (c2 - 1.0) * (1.0 + 2.0 * c2)).sqrt()) / 2.0;
+    let omega = omega2.sqrt();
+    (omega, (omega2 + 1.0 + 2.0 * c2) / (2.0 * omega))
+}
+
+/// Returns the normalized x, z and vy components of a halo orbit at its crossing of the XZ plane closest to the secondary,
+/// from Richardson's third order approximation.
+///
+/// Source: Richardson, "Analytic construction of periodic orbits about the collinear points", Celestial Mechanics 22, 1980
+fn richardson_halo(
+    mu: f64,
+    x_l: f64,
+    point: usize,
+    northern: bool,
+    amplitude_z: f64,
+) -> Result<(f64, f64, f64), NyxError> {
+    // Distance from the libration point to the secondary, which is the unit length of the approximation
+    let gamma = (x_l - 1.0 + mu).abs();
+    let c = |n: i32| -> f64 {
+        if point == 1 {
+            (mu + (-1.0_f64).powi(n) * (1.0 - mu) * gamma.powi(n + 1) / (1.0 - gamma).powi(n + 1))
+                / gamma.powi(3)
+        } else {
+            (-1.0_f64).powi(n) * (mu + (1.0 - mu) * gamma.powi(n + 1) / (1.0 + gamma).powi(n + 1))
+                / gamma.powi(3)
+        }
+    };
+    let (c2, c3, c4) = (c(2), c(3), c(4));
+    let (lambda, k) = in_plane_frequency(c2);
+    let lambda2 = lambda.powi(2);
+
+    let d1 = 3.0 * lambda2 / k * (k * (6.0 * lambda2 - 1.0) - 2.0 * lambda);
+    let d2 = 8.0 * lambda2 / k * (k * (11.0 * lambda2 - 1.0) - 2.0 * lambda);
+
+    let a21 = 3.0 * c3 * (k.powi(2) - 2.0) / (4.0 * (1.0 + 2.0 * c2));
+    let a22 = 3.0 * c3 / (4.0 * (1.0 + 2.0 * c2));
+    let a23 = -3.0 * c3 * lambda / (4.0 * k * d1)
+        * (3.0 * k.powi(3) * lambda - 6.0 * k * (k - lambda) + 4.0);
+    let a24 = -3.0 * c3 * lambda / (4.0 * k * d1) * (2.0 + 3.0 * k * lambda);
+    let b21 = -3.0 * c3 * lambda / (2.0 * d1) * (3.0 * k * lambda - 4.0);
+    let b22 = 3.0 * c3 * lambda / d1;
+    let d21 = -c3 / (2.0 * lambda2);
+
+    let a31 = -9.0 * lambda / (4.0 * d2)
+        * (4.0 * c3 * (k * a23 - b21) + k * c4 * (4.0 + k.powi(2)))
+        + (9.0 * lambda2 + 1.0 - c2) / (2.0 * d2)
+            * (3.0 * c3 * (2.0 * a23 - k * b21) + c4 * (2.0 + 3.0 * k.powi(2)));
+    let a32 = -1.0 / d2
+        * (9.0 * lambda / 4.0 * (4.0 * c3 * (k * a24 - b22) + k * c4)
+            + 1.5 * (9.0 * lambda2 + 1.0 - c2) * (c3 * (k * b22 + d21 - 2.0 * a24) - c4));
+    let b31 = 3.0 / (8.0 * d2)
+        * (8.0 * lambda * (3.0 * c3 * (k * b21 - 2.0 * a23) - c4 * (2.0 + 3.0 * k.powi(2)))
+            + (9.0 * lambda2 + 1.0 + 2.0 * c2)
+                * (4.0 * c3 * (k * a23 - b21) + k * c4 * (4.0 + k.powi(2))));
+    let b32 = 1.0 / d2
+        * (9.0 * lambda * (c3 * (k * b22 + d21 - 2.0 * a24) - c4)
+            + 3.0 / 8.0 * (9.0 * lambda2 + 1.0 + 2.0 * c2) * (4.0 * c3 * (k * a24 - b22) + k * c4));
+    let d31 = 3.0 / (64.0 * lambda2) * (4.0 * c3 * a24 + c4);
+    let d32 = 3.0 / (64.0 * lambda2) * (4.0 * c3 * (a23 - d21) + c4 * (4.0 + k.powi(2)));
+
+    let s_denom = 2.0 * lambda * (lambda * (1.0 + k.powi(2)) - 2.0 * k);
+    let s1 = (1.5 * c3 * (2.0 * a21 * (k.powi(2) - 2.0) - a23 * (k.powi(2) + 2.0) - 2.0 * k * b21)
+        - 3.0 / 8.0 * c4 * (3.0 * k.powi(4) - 8.0 * k.powi(2) + 8.0))
+        / s_denom;
+    let s2 = (1.5
+        * c3
+        * (2.0 * a22 * (k.powi(2) - 2.0) + a24 * (k.powi(2) + 2.0) + 2.0 * k * b22 + 5.0 * d21)
+        + 3.0 / 8.0 * c4 * (12.0 - k.powi(2)))
+        / s_denom;
+    let l1 = -1.5 * c3 * (2.0 * a21 + a23 + 5.0 * d21) - 3.0 / 8.0 * c4 * (12.0 - k.powi(2))
+        + 2.0 * lambda2 * s1;
+    let l2 = 1.5 * c3 * (a24 - 2.0 * a22) + 9.0 / 8.0 * c4 + 2.0 * lambda2 * s2;
+
+    // Amplitudes in units of gamma
+    let az = amplitude_z / gamma;
+    let ax2 = (-(lambda2 - c2) - l2 * az.powi(2)) / l1;
+    if ax2 < 0.0 {
+        return Err(NyxError::MathDomain {
+            msg: format!("no halo orbit of amplitude {amplitude_z} around L{point} in the third order approximation"),
+        });
+    }
+    let ax = ax2.sqrt();
+    let omega = 1.0 + s1 * ax2 + s2 * az.powi(2);
+    let delta_m = if northern { 1.0 } else { -1.0 };
+
+    // State at τ1 = 0, i.e. on the XZ plane
+    let x = a21 * ax2 + a22 * az.powi(2) - ax
+        + (a23 * ax2 - a24 * az.powi(2))
+        + (a31 * ax.powi(3) - a32 * ax * az.powi(2));
+    let z = delta_m * az - 2.0 * delta_m * d21 * ax * az
+        + delta_m * (d32 * az * ax2 - d31 * az.powi(3));
+    let vy = lambda
+        * omega
+        * (k * ax
+            + 2.0 * (b21 * ax2 - b22 * az.powi(2))
+            + 3.0 * (b31 * ax.powi(3) - b32 * ax * az.powi(2)));
+
+    Ok((x_l + gamma * x, gamma * z, gamma * vy))
+}
+
+/// A periodic orbit of the circular restricted three body problem, with its stability information.
+#[derive(Copy, Clone, Debug)]
+pub struct PeriodicOrbit {
+    /// Initial state, on the plane or axis of symmetry of the orbit
+    pub state: Orbit,
+    /// Period of the orbit
+    pub period: Duration,
+    /// Jacobi constant of the orbit
+    pub jacobi_constant: f64,
+    /// Monodromy matrix, i.e. the state transition matrix over one period, in normalized units
+    pub monodromy: Matrix6<f64>,
+    /// Eigenvalues of the monodromy matrix
+    pub eigenvalues: Vector6<Complex<f64>>,
+    /// Stability indices ν = (λ + 1/λ) / 2 of the two non-trivial pairs of eigenvalues, sorted by decreasing magnitude.
+    /// The orbit is linearly stable if both indices are within [-1, 1].
+    pub stability_indices: [f64; 2],
+}
+
+impl PeriodicOrbit {
+    /// Builds the periodic orbit from its initial state and its period, by propagating the monodromy matrix
+    pub(crate) fn from_state<E: ErrorCtrl>(
+        prop: &Propagator<'_, Cr3bpDynamics, E>,
+        state: Orbit,
+        period: Duration,
+    ) -> Result<Self, TargetingError> {
+        let mut state = state;
+        state.unset_stm();
+        let monodromy = prop
+            .with(state.with_stm())
+            .for_duration(period)
+            .with_context(|_| PropSnafu)?
+            .stm()
+            .unwrap();
+        let eigenvalues = monodromy.complex_eigenvalues();
+
+        // The eigenvalues come in reciprocal pairs, one of which is trivial (both are one): sort the indices by distance to that trivial pair
+        let mut indices = eigenvalues
+            .iter()
+            .map(|lambda| ((lambda + lambda.inv()) / 2.0).re)
+            .collect::<Vec<f64>>();
+        indices.sort_by(|a, b| (b - 1.0).abs().partial_cmp(&(a - 1.0).abs()).unwrap());
+        let mut stability_indices = [
+            (indices[0] + indices[1]) / 2.0,
+            (indices[2] + indices[3]) / 2.0,
+        ];
+        stability_indices.sort_by(|a, b| b.abs().partial_cmp(&a.abs()).unwrap());
+
+        Ok(Self {
+            state,
+            period,
+            jacobi_constant: state.jacobi_constant(),
+            monodromy,
+            eigenvalues,
+            stability_indices,
+        })
+    }
+
+    /// Propagates this orbit over one period with its STM, e.g. to generate its invariant manifolds
+    pub fn to_traj<E: ErrorCtrl>(
+        &self,
+        prop: &Propagator<'_, Cr3bpDynamics, E>,
+    ) -> Result<Traj<Orbit>, TargetingError> {
+        let (_, traj) = prop
+            .with(self.state.with_stm())
+            .for_duration_with_traj(self.period)
+            .with_context(|_| PropSnafu)?;
+        Ok(traj)
+    }
+
+    /// Returns whether this orbit is linearly stable, i.e. whether its stability indices are within [-1, 1]
+    pub fn is_stable(&self) -> bool {
+        self.stability_indices
+            .iter()
+            .all(|nu| nu.abs() <= 1.0 + 1e-6)
+    }
+}
+
+impl fmt::Display for PeriodicOrbit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}\tperiod = {}\tC = {:.9}\tν = [{:.6}, {:.6}]",
+            self.state,
+            self.period,
+            self.jacobi_constant,
+            self.stability_indices[0],
+            self.stability_indices[1]
+        )
+    }
+}
+
+/// Single shooting differential corrector of periodic orbits of the circular restricted three body problem, which exploits their symmetry,
+/// and natural parameter or pseudo-arclength continuation of their families.
+///
+/// The corrector propagates the initial state until the next crossing of the plane of symmetry, and corrects the initial state
+/// such that this crossing is perpendicular: the period is then twice this half period.
+/// Source: Howell, "Three-dimensional, periodic, 'halo' orbits", Celestial Mechanics 32, 1984
+pub struct PeriodicOrbitCorrector<'a, E: ErrorCtrl> {
+    /// The propagator setup (kind, stages, etc.), which must use the CR3BP dynamics
+    pub prop: &'a Propagator<'a, Cr3bpDynamics, E>,
+    /// Symmetry of the periodic orbits to correct
+    pub symmetry: PeriodicSymmetry,
+    /// Component of the initial state held constant by the single shooting and the natural parameter continuation:
+    /// X, Z or VY for the XZ plane symmetry, and X, VY or VZ for the X axis symmetry
+    pub fixed: StateParameter,
+    /// Convergence tolerance on the perpendicular crossing conditions, in normalized units
+    pub tolerance: f64,
+    /// Maximum number of iterations of the corrector
+    pub max_iterations: usize,
+    /// Maximum duration to search for the crossing of the plane of symmetry, in characteristic times of the frame
+    pub max_half_period: f64,
+}
+
+impl<'a, E: ErrorCtrl> PeriodicOrbitCorrector<'a, E> {
+    /// Initializes a corrector for orbits with the provided symmetry, holding the X component constant
+    pub fn new(prop: &'a Propagator<'a, Cr3bpDynamics, E>, symmetry: PeriodicSymmetry) -> Self {
+        Self {
+            prop,
+            symmetry,
+            fixed: StateParameter::X,
+            tolerance: 1e-11,
+            max_iterations: 50,
+            max_half_period: 10.0,
+        }
+    }
+
+    /// Initializes a corrector for the provided family of orbits, holding the component which defines the amplitude of the initial guess
+    /// constant: Z for halo orbits, VZ for vertical orbits, and X otherwise
+    pub fn for_family(prop: &'a Propagator<'a, Cr3bpDynamics, E>, family: PeriodicFamily) -> Self {
+        let fixed = match family {
+            PeriodicFamily::Halo { .. } => StateParameter::Z,
+            PeriodicFamily::Vertical { .. } => StateParameter::VZ,
+            _ => StateParameter::X,
+        };
+        Self::new(prop, family.symmetry()).holding(fixed)
+    }
+
+    /// Returns a copy of this corrector which holds the provided component of the initial state constant
+    pub fn holding(mut self, fixed: StateParameter) -> Self {
+        self.fixed = fixed;
+        self
+    }
+
+    /// Corrects the provided initial guess into a periodic orbit, holding the `fixed` component constant.
+    pub fn correct(&self, guess: Orbit) -> Result<PeriodicOrbit, TargetingError> {
+        let x0 = self.symmetric(guess)?;
+        let (candidates, constraints) = self.layout(&x0);
+        let fixed = self.fixed_index(&candidates)?;
+        let free: Vec<usize> = candidates.into_iter().filter(|i| *i != fixed).collect();
+        self.solve(x0, &free, &constraints, None)
+    }
+
+    /// Generates a family of periodic orbits from the provided guess by natural parameter continuation, i.e. incrementing the `fixed`
+    /// component of the initial state by `step` (normalized) between each of the `members` of the family.
+    pub fn natural_parameter_continuation(
+        &self,
+        guess: Orbit,
+        step: f64,
+        members: usize,
+    ) -> Result<Vec<PeriodicOrbit>, TargetingError> {
+        let x0 = self.symmetric(guess)?;
+        let (candidates, constraints) = self.layout(&x0);
+        let fixed = self.fixed_index(&candidates)?;
+        let free: Vec<usize> = candidates.iter().copied().filter(|i| *i != fixed).collect();
+
+        let mut family: Vec<PeriodicOrbit> = Vec::with_capacity(members);
+        family.push(self.solve(x0, &free, &constraints, None)?);
+        while family.len() < members {
+            let last = family[family.len() - 1].state.to_cartesian_vec();
+            // Predict along the tangent to the family for the first step, and use a secant predictor once two members are known
+            let next = match family.len() {
+                1 => {
+                    let tangent = self.tangent(&family[0].state, &candidates, &constraints)?;
+                    let scale = tangent[candidates.iter().position(|i| *i == fixed).unwrap()];
+                    let mut next = last;
+                    for (k, i) in candidates.iter().enumerate() {
+                        next[*i] += step * tangent[k] / scale;
+                    }
+                    next
+                }
+                n => 2.0 * last - family[n - 2].state.to_cartesian_vec(),
+            };
+            let member = self.solve(
+                Orbit::cartesian_vec(&next, x0.epoch, x0.frame),
+                &free,
+                &constraints,
+                None,
+            )?;
+            info!("Family member #{}: {member}", family.len());
+            family.push(member);
+        }
+        Ok(family)
+    }
+
+    /// Generates a family of periodic orbits from the provided guess by pseudo-arclength continuation, i.e. stepping by `step` (normalized)
+    /// along the tangent to the family between each of the `members`. A positive step initially increases the `fixed` component.
+    ///
+    /// Unlike the natural parameter continuation, this allows following a family past the turning points of any of its components.
+    pub fn pseudo_arclength_continuation(
+        &self,
+        guess: Orbit,
+        step: f64,
+        members: usize,
+    ) -> Result<Vec<PeriodicOrbit>, TargetingError> {
+        let first = self.correct(guess)?;
+        let (candidates, constraints) = self.layout(&first.state);
+        let fixed_index = self.fixed_index(&candidates)?;
+        let fixed = candidates.iter().position(|i| *i == fixed_index).unwrap();
+
+        let mut tangent = self.tangent(&first.state, &candidates, &constraints)?;
+        if tangent[fixed] < 0.0 {
+            tangent = -tangent;
+        }
+
+        let mut family = Vec::with_capacity(members);
+        family.push(first);
+        while family.len() < members {
+            let last = family[family.len() - 1].state;
+            let last_free = DVector::from_iterator(
+                candidates.len(),
+                candidates.iter().map(|i| last.to_cartesian_vec()[*i]),
+            );
+            // Predict along the tangent, then correct in the plane orthogonal to it
+            let mut next = last.to_cartesian_vec();
+            for (k, i) in candidates.iter().enumerate() {
+                next[*i] += step * tangent[k];
+            }
+            let member = self.solve(
+                Orbit::cartesian_vec(&next, last.epoch, last.frame),
+                &candidates,
+                &constraints,
+                Some((&last_free, &tangent, step)),
+            )?;
+            info!("Family member #{}: {member}", family.len());
+
+            let new_tangent = self.tangent(&member.state, &candidates, &constraints)?;
+            tangent = if new_tangent.dot(&tangent) < 0.0 {
+                -new_tangent
+            } else {
+                new_tangent
+            };
+            family.push(member);
+        }
+        Ok(family)
+    }
+
+    /// Returns the provided state with the components which must be zero for this symmetry set to zero
+    fn symmetric(&self, guess: Orbit) -> Result<Orbit, TargetingError> {
+        if !guess.frame.is_synodic() {
+            return Err(TargetingError::FrameError {
+                msg: format!(
+                    "periodic orbits must be corrected in a synodic frame, not {}",
+                    guess.frame
+                ),
+            });
+        }
+        let mut x0 = guess;
+        x0.unset_stm();
+        x0.y_km = 0.0;
+        x0.vx_km_s = 0.0;
+        match self.symmetry {
+            PeriodicSymmetry::XZPlane => x0.vz_km_s = 0.0,
+            PeriodicSymmetry::XAxis => x0.z_km = 0.0,
+        }
+        Ok(x0)
+    }
+
+    /// Returns the components of the initial state which may be corrected, and the components of the crossing state which must be zero
+    fn layout(&self, x0: &Orbit) -> (Vec<usize>, Vec<usize>) {
+        match self.symmetry {
+            PeriodicSymmetry::XZPlane => {
+                if x0.z_km.abs() < f64::EPSILON {
+                    // Planar orbits remain planar, so only the in-plane components are corrected
+                    (vec![0, 4], vec![3])
+                } else {
+                    (vec![0, 2, 4], vec![3, 5])
+                }
+            }
+            PeriodicSymmetry::XAxis => (vec![0, 4, 5], vec![1, 3]),
+        }
+    }
+
+    /// Returns the index of the fixed component, if it may be corrected for this symmetry
+    fn fixed_index(&self, candidates: &[usize]) -> Result<usize, TargetingError> {
+        let index = match self.fixed {
+            StateParameter::X => 0,
+            StateParameter::Z => 2,
+            StateParameter::VY => 4,
+            StateParameter::VZ => 5,
+            _ => usize::MAX,
+        };
+        if candidates.contains(&index) {
+            Ok(index)
+        } else {
+            Err(TargetingError::VariableError {
+                msg: format!(
+                    "{} cannot be held constant for a {:?} symmetric orbit",
+                    self.fixed, self.symmetry
+                ),
+            })
+        }
+    }
+
+    /// Propagates the provided initial state, with its STM, until the next crossing of the plane of symmetry
+    fn half_period(&self, x0: &Orbit) -> Result<Orbit, TargetingError> {
+        let crossing = self.crossing_index();
+        let time_s = characteristic_time(&x0.frame)?;
+        let max_duration = self.max_half_period * time_s * Unit::Second;
+
+        let mut instance = self.prop.with(x0.with_stm());
+        let mut prev = instance.state;
+        loop {
+            instance.single_step().with_context(|_| PropSnafu)?;
+            let cur = instance.state;
+            if prev.to_cartesian_vec()[crossing] * cur.to_cartesian_vec()[crossing] < 0.0 {
+                break;
+            }
+            if cur.epoch - x0.epoch > max_duration {
+                return Err(TargetingError::Verification {
+                    msg: format!(
+                        "no crossing of the plane of symmetry within {} characteristic times",
+                        self.max_half_period
+                    ),
+                });
+            }
+            prev = cur;
+        }
+
+        // Newton iterations on the time of the crossing, always propagating forward from the last state before the crossing
+        let mut dt_s = 0.0;
+        let mut state = prev;
+        for _ in 0..MAX_CROSSING_ITERATIONS {
+            let vec = state.to_cartesian_vec();
+            let delta = -vec[crossing] / vec[crossing + 3];
+            if delta.abs() < CROSSING_TOL {
+                return Ok(state);
+            }
+            dt_s += delta * time_s;
+            state = self
+                .prop
+                .with(prev)
+                .for_duration(dt_s * Unit::Second)
+                .with_context(|_| PropSnafu)?;
+        }
+        Err(TargetingError::TooManyIterations)
+    }
+
+    /// Component of the state which is zero on the plane of symmetry
+    fn crossing_index(&self) -> usize {
+        match self.symmetry {
+            PeriodicSymmetry::XZPlane => 1,
+            PeriodicSymmetry::XAxis => 2,
+        }
+    }
+
+    /// Returns the crossing conditions and their Jacobian with respect to the provided components of the initial state,
+    /// accounting for the variation of the time of the crossing
+    fn crossing_jacobian(
+        &self,
+        xf: &Orbit,
+        components: &[usize],
+        constraints: &[usize],
+    ) -> Result<(DVector<f64>, DMatrix<f64>), TargetingError> {
+        let crossing = self.crossing_index();
+        let stm = xf.stm().unwrap();
+        let deriv = state_derivative(&self.prop.dynamics, xf)?;
+        let vec = xf.to_cartesian_vec();
+
+        let mut g = DVector::zeros(constraints.len());
+        let mut jac = DMatrix::zeros(constraints.len(), components.len());
+        for (r, gi) in constraints.iter().enumerate() {
+            g[r] = vec[*gi];
+            for (c, j) in components.iter().enumerate() {
+                jac[(r, c)] = stm[(*gi, *j)] - deriv[*gi] / deriv[crossing] * stm[(crossing, *j)];
+            }
+        }
+        Ok((g, jac))
+    }
+
+    /// Returns the unit tangent to the family at the provided periodic orbit, i.e. the null vector of the Jacobian of the crossing conditions
+    fn tangent(
+        &self,
+        x0: &Orbit,
+        components: &[usize],
+        constraints: &[usize],
+    ) -> Result<DVector<f64>, TargetingError> {
+        let xf = self.half_period(x0)?;
+        let (_, jac) = self.crossing_jacobian(&xf, components, constraints)?;
+        // Square the Jacobian with a row of zeros so that the SVD includes the null space
+        let mut square = DMatrix::zeros(components.len(), components.len());
+        square.rows_mut(0, constraints.len()).copy_from(&jac);
+        let svd = square.svd(false, true);
+        let v_t = svd.v_t.unwrap();
+        let null = svd
+            .singular_values
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .unwrap()
+            .0;
+        Ok(v_t.row(null).transpose().normalize())
+    }
+
+    /// Corrects the provided components of the initial state until the crossing conditions are met.
+    /// If an arclength constraint is provided as (previous components, tangent, step), it is added to the problem.
+    fn solve(
+        &self,
+        x0: Orbit,
+        components: &[usize],
+        constraints: &[usize],
+        arclength: Option<(&DVector<f64>, &DVector<f64>, f64)>,
+    ) -> Result<PeriodicOrbit, TargetingError> {
+        let mut x0 = x0;
+        for it in 0..self.max_iterations {
+            let xf = self.half_period(&x0)?;
+            let (mut g, mut jac) = self.crossing_jacobian(&xf, components, constraints)?;
+            let vec = x0.to_cartesian_vec();
+
+            if let Some((prev, tangent, step)) = arclength {
+                let cur =
+                    DVector::from_iterator(components.len(), components.iter().map(|i| vec[*i]));
+                g = g.push((cur - prev).dot(tangent) - step);
+                jac = jac.insert_row(constraints.len(), 0.0);
+                jac.row_mut(constraints.len())
+                    .copy_from(&tangent.transpose());
+            }
+
+            debug!(
+                "Periodic orbit corrector iteration #{it}: |g| = {:.3e}",
+                g.norm()
+            );
+            if g.norm() < self.tolerance {
+                let mut state = x0;
+                state.unset_stm();
+                return PeriodicOrbit::from_state(self.prop, state, 2 * (xf.epoch - x0.epoch));
+            }
+
+            let correction = -pseudo_inverse!(&jac)? * g;
+            let mut next = vec;
+            for (k, i) in components.iter().enumerate() {
+                next[*i] += correction[k];
+            }
+            x0 = Orbit::cartesian_vec(&next, x0.epoch, x0.frame);
+        }
+        Err(TargetingError::TooManyIterations)
+    }
+}
+
+/// Returns the time derivative of the provided state, per second, ignoring its STM
+pub(crate) fn state_derivative(
+    dynamics: &Cr3bpDynamics,
+    state: &Orbit,
+) -> Result<Vector6<f64>, TargetingError> {
+    let mut osc = *state;
+    osc.unset_stm();
+    let deriv =
+        dynamics
+            .eom(0.0, &osc.as_vector(), &osc)
+            .map_err(|e| TargetingError::Verification {
+                msg: format!("{e}"),
+            })?;
+    Ok(deriv.fixed_rows::<6>(0).into_owned())
+}
+
+/// Returns the characteristic time of the provided synodic frame, in seconds
+pub(crate) fn characteristic_time(frame: &Frame) -> Result<f64, TargetingError> {
+    match frame {
+        Frame::Synodic { time_s, .. } => Ok(*time_s),
+        _ => Err(TargetingError::FrameError {
+            msg: format!("{frame} is not a synodic frame"),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod ut_periodic {
+    use super::*;
+    use crate::cosmic::Bodies;
+
+    fn earth_moon() -> Frame {
+        Frame::Synodic {
+            primary: Bodies::Earth,
+            secondary: Bodies::Luna,
+            mass_ratio: 0.012_150_585_609_624,
+            length_km: 384_400.0,
+            time_s: 375_190.0,
+        }
+    }
+
+    /// Checks that the orbit closes on itself after one period
+    fn assert_periodic(
+        prop: &Propagator<'_, Cr3bpDynamics, impl ErrorCtrl>,
+        orbit: &PeriodicOrbit,
+    ) {
+        let end = prop.with(orbit.state).for_duration(orbit.period).unwrap();
+        let err = (end.to_cartesian_vec() - orbit.state.to_cartesian_vec()).norm();
+        assert!(err < 1e-7, "{orbit} does not close: {err:e}");
+    }
+
+    #[test]
+    fn test_lyapunov_natural_continuation() {
+        let prop = Propagator::default(Cr3bpDynamics::new());
+        let frame = earth_moon();
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2022, 1, 1);
+        let family = PeriodicFamily::Lyapunov { point: 1 };
+        let guess = family.initial_guess(0.005, epoch, frame).unwrap();
+        let corrector = PeriodicOrbitCorrector::for_family(&prop, family);
+
+        let lyapunovs = corrector
+            .natural_parameter_continuation(guess, -0.005, 4)
+            .unwrap();
+        assert_eq!(lyapunovs.len(), 4);
+        for (n, orbit) in lyapunovs.iter().enumerate() {
+            assert_periodic(&prop, orbit);
+            // Lyapunov orbits are unstable
+            assert!(!orbit.is_stable());
+            assert!(orbit.stability_indices[0] > 100.0);
+            assert!((orbit.state.x_km - (guess.x_km - 0.005 * n as f64)).abs() < 1e-12);
+            if n > 0 {
+                // Larger orbits have a lower energy and a longer period
+                assert!(orbit.jacobi_constant < lyapunovs[n - 1].jacobi_constant);
+                assert!(orbit.period > lyapunovs[n - 1].period);
+            }
+        }
+        // Small amplitude orbits have the period of the linearized dynamics, about 11.9 days
+        let period_days = lyapunovs[0].period.to_unit(Unit::Day);
+        assert!((period_days - 11.9).abs() < 0.2, "{period_days} days");
+    }
+
+    #[test]
+    fn test_halo_and_nrho() {
+        let prop = Propagator::default(Cr3bpDynamics::new());
+        let frame = earth_moon();
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2022, 1, 1);
+
+        // Southern L2 halo from Richardson's approximation
+        let family = PeriodicFamily::Halo {
+            point: 2,
+            northern: false,
+        };
+        let guess = family.initial_guess(0.03, epoch, frame).unwrap();
+        assert!(guess.z_km < 0.0);
+        let corrector = PeriodicOrbitCorrector::for_family(&prop, family);
+        let halo = corrector.correct(guess).unwrap();
+        assert_periodic(&prop, &halo);
+        assert!((halo.state.z_km - guess.z_km).abs() < 1e-12);
+        assert!((halo.period.to_unit(Unit::Day) - 14.8).abs() < 0.3);
+
+        // Follow the family towards the Moon
+        let halos = corrector
+            .pseudo_arclength_continuation(halo.state, -0.01, 4)
+            .unwrap();
+        for pair in halos.windows(2) {
+            assert_periodic(&prop, &pair[1]);
+            assert!(pair[1].state.z_km < pair[0].state.z_km);
+        }
+
+        // 9:2 synodic resonant NRHO (e.g. Zimovan-Spreen et al., 2020): nearly stable and with a period of about 6.6 days
+        let nrho = PeriodicOrbitCorrector::new(&prop, PeriodicSymmetry::XZPlane)
+            .correct(Orbit::cartesian(
+                1.0221, 0.0, -0.1821, 0.0, -0.1033, 0.0, epoch, frame,
+            ))
+            .unwrap();
+        assert_periodic(&prop, &nrho);
+        assert!((nrho.period.to_unit(Unit::Day) - 6.56).abs() < 0.1);
+        assert!(
+            nrho.stability_indices.iter().all(|nu| nu.abs() < 1.5),
+            "{nrho}"
+        );
+    }
+
+    #[test]
+    fn test_vertical_and_dro() {
+        let prop = Propagator::default(Cr3bpDynamics::new());
+        let frame = earth_moon();
+        let epoch = Epoch::from_gregorian_tai_at_midnight(2022, 1, 1);
+
+        let family = PeriodicFamily::Vertical { point: 1 };
+        let vertical = PeriodicOrbitCorrector::for_family(&prop, family)
+            .correct(family.initial_guess(0.01, epoch, frame).unwrap())
+            .unwrap();
+        assert_periodic(&prop, &vertical);
+        assert!(vertical.state.vz_km_s > 0.0);
+
+        // Distant retrograde orbits are stable
+        let family = PeriodicFamily::DistantRetrograde;
+        let dros = PeriodicOrbitCorrector::for_family(&prop, family)
+            .pseudo_arclength_continuation(
+                family.initial_guess(0.1, epoch, frame).unwrap(),
+                -0.02,
+                3,
+            )
+            .unwrap();
+        for dro in &dros {
+            assert_periodic(&prop, dro);
+            assert!(dro.is_stable(), "{dro}");
+        }
+        assert!(dros[2].state.x_km < dros[0].state.x_km);
+
+        // Invalid configurations
+        assert!(PeriodicFamily::Halo {
+            point: 3,
+            northern: true
+        }
+        .initial_guess(0.01, epoch, frame)
+        .is_err());
+        assert!(
+            PeriodicOrbitCorrector::for_family(&prop, PeriodicFamily::Vertical { point: 1 })
+                .holding(StateParameter::Z)
+                .correct(vertical.state)
+                .is_err()
+        );
+    }
+}
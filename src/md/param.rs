@@ -17,6 +17,7 @@
 */
 
 use super::NyxError;
+use crate::cosmic::MeanElementsKind;
 use arrow::datatypes::{DataType, Field};
 use core::fmt;
 use enum_iterator::Sequence;
@@ -44,6 +45,30 @@ pub enum StateParameter {
     BdotT,
     /// B-Plane LTOF
     BLTOF,
+    /// Brouwer-Lyddane mean short semi major axis (km)
+    BrouwerShortSMA,
+    /// Brouwer-Lyddane mean short eccentricity (no unit)
+    BrouwerShortEcc,
+    /// Brouwer-Lyddane mean short inclination (deg)
+    BrouwerShortInc,
+    /// Brouwer-Lyddane mean short right ascension of the ascending node (deg)
+    BrouwerShortRAAN,
+    /// Brouwer-Lyddane mean short argument of periapsis (deg)
+    BrouwerShortAoP,
+    /// Brouwer-Lyddane mean short mean anomaly (deg)
+    BrouwerShortMA,
+    /// Brouwer-Lyddane mean long semi major axis (km)
+    BrouwerLongSMA,
+    /// Brouwer-Lyddane mean long eccentricity (no unit)
+    BrouwerLongEcc,
+    /// Brouwer-Lyddane mean long inclination (deg)
+    BrouwerLongInc,
+    /// Brouwer-Lyddane mean long right ascension of the ascending node (deg)
+    BrouwerLongRAAN,
+    /// Brouwer-Lyddane mean long argument of periapsis (deg)
+    BrouwerLongAoP,
+    /// Brouwer-Lyddane mean long mean anomaly (deg)
+    BrouwerLongMA,
     /// C_3 in (km/s)^2
     C3,
     /// Coefficient of drag
@@ -58,10 +83,42 @@ pub enum StateParameter {
     Epoch,
     /// Eccentric anomaly (deg)
     EccentricAnomaly,
+    /// Radial component of the dynamic model compensation acceleration (km/s^2)
+    DmcR,
+    /// In-track component of the dynamic model compensation acceleration (km/s^2)
+    DmcI,
+    /// Cross-track component of the dynamic model compensation acceleration (km/s^2)
+    DmcC,
     /// Eccentricity (no unit)
     Eccentricity,
+    /// Radial constant empirical acceleration (km/s^2)
+    EmpiricalConstantR,
+    /// In-track constant empirical acceleration (km/s^2)
+    EmpiricalConstantI,
+    /// Cross-track constant empirical acceleration (km/s^2)
+    EmpiricalConstantC,
+    /// Radial empirical acceleration amplitude of the cosine of the argument of latitude (km/s^2)
+    EmpiricalCosR,
+    /// In-track empirical acceleration amplitude of the cosine of the argument of latitude (km/s^2)
+    EmpiricalCosI,
+    /// Cross-track empirical acceleration amplitude of the cosine of the argument of latitude (km/s^2)
+    EmpiricalCosC,
+    /// Radial empirical acceleration amplitude of the sine of the argument of latitude (km/s^2)
+    EmpiricalSinR,
+    /// In-track empirical acceleration amplitude of the sine of the argument of latitude (km/s^2)
+    EmpiricalSinI,
+    /// Cross-track empirical acceleration amplitude of the sine of the argument of latitude (km/s^2)
+    EmpiricalSinC,
     /// Specific energy
     Energy,
+    /// Equinoctial element h = e sin(ω + Ω) (no unit)
+    EquinoctialH,
+    /// Equinoctial element k = e cos(ω + Ω) (no unit)
+    EquinoctialK,
+    /// Equinoctial element p = tan(i/2) sin(Ω) (no unit)
+    EquinoctialP,
+    /// Equinoctial element q = tan(i/2) cos(Ω) (no unit)
+    EquinoctialQ,
     /// Flight path angle (deg)
     FlightPathAngle,
     /// fuel mass in kilograms
@@ -88,18 +145,48 @@ pub enum StateParameter {
     Inclination,
     /// Specific impulse (isp) in seconds
     Isp,
+    /// Jacobi constant of the circular restricted three body problem (no unit), only valid in a synodic frame
+    JacobiConstant,
+    /// Kozai mean semi major axis (km)
+    KozaiSMA,
+    /// Kozai mean eccentricity (no unit)
+    KozaiEcc,
+    /// Kozai mean inclination (deg)
+    KozaiInc,
+    /// Kozai mean right ascension of the ascending node (deg)
+    KozaiRAAN,
+    /// Kozai mean argument of periapsis (deg)
+    KozaiAoP,
+    /// Kozai mean mean anomaly (deg)
+    KozaiMA,
     /// Mean anomaly (deg)
     MeanAnomaly,
+    /// Mean longitude, M + ω + Ω (deg)
+    MeanLongitude,
+    /// Modified equinoctial element f = e cos(ω + Ω) (no unit)
+    ModEquinoctialF,
+    /// Modified equinoctial element g = e sin(ω + Ω) (no unit)
+    ModEquinoctialG,
+    /// Modified equinoctial element h = tan(i/2) cos(Ω) (no unit)
+    ModEquinoctialH,
+    /// Modified equinoctial element k = tan(i/2) sin(Ω) (no unit)
+    ModEquinoctialK,
     /// Periapsis, shortcut for TA == 0.0
     Periapsis,
     /// Radius of periapse (km)
     PeriapsisRadius,
     /// Orbital period (s)
     Period,
+    /// Scale factor of the planetary radiation pressure (albedo and infrared) (no unit)
+    RadiationScale,
     /// Right ascension (deg)
     RightAscension,
     /// Right ascension of the ascending node (deg)
     RAAN,
+    /// Clock angle of the solar sail about the sunlight (deg)
+    SailClock,
+    /// Cone angle between the solar sail normal and the sunlight (deg)
+    SailCone,
     /// Norm of the radius vector
     Rmag,
     /// Semi parameter (km)
@@ -110,6 +197,8 @@ pub enum StateParameter {
     SemiMinorAxis,
     /// Thrust (Newtons)
     Thrust,
+    /// Throttle level of the guidance law, between 0.0 and 1.0 (no unit)
+    Throttle,
     /// True anomaly
     TrueAnomaly,
     /// True longitude
@@ -137,7 +226,18 @@ impl StateParameter {
     /// Returns the default event finding precision in the unit of that parameter
     pub fn default_event_precision(&self) -> f64 {
         match self {
-            Self::Eccentricity => 1e-5,
+            Self::Eccentricity
+            | Self::BrouwerShortEcc
+            | Self::BrouwerLongEcc
+            | Self::KozaiEcc
+            | Self::EquinoctialH
+            | Self::EquinoctialK
+            | Self::EquinoctialP
+            | Self::EquinoctialQ
+            | Self::ModEquinoctialF
+            | Self::ModEquinoctialG
+            | Self::ModEquinoctialH
+            | Self::ModEquinoctialK => 1e-5,
             // Non anomaly angles
             Self::AoL
             | Self::AoP
@@ -149,7 +249,16 @@ impl StateParameter {
             | Self::RightAscension
             | Self::RAAN
             | Self::TrueLongitude
-            | Self::VelocityDeclination => 1e-1,
+            | Self::VelocityDeclination
+            | Self::BrouwerShortInc
+            | Self::BrouwerLongInc
+            | Self::KozaiInc
+            | Self::BrouwerShortRAAN
+            | Self::BrouwerLongRAAN
+            | Self::KozaiRAAN
+            | Self::BrouwerShortAoP
+            | Self::BrouwerLongAoP
+            | Self::KozaiAoP => 1e-1,
 
             // Anomaly angles
             Self::Apoapsis
@@ -157,7 +266,11 @@ impl StateParameter {
             | Self::MeanAnomaly
             | Self::EccentricAnomaly
             | Self::HyperbolicAnomaly
-            | Self::TrueAnomaly => 1e-3,
+            | Self::TrueAnomaly
+            | Self::MeanLongitude
+            | Self::BrouwerShortMA
+            | Self::BrouwerLongMA
+            | Self::KozaiMA => 1e-3,
 
             // Distances
             Self::ApoapsisRadius
@@ -175,13 +288,17 @@ impl StateParameter {
             | Self::SemiMinorAxis
             | Self::X
             | Self::Y
-            | Self::Z => 1e-3,
+            | Self::Z
+            | Self::BrouwerShortSMA
+            | Self::BrouwerLongSMA
+            | Self::KozaiSMA => 1e-3,
 
             // Velocities
             Self::C3 | Self::VX | Self::VY | Self::VZ | Self::Vmag => 1e-3,
 
             // Special
             Self::Energy => 1e-3,
+            Self::JacobiConstant => 1e-9,
             Self::DryMass | Self::FuelMass => 1e-3,
             Self::Period => 1e-1,
             _ => unimplemented!("{self} cannot be used for event finding"),
@@ -193,9 +310,38 @@ impl StateParameter {
         matches!(&self, Self::BdotR | Self::BdotT | Self::BLTOF)
     }
 
+    /// Returns whether this parameter is a mean orbital element (Brouwer-Lyddane or Kozai)
+    pub const fn is_mean_element(&self) -> bool {
+        self.mean_elements_kind().is_some()
+    }
+
     /// Returns whether this is an orbital parameter
     pub const fn is_orbital(&self) -> bool {
-        !self.is_for_spacecraft() && !matches!(self, Self::Apoapsis | Self::Periapsis | Self::Epoch)
+        !self.is_for_spacecraft()
+            && !self.is_empirical()
+            && !matches!(
+                self,
+                Self::Apoapsis | Self::Periapsis | Self::Epoch | Self::RadiationScale
+            )
+    }
+
+    /// Returns whether this parameter is an empirical acceleration, only applicable to an orbit with estimated empirical accelerations
+    pub const fn is_empirical(&self) -> bool {
+        matches!(
+            &self,
+            Self::EmpiricalConstantR
+                | Self::EmpiricalConstantI
+                | Self::EmpiricalConstantC
+                | Self::EmpiricalCosR
+                | Self::EmpiricalCosI
+                | Self::EmpiricalCosC
+                | Self::EmpiricalSinR
+                | Self::EmpiricalSinI
+                | Self::EmpiricalSinC
+                | Self::DmcR
+                | Self::DmcI
+                | Self::DmcC
+        )
     }
 
     /// Returns whether this parameter is only applicable to a spacecraft state
@@ -209,6 +355,9 @@ impl StateParameter {
                 | Self::Isp
                 | Self::GuidanceMode
                 | Self::Thrust
+                | Self::Throttle
+                | Self::SailCone
+                | Self::SailClock
         )
     }
 
@@ -231,7 +380,22 @@ impl StateParameter {
             | Self::MeanAnomaly
             | Self::EccentricAnomaly
             | Self::HyperbolicAnomaly
-            | Self::TrueAnomaly => "deg",
+            | Self::TrueAnomaly
+            | Self::MeanLongitude
+            | Self::BrouwerShortInc
+            | Self::BrouwerLongInc
+            | Self::KozaiInc
+            | Self::BrouwerShortRAAN
+            | Self::BrouwerLongRAAN
+            | Self::KozaiRAAN
+            | Self::BrouwerShortAoP
+            | Self::BrouwerLongAoP
+            | Self::KozaiAoP
+            | Self::BrouwerShortMA
+            | Self::BrouwerLongMA
+            | Self::KozaiMA
+            | Self::SailCone
+            | Self::SailClock => "deg",
 
             // Distances
             Self::ApoapsisRadius
@@ -249,13 +413,30 @@ impl StateParameter {
             | Self::SemiMinorAxis
             | Self::X
             | Self::Y
-            | Self::Z => "km",
+            | Self::Z
+            | Self::BrouwerShortSMA
+            | Self::BrouwerLongSMA
+            | Self::KozaiSMA => "km",
 
             // Velocities
             Self::VX | Self::VY | Self::VZ | Self::Vmag => "km/s",
 
             Self::C3 | Self::Energy => "km^2/s^2",
 
+            // Accelerations
+            Self::EmpiricalConstantR
+            | Self::EmpiricalConstantI
+            | Self::EmpiricalConstantC
+            | Self::EmpiricalCosR
+            | Self::EmpiricalCosI
+            | Self::EmpiricalCosC
+            | Self::EmpiricalSinR
+            | Self::EmpiricalSinI
+            | Self::EmpiricalSinC
+            | Self::DmcR
+            | Self::DmcI
+            | Self::DmcC => "km/s^2",
+
             Self::DryMass | Self::FuelMass => "kg",
             Self::Isp => "isp",
             Self::Thrust => "N",
@@ -277,6 +458,31 @@ impl StateParameter {
 }
 
 impl StateParameter {
+    /// Returns the mean elements theory of this parameter, if it is a mean element
+    pub const fn mean_elements_kind(&self) -> Option<MeanElementsKind> {
+        match self {
+            Self::BrouwerShortSMA
+            | Self::BrouwerShortEcc
+            | Self::BrouwerShortInc
+            | Self::BrouwerShortRAAN
+            | Self::BrouwerShortAoP
+            | Self::BrouwerShortMA => Some(MeanElementsKind::BrouwerShort),
+            Self::BrouwerLongSMA
+            | Self::BrouwerLongEcc
+            | Self::BrouwerLongInc
+            | Self::BrouwerLongRAAN
+            | Self::BrouwerLongAoP
+            | Self::BrouwerLongMA => Some(MeanElementsKind::BrouwerLong),
+            Self::KozaiSMA
+            | Self::KozaiEcc
+            | Self::KozaiInc
+            | Self::KozaiRAAN
+            | Self::KozaiAoP
+            | Self::KozaiMA => Some(MeanElementsKind::Kozai),
+            _ => None,
+        }
+    }
+
     /// Returns the parquet field of this parameter
     pub(crate) fn to_field(self, more_meta: Option<Vec<(String, String)>>) -> Field {
         let mut meta = HashMap::new();
@@ -315,15 +521,43 @@ impl FromStr for StateParameter {
             "bltof" => Ok(Self::BLTOF),
             "bdotr" => Ok(Self::BdotR),
             "bdott" => Ok(Self::BdotT),
+            "brouwer_short_sma" => Ok(Self::BrouwerShortSMA),
+            "brouwer_short_ecc" => Ok(Self::BrouwerShortEcc),
+            "brouwer_short_inc" => Ok(Self::BrouwerShortInc),
+            "brouwer_short_raan" => Ok(Self::BrouwerShortRAAN),
+            "brouwer_short_aop" => Ok(Self::BrouwerShortAoP),
+            "brouwer_short_ma" => Ok(Self::BrouwerShortMA),
+            "brouwer_long_sma" => Ok(Self::BrouwerLongSMA),
+            "brouwer_long_ecc" => Ok(Self::BrouwerLongEcc),
+            "brouwer_long_inc" => Ok(Self::BrouwerLongInc),
+            "brouwer_long_raan" => Ok(Self::BrouwerLongRAAN),
+            "brouwer_long_aop" => Ok(Self::BrouwerLongAoP),
+            "brouwer_long_ma" => Ok(Self::BrouwerLongMA),
             "c3" => Ok(Self::C3),
             "cd" => Ok(Self::Cd),
             "cr" => Ok(Self::Cr),
             "declin" => Ok(Self::Declination),
             "dry_mass" => Ok(Self::DryMass),
+            "dmc_r" => Ok(Self::DmcR),
+            "dmc_i" => Ok(Self::DmcI),
+            "dmc_c" => Ok(Self::DmcC),
             "apoapsis_radius" => Ok(Self::ApoapsisRadius),
             "ea" => Ok(Self::EccentricAnomaly),
             "ecc" => Ok(Self::Eccentricity),
+            "empirical_constant_r" => Ok(Self::EmpiricalConstantR),
+            "empirical_constant_i" => Ok(Self::EmpiricalConstantI),
+            "empirical_constant_c" => Ok(Self::EmpiricalConstantC),
+            "empirical_cos_r" => Ok(Self::EmpiricalCosR),
+            "empirical_cos_i" => Ok(Self::EmpiricalCosI),
+            "empirical_cos_c" => Ok(Self::EmpiricalCosC),
+            "empirical_sin_r" => Ok(Self::EmpiricalSinR),
+            "empirical_sin_i" => Ok(Self::EmpiricalSinI),
+            "empirical_sin_c" => Ok(Self::EmpiricalSinC),
             "energy" => Ok(Self::Energy),
+            "equinoctial_h" => Ok(Self::EquinoctialH),
+            "equinoctial_k" => Ok(Self::EquinoctialK),
+            "equinoctial_p" => Ok(Self::EquinoctialP),
+            "equinoctial_q" => Ok(Self::EquinoctialQ),
             "fpa" => Ok(Self::FlightPathAngle),
             "fuel_mass" => Ok(Self::FuelMass),
             "guidance_mode" | "mode" => Ok(Self::GuidanceMode),
@@ -337,11 +571,26 @@ impl FromStr for StateParameter {
             "hz" => Ok(Self::HZ),
             "inc" => Ok(Self::Inclination),
             "isp" => Ok(Self::Isp),
+            "jacobi" => Ok(Self::JacobiConstant),
+            "kozai_sma" => Ok(Self::KozaiSMA),
+            "kozai_ecc" => Ok(Self::KozaiEcc),
+            "kozai_inc" => Ok(Self::KozaiInc),
+            "kozai_raan" => Ok(Self::KozaiRAAN),
+            "kozai_aop" => Ok(Self::KozaiAoP),
+            "kozai_ma" => Ok(Self::KozaiMA),
             "ma" => Ok(Self::MeanAnomaly),
+            "mean_long" => Ok(Self::MeanLongitude),
+            "mod_equinoctial_f" => Ok(Self::ModEquinoctialF),
+            "mod_equinoctial_g" => Ok(Self::ModEquinoctialG),
+            "mod_equinoctial_h" => Ok(Self::ModEquinoctialH),
+            "mod_equinoctial_k" => Ok(Self::ModEquinoctialK),
             "periapsis_radius" => Ok(Self::PeriapsisRadius),
             "period" => Ok(Self::Period),
+            "radiation_scale" => Ok(Self::RadiationScale),
             "right_asc" => Ok(Self::RightAscension),
             "raan" => Ok(Self::RAAN),
+            "sail_clock" => Ok(Self::SailClock),
+            "sail_cone" => Ok(Self::SailCone),
             "rmag" => Ok(Self::Rmag),
             "semi_parameter" => Ok(Self::SemiParameter),
             "semi_minor" => Ok(Self::SemiMinorAxis),
@@ -349,6 +598,7 @@ impl FromStr for StateParameter {
             "ta" => Ok(Self::TrueAnomaly),
             "tlong" => Ok(Self::TrueLongitude),
             "thrust" => Ok(Self::Thrust),
+            "throttle" => Ok(Self::Throttle),
             "vdeclin" => Ok(Self::VelocityDeclination),
             "vmag" => Ok(Self::Vmag),
             "x" => Ok(Self::X),
@@ -374,16 +624,44 @@ impl fmt::Display for StateParameter {
             Self::BLTOF => "BLToF",
             Self::BdotR => "BdotR",
             Self::BdotT => "BdotT",
+            Self::BrouwerShortSMA => "brouwer_short_sma",
+            Self::BrouwerShortEcc => "brouwer_short_ecc",
+            Self::BrouwerShortInc => "brouwer_short_inc",
+            Self::BrouwerShortRAAN => "brouwer_short_raan",
+            Self::BrouwerShortAoP => "brouwer_short_aop",
+            Self::BrouwerShortMA => "brouwer_short_ma",
+            Self::BrouwerLongSMA => "brouwer_long_sma",
+            Self::BrouwerLongEcc => "brouwer_long_ecc",
+            Self::BrouwerLongInc => "brouwer_long_inc",
+            Self::BrouwerLongRAAN => "brouwer_long_raan",
+            Self::BrouwerLongAoP => "brouwer_long_aop",
+            Self::BrouwerLongMA => "brouwer_long_ma",
             Self::C3 => "c3",
             Self::Cd => "cd",
             Self::Cr => "cr",
             Self::Declination => "declin",
             Self::DryMass => "dry_mass",
+            Self::DmcR => "dmc_r",
+            Self::DmcI => "dmc_i",
+            Self::DmcC => "dmc_c",
             Self::Epoch => "epoch",
             Self::ApoapsisRadius => "apoapsis_radius",
             Self::EccentricAnomaly => "ea",
             Self::Eccentricity => "ecc",
+            Self::EmpiricalConstantR => "empirical_constant_r",
+            Self::EmpiricalConstantI => "empirical_constant_i",
+            Self::EmpiricalConstantC => "empirical_constant_c",
+            Self::EmpiricalCosR => "empirical_cos_r",
+            Self::EmpiricalCosI => "empirical_cos_i",
+            Self::EmpiricalCosC => "empirical_cos_c",
+            Self::EmpiricalSinR => "empirical_sin_r",
+            Self::EmpiricalSinI => "empirical_sin_i",
+            Self::EmpiricalSinC => "empirical_sin_c",
             Self::Energy => "energy",
+            Self::EquinoctialH => "equinoctial_h",
+            Self::EquinoctialK => "equinoctial_k",
+            Self::EquinoctialP => "equinoctial_p",
+            Self::EquinoctialQ => "equinoctial_q",
             Self::FlightPathAngle => "fpa",
             Self::FuelMass => "fuel_mass",
             Self::GuidanceMode => "guidance_mode",
@@ -397,16 +675,32 @@ impl fmt::Display for StateParameter {
             Self::HZ => "hz",
             Self::Inclination => "inc",
             Self::Isp => "isp",
+            Self::JacobiConstant => "jacobi",
+            Self::KozaiSMA => "kozai_sma",
+            Self::KozaiEcc => "kozai_ecc",
+            Self::KozaiInc => "kozai_inc",
+            Self::KozaiRAAN => "kozai_raan",
+            Self::KozaiAoP => "kozai_aop",
+            Self::KozaiMA => "kozai_ma",
             Self::MeanAnomaly => "ma",
+            Self::MeanLongitude => "mean_long",
+            Self::ModEquinoctialF => "mod_equinoctial_f",
+            Self::ModEquinoctialG => "mod_equinoctial_g",
+            Self::ModEquinoctialH => "mod_equinoctial_h",
+            Self::ModEquinoctialK => "mod_equinoctial_k",
             Self::PeriapsisRadius => "periapsis_radius",
             Self::Period => "period",
+            Self::RadiationScale => "radiation_scale",
             Self::RightAscension => "right_asc",
             Self::RAAN => "raan",
+            Self::SailClock => "sail_clock",
+            Self::SailCone => "sail_cone",
             Self::Rmag => "rmag",
             Self::SemiParameter => "semi_parameter",
             Self::SemiMinorAxis => "semi_minor",
             Self::SMA => "sma",
             Self::Thrust => "thrust",
+            Self::Throttle => "throttle",
             Self::TrueAnomaly => "ta",
             Self::TrueLongitude => "tlong",
             Self::VelocityDeclination => "vdeclin",
@@ -441,15 +735,43 @@ mod ut_state_param {
             StateParameter::BdotR,
             StateParameter::BdotT,
             StateParameter::BLTOF,
+            StateParameter::BrouwerShortSMA,
+            StateParameter::BrouwerShortEcc,
+            StateParameter::BrouwerShortInc,
+            StateParameter::BrouwerShortRAAN,
+            StateParameter::BrouwerShortAoP,
+            StateParameter::BrouwerShortMA,
+            StateParameter::BrouwerLongSMA,
+            StateParameter::BrouwerLongEcc,
+            StateParameter::BrouwerLongInc,
+            StateParameter::BrouwerLongRAAN,
+            StateParameter::BrouwerLongAoP,
+            StateParameter::BrouwerLongMA,
             StateParameter::C3,
             StateParameter::Cd,
             StateParameter::Cr,
             StateParameter::Declination,
             StateParameter::DryMass,
+            StateParameter::DmcR,
+            StateParameter::DmcI,
+            StateParameter::DmcC,
             StateParameter::ApoapsisRadius,
             StateParameter::EccentricAnomaly,
             StateParameter::Eccentricity,
+            StateParameter::EmpiricalConstantR,
+            StateParameter::EmpiricalConstantI,
+            StateParameter::EmpiricalConstantC,
+            StateParameter::EmpiricalCosR,
+            StateParameter::EmpiricalCosI,
+            StateParameter::EmpiricalCosC,
+            StateParameter::EmpiricalSinR,
+            StateParameter::EmpiricalSinI,
+            StateParameter::EmpiricalSinC,
             StateParameter::Energy,
+            StateParameter::EquinoctialH,
+            StateParameter::EquinoctialK,
+            StateParameter::EquinoctialP,
+            StateParameter::EquinoctialQ,
             StateParameter::FlightPathAngle,
             StateParameter::FuelMass,
             StateParameter::GuidanceMode,
@@ -463,16 +785,32 @@ mod ut_state_param {
             StateParameter::HZ,
             StateParameter::Inclination,
             StateParameter::Isp,
+            StateParameter::JacobiConstant,
+            StateParameter::KozaiSMA,
+            StateParameter::KozaiEcc,
+            StateParameter::KozaiInc,
+            StateParameter::KozaiRAAN,
+            StateParameter::KozaiAoP,
+            StateParameter::KozaiMA,
             StateParameter::MeanAnomaly,
+            StateParameter::MeanLongitude,
+            StateParameter::ModEquinoctialF,
+            StateParameter::ModEquinoctialG,
+            StateParameter::ModEquinoctialH,
+            StateParameter::ModEquinoctialK,
             StateParameter::PeriapsisRadius,
             StateParameter::Period,
+            StateParameter::RadiationScale,
             StateParameter::RightAscension,
             StateParameter::RAAN,
+            StateParameter::SailClock,
+            StateParameter::SailCone,
             StateParameter::Rmag,
             StateParameter::SemiParameter,
             StateParameter::SemiMinorAxis,
             StateParameter::SMA,
             StateParameter::Thrust,
+            StateParameter::Throttle,
             StateParameter::TrueAnomaly,
             StateParameter::TrueLongitude,
             StateParameter::VelocityDeclination,
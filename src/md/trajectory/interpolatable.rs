@@ -20,14 +20,27 @@ pub(crate) const INTERPOLATION_SAMPLES: usize = 13;
 
 use super::StateParameter;
 use crate::cosmic::Frame;
+use crate::cosmic::{Attitude, EmpiricalOrbit, RadiationSpacecraft, RigidSpacecraft};
 use crate::linalg::allocator::Allocator;
 use crate::linalg::DefaultAllocator;
+use crate::na::UnitQuaternion;
 use crate::polyfit::hermite::hermite_eval;
+use crate::polyfit::lagrange::lagrange_eval;
 use crate::time::Epoch;
 use crate::{Orbit, Spacecraft, State};
 
 use enum_iterator::all;
 
+/// Interpolation method of the states of a trajectory
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum InterpolationMethod {
+    /// Hermite interpolation of the position and velocity, for states whose velocity is the derivative of their position (e.g. propagated states)
+    #[default]
+    Hermite,
+    /// Lagrange interpolation of each component of the state, for states whose velocity is not exactly the derivative of their position (e.g. SGP4 states)
+    Lagrange,
+}
+
 /// States that can be interpolated should implement this trait.
 pub trait Interpolatable: State
 where
@@ -39,6 +52,12 @@ where
     /// Interpolates a new state at the provided epochs given a slice of states.
     fn interpolate(self, epoch: Epoch, states: &[Self]) -> Self;
 
+    /// Interpolates a new state at the provided epochs given a slice of states, with a Lagrange interpolation of each component of the orbit.
+    /// By default, this is the same as `interpolate`.
+    fn interpolate_lagrange(self, epoch: Epoch, states: &[Self]) -> Self {
+        self.interpolate(epoch, states)
+    }
+
     /// Returns the frame of this state
     fn frame(&self) -> Frame;
 
@@ -46,8 +65,14 @@ where
     fn set_frame(&mut self, frame: Frame);
 
     /// List of state parameters that will be exported to a trajectory file in addition to the epoch (provided in this different formats).
+    /// Mean elements are costly to compute and not exported by default: add them to the `fields` of the export configuration instead.
     fn export_params() -> Vec<StateParameter>;
 
+    /// Parameters of the state vector which follow the Cartesian orbit, in order, e.g. to label the covariance of the orbit determination exports.
+    fn estimated_params() -> Vec<StateParameter> {
+        Vec::new()
+    }
+
     /// Returns the orbit
     fn orbit(&self) -> &Orbit;
 }
@@ -80,7 +105,7 @@ impl Interpolatable for Orbit {
             &epochs_tdb[..states.len()],
             &xs[..states.len()],
             &vxs[..states.len()],
-            epoch.to_et_seconds(),
+            epoch.to_tdb_seconds(),
         )
         .unwrap();
 
@@ -88,7 +113,7 @@ impl Interpolatable for Orbit {
             &epochs_tdb[..states.len()],
             &ys[..states.len()],
             &vys[..states.len()],
-            epoch.to_et_seconds(),
+            epoch.to_tdb_seconds(),
         )
         .unwrap();
 
@@ -96,7 +121,7 @@ impl Interpolatable for Orbit {
             &epochs_tdb[..states.len()],
             &zs[..states.len()],
             &vzs[..states.len()],
-            epoch.to_et_seconds(),
+            epoch.to_tdb_seconds(),
         )
         .unwrap();
 
@@ -113,6 +138,32 @@ impl Interpolatable for Orbit {
         me
     }
 
+    fn interpolate_lagrange(self, epoch: Epoch, states: &[Self]) -> Self {
+        // Offset the times by the first state for numerical conditioning
+        let epoch0_tdb = states[0].epoch().to_tdb_seconds();
+        let epochs_tdb: Vec<f64> = states
+            .iter()
+            .map(|state| state.epoch().to_tdb_seconds() - epoch0_tdb)
+            .collect();
+        let eval_tdb = epoch.to_tdb_seconds() - epoch0_tdb;
+
+        let interp = |component: fn(&Orbit) -> f64| {
+            let ys: Vec<f64> = states.iter().map(component).collect();
+            lagrange_eval(&epochs_tdb, &ys, eval_tdb).unwrap()
+        };
+
+        let mut me = self;
+        me.x_km = interp(|state| state.x_km);
+        me.y_km = interp(|state| state.y_km);
+        me.z_km = interp(|state| state.z_km);
+        me.vx_km_s = interp(|state| state.vx_km_s);
+        me.vy_km_s = interp(|state| state.vy_km_s);
+        me.vz_km_s = interp(|state| state.vz_km_s);
+        me.set_epoch(epoch);
+
+        me
+    }
+
     fn frame(&self) -> Frame {
         self.frame
     }
@@ -127,6 +178,7 @@ impl Interpolatable for Orbit {
             .filter(|p| {
                 p.is_orbital()
                     && !p.is_b_plane()
+                    && !p.is_mean_element()
                     && !matches!(
                         p,
                         StateParameter::X
@@ -139,6 +191,7 @@ impl Interpolatable for Orbit {
                             | StateParameter::GeodeticHeight
                             | StateParameter::GeodeticLatitude
                             | StateParameter::GeodeticLongitude
+                            | StateParameter::JacobiConstant
                     )
             })
             .collect::<Vec<StateParameter>>();
@@ -184,6 +237,17 @@ impl Interpolatable for Spacecraft {
         me
     }
 
+    fn interpolate_lagrange(self, epoch: Epoch, states: &[Self]) -> Self {
+        let orbit = Orbit::interpolate_lagrange(
+            self.orbit,
+            epoch,
+            &states.iter().map(|state| state.orbit).collect::<Vec<_>>(),
+        );
+        let mut me = self.interpolate(epoch, states);
+        me.orbit = orbit;
+        me
+    }
+
     fn frame(&self) -> Frame {
         self.orbit.frame
     }
@@ -198,6 +262,7 @@ impl Interpolatable for Spacecraft {
             .filter(|p| {
                 p.is_orbital()
                     && !p.is_b_plane()
+                    && !p.is_mean_element()
                     && !matches!(
                         p,
                         StateParameter::X
@@ -210,6 +275,7 @@ impl Interpolatable for Spacecraft {
                             | StateParameter::GeodeticHeight
                             | StateParameter::GeodeticLatitude
                             | StateParameter::GeodeticLongitude
+                            | StateParameter::JacobiConstant
                     )
             })
             .collect::<Vec<StateParameter>>();
@@ -233,7 +299,225 @@ impl Interpolatable for Spacecraft {
         .concat()
     }
 
+    fn estimated_params() -> Vec<StateParameter> {
+        vec![
+            StateParameter::Cr,
+            StateParameter::Cd,
+            StateParameter::FuelMass,
+        ]
+    }
+
     fn orbit(&self) -> &Orbit {
         &self.orbit
     }
 }
+
+impl Interpolatable for EmpiricalOrbit {
+    fn interpolate(self, epoch: Epoch, states: &[Self]) -> Self {
+        let orbit = Orbit::interpolate(
+            self.orbit,
+            epoch,
+            &states.iter().map(|state| state.orbit).collect::<Vec<_>>(),
+        );
+
+        // Bracket the epoch with the samples, which are sorted by epoch
+        let after_idx = states
+            .iter()
+            .position(|state| state.epoch() > epoch)
+            .unwrap_or(states.len() - 1)
+            .max(1)
+            .min(states.len() - 1);
+        let before = &states[after_idx.saturating_sub(1)];
+        let after = &states[after_idx];
+
+        // The constant and once-per-revolution terms are linearly interpolated, like the fuel mass of the spacecraft
+        let span_s = (after.epoch() - before.epoch()).to_seconds();
+        let ratio = if span_s.abs() > 0.0 {
+            (epoch - before.epoch()).to_seconds() / span_s
+        } else {
+            0.0
+        };
+        let mut params = before.empirical.to_vector()
+            + (after.empirical.to_vector() - before.empirical.to_vector()) * ratio;
+
+        // The DMC is a Gauss-Markov process, whose mean decays exponentially from the previous sample
+        params
+            .fixed_rows_mut::<3>(9)
+            .copy_from(&before.empirical.dmc_at(epoch));
+
+        let mut me = self;
+        me.orbit = orbit;
+        me.set_epoch(epoch);
+        me.empirical.set_vector(&params);
+
+        me
+    }
+
+    fn interpolate_lagrange(self, epoch: Epoch, states: &[Self]) -> Self {
+        let orbit = Orbit::interpolate_lagrange(
+            self.orbit,
+            epoch,
+            &states.iter().map(|state| state.orbit).collect::<Vec<_>>(),
+        );
+        let mut me = self.interpolate(epoch, states);
+        me.orbit = orbit;
+        me
+    }
+
+    fn frame(&self) -> Frame {
+        self.orbit.frame
+    }
+
+    fn set_frame(&mut self, frame: Frame) {
+        self.orbit.frame = frame;
+    }
+
+    fn export_params() -> Vec<StateParameter> {
+        [Orbit::export_params(), Self::estimated_params()].concat()
+    }
+
+    fn estimated_params() -> Vec<StateParameter> {
+        Self::empirical_params().to_vec()
+    }
+
+    fn orbit(&self) -> &Orbit {
+        &self.orbit
+    }
+}
+
+impl Interpolatable for RigidSpacecraft {
+    fn interpolate(self, epoch: Epoch, states: &[Self]) -> Self {
+        let sc = Spacecraft::interpolate(
+            self.sc,
+            epoch,
+            &states.iter().map(|state| state.sc).collect::<Vec<_>>(),
+        );
+
+        let mut me = self;
+        me.sc = sc;
+        me.attitude = interpolate_attitude(epoch, states);
+        me
+    }
+
+    fn interpolate_lagrange(self, epoch: Epoch, states: &[Self]) -> Self {
+        let sc = Spacecraft::interpolate_lagrange(
+            self.sc,
+            epoch,
+            &states.iter().map(|state| state.sc).collect::<Vec<_>>(),
+        );
+
+        let mut me = self;
+        me.sc = sc;
+        me.attitude = interpolate_attitude(epoch, states);
+        me
+    }
+
+    fn frame(&self) -> Frame {
+        self.sc.orbit.frame
+    }
+
+    fn set_frame(&mut self, frame: Frame) {
+        self.sc.orbit.frame = frame;
+    }
+
+    fn export_params() -> Vec<StateParameter> {
+        Spacecraft::export_params()
+    }
+
+    fn estimated_params() -> Vec<StateParameter> {
+        Spacecraft::estimated_params()
+    }
+
+    fn orbit(&self) -> &Orbit {
+        &self.sc.orbit
+    }
+}
+
+impl Interpolatable for RadiationSpacecraft {
+    fn interpolate(self, epoch: Epoch, states: &[Self]) -> Self {
+        let sc = Spacecraft::interpolate(
+            self.sc,
+            epoch,
+            &states.iter().map(|state| state.sc).collect::<Vec<_>>(),
+        );
+
+        // The scale factor is constant during the propagation
+        let mut me = self;
+        me.sc = sc;
+        me.radiation_scale = states.first().unwrap().radiation_scale;
+        me
+    }
+
+    fn interpolate_lagrange(self, epoch: Epoch, states: &[Self]) -> Self {
+        let sc = Spacecraft::interpolate_lagrange(
+            self.sc,
+            epoch,
+            &states.iter().map(|state| state.sc).collect::<Vec<_>>(),
+        );
+        let mut me = self.interpolate(epoch, states);
+        me.sc = sc;
+        me
+    }
+
+    fn frame(&self) -> Frame {
+        self.sc.orbit.frame
+    }
+
+    fn set_frame(&mut self, frame: Frame) {
+        self.sc.orbit.frame = frame;
+    }
+
+    fn export_params() -> Vec<StateParameter> {
+        [Spacecraft::export_params(), Self::estimated_params()].concat()
+    }
+
+    fn estimated_params() -> Vec<StateParameter> {
+        vec![StateParameter::RadiationScale]
+    }
+
+    fn orbit(&self) -> &Orbit {
+        &self.sc.orbit
+    }
+}
+
+/// Interpolates the attitude between the two states surrounding the provided epoch.
+///
+/// The quaternion is a slerp corrected by the angular velocities: the rotation vector `r` from the first state, such that q = q0 ⊗ exp(r),
+/// is a cubic Hermite polynomial whose derivatives at both ends are ṙ = ω + r × ω / 2, i.e. the body rates to first order in `r`.
+/// The angular velocity follows from the derivative of that polynomial, ω = ṙ - r × ṙ / 2.
+fn interpolate_attitude(epoch: Epoch, states: &[RigidSpacecraft]) -> Attitude {
+    let after = states
+        .iter()
+        .position(|state| state.epoch() >= epoch)
+        .unwrap_or(states.len() - 1)
+        .clamp(1, states.len().max(2) - 1);
+    let first = &states[after - 1];
+    let last = &states[after];
+
+    let span_s = (last.epoch() - first.epoch()).to_seconds();
+    if span_s.abs() <= 0.0 {
+        return first.attitude;
+    }
+    let t = (epoch - first.epoch()).to_seconds() / span_s;
+
+    let q0 = first.attitude.body_to_frame();
+    let theta = (q0.inverse() * last.attitude.body_to_frame()).scaled_axis();
+    let omega0 = first.attitude.omega();
+    let omega1 = last.attitude.omega();
+    let rate1 = omega1 + 0.5 * theta.cross(&omega1);
+    // Cubic Hermite basis, with r(0) = 0
+    let h10 = t * (1.0 - t).powi(2);
+    let h01 = t.powi(2) * (3.0 - 2.0 * t);
+    let h11 = t.powi(2) * (t - 1.0);
+    let r = h10 * span_s * omega0 + h01 * theta + h11 * span_s * rate1;
+    let dh10 = (1.0 - t) * (1.0 - 3.0 * t);
+    let dh01 = 6.0 * t * (1.0 - t) / span_s;
+    let dh11 = t * (3.0 * t - 2.0);
+    let r_dot = dh10 * omega0 + dh01 * theta + dh11 * rate1;
+
+    let mut attitude = first.attitude;
+    let q = (q0 * UnitQuaternion::from_scaled_axis(r)).into_inner();
+    attitude.quaternion = [q.w, q.i, q.j, q.k];
+    attitude.omega_rad_s = (r_dot - 0.5 * r.cross(&r_dot)).into();
+    attitude
+}
@@ -24,8 +24,8 @@ mod sc_traj;
 mod traj;
 mod traj_it;
 
-pub use interpolatable::Interpolatable;
 pub(crate) use interpolatable::INTERPOLATION_SAMPLES;
+pub use interpolatable::{Interpolatable, InterpolationMethod};
 pub use traj::Traj;
 
 pub use crate::io::ExportCfg;
@@ -18,7 +18,7 @@
 
 use super::traj_it::TrajIterator;
 use super::{ExportCfg, INTERPOLATION_SAMPLES};
-use super::{Interpolatable, TrajError};
+use super::{Interpolatable, InterpolationMethod, TrajError};
 use crate::errors::NyxError;
 use crate::io::watermark::pq_writer;
 use crate::linalg::allocator::Allocator;
@@ -51,6 +51,8 @@ where
     pub name: Option<String>,
     /// We use a vector because we know that the states are produced in a chronological manner (the direction does not matter).
     pub states: Vec<S>,
+    /// Interpolation method used when querying a state between two samples
+    pub interpolation: InterpolationMethod,
 }
 
 impl<S: Interpolatable> Traj<S>
@@ -62,6 +64,7 @@ where
         Self {
             name: None,
             states: Vec::new(),
+            interpolation: InterpolationMethod::default(),
         }
     }
     /// Orders the states, can be used to store the states out of order
@@ -111,7 +114,12 @@ where
                     states.push(self.states[idx]);
                 }
 
-                Ok(self.states[idx].interpolate(epoch, &states))
+                Ok(match self.interpolation {
+                    InterpolationMethod::Hermite => self.states[idx].interpolate(epoch, &states),
+                    InterpolationMethod::Lagrange => {
+                        self.states[idx].interpolate_lagrange(epoch, &states)
+                    }
+                })
             }
         }
     }
@@ -228,6 +228,13 @@ where
         let stm = nominal_state.stm().with_context(|_| ODDynamicsSnafu)?;
         let mut covar_bar = stm * self.prev_estimate.covar * stm.transpose();
 
+        // Add the process noise of the state itself, e.g. that of its Gauss-Markov parameters
+        if let Some(noise) =
+            nominal_state.process_noise(nominal_state.epoch() - self.prev_estimate.epoch())
+        {
+            covar_bar += noise;
+        }
+
         // Try to apply an SNC, if applicable
         for (i, snc) in self.process_noise.iter().enumerate().rev() {
             if let Some(snc_matrix) = snc.to_matrix(nominal_state.epoch()) {
@@ -311,6 +318,12 @@ where
         let epoch = nominal_state.epoch();
 
         let mut covar_bar = stm * self.prev_estimate.covar * stm.transpose();
+
+        // Add the process noise of the state itself, e.g. that of its Gauss-Markov parameters
+        if let Some(noise) = nominal_state.process_noise(epoch - self.prev_estimate.epoch()) {
+            covar_bar += noise;
+        }
+
         let mut snc_used = false;
         // Try to apply an SNC, if applicable
         for (i, snc) in self.process_noise.iter().enumerate().rev() {
@@ -19,7 +19,7 @@
 use super::msr::RangeDoppler;
 use super::noise::GaussMarkov;
 use super::{ODError, ODTrajSnafu, TrackingDeviceSim};
-use crate::cosmic::{Cosm, Frame, Orbit};
+use crate::cosmic::{Cosm, EmpiricalOrbit, Frame, Orbit, RadiationSpacecraft};
 use crate::io::{frame_from_str, frame_to_str, ConfigRepr, Configurable};
 use crate::md::prelude::{Interpolatable, Traj};
 use crate::md::EventEvaluator;
@@ -403,6 +403,75 @@ impl TrackingDeviceSim<Spacecraft, RangeDoppler> for GroundStation {
     }
 }
 
+impl TrackingDeviceSim<EmpiricalOrbit, RangeDoppler> for GroundStation {
+    /// Perform a measurement from the ground station to the receiver (rx).
+    fn measure(
+        &mut self,
+        epoch: Epoch,
+        traj: &Traj<EmpiricalOrbit>,
+        rng: Option<&mut Pcg64Mcg>,
+        cosm: Arc<Cosm>,
+    ) -> Result<Option<RangeDoppler>, ODError> {
+        let rx = traj.at(epoch).with_context(|_| ODTrajSnafu)?;
+        self.measure_instantaneous(rx, rng, cosm)
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn location(&self, epoch: Epoch, frame: Frame, cosm: &Cosm) -> Orbit {
+        cosm.frame_chg(&self.to_orbit(epoch), frame)
+    }
+
+    fn measure_instantaneous(
+        &mut self,
+        rx: EmpiricalOrbit,
+        rng: Option<&mut Pcg64Mcg>,
+        cosm: Arc<Cosm>,
+    ) -> Result<Option<RangeDoppler>, ODError> {
+        <Self as TrackingDeviceSim<Orbit, RangeDoppler>>::measure_instantaneous(
+            self, rx.orbit, rng, cosm,
+        )
+    }
+}
+
+impl TrackingDeviceSim<RadiationSpacecraft, RangeDoppler> for GroundStation {
+    /// Perform a measurement from the ground station to the receiver (rx).
+    fn measure(
+        &mut self,
+        epoch: Epoch,
+        traj: &Traj<RadiationSpacecraft>,
+        rng: Option<&mut Pcg64Mcg>,
+        cosm: Arc<Cosm>,
+    ) -> Result<Option<RangeDoppler>, ODError> {
+        let rx = traj.at(epoch).with_context(|_| ODTrajSnafu)?;
+        self.measure_instantaneous(rx, rng, cosm)
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn location(&self, epoch: Epoch, frame: Frame, cosm: &Cosm) -> Orbit {
+        cosm.frame_chg(&self.to_orbit(epoch), frame)
+    }
+
+    fn measure_instantaneous(
+        &mut self,
+        rx: RadiationSpacecraft,
+        rng: Option<&mut Pcg64Mcg>,
+        cosm: Arc<Cosm>,
+    ) -> Result<Option<RangeDoppler>, ODError> {
+        <Self as TrackingDeviceSim<Orbit, RangeDoppler>>::measure_instantaneous(
+            self,
+            rx.sc.orbit,
+            rng,
+            cosm,
+        )
+    }
+}
+
 impl fmt::Display for GroundStation {
     // Prints the Keplerian orbital elements with units
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
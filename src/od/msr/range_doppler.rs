@@ -16,9 +16,9 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::cosmic::Orbit;
+use crate::cosmic::{EmpiricalOrbit, Orbit, RadiationSpacecraft};
 use crate::linalg::allocator::Allocator;
-use crate::linalg::{DefaultAllocator, OMatrix, OVector, Vector2, U2};
+use crate::linalg::{Const, DefaultAllocator, OMatrix, OVector, Vector2, U2};
 use crate::od::msr::RangeMsr;
 use crate::od::{EstimateFrom, Measurement};
 use crate::{Spacecraft, TimeTagged};
@@ -234,3 +234,57 @@ impl EstimateFrom<Spacecraft, RangeDoppler> for Spacecraft {
         todo!("cannot yet estimate a full spacecraft state")
     }
 }
+
+impl EstimateFrom<EmpiricalOrbit, RangeDoppler> for EmpiricalOrbit {
+    fn extract(from: EmpiricalOrbit) -> Self {
+        from
+    }
+
+    /// The measurements do not depend on the empirical accelerations directly, only through the STM
+    fn sensitivity(
+        msr: &RangeDoppler,
+        receiver: Self,
+        transmitter: Orbit,
+    ) -> OMatrix<f64, <RangeDoppler as Measurement>::MeasurementSize, Self::Size>
+    where
+        DefaultAllocator:
+            Allocator<f64, <RangeDoppler as Measurement>::MeasurementSize, Self::Size>,
+    {
+        let mut h_tilde = OMatrix::<f64, U2, Const<18>>::zeros();
+        h_tilde
+            .fixed_view_mut::<2, 6>(0, 0)
+            .copy_from(&<Orbit as EstimateFrom<Orbit, RangeDoppler>>::sensitivity(
+                msr,
+                receiver.orbit,
+                transmitter,
+            ));
+        h_tilde
+    }
+}
+
+impl EstimateFrom<RadiationSpacecraft, RangeDoppler> for RadiationSpacecraft {
+    fn extract(from: RadiationSpacecraft) -> Self {
+        from
+    }
+
+    /// The measurements do not depend on the scale factor of the planetary radiation pressure directly, only through the STM
+    fn sensitivity(
+        msr: &RangeDoppler,
+        receiver: Self,
+        transmitter: Orbit,
+    ) -> OMatrix<f64, <RangeDoppler as Measurement>::MeasurementSize, Self::Size>
+    where
+        DefaultAllocator:
+            Allocator<f64, <RangeDoppler as Measurement>::MeasurementSize, Self::Size>,
+    {
+        let mut h_tilde = OMatrix::<f64, U2, Const<7>>::zeros();
+        h_tilde
+            .fixed_view_mut::<2, 6>(0, 0)
+            .copy_from(&<Orbit as EstimateFrom<Orbit, RangeDoppler>>::sensitivity(
+                msr,
+                receiver.sc.orbit,
+                transmitter,
+            ));
+        h_tilde
+    }
+}
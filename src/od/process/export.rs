@@ -130,38 +130,31 @@ where
             hdrs.push(field.to_field(more_meta.clone()));
         }
 
-        let cov_hdrs = match <S as State>::Size::dim() {
-            6 => {
-                // Add orbit 1-sigma covariance info, plotting to perform computations as desired
-                vec![
-                    "Covariance XX",
-                    "Covariance XY",
-                    "Covariance XZ",
-                    "Covariance XVx",
-                    "Covariance XVy",
-                    "Covariance XVz",
-                    "Covariance YY",
-                    "Covariance YZ",
-                    "Covariance YVx",
-                    "Covariance YVy",
-                    "Covariance YVz",
-                    "Covariance ZZ",
-                    "Covariance ZVx",
-                    "Covariance ZVy",
-                    "Covariance ZVz",
-                    "Covariance VxVx",
-                    "Covariance VxVy",
-                    "Covariance VxVz",
-                    "Covariance VyVy",
-                    "Covariance VyVz",
-                    "Covariance VzVz",
-                ]
-            }
-            _ => todo!(
+        // Label the covariance with the orbit components followed by the estimated parameters of the state
+        let labels = ["X", "Y", "Z", "Vx", "Vy", "Vz"]
+            .iter()
+            .map(|label| label.to_string())
+            .chain(S::estimated_params().iter().map(|param| param.to_string()))
+            .collect::<Vec<String>>();
+
+        if labels.len() != <S as State>::Size::dim() {
+            todo!(
                 "exporting a state of size {} is not yet supported",
                 <S as State>::Size::dim()
-            ),
-        };
+            )
+        }
+
+        // Add orbit 1-sigma covariance info, plotting to perform computations as desired
+        let mut cov_hdrs = Vec::new();
+        for (i, label_i) in labels.iter().enumerate() {
+            for (j, label_j) in labels.iter().enumerate().skip(i) {
+                cov_hdrs.push(if i < 6 && j < 6 {
+                    format!("Covariance {label_i}{label_j}")
+                } else {
+                    format!("Covariance {label_i} {label_j}")
+                });
+            }
+        }
 
         // Add the covariance in the integration frame
         for hdr in &cov_hdrs {
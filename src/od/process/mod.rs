@@ -18,7 +18,7 @@
 
 use crate::linalg::allocator::Allocator;
 use crate::linalg::{DefaultAllocator, DimName};
-use crate::md::trajectory::{Interpolatable, Traj};
+use crate::md::trajectory::{Interpolatable, InterpolationMethod, Traj};
 pub use crate::od::estimate::*;
 pub use crate::od::ground_station::*;
 pub use crate::od::snc::*;
@@ -740,6 +740,7 @@ where
                     .map(|est| est.nominal_state())
                     .collect(),
                 name: None,
+                interpolation: InterpolationMethod::default(),
             })
         }
     }
@@ -0,0 +1,60 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::NyxError;
+
+/// Evaluates the Lagrange polynomial through the provided points at `x_eval` with Neville's algorithm.
+pub fn lagrange_eval(xs: &[f64], ys: &[f64], x_eval: f64) -> Result<f64, NyxError> {
+    if xs.len() != ys.len() {
+        let msg = format!(
+            "Abscissas (xs) and ordinates (ys) must contain the same number of items, but they are of lengths {} and {}",
+            xs.len(),
+            ys.len()
+        );
+        return Err(NyxError::MathDomain { msg });
+    } else if xs.is_empty() {
+        let msg = "No interpolation data provided".to_string();
+        return Err(NyxError::MathDomain { msg });
+    }
+
+    let n = xs.len();
+    let mut p = ys.to_vec();
+    for j in 1..n {
+        for i in 0..n - j {
+            let denom = xs[i] - xs[i + j];
+            if denom.abs() < f64::EPSILON {
+                let msg = format!("duplicate abscissa data: denominator near zero ({denom:e})");
+                return Err(NyxError::MathDomain { msg });
+            }
+            p[i] = ((x_eval - xs[i + j]) * p[i] + (xs[i] - x_eval) * p[i + 1]) / denom;
+        }
+    }
+    Ok(p[0])
+}
+
+#[test]
+fn test_lagrange_eval() {
+    // Lagrange interpolation is exact on a polynomial of degree lower than the number of points
+    let xs = [0.0, 10.0, 20.0, 30.0];
+    let ys: Vec<f64> = xs.iter().map(|x| 2.0 * x * x * x - x + 1.0).collect();
+    let y = lagrange_eval(&xs, &ys, 15.0).unwrap();
+    assert!((y - (2.0 * 15.0_f64.powi(3) - 15.0 + 1.0)).abs() < 1e-9);
+
+    assert!(lagrange_eval(&xs, &ys[1..], 15.0).is_err());
+    assert!(lagrange_eval(&[1.0, 1.0], &[0.0, 1.0], 15.0).is_err());
+}
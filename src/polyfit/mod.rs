@@ -17,6 +17,7 @@
 */
 
 pub mod hermite;
+pub mod lagrange;
 mod polynomial;
 
 pub use polynomial::{CommonPolynomial, Polynomial};
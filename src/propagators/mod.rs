@@ -32,6 +32,8 @@ mod rk_methods;
 pub use rk_methods::*;
 mod options;
 pub use options::*;
+mod sgp4;
+pub use sgp4::*;
 
 use crate::{dynamics::DynamicsError, io::ConfigError, md::trajectory::TrajError, time::Duration};
 
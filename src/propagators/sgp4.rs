@@ -0,0 +1,1345 @@
+/*
+    Nyx, blazing fast astrodynamics
+    Copyright (C) 2023 Christopher Rabotin <christopher.rabotin@gmail.com>
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::cosmic::{Cosm, Frame, Orbit};
+use crate::errors::NyxError;
+use crate::io::tle::Tle;
+use crate::linalg::Vector6;
+use crate::md::trajectory::{InterpolationMethod, Traj};
+use crate::time::{Duration, Epoch, TimeSeries, Unit};
+use std::f64::consts::{PI, TAU};
+use std::sync::Arc;
+
+// WGS-72 constants, as required by the SGP4 theory used to generate the element sets
+const MU_KM3_S2: f64 = 398_600.8;
+const RADIUS_KM: f64 = 6_378.135;
+const J2: f64 = 0.001_082_616;
+const J3: f64 = -0.000_002_538_81;
+const J4: f64 = -0.000_001_655_97;
+const J3OJ2: f64 = J3 / J2;
+const X2O3: f64 = 2.0 / 3.0;
+/// Divisor for the divide by zero check on the inclination
+const TEMP4: f64 = 1.5e-12;
+/// Earth rotation rate in radians per minute
+const RPTIM: f64 = 4.375_269_088_011_3e-3;
+/// Julian date of 1949 December 31 00:00 UTC, the reference of the SGP4 epoch
+const JD_1950: f64 = 2_433_281.5;
+
+/// Square root of the gravitational parameter, in Earth radii^1.5 per minute
+fn xke() -> f64 {
+    60.0 / (RADIUS_KM.powi(3) / MU_KM3_S2).sqrt()
+}
+
+/// SGP4/SDP4 analytical propagator of two-line element sets.
+///
+/// This is a port of the revised SGP4 of Vallado et al., "Revisiting Spacetrack Report #3" (AIAA 2006-6753), in its
+/// "improved" operation mode and with the WGS-72 constants. Element sets with a period of 225 minutes or more are
+/// propagated with the deep space (SDP4) lunar-solar perturbations and the half-day and one-day resonances.
+///
+/// The states are computed in the Earth TEME frame, and can be converted to any other frame with the Cosm.
+#[derive(Clone, Debug)]
+pub struct Sgp4 {
+    tle: Tle,
+    /// Epoch of the element set in days since 1949 December 31 00:00 UTC
+    epoch_days: f64,
+    ecco: f64,
+    inclo: f64,
+    nodeo: f64,
+    argpo: f64,
+    mo: f64,
+    /// Un-Kozai'd mean motion, in radians per minute
+    no: f64,
+    bstar: f64,
+    /// Greenwich sidereal time at epoch
+    gsto: f64,
+    isimp: bool,
+    aycof: f64,
+    con41: f64,
+    cc1: f64,
+    cc4: f64,
+    cc5: f64,
+    d2: f64,
+    d3: f64,
+    d4: f64,
+    delmo: f64,
+    eta: f64,
+    argpdot: f64,
+    omgcof: f64,
+    sinmao: f64,
+    t2cof: f64,
+    t3cof: f64,
+    t4cof: f64,
+    t5cof: f64,
+    x1mth2: f64,
+    x7thm1: f64,
+    mdot: f64,
+    nodedot: f64,
+    xlcof: f64,
+    xmcof: f64,
+    nodecf: f64,
+    /// Deep space terms, only set if the period is greater than 225 minutes
+    deep: Option<Box<DeepSpace>>,
+}
+
+/// Lunar-solar periodics and resonance terms of SDP4.
+#[derive(Clone, Debug, Default)]
+struct DeepSpace {
+    e3: f64,
+    ee2: f64,
+    se2: f64,
+    se3: f64,
+    sgh2: f64,
+    sgh3: f64,
+    sgh4: f64,
+    sh2: f64,
+    sh3: f64,
+    si2: f64,
+    si3: f64,
+    sl2: f64,
+    sl3: f64,
+    sl4: f64,
+    xgh2: f64,
+    xgh3: f64,
+    xgh4: f64,
+    xh2: f64,
+    xh3: f64,
+    xi2: f64,
+    xi3: f64,
+    xl2: f64,
+    xl3: f64,
+    xl4: f64,
+    zmol: f64,
+    zmos: f64,
+    /// Resonance flag: 0 for none, 1 for one day (synchronous) and 2 for half-day orbits
+    irez: u8,
+    d2201: f64,
+    d2211: f64,
+    d3210: f64,
+    d3222: f64,
+    d4410: f64,
+    d4422: f64,
+    d5220: f64,
+    d5232: f64,
+    d5421: f64,
+    d5433: f64,
+    dedt: f64,
+    del1: f64,
+    del2: f64,
+    del3: f64,
+    didt: f64,
+    dmdt: f64,
+    dnodt: f64,
+    domdt: f64,
+    xfact: f64,
+    xlamo: f64,
+}
+
+/// Intermediate values of the deep space common computations (`dscom`), needed by the deep space initialization.
+#[derive(Default)]
+struct DsCom {
+    sinim: f64,
+    cosim: f64,
+    emsq: f64,
+    s1: f64,
+    s2: f64,
+    s3: f64,
+    s4: f64,
+    s5: f64,
+    ss1: f64,
+    ss2: f64,
+    ss3: f64,
+    ss4: f64,
+    ss5: f64,
+    sz1: f64,
+    sz3: f64,
+    sz11: f64,
+    sz13: f64,
+    sz21: f64,
+    sz23: f64,
+    sz31: f64,
+    sz33: f64,
+    z1: f64,
+    z3: f64,
+    z11: f64,
+    z13: f64,
+    z21: f64,
+    z23: f64,
+    z31: f64,
+    z33: f64,
+}
+
+impl Sgp4 {
+    /// Initializes the SGP4 propagator from this element set.
+    pub fn new(tle: Tle) -> Result<Self, NyxError> {
+        if !(0.0..1.0).contains(&tle.ecc) {
+            return Err(NyxError::Sgp4 {
+                msg: format!("eccentricity of {} out of range", tle.ecc),
+            });
+        }
+        if tle.mean_motion_rev_per_day <= 0.0 {
+            return Err(NyxError::Sgp4 {
+                msg: format!(
+                    "mean motion of {} rev/day must be positive",
+                    tle.mean_motion_rev_per_day
+                ),
+            });
+        }
+
+        let xke = xke();
+        let epoch_days = tle.epoch.to_jde_utc_days() - JD_1950;
+        let ecco = tle.ecc;
+        let inclo = tle.inc_deg.to_radians();
+        let nodeo = tle.raan_deg.to_radians();
+        let argpo = tle.aop_deg.to_radians();
+        let mo = tle.ma_deg.to_radians();
+        let bstar = tle.bstar;
+        // Kozai mean motion in radians per minute
+        let no_kozai = tle.mean_motion_rev_per_day * TAU / 1440.0;
+
+        let ss = 78.0 / RADIUS_KM + 1.0;
+        let qzms2t = ((120.0 - 78.0) / RADIUS_KM).powi(4);
+
+        // Recover the original (Brouwer) mean motion and semi-major axis from the Kozai mean motion
+        let eccsq = ecco * ecco;
+        let omeosq = 1.0 - eccsq;
+        let rteosq = omeosq.sqrt();
+        let cosio = inclo.cos();
+        let cosio2 = cosio * cosio;
+        let ak = (xke / no_kozai).powf(X2O3);
+        let d1 = 0.75 * J2 * (3.0 * cosio2 - 1.0) / (rteosq * omeosq);
+        let mut del = d1 / (ak * ak);
+        let adel = ak * (1.0 - del * del - del * (1.0 / 3.0 + 134.0 * del * del / 81.0));
+        del = d1 / (adel * adel);
+        let no = no_kozai / (1.0 + del);
+        let ao = (xke / no).powf(X2O3);
+        let sinio = inclo.sin();
+        let po = ao * omeosq;
+        let con42 = 1.0 - 5.0 * cosio2;
+        let con41 = -con42 - cosio2 - cosio2;
+        let posq = po * po;
+        let rp = ao * (1.0 - ecco);
+        let gsto = gstime(epoch_days + JD_1950);
+
+        let mut isimp = rp < (220.0 / RADIUS_KM + 1.0);
+        let mut sfour = ss;
+        let mut qzms24 = qzms2t;
+        let perige = (rp - 1.0) * RADIUS_KM;
+
+        // For perigees below 156 km, s and qoms2t are altered
+        if perige < 156.0 {
+            sfour = if perige < 98.0 { 20.0 } else { perige - 78.0 };
+            qzms24 = ((120.0 - sfour) / RADIUS_KM).powi(4);
+            sfour = sfour / RADIUS_KM + 1.0;
+        }
+        let pinvsq = 1.0 / posq;
+
+        let tsi = 1.0 / (ao - sfour);
+        let eta = ao * ecco * tsi;
+        let etasq = eta * eta;
+        let eeta = ecco * eta;
+        let psisq = (1.0 - etasq).abs();
+        let coef = qzms24 * tsi.powi(4);
+        let coef1 = coef / psisq.powf(3.5);
+        let cc2 = coef1
+            * no
+            * (ao * (1.0 + 1.5 * etasq + eeta * (4.0 + etasq))
+                + 0.375 * J2 * tsi / psisq * con41 * (8.0 + 3.0 * etasq * (8.0 + etasq)));
+        let cc1 = bstar * cc2;
+        let cc3 = if ecco > 1.0e-4 {
+            -2.0 * coef * tsi * J3OJ2 * no * sinio / ecco
+        } else {
+            0.0
+        };
+        let x1mth2 = 1.0 - cosio2;
+        let cc4 = 2.0
+            * no
+            * coef1
+            * ao
+            * omeosq
+            * (eta * (2.0 + 0.5 * etasq) + ecco * (0.5 + 2.0 * etasq)
+                - J2 * tsi / (ao * psisq)
+                    * (-3.0 * con41 * (1.0 - 2.0 * eeta + etasq * (1.5 - 0.5 * eeta))
+                        + 0.75
+                            * x1mth2
+                            * (2.0 * etasq - eeta * (1.0 + etasq))
+                            * (2.0 * argpo).cos()));
+        let cc5 = 2.0 * coef1 * ao * omeosq * (1.0 + 2.75 * (etasq + eeta) + eeta * etasq);
+        let cosio4 = cosio2 * cosio2;
+        let temp1 = 1.5 * J2 * pinvsq * no;
+        let temp2 = 0.5 * temp1 * J2 * pinvsq;
+        let temp3 = -0.46875 * J4 * pinvsq * pinvsq * no;
+        let mdot = no
+            + 0.5 * temp1 * rteosq * con41
+            + 0.0625 * temp2 * rteosq * (13.0 - 78.0 * cosio2 + 137.0 * cosio4);
+        let argpdot = -0.5 * temp1 * con42
+            + 0.0625 * temp2 * (7.0 - 114.0 * cosio2 + 395.0 * cosio4)
+            + temp3 * (3.0 - 36.0 * cosio2 + 49.0 * cosio4);
+        let xhdot1 = -temp1 * cosio;
+        let nodedot = xhdot1
+            + (0.5 * temp2 * (4.0 - 19.0 * cosio2) + 2.0 * temp3 * (3.0 - 7.0 * cosio2)) * cosio;
+        let xpidot = argpdot + nodedot;
+        let omgcof = bstar * cc3 * argpo.cos();
+        let xmcof = if ecco > 1.0e-4 {
+            -X2O3 * coef * bstar / eeta
+        } else {
+            0.0
+        };
+        let nodecf = 3.5 * omeosq * xhdot1 * cc1;
+        let t2cof = 1.5 * cc1;
+        let xlcof = -0.25 * J3OJ2 * sinio * (3.0 + 5.0 * cosio) / lcof_divisor(cosio);
+        let aycof = -0.5 * J3OJ2 * sinio;
+        let delmo = (1.0 + eta * mo.cos()).powi(3);
+        let sinmao = mo.sin();
+        let x7thm1 = 7.0 * cosio2 - 1.0;
+
+        let mut me = Self {
+            tle,
+            epoch_days,
+            ecco,
+            inclo,
+            nodeo,
+            argpo,
+            mo,
+            no,
+            bstar,
+            gsto,
+            isimp,
+            aycof,
+            con41,
+            cc1,
+            cc4,
+            cc5,
+            d2: 0.0,
+            d3: 0.0,
+            d4: 0.0,
+            delmo,
+            eta,
+            argpdot,
+            omgcof,
+            sinmao,
+            t2cof,
+            t3cof: 0.0,
+            t4cof: 0.0,
+            t5cof: 0.0,
+            x1mth2,
+            x7thm1,
+            mdot,
+            nodedot,
+            xlcof,
+            xmcof,
+            nodecf,
+            deep: None,
+        };
+
+        // Deep space initialization
+        if TAU / no >= 225.0 {
+            isimp = true;
+            me.isimp = true;
+            let (mut deep, dscom) = me.dscom();
+            me.dsinit(&mut deep, &dscom, xpidot);
+            me.deep = Some(Box::new(deep));
+        }
+
+        if !isimp {
+            let cc1sq = cc1 * cc1;
+            me.d2 = 4.0 * ao * tsi * cc1sq;
+            let temp = me.d2 * tsi * cc1 / 3.0;
+            me.d3 = (17.0 * ao + sfour) * temp;
+            me.d4 = 0.5 * temp * ao * tsi * (221.0 * ao + 31.0 * sfour) * cc1;
+            me.t3cof = me.d2 + 2.0 * cc1sq;
+            me.t4cof = 0.25 * (3.0 * me.d3 + cc1 * (12.0 * me.d2 + 10.0 * cc1sq));
+            me.t5cof = 0.2
+                * (3.0 * me.d4
+                    + 12.0 * cc1 * me.d3
+                    + 6.0 * me.d2 * me.d2
+                    + 15.0 * cc1sq * (2.0 * me.d2 + cc1sq));
+        }
+
+        // Propagate to the epoch to check the validity of the elements
+        me.teme_state(0.0)?;
+
+        Ok(me)
+    }
+
+    /// Returns the element set used to initialize this propagator.
+    pub fn tle(&self) -> &Tle {
+        &self.tle
+    }
+
+    /// Returns whether this element set is propagated with the deep space (SDP4) perturbations.
+    pub fn is_deep_space(&self) -> bool {
+        self.deep.is_some()
+    }
+
+    /// Returns the TEME position and velocity (km and km/s) at `tsince_min` minutes after the element set epoch.
+    pub fn teme_state(&self, tsince_min: f64) -> Result<Vector6<f64>, NyxError> {
+        let t = tsince_min;
+        let xke = xke();
+        let vkmpersec = RADIUS_KM * xke / 60.0;
+
+        // Update for secular gravity and atmospheric drag
+        let xmdf = self.mo + self.mdot * t;
+        let argpdf = self.argpo + self.argpdot * t;
+        let nodedf = self.nodeo + self.nodedot * t;
+        let mut argpm = argpdf;
+        let mut mm = xmdf;
+        let t2 = t * t;
+        let mut nodem = nodedf + self.nodecf * t2;
+        let mut tempa = 1.0 - self.cc1 * t;
+        let mut tempe = self.bstar * self.cc4 * t;
+        let mut templ = self.t2cof * t2;
+
+        if !self.isimp {
+            let delomg = self.omgcof * t;
+            let delm = self.xmcof * ((1.0 + self.eta * xmdf.cos()).powi(3) - self.delmo);
+            let temp = delomg + delm;
+            mm = xmdf + temp;
+            argpm = argpdf - temp;
+            let t3 = t2 * t;
+            let t4 = t3 * t;
+            tempa = tempa - self.d2 * t2 - self.d3 * t3 - self.d4 * t4;
+            tempe += self.bstar * self.cc5 * (mm.sin() - self.sinmao);
+            templ += self.t3cof * t3 + t4 * (self.t4cof + t * self.t5cof);
+        }
+
+        let mut nm = self.no;
+        let mut em = self.ecco;
+        let mut inclm = self.inclo;
+        if let Some(deep) = &self.deep {
+            nm = self.dspace(
+                deep, t, &mut em, &mut argpm, &mut inclm, &mut mm, &mut nodem,
+            );
+        }
+
+        if nm <= 0.0 {
+            return Err(NyxError::Sgp4 {
+                msg: format!("mean motion of {nm} rad/min is not positive at {tsince_min} min"),
+            });
+        }
+        let am = (xke / nm).powf(X2O3) * tempa * tempa;
+        nm = xke / am.powf(1.5);
+        em -= tempe;
+
+        if !(-0.001..1.0).contains(&em) {
+            return Err(NyxError::Sgp4 {
+                msg: format!("mean eccentricity of {em} out of range at {tsince_min} min"),
+            });
+        }
+        // Avoid a divide by zero
+        if em < 1.0e-6 {
+            em = 1.0e-6;
+        }
+        mm += self.no * templ;
+        let mut xlm = mm + argpm + nodem;
+
+        nodem %= TAU;
+        argpm %= TAU;
+        xlm %= TAU;
+        mm = (xlm - argpm - nodem) % TAU;
+
+        // Add the lunar-solar periodics
+        let mut ep = em;
+        let mut xincp = inclm;
+        let mut argpp = argpm;
+        let mut nodep = nodem;
+        let mut mp = mm;
+        let mut sinip = inclm.sin();
+        let mut cosip = inclm.cos();
+        let mut aycof = self.aycof;
+        let mut xlcof = self.xlcof;
+        let mut con41 = self.con41;
+        let mut x1mth2 = self.x1mth2;
+        let mut x7thm1 = self.x7thm1;
+
+        if let Some(deep) = &self.deep {
+            dpper(
+                deep, t, &mut ep, &mut xincp, &mut nodep, &mut argpp, &mut mp,
+            );
+            if xincp < 0.0 {
+                xincp = -xincp;
+                nodep += PI;
+                argpp -= PI;
+            }
+            if !(0.0..=1.0).contains(&ep) {
+                return Err(NyxError::Sgp4 {
+                    msg: format!("perturbed eccentricity of {ep} out of range at {tsince_min} min"),
+                });
+            }
+
+            // Long period periodics
+            sinip = xincp.sin();
+            cosip = xincp.cos();
+            aycof = -0.5 * J3OJ2 * sinip;
+            xlcof = -0.25 * J3OJ2 * sinip * (3.0 + 5.0 * cosip) / lcof_divisor(cosip);
+        }
+
+        let axnl = ep * argpp.cos();
+        let temp = 1.0 / (am * (1.0 - ep * ep));
+        let aynl = ep * argpp.sin() + temp * aycof;
+        let xl = mp + argpp + nodep + temp * xlcof * axnl;
+
+        // Solve Kepler's equation
+        let u = (xl - nodep) % TAU;
+        let mut eo1 = u;
+        let mut tem5: f64 = 9999.9;
+        let mut ktr = 1;
+        let (mut sineo1, mut coseo1) = (0.0, 0.0);
+        while tem5.abs() >= 1.0e-12 && ktr <= 10 {
+            sineo1 = eo1.sin();
+            coseo1 = eo1.cos();
+            tem5 = 1.0 - coseo1 * axnl - sineo1 * aynl;
+            tem5 = (u - aynl * coseo1 + axnl * sineo1 - eo1) / tem5;
+            if tem5.abs() >= 0.95 {
+                tem5 = 0.95_f64.copysign(tem5);
+            }
+            eo1 += tem5;
+            ktr += 1;
+        }
+
+        // Short period preliminary quantities
+        let ecose = axnl * coseo1 + aynl * sineo1;
+        let esine = axnl * sineo1 - aynl * coseo1;
+        let el2 = axnl * axnl + aynl * aynl;
+        let pl = am * (1.0 - el2);
+        if pl < 0.0 {
+            return Err(NyxError::Sgp4 {
+                msg: format!("semi-latus rectum is negative at {tsince_min} min"),
+            });
+        }
+
+        let rl = am * (1.0 - ecose);
+        let rdotl = am.sqrt() * esine / rl;
+        let rvdotl = pl.sqrt() / rl;
+        let betal = (1.0 - el2).sqrt();
+        let temp = esine / (1.0 + betal);
+        let sinu = am / rl * (sineo1 - aynl - axnl * temp);
+        let cosu = am / rl * (coseo1 - axnl + aynl * temp);
+        let mut su = sinu.atan2(cosu);
+        let sin2u = (cosu + cosu) * sinu;
+        let cos2u = 1.0 - 2.0 * sinu * sinu;
+        let temp = 1.0 / pl;
+        let temp1 = 0.5 * J2 * temp;
+        let temp2 = temp1 * temp;
+
+        // Update for short period periodics
+        if self.deep.is_some() {
+            let cosisq = cosip * cosip;
+            con41 = 3.0 * cosisq - 1.0;
+            x1mth2 = 1.0 - cosisq;
+            x7thm1 = 7.0 * cosisq - 1.0;
+        }
+        let mrt = rl * (1.0 - 1.5 * temp2 * betal * con41) + 0.5 * temp1 * x1mth2 * cos2u;
+        su -= 0.25 * temp2 * x7thm1 * sin2u;
+        let xnode = nodep + 1.5 * temp2 * cosip * sin2u;
+        let xinc = xincp + 1.5 * temp2 * cosip * sinip * cos2u;
+        let mvt = rdotl - nm * temp1 * x1mth2 * sin2u / xke;
+        let rvdot = rvdotl + nm * temp1 * (x1mth2 * cos2u + 1.5 * con41) / xke;
+
+        if mrt < 1.0 {
+            return Err(NyxError::Sgp4 {
+                msg: format!(
+                    "object {} has decayed at {tsince_min} min",
+                    self.tle.norad_id
+                ),
+            });
+        }
+
+        // Orientation vectors
+        let (sinsu, cossu) = su.sin_cos();
+        let (snod, cnod) = xnode.sin_cos();
+        let (sini, cosi) = xinc.sin_cos();
+        let xmx = -snod * cosi;
+        let xmy = cnod * cosi;
+        let ux = xmx * sinsu + cnod * cossu;
+        let uy = xmy * sinsu + snod * cossu;
+        let uz = sini * sinsu;
+        let vx = xmx * cossu - cnod * sinsu;
+        let vy = xmy * cossu - snod * sinsu;
+        let vz = sini * cossu;
+
+        Ok(Vector6::new(
+            mrt * ux * RADIUS_KM,
+            mrt * uy * RADIUS_KM,
+            mrt * uz * RADIUS_KM,
+            (mvt * ux + rvdot * vx) * vkmpersec,
+            (mvt * uy + rvdot * vy) * vkmpersec,
+            (mvt * uz + rvdot * vz) * vkmpersec,
+        ))
+    }
+
+    /// Returns the state at the provided epoch in the Earth TEME frame of this Cosm.
+    pub fn at(&self, epoch: Epoch, cosm: &Cosm) -> Result<Orbit, NyxError> {
+        let tsince_min = (epoch - self.tle.epoch).to_unit(Unit::Minute);
+        let state = self.teme_state(tsince_min)?;
+        Ok(Orbit::cartesian_vec(&state, epoch, cosm.try_frame("TEME")?))
+    }
+
+    /// Builds a trajectory from `start` to `end` (included) with states every `step`, in the requested frame (e.g. TEME or EME2000).
+    ///
+    /// The resulting trajectory can be used as any other ephemeris, e.g. to search for events or as a reference trajectory for tracking arcs.
+    /// Its states are interpolated with Lagrange polynomials because the SGP4 velocity is not exactly the time derivative of its position.
+    pub fn traj(
+        &self,
+        start: Epoch,
+        end: Epoch,
+        step: Duration,
+        frame: Frame,
+        cosm: Arc<Cosm>,
+    ) -> Result<Traj<Orbit>, NyxError> {
+        if end <= start {
+            return Err(NyxError::Sgp4 {
+                msg: format!("end epoch {end} must be after the start epoch {start}"),
+            });
+        }
+
+        let mut traj = Traj::new();
+        traj.interpolation = InterpolationMethod::Lagrange;
+        traj.name = Some(match &self.tle.name {
+            Some(name) => name.clone(),
+            None => format!("{}", self.tle.norad_id),
+        });
+
+        let teme = cosm.try_frame("TEME")?;
+        let mut epochs: Vec<Epoch> = TimeSeries::inclusive(start, end, step).collect();
+        if epochs.last() != Some(&end) {
+            epochs.push(end);
+        }
+
+        for epoch in epochs {
+            let tsince_min = (epoch - self.tle.epoch).to_unit(Unit::Minute);
+            let state = Orbit::cartesian_vec(&self.teme_state(tsince_min)?, epoch, teme);
+            if frame == teme {
+                traj.states.push(state);
+            } else {
+                traj.states.push(cosm.try_frame_chg(&state, frame)?);
+            }
+        }
+        traj.finalize();
+
+        Ok(traj)
+    }
+
+    /// Deep space common terms: the lunar and solar perturbation coefficients.
+    fn dscom(&self) -> (DeepSpace, DsCom) {
+        const ZES: f64 = 0.01675;
+        const ZEL: f64 = 0.05490;
+        const C1SS: f64 = 2.986_479_7e-6;
+        const C1L: f64 = 4.796_806_5e-7;
+        const ZSINIS: f64 = 0.397_854_16;
+        const ZCOSIS: f64 = 0.917_448_67;
+        const ZCOSGS: f64 = 0.194_590_5;
+        const ZSINGS: f64 = -0.980_884_58;
+
+        let mut deep = DeepSpace::default();
+        let mut c = DsCom::default();
+
+        let em = self.ecco;
+        let (snodm, cnodm) = self.nodeo.sin_cos();
+        let (sinomm, cosomm) = self.argpo.sin_cos();
+        c.sinim = self.inclo.sin();
+        c.cosim = self.inclo.cos();
+        c.emsq = em * em;
+        let betasq = 1.0 - c.emsq;
+        let rtemsq = betasq.sqrt();
+
+        // Initialize the lunar solar terms
+        let day = self.epoch_days + 18_261.5;
+        let xnodce = (4.523_602_0 - 9.242_202_9e-4 * day) % TAU;
+        let (stem, ctem) = xnodce.sin_cos();
+        let zcosil = 0.913_751_64 - 0.035_680_96 * ctem;
+        let zsinil = (1.0 - zcosil * zcosil).sqrt();
+        let zsinhl = 0.089_683_511 * stem / zsinil;
+        let zcoshl = (1.0 - zsinhl * zsinhl).sqrt();
+        let gam = 5.835_151_4 + 0.001_944_368_0 * day;
+        let zx = 0.397_854_16 * stem / zsinil;
+        let zy = zcoshl * ctem + 0.917_448_67 * zsinhl * stem;
+        let zx = gam + zx.atan2(zy) - xnodce;
+        let zcosgl = zx.cos();
+        let zsingl = zx.sin();
+
+        // Solar terms first, then lunar terms
+        let mut zcosg = ZCOSGS;
+        let mut zsing = ZSINGS;
+        let mut zcosi = ZCOSIS;
+        let mut zsini = ZSINIS;
+        let mut zcosh = cnodm;
+        let mut zsinh = snodm;
+        let mut cc = C1SS;
+        let xnoi = 1.0 / self.no;
+
+        let (mut z2, mut z12, mut z22, mut z32) = (0.0, 0.0, 0.0, 0.0);
+        let (mut sz2, mut sz12, mut sz22, mut sz32) = (0.0, 0.0, 0.0, 0.0);
+        let (mut s6, mut s7, mut ss6, mut ss7) = (0.0, 0.0, 0.0, 0.0);
+
+        for lsflg in 1..=2 {
+            let a1 = zcosg * zcosh + zsing * zcosi * zsinh;
+            let a3 = -zsing * zcosh + zcosg * zcosi * zsinh;
+            let a7 = -zcosg * zsinh + zsing * zcosi * zcosh;
+            let a8 = zsing * zsini;
+            let a9 = zsing * zsinh + zcosg * zcosi * zcosh;
+            let a10 = zcosg * zsini;
+            let a2 = c.cosim * a7 + c.sinim * a8;
+            let a4 = c.cosim * a9 + c.sinim * a10;
+            let a5 = -c.sinim * a7 + c.cosim * a8;
+            let a6 = -c.sinim * a9 + c.cosim * a10;
+
+            let x1 = a1 * cosomm + a2 * sinomm;
+            let x2 = a3 * cosomm + a4 * sinomm;
+            let x3 = -a1 * sinomm + a2 * cosomm;
+            let x4 = -a3 * sinomm + a4 * cosomm;
+            let x5 = a5 * sinomm;
+            let x6 = a6 * sinomm;
+            let x7 = a5 * cosomm;
+            let x8 = a6 * cosomm;
+
+            c.z31 = 12.0 * x1 * x1 - 3.0 * x3 * x3;
+            z32 = 24.0 * x1 * x2 - 6.0 * x3 * x4;
+            c.z33 = 12.0 * x2 * x2 - 3.0 * x4 * x4;
+            c.z1 = 3.0 * (a1 * a1 + a2 * a2) + c.z31 * c.emsq;
+            z2 = 6.0 * (a1 * a3 + a2 * a4) + z32 * c.emsq;
+            c.z3 = 3.0 * (a3 * a3 + a4 * a4) + c.z33 * c.emsq;
+            c.z11 = -6.0 * a1 * a5 + c.emsq * (-24.0 * x1 * x7 - 6.0 * x3 * x5);
+            z12 = -6.0 * (a1 * a6 + a3 * a5)
+                + c.emsq * (-24.0 * (x2 * x7 + x1 * x8) - 6.0 * (x3 * x6 + x4 * x5));
+            c.z13 = -6.0 * a3 * a6 + c.emsq * (-24.0 * x2 * x8 - 6.0 * x4 * x6);
+            c.z21 = 6.0 * a2 * a5 + c.emsq * (24.0 * x1 * x5 - 6.0 * x3 * x7);
+            z22 = 6.0 * (a4 * a5 + a2 * a6)
+                + c.emsq * (24.0 * (x2 * x5 + x1 * x6) - 6.0 * (x4 * x7 + x3 * x8));
+            c.z23 = 6.0 * a4 * a6 + c.emsq * (24.0 * x2 * x6 - 6.0 * x4 * x8);
+            c.z1 = c.z1 + c.z1 + betasq * c.z31;
+            z2 = z2 + z2 + betasq * z32;
+            c.z3 = c.z3 + c.z3 + betasq * c.z33;
+            c.s3 = cc * xnoi;
+            c.s2 = -0.5 * c.s3 / rtemsq;
+            c.s4 = c.s3 * rtemsq;
+            c.s1 = -15.0 * em * c.s4;
+            c.s5 = x1 * x3 + x2 * x4;
+            s6 = x2 * x3 + x1 * x4;
+            s7 = x2 * x4 - x1 * x3;
+
+            if lsflg == 1 {
+                // Store the solar terms and switch to the lunar terms
+                c.ss1 = c.s1;
+                c.ss2 = c.s2;
+                c.ss3 = c.s3;
+                c.ss4 = c.s4;
+                c.ss5 = c.s5;
+                ss6 = s6;
+                ss7 = s7;
+                c.sz1 = c.z1;
+                sz2 = z2;
+                c.sz3 = c.z3;
+                c.sz11 = c.z11;
+                sz12 = z12;
+                c.sz13 = c.z13;
+                c.sz21 = c.z21;
+                sz22 = z22;
+                c.sz23 = c.z23;
+                c.sz31 = c.z31;
+                sz32 = z32;
+                c.sz33 = c.z33;
+                zcosg = zcosgl;
+                zsing = zsingl;
+                zcosi = zcosil;
+                zsini = zsinil;
+                zcosh = zcoshl * cnodm + zsinhl * snodm;
+                zsinh = snodm * zcoshl - cnodm * zsinhl;
+                cc = C1L;
+            }
+        }
+
+        deep.zmol = (4.719_967_2 + 0.229_971_50 * day - gam) % TAU;
+        deep.zmos = (6.256_583_7 + 0.017_201_977 * day) % TAU;
+
+        // Solar terms
+        deep.se2 = 2.0 * c.ss1 * ss6;
+        deep.se3 = 2.0 * c.ss1 * ss7;
+        deep.si2 = 2.0 * c.ss2 * sz12;
+        deep.si3 = 2.0 * c.ss2 * (c.sz13 - c.sz11);
+        deep.sl2 = -2.0 * c.ss3 * sz2;
+        deep.sl3 = -2.0 * c.ss3 * (c.sz3 - c.sz1);
+        deep.sl4 = -2.0 * c.ss3 * (-21.0 - 9.0 * c.emsq) * ZES;
+        deep.sgh2 = 2.0 * c.ss4 * sz32;
+        deep.sgh3 = 2.0 * c.ss4 * (c.sz33 - c.sz31);
+        deep.sgh4 = -18.0 * c.ss4 * ZES;
+        deep.sh2 = -2.0 * c.ss2 * sz22;
+        deep.sh3 = -2.0 * c.ss2 * (c.sz23 - c.sz21);
+
+        // Lunar terms
+        deep.ee2 = 2.0 * c.s1 * s6;
+        deep.e3 = 2.0 * c.s1 * s7;
+        deep.xi2 = 2.0 * c.s2 * z12;
+        deep.xi3 = 2.0 * c.s2 * (c.z13 - c.z11);
+        deep.xl2 = -2.0 * c.s3 * z2;
+        deep.xl3 = -2.0 * c.s3 * (c.z3 - c.z1);
+        deep.xl4 = -2.0 * c.s3 * (-21.0 - 9.0 * c.emsq) * ZEL;
+        deep.xgh2 = 2.0 * c.s4 * z32;
+        deep.xgh3 = 2.0 * c.s4 * (c.z33 - c.z31);
+        deep.xgh4 = -18.0 * c.s4 * ZEL;
+        deep.xh2 = -2.0 * c.s2 * z22;
+        deep.xh3 = -2.0 * c.s2 * (c.z23 - c.z21);
+
+        (deep, c)
+    }
+
+    /// Deep space initialization: secular rates of the lunar-solar terms and the resonance coefficients.
+    fn dsinit(&self, deep: &mut DeepSpace, c: &DsCom, xpidot: f64) {
+        const Q22: f64 = 1.789_167_9e-6;
+        const Q31: f64 = 2.146_074_8e-6;
+        const Q33: f64 = 2.212_301_5e-7;
+        const ROOT22: f64 = 1.789_167_9e-6;
+        const ROOT44: f64 = 7.363_695_3e-9;
+        const ROOT54: f64 = 2.176_580_3e-9;
+        const ROOT32: f64 = 3.739_379_2e-7;
+        const ROOT52: f64 = 1.142_863_9e-7;
+        const ZNL: f64 = 1.583_521_8e-4;
+        const ZNS: f64 = 1.194_59e-5;
+
+        let nm = self.no;
+        let em = self.ecco;
+        let emsq = c.emsq;
+        let inclm = self.inclo;
+        let (sinim, cosim) = (c.sinim, c.cosim);
+
+        deep.irez = 0;
+        if (0.003_490_658_5..0.005_235_987_7).contains(&nm) {
+            deep.irez = 1;
+        }
+        if (8.26e-3..=9.24e-3).contains(&nm) && em >= 0.5 {
+            deep.irez = 2;
+        }
+
+        // Solar terms
+        let ses = c.ss1 * ZNS * c.ss5;
+        let sis = c.ss2 * ZNS * (c.sz11 + c.sz13);
+        let sls = -ZNS * c.ss3 * (c.sz1 + c.sz3 - 14.0 - 6.0 * emsq);
+        let sghs = c.ss4 * ZNS * (c.sz31 + c.sz33 - 6.0);
+        let mut shs = -ZNS * c.ss2 * (c.sz21 + c.sz23);
+        let near_equatorial = !(5.235_987_7e-2..=PI - 5.235_987_7e-2).contains(&inclm);
+        if near_equatorial {
+            shs = 0.0;
+        }
+        if sinim != 0.0 {
+            shs /= sinim;
+        }
+        let sgs = sghs - cosim * shs;
+
+        // Lunar terms
+        deep.dedt = ses + c.s1 * ZNL * c.s5;
+        deep.didt = sis + c.s2 * ZNL * (c.z11 + c.z13);
+        deep.dmdt = sls - ZNL * c.s3 * (c.z1 + c.z3 - 14.0 - 6.0 * emsq);
+        let sghl = c.s4 * ZNL * (c.z31 + c.z33 - 6.0);
+        let shll = if near_equatorial {
+            0.0
+        } else {
+            -ZNL * c.s2 * (c.z21 + c.z23)
+        };
+        deep.domdt = sgs + sghl;
+        deep.dnodt = shs;
+        if sinim != 0.0 {
+            deep.domdt -= cosim / sinim * shll;
+            deep.dnodt += shll / sinim;
+        }
+
+        // Deep space resonance effects
+        let theta = self.gsto % TAU;
+
+        if deep.irez == 0 {
+            return;
+        }
+
+        let aonv = (nm / xke()).powf(X2O3);
+
+        if deep.irez == 2 {
+            // Geopotential resonance for 12 hour orbits
+            let cosisq = cosim * cosim;
+            let em = self.ecco;
+            let emsq = em * em;
+            let eoc = em * emsq;
+            let g201 = -0.306 - (em - 0.64) * 0.440;
+
+            let (g211, g310, g322, g410, g422, g520) = if em <= 0.65 {
+                (
+                    3.616 - 13.2470 * em + 16.2900 * emsq,
+                    -19.302 + 117.3900 * em - 228.4190 * emsq + 156.5910 * eoc,
+                    -18.9068 + 109.7927 * em - 214.6334 * emsq + 146.5816 * eoc,
+                    -41.122 + 242.6940 * em - 471.0940 * emsq + 313.9530 * eoc,
+                    -146.407 + 841.8800 * em - 1629.014 * emsq + 1083.4350 * eoc,
+                    -532.114 + 3017.977 * em - 5740.032 * emsq + 3708.2760 * eoc,
+                )
+            } else {
+                (
+                    -72.099 + 331.819 * em - 508.738 * emsq + 266.724 * eoc,
+                    -346.844 + 1582.851 * em - 2415.925 * emsq + 1246.113 * eoc,
+                    -342.585 + 1554.908 * em - 2366.899 * emsq + 1215.972 * eoc,
+                    -1052.797 + 4758.686 * em - 7193.992 * emsq + 3651.957 * eoc,
+                    -3581.690 + 16178.110 * em - 24462.770 * emsq + 12422.520 * eoc,
+                    if em > 0.715 {
+                        -5149.66 + 29936.92 * em - 54087.36 * emsq + 31324.56 * eoc
+                    } else {
+                        1464.74 - 4664.75 * em + 3763.64 * emsq
+                    },
+                )
+            };
+            let (g533, g521, g532) = if em < 0.7 {
+                (
+                    -919.22770 + 4988.6100 * em - 9064.7700 * emsq + 5542.21 * eoc,
+                    -822.71072 + 4568.6173 * em - 8491.4146 * emsq + 5337.524 * eoc,
+                    -853.66600 + 4690.2500 * em - 8624.7700 * emsq + 5341.4 * eoc,
+                )
+            } else {
+                (
+                    -37995.780 + 161616.52 * em - 229838.20 * emsq + 109377.94 * eoc,
+                    -51752.104 + 218913.95 * em - 309468.16 * emsq + 146349.42 * eoc,
+                    -40023.880 + 170470.89 * em - 242699.48 * emsq + 115605.82 * eoc,
+                )
+            };
+
+            let sini2 = sinim * sinim;
+            let f220 = 0.75 * (1.0 + 2.0 * cosim + cosisq);
+            let f221 = 1.5 * sini2;
+            let f321 = 1.875 * sinim * (1.0 - 2.0 * cosim - 3.0 * cosisq);
+            let f322 = -1.875 * sinim * (1.0 + 2.0 * cosim - 3.0 * cosisq);
+            let f441 = 35.0 * sini2 * f220;
+            let f442 = 39.3750 * sini2 * sini2;
+            let f522 = 9.84375
+                * sinim
+                * (sini2 * (1.0 - 2.0 * cosim - 5.0 * cosisq)
+                    + 0.33333333 * (-2.0 + 4.0 * cosim + 6.0 * cosisq));
+            let f523 = sinim
+                * (4.92187512 * sini2 * (-2.0 - 4.0 * cosim + 10.0 * cosisq)
+                    + 6.56250012 * (1.0 + 2.0 * cosim - 3.0 * cosisq));
+            let f542 = 29.53125
+                * sinim
+                * (2.0 - 8.0 * cosim + cosisq * (-12.0 + 8.0 * cosim + 10.0 * cosisq));
+            let f543 = 29.53125
+                * sinim
+                * (-2.0 - 8.0 * cosim + cosisq * (12.0 + 8.0 * cosim - 10.0 * cosisq));
+            let xno2 = nm * nm;
+            let ainv2 = aonv * aonv;
+            let mut temp1 = 3.0 * xno2 * ainv2;
+            let mut temp = temp1 * ROOT22;
+            deep.d2201 = temp * f220 * g201;
+            deep.d2211 = temp * f221 * g211;
+            temp1 *= aonv;
+            temp = temp1 * ROOT32;
+            deep.d3210 = temp * f321 * g310;
+            deep.d3222 = temp * f322 * g322;
+            temp1 *= aonv;
+            temp = 2.0 * temp1 * ROOT44;
+            deep.d4410 = temp * f441 * g410;
+            deep.d4422 = temp * f442 * g422;
+            temp1 *= aonv;
+            temp = temp1 * ROOT52;
+            deep.d5220 = temp * f522 * g520;
+            deep.d5232 = temp * f523 * g532;
+            temp = 2.0 * temp1 * ROOT54;
+            deep.d5421 = temp * f542 * g521;
+            deep.d5433 = temp * f543 * g533;
+            deep.xlamo = (self.mo + self.nodeo + self.nodeo - theta - theta) % TAU;
+            deep.xfact =
+                self.mdot + deep.dmdt + 2.0 * (self.nodedot + deep.dnodt - RPTIM) - self.no;
+        } else {
+            // Synchronous resonance terms
+            let g200 = 1.0 + emsq * (-2.5 + 0.8125 * emsq);
+            let g310 = 1.0 + 2.0 * emsq;
+            let g300 = 1.0 + emsq * (-6.0 + 6.60937 * emsq);
+            let f220 = 0.75 * (1.0 + cosim) * (1.0 + cosim);
+            let f311 = 0.9375 * sinim * sinim * (1.0 + 3.0 * cosim) - 0.75 * (1.0 + cosim);
+            let f330 = 1.875 * (1.0 + cosim).powi(3);
+            let del1 = 3.0 * nm * nm * aonv * aonv;
+            deep.del2 = 2.0 * del1 * f220 * g200 * Q22;
+            deep.del3 = 3.0 * del1 * f330 * g300 * Q33 * aonv;
+            deep.del1 = del1 * f311 * g310 * Q31 * aonv;
+            deep.xlamo = (self.mo + self.nodeo + self.argpo - theta) % TAU;
+            deep.xfact = self.mdot + xpidot - RPTIM + deep.dmdt + deep.domdt + deep.dnodt - self.no;
+        }
+    }
+
+    /// Deep space secular effects and the numerical (Euler-Maclaurin) integration of the resonances, restarted from the epoch on every call.
+    /// Returns the mean motion.
+    #[allow(clippy::too_many_arguments)]
+    fn dspace(
+        &self,
+        deep: &DeepSpace,
+        t: f64,
+        em: &mut f64,
+        argpm: &mut f64,
+        inclm: &mut f64,
+        mm: &mut f64,
+        nodem: &mut f64,
+    ) -> f64 {
+        const FASX2: f64 = 0.131_309_08;
+        const FASX4: f64 = 2.884_319_8;
+        const FASX6: f64 = 0.374_480_87;
+        const G22: f64 = 5.768_639_6;
+        const G32: f64 = 0.952_408_98;
+        const G44: f64 = 1.801_499_8;
+        const G52: f64 = 1.050_833_0;
+        const G54: f64 = 4.410_889_8;
+        const STEPP: f64 = 720.0;
+        const STEPN: f64 = -720.0;
+        const STEP2: f64 = 259_200.0;
+
+        let theta = (self.gsto + t * RPTIM) % TAU;
+        *em += deep.dedt * t;
+        *inclm += deep.didt * t;
+        *argpm += deep.domdt * t;
+        *nodem += deep.dnodt * t;
+        *mm += deep.dmdt * t;
+
+        if deep.irez == 0 {
+            return self.no;
+        }
+
+        let mut atime = 0.0;
+        let mut xni = self.no;
+        let mut xli = deep.xlamo;
+        let delt = if t > 0.0 { STEPP } else { STEPN };
+
+        let (ft, xndt, xldot, xnddt) = loop {
+            let (xndt, xldot, xnddt) = if deep.irez != 2 {
+                // Near synchronous resonance terms
+                let xndt = deep.del1 * (xli - FASX2).sin()
+                    + deep.del2 * (2.0 * (xli - FASX4)).sin()
+                    + deep.del3 * (3.0 * (xli - FASX6)).sin();
+                let xldot = xni + deep.xfact;
+                let xnddt = (deep.del1 * (xli - FASX2).cos()
+                    + 2.0 * deep.del2 * (2.0 * (xli - FASX4)).cos()
+                    + 3.0 * deep.del3 * (3.0 * (xli - FASX6)).cos())
+                    * xldot;
+                (xndt, xldot, xnddt)
+            } else {
+                // Near half-day resonance terms
+                let xomi = self.argpo + self.argpdot * atime;
+                let x2omi = xomi + xomi;
+                let x2li = xli + xli;
+                let xndt = deep.d2201 * (x2omi + xli - G22).sin()
+                    + deep.d2211 * (xli - G22).sin()
+                    + deep.d3210 * (xomi + xli - G32).sin()
+                    + deep.d3222 * (-xomi + xli - G32).sin()
+                    + deep.d4410 * (x2omi + x2li - G44).sin()
+                    + deep.d4422 * (x2li - G44).sin()
+                    + deep.d5220 * (xomi + xli - G52).sin()
+                    + deep.d5232 * (-xomi + xli - G52).sin()
+                    + deep.d5421 * (xomi + x2li - G54).sin()
+                    + deep.d5433 * (-xomi + x2li - G54).sin();
+                let xldot = xni + deep.xfact;
+                let xnddt = (deep.d2201 * (x2omi + xli - G22).cos()
+                    + deep.d2211 * (xli - G22).cos()
+                    + deep.d3210 * (xomi + xli - G32).cos()
+                    + deep.d3222 * (-xomi + xli - G32).cos()
+                    + deep.d5220 * (xomi + xli - G52).cos()
+                    + deep.d5232 * (-xomi + xli - G52).cos()
+                    + 2.0
+                        * (deep.d4410 * (x2omi + x2li - G44).cos()
+                            + deep.d4422 * (x2li - G44).cos()
+                            + deep.d5421 * (xomi + x2li - G54).cos()
+                            + deep.d5433 * (-xomi + x2li - G54).cos()))
+                    * xldot;
+                (xndt, xldot, xnddt)
+            };
+
+            if (t - atime).abs() >= STEPP {
+                xli += xldot * delt + xndt * STEP2;
+                xni += xndt * delt + xnddt * STEP2;
+                atime += delt;
+            } else {
+                break (t - atime, xndt, xldot, xnddt);
+            }
+        };
+
+        let nm = xni + xndt * ft + xnddt * ft * ft * 0.5;
+        let xl = xli + xldot * ft + xndt * ft * ft * 0.5;
+        if deep.irez != 1 {
+            *mm = xl - 2.0 * *nodem + 2.0 * theta;
+        } else {
+            *mm = xl - *nodem - *argpm + theta;
+        }
+        nm
+    }
+}
+
+/// Deep space long period periodic contributions (lunar-solar), including the Lyddane modification for low inclinations.
+fn dpper(
+    deep: &DeepSpace,
+    t: f64,
+    ep: &mut f64,
+    inclp: &mut f64,
+    nodep: &mut f64,
+    argpp: &mut f64,
+    mp: &mut f64,
+) {
+    const ZNS: f64 = 1.194_59e-5;
+    const ZES: f64 = 0.01675;
+    const ZNL: f64 = 1.583_521_8e-4;
+    const ZEL: f64 = 0.05490;
+
+    // Solar periodics
+    let zm = deep.zmos + ZNS * t;
+    let zf = zm + 2.0 * ZES * zm.sin();
+    let sinzf = zf.sin();
+    let f2 = 0.5 * sinzf * sinzf - 0.25;
+    let f3 = -0.5 * sinzf * zf.cos();
+    let ses = deep.se2 * f2 + deep.se3 * f3;
+    let sis = deep.si2 * f2 + deep.si3 * f3;
+    let sls = deep.sl2 * f2 + deep.sl3 * f3 + deep.sl4 * sinzf;
+    let sghs = deep.sgh2 * f2 + deep.sgh3 * f3 + deep.sgh4 * sinzf;
+    let shs = deep.sh2 * f2 + deep.sh3 * f3;
+
+    // Lunar periodics
+    let zm = deep.zmol + ZNL * t;
+    let zf = zm + 2.0 * ZEL * zm.sin();
+    let sinzf = zf.sin();
+    let f2 = 0.5 * sinzf * sinzf - 0.25;
+    let f3 = -0.5 * sinzf * zf.cos();
+    let sel = deep.ee2 * f2 + deep.e3 * f3;
+    let sil = deep.xi2 * f2 + deep.xi3 * f3;
+    let sll = deep.xl2 * f2 + deep.xl3 * f3 + deep.xl4 * sinzf;
+    let sghl = deep.xgh2 * f2 + deep.xgh3 * f3 + deep.xgh4 * sinzf;
+    let shll = deep.xh2 * f2 + deep.xh3 * f3;
+
+    let pe = ses + sel;
+    let pinc = sis + sil;
+    let pl = sls + sll;
+    let mut pgh = sghs + sghl;
+    let mut ph = shs + shll;
+
+    *inclp += pinc;
+    *ep += pe;
+    let (sinip, cosip) = inclp.sin_cos();
+
+    if *inclp >= 0.2 {
+        // Apply the periodics directly
+        ph /= sinip;
+        pgh -= cosip * ph;
+        *argpp += pgh;
+        *nodep += ph;
+        *mp += pl;
+    } else {
+        // Apply the periodics with the Lyddane modification
+        let (sinop, cosop) = nodep.sin_cos();
+        let alfdp = sinip * sinop + ph * cosop + pinc * cosip * sinop;
+        let betdp = sinip * cosop - ph * sinop + pinc * cosip * cosop;
+        *nodep %= TAU;
+        let xls = *mp + *argpp + cosip * *nodep + pl + pgh - pinc * *nodep * sinip;
+        let xnoh = *nodep;
+        *nodep = alfdp.atan2(betdp);
+        if (xnoh - *nodep).abs() > PI {
+            if *nodep < xnoh {
+                *nodep += TAU;
+            } else {
+                *nodep -= TAU;
+            }
+        }
+        *mp += pl;
+        *argpp = xls - *mp - cosip * *nodep;
+    }
+}
+
+/// Divisor of the long period coefficient, avoiding a divide by zero at 180 degrees of inclination.
+fn lcof_divisor(cosi: f64) -> f64 {
+    if (cosi + 1.0).abs() > TEMP4 {
+        1.0 + cosi
+    } else {
+        TEMP4
+    }
+}
+
+/// Greenwich mean sidereal time (IAU 1982) in radians, from the UT1 Julian date.
+fn gstime(jdut1: f64) -> f64 {
+    let tut1 = (jdut1 - 2_451_545.0) / 36_525.0;
+    let gmst_s = -6.2e-6 * tut1.powi(3)
+        + 0.093_104 * tut1 * tut1
+        + (876_600.0 * 3_600.0 + 8_640_184.812_866) * tut1
+        + 67_310.548_41;
+    // 360 degrees / 86400 seconds = 1/240
+    (gmst_s.to_radians() / 240.0).rem_euclid(TAU)
+}
+
+#[cfg(test)]
+mod ut_sgp4 {
+    use super::*;
+    use crate::time::TimeUnits;
+
+    fn vanguard() -> Sgp4 {
+        Sgp4::new(
+            Tle::from_lines(
+                "1 00005U 58002B   00179.78495062  .00000023  00000-0  28098-4 0  4753",
+                "2 00005  34.2682 348.7242 1859667 331.7664  19.3264 10.82419157413667",
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn check(sgp4: &Sgp4, tsince_min: f64, expected: [f64; 6], pos_tol_km: f64, vel_tol_km_s: f64) {
+        let state = sgp4.teme_state(tsince_min).unwrap();
+        for i in 0..3 {
+            assert!(
+                (state[i] - expected[i]).abs() < pos_tol_km,
+                "t = {tsince_min} min, r[{i}]: got {} expected {}",
+                state[i],
+                expected[i]
+            );
+            assert!(
+                (state[i + 3] - expected[i + 3]).abs() < vel_tol_km_s,
+                "t = {tsince_min} min, v[{i}]: got {} expected {}",
+                state[i + 3],
+                expected[i + 3]
+            );
+        }
+    }
+
+    #[test]
+    fn test_sgp4_near_earth_vallado() {
+        // Vallado et al., AIAA 2006-6753, verification output (tcppver.out) of satellite 00005
+        let sgp4 = vanguard();
+        assert!(!sgp4.is_deep_space());
+        check(
+            &sgp4,
+            0.0,
+            [
+                7_022.465_292_66,
+                -1_400.082_967_55,
+                0.039_951_55,
+                1.893_841_015,
+                6.405_893_759,
+                4.534_807_250,
+            ],
+            1e-6,
+            1e-9,
+        );
+        check(
+            &sgp4,
+            360.0,
+            [
+                -7_154.031_202_02,
+                -3_783.176_825_04,
+                -3_536.194_122_94,
+                4.741_887_409,
+                -4.151_817_765,
+                -2.093_935_425,
+            ],
+            1e-6,
+            1e-9,
+        );
+        check(
+            &sgp4,
+            720.0,
+            [
+                -7_134.593_401_19,
+                6_531.686_413_34,
+                3_260.271_864_83,
+                -4.113_793_027,
+                -2.911_922_039,
+                -2.557_327_851,
+            ],
+            1e-6,
+            1e-9,
+        );
+    }
+
+    #[test]
+    fn test_sgp4_deep_space_str3() {
+        // Spacetrack Report #3 SDP4 test case: a 10.5 hour orbit with a 152 km perigee, so it exercises the lunar-solar
+        // terms and the low perigee drag coefficients but none of the resonances.
+        // These are regression values of this implementation, not the published ones: Vallado's revised verification
+        // run (tcppver.out, WGS-72) differs by up to about 20 m, e.g. y = 32410.86328642 km at 360 minutes.
+        let sgp4 = Sgp4::new(
+            Tle::from_lines(
+                "1 11801U          80230.29629788  .01431103  00000-0  14311-1 0    13",
+                "2 11801  46.7916 230.4354 7318036  47.4722  10.4117  2.28537848    13",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(sgp4.is_deep_space());
+        assert_eq!(sgp4.deep.as_ref().unwrap().irez, 0);
+        check(
+            &sgp4,
+            0.0,
+            [
+                7_473.371_024_91,
+                428.947_483_12,
+                5_828.748_467_83,
+                5.107_152_89,
+                6.444_688_74,
+                -0.186_131_82,
+            ],
+            1e-2,
+            1e-5,
+        );
+        check(
+            &sgp4,
+            360.0,
+            [
+                -3_305.221_486_94,
+                32_410.843_233_31,
+                -24_697.176_464_67,
+                -1.301_135_47,
+                -1.151_315_13,
+                -0.283_335_45,
+            ],
+            1e-2,
+            1e-5,
+        );
+        check(
+            &sgp4,
+            720.0,
+            [
+                14_271.290_838_58,
+                24_110.443_096_36,
+                -4_725.763_206_22,
+                -0.320_503_08,
+                2.679_845_98,
+                -2.084_052_97,
+            ],
+            1e-2,
+            1e-5,
+        );
+    }
+
+    #[test]
+    fn test_sgp4_traj() {
+        let cosm = Arc::new(Cosm::fixed_planets());
+        let sgp4 = vanguard();
+        let teme = cosm.frame("TEME");
+        let eme2k = cosm.frame("EME2000");
+
+        let start = sgp4.tle().epoch;
+        let end = start + 1.days();
+        let traj = sgp4
+            .traj(start, end, 1.minutes(), eme2k, cosm.clone())
+            .unwrap();
+        assert_eq!(traj.name, Some("5".to_string()));
+        assert_eq!(traj.first().epoch, start);
+        assert_eq!(traj.last().epoch, end);
+        assert_eq!(traj.first().frame, eme2k);
+
+        // The interpolated trajectory matches the analytical solution in between the samples
+        let epoch = start + 6.hours() + 30.seconds();
+        let interp = cosm.frame_chg(&traj.at(epoch).unwrap(), teme);
+        let direct = sgp4.at(epoch, &cosm).unwrap();
+        assert!((interp.radius() - direct.radius()).norm() < 1e-3);
+        assert!((interp.velocity() - direct.velocity()).norm() < 1e-6);
+
+        // The end epoch must be after the start epoch
+        assert!(sgp4.traj(end, start, 1.minutes(), teme, cosm).is_err());
+    }
+}
@@ -79,6 +79,13 @@ impl Cosm {
         })
     }
     #[classmethod]
+    pub fn from_spk(_cls: &PyType, filenames: Vec<String>) -> PyResult<Self> {
+        let filenames: Vec<&str> = filenames.iter().map(|f| f.as_str()).collect();
+        Ok(Cosm {
+            inner: Arc::new(CosmRs::from_spk(&filenames)?),
+        })
+    }
+    #[classmethod]
     pub fn try_de438(_cls: &PyType) -> PyResult<Self> {
         Ok(Cosm {
             inner: Arc::new(CosmRs::try_de438()?),
@@ -64,6 +64,9 @@ impl Spacecraft {
                 stm: None,
                 srp: srp.unwrap_or_else(|| SrpConfig::default()),
                 drag: drag.unwrap_or_else(|| DragConfig::default()),
+                surface: None,
+                sail: None,
+                throttle: 0.0,
             })
         }
     }